@@ -0,0 +1,281 @@
+//! Expectation ("alert if absent") rules.
+//!
+//! A rule with an `absence` block alerts when its condition matches fewer
+//! than `min_count` events, instead of when it matches. The time range
+//! examined is the dataset's coverage (earliest to latest event timestamp),
+//! split into consecutive windows of `window` starting at the earliest event;
+//! the last window is cut at the end of the coverage. Without a window the
+//! whole coverage is a single window.
+//!
+//! With a `by` field, every entity is checked on its own: the `expected`
+//! values, or every value of the field seen anywhere in the dataset (e.g. a
+//! host that sent other events but no heartbeat).
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use chrono::{DateTime, Utc};
+
+use crate::aggregation;
+use crate::db_engine;
+use crate::event_time;
+use crate::models::{Absence, AbsenceMatch, AlertEvent, LogType, RuleYaml, SiemError};
+
+/// Incremental state of an absence rule, fed one event at a time so large
+/// files can be streamed through it.
+///
+/// Events without a parseable timestamp don't extend the coverage and are
+/// only counted when no event has one (a single window without bounds).
+pub struct AbsenceTracker<'r> {
+    rule: &'r RuleYaml,
+    absence: &'r Absence,
+    log_type: LogType,
+    window_secs: Option<i64>,
+    events: usize,
+    coverage: Option<(DateTime<Utc>, DateTime<Utc>)>,
+    /// Values of the `by` field seen in any event
+    seen_entities: BTreeSet<String>,
+    /// Matching events per (entity, epoch second)
+    observed: BTreeMap<(String, Option<i64>), usize>,
+}
+
+impl<'r> AbsenceTracker<'r> {
+    pub fn new(
+        rule: &'r RuleYaml,
+        absence: &'r Absence,
+        log_type: &LogType,
+    ) -> Result<Self, SiemError> {
+        Ok(AbsenceTracker {
+            rule,
+            absence,
+            log_type: log_type.clone(),
+            window_secs: validate_absence(absence)?,
+            events: 0,
+            coverage: None,
+            seen_entities: BTreeSet::new(),
+            observed: BTreeMap::new(),
+        })
+    }
+
+    pub fn rule(&self) -> &'r RuleYaml {
+        self.rule
+    }
+
+    /// Account for one event of the dataset.
+    pub fn observe(&mut self, event: &serde_json::Value) {
+        self.events += 1;
+
+        let ts = event_time::event_time(
+            event,
+            self.absence.timestamp_field.as_deref(),
+            &self.log_type,
+        );
+        if let Some(ts) = ts {
+            self.coverage = Some(match self.coverage {
+                Some((first, last)) => (first.min(ts), last.max(ts)),
+                None => (ts, ts),
+            });
+        }
+
+        let entity = self
+            .absence
+            .by
+            .as_deref()
+            .map(|field| aggregation::group_value(event, field))
+            .unwrap_or_default();
+        if self.absence.by.is_some() && self.absence.expected.is_empty() && !entity.is_empty() {
+            self.seen_entities.insert(entity.clone());
+        }
+
+        if db_engine::matches_detection(event, &self.rule.detection) {
+            let second = ts.map(|ts| ts.timestamp());
+            *self.observed.entry((entity, second)).or_default() += 1;
+        }
+    }
+
+    /// Produce one alert per (entity, window) with too few matching events.
+    pub fn finish(self, source_file: Option<&str>) -> Vec<AlertEvent> {
+        if self.events == 0 {
+            return vec![];
+        }
+
+        // Entities expected to appear
+        let entities: BTreeSet<String> = match &self.absence.by {
+            None => BTreeSet::from([String::new()]),
+            Some(_) if !self.absence.expected.is_empty() => {
+                self.absence.expected.iter().cloned().collect()
+            }
+            Some(_) => self.seen_entities,
+        };
+
+        // Windows over the coverage, as (start, end) epoch seconds
+        let first = self.coverage.map(|(first, _)| first.timestamp());
+        let windows: Vec<Option<(i64, i64)>> = match self.coverage {
+            None => vec![None],
+            Some((first, last)) => {
+                let (first, last) = (first.timestamp(), last.timestamp());
+                let step = self.window_secs.unwrap_or(last - first + 1).max(1);
+                (first..=last)
+                    .step_by(step as usize)
+                    .map(|start| Some((start, (start + step).min(last + 1))))
+                    .collect()
+            }
+        };
+
+        // Count matching events per (entity, window index)
+        let mut counts: BTreeMap<(String, usize), usize> = BTreeMap::new();
+        for ((entity, second), count) in self.observed {
+            let index = match (first, second) {
+                (None, _) => 0,
+                (Some(first), Some(second)) => self
+                    .window_secs
+                    .map_or(0, |step| ((second - first) / step) as usize),
+                (Some(_), None) => continue,
+            };
+            *counts.entry((entity, index)).or_default() += count;
+        }
+
+        let mut alerts = Vec::new();
+        for entity in &entities {
+            for (index, window) in windows.iter().enumerate() {
+                let count = counts.get(&(entity.clone(), index)).copied().unwrap_or(0);
+                if count >= self.absence.min_count {
+                    continue;
+                }
+
+                let mut alert =
+                    AlertEvent::from_rule(self.rule, vec![], source_file.map(str::to_string));
+                alert.match_count = count;
+                alert.absence = Some(AbsenceMatch {
+                    entity: self
+                        .absence
+                        .by
+                        .iter()
+                        .map(|field| (field.clone(), entity.clone()))
+                        .collect(),
+                    window_start: window.and_then(|(start, _)| aggregation::format_epoch(start)),
+                    window_end: window.and_then(|(_, end)| aggregation::format_epoch(end)),
+                    observed: count,
+                    expected: self.absence.min_count,
+                });
+                alerts.push(alert);
+            }
+        }
+
+        alerts
+    }
+}
+
+/// Check that an absence block is well formed and get its window in seconds.
+pub fn validate_absence(absence: &Absence) -> Result<Option<i64>, SiemError> {
+    if absence.min_count == 0 {
+        return Err(SiemError::Rule(
+            "Absence rules need a min_count of at least 1".to_string(),
+        ));
+    }
+    if absence.by.is_none() && !absence.expected.is_empty() {
+        return Err(SiemError::Rule(
+            "Absence rules with expected values need a 'by' field".to_string(),
+        ));
+    }
+    absence
+        .window
+        .as_deref()
+        .map(aggregation::parse_window)
+        .transpose()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn rule(absence: &str) -> RuleYaml {
+        let yaml = format!(
+            "id: heartbeat\ntitle: Missing heartbeat\ndescription: ''\nauthor: ''\nstatus: active\ndate: ''\ndetection:\n  severity: medium\n  condition: \"type = 'heartbeat'\"\n  absence:\n{}",
+            absence
+        );
+        serde_yaml::from_str(&yaml).unwrap()
+    }
+
+    fn evaluate(rule: &RuleYaml, events: &[serde_json::Value]) -> Vec<AlertEvent> {
+        let absence = rule.detection.absence.as_ref().unwrap();
+        let mut tracker = AbsenceTracker::new(rule, absence, &LogType::FlatJson).unwrap();
+        for event in events {
+            tracker.observe(event);
+        }
+        tracker.finish(None)
+    }
+
+    #[test]
+    fn test_absence_over_whole_coverage() {
+        let rule = rule("    min_count: 1\n");
+        let events = vec![
+            json!({ "type": "login", "timestamp": "2024-01-01T10:00:00Z" }),
+            json!({ "type": "login", "timestamp": "2024-01-01T12:00:00Z" }),
+        ];
+
+        let alerts = evaluate(&rule, &events);
+        assert_eq!(alerts.len(), 1);
+        let absence = alerts[0].absence.as_ref().unwrap();
+        assert_eq!(absence.observed, 0);
+        assert_eq!(
+            absence.window_start.as_deref(),
+            Some("2024-01-01T10:00:00+00:00")
+        );
+        assert!(alerts[0].evidence.is_empty());
+
+        let mut present = events.clone();
+        present.push(json!({ "type": "heartbeat", "timestamp": "2024-01-01T11:00:00Z" }));
+        assert!(evaluate(&rule, &present).is_empty());
+    }
+
+    #[test]
+    fn test_absence_per_entity_and_window() {
+        let rule = rule("    window: 1h\n    by: host\n");
+        let events = vec![
+            json!({ "type": "heartbeat", "host": "a", "timestamp": "2024-01-01T10:00:00Z" }),
+            json!({ "type": "heartbeat", "host": "a", "timestamp": "2024-01-01T11:30:00Z" }),
+            json!({ "type": "heartbeat", "host": "b", "timestamp": "2024-01-01T10:10:00Z" }),
+            json!({ "type": "login", "host": "b", "timestamp": "2024-01-01T11:40:00Z" }),
+        ];
+
+        let alerts = evaluate(&rule, &events);
+        assert_eq!(alerts.len(), 1);
+        let absence = alerts[0].absence.as_ref().unwrap();
+        assert_eq!(absence.entity["host"], "b");
+        assert_eq!(
+            absence.window_start.as_deref(),
+            Some("2024-01-01T11:00:00+00:00")
+        );
+        assert_eq!(
+            absence.window_end.as_deref(),
+            Some("2024-01-01T11:40:01+00:00")
+        );
+    }
+
+    #[test]
+    fn test_absence_of_expected_entity() {
+        let rule = rule("    by: user\n    expected: [backup-svc, alice]\n    min_count: 2\n");
+        let events = vec![
+            json!({ "type": "heartbeat", "user": "alice", "timestamp": "2024-01-01T10:00:00Z" }),
+            json!({ "type": "heartbeat", "user": "alice", "timestamp": "2024-01-01T10:05:00Z" }),
+            json!({ "type": "heartbeat", "user": "bob", "timestamp": "2024-01-01T10:05:00Z" }),
+        ];
+
+        let alerts = evaluate(&rule, &events);
+        assert_eq!(alerts.len(), 1);
+        let absence = alerts[0].absence.as_ref().unwrap();
+        assert_eq!(absence.entity["user"], "backup-svc");
+        assert_eq!(absence.expected, 2);
+    }
+
+    #[test]
+    fn test_invalid_absence_rejected() {
+        let absence: Absence = serde_yaml::from_str("min_count: 0\n").unwrap();
+        assert!(validate_absence(&absence).is_err());
+        let absence: Absence = serde_yaml::from_str("expected: [a]\n").unwrap();
+        assert!(validate_absence(&absence).is_err());
+        let absence: Absence = serde_yaml::from_str("window: soon\n").unwrap();
+        assert!(validate_absence(&absence).is_err());
+    }
+}
@@ -0,0 +1,621 @@
+//! Threshold aggregation for detection rules.
+//!
+//! Rules with an `aggregation` block don't alert on every match. Instead,
+//! matching events are grouped by an optional key field and a window of the
+//! rule's length slides over each group's events in time order; a window
+//! produces an alert only if its event count satisfies the threshold (e.g.
+//! "> 5" failed logons in "5m"), wherever the burst falls on the clock. The
+//! events of an alerting window are not counted again, so one burst gives one
+//! alert. With a `distinct_field`, the threshold applies to the number of
+//! distinct values of that field instead (e.g. "> 20" distinct eventNames per
+//! access key).
+//!
+//! A `having` condition tests several aggregates of a bucket at once, like a
+//! SQL HAVING clause: "count(distinct eventName) > 10 AND count > 100".
+//! Supported aggregates are `count` and `count(distinct <field>)`; terms are
+//! combined with AND (binding tighter) and OR. When both `threshold` and
+//! `having` are set, a bucket must satisfy both.
+
+use std::collections::{BTreeMap, HashMap};
+
+use crate::db_engine;
+use crate::event_time;
+use crate::models::{Aggregation, AggregationBucket, LogType, SiemError};
+
+/// Comparison used by a threshold.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ThresholdOp {
+    Greater,
+    GreaterOrEqual,
+    Less,
+    LessOrEqual,
+    Equal,
+}
+
+/// Parsed threshold such as "> 5".
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Threshold {
+    op: ThresholdOp,
+    value: usize,
+}
+
+impl Threshold {
+    /// Check whether a count satisfies the threshold.
+    pub fn is_met(&self, count: usize) -> bool {
+        match self.op {
+            ThresholdOp::Greater => count > self.value,
+            ThresholdOp::GreaterOrEqual => count >= self.value,
+            ThresholdOp::Less => count < self.value,
+            ThresholdOp::LessOrEqual => count <= self.value,
+            ThresholdOp::Equal => count == self.value,
+        }
+    }
+}
+
+/// Parse a threshold: ">", ">=", "<", "<=", "=" or "==" followed by a number.
+/// A bare number means ">=".
+pub fn parse_threshold(threshold: &str) -> Result<Threshold, SiemError> {
+    let threshold = threshold.trim();
+
+    let (op, rest) = if let Some(rest) = threshold.strip_prefix(">=") {
+        (ThresholdOp::GreaterOrEqual, rest)
+    } else if let Some(rest) = threshold.strip_prefix("<=") {
+        (ThresholdOp::LessOrEqual, rest)
+    } else if let Some(rest) = threshold.strip_prefix("==") {
+        (ThresholdOp::Equal, rest)
+    } else if let Some(rest) = threshold.strip_prefix('>') {
+        (ThresholdOp::Greater, rest)
+    } else if let Some(rest) = threshold.strip_prefix('<') {
+        (ThresholdOp::Less, rest)
+    } else if let Some(rest) = threshold.strip_prefix('=') {
+        (ThresholdOp::Equal, rest)
+    } else {
+        (ThresholdOp::GreaterOrEqual, threshold)
+    };
+
+    let value = rest
+        .trim()
+        .parse::<usize>()
+        .map_err(|_| SiemError::Rule(format!("Invalid aggregation threshold: '{}'", threshold)))?;
+
+    Ok(Threshold { op, value })
+}
+
+/// Aggregate computed over the events of a bucket.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Measure {
+    Count,
+    Distinct(String),
+}
+
+impl Measure {
+    /// Display name, also the key in `AggregationBucket::measures`.
+    fn label(&self) -> String {
+        match self {
+            Measure::Count => "count".to_string(),
+            Measure::Distinct(field) => format!("count(distinct {})", field),
+        }
+    }
+}
+
+/// Aggregates of the events in a window, updated as events enter and leave it.
+struct WindowCounts {
+    events: usize,
+    /// Occurrences of each value of the fields whose distinct values are measured
+    values: HashMap<String, HashMap<String, usize>>,
+}
+
+impl WindowCounts {
+    fn new<'f>(distinct_fields: impl IntoIterator<Item = &'f String>) -> Self {
+        WindowCounts {
+            events: 0,
+            values: distinct_fields
+                .into_iter()
+                .map(|field| (field.clone(), HashMap::new()))
+                .collect(),
+        }
+    }
+
+    /// Count an event entering the window (array fields contribute every element).
+    fn add(&mut self, event: &serde_json::Value) {
+        self.events += 1;
+        for (field, counts) in &mut self.values {
+            for value in db_engine::get_field_values(event, field) {
+                *counts.entry(value).or_default() += 1;
+            }
+        }
+    }
+
+    /// Forget an event leaving the window.
+    fn remove(&mut self, event: &serde_json::Value) {
+        self.events -= 1;
+        for (field, counts) in &mut self.values {
+            for value in db_engine::get_field_values(event, field) {
+                if let Some(count) = counts.get_mut(&value) {
+                    *count -= 1;
+                    if *count == 0 {
+                        counts.remove(&value);
+                    }
+                }
+            }
+        }
+    }
+
+    fn distinct(&self, field: &str) -> usize {
+        self.values.get(field).map_or(0, HashMap::len)
+    }
+
+    fn value(&self, measure: &Measure) -> usize {
+        match measure {
+            Measure::Count => self.events,
+            Measure::Distinct(field) => self.distinct(field),
+        }
+    }
+}
+
+/// Parsed HAVING condition: OR of AND-ed aggregate comparisons.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Having {
+    any_of: Vec<Vec<(Measure, Threshold)>>,
+}
+
+impl Having {
+    /// Fields whose distinct values the condition counts.
+    fn distinct_fields(&self) -> impl Iterator<Item = &String> {
+        self.any_of
+            .iter()
+            .flatten()
+            .filter_map(|(measure, _)| match measure {
+                Measure::Distinct(field) => Some(field),
+                Measure::Count => None,
+            })
+    }
+
+    /// Compute every aggregate the condition uses.
+    fn measure(&self, counts: &WindowCounts) -> BTreeMap<String, usize> {
+        self.any_of
+            .iter()
+            .flatten()
+            .map(|(measure, _)| (measure.label(), counts.value(measure)))
+            .collect()
+    }
+
+    /// Check the condition against computed aggregates.
+    fn is_met(&self, values: &BTreeMap<String, usize>) -> bool {
+        self.any_of.iter().any(|all_of| {
+            all_of.iter().all(|(measure, threshold)| {
+                values
+                    .get(&measure.label())
+                    .is_some_and(|value| threshold.is_met(*value))
+            })
+        })
+    }
+}
+
+/// Parse a HAVING condition such as "count(distinct eventName) > 10 AND count > 100".
+pub fn parse_having(having: &str) -> Result<Having, SiemError> {
+    let invalid = || SiemError::Rule(format!("Invalid aggregation having: '{}'", having));
+
+    let mut any_of: Vec<Vec<(Measure, Threshold)>> = Vec::new();
+    let mut all_of: Vec<(Measure, Threshold)> = Vec::new();
+    let mut term: Vec<&str> = Vec::new();
+
+    for word in having.split_whitespace().chain(std::iter::once("OR")) {
+        let is_and = word.eq_ignore_ascii_case("AND");
+        let is_or = word.eq_ignore_ascii_case("OR");
+        if !is_and && !is_or {
+            term.push(word);
+            continue;
+        }
+
+        if term.is_empty() {
+            return Err(invalid());
+        }
+        all_of.push(parse_having_term(&term.join(" ")).ok_or_else(invalid)?);
+        term.clear();
+
+        if is_or {
+            any_of.push(std::mem::take(&mut all_of));
+        }
+    }
+
+    Ok(Having { any_of })
+}
+
+/// Parse one "<aggregate> <op> <number>" term.
+fn parse_having_term(term: &str) -> Option<(Measure, Threshold)> {
+    let split = term.find(['>', '<', '='])?;
+    let (aggregate, threshold) = term.split_at(split);
+
+    let aggregate: String = aggregate.split_whitespace().collect::<Vec<_>>().join(" ");
+    let lower = aggregate.to_lowercase();
+    let measure = if lower == "count" || lower == "count(*)" {
+        Measure::Count
+    } else if lower.starts_with("count(distinct ") && lower.ends_with(')') {
+        let field = aggregate
+            .get("count(distinct ".len()..aggregate.len() - 1)?
+            .trim();
+        if field.is_empty() {
+            return None;
+        }
+        Measure::Distinct(field.to_string())
+    } else {
+        return None;
+    };
+
+    Some((measure, parse_threshold(threshold).ok()?))
+}
+
+/// Parse a window duration such as "30s", "5m", "1h" or "1d" into seconds.
+pub fn parse_window(window: &str) -> Result<i64, SiemError> {
+    let window = window.trim();
+    let invalid = || SiemError::Rule(format!("Invalid aggregation window: '{}'", window));
+
+    let split = window
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(window.len());
+    let (number, unit) = window.split_at(split);
+
+    let number: i64 = number.parse().map_err(|_| invalid())?;
+    let multiplier = match unit.trim() {
+        "s" | "" => 1,
+        "m" => 60,
+        "h" => 3600,
+        "d" => 86400,
+        _ => return Err(invalid()),
+    };
+
+    if number <= 0 {
+        return Err(invalid());
+    }
+
+    Ok(number * multiplier)
+}
+
+/// Group matching events by key and report the windows meeting the threshold.
+///
+/// Each key's events are sorted by time and a window starts at each event in
+/// turn, spanning the events up to `window` after it. A window meeting the
+/// threshold becomes a bucket, and the next window starts after its last
+/// event. Events without a parseable timestamp are collected into a single
+/// bucket per key with no window bounds.
+pub fn aggregate_events(
+    aggregation: &Aggregation,
+    events: Vec<serde_json::Value>,
+    log_type: &LogType,
+) -> Result<Vec<(AggregationBucket, Vec<serde_json::Value>)>, SiemError> {
+    let window_secs = parse_window(&aggregation.window)?;
+    let threshold = if aggregation.threshold.trim().is_empty() {
+        None
+    } else {
+        Some(parse_threshold(&aggregation.threshold)?)
+    };
+    let having = aggregation
+        .having
+        .as_deref()
+        .map(parse_having)
+        .transpose()?;
+    if threshold.is_none() && having.is_none() {
+        return Err(SiemError::Rule(
+            "Aggregation needs a threshold or a having condition".to_string(),
+        ));
+    }
+
+    // Timed and untimed events of each key
+    type Group = (Vec<(i64, serde_json::Value)>, Vec<serde_json::Value>);
+    let mut groups: BTreeMap<Vec<String>, Group> = BTreeMap::new();
+
+    for event in events {
+        let key: Vec<String> = aggregation
+            .group_by
+            .iter()
+            .map(|field| group_value(&event, field))
+            .collect();
+
+        let group = groups.entry(key).or_default();
+        match event_time::event_time(&event, aggregation.timestamp_field.as_deref(), log_type) {
+            Some(ts) => group.0.push((ts.timestamp(), event)),
+            None => group.1.push(event),
+        }
+    }
+
+    let distinct_fields: Vec<&String> = aggregation
+        .distinct_field
+        .iter()
+        .chain(having.iter().flat_map(Having::distinct_fields))
+        .collect();
+    let new_counts = || WindowCounts::new(distinct_fields.iter().copied());
+
+    // Build the bucket of a window if it meets the threshold and the having condition
+    let evaluate = |key: &[String], counts: &WindowCounts, start: Option<i64>| {
+        // Either count distinct values of a field or count events
+        let distinct_count = aggregation
+            .distinct_field
+            .as_deref()
+            .map(|field| counts.distinct(field));
+        let measured = distinct_count.unwrap_or(counts.events);
+
+        if threshold.is_some_and(|t| !t.is_met(measured)) {
+            return None;
+        }
+
+        let measures = having
+            .as_ref()
+            .map(|h| h.measure(counts))
+            .unwrap_or_default();
+        if having.as_ref().is_some_and(|h| !h.is_met(&measures)) {
+            return None;
+        }
+
+        Some(AggregationBucket {
+            group: aggregation
+                .group_by
+                .iter()
+                .cloned()
+                .zip(key.iter().cloned())
+                .collect(),
+            window_start: start.and_then(format_epoch),
+            window_end: start.and_then(|s| format_epoch(s + window_secs)),
+            count: counts.events,
+            distinct_count,
+            measures,
+        })
+    };
+
+    let mut buckets = Vec::new();
+    for (key, (mut timed, untimed)) in groups {
+        timed.sort_by_key(|(ts, _)| *ts);
+
+        let mut counts = new_counts();
+        let (mut first, mut end) = (0, 0);
+        while first < timed.len() {
+            let start = timed[first].0;
+            while end < timed.len() && timed[end].0 < start + window_secs {
+                counts.add(&timed[end].1);
+                end += 1;
+            }
+
+            if let Some(bucket) = evaluate(&key, &counts, Some(start)) {
+                let events = timed[first..end].iter().map(|(_, e)| e.clone()).collect();
+                buckets.push((bucket, events));
+                // One burst is one alert: the next window starts after it
+                counts = new_counts();
+                first = end;
+            } else {
+                counts.remove(&timed[first].1);
+                first += 1;
+            }
+        }
+
+        if !untimed.is_empty() {
+            let mut counts = new_counts();
+            untimed.iter().for_each(|event| counts.add(event));
+            if let Some(bucket) = evaluate(&key, &counts, None) {
+                buckets.push((bucket, untimed));
+            }
+        }
+    }
+
+    Ok(buckets)
+}
+
+/// Get the grouping value of a field (first value for array fields, empty if missing).
+pub fn group_value(event: &serde_json::Value, field: &str) -> String {
+    db_engine::get_field_values(event, field)
+        .into_iter()
+        .next()
+        .unwrap_or_default()
+}
+
+/// Format epoch seconds as RFC 3339.
+pub fn format_epoch(secs: i64) -> Option<String> {
+    chrono::DateTime::from_timestamp(secs, 0).map(|dt| dt.to_rfc3339())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn aggregation(window: &str, threshold: &str, group_by: &[&str]) -> Aggregation {
+        Aggregation {
+            enabled: true,
+            window: window.to_string(),
+            threshold: threshold.to_string(),
+            group_by: group_by.iter().map(|s| s.to_string()).collect(),
+            distinct_field: None,
+            timestamp_field: None,
+            having: None,
+        }
+    }
+
+    #[test]
+    fn test_parse_threshold() {
+        assert!(parse_threshold("> 5").unwrap().is_met(6));
+        assert!(!parse_threshold("> 5").unwrap().is_met(5));
+        assert!(parse_threshold(">=5").unwrap().is_met(5));
+        assert!(parse_threshold("3").unwrap().is_met(3));
+        assert!(parse_threshold("< 2").unwrap().is_met(1));
+        assert!(parse_threshold("many").is_err());
+    }
+
+    #[test]
+    fn test_parse_window() {
+        assert_eq!(parse_window("30s").unwrap(), 30);
+        assert_eq!(parse_window("5m").unwrap(), 300);
+        assert_eq!(parse_window("1h").unwrap(), 3600);
+        assert_eq!(parse_window("2d").unwrap(), 172800);
+        assert!(parse_window("5 parsecs").is_err());
+        assert!(parse_window("0m").is_err());
+    }
+
+    #[test]
+    fn test_aggregate_by_key_and_window() {
+        let events = vec![
+            serde_json::json!({ "eventTime": "2025-12-16T11:00:10Z", "sourceIPAddress": "1.2.3.4" }),
+            serde_json::json!({ "eventTime": "2025-12-16T11:01:00Z", "sourceIPAddress": "1.2.3.4" }),
+            serde_json::json!({ "eventTime": "2025-12-16T11:04:59Z", "sourceIPAddress": "1.2.3.4" }),
+            serde_json::json!({ "eventTime": "2025-12-16T11:02:00Z", "sourceIPAddress": "5.6.7.8" }),
+            serde_json::json!({ "eventTime": "2025-12-16T11:06:00Z", "sourceIPAddress": "1.2.3.4" }),
+        ];
+
+        let agg = aggregation("5m", ">= 3", &["sourceIPAddress"]);
+        let buckets = aggregate_events(&agg, events, &LogType::CloudTrail).unwrap();
+
+        assert_eq!(buckets.len(), 1);
+        let (bucket, evidence) = &buckets[0];
+        assert_eq!(bucket.group["sourceIPAddress"], "1.2.3.4");
+        assert_eq!(bucket.count, 3);
+        assert_eq!(evidence.len(), 3);
+        assert_eq!(
+            bucket.window_start.as_deref(),
+            Some("2025-12-16T11:00:10+00:00")
+        );
+        assert_eq!(
+            bucket.window_end.as_deref(),
+            Some("2025-12-16T11:05:10+00:00")
+        );
+    }
+
+    #[test]
+    fn test_window_slides_across_clock_boundaries() {
+        // Four failures in under two minutes, split 2/2 by the 11:05 boundary
+        let events: Vec<serde_json::Value> = ["11:04:00", "11:04:30", "11:05:10", "11:05:40"]
+            .iter()
+            .map(|time| {
+                serde_json::json!({ "eventTime": format!("2025-12-16T{}Z", time), "user": "alice" })
+            })
+            .collect();
+
+        let agg = aggregation("5m", ">= 4", &["user"]);
+        let buckets = aggregate_events(&agg, events.clone(), &LogType::FlatJson).unwrap();
+        assert_eq!(buckets.len(), 1);
+        assert_eq!(buckets[0].0.count, 4);
+        assert_eq!(
+            buckets[0].0.window_start.as_deref(),
+            Some("2025-12-16T11:04:00+00:00")
+        );
+
+        // Events more than a window apart never add up
+        let agg = aggregation("1m", ">= 3", &["user"]);
+        assert!(aggregate_events(&agg, events.clone(), &LogType::FlatJson)
+            .unwrap()
+            .is_empty());
+
+        // A burst is reported once, not once per overlapping window
+        let agg = aggregation("5m", ">= 2", &["user"]);
+        let buckets = aggregate_events(&agg, events, &LogType::FlatJson).unwrap();
+        assert_eq!(buckets.len(), 1);
+        assert_eq!(buckets[0].0.count, 4);
+    }
+
+    #[test]
+    fn test_aggregate_per_entity_with_multiple_fields() {
+        let events = vec![
+            serde_json::json!({ "eventTime": "2025-12-16T11:00:00Z", "user": "alice", "ip": "1.1.1.1" }),
+            serde_json::json!({ "eventTime": "2025-12-16T11:00:01Z", "user": "alice", "ip": "1.1.1.1" }),
+            serde_json::json!({ "eventTime": "2025-12-16T11:00:02Z", "user": "alice", "ip": "2.2.2.2" }),
+            serde_json::json!({ "eventTime": "2025-12-16T11:00:03Z", "user": "bob", "ip": "1.1.1.1" }),
+        ];
+
+        let agg = aggregation("1m", "> 1", &["user", "ip"]);
+        let buckets = aggregate_events(&agg, events, &LogType::FlatJson).unwrap();
+
+        assert_eq!(buckets.len(), 1);
+        assert_eq!(buckets[0].0.group["user"], "alice");
+        assert_eq!(buckets[0].0.group["ip"], "1.1.1.1");
+        assert_eq!(buckets[0].0.count, 2);
+    }
+
+    #[test]
+    fn test_group_by_accepts_single_string() {
+        let agg: Aggregation =
+            serde_yaml::from_str("window: 5m\nthreshold: '> 5'\ngroup_by: sourceIPAddress\n")
+                .unwrap();
+        assert_eq!(agg.group_by, vec!["sourceIPAddress".to_string()]);
+        assert!(agg.enabled);
+    }
+
+    #[test]
+    fn test_count_distinct_aggregation() {
+        let mut events = Vec::new();
+        for name in ["ListUsers", "ListRoles", "ListRoles", "GetPolicy"] {
+            events.push(serde_json::json!({
+                "eventTime": "2025-12-16T11:00:00Z",
+                "userIdentity": { "accessKeyId": "AKIA1" },
+                "eventName": name
+            }));
+        }
+
+        let mut agg = aggregation("10m", "> 2", &["userIdentity.accessKeyId"]);
+        agg.distinct_field = Some("eventName".to_string());
+        let buckets = aggregate_events(&agg, events.clone(), &LogType::CloudTrail).unwrap();
+
+        assert_eq!(buckets.len(), 1);
+        assert_eq!(buckets[0].0.count, 4);
+        assert_eq!(buckets[0].0.distinct_count, Some(3));
+
+        agg.threshold = "> 3".to_string();
+        assert!(aggregate_events(&agg, events, &LogType::CloudTrail)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_parse_having() {
+        let having = parse_having("count(distinct eventName) > 10 AND count > 100").unwrap();
+        assert_eq!(having.any_of.len(), 1);
+        assert_eq!(having.any_of[0].len(), 2);
+        assert_eq!(
+            having.any_of[0][0].0,
+            Measure::Distinct("eventName".to_string())
+        );
+
+        let having = parse_having("COUNT >= 5 or count(DISTINCT user) = 1").unwrap();
+        assert_eq!(having.any_of.len(), 2);
+        assert_eq!(having.any_of[1][0].0, Measure::Distinct("user".to_string()));
+
+        assert!(parse_having("").is_err());
+        assert!(parse_having("count > 1 AND").is_err());
+        assert!(parse_having("sum(bytes) > 1").is_err());
+        assert!(parse_having("count(distinct ) > 1").is_err());
+        assert!(parse_having("count 5").is_err());
+    }
+
+    #[test]
+    fn test_aggregate_with_having_on_several_aggregates() {
+        let mut events = Vec::new();
+        // alice enumerates 4 distinct APIs in 6 calls, bob repeats one API 6 times
+        for (i, name) in ["List", "Get", "Describe", "Head", "List", "Get"]
+            .iter()
+            .enumerate()
+        {
+            events.push(serde_json::json!({
+                "eventTime": format!("2025-12-16T11:00:{:02}Z", i),
+                "user": "alice",
+                "eventName": name
+            }));
+            events.push(serde_json::json!({
+                "eventTime": format!("2025-12-16T11:00:{:02}Z", i),
+                "user": "bob",
+                "eventName": "List"
+            }));
+        }
+
+        let mut agg = aggregation("1h", "", &["user"]);
+        agg.having = Some("count(distinct eventName) > 3 AND count > 5".to_string());
+        let buckets = aggregate_events(&agg, events, &LogType::FlatJson).unwrap();
+
+        assert_eq!(buckets.len(), 1);
+        let bucket = &buckets[0].0;
+        assert_eq!(bucket.group["user"], "alice");
+        assert_eq!(bucket.measures["count"], 6);
+        assert_eq!(bucket.measures["count(distinct eventName)"], 4);
+    }
+
+    #[test]
+    fn test_aggregate_requires_threshold_or_having() {
+        let agg = aggregation("1h", "", &["user"]);
+        let events = vec![serde_json::json!({ "user": "alice" })];
+        assert!(aggregate_events(&agg, events, &LogType::FlatJson).is_err());
+    }
+}
@@ -0,0 +1,1213 @@
+//! Alert exporters behind a common `ExportSink` trait.
+//!
+//! Each output format implements `ExportSink` and is listed in `SINKS`; the
+//! `export_alerts` command looks sinks up by ID, so adding a format only
+//! means writing the sink and registering it here. The severity-based alert
+//! router reuses the same sinks for its file outputs.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::OpenOptions;
+use std::io::{BufWriter, Write};
+
+use crate::anonymize::{AnonymizeOptions, Pseudonymizer};
+use crate::indicators::{self, Indicator, IndicatorKind};
+use crate::models::{AlertEvent, ScanHistoryEntry, SiemError};
+
+/// Default Elasticsearch index for bulk exports.
+const DEFAULT_ELASTIC_INDEX: &str = "offline-siem-alerts";
+
+/// Options shared by all sinks.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ExportOptions {
+    /// Destination file path
+    pub dest_path: String,
+    /// Append to the file instead of replacing it
+    #[serde(default)]
+    pub append: bool,
+    /// Include evidence events (formats that support it)
+    #[serde(default = "default_true")]
+    pub include_evidence: bool,
+    /// Target index (Elastic bulk only)
+    #[serde(default)]
+    pub index: Option<String>,
+    /// Report title (HTML only)
+    #[serde(default)]
+    pub title: Option<String>,
+    /// Pseudonymize selected fields before writing
+    #[serde(default)]
+    pub anonymize: Option<AnonymizeOptions>,
+    /// Columns to write, in order (CSV and JSONL only; all when empty)
+    #[serde(default)]
+    pub columns: Vec<String>,
+    /// Write one row per evidence event, its fields flattened to
+    /// `evidence.<dotted.path>` columns (CSV and JSONL only)
+    #[serde(default)]
+    pub flatten_evidence: bool,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+impl ExportOptions {
+    /// Options appending full alerts to a file (used by the alert router).
+    pub fn append_to(path: &str) -> Self {
+        ExportOptions {
+            dest_path: path.to_string(),
+            append: true,
+            include_evidence: true,
+            index: None,
+            title: None,
+            anonymize: None,
+            columns: Vec::new(),
+            flatten_evidence: false,
+        }
+    }
+}
+
+/// Description of a registered sink, for the frontend.
+#[derive(Debug, Serialize, Clone)]
+pub struct ExportSinkInfo {
+    pub id: String,
+    pub description: String,
+    pub extension: String,
+}
+
+/// Result of an export.
+#[derive(Debug, Serialize, Clone)]
+pub struct AlertExportSummary {
+    pub sink: String,
+    pub dest_path: String,
+    pub alert_count: usize,
+}
+
+/// An alert output format.
+pub trait ExportSink: Sync {
+    /// Unique identifier used to select the sink (e.g. "csv")
+    fn id(&self) -> &'static str;
+    /// Human-readable description
+    fn description(&self) -> &'static str;
+    /// Default file extension
+    fn extension(&self) -> &'static str;
+    /// Write alerts to the output.
+    fn write_alerts(
+        &self,
+        alerts: &[AlertEvent],
+        options: &ExportOptions,
+        out: &mut dyn Write,
+    ) -> std::io::Result<()>;
+}
+
+/// All registered sinks.
+static SINKS: &[&dyn ExportSink] = &[
+    &CsvSink,
+    &JsonlSink,
+    &StixSink,
+    &MispSink { scan: None },
+    &HtmlSink,
+    &SyslogSink,
+    &ElasticBulkSink,
+];
+
+/// List the registered sinks.
+pub fn list_sinks() -> Vec<ExportSinkInfo> {
+    SINKS
+        .iter()
+        .map(|sink| ExportSinkInfo {
+            id: sink.id().to_string(),
+            description: sink.description().to_string(),
+            extension: sink.extension().to_string(),
+        })
+        .collect()
+}
+
+/// Find a sink by ID.
+pub fn find_sink(id: &str) -> Option<&'static dyn ExportSink> {
+    SINKS
+        .iter()
+        .copied()
+        .find(|sink| sink.id().eq_ignore_ascii_case(id))
+}
+
+/// Export alerts with the given sink.
+pub fn export_alerts(
+    sink_id: &str,
+    alerts: &[AlertEvent],
+    options: &ExportOptions,
+) -> Result<AlertExportSummary, SiemError> {
+    let sink = find_sink(sink_id)
+        .ok_or_else(|| SiemError::FileIO(format!("Unknown export sink: {}", sink_id)))?;
+    export_with_sink(sink, alerts, options)
+}
+
+/// Export the alerts of a recorded scan as a MISP event identified by the
+/// scan's ID, so exporting the scan again updates the same event.
+pub fn export_alerts_misp(
+    scan: &ScanHistoryEntry,
+    alerts: &[AlertEvent],
+    options: &ExportOptions,
+) -> Result<AlertExportSummary, SiemError> {
+    export_with_sink(&MispSink { scan: Some(scan) }, alerts, options)
+}
+
+/// Write alerts to the destination file with a sink.
+fn export_with_sink(
+    sink: &dyn ExportSink,
+    alerts: &[AlertEvent],
+    options: &ExportOptions,
+) -> Result<AlertExportSummary, SiemError> {
+    let file = OpenOptions::new()
+        .create(true)
+        .write(true)
+        .append(options.append)
+        .truncate(!options.append)
+        .open(&options.dest_path)
+        .map_err(|e| SiemError::FileIO(format!("Cannot open {}: {}", options.dest_path, e)))?;
+
+    let anonymized;
+    let alerts = match &options.anonymize {
+        Some(anonymize) => {
+            anonymized = Pseudonymizer::new(anonymize)?.apply_alerts(alerts);
+            &anonymized[..]
+        }
+        None => alerts,
+    };
+
+    let mut out = BufWriter::new(file);
+    sink.write_alerts(alerts, options, &mut out)
+        .and_then(|_| out.flush())
+        .map_err(|e| SiemError::FileIO(format!("Cannot write {}: {}", options.dest_path, e)))?;
+
+    Ok(AlertExportSummary {
+        sink: sink.id().to_string(),
+        dest_path: options.dest_path.clone(),
+        alert_count: alerts.len(),
+    })
+}
+
+/// Serialize an alert, optionally without its evidence.
+fn alert_json(alert: &AlertEvent, include_evidence: bool) -> serde_json::Value {
+    let mut value = serde_json::to_value(alert).unwrap_or(serde_json::Value::Null);
+    if !include_evidence {
+        if let Some(obj) = value.as_object_mut() {
+            obj.remove("evidence");
+        }
+    }
+    value
+}
+
+/// Alert columns written by the tabular sinks, in default order.
+const ALERT_COLUMNS: [&str; 9] = [
+    "rule_id",
+    "rule_title",
+    "severity",
+    "timestamp",
+    "match_count",
+    "source_file",
+    "remediation",
+    "falsepositives",
+    "references",
+];
+
+/// Flat rows of an alert for the tabular sinks: one row, with the evidence
+/// as a JSON array, or one row per evidence event with flattened fields.
+fn alert_rows(
+    alert: &AlertEvent,
+    options: &ExportOptions,
+) -> Vec<serde_json::Map<String, serde_json::Value>> {
+    let mut row = serde_json::Map::new();
+    row.insert("rule_id".into(), alert.rule_id.clone().into());
+    row.insert("rule_title".into(), alert.rule_title.clone().into());
+    row.insert("severity".into(), alert.severity.clone().into());
+    row.insert("timestamp".into(), alert.timestamp.clone().into());
+    row.insert("match_count".into(), alert.match_count.into());
+    row.insert(
+        "source_file".into(),
+        alert.source_file.clone().unwrap_or_default().into(),
+    );
+    row.insert(
+        "remediation".into(),
+        alert.remediation.clone().unwrap_or_default().into(),
+    );
+    row.insert(
+        "falsepositives".into(),
+        alert.falsepositives.join("; ").into(),
+    );
+    row.insert("references".into(), alert.references.join("; ").into());
+
+    if !options.include_evidence {
+        return vec![row];
+    }
+    if !options.flatten_evidence {
+        row.insert("evidence".into(), alert.evidence.clone().into());
+        return vec![row];
+    }
+    if alert.evidence.is_empty() {
+        return vec![row];
+    }
+
+    alert
+        .evidence
+        .iter()
+        .map(|event| {
+            let mut event_row = row.clone();
+            flatten_into(&mut event_row, "evidence", event);
+            event_row
+        })
+        .collect()
+}
+
+/// Flatten nested objects into dotted keys under `prefix`; arrays and
+/// scalars are kept as values.
+fn flatten_into(
+    row: &mut serde_json::Map<String, serde_json::Value>,
+    prefix: &str,
+    value: &serde_json::Value,
+) {
+    match value {
+        serde_json::Value::Object(obj) if !obj.is_empty() => {
+            for (key, value) in obj {
+                flatten_into(row, &format!("{}.{}", prefix, key), value);
+            }
+        }
+        _ => {
+            row.insert(prefix.to_string(), value.clone());
+        }
+    }
+}
+
+/// Columns of a tabular export: the selected ones, or the default alert
+/// columns followed by every evidence column, sorted by name.
+fn table_columns(
+    rows: &[serde_json::Map<String, serde_json::Value>],
+    options: &ExportOptions,
+) -> Vec<String> {
+    if !options.columns.is_empty() {
+        return options.columns.clone();
+    }
+
+    let mut columns: Vec<String> = ALERT_COLUMNS.iter().map(|c| c.to_string()).collect();
+    for row in rows {
+        for key in row.keys() {
+            if !columns.contains(key) {
+                columns.push(key.clone());
+            }
+        }
+    }
+    // Evidence columns after the alert ones, sorted by name
+    columns[ALERT_COLUMNS.len()..].sort();
+    columns
+}
+
+// ============================================================================
+// CSV
+// ============================================================================
+
+/// One row per alert.
+pub struct CsvSink;
+
+impl ExportSink for CsvSink {
+    fn id(&self) -> &'static str {
+        "csv"
+    }
+    fn description(&self) -> &'static str {
+        "Comma-separated values, one row per alert"
+    }
+    fn extension(&self) -> &'static str {
+        "csv"
+    }
+    fn write_alerts(
+        &self,
+        alerts: &[AlertEvent],
+        options: &ExportOptions,
+        out: &mut dyn Write,
+    ) -> std::io::Result<()> {
+        let rows: Vec<_> = alerts
+            .iter()
+            .flat_map(|alert| alert_rows(alert, options))
+            .collect();
+        let columns = table_columns(&rows, options);
+        if !options.append {
+            let header: Vec<String> = columns.iter().map(|c| csv_field(c)).collect();
+            writeln!(out, "{}", header.join(","))?;
+        }
+
+        for row in &rows {
+            let cells: Vec<String> = columns
+                .iter()
+                .map(|column| match row.get(column) {
+                    None | Some(serde_json::Value::Null) => String::new(),
+                    Some(serde_json::Value::String(text)) => csv_field(text),
+                    Some(value) => csv_field(&value.to_string()),
+                })
+                .collect();
+            writeln!(out, "{}", cells.join(","))?;
+        }
+        Ok(())
+    }
+}
+
+/// Quote a CSV field when needed (RFC 4180).
+pub(crate) fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+// ============================================================================
+// JSON Lines
+// ============================================================================
+
+/// One JSON object per line.
+pub struct JsonlSink;
+
+impl ExportSink for JsonlSink {
+    fn id(&self) -> &'static str {
+        "jsonl"
+    }
+    fn description(&self) -> &'static str {
+        "JSON Lines, one alert per line"
+    }
+    fn extension(&self) -> &'static str {
+        "jsonl"
+    }
+    fn write_alerts(
+        &self,
+        alerts: &[AlertEvent],
+        options: &ExportOptions,
+        out: &mut dyn Write,
+    ) -> std::io::Result<()> {
+        // Full alerts, unless columns or flattening ask for flat rows
+        if options.columns.is_empty() && !options.flatten_evidence {
+            for alert in alerts {
+                writeln!(out, "{}", alert_json(alert, options.include_evidence))?;
+            }
+            return Ok(());
+        }
+
+        for alert in alerts {
+            for row in alert_rows(alert, options) {
+                if options.columns.is_empty() {
+                    writeln!(out, "{}", serde_json::Value::Object(row))?;
+                    continue;
+                }
+                // Written by hand to keep the selected column order
+                let fields: Vec<String> = options
+                    .columns
+                    .iter()
+                    .map(|column| {
+                        let value = row.get(column).unwrap_or(&serde_json::Value::Null);
+                        format!("{}:{}", serde_json::Value::from(column.as_str()), value)
+                    })
+                    .collect();
+                writeln!(out, "{{{}}}", fields.join(","))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+// ============================================================================
+// STIX 2.1
+// ============================================================================
+
+/// STIX 2.1 bundle with one `incident` per alert, plus the indicators found
+/// in the evidence: each alert's observables as `observed-data`, one
+/// `indicator` per distinct observable and a `sighting` of it per alert.
+pub struct StixSink;
+
+impl ExportSink for StixSink {
+    fn id(&self) -> &'static str {
+        "stix"
+    }
+    fn description(&self) -> &'static str {
+        "STIX 2.1 bundle: incidents, indicators, observed data and sightings"
+    }
+    fn extension(&self) -> &'static str {
+        "json"
+    }
+    fn write_alerts(
+        &self,
+        alerts: &[AlertEvent],
+        options: &ExportOptions,
+        out: &mut dyn Write,
+    ) -> std::io::Result<()> {
+        let now = chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true);
+        let identity_id = format!("identity--{}", uuid::Uuid::new_v4());
+
+        // Observable and indicator IDs per distinct indicator
+        let mut stix_indicators: HashMap<(IndicatorKind, String), (String, String)> =
+            HashMap::new();
+
+        let mut objects = vec![serde_json::json!({
+            "type": "identity",
+            "spec_version": "2.1",
+            "id": identity_id,
+            "created": now,
+            "modified": now,
+            "name": "Offline SIEM",
+            "identity_class": "system"
+        })];
+
+        for alert in alerts {
+            let mut incident = serde_json::json!({
+                "type": "incident",
+                "spec_version": "2.1",
+                "id": format!("incident--{}", uuid::Uuid::new_v4()),
+                "created": now,
+                "modified": now,
+                "created_by_ref": identity_id,
+                "name": alert.rule_title,
+                "description": format!(
+                    "Rule {} matched {} event(s){}",
+                    alert.rule_id,
+                    alert.match_count,
+                    alert
+                        .source_file
+                        .as_deref()
+                        .map(|f| format!(" in {}", f))
+                        .unwrap_or_default()
+                ),
+                "labels": [alert.severity.to_lowercase()],
+                "x_offline_siem_rule_id": alert.rule_id,
+                "x_offline_siem_severity": alert.severity,
+                "x_offline_siem_detected_at": alert.timestamp,
+                "x_offline_siem_match_count": alert.match_count
+            });
+            if !alert.references.is_empty() {
+                incident["external_references"] =
+                    alert.references.iter().map(|r| stix_reference(r)).collect();
+            }
+            if !alert.falsepositives.is_empty() {
+                incident["x_offline_siem_false_positives"] =
+                    serde_json::json!(alert.falsepositives);
+            }
+            if let Some(remediation) = &alert.remediation {
+                incident["x_offline_siem_remediation"] = serde_json::json!(remediation);
+            }
+            if options.include_evidence {
+                incident["x_offline_siem_evidence"] = serde_json::json!(alert.evidence);
+            }
+            objects.push(incident);
+
+            let found = indicators::extract_indicators(alert);
+            if found.is_empty() {
+                continue;
+            }
+
+            let seen = stix_timestamp(&alert.timestamp).unwrap_or_else(|| now.clone());
+            let mut refs = Vec::new();
+            for indicator in &found {
+                let key = (indicator.kind, indicator.value.clone());
+                let ids = stix_indicators.entry(key).or_insert_with(|| {
+                    let observable_id = format!(
+                        "{}--{}",
+                        stix_observable_type(indicator.kind),
+                        uuid::Uuid::new_v4()
+                    );
+                    let indicator_id = format!("indicator--{}", uuid::Uuid::new_v4());
+                    objects.push(stix_observable(&observable_id, indicator));
+                    objects.push(serde_json::json!({
+                        "type": "indicator",
+                        "spec_version": "2.1",
+                        "id": indicator_id,
+                        "created": now,
+                        "modified": now,
+                        "created_by_ref": identity_id,
+                        "name": indicator.value,
+                        "indicator_types": ["anomalous-activity"],
+                        "pattern_type": "stix",
+                        "pattern": stix_pattern(indicator),
+                        "valid_from": now
+                    }));
+                    (observable_id, indicator_id)
+                });
+                refs.push(ids.clone());
+            }
+
+            let observed_id = format!("observed-data--{}", uuid::Uuid::new_v4());
+            objects.push(serde_json::json!({
+                "type": "observed-data",
+                "spec_version": "2.1",
+                "id": observed_id,
+                "created": now,
+                "modified": now,
+                "created_by_ref": identity_id,
+                "first_observed": seen,
+                "last_observed": seen,
+                "number_observed": alert.match_count.max(1),
+                "object_refs": refs.iter().map(|(observable_id, _)| observable_id).collect::<Vec<_>>()
+            }));
+            for (_, indicator_id) in refs {
+                objects.push(serde_json::json!({
+                    "type": "sighting",
+                    "spec_version": "2.1",
+                    "id": format!("sighting--{}", uuid::Uuid::new_v4()),
+                    "created": now,
+                    "modified": now,
+                    "created_by_ref": identity_id,
+                    "first_seen": seen,
+                    "last_seen": seen,
+                    "count": alert.match_count.max(1),
+                    "sighting_of_ref": indicator_id,
+                    "observed_data_refs": [observed_id],
+                    "where_sighted_refs": [identity_id],
+                    "description": format!("Evidence of rule {}: {}", alert.rule_id, alert.rule_title)
+                }));
+            }
+        }
+
+        let bundle = serde_json::json!({
+            "type": "bundle",
+            "id": format!("bundle--{}", uuid::Uuid::new_v4()),
+            "objects": objects
+        });
+        let content = serde_json::to_string_pretty(&bundle)?;
+        out.write_all(content.as_bytes())
+    }
+}
+
+/// STIX cyber-observable type of an indicator.
+fn stix_observable_type(kind: IndicatorKind) -> &'static str {
+    match kind {
+        IndicatorKind::Ipv4 => "ipv4-addr",
+        IndicatorKind::Ipv6 => "ipv6-addr",
+        IndicatorKind::UserName | IndicatorKind::Arn => "user-account",
+        IndicatorKind::Md5
+        | IndicatorKind::Sha1
+        | IndicatorKind::Sha256
+        | IndicatorKind::Imphash => "file",
+    }
+}
+
+/// STIX hash algorithm name of a hash indicator.
+fn stix_hash_name(kind: IndicatorKind) -> Option<&'static str> {
+    match kind {
+        IndicatorKind::Md5 => Some("MD5"),
+        IndicatorKind::Sha1 => Some("SHA-1"),
+        IndicatorKind::Sha256 => Some("SHA-256"),
+        // Not in hash-algorithm-ov, which is an open vocabulary
+        IndicatorKind::Imphash => Some("IMPHASH"),
+        _ => None,
+    }
+}
+
+/// STIX cyber-observable object for an indicator.
+fn stix_observable(id: &str, indicator: &Indicator) -> serde_json::Value {
+    let mut observable = serde_json::json!({
+        "type": stix_observable_type(indicator.kind),
+        "spec_version": "2.1",
+        "id": id
+    });
+    match (indicator.kind, stix_hash_name(indicator.kind)) {
+        (_, Some(algorithm)) => {
+            observable["hashes"] = serde_json::json!({ algorithm: indicator.value });
+        }
+        (IndicatorKind::UserName | IndicatorKind::Arn, _) => {
+            observable["user_id"] = serde_json::json!(indicator.value);
+        }
+        _ => observable["value"] = serde_json::json!(indicator.value),
+    }
+    observable
+}
+
+/// STIX pattern matching an indicator.
+fn stix_pattern(indicator: &Indicator) -> String {
+    let value = indicator.value.replace('\\', "\\\\").replace('\'', "\\'");
+    let property = match (indicator.kind, stix_hash_name(indicator.kind)) {
+        (_, Some(algorithm)) => format!("hashes.'{}'", algorithm),
+        (IndicatorKind::UserName | IndicatorKind::Arn, _) => "user_id".to_string(),
+        _ => "value".to_string(),
+    };
+    format!(
+        "[{}:{} = '{}']",
+        stix_observable_type(indicator.kind),
+        property,
+        value
+    )
+}
+
+/// Alert timestamp in the STIX format (UTC, milliseconds, `Z`).
+fn stix_timestamp(timestamp: &str) -> Option<String> {
+    chrono::DateTime::parse_from_rfc3339(timestamp)
+        .ok()
+        .map(|t| {
+            t.with_timezone(&chrono::Utc)
+                .to_rfc3339_opts(chrono::SecondsFormat::Millis, true)
+        })
+}
+
+/// STIX external reference for a rule reference (URL or identifier).
+fn stix_reference(reference: &str) -> serde_json::Value {
+    if is_url(reference) {
+        serde_json::json!({ "source_name": "rule-reference", "url": reference })
+    } else {
+        serde_json::json!({ "source_name": "rule-reference", "external_id": reference })
+    }
+}
+
+pub(crate) fn is_url(value: &str) -> bool {
+    value.starts_with("http://") || value.starts_with("https://")
+}
+
+// ============================================================================
+// MISP
+// ============================================================================
+
+/// MISP event with one attribute per distinct indicator found in the
+/// evidence. For a recorded scan, the event takes the scan's ID as UUID and
+/// the scan's date.
+pub struct MispSink<'a> {
+    pub scan: Option<&'a ScanHistoryEntry>,
+}
+
+impl ExportSink for MispSink<'_> {
+    fn id(&self) -> &'static str {
+        "misp"
+    }
+    fn description(&self) -> &'static str {
+        "MISP event, one attribute per indicator (IPs, users, ARNs, hashes)"
+    }
+    fn extension(&self) -> &'static str {
+        "json"
+    }
+    fn write_alerts(
+        &self,
+        alerts: &[AlertEvent],
+        options: &ExportOptions,
+        out: &mut dyn Write,
+    ) -> std::io::Result<()> {
+        let now = chrono::Utc::now();
+        let timestamp = now.timestamp().to_string();
+        let (uuid, date, default_info) = match self.scan {
+            Some(scan) => (
+                scan.scan_id.clone(),
+                scan.scanned_at.get(..10).unwrap_or_default().to_string(),
+                format!(
+                    "Offline SIEM scan {}: {} alert(s)",
+                    scan.scanned_at,
+                    alerts.len()
+                ),
+            ),
+            None => (
+                uuid::Uuid::new_v4().to_string(),
+                now.format("%Y-%m-%d").to_string(),
+                format!("Offline SIEM alerts: {} alert(s)", alerts.len()),
+            ),
+        };
+
+        let mut attributes: Vec<serde_json::Value> = Vec::new();
+        let mut seen: Vec<(&'static str, String)> = Vec::new();
+        let mut tags: Vec<serde_json::Value> = Vec::new();
+        for alert in alerts {
+            let tag =
+                serde_json::json!({ "name": format!("offline-siem:rule=\"{}\"", alert.rule_id) });
+            if !tags.contains(&tag) {
+                tags.push(tag);
+            }
+
+            for indicator in indicators::extract_indicators(alert) {
+                let (attribute_type, category, to_ids) = misp_attribute_type(&indicator);
+                let key = (attribute_type, indicator.value.clone());
+                if seen.contains(&key) {
+                    continue;
+                }
+                seen.push(key);
+                attributes.push(serde_json::json!({
+                    "uuid": uuid::Uuid::new_v4().to_string(),
+                    "type": attribute_type,
+                    "category": category,
+                    "value": indicator.value,
+                    "to_ids": to_ids,
+                    "distribution": "5",
+                    "timestamp": timestamp,
+                    "comment": format!(
+                        "{} ({}), field {}",
+                        alert.rule_title, alert.rule_id, indicator.field
+                    )
+                }));
+            }
+        }
+
+        let most_severe = alerts
+            .iter()
+            .map(|alert| crate::severity_order(&alert.severity))
+            .max()
+            .unwrap_or(0);
+        let threat_level = match most_severe {
+            4.. => "1",
+            3 => "2",
+            2 => "3",
+            _ => "4",
+        };
+
+        let event = serde_json::json!({
+            "Event": {
+                "uuid": uuid,
+                "info": options.title.clone().unwrap_or(default_info),
+                "date": date,
+                "timestamp": timestamp,
+                "threat_level_id": threat_level,
+                "analysis": "2",
+                "distribution": "0",
+                "published": false,
+                "Tag": tags,
+                "Attribute": attributes
+            }
+        });
+        let content = serde_json::to_string_pretty(&event)?;
+        out.write_all(content.as_bytes())
+    }
+}
+
+/// MISP attribute type, category and IDS flag of an indicator.
+fn misp_attribute_type(indicator: &Indicator) -> (&'static str, &'static str, bool) {
+    match indicator.kind {
+        IndicatorKind::Ipv4 | IndicatorKind::Ipv6 => {
+            let field = indicator.field.to_lowercase();
+            let source = ["source", "src", "client", "remote"]
+                .iter()
+                .any(|hint| field.contains(hint));
+            let attribute_type = if source { "ip-src" } else { "ip-dst" };
+            (attribute_type, "Network activity", true)
+        }
+        IndicatorKind::UserName => ("target-user", "Targeting data", false),
+        IndicatorKind::Arn => ("text", "Other", false),
+        IndicatorKind::Md5 => ("md5", "Payload delivery", true),
+        IndicatorKind::Sha1 => ("sha1", "Payload delivery", true),
+        IndicatorKind::Sha256 => ("sha256", "Payload delivery", true),
+        IndicatorKind::Imphash => ("imphash", "Payload delivery", true),
+    }
+}
+
+// ============================================================================
+// HTML
+// ============================================================================
+
+/// Standalone HTML report with an alert table.
+pub struct HtmlSink;
+
+impl ExportSink for HtmlSink {
+    fn id(&self) -> &'static str {
+        "html"
+    }
+    fn description(&self) -> &'static str {
+        "Standalone HTML report"
+    }
+    fn extension(&self) -> &'static str {
+        "html"
+    }
+    fn write_alerts(
+        &self,
+        alerts: &[AlertEvent],
+        options: &ExportOptions,
+        out: &mut dyn Write,
+    ) -> std::io::Result<()> {
+        let title = html_escape(options.title.as_deref().unwrap_or("Offline SIEM Alerts"));
+
+        writeln!(out, "<!DOCTYPE html>")?;
+        writeln!(
+            out,
+            "<html><head><meta charset=\"utf-8\"><title>{}</title>",
+            title
+        )?;
+        writeln!(
+            out,
+            "<style>body{{font-family:sans-serif}}table{{border-collapse:collapse}}td,th{{border:1px solid #ccc;padding:4px 8px;vertical-align:top}}pre{{margin:0;white-space:pre-wrap}}</style>"
+        )?;
+        writeln!(out, "</head><body>")?;
+        writeln!(out, "<h1>{}</h1>", title)?;
+        writeln!(
+            out,
+            "<p>{} alert(s), generated {}</p>",
+            alerts.len(),
+            chrono::Utc::now().to_rfc3339()
+        )?;
+        writeln!(out, "<table><thead><tr><th>Severity</th><th>Rule</th><th>Matches</th><th>Source</th><th>Detected</th><th>Guidance</th>{}</tr></thead><tbody>",
+            if options.include_evidence { "<th>Evidence</th>" } else { "" })?;
+
+        for alert in alerts {
+            write!(
+                out,
+                "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td>",
+                html_escape(&alert.severity),
+                html_escape(&alert.rule_title),
+                alert.match_count,
+                html_escape(alert.source_file.as_deref().unwrap_or("-")),
+                html_escape(&alert.timestamp)
+            )?;
+            write!(out, "<td>{}</td>", html_guidance(alert))?;
+            if options.include_evidence {
+                let evidence = serde_json::to_string_pretty(&alert.evidence).unwrap_or_default();
+                write!(out, "<td><pre>{}</pre></td>", html_escape(&evidence))?;
+            }
+            writeln!(out, "</tr>")?;
+        }
+
+        writeln!(out, "</tbody></table></body></html>")
+    }
+}
+
+/// Remediation, false positives and references of an alert as HTML.
+fn html_guidance(alert: &AlertEvent) -> String {
+    let mut parts = Vec::new();
+    if let Some(remediation) = &alert.remediation {
+        parts.push(format!(
+            "<p><b>Remediation:</b> {}</p>",
+            html_escape(remediation)
+        ));
+    }
+    if !alert.falsepositives.is_empty() {
+        let items: Vec<String> = alert
+            .falsepositives
+            .iter()
+            .map(|fp| format!("<li>{}</li>", html_escape(fp)))
+            .collect();
+        parts.push(format!(
+            "<b>False positives:</b><ul>{}</ul>",
+            items.concat()
+        ));
+    }
+    if !alert.references.is_empty() {
+        let items: Vec<String> = alert
+            .references
+            .iter()
+            .map(|r| {
+                if is_url(r) {
+                    format!("<li><a href=\"{0}\">{0}</a></li>", html_escape(r))
+                } else {
+                    format!("<li>{}</li>", html_escape(r))
+                }
+            })
+            .collect();
+        parts.push(format!("<b>References:</b><ul>{}</ul>", items.concat()));
+    }
+    if parts.is_empty() {
+        "-".to_string()
+    } else {
+        parts.concat()
+    }
+}
+
+/// Escape text for HTML content and attributes.
+fn html_escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+// ============================================================================
+// Syslog file
+// ============================================================================
+
+/// RFC 5424 syslog lines.
+pub struct SyslogSink;
+
+impl ExportSink for SyslogSink {
+    fn id(&self) -> &'static str {
+        "syslog"
+    }
+    fn description(&self) -> &'static str {
+        "RFC 5424 syslog lines (facility local0)"
+    }
+    fn extension(&self) -> &'static str {
+        "log"
+    }
+    fn write_alerts(
+        &self,
+        alerts: &[AlertEvent],
+        _options: &ExportOptions,
+        out: &mut dyn Write,
+    ) -> std::io::Result<()> {
+        for alert in alerts {
+            writeln!(out, "{}", format_syslog_line(alert))?;
+        }
+        Ok(())
+    }
+}
+
+/// Format an alert as an RFC 5424 syslog line (facility local0).
+pub fn format_syslog_line(alert: &AlertEvent) -> String {
+    let severity_code = match alert.severity.to_lowercase().as_str() {
+        "critical" => 2,
+        "high" => 3,
+        "medium" => 4,
+        "low" => 5,
+        _ => 6,
+    };
+    let priority = 16 * 8 + severity_code;
+
+    let hostname = std::env::var("COMPUTERNAME")
+        .or_else(|_| std::env::var("HOSTNAME"))
+        .unwrap_or_else(|_| "-".to_string());
+
+    format!(
+        "<{}>1 {} {} offline_siem - - - [{}] {} severity={} matches={} source={}",
+        priority,
+        alert.timestamp,
+        hostname,
+        alert.rule_id,
+        alert.rule_title,
+        alert.severity,
+        alert.match_count,
+        alert.source_file.as_deref().unwrap_or("-")
+    )
+}
+
+// ============================================================================
+// Elasticsearch bulk
+// ============================================================================
+
+/// NDJSON body for the Elasticsearch `_bulk` API.
+pub struct ElasticBulkSink;
+
+impl ExportSink for ElasticBulkSink {
+    fn id(&self) -> &'static str {
+        "elastic_bulk"
+    }
+    fn description(&self) -> &'static str {
+        "Elasticsearch _bulk NDJSON"
+    }
+    fn extension(&self) -> &'static str {
+        "ndjson"
+    }
+    fn write_alerts(
+        &self,
+        alerts: &[AlertEvent],
+        options: &ExportOptions,
+        out: &mut dyn Write,
+    ) -> std::io::Result<()> {
+        let index = options.index.as_deref().unwrap_or(DEFAULT_ELASTIC_INDEX);
+        let action = serde_json::json!({ "index": { "_index": index } });
+
+        for alert in alerts {
+            let mut doc = alert_json(alert, options.include_evidence);
+            if let Some(obj) = doc.as_object_mut() {
+                obj.insert(
+                    "@timestamp".to_string(),
+                    serde_json::Value::String(alert.timestamp.clone()),
+                );
+            }
+            writeln!(out, "{}", action)?;
+            writeln!(out, "{}", doc)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn alert() -> AlertEvent {
+        AlertEvent {
+            rule_id: "r1".to_string(),
+            rule_title: "Root login, \"console\"".to_string(),
+            alert_title: None,
+            alert_title_template: None,
+            severity: "critical".to_string(),
+            timestamp: "2025-12-16T11:05:00+00:00".to_string(),
+            match_count: 2,
+            evidence: vec![serde_json::json!({ "eventName": "<ConsoleLogin>" })],
+            source_file: None,
+            aggregation: None,
+            correlation: None,
+            meta: None,
+            absence: None,
+            rarity: None,
+            first_seen: None,
+            truncation: None,
+            hash_annotations: vec![],
+            geo_annotations: vec![],
+            ioc_matches: vec![],
+            references: vec![
+                "https://attack.mitre.org/techniques/T1078/".to_string(),
+                "INC-42".to_string(),
+            ],
+            falsepositives: vec!["Break-glass procedures".to_string()],
+            remediation: Some("Rotate root credentials".to_string()),
+        }
+    }
+
+    fn render(sink_id: &str, options: &ExportOptions) -> String {
+        let mut out = Vec::new();
+        find_sink(sink_id)
+            .unwrap()
+            .write_alerts(&[alert()], options, &mut out)
+            .unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_registry_ids_are_unique() {
+        let sinks = list_sinks();
+        let mut ids: Vec<&str> = sinks.iter().map(|s| s.id.as_str()).collect();
+        ids.sort();
+        ids.dedup();
+        assert_eq!(ids.len(), sinks.len());
+        assert!(find_sink("CSV").is_some());
+        assert!(find_sink("pdf").is_none());
+    }
+
+    #[test]
+    fn test_csv_quoting() {
+        let csv = render("csv", &ExportOptions::append_to("unused"));
+        assert_eq!(csv.lines().count(), 1);
+        assert!(csv.contains("\"Root login, \"\"console\"\"\""));
+    }
+
+    #[test]
+    fn test_selected_columns_and_flattened_evidence() {
+        let mut options = ExportOptions::append_to("unused");
+        options.append = false;
+        options.flatten_evidence = true;
+        let csv = render("csv", &options);
+        let lines: Vec<&str> = csv.lines().collect();
+        assert!(lines[0].ends_with(",references,evidence.eventName"));
+        assert!(lines[1].ends_with(",<ConsoleLogin>"));
+
+        options.columns = vec!["severity".to_string(), "evidence.eventName".to_string()];
+        assert_eq!(
+            render("csv", &options),
+            "severity,evidence.eventName\ncritical,<ConsoleLogin>\n"
+        );
+        assert_eq!(
+            render("jsonl", &options).trim(),
+            r#"{"severity":"critical","evidence.eventName":"<ConsoleLogin>"}"#
+        );
+    }
+
+    #[test]
+    fn test_html_escapes_evidence() {
+        let html = render("html", &ExportOptions::append_to("unused"));
+        assert!(html.contains("&lt;ConsoleLogin&gt;"));
+        assert!(!html.contains("<ConsoleLogin>"));
+    }
+
+    #[test]
+    fn test_html_rule_guidance() {
+        let html = render("html", &ExportOptions::append_to("unused"));
+        assert!(html.contains("Rotate root credentials"));
+        assert!(html.contains("<li>Break-glass procedures</li>"));
+        assert!(html.contains("<a href=\"https://attack.mitre.org/techniques/T1078/\">"));
+        assert!(html.contains("<li>INC-42</li>"));
+    }
+
+    #[test]
+    fn test_elastic_bulk_pairs() {
+        let mut options = ExportOptions::append_to("unused");
+        options.index = Some("alerts".to_string());
+        let body = render("elastic_bulk", &options);
+        let lines: Vec<&str> = body.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0], r#"{"index":{"_index":"alerts"}}"#);
+        assert!(lines[1].contains("\"@timestamp\""));
+    }
+
+    #[test]
+    fn test_stix_bundle() {
+        let bundle: serde_json::Value =
+            serde_json::from_str(&render("stix", &ExportOptions::append_to("unused"))).unwrap();
+        assert_eq!(bundle["type"], "bundle");
+        assert_eq!(bundle["objects"][1]["type"], "incident");
+        assert_eq!(bundle["objects"][1]["labels"][0], "critical");
+        let refs = &bundle["objects"][1]["external_references"];
+        assert_eq!(refs[0]["url"], "https://attack.mitre.org/techniques/T1078/");
+        assert_eq!(refs[1]["external_id"], "INC-42");
+        assert_eq!(
+            bundle["objects"][1]["x_offline_siem_remediation"],
+            "Rotate root credentials"
+        );
+        // No observables in the evidence, so no indicators
+        assert_eq!(bundle["objects"].as_array().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_stix_indicators_and_sightings() {
+        let mut first = alert();
+        first.evidence =
+            vec![serde_json::json!({ "sourceIPAddress": "203.0.113.7", "userName": "o'brien" })];
+        let mut second = alert();
+        second.evidence = vec![serde_json::json!({ "sourceIPAddress": "203.0.113.7" })];
+
+        let mut out = Vec::new();
+        StixSink
+            .write_alerts(
+                &[first, second],
+                &ExportOptions::append_to("unused"),
+                &mut out,
+            )
+            .unwrap();
+        let bundle: serde_json::Value = serde_json::from_slice(&out).unwrap();
+        let objects = bundle["objects"].as_array().unwrap();
+        let of_type = |t: &str| {
+            objects
+                .iter()
+                .filter(|o| o["type"] == t)
+                .collect::<Vec<_>>()
+        };
+
+        let indicators = of_type("indicator");
+        assert_eq!(indicators.len(), 2);
+        assert!(indicators
+            .iter()
+            .any(|i| i["pattern"] == "[ipv4-addr:value = '203.0.113.7']"));
+        assert!(indicators
+            .iter()
+            .any(|i| i["pattern"] == "[user-account:user_id = 'o\\'brien']"));
+        assert_eq!(of_type("observed-data").len(), 2);
+        assert_eq!(of_type("ipv4-addr").len(), 1);
+
+        let sightings = of_type("sighting");
+        assert_eq!(sightings.len(), 3);
+        assert_eq!(sightings[0]["first_seen"], "2025-12-16T11:05:00.000Z");
+        assert_eq!(sightings[0]["count"], 2);
+    }
+
+    #[test]
+    fn test_misp_event_for_scan() {
+        let mut critical = alert();
+        critical.evidence = vec![serde_json::json!({
+            "sourceIPAddress": "203.0.113.7",
+            "userIdentity": { "arn": "arn:aws:iam::123456789012:root" },
+            "sha256": "E3B0C44298FC1C149AFBF4C8996FB92427AE41E4649B934CA495991B7852B855"
+        })];
+        let scan = crate::scan_history::summarize_scan(vec![], &[critical.clone()]);
+
+        let mut out = Vec::new();
+        MispSink { scan: Some(&scan) }
+            .write_alerts(
+                &[critical.clone(), critical],
+                &ExportOptions::append_to("unused"),
+                &mut out,
+            )
+            .unwrap();
+        let event: serde_json::Value = serde_json::from_slice(&out).unwrap();
+        let event = &event["Event"];
+
+        assert_eq!(event["uuid"], scan.scan_id);
+        assert_eq!(event["date"], &scan.scanned_at[..10]);
+        assert_eq!(event["threat_level_id"], "1");
+        assert_eq!(event["Tag"][0]["name"], "offline-siem:rule=\"r1\"");
+        let attributes = event["Attribute"].as_array().unwrap();
+        let types: Vec<&str> = attributes
+            .iter()
+            .map(|a| a["type"].as_str().unwrap())
+            .collect();
+        assert_eq!(types, vec!["sha256", "ip-src", "text"]);
+        assert_eq!(attributes[0]["to_ids"], true);
+    }
+
+    #[test]
+    fn test_format_syslog_line() {
+        let line = format_syslog_line(&alert());
+        assert!(line.starts_with("<130>1 2025-12-16T11:05:00+00:00 "));
+        assert!(line.ends_with("matches=2 source=-"));
+    }
+}
@@ -0,0 +1,112 @@
+//! Severity-based routing of alerts to output sinks.
+//!
+//! After each scan, every alert is matched against the `alert_routes` in the
+//! application config and delivered to the sinks of each matching route.
+//! Sink failures never fail the scan itself: they are reported in the scan
+//! response's routing summary, or on `ROUTING_EVENT` for background scans.
+
+use tauri::Emitter;
+
+use crate::alert_export::{self, ExportOptions};
+use crate::config::{AlertRoute, AlertSink, AppConfig};
+use crate::models::{AlertEvent, RoutingSummary};
+
+/// Tauri event emitted for the `notification` sink.
+pub const NOTIFICATION_EVENT: &str = "alert://notification";
+
+/// Tauri event carrying the routing summary of a background scan (folder
+/// watch, scan jobs, REST API), which has no response to report it in.
+pub const ROUTING_EVENT: &str = "alert://routed";
+
+/// Get the sinks an alert of the given severity should be delivered to.
+pub fn sinks_for_severity<'a>(routes: &'a [AlertRoute], severity: &str) -> Vec<&'a AlertSink> {
+    routes
+        .iter()
+        .filter(|route| {
+            route.severities.is_empty()
+                || route
+                    .severities
+                    .iter()
+                    .any(|s| s.eq_ignore_ascii_case(severity))
+        })
+        .flat_map(|route| route.sinks.iter())
+        .collect()
+}
+
+/// Deliver alerts to the sinks configured for their severity.
+pub fn route_alerts(
+    app_handle: &tauri::AppHandle,
+    config: &AppConfig,
+    alerts: &[AlertEvent],
+) -> RoutingSummary {
+    let mut summary = RoutingSummary::default();
+
+    for alert in alerts {
+        for sink in sinks_for_severity(&config.alert_routes, &alert.severity) {
+            match deliver(app_handle, sink, alert) {
+                Ok(()) => summary.delivered += 1,
+                Err(e) => {
+                    eprintln!("Warning: Alert sink failed: {}", e);
+                    summary.errors.push(e);
+                }
+            }
+        }
+    }
+
+    summary
+}
+
+/// Deliver a single alert to a sink.
+/// File sinks append through the matching alert exporter.
+fn deliver(
+    app_handle: &tauri::AppHandle,
+    sink: &AlertSink,
+    alert: &AlertEvent,
+) -> Result<(), String> {
+    let (sink_id, path) = match sink {
+        AlertSink::Notification => {
+            return app_handle
+                .emit(NOTIFICATION_EVENT, alert)
+                .map_err(|e| format!("Cannot emit notification: {}", e));
+        }
+        AlertSink::SyslogFile { path } => ("syslog", path),
+        AlertSink::JsonlFile { path } => ("jsonl", path),
+    };
+
+    alert_export::export_alerts(
+        sink_id,
+        std::slice::from_ref(alert),
+        &ExportOptions::append_to(path),
+    )
+    .map(|_| ())
+    .map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sinks_for_severity() {
+        let routes = vec![
+            AlertRoute {
+                severities: vec!["critical".to_string()],
+                sinks: vec![
+                    AlertSink::Notification,
+                    AlertSink::SyslogFile {
+                        path: "siem.log".to_string(),
+                    },
+                ],
+            },
+            AlertRoute {
+                severities: vec![],
+                sinks: vec![AlertSink::JsonlFile {
+                    path: "all.jsonl".to_string(),
+                }],
+            },
+        ];
+
+        assert_eq!(sinks_for_severity(&routes, "Critical").len(), 3);
+        assert_eq!(sinks_for_severity(&routes, "high").len(), 1);
+    }
+}
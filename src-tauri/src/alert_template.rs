@@ -0,0 +1,70 @@
+//! Alert title templates.
+//!
+//! A rule's `output.alert_title` may reference fields of the matched event as
+//! `{{field.path}}`, e.g. "Suspicious AssumeRole by {{userIdentity.arn}}".
+//! Placeholders are resolved like condition fields (dot paths, arrays fanned
+//! out, normalization functions allowed); several values are joined with
+//! ", " and a missing field renders as `MISSING_VALUE`.
+
+use crate::db_engine;
+
+/// Rendered in place of a field the event doesn't have.
+pub const MISSING_VALUE: &str = "-";
+
+/// Render a title template against an event. An unclosed `{{` is kept as is.
+pub fn render(template: &str, event: Option<&serde_json::Value>) -> String {
+    let mut title = String::with_capacity(template.len());
+    let mut rest = template;
+
+    while let Some(start) = rest.find("{{") {
+        let Some(end) = rest[start + 2..].find("}}") else {
+            break;
+        };
+        title.push_str(&rest[..start]);
+
+        let field = rest[start + 2..start + 2 + end].trim();
+        let values = event
+            .map(|event| db_engine::get_field_values(event, field))
+            .unwrap_or_default();
+        if values.is_empty() {
+            title.push_str(MISSING_VALUE);
+        } else {
+            title.push_str(&values.join(", "));
+        }
+
+        rest = &rest[start + 2 + end + 2..];
+    }
+    title.push_str(rest);
+    title
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let event = serde_json::json!({
+            "userIdentity": { "arn": "arn:aws:iam::1:user/alice" },
+            "sourceIPAddress": "203.0.113.7",
+            "resources": [{ "ARN": "r1" }, { "ARN": "r2" }]
+        });
+
+        assert_eq!(
+            render(
+                "Suspicious AssumeRole by {{userIdentity.arn}} from {{ sourceIPAddress }}",
+                Some(&event)
+            ),
+            "Suspicious AssumeRole by arn:aws:iam::1:user/alice from 203.0.113.7"
+        );
+        assert_eq!(
+            render("{{resources.ARN}} by {{missing}}", Some(&event)),
+            "r1, r2 by -"
+        );
+        assert_eq!(
+            render("Open {{userIdentity.arn", Some(&event)),
+            "Open {{userIdentity.arn"
+        );
+        assert_eq!(render("By {{user}}", None), "By -");
+    }
+}
@@ -0,0 +1,74 @@
+//! Alert triage verdicts.
+//!
+//! Analysts mark alerts as true positives, benign or false positives. Verdicts
+//! are stored in `alert_triage.json` in the application's data directory,
+//! keyed by the alert's identity across scans (`scan_history::alert_key`), so
+//! the same alert raised again by a later scan keeps its verdict and rule
+//! statistics can report false-positive rates.
+
+use std::fs;
+use std::path::PathBuf;
+
+use crate::app_data;
+use crate::models::{AlertEvent, AlertTriage, SiemError, TriageStatus};
+use crate::scan_history;
+
+/// Get the path to the triage file.
+fn get_triage_path(app_handle: &tauri::AppHandle) -> Result<PathBuf, SiemError> {
+    app_data::app_data_file(app_handle, "alert_triage.json")
+}
+
+/// Load all triage verdicts from disk.
+pub fn load_triage(app_handle: &tauri::AppHandle) -> Result<Vec<AlertTriage>, SiemError> {
+    let path = get_triage_path(app_handle)?;
+
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let content = fs::read_to_string(&path)
+        .map_err(|e| SiemError::FileIO(format!("Cannot read alert triage: {}", e)))?;
+
+    serde_json::from_str(&content)
+        .map_err(|e| SiemError::Serialization(format!("Cannot parse alert triage: {}", e)))
+}
+
+/// Save all triage verdicts to disk.
+fn save_triage(app_handle: &tauri::AppHandle, triage: &[AlertTriage]) -> Result<(), SiemError> {
+    let path = get_triage_path(app_handle)?;
+
+    let content = serde_json::to_string_pretty(triage)
+        .map_err(|e| SiemError::Serialization(format!("Cannot serialize alert triage: {}", e)))?;
+
+    fs::write(&path, content)
+        .map_err(|e| SiemError::FileIO(format!("Cannot write alert triage: {}", e)))?;
+
+    Ok(())
+}
+
+/// Set the verdict of an alert, replacing any previous one.
+/// Without a status, the alert's verdict is cleared.
+pub fn set_triage(
+    app_handle: &tauri::AppHandle,
+    alert: &AlertEvent,
+    status: Option<TriageStatus>,
+    note: String,
+) -> Result<Option<AlertTriage>, SiemError> {
+    let key = scan_history::alert_key(alert);
+    let mut triage = load_triage(app_handle)?;
+    triage.retain(|verdict| verdict.key != key);
+
+    let verdict = status.map(|status| AlertTriage {
+        key,
+        rule_id: alert.rule_id.clone(),
+        status,
+        note,
+        updated_at: chrono::Utc::now().to_rfc3339(),
+    });
+    if let Some(verdict) = &verdict {
+        triage.push(verdict.clone());
+    }
+
+    save_triage(app_handle, &triage)?;
+    Ok(verdict)
+}
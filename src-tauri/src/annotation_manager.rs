@@ -0,0 +1,243 @@
+//! Event annotation manager for flagging notable events during review.
+//!
+//! Flags are stored in `annotations.json` in the application's data directory,
+//! keyed by log file path and record index. A snapshot of the flagged event is
+//! kept so flags remain meaningful (and exportable) even if the file changes.
+
+use duckdb::Connection;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::app_data;
+use crate::db_engine;
+use crate::models::{EventAnnotation, LogType, SiemError};
+
+/// Get the path to the annotations file.
+fn get_annotations_path(app_handle: &tauri::AppHandle) -> Result<PathBuf, SiemError> {
+    app_data::app_data_file(app_handle, "annotations.json")
+}
+
+/// Load all annotations from the file at `path`.
+fn load_annotations(path: &Path) -> Result<Vec<EventAnnotation>, SiemError> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let content = fs::read_to_string(path)
+        .map_err(|e| SiemError::FileIO(format!("Cannot read annotations: {}", e)))?;
+
+    serde_json::from_str(&content)
+        .map_err(|e| SiemError::Serialization(format!("Cannot parse annotations: {}", e)))
+}
+
+/// Save all annotations to the file at `path`.
+fn save_annotations(path: &Path, annotations: &[EventAnnotation]) -> Result<(), SiemError> {
+    let content = serde_json::to_string_pretty(annotations)
+        .map_err(|e| SiemError::Serialization(format!("Cannot serialize annotations: {}", e)))?;
+
+    fs::write(path, content)
+        .map_err(|e| SiemError::FileIO(format!("Cannot write annotations: {}", e)))?;
+
+    Ok(())
+}
+
+/// Flag the event at `record_index` of `log_path` in `annotations`, or update
+/// its flag if it already has one.
+fn upsert_annotation(
+    annotations: &mut Vec<EventAnnotation>,
+    log_path: &str,
+    record_index: usize,
+    label: String,
+    comment: String,
+    event: serde_json::Value,
+) -> EventAnnotation {
+    let now = chrono::Utc::now().to_rfc3339();
+
+    match annotations
+        .iter_mut()
+        .find(|a| a.file_path == log_path && a.record_index == record_index)
+    {
+        Some(existing) => {
+            existing.label = label;
+            existing.comment = comment;
+            existing.updated_at = now;
+            existing.event = event;
+            existing.clone()
+        }
+        None => {
+            let annotation = EventAnnotation {
+                id: uuid::Uuid::new_v4().to_string(),
+                file_path: log_path.to_string(),
+                record_index,
+                label,
+                comment,
+                created_at: now.clone(),
+                updated_at: now,
+                event,
+            };
+            annotations.push(annotation.clone());
+            annotation
+        }
+    }
+}
+
+/// Remove the annotation with ID `annotation_id` from `annotations`.
+fn remove_annotation(
+    annotations: &mut Vec<EventAnnotation>,
+    annotation_id: &str,
+) -> Result<(), SiemError> {
+    let before = annotations.len();
+    annotations.retain(|a| a.id != annotation_id);
+
+    if annotations.len() == before {
+        return Err(SiemError::Query(format!(
+            "Annotation not found: {}",
+            annotation_id
+        )));
+    }
+    Ok(())
+}
+
+/// Flag an event with a label and comment.
+/// Flagging an already-flagged event updates its label and comment.
+pub fn flag_event(
+    app_handle: &tauri::AppHandle,
+    conn: &Connection,
+    log_path: &str,
+    log_type: LogType,
+    record_index: usize,
+    label: String,
+    comment: String,
+) -> Result<EventAnnotation, SiemError> {
+    let events = db_engine::load_all_events(conn, log_path, log_type)?;
+
+    let event = events.get(record_index).cloned().ok_or_else(|| {
+        SiemError::Query(format!(
+            "Record index {} out of range ({} events)",
+            record_index,
+            events.len()
+        ))
+    })?;
+
+    let path = get_annotations_path(app_handle)?;
+    let mut annotations = load_annotations(&path)?;
+    let annotation = upsert_annotation(
+        &mut annotations,
+        log_path,
+        record_index,
+        label,
+        comment,
+        event,
+    );
+
+    save_annotations(&path, &annotations)?;
+    Ok(annotation)
+}
+
+/// Remove a flag by annotation ID.
+pub fn unflag_event(app_handle: &tauri::AppHandle, annotation_id: &str) -> Result<(), SiemError> {
+    let path = get_annotations_path(app_handle)?;
+    let mut annotations = load_annotations(&path)?;
+    remove_annotation(&mut annotations, annotation_id)?;
+    save_annotations(&path, &annotations)
+}
+
+/// List flagged events, optionally restricted to one log file.
+pub fn list_flagged_events(
+    app_handle: &tauri::AppHandle,
+    log_path: Option<&str>,
+) -> Result<Vec<EventAnnotation>, SiemError> {
+    let mut annotations = load_annotations(&get_annotations_path(app_handle)?)?;
+
+    if let Some(path) = log_path {
+        annotations.retain(|a| a.file_path == path);
+    }
+
+    // Sort by file then record position for a stable review order
+    annotations.sort_by(|a, b| {
+        a.file_path
+            .cmp(&b.file_path)
+            .then(a.record_index.cmp(&b.record_index))
+    });
+
+    Ok(annotations)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_flag_update_and_unflag() {
+        let mut annotations = Vec::new();
+        let event = serde_json::json!({"eventName": "ConsoleLogin"});
+
+        let flagged = upsert_annotation(
+            &mut annotations,
+            "trail.json",
+            3,
+            "suspicious".to_string(),
+            String::new(),
+            event.clone(),
+        );
+        assert_eq!(annotations.len(), 1);
+        assert_eq!(flagged.event, event);
+
+        // Flagging the same event again updates its flag
+        let updated = upsert_annotation(
+            &mut annotations,
+            "trail.json",
+            3,
+            "benign".to_string(),
+            "Admin login".to_string(),
+            event.clone(),
+        );
+        assert_eq!(annotations.len(), 1);
+        assert_eq!(updated.id, flagged.id);
+        assert_eq!(updated.created_at, flagged.created_at);
+        assert_eq!(annotations[0].label, "benign");
+        assert_eq!(annotations[0].comment, "Admin login");
+
+        let other = upsert_annotation(
+            &mut annotations,
+            "trail.json",
+            4,
+            "follow-up".to_string(),
+            String::new(),
+            event,
+        );
+        assert_eq!(annotations.len(), 2);
+
+        remove_annotation(&mut annotations, &flagged.id).unwrap();
+        assert_eq!(annotations.len(), 1);
+        assert_eq!(annotations[0].id, other.id);
+        assert!(remove_annotation(&mut annotations, &flagged.id).is_err());
+    }
+
+    #[test]
+    fn test_annotations_round_trip() {
+        let path = std::env::temp_dir().join("offline_siem_test_annotations.json");
+        let _ = fs::remove_file(&path);
+        assert!(load_annotations(&path).unwrap().is_empty());
+
+        let mut annotations = Vec::new();
+        upsert_annotation(
+            &mut annotations,
+            "security.xml",
+            0,
+            "suspicious".to_string(),
+            "Encoded PowerShell".to_string(),
+            serde_json::json!({"EventID": 4104}),
+        );
+        save_annotations(&path, &annotations).unwrap();
+
+        let loaded = load_annotations(&path).unwrap();
+        assert_eq!(loaded.len(), 1);
+        assert_eq!(loaded[0].id, annotations[0].id);
+        assert_eq!(loaded[0].file_path, "security.xml");
+        assert_eq!(loaded[0].comment, "Encoded PowerShell");
+        assert_eq!(loaded[0].event, serde_json::json!({"EventID": 4104}));
+
+        fs::remove_file(&path).unwrap();
+    }
+}
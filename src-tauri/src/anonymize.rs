@@ -0,0 +1,364 @@
+//! Pseudonymization of exported events, alerts and reports.
+//!
+//! Selected fields (usernames, IP addresses, account IDs, ...) are replaced by
+//! HMAC-SHA256 tokens keyed with an analyst-provided secret. The same value
+//! always maps to the same token under the same secret, so relationships in
+//! the data (one user across many events) survive, while the original values
+//! cannot be recovered without the secret.
+//!
+//! A field is selected when its dotted path or its last path segment matches
+//! an entry of `AnonymizeOptions::fields` (case-insensitive). Every string and
+//! number below a selected field is pseudonymized.
+
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::collections::BTreeMap;
+use std::net::IpAddr;
+
+use crate::alert_template;
+use crate::app_data;
+use crate::models::{AlertEvent, SiemError};
+
+/// Prefix of every pseudonymization token.
+const TOKEN_PREFIX: &str = "anon_";
+/// Number of hex characters of the HMAC kept in a token.
+const TOKEN_HEX_LEN: usize = 16;
+
+/// Pseudonymization settings of an export.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AnonymizeOptions {
+    /// HMAC key; share it only with people allowed to re-identify values
+    pub secret: String,
+    /// Field names or dotted paths to pseudonymize
+    #[serde(default = "default_fields")]
+    pub fields: Vec<String>,
+}
+
+/// Identity and network fields of CloudTrail and common flat JSON logs.
+pub fn default_fields() -> Vec<String> {
+    [
+        "userName",
+        "principalId",
+        "arn",
+        "accountId",
+        "recipientAccountId",
+        "accessKeyId",
+        "sourceIPAddress",
+        "user",
+        "username",
+        "user_name",
+        "account_id",
+        "src_ip",
+        "dst_ip",
+        "source_ip",
+        "destination_ip",
+        "client_ip",
+        "ip",
+        "email",
+        "hostname",
+    ]
+    .iter()
+    .map(|f| f.to_string())
+    .collect()
+}
+
+/// Replaces selected field values with consistent HMAC tokens.
+pub struct Pseudonymizer {
+    mac: Hmac<Sha256>,
+    fields: Vec<String>,
+}
+
+impl Pseudonymizer {
+    /// Create a pseudonymizer from export options.
+    pub fn new(options: &AnonymizeOptions) -> Result<Self, SiemError> {
+        if options.secret.is_empty() {
+            return Err(SiemError::Query(
+                "Anonymization secret cannot be empty".to_string(),
+            ));
+        }
+
+        let mac = Hmac::<Sha256>::new_from_slice(options.secret.as_bytes())
+            .map_err(|e| SiemError::Query(format!("Invalid anonymization secret: {}", e)))?;
+
+        Ok(Pseudonymizer {
+            mac,
+            fields: options.fields.iter().map(|f| f.to_lowercase()).collect(),
+        })
+    }
+
+    /// Token for a value.
+    pub fn token(&self, value: &str) -> String {
+        let mut mac = self.mac.clone();
+        mac.update(value.as_bytes());
+        let digest = mac.finalize().into_bytes();
+
+        let hex = app_data::hex(&digest);
+        format!("{}{}", TOKEN_PREFIX, &hex[..TOKEN_HEX_LEN])
+    }
+
+    /// Whether a dotted field path is selected.
+    fn is_selected(&self, path: &str) -> bool {
+        let path = path.to_lowercase();
+        let last = path.rsplit('.').next().unwrap_or(&path);
+        self.fields.iter().any(|f| *f == path || f == last)
+    }
+
+    /// Pseudonymize the selected fields of an event in place.
+    pub fn apply_event(&self, event: &mut serde_json::Value) {
+        self.walk(event, "", false);
+    }
+
+    fn walk(&self, value: &mut serde_json::Value, path: &str, selected: bool) {
+        match value {
+            serde_json::Value::Object(map) => {
+                for (key, child) in map.iter_mut() {
+                    let child_path = if path.is_empty() {
+                        key.clone()
+                    } else {
+                        format!("{}.{}", path, key)
+                    };
+                    let child_selected = selected || self.is_selected(&child_path);
+                    self.walk(child, &child_path, child_selected);
+                }
+            }
+            serde_json::Value::Array(items) => {
+                for item in items {
+                    self.walk(item, path, selected);
+                }
+            }
+            serde_json::Value::String(s) if selected => {
+                *s = self.token(s);
+            }
+            serde_json::Value::Number(n) if selected => {
+                *value = serde_json::Value::String(self.token(&n.to_string()));
+            }
+            _ => {}
+        }
+    }
+
+    /// Pseudonymize a value found in an evidence field: as in the evidence when
+    /// the field is selected, and always when it is an IP address.
+    fn apply_field_value(&self, field: &str, value: &str) -> String {
+        if self.is_selected(field) || value.parse::<IpAddr>().is_ok() {
+            self.token(value)
+        } else {
+            value.to_string()
+        }
+    }
+
+    /// Pseudonymize selected values of a field -> value map (group keys, entities).
+    fn apply_map(&self, map: &mut BTreeMap<String, String>) {
+        for (field, value) in map.iter_mut() {
+            if self.is_selected(field) {
+                *value = self.token(value);
+            }
+        }
+    }
+
+    /// Pseudonymize an alert: its evidence, its title rendered from the
+    /// evidence and the entity values it was grouped by.
+    pub fn apply_alert(&self, alert: &mut AlertEvent) {
+        for event in &mut alert.evidence {
+            self.apply_event(event);
+        }
+        // The rendered title may quote selected fields; without its template
+        // it can't be rendered again, so it is dropped
+        alert.alert_title = alert
+            .alert_title_template
+            .as_ref()
+            .map(|template| alert_template::render(template, alert.evidence.first()));
+        if let Some(aggregation) = &mut alert.aggregation {
+            self.apply_map(&mut aggregation.group);
+        }
+        if let Some(correlation) = &mut alert.correlation {
+            self.apply_map(&mut correlation.key);
+        }
+        if let Some(meta) = &mut alert.meta {
+            self.apply_map(&mut meta.entity);
+        }
+        if let Some(absence) = &mut alert.absence {
+            self.apply_map(&mut absence.entity);
+        }
+        if let Some(rarity) = &mut alert.rarity {
+            if self.is_selected(&rarity.field) {
+                rarity.value = self.token(&rarity.value);
+            }
+        }
+        if let Some(first_seen) = &mut alert.first_seen {
+            if self.is_selected(&first_seen.field) {
+                first_seen.value = self.token(&first_seen.value);
+            }
+        }
+        for geo in &mut alert.geo_annotations {
+            geo.ip = self.apply_field_value(&geo.field, &geo.ip);
+        }
+        // A matched indicator (IP, domain, hash...) always identifies what was
+        // seen, whichever field held it
+        for ioc in &mut alert.ioc_matches {
+            ioc.value = self.token(&ioc.value);
+        }
+    }
+
+    /// Pseudonymized copies of alerts.
+    pub fn apply_alerts(&self, alerts: &[AlertEvent]) -> Vec<AlertEvent> {
+        alerts
+            .iter()
+            .cloned()
+            .map(|mut alert| {
+                self.apply_alert(&mut alert);
+                alert
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pseudonymizer(secret: &str) -> Pseudonymizer {
+        Pseudonymizer::new(&AnonymizeOptions {
+            secret: secret.to_string(),
+            fields: default_fields(),
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_tokens_are_consistent_per_secret() {
+        let a = pseudonymizer("s1");
+        let b = pseudonymizer("s2");
+
+        assert_eq!(a.token("alice"), a.token("alice"));
+        assert_ne!(a.token("alice"), a.token("bob"));
+        assert_ne!(a.token("alice"), b.token("alice"));
+        assert!(a.token("alice").starts_with(TOKEN_PREFIX));
+        assert_eq!(a.token("alice").len(), TOKEN_PREFIX.len() + TOKEN_HEX_LEN);
+    }
+
+    #[test]
+    fn test_apply_event_selected_fields_only() {
+        let p = pseudonymizer("secret");
+        let mut event = serde_json::json!({
+            "eventName": "ConsoleLogin",
+            "sourceIPAddress": "203.0.113.7",
+            "recipientAccountId": 123456789012u64,
+            "userIdentity": { "type": "IAMUser", "userName": "alice" },
+            "resources": [{ "accountId": "123456789012" }]
+        });
+        p.apply_event(&mut event);
+
+        assert_eq!(event["eventName"], "ConsoleLogin");
+        assert_eq!(event["userIdentity"]["type"], "IAMUser");
+        assert_eq!(event["userIdentity"]["userName"], p.token("alice"));
+        assert_eq!(event["sourceIPAddress"], p.token("203.0.113.7"));
+        // Numeric and string account IDs map to the same token
+        assert_eq!(event["recipientAccountId"], p.token("123456789012"));
+        assert_eq!(event["resources"][0]["accountId"], p.token("123456789012"));
+    }
+
+    #[test]
+    fn test_selected_object_is_fully_pseudonymized() {
+        let p = Pseudonymizer::new(&AnonymizeOptions {
+            secret: "secret".to_string(),
+            fields: vec!["userIdentity".to_string()],
+        })
+        .unwrap();
+        let mut event = serde_json::json!({
+            "eventName": "ConsoleLogin",
+            "userIdentity": { "type": "IAMUser", "sessionContext": { "mfa": true } }
+        });
+        p.apply_event(&mut event);
+
+        assert_eq!(event["eventName"], "ConsoleLogin");
+        assert_eq!(event["userIdentity"]["type"], p.token("IAMUser"));
+        assert_eq!(event["userIdentity"]["sessionContext"]["mfa"], true);
+    }
+
+    #[test]
+    fn test_apply_alert_renders_title_from_pseudonymized_evidence() {
+        let p = pseudonymizer("secret");
+        let mut alert: AlertEvent = serde_json::from_value(serde_json::json!({
+            "rule_id": "r1",
+            "rule_title": "Suspicious AssumeRole",
+            "alert_title": "AssumeRole by arn:aws:iam::1:user/alice",
+            "alert_title_template": "AssumeRole by {{userIdentity.arn}}",
+            "severity": "high",
+            "timestamp": "2024-03-01T12:00:00Z",
+            "match_count": 1,
+            "evidence": [{ "userIdentity": { "arn": "arn:aws:iam::1:user/alice" } }],
+        }))
+        .unwrap();
+        p.apply_alert(&mut alert);
+        assert_eq!(
+            alert.alert_title,
+            Some(format!(
+                "AssumeRole by {}",
+                p.token("arn:aws:iam::1:user/alice")
+            ))
+        );
+
+        alert.alert_title = Some("AssumeRole by alice".to_string());
+        alert.alert_title_template = None;
+        p.apply_alert(&mut alert);
+        assert_eq!(alert.alert_title, None);
+    }
+
+    #[test]
+    fn test_apply_alert_leaves_no_original_ip() {
+        let p = pseudonymizer("secret");
+        let mut alert: AlertEvent = serde_json::from_value(serde_json::json!({
+            "rule_id": "r1",
+            "rule_title": "Console login",
+            "severity": "high",
+            "timestamp": "2024-03-01T12:00:00Z",
+            "match_count": 1,
+            "evidence": [{ "sourceIPAddress": "203.0.113.7" }],
+            "geo_annotations": [
+                { "field": "sourceIPAddress", "ip": "203.0.113.7", "country": "NL" },
+                { "field": "relay", "ip": "198.51.100.9", "country": "US" },
+            ],
+        }))
+        .unwrap();
+        p.apply_alert(&mut alert);
+
+        let exported = serde_json::to_string(&alert).unwrap();
+        assert!(!exported.contains("203.0.113.7"));
+        assert!(!exported.contains("198.51.100.9"));
+        assert_eq!(alert.geo_annotations[0].ip, p.token("203.0.113.7"));
+        assert_eq!(alert.geo_annotations[0].country.as_deref(), Some("NL"));
+    }
+
+    #[test]
+    fn test_apply_alert_pseudonymizes_ioc_matches() {
+        let p = pseudonymizer("secret");
+        let mut alert: AlertEvent = serde_json::from_value(serde_json::json!({
+            "rule_id": "r1",
+            "rule_title": "Known bad host",
+            "severity": "high",
+            "timestamp": "2024-03-01T12:00:00Z",
+            "match_count": 1,
+            "evidence": [{ "sourceIPAddress": "203.0.113.7" }],
+            "ioc_matches": [
+                { "field": "sourceIPAddress", "value": "203.0.113.7", "list": "feed" },
+                { "field": "query", "value": "evil.example", "list": "feed" },
+            ],
+        }))
+        .unwrap();
+        p.apply_alert(&mut alert);
+
+        assert_eq!(alert.ioc_matches[0].value, p.token("203.0.113.7"));
+        assert_eq!(alert.ioc_matches[1].value, p.token("evil.example"));
+        assert_eq!(alert.ioc_matches[1].list, "feed");
+    }
+
+    #[test]
+    fn test_empty_secret_rejected() {
+        let options = AnonymizeOptions {
+            secret: String::new(),
+            fields: default_fields(),
+        };
+        assert!(Pseudonymizer::new(&options).is_err());
+    }
+}
@@ -0,0 +1,277 @@
+//! App data backup and restore, to move a configured workstation to another
+//! machine.
+//!
+//! Unlike a workspace snapshot (see `workspace`), a backup only holds what
+//! makes up the analyst's setup, not the investigation data (logs, cases,
+//! annotations...). It is a ZIP archive with the snapshot layout:
+//! - `manifest.json`: backup metadata
+//! - `rules/`: every rule YAML file (from the effective rules directory)
+//! - `data/`: the `BACKUP_ITEMS` of the application data directory: config,
+//!   saved queries, alert store (triage verdicts, suppressions, scan history),
+//!   lookup lists, hash sets, threat intel and parser definitions
+//!
+//! Restoring replaces each item the backup holds (the rules included) and
+//! leaves everything else alone, the audit log in particular. Like a snapshot
+//! restore, it is staged and swapped in at once, so it either fully succeeds
+//! or changes nothing. Settings that only make sense on the machine the
+//! backup came from are not restored; the current ones are kept: configured
+//! paths (rules directory, recent log files, GeoIP databases, alert route
+//! output files...) and the REST API settings, token included.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use crate::app_data;
+use crate::config::{self, AppConfig};
+use crate::models::SiemError;
+use crate::workspace;
+
+/// Backup format version, bumped on incompatible layout changes.
+const BACKUP_VERSION: u32 = 1;
+
+const MANIFEST_ENTRY: &str = "manifest.json";
+const RULES_PREFIX: &str = "rules/";
+const DATA_PREFIX: &str = "data/";
+
+/// Files and directories of the app data dir that are backed up.
+const BACKUP_ITEMS: &[&str] = &[
+    config::CONFIG_FILE,
+    "queries",
+    "alert_triage.json",
+    "suppressions.json",
+    "scan_history.json",
+    "lookup_lists",
+    "hash_sets",
+    "intel",
+    "text_parsers.json",
+    "plugins",
+];
+
+/// Metadata stored in a backup archive.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct BackupManifest {
+    /// Backup format version
+    pub version: u32,
+    /// Creation timestamp (ISO 8601)
+    pub created_at: String,
+    /// Backed-up items of the app data dir, among `BACKUP_ITEMS`
+    pub items: Vec<String>,
+    /// Number of rule files
+    pub rule_count: usize,
+    /// Number of data files
+    pub data_file_count: usize,
+}
+
+/// Result of restoring a backup.
+#[derive(Debug, Serialize, Clone)]
+pub struct BackupRestoreSummary {
+    /// Manifest of the restored backup
+    pub manifest: BackupManifest,
+    /// Number of files written
+    pub files_restored: usize,
+}
+
+/// The backup item a path relative to the app data dir belongs to, if any.
+fn backup_item(relative: &Path) -> Option<&'static str> {
+    let first = relative.components().next()?.as_os_str();
+    BACKUP_ITEMS.iter().copied().find(|item| first == *item)
+}
+
+/// `restored` with the settings of `local` that only make sense on this
+/// machine: paths, and the REST API settings.
+fn keep_local_settings(restored: AppConfig, local: &AppConfig) -> AppConfig {
+    AppConfig {
+        rules_directory: local.rules_directory.clone(),
+        default_logs_directory: local.default_logs_directory.clone(),
+        recent_log_files: local.recent_log_files.clone(),
+        geoip_country_db: local.geoip_country_db.clone(),
+        geoip_asn_db: local.geoip_asn_db.clone(),
+        watch_directory: local.watch_directory.clone(),
+        alert_routes: local.alert_routes.clone(),
+        api_enabled: local.api_enabled,
+        api_port: local.api_port,
+        api_token: local.api_token.clone(),
+        ..restored
+    }
+}
+
+/// Back up rules, configuration, saved queries, the alert store and lookup
+/// lists into a ZIP archive.
+pub fn backup_app_data(
+    app_handle: &tauri::AppHandle,
+    dest_path: &str,
+) -> Result<BackupManifest, SiemError> {
+    use zip::write::FileOptions;
+
+    let data_dir = app_data::app_data_dir(app_handle)?;
+    let rules_dir = config::get_rules_directory(app_handle)?;
+
+    let rule_files = workspace::collect_rule_files(&rules_dir)?;
+
+    let mut data_files = Vec::new();
+    workspace::collect_files(&data_dir, &data_dir, &mut data_files)?;
+    data_files.retain(|f| backup_item(f).is_some());
+    data_files.sort();
+    let items: BTreeSet<&str> = data_files.iter().filter_map(|f| backup_item(f)).collect();
+
+    let manifest = BackupManifest {
+        version: BACKUP_VERSION,
+        created_at: chrono::Utc::now().to_rfc3339(),
+        items: items.into_iter().map(String::from).collect(),
+        rule_count: rule_files.len(),
+        data_file_count: data_files.len(),
+    };
+
+    let file = fs::File::create(dest_path)
+        .map_err(|e| SiemError::FileIO(format!("Cannot create backup file: {}", e)))?;
+    let mut zip = zip::ZipWriter::new(file);
+    let options = FileOptions::default()
+        .compression_method(zip::CompressionMethod::Deflated)
+        .unix_permissions(0o644);
+
+    let manifest_json = serde_json::to_string_pretty(&manifest)
+        .map_err(|e| SiemError::Serialization(format!("Cannot serialize manifest: {}", e)))?;
+    zip.start_file(MANIFEST_ENTRY, options)
+        .map_err(|e| SiemError::FileIO(format!("Cannot add file to ZIP: {}", e)))?;
+    zip.write_all(manifest_json.as_bytes())
+        .map_err(|e| SiemError::FileIO(format!("Cannot write to ZIP: {}", e)))?;
+
+    let entries = rule_files
+        .iter()
+        .map(|f| (workspace::entry_name(RULES_PREFIX, f), rules_dir.join(f)))
+        .chain(
+            data_files
+                .iter()
+                .map(|f| (workspace::entry_name(DATA_PREFIX, f), data_dir.join(f))),
+        );
+
+    for (name, path) in entries {
+        let content = fs::read(&path)
+            .map_err(|e| SiemError::FileIO(format!("Cannot read {:?}: {}", path, e)))?;
+        zip.start_file(name, options)
+            .map_err(|e| SiemError::FileIO(format!("Cannot add file to ZIP: {}", e)))?;
+        zip.write_all(&content)
+            .map_err(|e| SiemError::FileIO(format!("Cannot write to ZIP: {}", e)))?;
+    }
+
+    zip.finish()
+        .map_err(|e| SiemError::FileIO(format!("Cannot finalize ZIP: {}", e)))?;
+
+    Ok(manifest)
+}
+
+/// Restore a backup, replacing the rules and the backed-up items of the app
+/// data dir. The new contents are staged and swapped in at the end (as for
+/// snapshots), so a restore that fails leaves the current setup untouched.
+pub fn restore_app_data(
+    app_handle: &tauri::AppHandle,
+    src_path: &str,
+) -> Result<BackupRestoreSummary, SiemError> {
+    let archive = workspace::read_archive::<BackupManifest>(
+        src_path,
+        "app data backup",
+        |manifest| manifest.version,
+        BACKUP_VERSION,
+    )?;
+
+    let data_dir = app_data::app_data_dir(app_handle)?;
+    let local_config = config::load_config(app_handle)?;
+    let rules_dir = config::get_rules_directory(app_handle)?;
+
+    // Replace each backed-up item as a whole, so e.g. a list index and its
+    // files stay consistent
+    let mut data = workspace::StagedRestore::new(&data_dir)?;
+    for (relative, content) in &archive.data {
+        let Some(item) = backup_item(relative) else {
+            continue;
+        };
+        if relative == Path::new(config::CONFIG_FILE) {
+            let restored: AppConfig = serde_json::from_slice(content)
+                .map_err(|e| SiemError::Serialization(format!("Cannot parse config: {}", e)))?;
+            let config =
+                serde_json::to_string_pretty(&keep_local_settings(restored, &local_config))
+                    .map_err(|e| {
+                        SiemError::Serialization(format!("Cannot serialize config: {}", e))
+                    })?;
+            data.write(relative, config.as_bytes())?;
+        } else {
+            data.write(relative, content)?;
+        }
+        data.replace(PathBuf::from(item));
+    }
+
+    let mut rules = workspace::StagedRestore::new(&rules_dir)?;
+    for relative in workspace::collect_rule_files(&rules_dir)? {
+        rules.replace(relative);
+    }
+    for (relative, content) in &archive.rules {
+        rules.write(relative, content)?;
+        if let Some(entry) = workspace::top_entry(relative) {
+            rules.replace(entry);
+        }
+    }
+    let files_restored = data.files_staged() + rules.files_staged();
+
+    workspace::swap_all(vec![data, rules])?;
+
+    Ok(BackupRestoreSummary {
+        manifest: archive.manifest,
+        files_restored,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{AlertRoute, AlertSink};
+
+    #[test]
+    fn test_backup_items_and_local_paths() {
+        assert_eq!(backup_item(Path::new("config.json")), Some("config.json"));
+        assert_eq!(
+            backup_item(Path::new("lookup_lists/admins.txt")),
+            Some("lookup_lists")
+        );
+        assert_eq!(
+            backup_item(Path::new("queries/index.json")),
+            Some("queries")
+        );
+        assert_eq!(backup_item(Path::new("logs/trail.json")), None);
+        assert_eq!(backup_item(Path::new("cases.json")), None);
+        assert_eq!(backup_item(Path::new("ingest_cache.duckdb")), None);
+
+        let restored = AppConfig {
+            rules_directory: Some("D:\\siem\\rules".to_string()),
+            recent_log_files: vec!["D:\\logs\\trail.json".to_string()],
+            max_alerts_per_scan: 42,
+            alert_routes: vec![AlertRoute {
+                severities: Vec::new(),
+                sinks: vec![AlertSink::JsonlFile {
+                    path: "D:\\siem\\alerts.jsonl".to_string(),
+                }],
+            }],
+            api_enabled: true,
+            api_token: Some("other-machine".to_string()),
+            ..AppConfig::default()
+        };
+        let local = AppConfig {
+            rules_directory: Some("/home/analyst/rules".to_string()),
+            api_token: Some("this-machine".to_string()),
+            ..AppConfig::default()
+        };
+
+        let config = keep_local_settings(restored, &local);
+        assert_eq!(
+            config.rules_directory.as_deref(),
+            Some("/home/analyst/rules")
+        );
+        assert!(config.recent_log_files.is_empty());
+        assert!(config.alert_routes.is_empty());
+        assert!(!config.api_enabled);
+        assert_eq!(config.api_token.as_deref(), Some("this-machine"));
+        assert_eq!(config.max_alerts_per_scan, 42);
+    }
+}
@@ -0,0 +1,53 @@
+//! Locations in the application's data directory.
+//!
+//! Every store (config, cases, hash sets, ...) lives in the app data dir;
+//! these helpers resolve paths in it, creating directories as needed, so the
+//! stores don't each repeat the lookup and its error handling. Also holds the
+//! hex encoding shared by the modules that name or fingerprint their files
+//! with digests.
+
+use std::fs;
+use std::path::PathBuf;
+use tauri::Manager;
+
+use crate::models::SiemError;
+
+/// The application's data directory, created if needed.
+pub fn app_data_dir(app_handle: &tauri::AppHandle) -> Result<PathBuf, SiemError> {
+    let dir = app_handle
+        .path()
+        .app_data_dir()
+        .map_err(|e| SiemError::FileIO(format!("Cannot get app data dir: {}", e)))?;
+    fs::create_dir_all(&dir)
+        .map_err(|e| SiemError::FileIO(format!("Cannot create app data dir: {}", e)))?;
+    Ok(dir)
+}
+
+/// Path of a file in the app data dir.
+pub fn app_data_file(app_handle: &tauri::AppHandle, name: &str) -> Result<PathBuf, SiemError> {
+    Ok(app_data_dir(app_handle)?.join(name))
+}
+
+/// A directory in the app data dir, created if needed.
+pub fn app_data_subdir(app_handle: &tauri::AppHandle, name: &str) -> Result<PathBuf, SiemError> {
+    let dir = app_data_file(app_handle, name)?;
+    fs::create_dir_all(&dir)
+        .map_err(|e| SiemError::FileIO(format!("Cannot create {} dir: {}", name, e)))?;
+    Ok(dir)
+}
+
+/// Lowercase hex encoding of bytes.
+pub fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hex() {
+        assert_eq!(hex(&[0x00, 0x0f, 0xa5, 0xff]), "000fa5ff");
+        assert_eq!(hex(&[]), "");
+    }
+}
@@ -0,0 +1,237 @@
+//! MITRE ATT&CK technique tagging and coverage.
+//!
+//! Rules carry ATT&CK techniques in `attack_techniques`, filled from Sigma
+//! style tags (`attack.t1078`, `attack.t1078.004`) when rules are saved or
+//! loaded. Tactics come from tags too (`attack.persistence`); the coverage
+//! matrix places each technique under the tactics of the rules covering it,
+//! as no ATT&CK dataset ships with the app.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::models::{
+    AttackCoverage, RuleYaml, ScanHistoryEntry, TacticCoverage, TechniqueCoverage,
+};
+
+/// Enterprise ATT&CK tactics in matrix order, as used in tags.
+pub const TACTICS: [&str; 14] = [
+    "reconnaissance",
+    "resource_development",
+    "initial_access",
+    "execution",
+    "persistence",
+    "privilege_escalation",
+    "defense_evasion",
+    "credential_access",
+    "discovery",
+    "lateral_movement",
+    "collection",
+    "command_and_control",
+    "exfiltration",
+    "impact",
+];
+
+/// Column of techniques whose rules name no tactic.
+pub const UNKNOWN_TACTIC: &str = "unknown";
+
+/// Normalize a technique ID ("t1078.004" -> "T1078.004"), if valid.
+fn normalize_technique(id: &str) -> Option<String> {
+    let id = id.trim().to_uppercase();
+    let digits = id.strip_prefix('T')?;
+    let (base, sub) = match digits.split_once('.') {
+        Some((base, sub)) => (base, Some(sub)),
+        None => (digits, None),
+    };
+    let numeric = |s: &str, len: usize| s.len() == len && s.chars().all(|c| c.is_ascii_digit());
+    (numeric(base, 4) && sub.is_none_or(|sub| numeric(sub, 3))).then_some(id)
+}
+
+/// Techniques named by `attack.tNNNN[.NNN]` tags.
+pub fn techniques_from_tags(tags: &[String]) -> Vec<String> {
+    let mut techniques: Vec<String> = tags
+        .iter()
+        .filter_map(|tag| {
+            tag.trim()
+                .to_lowercase()
+                .strip_prefix("attack.")
+                .map(str::to_string)
+        })
+        .filter_map(|id| normalize_technique(&id))
+        .collect();
+    techniques.sort();
+    techniques.dedup();
+    techniques
+}
+
+/// Tactics named by `attack.<tactic>` tags (hyphens or underscores).
+fn tactics_from_tags(tags: &[String]) -> Vec<&'static str> {
+    TACTICS
+        .iter()
+        .copied()
+        .filter(|tactic| {
+            tags.iter().any(|tag| {
+                tag.trim()
+                    .to_lowercase()
+                    .replace('-', "_")
+                    .strip_prefix("attack.")
+                    .is_some_and(|name| name == *tactic)
+            })
+        })
+        .collect()
+}
+
+/// Set a rule's techniques to the valid ones it lists plus those in its tags.
+pub fn tag_rule(rule: &mut RuleYaml) {
+    let mut techniques: Vec<String> = rule
+        .attack_techniques
+        .iter()
+        .filter_map(|id| normalize_technique(id))
+        .chain(techniques_from_tags(&rule.tags))
+        .collect();
+    techniques.sort();
+    techniques.dedup();
+    rule.attack_techniques = techniques;
+}
+
+/// Tactic/technique matrix of the techniques covered by active rules, and of
+/// those whose rules fired in `last_scan`.
+pub fn compute_coverage(
+    rules: &[RuleYaml],
+    last_scan: Option<&ScanHistoryEntry>,
+) -> AttackCoverage {
+    let fired = |rule: &RuleYaml| last_scan.is_some_and(|scan| scan.by_rule.contains_key(&rule.id));
+
+    // tactic -> technique -> (covering rules, fired rules)
+    let mut matrix: BTreeMap<&str, BTreeMap<&str, (BTreeSet<&str>, BTreeSet<&str>)>> =
+        BTreeMap::new();
+    for rule in rules {
+        let active = rule.status == "active";
+        let rule_fired = fired(rule);
+        if !active && !rule_fired {
+            continue;
+        }
+
+        let mut tactics = tactics_from_tags(&rule.tags);
+        if tactics.is_empty() {
+            tactics.push(UNKNOWN_TACTIC);
+        }
+        for tactic in tactics {
+            for technique in &rule.attack_techniques {
+                let (covering, fired_by) = matrix
+                    .entry(tactic)
+                    .or_default()
+                    .entry(technique.as_str())
+                    .or_default();
+                if active {
+                    covering.insert(rule.id.as_str());
+                }
+                if rule_fired {
+                    fired_by.insert(rule.id.as_str());
+                }
+            }
+        }
+    }
+
+    let mut covered = BTreeSet::new();
+    let mut fired_techniques = BTreeSet::new();
+    let tactics = TACTICS
+        .iter()
+        .chain(std::iter::once(&UNKNOWN_TACTIC))
+        .filter_map(|tactic| {
+            let techniques = matrix.remove(tactic)?;
+            Some(TacticCoverage {
+                tactic: tactic.to_string(),
+                techniques: techniques
+                    .into_iter()
+                    .map(|(technique, (covering, fired_by))| {
+                        if !covering.is_empty() {
+                            covered.insert(technique);
+                        }
+                        if !fired_by.is_empty() {
+                            fired_techniques.insert(technique);
+                        }
+                        TechniqueCoverage {
+                            technique: technique.to_string(),
+                            rule_ids: covering.into_iter().map(str::to_string).collect(),
+                            fired_rule_ids: fired_by.into_iter().map(str::to_string).collect(),
+                        }
+                    })
+                    .collect(),
+            })
+        })
+        .collect();
+
+    AttackCoverage {
+        tactics,
+        covered_techniques: covered.len(),
+        fired_techniques: fired_techniques.len(),
+        last_scan_id: last_scan.map(|scan| scan.scan_id.clone()),
+        last_scan_at: last_scan.map(|scan| scan.scanned_at.clone()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(id: &str, status: &str, tags: &[&str]) -> RuleYaml {
+        let yaml = format!(
+            "id: {}\ntitle: {}\ndescription: ''\nauthor: ''\nstatus: {}\ndate: ''\ntags: [{}]\ndetection:\n  severity: high\n  condition: \"a = 'b'\"\n",
+            id,
+            id,
+            status,
+            tags.join(", ")
+        );
+        let mut rule: RuleYaml = serde_yaml::from_str(&yaml).unwrap();
+        tag_rule(&mut rule);
+        rule
+    }
+
+    #[test]
+    fn test_techniques_from_tags() {
+        let tags: Vec<String> = [
+            "attack.t1078.004",
+            "ATTACK.T1098",
+            "attack.t10",
+            "attack.persistence",
+            "cloud",
+        ]
+        .iter()
+        .map(|t| t.to_string())
+        .collect();
+        assert_eq!(techniques_from_tags(&tags), vec!["T1078.004", "T1098"]);
+        assert_eq!(tactics_from_tags(&tags), vec!["persistence"]);
+    }
+
+    #[test]
+    fn test_coverage_matrix() {
+        let rules = vec![
+            rule("r1", "active", &["attack.initial-access", "attack.t1078"]),
+            rule("r2", "active", &["attack.t1098"]),
+            rule("r3", "disabled", &["attack.persistence", "attack.t1136"]),
+            rule("r4", "disabled", &["attack.t1110"]),
+        ];
+        let mut scan = crate::scan_history::summarize_scan(vec![], &[]);
+        scan.by_rule.insert("r1".to_string(), 1);
+        scan.by_rule.insert("r3".to_string(), 2);
+
+        let coverage = compute_coverage(&rules, Some(&scan));
+        let tactics: Vec<&str> = coverage.tactics.iter().map(|t| t.tactic.as_str()).collect();
+        assert_eq!(
+            tactics,
+            vec!["initial_access", "persistence", UNKNOWN_TACTIC]
+        );
+
+        let initial_access = &coverage.tactics[0].techniques[0];
+        assert_eq!(initial_access.technique, "T1078");
+        assert_eq!(initial_access.rule_ids, vec!["r1"]);
+        assert_eq!(initial_access.fired_rule_ids, vec!["r1"]);
+
+        // A disabled rule that fired shows up as fired but not covering
+        let persistence = &coverage.tactics[1].techniques[0];
+        assert!(persistence.rule_ids.is_empty());
+        assert_eq!(persistence.fired_rule_ids, vec!["r3"]);
+
+        assert_eq!(coverage.covered_techniques, 2);
+        assert_eq!(coverage.fired_techniques, 2);
+    }
+}
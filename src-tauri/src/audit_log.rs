@@ -0,0 +1,154 @@
+//! Analyst action audit log.
+//!
+//! Rule edits and deletions, scans, queries and alert triage changes are
+//! appended, one JSON object per line, to `audit_log.jsonl` in the
+//! application's data directory, so an offline investigation can be reviewed
+//! after the fact. The log is append-only: entries are never rewritten or
+//! removed, and there is no command to do so.
+//!
+//! The log is local to this machine: workspace snapshots don't carry it, and
+//! restoring a snapshot or an app data backup never replaces it. Restores are
+//! recorded like any other action.
+//!
+//! Recording never fails the action being audited; errors are logged. Reads
+//! skip lines that can't be parsed (e.g. a line cut short by a crash) rather
+//! than losing the whole log.
+
+use std::fs::{self, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+
+use crate::app_data;
+use crate::models::{AuditAction, AuditActor, AuditEntry, SiemError};
+
+/// Name of the audit log file in the app data dir.
+pub const AUDIT_LOG_FILE: &str = "audit_log.jsonl";
+
+/// Get the path to the audit log file.
+fn get_audit_path(app_handle: &tauri::AppHandle) -> Result<PathBuf, SiemError> {
+    app_data::app_data_file(app_handle, AUDIT_LOG_FILE)
+}
+
+/// Append an entry to the audit log at `path`. The line is written with a
+/// single call so concurrent writers don't interleave entries.
+fn append_entry(path: &Path, entry: &AuditEntry) -> Result<(), SiemError> {
+    let mut line = serde_json::to_string(entry)
+        .map_err(|e| SiemError::Serialization(format!("Cannot serialize audit entry: {}", e)))?;
+    line.push('\n');
+
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(|e| SiemError::FileIO(format!("Cannot open audit log: {}", e)))?;
+    file.write_all(line.as_bytes())
+        .map_err(|e| SiemError::FileIO(format!("Cannot write audit log: {}", e)))
+}
+
+/// Read the entries of the audit log at `path`, oldest first.
+fn read_entries(path: &Path) -> Result<Vec<AuditEntry>, SiemError> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let file = fs::File::open(path)
+        .map_err(|e| SiemError::FileIO(format!("Cannot open audit log: {}", e)))?;
+    let mut entries = Vec::new();
+    for (index, line) in BufReader::new(file).lines().enumerate() {
+        let line = line.map_err(|e| SiemError::FileIO(format!("Cannot read audit log: {}", e)))?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str(&line) {
+            Ok(entry) => entries.push(entry),
+            Err(e) => eprintln!(
+                "Warning: Skipping invalid audit log line {}: {}",
+                index + 1,
+                e
+            ),
+        }
+    }
+    Ok(entries)
+}
+
+/// Record an analyst action taken in the app. Failures are logged and never
+/// fail the action.
+pub fn record(
+    app_handle: &tauri::AppHandle,
+    action: AuditAction,
+    target: impl Into<String>,
+    details: serde_json::Value,
+) {
+    record_as(app_handle, AuditActor::App, action, target, details);
+}
+
+/// Record an action taken by `actor`, such as a REST API client.
+pub fn record_as(
+    app_handle: &tauri::AppHandle,
+    actor: AuditActor,
+    action: AuditAction,
+    target: impl Into<String>,
+    details: serde_json::Value,
+) {
+    let entry = AuditEntry {
+        timestamp: chrono::Utc::now().to_rfc3339(),
+        actor,
+        action,
+        target: target.into(),
+        details,
+    };
+    if let Err(e) = get_audit_path(app_handle).and_then(|path| append_entry(&path, &entry)) {
+        eprintln!("Warning: Cannot record audit entry: {}", e);
+    }
+}
+
+/// Audit log entries, newest first, optionally only those of one action.
+pub fn get_audit_log(
+    app_handle: &tauri::AppHandle,
+    limit: Option<usize>,
+    action: Option<AuditAction>,
+) -> Result<Vec<AuditEntry>, SiemError> {
+    let entries = read_entries(&get_audit_path(app_handle)?)?;
+    Ok(entries
+        .into_iter()
+        .rev()
+        .filter(|entry| action.is_none_or(|action| entry.action == action))
+        .take(limit.unwrap_or(usize::MAX))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_append_and_read_entries() {
+        let path = std::env::temp_dir().join("offline_siem_test_audit_log.jsonl");
+        let _ = fs::remove_file(&path);
+
+        let entry = |action, target: &str| AuditEntry {
+            timestamp: "2024-03-01T12:00:00+00:00".to_string(),
+            actor: AuditActor::App,
+            action,
+            target: target.to_string(),
+            details: json!({ "rows": 3 }),
+        };
+        append_entry(&path, &entry(AuditAction::RuleSaved, "rule-1")).unwrap();
+        // A line cut short by a crash doesn't hide the entries around it
+        fs::OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .unwrap()
+            .write_all(b"{\"timestamp\":\"2024\n")
+            .unwrap();
+        append_entry(&path, &entry(AuditAction::QueryRun, "SELECT 1")).unwrap();
+
+        let entries = read_entries(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].action, AuditAction::RuleSaved);
+        assert_eq!(entries[1].target, "SELECT 1");
+        assert_eq!(entries[1].details, json!({ "rows": 3 }));
+    }
+}
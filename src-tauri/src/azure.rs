@@ -0,0 +1,150 @@
+//! Azure Activity Log and Entra ID sign-in log profiles
+//! (`LogType::AzureActivity`, `LogType::EntraSignIn`).
+//!
+//! Both are exported in two JSON shapes:
+//!
+//! - the REST/Graph shape (portal download, `az monitor activity-log list`,
+//!   Graph `auditLogs/signIns`): a JSON array or a `{"value": [...]}` page of
+//!   records such as `{"eventTimestamp", "operationName": {"value"}, "caller"}`
+//!   or `{"createdDateTime", "userPrincipalName", "status": {"errorCode"}}`;
+//! - the diagnostic settings shape (storage account, Event Hub): a
+//!   `{"records": [...]}` object or NDJSON of records such as
+//!   `{"time", "category", "operationName", "properties": {...}}`.
+//!
+//! This module recognizes the records of each log and knows their fields,
+//! for log type detection and field suggestions in the rule editor.
+
+use crate::models::LogType;
+
+/// Keys of the object wrapping the records of an export, if not an array.
+pub const RECORD_KEYS: &[&str] = &["value", "records"];
+
+/// Fields of Azure Activity Log records (both shapes).
+pub const ACTIVITY_FIELDS: &[&str] = &[
+    "eventTimestamp",
+    "operationName.value",
+    "operationName.localizedValue",
+    "status.value",
+    "subStatus.value",
+    "caller",
+    "httpRequest.clientIpAddress",
+    "resourceId",
+    "resourceGroupName",
+    "resourceProviderName.value",
+    "resourceType.value",
+    "subscriptionId",
+    "category.value",
+    "level",
+    "correlationId",
+    "authorization.action",
+    "authorization.scope",
+    "time",
+    "operationName",
+    "category",
+    "resultType",
+    "resultSignature",
+    "callerIpAddress",
+    "identity.authorization.action",
+    "properties.statusCode",
+];
+
+/// Fields of Entra ID sign-in records (both shapes).
+pub const SIGNIN_FIELDS: &[&str] = &[
+    "createdDateTime",
+    "userPrincipalName",
+    "userDisplayName",
+    "userId",
+    "appDisplayName",
+    "appId",
+    "ipAddress",
+    "clientAppUsed",
+    "isInteractive",
+    "conditionalAccessStatus",
+    "authenticationRequirement",
+    "riskLevelDuringSignIn",
+    "riskState",
+    "status.errorCode",
+    "status.failureReason",
+    "location.city",
+    "location.countryOrRegion",
+    "deviceDetail.operatingSystem",
+    "deviceDetail.browser",
+    "resourceDisplayName",
+    "time",
+    "category",
+    "resultType",
+    "callerIpAddress",
+    "properties.userPrincipalName",
+    "properties.appDisplayName",
+    "properties.ipAddress",
+    "properties.status.errorCode",
+    "properties.conditionalAccessStatus",
+];
+
+/// Azure log type of a record, if it is an Activity Log or sign-in record.
+pub fn detect_record(record: &serde_json::Value) -> Option<LogType> {
+    let has = |field: &str| record.get(field).is_some();
+    let category = record.get("category").and_then(|c| c.as_str());
+
+    if (has("createdDateTime") && has("userPrincipalName"))
+        || category.is_some_and(|c| c.ends_with("SignInLogs"))
+    {
+        return Some(LogType::EntraSignIn);
+    }
+    if (has("eventTimestamp") && has("operationName"))
+        || (has("time") && has("resourceId") && has("operationName") && has("category"))
+    {
+        return Some(LogType::AzureActivity);
+    }
+    None
+}
+
+/// The records of a parsed export: a root array, or the array under one of
+/// `RECORD_KEYS`.
+pub fn export_records(json: &serde_json::Value) -> Option<&Vec<serde_json::Value>> {
+    json.as_array().or_else(|| {
+        RECORD_KEYS
+            .iter()
+            .find_map(|key| json.get(*key).and_then(|records| records.as_array()))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_azure_records() {
+        let activity = serde_json::json!({
+            "eventTimestamp": "2024-03-01T10:01:02Z",
+            "operationName": { "value": "Microsoft.Compute/virtualMachines/delete" },
+            "caller": "alice@contoso.com"
+        });
+        let diagnostic = serde_json::json!({
+            "time": "2024-03-01T10:01:02Z",
+            "resourceId": "/SUBSCRIPTIONS/123",
+            "operationName": "MICROSOFT.AUTHORIZATION/ROLEASSIGNMENTS/WRITE",
+            "category": "Administrative"
+        });
+        let signin = serde_json::json!({
+            "createdDateTime": "2024-03-01T10:01:02Z",
+            "userPrincipalName": "alice@contoso.com",
+            "status": { "errorCode": 50126 }
+        });
+        assert_eq!(detect_record(&activity), Some(LogType::AzureActivity));
+        assert_eq!(detect_record(&diagnostic), Some(LogType::AzureActivity));
+        assert_eq!(detect_record(&signin), Some(LogType::EntraSignIn));
+        assert_eq!(
+            detect_record(&serde_json::json!({ "time": "x", "category": "SignInLogs" })),
+            Some(LogType::EntraSignIn)
+        );
+        assert_eq!(
+            detect_record(&serde_json::json!({ "eventName": "x" })),
+            None
+        );
+
+        let page = serde_json::json!({ "value": [signin], "@odata.nextLink": "..." });
+        assert_eq!(export_records(&page).map(Vec::len), Some(1));
+        assert!(export_records(&serde_json::json!({ "Records": [] })).is_none());
+    }
+}
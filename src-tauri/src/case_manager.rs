@@ -0,0 +1,512 @@
+//! Case management for DFIR engagements.
+//!
+//! Cases group findings across scans and log files: attached alerts, single
+//! events (with a snapshot of the record), log files, and a timeline of case
+//! activity. Cases are stored in `cases.json` in the application's data
+//! directory and can be exported as a self-contained ZIP bundle.
+
+use duckdb::Connection;
+use serde::Serialize;
+use std::collections::{BTreeSet, HashSet};
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use crate::alert_export::{self, ExportOptions};
+use crate::annotation_manager;
+use crate::anonymize::{AnonymizeOptions, Pseudonymizer};
+use crate::app_data;
+use crate::db_engine;
+use crate::models::{
+    AlertEvent, Case, CaseEvent, CaseStatus, CaseSummary, CaseTimelineEntry, CaseTimelineKind,
+    LogType, SiemError,
+};
+
+/// Summary of a case bundle export.
+#[derive(Debug, Serialize, Clone)]
+pub struct CaseExportSummary {
+    /// Destination file path
+    pub dest_path: String,
+    /// Names of the files written into the bundle
+    pub files: Vec<String>,
+}
+
+/// Get the path to the cases file.
+fn get_cases_path(app_handle: &tauri::AppHandle) -> Result<PathBuf, SiemError> {
+    app_data::app_data_file(app_handle, "cases.json")
+}
+
+/// Load all cases from disk.
+fn load_cases(app_handle: &tauri::AppHandle) -> Result<Vec<Case>, SiemError> {
+    let path = get_cases_path(app_handle)?;
+
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let content = fs::read_to_string(&path)
+        .map_err(|e| SiemError::FileIO(format!("Cannot read cases: {}", e)))?;
+
+    serde_json::from_str(&content)
+        .map_err(|e| SiemError::Serialization(format!("Cannot parse cases: {}", e)))
+}
+
+/// Save all cases to disk.
+fn save_cases(app_handle: &tauri::AppHandle, cases: &[Case]) -> Result<(), SiemError> {
+    let path = get_cases_path(app_handle)?;
+
+    let content = serde_json::to_string_pretty(cases)
+        .map_err(|e| SiemError::Serialization(format!("Cannot serialize cases: {}", e)))?;
+
+    fs::write(&path, content).map_err(|e| SiemError::FileIO(format!("Cannot write cases: {}", e)))
+}
+
+/// Append a timeline entry to a case.
+fn record(case: &mut Case, kind: CaseTimelineKind, message: String) {
+    let now = chrono::Utc::now().to_rfc3339();
+    case.timeline.push(CaseTimelineEntry {
+        timestamp: now.clone(),
+        kind,
+        message,
+    });
+    case.updated_at = now;
+}
+
+/// Load a case, apply a change, and save it.
+fn update_case<F>(
+    app_handle: &tauri::AppHandle,
+    case_id: &str,
+    change: F,
+) -> Result<Case, SiemError>
+where
+    F: FnOnce(&mut Case) -> Result<(), SiemError>,
+{
+    let mut cases = load_cases(app_handle)?;
+    let case = cases
+        .iter_mut()
+        .find(|c| c.id == case_id)
+        .ok_or_else(|| SiemError::Query(format!("Case not found: {}", case_id)))?;
+
+    change(case)?;
+    let updated = case.clone();
+
+    save_cases(app_handle, &cases)?;
+    Ok(updated)
+}
+
+/// Create a new open case.
+pub fn create_case(
+    app_handle: &tauri::AppHandle,
+    name: String,
+    description: String,
+) -> Result<Case, SiemError> {
+    if name.trim().is_empty() {
+        return Err(SiemError::Query("Case name cannot be empty".to_string()));
+    }
+
+    let now = chrono::Utc::now().to_rfc3339();
+    let mut case = Case {
+        id: uuid::Uuid::new_v4().to_string(),
+        name,
+        description,
+        status: CaseStatus::Open,
+        created_at: now.clone(),
+        updated_at: now,
+        alerts: Vec::new(),
+        events: Vec::new(),
+        log_files: Vec::new(),
+        timeline: Vec::new(),
+    };
+    record(
+        &mut case,
+        CaseTimelineKind::Created,
+        "Case created".to_string(),
+    );
+
+    let mut cases = load_cases(app_handle)?;
+    cases.push(case.clone());
+    save_cases(app_handle, &cases)?;
+
+    Ok(case)
+}
+
+/// List cases, most recently updated first.
+pub fn list_cases(app_handle: &tauri::AppHandle) -> Result<Vec<CaseSummary>, SiemError> {
+    let mut cases = load_cases(app_handle)?;
+    cases.sort_by(|a, b| b.updated_at.cmp(&a.updated_at));
+
+    Ok(cases.iter().map(summarize).collect())
+}
+
+/// Build the listing overview of a case.
+fn summarize(case: &Case) -> CaseSummary {
+    CaseSummary {
+        id: case.id.clone(),
+        name: case.name.clone(),
+        status: case.status,
+        created_at: case.created_at.clone(),
+        updated_at: case.updated_at.clone(),
+        alert_count: case.alerts.len(),
+        event_count: case.events.len(),
+        log_file_count: case.log_files.len(),
+    }
+}
+
+/// Get a case by ID.
+pub fn get_case(app_handle: &tauri::AppHandle, case_id: &str) -> Result<Case, SiemError> {
+    load_cases(app_handle)?
+        .into_iter()
+        .find(|c| c.id == case_id)
+        .ok_or_else(|| SiemError::Query(format!("Case not found: {}", case_id)))
+}
+
+/// Delete a case by ID.
+pub fn delete_case(app_handle: &tauri::AppHandle, case_id: &str) -> Result<(), SiemError> {
+    let mut cases = load_cases(app_handle)?;
+    let before = cases.len();
+    cases.retain(|c| c.id != case_id);
+
+    if cases.len() == before {
+        return Err(SiemError::Query(format!("Case not found: {}", case_id)));
+    }
+
+    save_cases(app_handle, &cases)
+}
+
+/// Change the status of a case.
+pub fn set_case_status(
+    app_handle: &tauri::AppHandle,
+    case_id: &str,
+    status: CaseStatus,
+) -> Result<Case, SiemError> {
+    update_case(app_handle, case_id, |case| {
+        if case.status != status {
+            let message = format!("Status changed from {:?} to {:?}", case.status, status);
+            case.status = status;
+            record(case, CaseTimelineKind::StatusChanged, message);
+        }
+        Ok(())
+    })
+}
+
+/// Attach alerts (e.g. from a scan result) to a case.
+pub fn attach_alerts(
+    app_handle: &tauri::AppHandle,
+    case_id: &str,
+    alerts: Vec<AlertEvent>,
+) -> Result<Case, SiemError> {
+    update_case(app_handle, case_id, |case| {
+        for alert in alerts {
+            let message = format!(
+                "Alert attached: [{}] {} ({} matches{})",
+                alert.severity,
+                alert.rule_title,
+                alert.match_count,
+                alert
+                    .source_file
+                    .as_deref()
+                    .map(|f| format!(", {}", f))
+                    .unwrap_or_default()
+            );
+            case.alerts.push(alert);
+            record(case, CaseTimelineKind::AlertAttached, message);
+        }
+        Ok(())
+    })
+}
+
+/// Attach a single event to a case, snapshotting the record.
+pub fn attach_event(
+    app_handle: &tauri::AppHandle,
+    conn: &Connection,
+    case_id: &str,
+    log_path: &str,
+    log_type: LogType,
+    record_index: usize,
+    note: String,
+) -> Result<Case, SiemError> {
+    let events = db_engine::load_all_events(conn, log_path, log_type)?;
+
+    let event = events.get(record_index).cloned().ok_or_else(|| {
+        SiemError::Query(format!(
+            "Record index {} out of range ({} events)",
+            record_index,
+            events.len()
+        ))
+    })?;
+
+    update_case(app_handle, case_id, |case| {
+        case.events.push(CaseEvent {
+            file_path: log_path.to_string(),
+            record_index,
+            note,
+            event,
+        });
+        record(
+            case,
+            CaseTimelineKind::EventAttached,
+            format!("Event #{} of {} attached", record_index, log_path),
+        );
+        Ok(())
+    })
+}
+
+/// Attach a log file to a case (attaching the same file twice is a no-op).
+pub fn attach_log_file(
+    app_handle: &tauri::AppHandle,
+    case_id: &str,
+    log_path: &str,
+) -> Result<Case, SiemError> {
+    if !Path::new(log_path).exists() {
+        return Err(SiemError::FileIO(format!(
+            "Log file not found: {}",
+            log_path
+        )));
+    }
+
+    update_case(app_handle, case_id, |case| {
+        if !case.log_files.iter().any(|f| f == log_path) {
+            case.log_files.push(log_path.to_string());
+            record(
+                case,
+                CaseTimelineKind::LogFileAttached,
+                format!("Log file attached: {}", log_path),
+            );
+        }
+        Ok(())
+    })
+}
+
+/// Add an analyst note to a case timeline.
+pub fn add_case_note(
+    app_handle: &tauri::AppHandle,
+    case_id: &str,
+    message: String,
+) -> Result<Case, SiemError> {
+    if message.trim().is_empty() {
+        return Err(SiemError::Query("Note cannot be empty".to_string()));
+    }
+
+    update_case(app_handle, case_id, |case| {
+        record(case, CaseTimelineKind::Note, message);
+        Ok(())
+    })
+}
+
+/// Serialize a bundle entry as pretty JSON.
+fn to_json<T: Serialize>(value: &T) -> Result<Vec<u8>, SiemError> {
+    serde_json::to_vec_pretty(value)
+        .map_err(|e| SiemError::Serialization(format!("Cannot serialize case data: {}", e)))
+}
+
+/// Export a case as a ZIP bundle.
+///
+/// Contents: `case.json` (full case), `alerts.jsonl`, `report.html`,
+/// `events.jsonl`, `timeline.json`, `flagged_events.json` (flags on the
+/// case's log files), and with `include_logs` the attached log files under
+/// `logs/`. With `anonymize`, alerts and events are pseudonymized; raw logs
+/// cannot be included then.
+pub fn export_case_bundle(
+    app_handle: &tauri::AppHandle,
+    case_id: &str,
+    dest_path: &str,
+    include_logs: bool,
+    anonymize: Option<&AnonymizeOptions>,
+) -> Result<CaseExportSummary, SiemError> {
+    use zip::write::FileOptions;
+
+    if anonymize.is_some() && include_logs {
+        return Err(SiemError::Query(
+            "Raw log files cannot be included in an anonymized bundle".to_string(),
+        ));
+    }
+
+    let mut case = get_case(app_handle, case_id)?;
+
+    // Flags on any log file referenced by the case
+    let case_files: BTreeSet<&str> = case
+        .log_files
+        .iter()
+        .map(|f| f.as_str())
+        .chain(case.events.iter().map(|e| e.file_path.as_str()))
+        .collect();
+    let mut flagged: Vec<_> = annotation_manager::list_flagged_events(app_handle, None)?
+        .into_iter()
+        .filter(|a| case_files.contains(a.file_path.as_str()))
+        .collect();
+
+    if let Some(options) = anonymize {
+        let pseudonymizer = Pseudonymizer::new(options)?;
+        for alert in &mut case.alerts {
+            pseudonymizer.apply_alert(alert);
+        }
+        for attached in &mut case.events {
+            pseudonymizer.apply_event(&mut attached.event);
+        }
+        for annotation in &mut flagged {
+            pseudonymizer.apply_event(&mut annotation.event);
+        }
+    }
+
+    let mut entries: Vec<(String, Vec<u8>)> = Vec::new();
+
+    entries.push(("case.json".to_string(), to_json(&case)?));
+    entries.push(("timeline.json".to_string(), to_json(&case.timeline)?));
+    entries.push(("flagged_events.json".to_string(), to_json(&flagged)?));
+
+    let events_jsonl: String = case
+        .events
+        .iter()
+        .filter_map(|e| serde_json::to_string(e).ok())
+        .map(|line| line + "\n")
+        .collect();
+    entries.push(("events.jsonl".to_string(), events_jsonl.into_bytes()));
+
+    // Alerts go through the shared exporters
+    let mut report_options = ExportOptions::append_to("");
+    report_options.title = Some(format!("Case: {}", case.name));
+    for (name, sink_id) in [("alerts.jsonl", "jsonl"), ("report.html", "html")] {
+        let sink = alert_export::find_sink(sink_id)
+            .ok_or_else(|| SiemError::FileIO(format!("Unknown export sink: {}", sink_id)))?;
+        let mut content = Vec::new();
+        sink.write_alerts(&case.alerts, &report_options, &mut content)
+            .map_err(|e| SiemError::FileIO(format!("Cannot render {}: {}", name, e)))?;
+        entries.push((name.to_string(), content));
+    }
+
+    if include_logs {
+        let names = log_entry_names(&case.log_files);
+        for (log_path, name) in case.log_files.iter().zip(names) {
+            let content = fs::read(log_path)
+                .map_err(|e| SiemError::FileIO(format!("Cannot read {}: {}", log_path, e)))?;
+            entries.push((name, content));
+        }
+    }
+
+    let file = fs::File::create(dest_path)
+        .map_err(|e| SiemError::FileIO(format!("Cannot create ZIP file: {}", e)))?;
+    let mut zip = zip::ZipWriter::new(file);
+    let options = FileOptions::default()
+        .compression_method(zip::CompressionMethod::Deflated)
+        .unix_permissions(0o644);
+
+    let mut files = Vec::new();
+    for (name, content) in entries {
+        zip.start_file(name.as_str(), options)
+            .map_err(|e| SiemError::FileIO(format!("Cannot add file to ZIP: {}", e)))?;
+        zip.write_all(&content)
+            .map_err(|e| SiemError::FileIO(format!("Cannot write to ZIP: {}", e)))?;
+        files.push(name);
+    }
+
+    zip.finish()
+        .map_err(|e| SiemError::FileIO(format!("Cannot finalize ZIP: {}", e)))?;
+
+    Ok(CaseExportSummary {
+        dest_path: dest_path.to_string(),
+        files,
+    })
+}
+
+/// Bundle entry names of log files: `logs/<file name>`, with `-2`, `-3`, ...
+/// added before the extension when logs from different directories share a
+/// file name.
+fn log_entry_names(log_files: &[String]) -> Vec<String> {
+    let mut used = HashSet::new();
+    log_files
+        .iter()
+        .map(|log_path| {
+            let path = Path::new(log_path);
+            let file_name = path
+                .file_name()
+                .and_then(|n| n.to_str())
+                .unwrap_or(log_path);
+            let mut name = format!("logs/{}", file_name);
+            let mut n = 1;
+            while !used.insert(name.to_lowercase()) {
+                n += 1;
+                let stem = path
+                    .file_stem()
+                    .and_then(|s| s.to_str())
+                    .unwrap_or(file_name);
+                name = match path.extension().and_then(|e| e.to_str()) {
+                    Some(ext) => format!("logs/{}-{}.{}", stem, n, ext),
+                    None => format!("logs/{}-{}", stem, n),
+                };
+            }
+            name
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn empty_case() -> Case {
+        Case {
+            id: "c1".to_string(),
+            name: "Incident".to_string(),
+            description: String::new(),
+            status: CaseStatus::Open,
+            created_at: "2024-01-01T00:00:00+00:00".to_string(),
+            updated_at: "2024-01-01T00:00:00+00:00".to_string(),
+            alerts: vec![],
+            events: vec![],
+            log_files: vec![],
+            timeline: vec![],
+        }
+    }
+
+    #[test]
+    fn test_record_appends_timeline_and_touches_case() {
+        let mut case = empty_case();
+        record(&mut case, CaseTimelineKind::Note, "First note".to_string());
+
+        assert_eq!(case.timeline.len(), 1);
+        assert_eq!(case.timeline[0].kind, CaseTimelineKind::Note);
+        assert_eq!(case.timeline[0].timestamp, case.updated_at);
+        assert!(case.updated_at > case.created_at);
+    }
+
+    #[test]
+    fn test_summarize_counts_attachments() {
+        let mut case = empty_case();
+        case.log_files.push("/logs/a.json".to_string());
+        case.events.push(CaseEvent {
+            file_path: "/logs/a.json".to_string(),
+            record_index: 3,
+            note: String::new(),
+            event: serde_json::json!({"eventName": "ConsoleLogin"}),
+        });
+
+        let summary = summarize(&case);
+        assert_eq!(summary.alert_count, 0);
+        assert_eq!(summary.event_count, 1);
+        assert_eq!(summary.log_file_count, 1);
+    }
+
+    #[test]
+    fn test_log_entry_names_are_unique() {
+        let logs = [
+            "/cases/a/CloudTrail.json",
+            "/cases/b/CloudTrail.json",
+            "/cases/c/cloudtrail.json",
+            "/cases/d/CloudTrail-2.json",
+            "/var/log/syslog",
+            "/backup/syslog",
+        ]
+        .map(String::from);
+        assert_eq!(
+            log_entry_names(&logs),
+            vec![
+                "logs/CloudTrail.json",
+                "logs/CloudTrail-2.json",
+                "logs/cloudtrail-3.json",
+                "logs/CloudTrail-2-2.json",
+                "logs/syslog",
+                "logs/syslog-2",
+            ]
+        );
+    }
+}
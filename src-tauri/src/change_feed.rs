@@ -0,0 +1,184 @@
+//! Workspace change feed for the frontend.
+//!
+//! Every command that modifies rules, logs, alerts, cases or configuration
+//! emits a `ChangeEvent` on `CHANGE_EVENT`, so all open windows can refresh
+//! the affected view instead of polling. A background watcher also polls the
+//! rules directory and reports external edits (files added, modified or
+//! removed outside the app).
+//!
+//! Changes made by the app itself resynchronize the watcher, so they are not
+//! reported a second time as external.
+
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, SystemTime};
+use tauri::Emitter;
+
+use crate::config;
+
+/// Tauri event carrying a `ChangeEvent`.
+pub const CHANGE_EVENT: &str = "workspace://changed";
+
+/// How often the rules directory is polled for external edits.
+const WATCH_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Area of the workspace that changed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeKind {
+    Rules,
+    Logs,
+    Alerts,
+    Config,
+    Cases,
+    Queries,
+}
+
+/// Payload of a change notification.
+#[derive(Debug, Clone, Serialize)]
+pub struct ChangeEvent {
+    /// What changed
+    pub kind: ChangeKind,
+    /// Affected identifiers (rule IDs, log filenames, ...); empty = reload everything
+    pub ids: Vec<String>,
+    /// Whether the change was made outside the app (e.g. rule files edited on disk)
+    pub external: bool,
+    /// When the change was detected (ISO 8601)
+    pub timestamp: String,
+}
+
+/// Fingerprint of a rules directory: file path -> (modified time, size).
+type DirState = HashMap<PathBuf, (SystemTime, u64)>;
+
+fn watch_state() -> &'static Mutex<Option<(PathBuf, DirState)>> {
+    static STATE: OnceLock<Mutex<Option<(PathBuf, DirState)>>> = OnceLock::new();
+    STATE.get_or_init(|| Mutex::new(None))
+}
+
+/// Emit a change made by the app.
+pub fn notify(app_handle: &tauri::AppHandle, kind: ChangeKind, ids: Vec<String>) {
+    // Our own rule writes must not come back as external edits
+    if matches!(kind, ChangeKind::Rules | ChangeKind::Config) {
+        resync_rules(app_handle);
+    }
+    emit(app_handle, kind, ids, false);
+}
+
+fn emit(app_handle: &tauri::AppHandle, kind: ChangeKind, ids: Vec<String>, external: bool) {
+    let event = ChangeEvent {
+        kind,
+        ids,
+        external,
+        timestamp: chrono::Utc::now().to_rfc3339(),
+    };
+    if let Err(e) = app_handle.emit(CHANGE_EVENT, event) {
+        eprintln!("Warning: Cannot emit change event: {}", e);
+    }
+}
+
+/// Snapshot the rule files of a directory.
+fn scan_rules_dir(dir: &Path) -> DirState {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return DirState::new();
+    };
+
+    entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|ext| ext == "yaml" || ext == "yml")
+        })
+        .filter_map(|path| {
+            let meta = fs::metadata(&path).ok()?;
+            Some((path, (meta.modified().ok()?, meta.len())))
+        })
+        .collect()
+}
+
+/// Rule IDs (file stems) whose files differ between two snapshots.
+fn changed_rule_ids(old: &DirState, new: &DirState) -> Vec<String> {
+    let mut changed: Vec<String> = new
+        .iter()
+        .filter(|(path, state)| old.get(*path) != Some(*state))
+        .map(|(path, _)| path)
+        .chain(old.keys().filter(|path| !new.contains_key(*path)))
+        .filter_map(|path| path.file_stem().map(|s| s.to_string_lossy().to_string()))
+        .collect();
+    changed.sort();
+    changed.dedup();
+    changed
+}
+
+/// Record the current rules directory contents as known.
+fn resync_rules(app_handle: &tauri::AppHandle) {
+    let Ok(dir) = config::get_rules_directory(app_handle) else {
+        return;
+    };
+    let state = scan_rules_dir(&dir);
+    if let Ok(mut guard) = watch_state().lock() {
+        *guard = Some((dir, state));
+    }
+}
+
+/// Start the background watcher reporting external edits to rule files.
+pub fn start_rules_watcher(app_handle: tauri::AppHandle) {
+    resync_rules(&app_handle);
+
+    std::thread::spawn(move || loop {
+        std::thread::sleep(WATCH_INTERVAL);
+
+        let Ok(dir) = config::get_rules_directory(&app_handle) else {
+            continue;
+        };
+        let current = scan_rules_dir(&dir);
+
+        let changed = {
+            let Ok(mut guard) = watch_state().lock() else {
+                continue;
+            };
+            let changed = match guard.as_ref() {
+                // Directory switched in config: the app already reported it
+                Some((known_dir, known)) if *known_dir == dir => changed_rule_ids(known, &current),
+                _ => Vec::new(),
+            };
+            *guard = Some((dir, current));
+            changed
+        };
+
+        if !changed.is_empty() {
+            emit(&app_handle, ChangeKind::Rules, changed, true);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_changed_rule_ids() {
+        let t0 = SystemTime::UNIX_EPOCH;
+        let t1 = t0 + Duration::from_secs(1);
+        let old: DirState = [
+            (PathBuf::from("/r/a.yaml"), (t0, 10)),
+            (PathBuf::from("/r/b.yaml"), (t0, 10)),
+            (PathBuf::from("/r/c.yaml"), (t0, 10)),
+        ]
+        .into_iter()
+        .collect();
+        let new: DirState = [
+            (PathBuf::from("/r/a.yaml"), (t0, 10)),
+            (PathBuf::from("/r/b.yaml"), (t1, 12)),
+            (PathBuf::from("/r/d.yaml"), (t1, 5)),
+        ]
+        .into_iter()
+        .collect();
+
+        assert_eq!(changed_rule_ids(&old, &new), vec!["b", "c", "d"]);
+        assert!(changed_rule_ids(&new, &new).is_empty());
+    }
+}
@@ -5,9 +5,12 @@
 use serde::{Deserialize, Serialize};
 use std::fs;
 use std::path::PathBuf;
-use tauri::Manager;
 
-use crate::models::SiemError;
+use crate::app_data;
+use crate::models::{AlertGrouping, SiemError};
+
+/// Name of the config file in the app data dir.
+pub const CONFIG_FILE: &str = "config.json";
 
 /// Application configuration stored as JSON.
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -29,6 +32,94 @@ pub struct AppConfig {
     /// UI preferences
     #[serde(default)]
     pub ui_preferences: UiPreferences,
+
+    /// Severity-based routing of alerts to output sinks, evaluated after each scan
+    #[serde(default)]
+    pub alert_routes: Vec<AlertRoute>,
+
+    /// Evidence fields checked for file hashes during hash set annotation
+    #[serde(default = "default_hash_fields")]
+    pub hash_fields: Vec<String>,
+
+    /// GeoLite2 Country or City database (.mmdb) for GeoIP enrichment
+    #[serde(default)]
+    pub geoip_country_db: Option<String>,
+
+    /// GeoLite2 ASN database (.mmdb) for GeoIP enrichment
+    #[serde(default)]
+    pub geoip_asn_db: Option<String>,
+
+    /// Evidence fields checked for IP addresses during GeoIP annotation
+    #[serde(default = "default_ip_fields")]
+    pub ip_fields: Vec<String>,
+
+    /// Default alert grouping for rules that don't set their own
+    #[serde(default)]
+    pub alert_grouping: AlertGrouping,
+
+    /// Worker threads evaluating rules during a scan (0 = one per CPU core)
+    #[serde(default)]
+    pub scan_workers: usize,
+
+    /// Parse imported log files into the persistent ingestion cache
+    #[serde(default)]
+    pub ingest_on_import: bool,
+
+    /// Ad-hoc queries running longer than this are interrupted (0 = no limit)
+    #[serde(default = "default_query_timeout")]
+    pub query_timeout_secs: u64,
+
+    /// Alerts a rule may produce per scan before they are merged into one
+    /// summary alert, for rules that don't set their own (0 = no limit)
+    #[serde(default = "default_max_alerts_per_scan")]
+    pub max_alerts_per_scan: usize,
+
+    /// Directory watched for new log files (if None, the log library)
+    #[serde(default)]
+    pub watch_directory: Option<String>,
+
+    /// Start the folder watch when the app starts
+    #[serde(default)]
+    pub watch_on_startup: bool,
+
+    /// The starter rules were offered on first run (installed if the rules
+    /// directory was empty)
+    #[serde(default)]
+    pub default_rules_installed: bool,
+
+    /// Start the local REST API when the app starts
+    #[serde(default)]
+    pub api_enabled: bool,
+
+    /// Port of the local REST API (bound to 127.0.0.1)
+    #[serde(default = "default_api_port")]
+    pub api_port: u16,
+
+    /// Bearer token REST API clients must send (generated on first start)
+    #[serde(default)]
+    pub api_token: Option<String>,
+}
+
+/// Route alerts of the listed severities to a set of sinks.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AlertRoute {
+    /// Severities this route applies to (empty = all severities)
+    #[serde(default)]
+    pub severities: Vec<String>,
+    /// Sinks receiving matching alerts
+    pub sinks: Vec<AlertSink>,
+}
+
+/// Output sink for routed alerts.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AlertSink {
+    /// Desktop notification shown by the frontend (emitted as a Tauri event)
+    Notification,
+    /// Append RFC 5424 syslog lines to a file
+    SyslogFile { path: String },
+    /// Append alerts as JSON lines to a file
+    JsonlFile { path: String },
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -50,6 +141,22 @@ impl Default for AppConfig {
             recent_log_files: Vec::new(),
             max_recent_files: default_max_recent(),
             ui_preferences: UiPreferences::default(),
+            alert_routes: Vec::new(),
+            hash_fields: default_hash_fields(),
+            geoip_country_db: None,
+            geoip_asn_db: None,
+            ip_fields: default_ip_fields(),
+            alert_grouping: AlertGrouping::default(),
+            scan_workers: 0,
+            ingest_on_import: false,
+            query_timeout_secs: default_query_timeout(),
+            max_alerts_per_scan: default_max_alerts_per_scan(),
+            watch_directory: None,
+            watch_on_startup: false,
+            default_rules_installed: false,
+            api_enabled: false,
+            api_port: default_api_port(),
+            api_token: None,
         }
     }
 }
@@ -67,23 +174,52 @@ fn default_max_recent() -> usize {
     10
 }
 
+fn default_query_timeout() -> u64 {
+    300
+}
+
+fn default_max_alerts_per_scan() -> usize {
+    100
+}
+
+fn default_api_port() -> u16 {
+    8765
+}
+
+fn default_hash_fields() -> Vec<String> {
+    [
+        "Hashes",
+        "hashes",
+        "md5",
+        "sha1",
+        "sha256",
+        "file.hash.sha256",
+    ]
+    .iter()
+    .map(|f| f.to_string())
+    .collect()
+}
+
+fn default_ip_fields() -> Vec<String> {
+    [
+        "sourceIPAddress",
+        "SourceIp",
+        "IpAddress",
+        "src_ip",
+        "dst_ip",
+    ]
+    .iter()
+    .map(|f| f.to_string())
+    .collect()
+}
+
 fn default_true() -> bool {
     true
 }
 
 /// Get the path to the config file.
 fn get_config_path(app_handle: &tauri::AppHandle) -> Result<PathBuf, SiemError> {
-    let app_data_dir = app_handle
-        .path()
-        .app_data_dir()
-        .map_err(|e| SiemError::FileIO(format!("Cannot get app data dir: {}", e)))?;
-
-    if !app_data_dir.exists() {
-        fs::create_dir_all(&app_data_dir)
-            .map_err(|e| SiemError::FileIO(format!("Cannot create app data dir: {}", e)))?;
-    }
-
-    Ok(app_data_dir.join("config.json"))
+    app_data::app_data_file(app_handle, CONFIG_FILE)
 }
 
 /// Load configuration from disk.
@@ -169,9 +305,15 @@ pub fn clear_recent_files(app_handle: &tauri::AppHandle) -> Result<AppConfig, Si
 
 /// Get the effective rules directory (custom or default).
 pub fn get_rules_directory(app_handle: &tauri::AppHandle) -> Result<PathBuf, SiemError> {
-    let config = load_config(app_handle)?;
+    rules_directory_of(app_handle, &load_config(app_handle)?)
+}
 
-    if let Some(custom_dir) = config.rules_directory {
+/// Get the rules directory a configuration points to (custom or default).
+pub fn rules_directory_of(
+    app_handle: &tauri::AppHandle,
+    config: &AppConfig,
+) -> Result<PathBuf, SiemError> {
+    if let Some(custom_dir) = &config.rules_directory {
         let path = PathBuf::from(custom_dir);
 
         // Create if doesn't exist
@@ -183,19 +325,7 @@ pub fn get_rules_directory(app_handle: &tauri::AppHandle) -> Result<PathBuf, Sie
         Ok(path)
     } else {
         // Use default app data directory
-        let app_data_dir = app_handle
-            .path()
-            .app_data_dir()
-            .map_err(|e| SiemError::FileIO(format!("Cannot get app data dir: {}", e)))?;
-
-        let rules_dir = app_data_dir.join("rules");
-
-        if !rules_dir.exists() {
-            fs::create_dir_all(&rules_dir)
-                .map_err(|e| SiemError::FileIO(format!("Cannot create rules dir: {}", e)))?;
-        }
-
-        Ok(rules_dir)
+        app_data::app_data_subdir(app_handle, "rules")
     }
 }
 
@@ -0,0 +1,271 @@
+//! Sequence (correlation) rules.
+//!
+//! A rule with a `sequence` block alerts when events match its steps in
+//! order, all sharing the same values for the `by` fields, with the last step
+//! no later than `window` after the first. Each completed sequence produces a
+//! single alert whose evidence is the matched events, one per step.
+//!
+//! Per key, the engine tracks at most one partial match per step and prefers
+//! the most recent start, so memory stays linear in the number of keys.
+
+use std::collections::{BTreeMap, HashMap};
+
+use chrono::{DateTime, Utc};
+
+use crate::aggregation;
+use crate::db_engine::{self, CaseMode};
+use crate::event_time;
+use crate::models::{CorrelationMatch, LogType, Sequence, SiemError};
+
+/// A sequence matched up to some step.
+#[derive(Clone)]
+struct Partial {
+    started: DateTime<Utc>,
+    events: Vec<(DateTime<Utc>, serde_json::Value)>,
+}
+
+/// Check that a sequence is well formed.
+pub fn validate_sequence(sequence: &Sequence) -> Result<i64, SiemError> {
+    if sequence.steps.len() < 2 {
+        return Err(SiemError::Rule(
+            "Sequence rules need at least two steps".to_string(),
+        ));
+    }
+    if let Some(step) = sequence
+        .steps
+        .iter()
+        .find(|s| s.condition.trim().is_empty())
+    {
+        return Err(SiemError::Rule(format!(
+            "Sequence step '{}' has an empty condition",
+            step.name.as_deref().unwrap_or("unnamed")
+        )));
+    }
+    aggregation::parse_window(&sequence.window)
+}
+
+/// Check whether an event matches any step of a sequence.
+pub fn matches_any_step(
+    event: &serde_json::Value,
+    sequence: &Sequence,
+    case_mode: CaseMode,
+) -> bool {
+    sequence
+        .steps
+        .iter()
+        .any(|step| db_engine::matches_condition_with_case(event, &step.condition, case_mode))
+}
+
+/// Find completed sequences among candidate events.
+///
+/// Events without a parseable timestamp or missing a `by` field are ignored,
+/// since neither ordering nor key equality can be established for them.
+pub fn correlate_events(
+    sequence: &Sequence,
+    case_mode: CaseMode,
+    events: Vec<serde_json::Value>,
+    log_type: &LogType,
+) -> Result<Vec<(CorrelationMatch, Vec<serde_json::Value>)>, SiemError> {
+    let window_secs = validate_sequence(sequence)?;
+    let step_count = sequence.steps.len();
+
+    // Order candidates by time
+    let mut timed: Vec<(DateTime<Utc>, serde_json::Value)> = events
+        .into_iter()
+        .filter_map(|event| {
+            event_time::event_time(&event, sequence.timestamp_field.as_deref(), log_type)
+                .map(|ts| (ts, event))
+        })
+        .collect();
+    timed.sort_by_key(|(ts, _)| *ts);
+
+    // partials[key][i] = latest partial match that completed steps 0..=i-1
+    let mut partials: HashMap<Vec<String>, Vec<Option<Partial>>> = HashMap::new();
+    let mut matches = Vec::new();
+
+    for (ts, event) in timed {
+        let key: Vec<String> = sequence
+            .by
+            .iter()
+            .map(|field| aggregation::group_value(&event, field))
+            .collect();
+        if key.iter().any(|value| value.is_empty()) {
+            continue;
+        }
+
+        let slots = partials
+            .entry(key.clone())
+            .or_insert_with(|| vec![None; step_count]);
+
+        // Walk steps from last to first so one event never advances its own partial
+        for index in (0..step_count).rev() {
+            let step = &sequence.steps[index];
+            if !db_engine::matches_condition_with_case(&event, &step.condition, case_mode) {
+                continue;
+            }
+
+            let extended = if index == 0 {
+                Some(Partial {
+                    started: ts,
+                    events: vec![(ts, event.clone())],
+                })
+            } else {
+                slots[index].take().and_then(|mut partial| {
+                    if (ts - partial.started).num_seconds() > window_secs {
+                        return None;
+                    }
+                    partial.events.push((ts, event.clone()));
+                    Some(partial)
+                })
+            };
+
+            let Some(partial) = extended else {
+                continue;
+            };
+
+            if index + 1 == step_count {
+                matches.push(build_match(sequence, &key, partial));
+            } else {
+                let next = &mut slots[index + 1];
+                if next.as_ref().map_or(true, |p| p.started <= partial.started) {
+                    *next = Some(partial);
+                }
+            }
+        }
+    }
+
+    Ok(matches)
+}
+
+/// Turn a completed partial into an alert payload.
+fn build_match(
+    sequence: &Sequence,
+    key: &[String],
+    partial: Partial,
+) -> (CorrelationMatch, Vec<serde_json::Value>) {
+    let correlation = CorrelationMatch {
+        key: sequence
+            .by
+            .iter()
+            .cloned()
+            .zip(key.iter().cloned())
+            .collect::<BTreeMap<_, _>>(),
+        steps: sequence
+            .steps
+            .iter()
+            .enumerate()
+            .map(|(i, step)| {
+                step.name
+                    .clone()
+                    .unwrap_or_else(|| format!("step {}", i + 1))
+            })
+            .collect(),
+        first_seen: partial.events.first().map(|(ts, _)| ts.to_rfc3339()),
+        last_seen: partial.events.last().map(|(ts, _)| ts.to_rfc3339()),
+    };
+
+    let evidence = partial.events.into_iter().map(|(_, event)| event).collect();
+    (correlation, evidence)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::SequenceStep;
+    use serde_json::json;
+
+    fn sequence(window: &str) -> Sequence {
+        Sequence {
+            steps: vec![
+                SequenceStep {
+                    name: Some("login without MFA".to_string()),
+                    condition: "eventName = 'ConsoleLogin' AND additionalEventData.MFAUsed = 'No'"
+                        .to_string(),
+                },
+                SequenceStep {
+                    name: None,
+                    condition: "eventName = 'CreateAccessKey'".to_string(),
+                },
+            ],
+            window: window.to_string(),
+            by: vec!["userIdentity.arn".to_string()],
+            timestamp_field: None,
+        }
+    }
+
+    fn event(name: &str, arn: &str, time: &str) -> serde_json::Value {
+        json!({
+            "eventName": name,
+            "eventTime": time,
+            "userIdentity": { "arn": arn },
+            "additionalEventData": { "MFAUsed": "No" }
+        })
+    }
+
+    #[test]
+    fn test_sequence_within_window_same_key() {
+        let events = vec![
+            event("CreateAccessKey", "arn:alice", "2024-01-01T09:00:00Z"),
+            event("ConsoleLogin", "arn:alice", "2024-01-01T10:00:00Z"),
+            event("CreateAccessKey", "arn:bob", "2024-01-01T10:05:00Z"),
+            event("CreateAccessKey", "arn:alice", "2024-01-01T10:10:00Z"),
+        ];
+
+        let matches = correlate_events(
+            &sequence("30m"),
+            CaseMode::Default,
+            events,
+            &LogType::CloudTrail,
+        )
+        .unwrap();
+
+        assert_eq!(matches.len(), 1);
+        let (correlation, evidence) = &matches[0];
+        assert_eq!(correlation.key["userIdentity.arn"], "arn:alice");
+        assert_eq!(correlation.steps, vec!["login without MFA", "step 2"]);
+        assert_eq!(evidence.len(), 2);
+        assert_eq!(evidence[0]["eventName"], "ConsoleLogin");
+        assert_eq!(evidence[1]["eventTime"], "2024-01-01T10:10:00Z");
+    }
+
+    #[test]
+    fn test_sequence_outside_window() {
+        let events = vec![
+            event("ConsoleLogin", "arn:alice", "2024-01-01T10:00:00Z"),
+            event("CreateAccessKey", "arn:alice", "2024-01-01T11:00:00Z"),
+        ];
+
+        let matches = correlate_events(
+            &sequence("30m"),
+            CaseMode::Default,
+            events,
+            &LogType::CloudTrail,
+        )
+        .unwrap();
+        assert!(matches.is_empty());
+    }
+
+    #[test]
+    fn test_sequence_requires_order() {
+        let events = vec![
+            event("CreateAccessKey", "arn:alice", "2024-01-01T10:00:00Z"),
+            event("ConsoleLogin", "arn:alice", "2024-01-01T10:05:00Z"),
+        ];
+
+        let matches = correlate_events(
+            &sequence("1h"),
+            CaseMode::Default,
+            events,
+            &LogType::CloudTrail,
+        )
+        .unwrap();
+        assert!(matches.is_empty());
+    }
+
+    #[test]
+    fn test_single_step_sequence_rejected() {
+        let mut seq = sequence("1h");
+        seq.steps.truncate(1);
+        assert!(validate_sequence(&seq).is_err());
+    }
+}
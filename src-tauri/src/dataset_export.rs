@@ -0,0 +1,209 @@
+//! Dataset export of normalized logs for offline analysis tooling.
+//!
+//! Events are flattened into a single-level record (nested keys joined with
+//! dots), enriched with derived columns, and written to CSV or Parquet via
+//! DuckDB's `COPY ... TO` so the output loads directly into pandas & co.
+//!
+//! Derived columns:
+//! - `_record_index`: position of the event in the source file
+//! - `_timestamp`: event time normalized to RFC 3339 UTC (null if unknown)
+//! - `_log_type`: log format the file was parsed as
+//! - `_source_file`: source filename
+//!
+//! With anonymization options, selected fields are pseudonymized before
+//! flattening (see `anonymize`).
+
+use duckdb::Connection;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::Write;
+
+use crate::anonymize::{AnonymizeOptions, Pseudonymizer};
+use crate::db_engine;
+use crate::event_time;
+use crate::models::{LogType, SiemError};
+
+/// Output format for dataset export.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DatasetFormat {
+    Csv,
+    Parquet,
+}
+
+/// Summary of a dataset export.
+#[derive(Debug, Serialize, Clone)]
+pub struct DatasetExportSummary {
+    /// Destination file path
+    pub dest_path: String,
+    /// Number of rows written
+    pub row_count: usize,
+    /// Number of distinct columns across all rows
+    pub column_count: usize,
+}
+
+/// Flatten nested JSON objects into dot-separated keys.
+/// Arrays are kept as JSON-encoded strings so every column is a scalar.
+pub fn flatten_json(
+    value: &serde_json::Value,
+    prefix: &str,
+    out: &mut serde_json::Map<String, serde_json::Value>,
+) {
+    match value {
+        serde_json::Value::Object(map) => {
+            for (key, val) in map {
+                let path = if prefix.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{}.{}", prefix, key)
+                };
+                flatten_json(val, &path, out);
+            }
+        }
+        serde_json::Value::Array(_) => {
+            out.insert(
+                prefix.to_string(),
+                serde_json::Value::String(value.to_string()),
+            );
+        }
+        _ => {
+            out.insert(prefix.to_string(), value.clone());
+        }
+    }
+}
+
+/// Normalize a single event into a flat record with derived columns.
+pub fn normalize_event(
+    event: &serde_json::Value,
+    record_index: usize,
+    log_type: &LogType,
+    source_file: &str,
+) -> serde_json::Map<String, serde_json::Value> {
+    let mut record = serde_json::Map::new();
+
+    record.insert("_record_index".to_string(), record_index.into());
+    record.insert(
+        "_timestamp".to_string(),
+        event_time::event_time(event, None, log_type)
+            .map(|ts| serde_json::Value::String(ts.to_rfc3339()))
+            .unwrap_or(serde_json::Value::Null),
+    );
+    record.insert(
+        "_log_type".to_string(),
+        serde_json::to_value(log_type).unwrap_or(serde_json::Value::Null),
+    );
+    record.insert("_source_file".to_string(), source_file.into());
+
+    flatten_json(event, "", &mut record);
+    record
+}
+
+/// Export a log file as a normalized CSV or Parquet dataset.
+pub fn export_dataset(
+    conn: &Connection,
+    log_path: &str,
+    log_type: LogType,
+    dest_path: &str,
+    format: DatasetFormat,
+    anonymize: Option<&AnonymizeOptions>,
+) -> Result<DatasetExportSummary, SiemError> {
+    let mut events = db_engine::load_all_events(conn, log_path, log_type.clone())?;
+
+    if let Some(options) = anonymize {
+        let pseudonymizer = Pseudonymizer::new(options)?;
+        for event in &mut events {
+            pseudonymizer.apply_event(event);
+        }
+    }
+
+    let source_file = std::path::Path::new(log_path)
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or(log_path)
+        .to_string();
+
+    // Stage normalized records as NDJSON so DuckDB can infer a unified schema
+    let staging_path = std::env::temp_dir().join(format!(
+        "offline_siem_export_{}.ndjson",
+        uuid::Uuid::new_v4()
+    ));
+    let mut columns = std::collections::HashSet::new();
+
+    {
+        let file = fs::File::create(&staging_path)
+            .map_err(|e| SiemError::FileIO(format!("Cannot create staging file: {}", e)))?;
+        let mut writer = std::io::BufWriter::new(file);
+
+        for (index, event) in events.iter().enumerate() {
+            let record = normalize_event(event, index, &log_type, &source_file);
+            columns.extend(record.keys().cloned());
+
+            let line = serde_json::to_string(&record)
+                .map_err(|e| SiemError::Serialization(format!("Cannot serialize event: {}", e)))?;
+            writeln!(writer, "{}", line)
+                .map_err(|e| SiemError::FileIO(format!("Cannot write staging file: {}", e)))?;
+        }
+
+        writer
+            .flush()
+            .map_err(|e| SiemError::FileIO(format!("Cannot write staging file: {}", e)))?;
+    }
+
+    let format_clause = match format {
+        DatasetFormat::Csv => "FORMAT CSV, HEADER",
+        DatasetFormat::Parquet => "FORMAT PARQUET",
+    };
+
+    let query = format!(
+        "COPY (SELECT * FROM read_json_auto({}, format = 'newline_delimited', sample_size = -1)) TO {} ({})",
+        db_engine::sql_string(&staging_path.to_string_lossy()),
+        db_engine::sql_string(dest_path),
+        format_clause
+    );
+
+    let result = conn
+        .execute_batch(&query)
+        .map_err(|e| SiemError::Query(format!("Failed to export dataset: {}", e)));
+
+    let _ = fs::remove_file(&staging_path);
+    result?;
+
+    Ok(DatasetExportSummary {
+        dest_path: dest_path.to_string(),
+        row_count: events.len(),
+        column_count: columns.len(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_flatten_json() {
+        let event = serde_json::json!({
+            "eventName": "AssumeRole",
+            "userIdentity": { "type": "IAMUser", "sessionContext": { "mfa": true } },
+            "resources": [{ "ARN": "arn:aws:iam::1:role/x" }]
+        });
+
+        let mut out = serde_json::Map::new();
+        flatten_json(&event, "", &mut out);
+
+        assert_eq!(out["eventName"], "AssumeRole");
+        assert_eq!(out["userIdentity.type"], "IAMUser");
+        assert_eq!(out["userIdentity.sessionContext.mfa"], true);
+        assert_eq!(out["resources"], r#"[{"ARN":"arn:aws:iam::1:role/x"}]"#);
+    }
+
+    #[test]
+    fn test_normalize_event_derived_columns() {
+        let event = serde_json::json!({ "eventTime": "2025-12-16T11:05:00Z" });
+        let record = normalize_event(&event, 7, &LogType::CloudTrail, "trail.json");
+
+        assert_eq!(record["_record_index"], 7);
+        assert_eq!(record["_timestamp"], "2025-12-16T11:05:00+00:00");
+        assert_eq!(record["_log_type"], "cloudtrail");
+        assert_eq!(record["_source_file"], "trail.json");
+    }
+}
@@ -1,8 +1,24 @@
+use duckdb::arrow::datatypes::DataType;
+use duckdb::types::{TimeUnit, Value as DuckValue};
 use duckdb::Connection;
+use serde::de::{DeserializeSeed, Deserializer, IgnoredAny, MapAccess, SeqAccess, Visitor};
 use serde_json;
-use std::collections::HashMap;
-
-use crate::models::{LogType, SiemError};
+use std::io::{BufRead, BufReader};
+
+use crate::app_data;
+use crate::azure;
+use crate::correlation;
+use crate::evtx_xml;
+use crate::kv_log;
+use crate::models::{DetectionLogic, DetectionType, LogType, SiemError};
+use crate::okta;
+use crate::parser_plugins;
+use crate::safe_regex;
+use crate::script_rules;
+use crate::suricata;
+use crate::term_sets;
+use crate::text_parser;
+use crate::windows_events;
 
 /// Create a new in-memory DuckDB connection.
 pub fn create_connection() -> Result<Connection, SiemError> {
@@ -10,58 +26,275 @@ pub fn create_connection() -> Result<Connection, SiemError> {
         .map_err(|e| SiemError::Query(format!("Failed to create database connection: {}", e)))
 }
 
-/// Execute a scan query against a log file using a rule's condition.
-pub fn execute_scan_query(
+/// Quote text as a SQL string literal. Every value or path spliced into SQL
+/// goes through here, so quotes in it can't end the literal.
+pub fn sql_string(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "''"))
+}
+
+/// Rows of a query with the result set's column names and DuckDB types.
+pub struct QueryRows {
+    pub columns: Vec<String>,
+    pub column_types: Vec<String>,
+    pub rows: Vec<serde_json::Value>,
+}
+
+/// Execute an ad-hoc SQL query against log files. Only read-only queries are
+/// accepted (see `ensure_read_only`).
+pub fn execute_adhoc_query(conn: &Connection, query: &str) -> Result<QueryRows, SiemError> {
+    ensure_read_only(query)?;
+    execute_and_collect(conn, query)
+}
+
+/// Largest page `execute_paged_query` returns.
+pub const MAX_PAGE_SIZE: usize = 10_000;
+
+/// Execute an ad-hoc query and return one page of its rows, plus whether more
+/// rows follow. Only that page (and one lookahead row) is fetched from DuckDB.
+///
+/// Without an ORDER BY, pages follow DuckDB's scan order, which is stable
+/// for files and tables that don't change between calls.
+pub fn execute_paged_query(
     conn: &Connection,
-    log_path: &str,
-    condition: &str,
+    query: &str,
+    offset: usize,
     limit: usize,
-    log_type: LogType,
-) -> Result<Vec<serde_json::Value>, SiemError> {
-    match log_type {
-        LogType::CloudTrail => {
-            // For CloudTrail, load all events and filter in Rust
-            let all_events = load_all_events(conn, log_path, log_type)?;
-
-            // Simple filtering - check if condition matches
-            // For now, we'll use a basic approach: convert to SQL-like matching
-            let filtered: Vec<serde_json::Value> = all_events
-                .into_iter()
-                .filter(|event| matches_condition(event, condition))
-                .take(limit)
-                .collect();
+) -> Result<(QueryRows, bool), SiemError> {
+    ensure_read_only(query)?;
+    let limit = limit.clamp(1, MAX_PAGE_SIZE);
+    let inner = query.trim().trim_end_matches(';').trim_end();
+    let paged = format!(
+        "SELECT * FROM ({}) AS page LIMIT {} OFFSET {}",
+        inner,
+        limit + 1,
+        offset
+    );
 
-            Ok(filtered)
-        }
-        LogType::FlatJson => {
-            // For flat JSON, load all events and filter in Rust (same as CloudTrail)
-            let all_events = load_all_events(conn, log_path, log_type)?;
+    let mut results = execute_and_collect(conn, &paged)?;
+    let has_more = results.rows.len() > limit;
+    results.rows.truncate(limit);
+    Ok((results, has_more))
+}
 
-            // Filter events using the condition
-            let filtered: Vec<serde_json::Value> = all_events
-                .into_iter()
-                .filter(|event| matches_condition(event, condition))
-                .take(limit)
-                .collect();
+/// Statements an ad-hoc query may be.
+const READ_ONLY_STATEMENTS: [&str; 11] = [
+    "SELECT",
+    "WITH",
+    "FROM",
+    "VALUES",
+    "TABLE",
+    "DESCRIBE",
+    "SHOW",
+    "SUMMARIZE",
+    "PIVOT",
+    "UNPIVOT",
+    "EXPLAIN",
+];
+
+/// Keywords of statements that change the database, its settings or files;
+/// rejected anywhere in an ad-hoc query, as nested statements could use them.
+const WRITE_KEYWORDS: [&str; 19] = [
+    "ALTER",
+    "ATTACH",
+    "CALL",
+    "CHECKPOINT",
+    "COPY",
+    "CREATE",
+    "DELETE",
+    "DETACH",
+    "DROP",
+    "EXPORT",
+    "IMPORT",
+    "INSERT",
+    "INSTALL",
+    "LOAD",
+    "MERGE",
+    "PRAGMA",
+    "TRUNCATE",
+    "UPDATE",
+    "VACUUM",
+];
+
+/// Functions that run SQL given as text, which would get around the checks.
+const SQL_TEXT_FUNCTIONS: [&str; 2] = ["QUERY", "QUERY_TABLE"];
+
+/// Reject ad-hoc queries that could change anything.
+///
+/// Queries come straight from the webview and run on the app's connections
+/// (including the ingestion cache), so only a single read-only statement is
+/// allowed: SELECT and friends, with no write keyword anywhere outside string
+/// literals and comments. A column named like a keyword (e.g. `update`) must
+/// be double-quoted.
+pub fn ensure_read_only(query: &str) -> Result<(), SiemError> {
+    let tokens = sql_tokens(query);
+    let mut statements = tokens
+        .split(|token| *token == SqlToken::Semicolon)
+        .filter(|statement| !statement.is_empty());
+    let statement = statements
+        .next()
+        .ok_or_else(|| SiemError::Query("Query is empty".to_string()))?;
+    if statements.next().is_some() {
+        return Err(SiemError::Query(
+            "Only one statement can be run at a time".to_string(),
+        ));
+    }
+
+    // EXPLAIN [ANALYZE] runs the statement it explains, so check that one
+    let mut words = statement.iter().filter_map(|token| match token {
+        SqlToken::Word(word) => Some(word.as_str()),
+        _ => None,
+    });
+    let mut first = words.next().unwrap_or_default();
+    if first == "EXPLAIN" {
+        first = words.next().unwrap_or_default();
+        if first == "ANALYZE" {
+            first = words.next().unwrap_or_default();
+        }
+    }
+    if first == "EXPLAIN" || !READ_ONLY_STATEMENTS.contains(&first) {
+        return Err(SiemError::Query(format!(
+            "{} statements are not allowed: queries are read-only",
+            first
+        )));
+    }
 
-            Ok(filtered)
+    for (i, token) in statement.iter().enumerate() {
+        let (name, quoted) = match token {
+            SqlToken::Word(word) => (word.as_str(), false),
+            SqlToken::QuotedName(name) => (name.as_str(), true),
+            _ => continue,
+        };
+        if !quoted && WRITE_KEYWORDS.contains(&name) {
+            return Err(SiemError::Query(format!(
+                "{} is not allowed: queries are read-only",
+                name
+            )));
+        }
+        if SQL_TEXT_FUNCTIONS.contains(&name.to_uppercase().as_str())
+            && statement.get(i + 1) == Some(&SqlToken::OpenParen)
+        {
+            return Err(SiemError::Query(format!(
+                "{}() is not allowed in queries",
+                name.to_lowercase()
+            )));
         }
     }
+
+    Ok(())
 }
 
-/// Execute an ad-hoc SQL query against log files.
-pub fn execute_adhoc_query(
-    conn: &Connection,
-    query: &str,
-) -> Result<Vec<serde_json::Value>, SiemError> {
-    execute_and_collect(conn, query)
+/// SQL tokens relevant to `ensure_read_only`.
+#[derive(Debug, PartialEq)]
+enum SqlToken {
+    /// Keyword or bare identifier, uppercased
+    Word(String),
+    /// Double-quoted identifier
+    QuotedName(String),
+    OpenParen,
+    Semicolon,
+    Other,
+}
+
+/// Split SQL into tokens, dropping comments and string literals (including
+/// escape strings `E'...'` and dollar-quoted `$tag$...$tag$`).
+fn sql_tokens(sql: &str) -> Vec<SqlToken> {
+    let chars: Vec<char> = sql.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    // Index after a literal closed by `quote`, where a doubled quote (and a
+    // backslash, if `backslash_escapes`) escapes it
+    let skip_quoted = |mut i: usize, quote: char, backslash_escapes: bool| -> usize {
+        while i < chars.len() {
+            if backslash_escapes && chars[i] == '\\' {
+                i += 2;
+            } else if chars[i] == quote {
+                if chars.get(i + 1) == Some(&quote) {
+                    i += 2;
+                } else {
+                    return i + 1;
+                }
+            } else {
+                i += 1;
+            }
+        }
+        i
+    };
+
+    while i < chars.len() {
+        let c = chars[i];
+        let next = chars.get(i + 1).copied();
+        match c {
+            _ if c.is_whitespace() => i += 1,
+            '-' if next == Some('-') => {
+                while i < chars.len() && chars[i] != '\n' {
+                    i += 1;
+                }
+            }
+            '/' if next == Some('*') => {
+                i += 2;
+                while i < chars.len() && !(chars[i] == '*' && chars.get(i + 1) == Some(&'/')) {
+                    i += 1;
+                }
+                i += 2;
+            }
+            '\'' => i = skip_quoted(i + 1, '\'', false),
+            '"' => {
+                let end = skip_quoted(i + 1, '"', false);
+                let name: String = chars[i + 1..end.saturating_sub(1).max(i + 1)]
+                    .iter()
+                    .collect();
+                tokens.push(SqlToken::QuotedName(name.replace("\"\"", "\"")));
+                i = end;
+            }
+            '$' if next.is_some_and(|n| n == '$' || n.is_alphabetic() || n == '_') => {
+                let tag_end = (i + 1..chars.len())
+                    .find(|&j| !(chars[j].is_alphanumeric() || chars[j] == '_'));
+                match tag_end {
+                    Some(tag_end) if chars[tag_end] == '$' => {
+                        let delimiter = &chars[i..=tag_end];
+                        i = (tag_end + 1..chars.len())
+                            .find(|&j| chars[j..].starts_with(delimiter))
+                            .map_or(chars.len(), |j| j + delimiter.len());
+                    }
+                    _ => {
+                        tokens.push(SqlToken::Other);
+                        i += 1;
+                    }
+                }
+            }
+            '(' => {
+                tokens.push(SqlToken::OpenParen);
+                i += 1;
+            }
+            ';' => {
+                tokens.push(SqlToken::Semicolon);
+                i += 1;
+            }
+            _ if c.is_alphanumeric() || c == '_' => {
+                let start = i;
+                while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                    i += 1;
+                }
+                let word: String = chars[start..i].iter().collect();
+                if word.eq_ignore_ascii_case("e") && chars.get(i) == Some(&'\'') {
+                    i = skip_quoted(i + 1, '\'', true);
+                } else {
+                    tokens.push(SqlToken::Word(word.to_uppercase()));
+                }
+            }
+            _ => {
+                tokens.push(SqlToken::Other);
+                i += 1;
+            }
+        }
+    }
+
+    tokens
 }
 
 /// Helper to execute a query and collect results as JSON.
-fn execute_and_collect(
-    conn: &Connection,
-    query: &str,
-) -> Result<Vec<serde_json::Value>, SiemError> {
+fn execute_and_collect(conn: &Connection, query: &str) -> Result<QueryRows, SiemError> {
     let mut stmt = conn
         .prepare(query)
         .map_err(|e| SiemError::Query(format!("Failed to prepare query: {}", e)))?;
@@ -70,53 +303,264 @@ fn execute_and_collect(
         .query([])
         .map_err(|e| SiemError::Query(format!("Failed to execute query: {}", e)))?;
 
+    // The result schema is known once the statement ran, even without rows
+    let (columns, column_types) = match rows.as_ref() {
+        Some(stmt) => {
+            let columns = stmt.column_names();
+            let column_types = (0..columns.len())
+                .map(|i| sql_type_name(&stmt.column_type(i)))
+                .collect();
+            (columns, column_types)
+        }
+        None => (vec![], vec![]),
+    };
+
     let mut results = Vec::new();
 
     while let Some(row) = rows
         .next()
         .map_err(|e| SiemError::Query(format!("Failed to fetch row: {}", e)))?
     {
-        let column_count = row.as_ref().column_count();
-        let mut map = HashMap::new();
+        let mut map = serde_json::Map::new();
 
-        for i in 0..column_count {
-            let column_name = row
-                .as_ref()
-                .column_name(i)
-                .ok()
-                .cloned()
-                .unwrap_or_else(|| format!("column_{}", i));
-
-            // Get value as string first, then parse to JSON
-            let value: serde_json::Value = row
-                .get::<_, String>(i)
-                .ok()
-                .and_then(|s| serde_json::from_str(&s).ok())
-                .unwrap_or(serde_json::Value::Null);
+        for (i, column_name) in columns.iter().enumerate() {
+            let value = row
+                .get::<_, DuckValue>(i)
+                .map(|value| json_value(&value))
+                .map_err(|e| SiemError::Query(format!("Failed to fetch row: {}", e)))?;
 
-            map.insert(column_name, value);
+            map.insert(column_name.clone(), value);
         }
 
-        results.push(serde_json::to_value(map).unwrap());
+        results.push(serde_json::Value::Object(map));
+    }
+
+    Ok(QueryRows {
+        columns,
+        column_types,
+        rows: results,
+    })
+}
+
+/// Convert a DuckDB value to JSON, keeping its type.
+///
+/// Numbers that don't fit a JSON number (NaN, 128-bit integers out of range)
+/// become strings, as do temporal values (ISO 8601) and blobs (hex). Text is
+/// kept as is, except JSON objects and arrays (JSON columns such as `event`
+/// arrive as text), which are decoded.
+fn json_value(value: &DuckValue) -> serde_json::Value {
+    use serde_json::Value as Json;
+
+    let float = |f: f64| {
+        serde_json::Number::from_f64(f).map_or_else(|| Json::String(f.to_string()), Json::Number)
+    };
+    let integer = |i: i128| match (i64::try_from(i), u64::try_from(i)) {
+        (Ok(i), _) => Json::from(i),
+        (_, Ok(u)) => Json::from(u),
+        _ => Json::String(i.to_string()),
+    };
+
+    match value {
+        DuckValue::Null => Json::Null,
+        DuckValue::Boolean(b) => Json::Bool(*b),
+        DuckValue::TinyInt(i) => Json::from(*i),
+        DuckValue::SmallInt(i) => Json::from(*i),
+        DuckValue::Int(i) => Json::from(*i),
+        DuckValue::BigInt(i) => Json::from(*i),
+        DuckValue::HugeInt(i) => integer(*i),
+        DuckValue::UTinyInt(u) => Json::from(*u),
+        DuckValue::USmallInt(u) => Json::from(*u),
+        DuckValue::UInt(u) => Json::from(*u),
+        DuckValue::UBigInt(u) => Json::from(*u),
+        DuckValue::Float(f) => float(*f as f64),
+        DuckValue::Double(f) => float(*f),
+        DuckValue::Decimal(d) => d
+            .to_string()
+            .parse::<f64>()
+            .map_or_else(|_| Json::String(d.to_string()), float),
+        DuckValue::Timestamp(unit, t) => {
+            let micros = match unit {
+                TimeUnit::Second => t.saturating_mul(1_000_000),
+                TimeUnit::Millisecond => t.saturating_mul(1_000),
+                TimeUnit::Microsecond => *t,
+                TimeUnit::Nanosecond => t / 1_000,
+            };
+            chrono::DateTime::from_timestamp_micros(micros).map_or(Json::Null, |ts| {
+                Json::String(ts.to_rfc3339_opts(chrono::SecondsFormat::AutoSi, true))
+            })
+        }
+        DuckValue::Date32(days) => chrono::NaiveDate::from_ymd_opt(1970, 1, 1)
+            .and_then(|epoch| epoch.checked_add_signed(chrono::Duration::days(*days as i64)))
+            .map_or(Json::Null, |date| Json::String(date.to_string())),
+        DuckValue::Time64(unit, t) => {
+            let nanos = match unit {
+                TimeUnit::Second => t.saturating_mul(1_000_000_000),
+                TimeUnit::Millisecond => t.saturating_mul(1_000_000),
+                TimeUnit::Microsecond => t.saturating_mul(1_000),
+                TimeUnit::Nanosecond => *t,
+            };
+            chrono::NaiveTime::from_num_seconds_from_midnight_opt(
+                (nanos / 1_000_000_000) as u32,
+                (nanos % 1_000_000_000) as u32,
+            )
+            .map_or(Json::Null, |time| Json::String(time.to_string()))
+        }
+        DuckValue::Interval {
+            months,
+            days,
+            nanos,
+        } => Json::String(format!("{} months {} days {} ns", months, days, nanos)),
+        DuckValue::Text(text) => {
+            let trimmed = text.trim_start();
+            if trimmed.starts_with('{') || trimmed.starts_with('[') {
+                if let Ok(json) = serde_json::from_str(text) {
+                    return json;
+                }
+            }
+            Json::String(text.clone())
+        }
+        DuckValue::Enum(text) => Json::String(text.clone()),
+        DuckValue::Blob(bytes) => Json::String(app_data::hex(bytes)),
+        DuckValue::List(values) | DuckValue::Array(values) => {
+            Json::Array(values.iter().map(json_value).collect())
+        }
+        DuckValue::Struct(fields) => Json::Object(
+            fields
+                .iter()
+                .map(|(name, value)| (name.clone(), json_value(value)))
+                .collect(),
+        ),
+        DuckValue::Map(entries) => Json::Object(
+            entries
+                .iter()
+                .map(|(key, value)| {
+                    let key = match json_value(key) {
+                        Json::String(key) => key,
+                        other => other.to_string(),
+                    };
+                    (key, json_value(value))
+                })
+                .collect(),
+        ),
+        DuckValue::Union(value) => json_value(value),
     }
+}
 
-    Ok(results)
+/// DuckDB SQL name of a result column type.
+fn sql_type_name(data_type: &DataType) -> String {
+    let name = match data_type {
+        DataType::Null => "NULL",
+        DataType::Boolean => "BOOLEAN",
+        DataType::Int8 => "TINYINT",
+        DataType::Int16 => "SMALLINT",
+        DataType::Int32 => "INTEGER",
+        DataType::Int64 => "BIGINT",
+        DataType::UInt8 => "UTINYINT",
+        DataType::UInt16 => "USMALLINT",
+        DataType::UInt32 => "UINTEGER",
+        DataType::UInt64 => "UBIGINT",
+        DataType::Float16 | DataType::Float32 => "FLOAT",
+        DataType::Float64 => "DOUBLE",
+        DataType::Decimal128(precision, scale) | DataType::Decimal256(precision, scale) => {
+            return format!("DECIMAL({},{})", precision, scale)
+        }
+        DataType::Utf8 | DataType::LargeUtf8 => "VARCHAR",
+        DataType::Binary | DataType::LargeBinary | DataType::FixedSizeBinary(_) => "BLOB",
+        DataType::Date32 | DataType::Date64 => "DATE",
+        DataType::Time32(_) | DataType::Time64(_) => "TIME",
+        DataType::Timestamp(_, None) => "TIMESTAMP",
+        DataType::Timestamp(_, Some(_)) => "TIMESTAMP WITH TIME ZONE",
+        DataType::Interval(_) | DataType::Duration(_) => "INTERVAL",
+        DataType::List(field) | DataType::LargeList(field) | DataType::FixedSizeList(field, _) => {
+            return format!("{}[]", sql_type_name(field.data_type()))
+        }
+        DataType::Struct(_) => "STRUCT",
+        DataType::Map(_, _) => "MAP",
+        DataType::Union(_, _) => "UNION",
+        DataType::Dictionary(_, value) => return sql_type_name(value),
+        other => return format!("{:?}", other).to_uppercase(),
+    };
+    name.to_string()
 }
 
 /// Auto-detect log type based on file content.
 /// Returns CloudTrail if file has "Records" array at root level,
+/// Suricata if its first line is an EVE record (with `event_type`),
+/// AzureActivity/EntraSignIn if its records are Azure Activity Log or
+/// Entra ID sign-in records, Okta if they are Okta System Log events,
+/// Windows if they are exported Windows events, WindowsXml if it is an XML
+/// export of Windows events,
+/// Kv if it isn't JSON but its first line is `key=value` pairs,
 /// otherwise returns FlatJson.
 pub fn detect_log_type(log_path: &str) -> Result<LogType, SiemError> {
+    // Text logs are recognized by the file patterns of the text parsers
+    if let Some(log_type) = text_parser::log_type_for_file(log_path) {
+        return Ok(log_type);
+    }
+
     let file_content = std::fs::read_to_string(log_path)
         .map_err(|e| SiemError::Query(format!("Failed to read log file: {}", e)))?;
 
+    if evtx_xml::is_event_xml(&file_content) {
+        return Ok(LogType::WindowsXml);
+    }
+
+    // Suricata EVE files are NDJSON of records with an event_type
+    let first_line = file_content.lines().find(|line| !line.trim().is_empty());
+    if first_line
+        .and_then(|line| serde_json::from_str::<serde_json::Value>(line).ok())
+        .is_some_and(|event| suricata::is_eve_record(&event))
+    {
+        return Ok(LogType::Suricata);
+    }
+    // Azure diagnostic exports may be NDJSON too
+    if let Some(log_type) = first_line
+        .and_then(|line| serde_json::from_str::<serde_json::Value>(line).ok())
+        .and_then(|record| azure::detect_record(&record))
+    {
+        return Ok(log_type);
+    }
+    if first_line
+        .and_then(|line| serde_json::from_str::<serde_json::Value>(line).ok())
+        .is_some_and(|event| okta::is_okta_event(&event))
+    {
+        return Ok(LogType::Okta);
+    }
+    if first_line
+        .and_then(|line| serde_json::from_str::<serde_json::Value>(line).ok())
+        .is_some_and(|event| windows_events::is_windows_event(&event))
+    {
+        return Ok(LogType::Windows);
+    }
+
     // Try to parse as JSON
-    let json: serde_json::Value = serde_json::from_str(&file_content)
-        .map_err(|e| SiemError::Query(format!("Failed to parse JSON: {}", e)))?;
+    let json: serde_json::Value = match serde_json::from_str(&file_content) {
+        Ok(json) => json,
+        Err(_) if first_line.is_some_and(kv_log::looks_like_kv) => return Ok(LogType::Kv),
+        Err(e) => return Err(SiemError::Query(format!("Failed to parse JSON: {}", e))),
+    };
 
     // Check if it has "Records" array at root level (CloudTrail format)
     if json.get("Records").and_then(|r| r.as_array()).is_some() {
         Ok(LogType::CloudTrail)
+    } else if let Some(log_type) = azure::export_records(&json)
+        .and_then(|records| records.first())
+        .and_then(azure::detect_record)
+    {
+        Ok(log_type)
+    } else if json
+        .as_array()
+        .and_then(|events| events.first())
+        .is_some_and(okta::is_okta_event)
+    {
+        Ok(LogType::Okta)
+    } else if json
+        .as_array()
+        .and_then(|events| events.first())
+        .or(Some(&json))
+        .is_some_and(windows_events::is_windows_event)
+    {
+        Ok(LogType::Windows)
     } else {
         // Otherwise treat as FlatJson
         Ok(LogType::FlatJson)
@@ -129,69 +573,428 @@ pub fn load_all_events(
     log_path: &str,
     log_type: LogType,
 ) -> Result<Vec<serde_json::Value>, SiemError> {
+    let mut events = Vec::new();
+    stream_events(log_path, &log_type, STREAM_CHUNK_SIZE, |chunk| {
+        events.extend(chunk)
+    })?;
+    Ok(events)
+}
+
+/// Number of events handed over at once when streaming a log file.
+pub const STREAM_CHUNK_SIZE: usize = 10_000;
+
+/// Read the events of a log file in chunks of at most `chunk_size` events,
+/// without holding the whole file in memory. Returns the number of events.
+///
+/// CloudTrail files are parsed incrementally, yielding the `Records` array
+/// element by element, and so are Azure, Okta and Windows exports (a root
+/// array, or Azure's `value`/`records` array); Windows events are normalized
+/// (see `windows_events`), as are those of Windows XML exports, read element
+/// by element (see `evtx_xml`). Flat JSON and Suricata EVE files are read line
+/// by line as NDJSON; a file whose first line is not valid JSON is read whole, as
+/// a single (pretty-printed) JSON object. Key=value files are read line by
+/// line. Plugin log types are read through their parser plugin, text log types
+/// line by line through their text parser.
+pub fn stream_events<F>(
+    log_path: &str,
+    log_type: &LogType,
+    chunk_size: usize,
+    mut on_chunk: F,
+) -> Result<usize, SiemError>
+where
+    F: FnMut(Vec<serde_json::Value>),
+{
+    let file = std::fs::File::open(log_path)
+        .map_err(|e| SiemError::Query(format!("Failed to read log file: {}", e)))?;
+    let reader = BufReader::new(file);
+    let chunk_size = chunk_size.max(1);
+
     match log_type {
         LogType::CloudTrail => {
-            // For CloudTrail, we need to parse the JSON file and extract the Records array
-            // DuckDB's UNNEST doesn't work well with nested JSON
-            let file_content = std::fs::read_to_string(log_path)
-                .map_err(|e| SiemError::Query(format!("Failed to read log file: {}", e)))?;
-
-            let json: serde_json::Value = serde_json::from_str(&file_content)
+            // Walk the root object and stream its Records array
+            let mut deserializer = serde_json::Deserializer::from_reader(reader);
+            let total = (&mut deserializer)
+                .deserialize_map(WrappedRecords {
+                    keys: &["Records"],
+                    chunk_size,
+                    on_chunk: &mut on_chunk,
+                })
+                .and_then(|total| deserializer.end().map(|_| total))
                 .map_err(|e| SiemError::Query(format!("Failed to parse JSON: {}", e)))?;
 
-            // Extract Records array
-            if let Some(records) = json.get("Records").and_then(|r| r.as_array()) {
-                Ok(records.clone())
-            } else {
-                Err(SiemError::Query(
-                    "CloudTrail file must have 'Records' array".to_string(),
-                ))
-            }
-        }
-        LogType::FlatJson => {
-            // For flat JSON, support both formats:
-            // 1. NDJSON (newline-delimited JSON): each line is a separate JSON object
-            // 2. Single JSON object: entire file is one event
-            let file_content = std::fs::read_to_string(log_path)
-                .map_err(|e| SiemError::Query(format!("Failed to read log file: {}", e)))?;
-
-            // Try to parse as single JSON object first
-            if let Ok(single_event) = serde_json::from_str::<serde_json::Value>(&file_content) {
-                // Check if it's an object (not array)
-                if single_event.is_object() {
-                    // Single JSON object - return as single event
-                    return Ok(vec![single_event]);
+            total.ok_or_else(|| {
+                SiemError::Query("CloudTrail file must have 'Records' array".to_string())
+            })
+        }
+        LogType::FlatJson | LogType::Suricata => {
+            stream_flat_json(reader, chunk_size, &mut on_chunk)
+        }
+        LogType::AzureActivity | LogType::EntraSignIn => stream_json_export(
+            log_path,
+            reader,
+            azure::RECORD_KEYS,
+            chunk_size,
+            &mut on_chunk,
+        ),
+        LogType::Okta => stream_json_export(log_path, reader, &[], chunk_size, &mut on_chunk),
+        LogType::Windows => stream_json_export(log_path, reader, &[], chunk_size, &mut |chunk| {
+            on_chunk(chunk.into_iter().map(windows_events::normalize).collect())
+        }),
+        LogType::WindowsXml => evtx_xml::stream_events(reader, chunk_size, &mut on_chunk),
+        LogType::Kv => kv_log::stream_events(reader, chunk_size, &mut on_chunk),
+        LogType::Plugin(name) => {
+            parser_plugins::stream_events(name, reader, chunk_size, &mut on_chunk)
+        }
+        LogType::Text(name) => {
+            text_parser::compiled_parser(name)?.stream_events(reader, chunk_size, &mut on_chunk)
+        }
+    }
+}
+
+/// Stream a flat JSON file: NDJSON (one event per line) or a single JSON object.
+fn stream_flat_json<R, F>(
+    reader: R,
+    chunk_size: usize,
+    on_chunk: &mut F,
+) -> Result<usize, SiemError>
+where
+    R: BufRead,
+    F: FnMut(Vec<serde_json::Value>),
+{
+    let read_error =
+        |e: std::io::Error| SiemError::Query(format!("Failed to read log file: {}", e));
+
+    let mut lines = reader.lines();
+    let mut chunk = Vec::new();
+    let mut total = 0;
+
+    while let Some(line) = lines.next() {
+        let line = line.map_err(read_error)?;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let event = match serde_json::from_str::<serde_json::Value>(&line) {
+            Ok(event) => event,
+            Err(e) if total == 0 => {
+                // Not NDJSON: read the rest and parse the file as a whole
+                let mut content = line;
+                for rest in lines {
+                    content.push('\n');
+                    content.push_str(&rest.map_err(read_error)?);
+                }
+                let events = parse_whole_flat_json(&content, e);
+                if events.is_empty() {
+                    break;
+                }
+                total = events.len();
+                for events in events.chunks(chunk_size) {
+                    on_chunk(events.to_vec());
                 }
+                return Ok(total);
             }
+            Err(e) => {
+                eprintln!("Warning: Failed to parse line: {}", e);
+                continue;
+            }
+        };
 
-            // If not a single object, try parsing as NDJSON
-            let events: Vec<serde_json::Value> = file_content
-                .lines()
-                .filter(|line| !line.trim().is_empty())
-                .filter_map(|line| {
-                    serde_json::from_str(line)
-                        .map_err(|e| eprintln!("Warning: Failed to parse line: {}", e))
-                        .ok()
-                })
-                .collect();
+        chunk.push(event);
+        total += 1;
+        if chunk.len() >= chunk_size {
+            on_chunk(std::mem::take(&mut chunk));
+        }
+    }
+
+    if !chunk.is_empty() {
+        on_chunk(chunk);
+    }
+
+    if total == 0 {
+        return Err(SiemError::Query(
+            "No valid JSON objects found in file".to_string(),
+        ));
+    }
+
+    Ok(total)
+}
+
+/// Parse a flat JSON file that is not line-delimited.
+/// A single JSON object is one event; otherwise lines that parse are kept.
+fn parse_whole_flat_json(
+    content: &str,
+    first_line_error: serde_json::Error,
+) -> Vec<serde_json::Value> {
+    if let Ok(single_event) = serde_json::from_str::<serde_json::Value>(content) {
+        if single_event.is_object() {
+            return vec![single_event];
+        }
+    }
+
+    eprintln!("Warning: Failed to parse line: {}", first_line_error);
+    content
+        .lines()
+        .skip(1)
+        .filter(|line| !line.trim().is_empty())
+        .filter_map(|line| {
+            serde_json::from_str(line)
+                .map_err(|e| eprintln!("Warning: Failed to parse line: {}", e))
+                .ok()
+        })
+        .collect()
+}
+
+/// Stream an API export: a root array of records, an object wrapping them
+/// under one of `keys` (e.g. Azure's `value` or `records`), or NDJSON records.
+fn stream_json_export<F>(
+    log_path: &str,
+    mut reader: BufReader<std::fs::File>,
+    keys: &'static [&'static str],
+    chunk_size: usize,
+    on_chunk: &mut F,
+) -> Result<usize, SiemError>
+where
+    F: FnMut(Vec<serde_json::Value>),
+{
+    let parse_error =
+        |e: serde_json::Error| SiemError::Query(format!("Failed to parse JSON: {}", e));
+    let first_byte = reader
+        .fill_buf()
+        .map_err(|e| SiemError::Query(format!("Failed to read log file: {}", e)))?
+        .iter()
+        .find(|b| !b.is_ascii_whitespace())
+        .copied();
+
+    match first_byte {
+        Some(b'[') => {
+            let mut deserializer = serde_json::Deserializer::from_reader(reader);
+            RecordChunks {
+                chunk_size,
+                on_chunk,
+            }
+            .deserialize(&mut deserializer)
+            .and_then(|total| deserializer.end().map(|_| total))
+            .map_err(parse_error)
+        }
+        Some(b'{') => {
+            let mut deserializer = serde_json::Deserializer::from_reader(reader);
+            let wrapped = (&mut deserializer).deserialize_map(WrappedRecords {
+                keys,
+                chunk_size,
+                on_chunk: &mut *on_chunk,
+            });
+            match wrapped {
+                Ok(Some(total)) => deserializer.end().map(|_| total).map_err(parse_error),
+                // Not a wrapper: the records themselves, one per line
+                Ok(None) => {
+                    let file = std::fs::File::open(log_path)
+                        .map_err(|e| SiemError::Query(format!("Failed to read log file: {}", e)))?;
+                    stream_flat_json(BufReader::new(file), chunk_size, on_chunk)
+                }
+                Err(e) => Err(parse_error(e)),
+            }
+        }
+        _ => stream_flat_json(reader, chunk_size, on_chunk),
+    }
+}
+
+/// Visitor over a root object, streaming the array under the first of `keys`
+/// it has (CloudTrail's `Records`). Yields the number of records, or None if
+/// the object has no such array.
+struct WrappedRecords<'f, F> {
+    keys: &'static [&'static str],
+    chunk_size: usize,
+    on_chunk: &'f mut F,
+}
+
+impl<'de, F> Visitor<'de> for WrappedRecords<'_, F>
+where
+    F: FnMut(Vec<serde_json::Value>),
+{
+    type Value = Option<usize>;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str("an object with a records array")
+    }
 
-            if events.is_empty() {
-                return Err(SiemError::Query(
-                    "No valid JSON objects found in file".to_string(),
-                ));
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let mut total = None;
+        while let Some(key) = map.next_key::<String>()? {
+            if self.keys.contains(&key.as_str()) && total.is_none() {
+                total = Some(map.next_value_seed(RecordChunks {
+                    chunk_size: self.chunk_size,
+                    on_chunk: &mut *self.on_chunk,
+                })?);
+            } else {
+                map.next_value::<IgnoredAny>()?;
             }
+        }
+        Ok(total)
+    }
+}
+
+/// Seed streaming the elements of a records array in chunks.
+/// Yields the number of records.
+struct RecordChunks<'f, F> {
+    chunk_size: usize,
+    on_chunk: &'f mut F,
+}
+
+impl<'de, F> DeserializeSeed<'de> for RecordChunks<'_, F>
+where
+    F: FnMut(Vec<serde_json::Value>),
+{
+    type Value = usize;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+impl<'de, F> Visitor<'de> for RecordChunks<'_, F>
+where
+    F: FnMut(Vec<serde_json::Value>),
+{
+    type Value = usize;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str("a records array")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut chunk = Vec::new();
+        let mut total = 0;
+        while let Some(event) = seq.next_element::<serde_json::Value>()? {
+            chunk.push(event);
+            total += 1;
+            if chunk.len() >= self.chunk_size {
+                (self.on_chunk)(std::mem::take(&mut chunk));
+            }
+        }
+        if !chunk.is_empty() {
+            (self.on_chunk)(chunk);
+        }
+        Ok(total)
+    }
+}
+
+/// Stream the CloudTrail records of a file accepted by any of the given SQL
+/// predicates (over a JSON column named `event`), filtered inside DuckDB.
+///
+/// Chunks hold each accepted record with one flag per predicate. Records are
+/// returned in file order and keep their original JSON.
+pub fn query_cloudtrail_records<F>(
+    conn: &Connection,
+    log_path: &str,
+    predicates: &[String],
+    chunk_size: usize,
+    on_chunk: F,
+) -> Result<(), SiemError>
+where
+    F: FnMut(Vec<(serde_json::Value, Vec<bool>)>),
+{
+    if predicates.is_empty() {
+        return Ok(());
+    }
+    let records = cloudtrail_records_query(log_path)?;
+    query_event_records(conn, &records, predicates, chunk_size, on_chunk)
+}
+
+/// Stream the rows of an event subquery (a JSON column named `event`)
+/// accepted by any of the given SQL predicates, with one flag per predicate.
+pub fn query_event_records<F>(
+    conn: &Connection,
+    records: &str,
+    predicates: &[String],
+    chunk_size: usize,
+    mut on_chunk: F,
+) -> Result<(), SiemError>
+where
+    F: FnMut(Vec<(serde_json::Value, Vec<bool>)>),
+{
+    if predicates.is_empty() {
+        return Ok(());
+    }
+
+    let flags: Vec<String> = predicates
+        .iter()
+        .map(|predicate| format!("coalesce({}, FALSE)", predicate))
+        .collect();
+    let query = format!(
+        "SELECT CAST(event AS VARCHAR), {} FROM ({}) WHERE {}",
+        flags.join(", "),
+        records,
+        flags.join(" OR ")
+    );
+
+    let mut stmt = conn
+        .prepare(&query)
+        .map_err(|e| SiemError::Query(format!("Failed to prepare query: {}", e)))?;
+    let mut rows = stmt
+        .query([])
+        .map_err(|e| SiemError::Query(format!("Failed to execute query: {}", e)))?;
 
-            Ok(events)
+    let chunk_size = chunk_size.max(1);
+    let mut chunk = Vec::new();
+    while let Some(row) = rows
+        .next()
+        .map_err(|e| SiemError::Query(format!("Failed to fetch row: {}", e)))?
+    {
+        let text: String = row
+            .get(0)
+            .map_err(|e| SiemError::Query(format!("Failed to fetch row: {}", e)))?;
+        let event = serde_json::from_str(&text)
+            .map_err(|e| SiemError::Query(format!("Failed to parse JSON: {}", e)))?;
+        let flags = (1..=predicates.len())
+            .map(|i| row.get::<_, bool>(i).unwrap_or(false))
+            .collect();
+
+        chunk.push((event, flags));
+        if chunk.len() >= chunk_size {
+            on_chunk(std::mem::take(&mut chunk));
         }
     }
+    if !chunk.is_empty() {
+        on_chunk(chunk);
+    }
+
+    Ok(())
+}
+
+/// Count the records of a CloudTrail file with DuckDB.
+pub fn count_cloudtrail_records(conn: &Connection, log_path: &str) -> Result<usize, SiemError> {
+    let query = format!(
+        "SELECT COUNT(*) FROM ({})",
+        cloudtrail_records_query(log_path)?
+    );
+    conn.query_row(&query, [], |row| row.get::<_, i64>(0))
+        .map(|count| count as usize)
+        .map_err(|e| SiemError::Query(format!("Failed to execute query: {}", e)))
+}
+
+/// Subquery yielding one row per CloudTrail record, as a JSON column `event`.
+pub fn cloudtrail_records_query(log_path: &str) -> Result<String, SiemError> {
+    Ok(format!(
+        "SELECT unnest(Records) AS event FROM read_json({}, columns = {{Records: 'JSON[]'}}, maximum_object_size = {})",
+        sql_string(log_path),
+        cloudtrail_object_size(log_path)?
+    ))
+}
+
+/// `maximum_object_size` for reading a CloudTrail file with DuckDB.
+/// The whole file is one JSON object, so the object size limit is the file size.
+pub fn cloudtrail_object_size(log_path: &str) -> Result<u64, SiemError> {
+    let size = std::fs::metadata(log_path)
+        .map_err(|e| SiemError::Query(format!("Failed to read log file: {}", e)))?
+        .len();
+    Ok((size + 1).clamp(16 * 1024 * 1024, u32::MAX as u64))
 }
 
 /// Validate that a log file exists and can be read by DuckDB.
 pub fn validate_log_file(conn: &Connection, log_path: &str) -> Result<bool, SiemError> {
-    let escaped_path = log_path.replace("'", "''");
     let query = format!(
-        "SELECT COUNT(*) FROM read_json_auto('{}') LIMIT 1",
-        escaped_path
+        "SELECT COUNT(*) FROM read_json_auto({}) LIMIT 1",
+        sql_string(log_path)
     );
 
     match conn.prepare(&query) {
@@ -203,11 +1006,82 @@ pub fn validate_log_file(conn: &Connection, log_path: &str) -> Result<bool, Siem
     }
 }
 
+/// How string comparisons in a condition treat letter case.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CaseMode {
+    /// =, !=, <> and IN compare exactly; CONTAINS, STARTSWITH, ENDSWITH and MATCH ignore case
+    Default,
+    /// Every string comparison is exact
+    Sensitive,
+    /// Every string comparison ignores case
+    Insensitive,
+}
+
+impl CaseMode {
+    /// Build from a rule's optional `case_sensitive` flag.
+    pub fn from_flag(case_sensitive: Option<bool>) -> Self {
+        match case_sensitive {
+            Some(true) => CaseMode::Sensitive,
+            Some(false) => CaseMode::Insensitive,
+            None => CaseMode::Default,
+        }
+    }
+
+    /// Whether an operator should fold case, given its behaviour in Default mode.
+    fn folds(self, default_fold: bool) -> bool {
+        match self {
+            CaseMode::Default => default_fold,
+            CaseMode::Sensitive => false,
+            CaseMode::Insensitive => true,
+        }
+    }
+}
+
+/// Check if an event matches a rule's detection logic, honoring its case sensitivity.
+///
+/// For sequence rules an event matches if it satisfies any step (and the
+/// rule's condition, when set); ordering is checked later by `correlation`.
+/// Script rules run their script (see `script_rules`).
+/// Events matching one of the rule's filters never match.
+pub fn matches_detection(event: &serde_json::Value, detection: &DetectionLogic) -> bool {
+    let case_mode = CaseMode::from_flag(detection.case_sensitive);
+
+    // Meta-rules are evaluated over alerts, not events (see meta_rules)
+    if detection.meta.is_some() {
+        return false;
+    }
+
+    let excluded = detection
+        .filters
+        .iter()
+        .filter(|filter| !filter.trim().is_empty())
+        .any(|filter| matches_condition_with_case(event, filter, case_mode));
+    if excluded {
+        return false;
+    }
+
+    if detection.detection_type == DetectionType::Script {
+        return script_rules::matches_script(event, &detection.condition);
+    }
+
+    if let Some(sequence) = &detection.sequence {
+        let filtered = detection.condition.trim().is_empty()
+            || matches_condition_with_case(event, &detection.condition, case_mode);
+        return filtered && correlation::matches_any_step(event, sequence, case_mode);
+    }
+
+    matches_condition_with_case(event, &detection.condition, case_mode)
+}
+
 /// Helper function to check if a JSON event matches a SQL-like condition.
 /// Supports:
-/// - Operators: =, !=, <>, IN, NOT IN, CONTAINS, NOT CONTAINS
+/// - Operators: =, =~, !=, <>, IN, NOT IN, CONTAINS, NOT CONTAINS
 /// - Logic: AND, OR
 /// - Nested fields: userIdentity.type
+/// - Field functions: strip_whitespace(f), normalize_path(f), decode_hex(f)
+/// - Term files: IN_FILE, NOT IN_FILE, CONTAINS_FILE, NOT CONTAINS_FILE
+/// - Named lists (IOC lists): IN_LIST, NOT IN_LIST
+/// - Regular expressions: REGEX, NOT REGEX (bounded, see `safe_regex`)
 ///
 /// Examples:
 /// - eventName = 'AssumeRole'
@@ -224,19 +1098,49 @@ pub fn validate_log_file(conn: &Connection, log_path: &str) -> Result<bool, Siem
 /// - eventName STARTSWITH 'Assume'
 /// - eventName ENDSWITH 'Role'
 /// - eventName MATCH 'Assume*'
+/// - userAgent REGEX '^aws-cli/[0-9]+\.'
+/// - userIdentity.userName =~ 'ADMIN' (case-insensitive equality)
+/// - Image IN_FILE '/path/to/process_names.txt'
+/// - CommandLine CONTAINS_FILE '/path/to/keywords.txt'
+/// - sourceIPAddress IN_LIST 'known_bad_ips'
+/// - normalize_path(Image) ENDSWITH '/windows/system32/cmd.exe'
+/// - strip_whitespace(CommandLine) CONTAINS 'invoke-expression'
 pub fn matches_condition(event: &serde_json::Value, condition: &str) -> bool {
+    matches_condition_with_case(event, condition, CaseMode::Default)
+}
+
+/// Same as `matches_condition`, with explicit control over letter case in
+/// string comparisons (see `CaseMode`).
+pub fn matches_condition_with_case(
+    event: &serde_json::Value,
+    condition: &str,
+    case_mode: CaseMode,
+) -> bool {
     let condition = condition.trim();
 
     // Check for IN/NOT IN operators FIRST before evaluating parentheses
     // This prevents the () in "IN ('value1', 'value2')" from being treated as logic grouping
     let upper = condition.to_uppercase();
     if upper.contains(" IN (") || upper.contains(" NOT IN (") {
+        // Split top-level OR/AND first, so "a = 'x' AND b IN ('y', 'z')" works
+        let or_parts = split_by_keyword_safe(condition, "OR");
+        if or_parts.len() > 1 {
+            return or_parts
+                .iter()
+                .any(|part| matches_condition_with_case(event, part, case_mode));
+        }
+        let and_parts = split_by_keyword_safe(condition, "AND");
+        if and_parts.len() > 1 {
+            return and_parts
+                .iter()
+                .all(|part| matches_condition_with_case(event, part, case_mode));
+        }
         // This is an IN/NOT IN operator, handle it directly
-        return matches_single_condition(event, condition);
+        return matches_single_condition(event, condition, case_mode);
     }
 
     // Handle parentheses for logic grouping - evaluate expressions inside () before AND/OR
-    if let Some(result) = evaluate_with_parentheses(event, condition) {
+    if let Some(result) = evaluate_with_parentheses(event, condition, case_mode) {
         return result;
     }
 
@@ -246,64 +1150,102 @@ pub fn matches_condition(event: &serde_json::Value, condition: &str) -> bool {
         let or_parts: Vec<&str> = split_by_keyword_safe(condition, "OR");
         return or_parts
             .iter()
-            .any(|part| matches_and_condition(part.trim(), event));
+            .any(|part| matches_and_condition(part.trim(), event, case_mode));
     }
 
     // No OR, check AND logic
-    matches_and_condition(condition, event)
+    matches_and_condition(condition, event, case_mode)
 }
 
 /// Evaluate expressions with parentheses
 /// Returns Some(bool) if parentheses found, None otherwise
-fn evaluate_with_parentheses(event: &serde_json::Value, condition: &str) -> Option<bool> {
-    // Find matching parentheses
-    if !condition.contains('(') {
-        return None;
-    }
+fn evaluate_with_parentheses(
+    event: &serde_json::Value,
+    condition: &str,
+    case_mode: CaseMode,
+) -> Option<bool> {
+    // Find grouping parentheses (function calls like normalize_path(...) are not groups)
+    find_grouping_parens(condition)?;
 
     let mut result = condition.to_string();
 
-    // Process innermost parentheses first
-    while result.contains('(') {
-        // Find innermost parentheses
-        let mut depth = 0;
-        let mut start = 0;
-        let mut end = 0;
+    // Process each top-level group until none remain
+    while let Some((start, end)) = find_grouping_parens(&result) {
+        // Extract expression inside parentheses
+        let inner = &result[start + 1..end];
+        // Evaluate it
+        let inner_result = matches_condition_with_case(event, inner, case_mode);
+        // Replace with result
+        let replacement = if inner_result { "true" } else { "false" };
+        result = format!("{}{}{}", &result[..start], replacement, &result[end + 1..]);
+    }
 
-        for (i, c) in result.chars().enumerate() {
-            if c == '(' {
-                if depth == 0 {
-                    start = i;
-                }
-                depth += 1;
-            } else if c == ')' {
+    // Now evaluate the simplified expression
+    Some(evaluate_boolean_expression(event, &result, case_mode))
+}
+
+/// Find the first top-level pair of grouping parentheses.
+/// Parentheses directly preceded by an identifier (e.g. `decode_hex(field)`)
+/// belong to a field function and are skipped.
+pub fn find_grouping_parens(expr: &str) -> Option<(usize, usize)> {
+    let bytes = expr.as_bytes();
+    let mut i = 0;
+    let mut quote: Option<u8> = None;
+
+    while i < bytes.len() {
+        if let Some(q) = quote {
+            if bytes[i] == q {
+                quote = None;
+            }
+            i += 1;
+        } else if bytes[i] == b'\'' || bytes[i] == b'"' {
+            quote = Some(bytes[i]);
+            i += 1;
+        } else if bytes[i] == b'(' {
+            let close = find_matching_paren(bytes, i)?;
+            let is_function_call =
+                i > 0 && (bytes[i - 1].is_ascii_alphanumeric() || bytes[i - 1] == b'_');
+            if !is_function_call {
+                return Some((i, close));
+            }
+            i = close + 1;
+        } else {
+            i += 1;
+        }
+    }
+
+    None
+}
+
+/// Find the index of the ')' matching the '(' at `open`.
+/// Parentheses inside quoted values (e.g. regex groups) are ignored.
+fn find_matching_paren(bytes: &[u8], open: usize) -> Option<usize> {
+    let mut depth = 0;
+    let mut quote: Option<u8> = None;
+    for (i, b) in bytes.iter().enumerate().skip(open) {
+        if let Some(q) = quote {
+            if *b == q {
+                quote = None;
+            }
+            continue;
+        }
+        match b {
+            b'\'' | b'"' => quote = Some(*b),
+            b'(' => depth += 1,
+            b')' => {
                 depth -= 1;
                 if depth == 0 {
-                    end = i;
-                    break;
+                    return Some(i);
                 }
             }
-        }
-
-        if start < end {
-            // Extract expression inside parentheses
-            let inner = &result[start + 1..end];
-            // Evaluate it
-            let inner_result = matches_condition(event, inner);
-            // Replace with result
-            let replacement = if inner_result { "true" } else { "false" };
-            result = format!("{}{}{}", &result[..start], replacement, &result[end + 1..]);
-        } else {
-            break;
+            _ => {}
         }
     }
-
-    // Now evaluate the simplified expression
-    Some(evaluate_boolean_expression(event, &result))
+    None
 }
 
 /// Evaluate a boolean expression (may contain 'true'/'false' literals)
-fn evaluate_boolean_expression(event: &serde_json::Value, expr: &str) -> bool {
+fn evaluate_boolean_expression(event: &serde_json::Value, expr: &str, case_mode: CaseMode) -> bool {
     let expr = expr.trim();
 
     // Handle boolean literals
@@ -324,7 +1266,7 @@ fn evaluate_boolean_expression(event: &serde_json::Value, expr: &str) -> bool {
             } else if p == "false" {
                 false
             } else {
-                matches_and_condition(p, event)
+                matches_and_condition(p, event, case_mode)
             }
         });
     }
@@ -339,28 +1281,35 @@ fn evaluate_boolean_expression(event: &serde_json::Value, expr: &str) -> bool {
             } else if p == "false" {
                 false
             } else {
-                matches_single_condition(event, p)
+                matches_single_condition(event, p, case_mode)
             }
         });
     }
 
     // Single condition
-    matches_single_condition(event, expr)
+    matches_single_condition(event, expr, case_mode)
 }
 
-/// Split by keyword, but only outside of parentheses
-fn split_by_keyword_safe<'a>(condition: &'a str, keyword: &str) -> Vec<&'a str> {
+/// Split by keyword, but only outside of parentheses and quoted values
+pub fn split_by_keyword_safe<'a>(condition: &'a str, keyword: &str) -> Vec<&'a str> {
     let upper = condition.to_uppercase();
     let keyword_upper = format!(" {} ", keyword);
 
     let mut parts = Vec::new();
     let mut last_pos = 0;
     let mut depth = 0;
+    let mut quote: Option<char> = None;
 
     let chars: Vec<char> = condition.chars().collect();
 
     for i in 0..chars.len() {
-        if chars[i] == '(' {
+        if let Some(q) = quote {
+            if chars[i] == q {
+                quote = None;
+            }
+        } else if chars[i] == '\'' || chars[i] == '"' {
+            quote = Some(chars[i]);
+        } else if chars[i] == '(' {
             depth += 1;
         } else if chars[i] == ')' {
             depth -= 1;
@@ -380,22 +1329,37 @@ fn split_by_keyword_safe<'a>(condition: &'a str, keyword: &str) -> Vec<&'a str>
 }
 
 /// Handle AND logic - all conditions must match
-fn matches_and_condition(condition: &str, event: &serde_json::Value) -> bool {
+fn matches_and_condition(condition: &str, event: &serde_json::Value, case_mode: CaseMode) -> bool {
     if condition.to_uppercase().contains(" AND ") {
         let and_parts: Vec<&str> = split_by_keyword_safe(condition, "AND");
         return and_parts
             .iter()
-            .all(|part| matches_single_condition(event, part.trim()));
+            .all(|part| matches_single_condition(event, part.trim(), case_mode));
     }
 
     // Single condition
-    matches_single_condition(event, condition)
+    matches_single_condition(event, condition, case_mode)
 }
 
 /// Check if a single condition matches (field = 'value' or field CONTAINS 'value')
-fn matches_single_condition(event: &serde_json::Value, condition: &str) -> bool {
+fn matches_single_condition(
+    event: &serde_json::Value,
+    condition: &str,
+    case_mode: CaseMode,
+) -> bool {
     let condition = condition.trim();
 
+    // Check for term-file operators (IN_FILE / CONTAINS_FILE / IN_LIST and their NOT forms)
+    if let Some(result) = matches_term_file_condition(event, condition, case_mode) {
+        return result;
+    }
+
+    // Check for REGEX operators before the others, since patterns may contain
+    // characters like '=' that would otherwise be taken for an operator
+    if let Some(result) = matches_regex_condition(event, condition, case_mode) {
+        return result;
+    }
+
     // Check for NOT IN operator (must check before IN to avoid false match)
     if condition.to_uppercase().contains(" NOT IN ") {
         if let Some(not_in_pos) = condition.to_uppercase().find(" NOT IN ") {
@@ -404,12 +1368,10 @@ fn matches_single_condition(event: &serde_json::Value, condition: &str) -> bool
 
             // Parse list: (value1, value2, value3)
             if let Some(values) = parse_in_list(value_part) {
-                if let Some(actual_value) = get_field_value(event, field) {
-                    // Check if actual value is NOT in the list
-                    return !values.iter().any(|v| v == &actual_value);
-                }
-                // FIXED: If field doesn't exist, return false
-                return false;
+                let fold = case_mode.folds(false);
+                return no_field_value(event, field, |actual_value| {
+                    values.iter().any(|v| text_eq(v, actual_value, fold))
+                });
             }
         }
     }
@@ -422,11 +1384,10 @@ fn matches_single_condition(event: &serde_json::Value, condition: &str) -> bool
 
             // Parse list: (value1, value2, value3)
             if let Some(values) = parse_in_list(value_part) {
-                if let Some(actual_value) = get_field_value(event, field) {
-                    // Check if actual value is in the list
-                    return values.iter().any(|v| v == &actual_value);
-                }
-                return false; // If field doesn't exist, it's not in the list
+                let fold = case_mode.folds(false);
+                return any_field_value(event, field, |actual_value| {
+                    values.iter().any(|v| text_eq(v, actual_value, fold))
+                });
             }
         }
     }
@@ -441,13 +1402,11 @@ fn matches_single_condition(event: &serde_json::Value, condition: &str) -> bool
             let search_value = value_part.trim_matches('\'').trim_matches('"');
 
             // Get field value and check if it does NOT contain the search value
-            if let Some(actual_value) = get_field_value(event, field) {
-                return !actual_value
-                    .to_lowercase()
-                    .contains(&search_value.to_lowercase());
-            }
-            // FIXED: If field doesn't exist, return false
-            return false;
+            let fold = case_mode.folds(true);
+            let search_value = fold_case(search_value, fold);
+            return no_field_value(event, field, |actual_value| {
+                fold_case(actual_value, fold).contains(&search_value)
+            });
         }
     }
 
@@ -461,15 +1420,26 @@ fn matches_single_condition(event: &serde_json::Value, condition: &str) -> bool
             let search_value = value_part.trim_matches('\'').trim_matches('"');
 
             // Get field value and check if it contains the search value
-            if let Some(actual_value) = get_field_value(event, field) {
-                return actual_value
-                    .to_lowercase()
-                    .contains(&search_value.to_lowercase());
-            }
-            return false;
+            let fold = case_mode.folds(true);
+            let search_value = fold_case(search_value, fold);
+            return any_field_value(event, field, |actual_value| {
+                fold_case(actual_value, fold).contains(&search_value)
+            });
         }
     }
 
+    // Check for =~ operator (case-insensitive equality, must check before = and !=)
+    if let Some(pos) = find_unquoted(condition, "=~") {
+        let field = condition[..pos].trim();
+        let value_part = condition[pos + 2..].trim();
+
+        let expected_value = value_part.trim_matches('\'').trim_matches('"');
+
+        return any_field_value(event, field, |actual_value| {
+            text_eq(actual_value, expected_value, true)
+        });
+    }
+
     // Check for != operator (must check before = to avoid false match)
     if condition.contains("!=") {
         if let Some(neq_pos) = condition.find("!=") {
@@ -480,12 +1450,10 @@ fn matches_single_condition(event: &serde_json::Value, condition: &str) -> bool
             let expected_value = value_part.trim_matches('\'').trim_matches('"');
 
             // Get field value from event
-            if let Some(actual_value) = get_field_value(event, field) {
-                return actual_value != expected_value;
-            }
-            // FIXED: If field doesn't exist, we can't compare it, return false
-            // This prevents K8s rules from matching AWS logs (false positives)
-            return false;
+            let fold = case_mode.folds(false);
+            return no_field_value(event, field, |actual_value| {
+                text_eq(actual_value, expected_value, fold)
+            });
         }
     }
 
@@ -499,11 +1467,10 @@ fn matches_single_condition(event: &serde_json::Value, condition: &str) -> bool
             let expected_value = value_part.trim_matches('\'').trim_matches('"');
 
             // Get field value from event
-            if let Some(actual_value) = get_field_value(event, field) {
-                return actual_value != expected_value;
-            }
-            // FIXED: If field doesn't exist, we can't compare it, return false
-            return false;
+            let fold = case_mode.folds(false);
+            return no_field_value(event, field, |actual_value| {
+                text_eq(actual_value, expected_value, fold)
+            });
         }
     }
 
@@ -516,8 +1483,12 @@ fn matches_single_condition(event: &serde_json::Value, condition: &str) -> bool
         let expected_value = value_part.trim_matches('\'').trim_matches('"');
 
         // Get field value from event (supports nested fields with dot notation)
-        if let Some(actual_value) = get_field_value(event, field) {
-            return actual_value == expected_value;
+        let actual_values = get_field_values(event, field);
+        if !actual_values.is_empty() {
+            let fold = case_mode.folds(false);
+            return actual_values
+                .iter()
+                .any(|actual_value| text_eq(actual_value, expected_value, fold));
         }
     }
 
@@ -529,13 +1500,11 @@ fn matches_single_condition(event: &serde_json::Value, condition: &str) -> bool
 
             let search_value = value_part.trim_matches('\'').trim_matches('"');
 
-            if let Some(actual_value) = get_field_value(event, field) {
-                return !actual_value
-                    .to_lowercase()
-                    .starts_with(&search_value.to_lowercase());
-            }
-            // FIXED: If field doesn't exist, return false
-            return false;
+            let fold = case_mode.folds(true);
+            let search_value = fold_case(search_value, fold);
+            return no_field_value(event, field, |actual_value| {
+                fold_case(actual_value, fold).starts_with(&search_value)
+            });
         }
     }
 
@@ -547,12 +1516,11 @@ fn matches_single_condition(event: &serde_json::Value, condition: &str) -> bool
 
             let search_value = value_part.trim_matches('\'').trim_matches('"');
 
-            if let Some(actual_value) = get_field_value(event, field) {
-                return actual_value
-                    .to_lowercase()
-                    .starts_with(&search_value.to_lowercase());
-            }
-            return false;
+            let fold = case_mode.folds(true);
+            let search_value = fold_case(search_value, fold);
+            return any_field_value(event, field, |actual_value| {
+                fold_case(actual_value, fold).starts_with(&search_value)
+            });
         }
     }
 
@@ -564,13 +1532,11 @@ fn matches_single_condition(event: &serde_json::Value, condition: &str) -> bool
 
             let search_value = value_part.trim_matches('\'').trim_matches('"');
 
-            if let Some(actual_value) = get_field_value(event, field) {
-                return !actual_value
-                    .to_lowercase()
-                    .ends_with(&search_value.to_lowercase());
-            }
-            // FIXED: If field doesn't exist, return false
-            return false;
+            let fold = case_mode.folds(true);
+            let search_value = fold_case(search_value, fold);
+            return no_field_value(event, field, |actual_value| {
+                fold_case(actual_value, fold).ends_with(&search_value)
+            });
         }
     }
 
@@ -582,12 +1548,11 @@ fn matches_single_condition(event: &serde_json::Value, condition: &str) -> bool
 
             let search_value = value_part.trim_matches('\'').trim_matches('"');
 
-            if let Some(actual_value) = get_field_value(event, field) {
-                return actual_value
-                    .to_lowercase()
-                    .ends_with(&search_value.to_lowercase());
-            }
-            return false;
+            let fold = case_mode.folds(true);
+            let search_value = fold_case(search_value, fold);
+            return any_field_value(event, field, |actual_value| {
+                fold_case(actual_value, fold).ends_with(&search_value)
+            });
         }
     }
 
@@ -598,6 +1563,7 @@ fn matches_single_condition(event: &serde_json::Value, condition: &str) -> bool
             let value_part = condition[pos + 7..].trim(); // " MATCH " is 7 chars
 
             let search_value = value_part.trim_matches('\'').trim_matches('"');
+            let fold = case_mode.folds(true);
 
             // Check if it's a list match: field MATCH ['a*', 'b*']
             if value_part.starts_with('[') && value_part.ends_with(']') {
@@ -607,46 +1573,415 @@ fn matches_single_condition(event: &serde_json::Value, condition: &str) -> bool
                     .map(|s| s.trim().trim_matches('\'').trim_matches('"').to_string())
                     .collect();
 
-                if let Some(actual_value) = get_field_value(event, field) {
-                    return patterns
+                return any_field_value(event, field, |actual_value| {
+                    patterns
                         .iter()
-                        .any(|pattern| wildcard_match(&actual_value, pattern));
+                        .any(|pattern| wildcard_match(actual_value, pattern, fold))
+                });
+            }
+
+            return any_field_value(event, field, |actual_value| {
+                wildcard_match(actual_value, search_value, fold)
+            });
+        }
+    }
+
+    false
+}
+
+/// Term-file operators: (keyword, negated, substring). NOT forms come first
+/// to avoid a false match on the positive form.
+const TERM_FILE_OPERATORS: [(&str, bool, bool); 6] = [
+    (" NOT IN_FILE ", true, false),
+    (" NOT CONTAINS_FILE ", true, true),
+    (" NOT IN_LIST ", true, false),
+    (" IN_FILE ", false, false),
+    (" CONTAINS_FILE ", false, true),
+    (" IN_LIST ", false, false),
+];
+
+/// The term set a term-file operator keyword refers to with `reference`.
+fn term_source(keyword: &str, reference: &str) -> term_sets::TermSource {
+    if keyword.ends_with("IN_LIST ") {
+        term_sets::TermSource::List(reference.to_string())
+    } else {
+        term_sets::TermSource::File(reference.to_string())
+    }
+}
+
+/// Resolve the term files and lists a detection references, so a scan loads
+/// each before evaluating and reports a missing one as a rule failure instead
+/// of silently not matching.
+pub fn resolve_term_sets(detection: &DetectionLogic) -> Result<(), SiemError> {
+    if detection.detection_type == DetectionType::Script {
+        return Ok(());
+    }
+    let case_mode = CaseMode::from_flag(detection.case_sensitive);
+    let steps = detection
+        .sequence
+        .iter()
+        .flat_map(|sequence| &sequence.steps);
+    std::iter::once(&detection.condition)
+        .chain(&detection.filters)
+        .chain(steps.map(|step| &step.condition))
+        .try_for_each(|condition| resolve_condition_term_sets(condition, case_mode))
+}
+
+/// Resolve the term files and lists referenced anywhere in a condition.
+pub fn resolve_condition_term_sets(condition: &str, case_mode: CaseMode) -> Result<(), SiemError> {
+    let upper = condition.to_uppercase();
+    let fold = case_mode.folds(true);
+    // Every NOT form contains its positive form, so these find them all
+    for (keyword, _, _) in &TERM_FILE_OPERATORS[3..] {
+        for (pos, _) in upper.match_indices(keyword) {
+            let rest = condition[pos + keyword.len()..].trim_start();
+            let Some(quote) = rest.chars().next().filter(|c| *c == '\'' || *c == '"') else {
+                continue;
+            };
+            let Some(end) = rest[1..].find(quote) else {
+                continue;
+            };
+            term_sets::resolve(&term_source(keyword, &rest[1..1 + end]), fold)?;
+        }
+    }
+    Ok(())
+}
+
+/// Evaluate `field IN_FILE 'terms.txt'` / `field CONTAINS_FILE 'terms.txt'` /
+/// `field IN_LIST 'list_name'` (optionally prefixed with NOT) against the
+/// current content of the term set. Terms compare ignoring case unless the
+/// rule is case-sensitive; relative paths are taken from the rules directory.
+/// An unresolvable set never matches (`resolve_term_sets` reports it up front).
+/// Returns None if the condition doesn't use a term-file operator.
+fn matches_term_file_condition(
+    event: &serde_json::Value,
+    condition: &str,
+    case_mode: CaseMode,
+) -> Option<bool> {
+    let upper = condition.to_uppercase();
+
+    for (keyword, negated, substring) in TERM_FILE_OPERATORS {
+        if let Some(pos) = upper.find(keyword) {
+            let field = condition[..pos].trim();
+            let value_part = condition[pos + keyword.len()..].trim();
+            let reference = value_part.trim_matches('\'').trim_matches('"');
+
+            let source = term_source(keyword, reference);
+            let Ok(term_set) = term_sets::resolve(&source, case_mode.folds(true)) else {
+                return Some(false);
+            };
+
+            let found = |actual_value: &str| {
+                if substring {
+                    term_set.contains_any(actual_value)
+                } else {
+                    term_set.contains_exact(actual_value)
                 }
-                return false;
+            };
+            return Some(if negated {
+                no_field_value(event, field, found)
+            } else {
+                any_field_value(event, field, found)
+            });
+        }
+    }
+
+    None
+}
+
+/// Evaluate `field REGEX 'pattern'` / `field NOT REGEX 'pattern'`.
+/// Patterns are compiled through `safe_regex`, so an oversized or overly
+/// complex pattern never matches instead of stalling the scan. Values are
+/// matched whole, so `NOT REGEX` never holds because of a cut value.
+/// Returns None if the condition doesn't use a regex operator.
+fn matches_regex_condition(
+    event: &serde_json::Value,
+    condition: &str,
+    case_mode: CaseMode,
+) -> Option<bool> {
+    let (pos, keyword, negated) = find_regex_operator(condition)?;
+    let field = condition[..pos].trim();
+    let value_part = condition[pos + keyword.len()..].trim();
+    let pattern = strip_quotes(value_part);
+
+    let regex = match safe_regex::compile(pattern, case_mode.folds(true)) {
+        Ok(regex) => regex,
+        Err(e) => {
+            eprintln!("Warning: {}", e);
+            return Some(false);
+        }
+    };
+
+    let found = |actual_value: &str| safe_regex::is_match(&regex, actual_value);
+    Some(if negated {
+        no_field_value(event, field, found)
+    } else {
+        any_field_value(event, field, found)
+    })
+}
+
+/// Locate the first ` REGEX ` / ` NOT REGEX ` keyword outside quoted values.
+/// Returns (position, keyword, negated).
+pub fn find_regex_operator(condition: &str) -> Option<(usize, &'static str, bool)> {
+    let bytes = condition.as_bytes();
+    let mut quote: Option<u8> = None;
+
+    for i in 0..bytes.len() {
+        if let Some(q) = quote {
+            if bytes[i] == q {
+                quote = None;
+            }
+            continue;
+        }
+        if bytes[i] == b'\'' || bytes[i] == b'"' {
+            quote = Some(bytes[i]);
+            continue;
+        }
+        for (keyword, negated) in [(" NOT REGEX ", true), (" REGEX ", false)] {
+            if bytes[i..]
+                .get(..keyword.len())
+                .is_some_and(|b| b.eq_ignore_ascii_case(keyword.as_bytes()))
+            {
+                return Some((i, keyword, negated));
             }
+        }
+    }
+
+    None
+}
 
-            if let Some(actual_value) = get_field_value(event, field) {
-                return wildcard_match(&actual_value, search_value);
+/// Find `operator` outside quoted values, so a value containing it (e.g.
+/// `url = 'a=~b'`) isn't split there.
+pub fn find_unquoted(condition: &str, operator: &str) -> Option<usize> {
+    let bytes = condition.as_bytes();
+    let mut quote: Option<u8> = None;
+
+    for i in 0..bytes.len() {
+        if let Some(q) = quote {
+            if bytes[i] == q {
+                quote = None;
             }
-            return false;
+            continue;
+        }
+        if bytes[i] == b'\'' || bytes[i] == b'"' {
+            quote = Some(bytes[i]);
+            continue;
+        }
+        if bytes[i..].starts_with(operator.as_bytes()) {
+            return Some(i);
         }
     }
 
-    false
+    None
 }
 
-/// Get a field value from JSON, supporting dot notation for nested fields.
+/// Remove one pair of surrounding quotes, keeping quotes inside the value
+/// (regex patterns may legitimately end with a quote character class).
+fn strip_quotes(value: &str) -> &str {
+    for q in ['\'', '"'] {
+        if value.len() >= 2 && value.starts_with(q) && value.ends_with(q) {
+            return &value[1..value.len() - 1];
+        }
+    }
+    value
+}
+
+/// Check whether any value of a field satisfies the predicate.
+/// A missing field never matches, even for negated operators (!=, NOT IN, ...),
+/// so rules written for one log source don't fire on another.
+fn any_field_value<F>(event: &serde_json::Value, field: &str, predicate: F) -> bool
+where
+    F: Fn(&str) -> bool,
+{
+    get_field_values(event, field)
+        .iter()
+        .any(|value| predicate(value.as_str()))
+}
+
+/// Check that a field is present and none of its values satisfies the
+/// predicate, the positive form of a negated operator. On an array field,
+/// `tags != 'prod'` holds only when no element is 'prod'. A missing field
+/// never matches, as with `any_field_value`.
+fn no_field_value<F>(event: &serde_json::Value, field: &str, predicate: F) -> bool
+where
+    F: Fn(&str) -> bool,
+{
+    let values = get_field_values(event, field);
+    !values.is_empty() && !values.iter().any(|value| predicate(value.as_str()))
+}
+
+/// Get all values of a field from JSON, supporting dot notation for nested fields.
 /// e.g., "eventName" or "userIdentity.type"
-fn get_field_value(event: &serde_json::Value, field_path: &str) -> Option<String> {
+///
+/// When the path traverses an array (e.g. "resources.ARN" in CloudTrail),
+/// every element is visited and one value is returned per matching element.
+/// The fan-out can be written explicitly, as in "target[].type", and an
+/// element can be picked by index, as in "target[0].type".
+///
+/// The field may be wrapped in a normalization function, e.g.
+/// "normalize_path(Image)" or "strip_whitespace(decode_hex(CommandLine))".
+pub fn get_field_values(event: &serde_json::Value, field_path: &str) -> Vec<String> {
+    if let Some((function, inner)) = parse_field_function(field_path) {
+        return get_field_values(event, inner)
+            .iter()
+            .filter_map(|value| apply_field_function(function, value))
+            .collect();
+    }
+
     let parts: Vec<&str> = field_path.split('.').collect();
-    let mut current = event;
+    let mut values = Vec::new();
+    collect_field_values(event, &parts, &mut values);
+    values
+}
 
-    for part in parts {
-        current = current.get(part)?;
+/// Walk the remaining path segments, fanning out over arrays.
+fn collect_field_values(current: &serde_json::Value, parts: &[&str], values: &mut Vec<String>) {
+    // Arrays are transparent: evaluate the rest of the path against each element
+    if let serde_json::Value::Array(items) = current {
+        for item in items {
+            collect_field_values(item, parts, values);
+        }
+        return;
     }
 
-    // Convert to string
-    match current {
-        serde_json::Value::String(s) => Some(s.clone()),
-        serde_json::Value::Number(n) => Some(n.to_string()),
-        serde_json::Value::Bool(b) => Some(b.to_string()),
-        _ => None,
+    match parts.split_first() {
+        Some((part, rest)) => {
+            let (name, index) = array_segment(part);
+            let next = current.get(name).and_then(|next| match index {
+                Some(index) => next.get(index),
+                None => Some(next),
+            });
+            if let Some(next) = next {
+                collect_field_values(next, rest, values);
+            }
+        }
+        None => {
+            // Convert to string
+            match current {
+                serde_json::Value::String(s) => values.push(s.clone()),
+                serde_json::Value::Number(n) => values.push(n.to_string()),
+                serde_json::Value::Bool(b) => values.push(b.to_string()),
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Split a path segment into its name and array index: "target[]" is
+/// ("target", None) like "target", "target[0]" is ("target", Some(0)).
+fn array_segment(part: &str) -> (&str, Option<usize>) {
+    let Some((name, inner)) = part
+        .strip_suffix(']')
+        .and_then(|part| part.rsplit_once('['))
+    else {
+        return (part, None);
+    };
+    if inner.is_empty() {
+        return (name, None);
+    }
+    match inner.parse() {
+        Ok(index) => (name, Some(index)),
+        Err(_) => (part, None),
+    }
+}
+
+/// Normalization functions that can wrap a field in a condition.
+const FIELD_FUNCTIONS: [&str; 5] = [
+    "strip_whitespace",
+    "normalize_path",
+    "decode_hex",
+    "geo_country",
+    "geo_asn",
+];
+
+/// Split "function(inner)" into its function name and inner field expression.
+/// Returns None if the field is not wrapped in a known normalization function.
+fn parse_field_function(field_path: &str) -> Option<(&'static str, &str)> {
+    let field_path = field_path.trim();
+    let open = field_path.find('(')?;
+
+    if !field_path.ends_with(')') {
+        return None;
+    }
+
+    let name = field_path[..open].trim().to_lowercase();
+    let function = *FIELD_FUNCTIONS.iter().find(|f| **f == name)?;
+
+    Some((function, field_path[open + 1..field_path.len() - 1].trim()))
+}
+
+/// Apply a normalization function to a field value.
+/// - strip_whitespace: removes all whitespace ("c m d . e x e" -> "cmd.exe")
+/// - normalize_path: lowercases, converts backslashes to slashes and collapses repeats
+/// - decode_hex: decodes a hex string (optional 0x prefix, UTF-16LE NULs dropped);
+///   values that are not valid hex are returned unchanged
+/// - geo_country / geo_asn: ISO country code / AS number of an IP address from
+///   the configured GeoIP databases; no value if unknown
+fn apply_field_function(function: &str, value: &str) -> Option<String> {
+    let normalized = match function {
+        "strip_whitespace" => value.chars().filter(|c| !c.is_whitespace()).collect(),
+        "normalize_path" => {
+            let mut normalized = String::with_capacity(value.len());
+            for c in value.to_lowercase().chars() {
+                let c = if c == '\\' { '/' } else { c };
+                if c == '/' && normalized.ends_with('/') {
+                    continue;
+                }
+                normalized.push(c);
+            }
+            normalized
+        }
+        "decode_hex" => decode_hex(value).unwrap_or_else(|| value.to_string()),
+        "geo_country" => return crate::geoip::active()?.country(value),
+        "geo_asn" => return Some(crate::geoip::active()?.asn(value)?.0.to_string()),
+        _ => value.to_string(),
+    };
+    Some(normalized)
+}
+
+/// Decode a hex-encoded string into text.
+fn decode_hex(value: &str) -> Option<String> {
+    let trimmed = value.trim();
+    let hex = trimmed
+        .strip_prefix("0x")
+        .or_else(|| trimmed.strip_prefix("0X"))
+        .unwrap_or(trimmed);
+    let hex: String = hex.chars().filter(|c| !c.is_whitespace()).collect();
+
+    if hex.is_empty() || hex.len() % 2 != 0 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+
+    let bytes: Vec<u8> = (0..hex.len())
+        .step_by(2)
+        .filter_map(|i| u8::from_str_radix(&hex[i..i + 2], 16).ok())
+        .filter(|b| *b != 0)
+        .collect();
+
+    Some(String::from_utf8_lossy(&bytes).to_string())
+}
+
+/// Compare two strings, optionally ignoring case.
+fn text_eq(a: &str, b: &str, fold: bool) -> bool {
+    if fold {
+        a.to_lowercase() == b.to_lowercase()
+    } else {
+        a == b
+    }
+}
+
+/// Lowercase a value if case folding is enabled.
+fn fold_case(value: &str, fold: bool) -> String {
+    if fold {
+        value.to_lowercase()
+    } else {
+        value.to_string()
     }
 }
 
 /// Parse an IN clause list: ('value1', 'value2', 'value3')
 /// Returns a vector of values without quotes
-fn parse_in_list(list_str: &str) -> Option<Vec<String>> {
+pub fn parse_in_list(list_str: &str) -> Option<Vec<String>> {
     let list_str = list_str.trim();
 
     // Check if it starts with ( and ends with )
@@ -675,6 +2010,104 @@ fn parse_in_list(list_str: &str) -> Option<Vec<String>> {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_query_values_keep_their_types() {
+        let conn = create_connection().unwrap();
+        let result = execute_adhoc_query(
+            &conn,
+            "SELECT 'plain' AS s, '123' AS digits, 42 AS n, 1.5 AS d, true AS b, NULL AS nothing, \
+             TIMESTAMP '2024-01-01 10:00:00' AS ts, DATE '2024-01-02' AS day, \
+             [1, 2] AS list, {'a': 1} AS obj, '{\"k\": \"v\"}'::JSON AS doc",
+        )
+        .unwrap();
+
+        let row = &result.rows[0];
+        assert_eq!(row["s"], "plain");
+        assert_eq!(row["digits"], "123");
+        assert_eq!(row["n"], 42);
+        assert_eq!(row["d"], 1.5);
+        assert_eq!(row["b"], true);
+        assert!(row["nothing"].is_null());
+        assert_eq!(row["ts"], "2024-01-01T10:00:00Z");
+        assert_eq!(row["day"], "2024-01-02");
+        assert_eq!(row["list"], serde_json::json!([1, 2]));
+        assert_eq!(row["obj"]["a"], 1);
+        assert_eq!(row["doc"]["k"], "v");
+    }
+
+    #[test]
+    fn test_adhoc_queries_are_read_only() {
+        let allowed = [
+            "SELECT * FROM read_json_auto('/logs/a.json') WHERE eventName = 'DeleteBucket'",
+            "with t as (select 1 as n) select n from t;",
+            "FROM range(3)",
+            "DESCRIBE SELECT 1",
+            "SELECT 1 -- drop table later; copy it",
+            "SELECT \"update\", $$INSERT INTO x$$, E'it\\'s; COPY' FROM (SELECT 1 AS \"update\")",
+            "EXPLAIN ANALYZE SELECT 1",
+        ];
+        for query in allowed {
+            assert!(ensure_read_only(query).is_ok(), "{}", query);
+        }
+
+        let rejected = [
+            "",
+            "ATTACH '/tmp/other.duckdb' AS other",
+            "COPY (SELECT 1) TO '/tmp/out.csv'",
+            "INSTALL httpfs",
+            "CREATE TABLE t AS SELECT 1",
+            "SELECT 1; DELETE FROM log_events",
+            "DELETE FROM log_events",
+            "SET threads = 1",
+            "WITH t AS (SELECT 1) INSERT INTO x SELECT * FROM t",
+            "EXPLAIN ANALYZE DROP TABLE t",
+            "SELECT * FROM query('DROP TABLE t')",
+            "SELECT * FROM \"query_table\"('t')",
+            "SELECT E'\\'' FROM t; DROP TABLE t",
+            "SELECT 1 /* ' */ ; PRAGMA version",
+        ];
+        for query in rejected {
+            assert!(ensure_read_only(query).is_err(), "{}", query);
+        }
+
+        let conn = create_connection().unwrap();
+        assert!(execute_adhoc_query(&conn, "CREATE TABLE t (x INTEGER)").is_err());
+        assert!(execute_paged_query(&conn, "DROP TABLE t", 0, 10).is_err());
+    }
+
+    #[test]
+    fn test_paged_query() {
+        let conn = create_connection().unwrap();
+        let query = "SELECT range AS n FROM range(25) ORDER BY n;";
+
+        let (first, has_more) = execute_paged_query(&conn, query, 0, 10).unwrap();
+        assert_eq!(first.rows.len(), 10);
+        assert_eq!(first.rows[0]["n"], 0);
+        assert!(has_more);
+
+        let (last, has_more) = execute_paged_query(&conn, query, 20, 10).unwrap();
+        assert_eq!(last.rows.len(), 5);
+        assert_eq!(last.rows[4]["n"], 24);
+        assert!(!has_more);
+        assert_eq!(last.columns, vec!["n"]);
+    }
+
+    #[test]
+    fn test_query_reports_column_names_and_types() {
+        let conn = create_connection().unwrap();
+        let result = execute_adhoc_query(
+            &conn,
+            "SELECT 42::BIGINT AS n, 'a' AS s, [1, 2] AS l, 1.5::DOUBLE AS d WHERE false",
+        )
+        .unwrap();
+        assert!(result.rows.is_empty());
+        assert_eq!(result.columns, vec!["n", "s", "l", "d"]);
+        assert_eq!(
+            result.column_types,
+            vec!["BIGINT", "VARCHAR", "INTEGER[]", "DOUBLE"]
+        );
+    }
+
     #[test]
     fn test_validate_log_file() {
         // This is a placeholder test
@@ -774,6 +2207,21 @@ mod tests {
         assert_eq!(result, false, "Non-existent field should return false");
     }
 
+    #[test]
+    fn test_in_operator_with_and_or() {
+        let event = serde_json::json!({
+            "event_type": "tls",
+            "tls": { "version": "SSLv3" }
+        });
+
+        let condition = "event_type = 'tls' AND tls.version IN ('SSLv2', 'SSLv3')";
+        assert!(matches_condition(&event, condition));
+        let condition = "event_type = 'dns' AND tls.version IN ('SSLv2', 'SSLv3')";
+        assert!(!matches_condition(&event, condition));
+        let condition = "event_type = 'dns' OR tls.version NOT IN ('TLS 1.2', 'TLS 1.3')";
+        assert!(matches_condition(&event, condition));
+    }
+
     #[test]
     fn test_parse_in_list_basic() {
         let list_str = "('value1', 'value2', 'value3')";
@@ -825,12 +2273,348 @@ mod tests {
         let result = parse_in_list(list_str);
         assert_eq!(result, None, "Missing parentheses should return None");
     }
+
+    #[test]
+    fn test_array_field_any_element_matches() {
+        let event = serde_json::json!({
+            "eventName": "GetObject",
+            "resources": [
+                { "type": "AWS::S3::Object", "ARN": "arn:aws:s3:::bucket/public.txt" },
+                { "type": "AWS::S3::Bucket", "ARN": "arn:aws:s3:::secret-bucket" }
+            ]
+        });
+
+        assert!(matches_condition(
+            &event,
+            "resources.ARN = 'arn:aws:s3:::secret-bucket'"
+        ));
+        assert!(matches_condition(
+            &event,
+            "resources.type IN ('AWS::S3::Bucket', 'AWS::KMS::Key')"
+        ));
+        assert!(!matches_condition(
+            &event,
+            "resources.ARN CONTAINS 'other-bucket'"
+        ));
+    }
+
+    #[test]
+    fn test_array_of_scalars() {
+        let event = serde_json::json!({
+            "tags": ["prod", "finance"]
+        });
+
+        assert!(matches_condition(&event, "tags = 'finance'"));
+        assert!(!matches_condition(&event, "tags = 'dev'"));
+    }
+
+    #[test]
+    fn test_negated_operators_on_array_fields() {
+        let event = serde_json::json!({
+            "tags": ["prod", "finance"],
+            "resources": [
+                { "ARN": "arn:aws:s3:::public-bucket" },
+                { "ARN": "arn:aws:s3:::secret-bucket" }
+            ]
+        });
+
+        // A negated operator holds only when no element matches the positive form
+        assert!(!matches_condition(&event, "tags != 'prod'"));
+        assert!(!matches_condition(&event, "tags <> 'finance'"));
+        assert!(matches_condition(&event, "tags != 'dev'"));
+        assert!(!matches_condition(&event, "tags NOT IN ('prod', 'dev')"));
+        assert!(matches_condition(&event, "tags NOT IN ('dev', 'test')"));
+        assert!(!matches_condition(
+            &event,
+            "resources.ARN NOT CONTAINS 'secret'"
+        ));
+        assert!(matches_condition(
+            &event,
+            "resources.ARN NOT CONTAINS 'kms'"
+        ));
+        assert!(!matches_condition(&event, "tags NOT STARTSWITH 'fin'"));
+        assert!(!matches_condition(&event, "tags NOT ENDSWITH 'rod'"));
+        assert!(!matches_condition(&event, "tags NOT REGEX '^pr'"));
+        assert!(matches_condition(&event, "tags NOT REGEX '^dev'"));
+
+        // A missing field still never matches
+        assert!(!matches_condition(&event, "missing != 'prod'"));
+    }
+
+    #[test]
+    fn test_case_insensitive_equality_operator() {
+        let event = serde_json::json!({
+            "userName": "Administrator"
+        });
+
+        assert!(!matches_condition(&event, "userName = 'administrator'"));
+        assert!(matches_condition(&event, "userName =~ 'administrator'"));
+    }
+
+    #[test]
+    fn test_quoted_values_containing_case_insensitive_operator() {
+        let event = serde_json::json!({
+            "url": "a=~b",
+            "path": "X=~Y"
+        });
+
+        assert!(matches_condition(&event, "url = 'a=~b'"));
+        assert!(!matches_condition(&event, "url = 'a'"));
+        // Keeps its negation and stays case-sensitive
+        assert!(matches_condition(&event, "path != 'x=~y'"));
+        assert!(!matches_condition(&event, "path != 'X=~Y'"));
+        assert!(matches_condition(&event, "path =~ 'x=~y'"));
+    }
+
+    #[test]
+    fn test_case_mode_overrides() {
+        let event = serde_json::json!({
+            "eventName": "AssumeRole"
+        });
+
+        // Default: equality exact, CONTAINS folded
+        assert!(matches_condition(&event, "eventName CONTAINS 'assume'"));
+
+        // Sensitive: CONTAINS becomes exact too
+        assert!(!matches_condition_with_case(
+            &event,
+            "eventName CONTAINS 'assume'",
+            CaseMode::Sensitive
+        ));
+
+        // Insensitive: equality and IN fold as well
+        assert!(matches_condition_with_case(
+            &event,
+            "eventName = 'assumerole'",
+            CaseMode::Insensitive
+        ));
+        assert!(matches_condition_with_case(
+            &event,
+            "eventName IN ('ASSUMEROLE', 'GetObject')",
+            CaseMode::Insensitive
+        ));
+    }
+
+    #[test]
+    fn test_detection_filters_exclude_events() {
+        let detection: DetectionLogic = serde_yaml::from_str(
+            "severity: high\ncondition: \"eventName = 'AssumeRole'\"\nfilters:\n  - \"userName = 'svc-backup'\"\n",
+        )
+        .unwrap();
+
+        let user = serde_json::json!({ "eventName": "AssumeRole", "userName": "alice" });
+        let service = serde_json::json!({ "eventName": "AssumeRole", "userName": "svc-backup" });
+        assert!(matches_detection(&user, &detection));
+        assert!(!matches_detection(&service, &detection));
+    }
+
+    #[test]
+    fn test_term_file_operators() {
+        let path = std::env::temp_dir().join("offline_siem_test_terms.txt");
+        std::fs::write(&path, "# suspicious tools\nmimikatz.exe\npsexec.exe\n").unwrap();
+        let path_str = path.to_string_lossy().to_string();
+
+        let event = serde_json::json!({
+            "Image": "C:\\Tools\\Mimikatz.exe",
+            "OriginalFileName": "mimikatz.exe"
+        });
+
+        assert!(matches_condition(
+            &event,
+            &format!("OriginalFileName IN_FILE '{}'", path_str)
+        ));
+        assert!(matches_condition(
+            &event,
+            &format!("Image CONTAINS_FILE '{}'", path_str)
+        ));
+        assert!(!matches_condition(
+            &event,
+            &format!("Image NOT CONTAINS_FILE '{}'", path_str)
+        ));
+        assert!(!matches_condition(
+            &event,
+            "Image IN_FILE '/nonexistent/terms.txt'"
+        ));
+
+        // A case-sensitive rule compares terms exactly
+        let exact = format!("Image CONTAINS_FILE '{}'", path_str);
+        assert!(!matches_condition_with_case(
+            &event,
+            &exact,
+            CaseMode::Sensitive
+        ));
+
+        // Scans resolve the references up front and report missing ones
+        let detection: DetectionLogic = serde_yaml::from_str(&format!(
+            "severity: high\ncondition: \"Image CONTAINS_FILE '{}' AND User NOT IN_LIST 'admins'\"\n",
+            path_str
+        ))
+        .unwrap();
+        let err = resolve_term_sets(&detection).unwrap_err();
+        assert!(err.to_string().contains("List not found: admins"));
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_stream_cloudtrail_records_in_chunks() {
+        let path = std::env::temp_dir().join("offline_siem_test_stream_cloudtrail.json");
+        std::fs::write(
+            &path,
+            r#"{"Records": [{"eventName": "A"}, {"eventName": "B"}, {"eventName": "C"}], "x": 1}"#,
+        )
+        .unwrap();
+        let path_str = path.to_string_lossy().to_string();
+
+        let mut chunks = Vec::new();
+        let total = stream_events(&path_str, &LogType::CloudTrail, 2, |chunk| {
+            chunks.push(chunk)
+        })
+        .unwrap();
+        assert_eq!(total, 3);
+        assert_eq!(chunks.len(), 2);
+        assert_eq!(chunks[1][0]["eventName"], "C");
+
+        std::fs::write(&path, r#"{"records": []}"#).unwrap();
+        assert!(stream_events(&path_str, &LogType::CloudTrail, 2, |_| {}).is_err());
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_stream_flat_json_ndjson_and_single_object() {
+        let path = std::env::temp_dir().join("offline_siem_test_stream_flat.json");
+        let path_str = path.to_string_lossy().to_string();
+
+        std::fs::write(&path, "{\"a\": 1}\n\nnot json\n{\"a\": 2}\n{\"a\": 3}\n").unwrap();
+        let mut chunks = Vec::new();
+        let total =
+            stream_events(&path_str, &LogType::FlatJson, 2, |chunk| chunks.push(chunk)).unwrap();
+        assert_eq!(total, 3);
+        assert_eq!(chunks.len(), 2);
+
+        std::fs::write(&path, "{\n  \"a\": 1,\n  \"b\": 2\n}\n").unwrap();
+        let events =
+            load_all_events(&create_connection().unwrap(), &path_str, LogType::FlatJson).unwrap();
+        assert_eq!(events, vec![serde_json::json!({ "a": 1, "b": 2 })]);
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_stream_azure_exports() {
+        let path = std::env::temp_dir().join("offline_siem_test_stream_azure.json");
+        let path_str = path.to_string_lossy().to_string();
+        let count = |log_type: &LogType| {
+            let mut events = Vec::new();
+            let total =
+                stream_events(&path_str, log_type, 2, |chunk| events.extend(chunk)).unwrap();
+            assert_eq!(total, events.len());
+            events
+        };
+
+        let signin = r#"{"createdDateTime": "2024-03-01T10:01:02Z", "userPrincipalName": "a@b.c"}"#;
+        std::fs::write(
+            &path,
+            format!(
+                r#"{{"value": [{0}, {0}, {0}], "@odata.nextLink": null}}"#,
+                signin
+            ),
+        )
+        .unwrap();
+        assert_eq!(count(&LogType::EntraSignIn).len(), 3);
+        assert_eq!(detect_log_type(&path_str).unwrap(), LogType::EntraSignIn);
+
+        std::fs::write(&path, format!("[{0}, {0}]", signin)).unwrap();
+        assert_eq!(count(&LogType::EntraSignIn).len(), 2);
+
+        let activity = r#"{"time": "2024-03-01T10:01:02Z", "resourceId": "/SUBSCRIPTIONS/1", "operationName": "X", "category": "Administrative"}"#;
+        std::fs::write(&path, format!("{0}\n{0}\n", activity)).unwrap();
+        let events = count(&LogType::AzureActivity);
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0]["category"], "Administrative");
+        assert_eq!(detect_log_type(&path_str).unwrap(), LogType::AzureActivity);
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_normalize_path_function() {
+        let event = serde_json::json!({
+            "Image": "C:\\Windows\\\\System32\\CMD.exe"
+        });
+
+        let condition = "normalize_path(Image) = 'c:/windows/system32/cmd.exe'";
+        assert!(matches_condition(&event, condition));
+    }
+
+    #[test]
+    fn test_strip_whitespace_function() {
+        let event = serde_json::json!({
+            "CommandLine": "powershell  Invoke - Expression"
+        });
+
+        let condition = "strip_whitespace(CommandLine) CONTAINS 'invoke-expression'";
+        assert!(matches_condition(&event, condition));
+    }
+
+    #[test]
+    fn test_decode_hex_function() {
+        let event = serde_json::json!({
+            "payload": "0x77686F616D69"
+        });
+
+        assert!(matches_condition(&event, "decode_hex(payload) = 'whoami'"));
+        assert_eq!(decode_hex("not hex"), None);
+    }
+
+    #[test]
+    fn test_field_function_with_grouping() {
+        let event = serde_json::json!({
+            "Image": "C:\\Windows\\System32\\cmd.exe",
+            "User": "admin"
+        });
+
+        let condition = "User = 'admin' AND (normalize_path(Image) ENDSWITH '/cmd.exe' OR normalize_path(Image) ENDSWITH '/powershell.exe')";
+        assert!(matches_condition(&event, condition));
+    }
+
+    #[test]
+    fn test_regex_operator() {
+        let event = serde_json::json!({
+            "CommandLine": "powershell.exe -enc SQBFAFgA",
+            "User": "admin"
+        });
+
+        assert!(matches_condition(
+            &event,
+            r"CommandLine REGEX '-e(nc|ncodedcommand)?\s+[A-Za-z0-9+/=]+'"
+        ));
+        assert!(matches_condition(&event, "CommandLine REGEX '^POWERSHELL'"));
+        assert!(!matches_condition_with_case(
+            &event,
+            "CommandLine REGEX '^POWERSHELL'",
+            CaseMode::Sensitive
+        ));
+        assert!(matches_condition(&event, "CommandLine NOT REGEX '^cmd'"));
+        assert!(!matches_condition(&event, "Missing REGEX '.*'"));
+        // Parentheses and keywords inside the pattern are not logic
+        assert!(matches_condition(
+            &event,
+            "User = 'admin' AND (CommandLine REGEX '(enc|e) ' OR User = 'x')"
+        ));
+        // Patterns over the complexity bounds never match
+        assert!(!matches_condition(
+            &event,
+            "CommandLine REGEX '(a{1000}){1000}'"
+        ));
+    }
 }
 
-/// Simple wildcard matching (supports * and ?)
-fn wildcard_match(text: &str, pattern: &str) -> bool {
-    let text_chars: Vec<char> = text.to_lowercase().chars().collect();
-    let pattern_chars: Vec<char> = pattern.to_lowercase().chars().collect();
+/// Simple wildcard matching (supports * and ?), optionally ignoring case
+pub fn wildcard_match(text: &str, pattern: &str, fold: bool) -> bool {
+    let text_chars: Vec<char> = fold_case(text, fold).chars().collect();
+    let pattern_chars: Vec<char> = fold_case(pattern, fold).chars().collect();
     let mut i = 0;
     let mut j = 0;
     let mut star_idx = None;
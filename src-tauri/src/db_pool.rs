@@ -0,0 +1,111 @@
+//! Reusable DuckDB connections.
+//!
+//! Commands used to open a fresh in-memory database per call, paying for the
+//! database setup and extension loading (json, parquet) every time. The pool
+//! keeps one in-memory database for the app's lifetime, stored in Tauri
+//! state, and hands out connections to it; connections are returned to the
+//! pool when dropped and reused by later calls.
+
+use duckdb::Connection;
+use std::ops::Deref;
+use std::sync::Mutex;
+
+use crate::models::SiemError;
+
+/// Idle connections kept for reuse; extra ones are closed when returned.
+const MAX_IDLE_CONNECTIONS: usize = 8;
+
+/// Pool of connections to a shared in-memory DuckDB database.
+pub struct ConnectionPool {
+    /// Connection owning the database, only used to create the others
+    database: Mutex<Connection>,
+    idle: Mutex<Vec<Connection>>,
+}
+
+impl ConnectionPool {
+    pub fn new() -> Result<Self, SiemError> {
+        Ok(ConnectionPool {
+            database: Mutex::new(crate::db_engine::create_connection()?),
+            idle: Mutex::new(Vec::new()),
+        })
+    }
+
+    /// Get an idle connection, or open a new one to the shared database.
+    pub fn get(&self) -> Result<PooledConnection<'_>, SiemError> {
+        let idle = self.idle.lock().ok().and_then(|mut idle| idle.pop());
+        let conn = match idle {
+            Some(conn) => conn,
+            None => self
+                .database
+                .lock()
+                .map_err(|_| SiemError::Query("Connection pool is unavailable".to_string()))?
+                .try_clone()
+                .map_err(|e| {
+                    SiemError::Query(format!("Failed to create database connection: {}", e))
+                })?,
+        };
+
+        Ok(PooledConnection {
+            conn: Some(conn),
+            pool: self,
+        })
+    }
+}
+
+/// A connection borrowed from the pool, returned to it when dropped.
+pub struct PooledConnection<'p> {
+    conn: Option<Connection>,
+    pool: &'p ConnectionPool,
+}
+
+impl Deref for PooledConnection<'_> {
+    type Target = Connection;
+
+    fn deref(&self) -> &Connection {
+        self.conn
+            .as_ref()
+            .expect("connection is only taken on drop")
+    }
+}
+
+impl Drop for PooledConnection<'_> {
+    fn drop(&mut self) {
+        if let (Some(conn), Ok(mut idle)) = (self.conn.take(), self.pool.idle.lock()) {
+            if idle.len() < MAX_IDLE_CONNECTIONS {
+                idle.push(conn);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn idle_count(pool: &ConnectionPool) -> usize {
+        pool.idle.lock().unwrap().len()
+    }
+
+    #[test]
+    fn test_connections_are_reused_and_share_the_database() {
+        let pool = ConnectionPool::new().unwrap();
+        {
+            let conn = pool.get().unwrap();
+            conn.execute_batch("CREATE TABLE shared (x INTEGER); INSERT INTO shared VALUES (42)")
+                .unwrap();
+        }
+        assert_eq!(idle_count(&pool), 1);
+
+        let first = pool.get().unwrap();
+        let second = pool.get().unwrap();
+        assert_eq!(idle_count(&pool), 0);
+        let x: i32 = second
+            .query_row("SELECT x FROM shared", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(x, 42);
+
+        drop(first);
+        drop(second);
+        assert_eq!(idle_count(&pool), 2);
+    }
+}
@@ -0,0 +1,124 @@
+//! Built-in starter rules.
+//!
+//! A curated set of CloudTrail, Windows and Suricata detection rules is
+//! compiled into the binary (from `default_rules/`) and written to the rules
+//! directory on first run, so a new installation doesn't start with an empty
+//! rule list.
+//! They can be reinstalled later, e.g. to restore rules that were edited.
+
+use crate::models::{RuleYaml, SiemError};
+use crate::rule_manager::{self, ImportSummary};
+
+macro_rules! rule {
+    ($file:literal) => {
+        ($file, include_str!(concat!("../default_rules/", $file)))
+    };
+}
+
+/// File name and YAML content of each starter rule.
+const DEFAULT_RULES: [(&str, &str); 19] = [
+    rule!("aws_cloudtrail_tampering.yaml"),
+    rule!("aws_console_login_brute_force.yaml"),
+    rule!("aws_console_login_without_mfa.yaml"),
+    rule!("aws_guardduty_disabled.yaml"),
+    rule!("aws_iam_access_key_created.yaml"),
+    rule!("aws_iam_admin_policy_attached.yaml"),
+    rule!("aws_root_account_usage.yaml"),
+    rule!("aws_s3_bucket_made_public.yaml"),
+    rule!("aws_security_group_open_to_world.yaml"),
+    rule!("suricata_dns_onion_lookup.yaml"),
+    rule!("suricata_high_severity_alert.yaml"),
+    rule!("suricata_ssh_nonstandard_port.yaml"),
+    rule!("suricata_tls_deprecated_version.yaml"),
+    rule!("win_local_account_created.yaml"),
+    rule!("win_logon_brute_force.yaml"),
+    rule!("win_scheduled_task_created.yaml"),
+    rule!("win_security_log_cleared.yaml"),
+    rule!("win_service_installed.yaml"),
+    rule!("win_user_added_to_admin_group.yaml"),
+];
+
+/// Parse the starter rules, with the file each comes from.
+fn default_rules() -> Vec<(&'static str, Result<RuleYaml, SiemError>)> {
+    DEFAULT_RULES
+        .iter()
+        .map(|(file, content)| {
+            let rule = serde_yaml::from_str(content)
+                .map_err(|e| SiemError::Serialization(format!("Cannot parse YAML: {}", e)));
+            (*file, rule)
+        })
+        .collect()
+}
+
+/// Write the starter rules to the rules directory. Rules that already exist
+/// are skipped unless `overwrite` is set.
+pub fn install_default_rules(
+    app_handle: &tauri::AppHandle,
+    overwrite: bool,
+) -> Result<ImportSummary, SiemError> {
+    let rules_dir = rule_manager::get_rules_dir(app_handle)?;
+    let mut summary = ImportSummary {
+        success_count: 0,
+        skipped: Vec::new(),
+        errors: Vec::new(),
+    };
+
+    for (file, rule) in default_rules() {
+        let rule = match rule {
+            Ok(rule) => rule,
+            Err(e) => {
+                summary.errors.push(format!("{}: {}", file, e));
+                continue;
+            }
+        };
+
+        if rules_dir.join(format!("{}.yaml", rule.id)).exists() && !overwrite {
+            summary.skipped.push(rule.id);
+            continue;
+        }
+
+        match rule_manager::save_rule(app_handle, rule) {
+            Ok(_) => summary.success_count += 1,
+            Err(e) => summary.errors.push(format!("{}: {}", file, e)),
+        }
+    }
+
+    Ok(summary)
+}
+
+/// Install the starter rules on first run: once, and only if the rules
+/// directory is empty, so rules a user deleted don't come back.
+pub fn install_on_first_run(app_handle: &tauri::AppHandle) -> Result<(), SiemError> {
+    let mut config = crate::config::load_config(app_handle)?;
+    if config.default_rules_installed {
+        return Ok(());
+    }
+
+    if rule_manager::list_rules(app_handle)?.is_empty() {
+        install_default_rules(app_handle, false)?;
+    }
+    config.default_rules_installed = true;
+    crate::config::save_config(app_handle, &config)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_rules_are_valid() {
+        let rules = default_rules();
+        let mut ids = std::collections::HashSet::new();
+        for (file, rule) in rules {
+            let rule = rule.unwrap_or_else(|e| panic!("{}: {}", file, e));
+            assert!(ids.insert(rule.id.clone()), "{}: duplicate ID", file);
+            assert!(
+                rule_manager::RULE_STATUSES.contains(&rule.status.as_str()),
+                "{}: invalid status",
+                file
+            );
+            let validation = crate::test_rule::validate_condition(&rule.detection.condition);
+            assert!(validation.valid, "{}: {:?}", file, validation.error_message);
+        }
+    }
+}
@@ -0,0 +1,208 @@
+//! Entity pivot summaries.
+//!
+//! Gathers what a log file says about one entity (a user ARN, an IP address,
+//! an access key, ...) in a single pass, for the pivot workflow of an
+//! investigation: the events where a field has the entity's value, their
+//! counts over time, the actions taken, the users and IP addresses involved
+//! and when the entity was first and last seen.
+
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+
+use crate::db_engine;
+use crate::event_time;
+use crate::models::{EntitySummary, FieldValueCount, LogType, TimelineBucket};
+use crate::timeline::TimelineBuilder;
+
+/// Related values listed per category, at most.
+const MAX_RELATED_VALUES: usize = 20;
+
+/// Fields naming the action of an event.
+fn action_fields(log_type: &LogType) -> &'static [&'static str] {
+    match log_type {
+        LogType::CloudTrail => &["eventName"],
+        // Alerts by signature, other records by their type
+        LogType::Suricata => &["alert.signature", "event_type"],
+        LogType::AzureActivity => &["operationName.value", "operationName"],
+        LogType::EntraSignIn => &["appDisplayName", "properties.appDisplayName"],
+        LogType::Okta => &["eventType"],
+        LogType::Windows | LogType::WindowsXml => &["EventID"],
+        LogType::FlatJson | LogType::Kv | LogType::Plugin(_) | LogType::Text(_) => &[
+            "action",
+            "eventName",
+            "event_type",
+            "event.action",
+            "EventID",
+        ],
+    }
+}
+
+/// Fields naming the user of an event.
+fn user_fields(log_type: &LogType) -> &'static [&'static str] {
+    match log_type {
+        LogType::CloudTrail => &["userIdentity.arn"],
+        LogType::Suricata => &["krb5.cname", "smb.ntlmssp.user", "ftp.user"],
+        LogType::AzureActivity => &["caller"],
+        LogType::EntraSignIn => &["userPrincipalName", "properties.userPrincipalName"],
+        LogType::Okta => &["actor.alternateId"],
+        LogType::Windows | LogType::WindowsXml => &["User", "TargetUserName", "SubjectUserName"],
+        LogType::FlatJson | LogType::Kv | LogType::Plugin(_) | LogType::Text(_) => {
+            &["user", "userName", "username", "user_name", "user.name"]
+        }
+    }
+}
+
+/// Values of the first of `fields` the event has.
+fn first_field_values(event: &serde_json::Value, fields: &[&str]) -> Vec<String> {
+    fields
+        .iter()
+        .map(|field| db_engine::get_field_values(event, field))
+        .find(|values| !values.is_empty())
+        .unwrap_or_default()
+}
+
+/// Value counts, most frequent first, without the entity's own value.
+fn top_values(counts: HashMap<String, usize>, exclude: &str) -> Vec<FieldValueCount> {
+    let mut values: Vec<FieldValueCount> = counts
+        .into_iter()
+        .filter(|(value, _)| value != exclude)
+        .map(|(value, count)| FieldValueCount { value, count })
+        .collect();
+    values.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.value.cmp(&b.value)));
+    values.truncate(MAX_RELATED_VALUES);
+    values
+}
+
+/// Accumulates the summary of an entity over a stream of events.
+pub struct EntitySummaryBuilder<'a> {
+    field: &'a str,
+    value: &'a str,
+    log_type: &'a LogType,
+    ip_fields: &'a [String],
+    timeline: TimelineBuilder<'a>,
+    total_events: usize,
+    first_seen: Option<DateTime<Utc>>,
+    last_seen: Option<DateTime<Utc>>,
+    actions: HashMap<String, usize>,
+    users: HashMap<String, usize>,
+    ips: HashMap<String, usize>,
+}
+
+impl<'a> EntitySummaryBuilder<'a> {
+    pub fn new(
+        field: &'a str,
+        value: &'a str,
+        log_type: &'a LogType,
+        ip_fields: &'a [String],
+        bucket: TimelineBucket,
+    ) -> Self {
+        EntitySummaryBuilder {
+            field,
+            value,
+            log_type,
+            ip_fields,
+            timeline: TimelineBuilder::new(log_type, bucket, None),
+            total_events: 0,
+            first_seen: None,
+            last_seen: None,
+            actions: HashMap::new(),
+            users: HashMap::new(),
+            ips: HashMap::new(),
+        }
+    }
+
+    pub fn observe(&mut self, event: &serde_json::Value) {
+        self.total_events += 1;
+        if !db_engine::get_field_values(event, self.field)
+            .iter()
+            .any(|value| value == self.value)
+        {
+            return;
+        }
+
+        self.timeline.observe(event);
+        if let Some(ts) = event_time::event_time(event, None, self.log_type) {
+            self.first_seen = Some(self.first_seen.map_or(ts, |first| first.min(ts)));
+            self.last_seen = Some(self.last_seen.map_or(ts, |last| last.max(ts)));
+        }
+
+        for action in first_field_values(event, action_fields(self.log_type)) {
+            *self.actions.entry(action).or_default() += 1;
+        }
+        for user in first_field_values(event, user_fields(self.log_type)) {
+            *self.users.entry(user).or_default() += 1;
+        }
+        for field in self.ip_fields {
+            for ip in db_engine::get_field_values(event, field) {
+                *self.ips.entry(ip).or_default() += 1;
+            }
+        }
+    }
+
+    pub fn finish(self) -> EntitySummary {
+        let timeline = self.timeline.finish();
+        EntitySummary {
+            field: self.field.to_string(),
+            value: self.value.to_string(),
+            event_count: timeline.total_events,
+            total_events: self.total_events,
+            first_seen: self.first_seen.map(|ts| ts.to_rfc3339()),
+            last_seen: self.last_seen.map(|ts| ts.to_rfc3339()),
+            timeline,
+            distinct_actions: self.actions.len(),
+            actions: top_values(self.actions, ""),
+            related_users: top_values(self.users, self.value),
+            related_ips: top_values(self.ips, self.value),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_entity_summary() {
+        let events = [
+            json!({ "eventTime": "2025-12-16T11:05:00Z", "eventName": "ConsoleLogin",
+                    "sourceIPAddress": "203.0.113.7", "userIdentity": { "arn": "arn:alice" } }),
+            json!({ "eventTime": "2025-12-16T11:20:00Z", "eventName": "ListBuckets",
+                    "sourceIPAddress": "203.0.113.7", "userIdentity": { "arn": "arn:alice" } }),
+            json!({ "eventTime": "2025-12-16T13:00:00Z", "eventName": "ConsoleLogin",
+                    "sourceIPAddress": "203.0.113.7", "userIdentity": { "arn": "arn:bob" } }),
+            json!({ "eventTime": "2025-12-16T14:00:00Z", "eventName": "ConsoleLogin",
+                    "sourceIPAddress": "198.51.100.1", "userIdentity": { "arn": "arn:alice" } }),
+        ];
+        let ip_fields = vec!["sourceIPAddress".to_string()];
+
+        let mut builder = EntitySummaryBuilder::new(
+            "sourceIPAddress",
+            "203.0.113.7",
+            &LogType::CloudTrail,
+            &ip_fields,
+            TimelineBucket::Hour,
+        );
+        events.iter().for_each(|event| builder.observe(event));
+        let summary = builder.finish();
+
+        assert_eq!(summary.event_count, 3);
+        assert_eq!(summary.total_events, 4);
+        assert_eq!(
+            summary.first_seen.as_deref(),
+            Some("2025-12-16T11:05:00+00:00")
+        );
+        assert_eq!(
+            summary.last_seen.as_deref(),
+            Some("2025-12-16T13:00:00+00:00")
+        );
+        assert_eq!(summary.timeline.buckets.len(), 2);
+        assert_eq!(summary.distinct_actions, 2);
+        assert_eq!(summary.actions[0].value, "ConsoleLogin");
+        assert_eq!(summary.actions[0].count, 2);
+        assert_eq!(summary.related_users[0].value, "arn:alice");
+        assert_eq!(summary.related_users.len(), 2);
+        // The entity itself isn't listed as related
+        assert!(summary.related_ips.is_empty());
+    }
+}
@@ -0,0 +1,220 @@
+//! Paged reading of a log file's events for the event viewer.
+//!
+//! The viewer scrolls through files of millions of events one page at a
+//! time, so only the requested page is kept and sent over IPC. Events can be
+//! restricted by a condition and sorted by a field: sorting reads the file
+//! twice, first keeping only each matching event's sort key to rank them,
+//! then picking the events of the page.
+
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+use crate::aggregation;
+use crate::db_engine;
+use crate::models::{EventPage, SiemError};
+
+/// Sort value of an event: numbers before text, events without the field last.
+#[derive(Debug, PartialEq, PartialOrd)]
+enum SortKey {
+    Number(f64),
+    Text(String),
+}
+
+impl SortKey {
+    fn of(event: &serde_json::Value, field: &str) -> Option<Self> {
+        let value = aggregation::group_value(event, field);
+        if value.is_empty() {
+            return None;
+        }
+        Some(match value.parse::<f64>() {
+            Ok(number) if number.is_finite() => SortKey::Number(number),
+            _ => SortKey::Text(value),
+        })
+    }
+}
+
+/// Order two optional keys, keeping missing keys last in either direction.
+fn compare_keys(a: &Option<SortKey>, b: &Option<SortKey>, descending: bool) -> Ordering {
+    match (a, b) {
+        (Some(a), Some(b)) => {
+            let ordering = a.partial_cmp(b).unwrap_or(Ordering::Equal);
+            if descending {
+                ordering.reverse()
+            } else {
+                ordering
+            }
+        }
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => Ordering::Equal,
+    }
+}
+
+/// Read one page of events. `stream` feeds the file's events in chunks to
+/// the callback it is given, and is called twice when sorting.
+/// `sort_field` sorts ascending, or descending with a leading `-`
+/// (`-eventTime`); `filter` is a condition the events must match.
+pub fn read_page<S>(
+    mut stream: S,
+    offset: usize,
+    limit: usize,
+    sort_field: Option<&str>,
+    filter: Option<&str>,
+) -> Result<EventPage, SiemError>
+where
+    S: FnMut(&mut dyn FnMut(Vec<serde_json::Value>)) -> Result<usize, SiemError>,
+{
+    let limit = limit.clamp(1, db_engine::MAX_PAGE_SIZE);
+    let filter = filter.filter(|f| !f.trim().is_empty());
+    if let Some(filter) = filter {
+        db_engine::resolve_condition_term_sets(filter, db_engine::CaseMode::Default)?;
+    }
+    let matches = |event: &serde_json::Value| {
+        filter.is_none_or(|filter| db_engine::matches_condition(event, filter))
+    };
+    let sort = sort_field
+        .map(str::trim)
+        .filter(|field| !field.is_empty())
+        .map(|field| match field.strip_prefix('-') {
+            Some(field) => (field, true),
+            None => (field, false),
+        });
+
+    let mut matched_events = 0;
+    let mut events = Vec::new();
+    let total_events = match sort {
+        None => stream(&mut |chunk| {
+            for event in chunk {
+                if !matches(&event) {
+                    continue;
+                }
+                if matched_events >= offset && events.len() < limit {
+                    events.push(event);
+                }
+                matched_events += 1;
+            }
+        })?,
+        Some((field, descending)) => {
+            // First pass: rank the matching events by their sort key
+            let mut keys: Vec<(Option<SortKey>, usize)> = Vec::new();
+            stream(&mut |chunk| {
+                for event in chunk {
+                    if matches(&event) {
+                        keys.push((SortKey::of(&event, field), keys.len()));
+                    }
+                }
+            })?;
+            matched_events = keys.len();
+            keys.sort_by(|a, b| compare_keys(&a.0, &b.0, descending).then(a.1.cmp(&b.1)));
+
+            // Second pass: pick the events of the page, in rank order
+            let slots: HashMap<usize, usize> = keys
+                .into_iter()
+                .skip(offset)
+                .take(limit)
+                .enumerate()
+                .map(|(slot, (_, position))| (position, slot))
+                .collect();
+            let mut page: Vec<Option<serde_json::Value>> = vec![None; slots.len()];
+            let mut position = 0;
+            let total = stream(&mut |chunk| {
+                for event in chunk {
+                    if !matches(&event) {
+                        continue;
+                    }
+                    if let Some(slot) = slots.get(&position) {
+                        page[*slot] = Some(event);
+                    }
+                    position += 1;
+                }
+            })?;
+            events = page.into_iter().flatten().collect();
+            total
+        }
+    };
+
+    Ok(EventPage {
+        has_more: offset + events.len() < matched_events,
+        events,
+        offset,
+        limit,
+        matched_events,
+        total_events,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn page(
+        events: &[serde_json::Value],
+        offset: usize,
+        limit: usize,
+        sort_field: Option<&str>,
+        filter: Option<&str>,
+    ) -> EventPage {
+        let stream = |observe: &mut dyn FnMut(Vec<serde_json::Value>)| {
+            for chunk in events.chunks(2) {
+                observe(chunk.to_vec());
+            }
+            Ok(events.len())
+        };
+        read_page(stream, offset, limit, sort_field, filter).unwrap()
+    }
+
+    fn ids(page: &EventPage) -> Vec<i64> {
+        page.events
+            .iter()
+            .map(|event| event["id"].as_i64().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_pages_sorted_and_filtered() {
+        let events: Vec<serde_json::Value> = [
+            (1, "GetObject", 30),
+            (2, "ConsoleLogin", 5),
+            (3, "GetObject", 200),
+            (4, "PutObject", 7),
+            (5, "GetObject", 10),
+        ]
+        .iter()
+        .map(|(id, name, bytes)| serde_json::json!({ "id": id, "eventName": name, "bytes": bytes }))
+        .chain(std::iter::once(
+            serde_json::json!({ "id": 6, "eventName": "GetObject" }),
+        ))
+        .collect();
+
+        // File order
+        let first = page(&events, 0, 4, None, None);
+        assert_eq!(ids(&first), vec![1, 2, 3, 4]);
+        assert!(first.has_more);
+        let last = page(&events, 4, 4, None, None);
+        assert_eq!(ids(&last), vec![5, 6]);
+        assert!(!last.has_more);
+
+        // Numeric sort, events without the field last in both directions
+        assert_eq!(
+            ids(&page(&events, 0, 10, Some("bytes"), None)),
+            vec![2, 4, 5, 1, 3, 6]
+        );
+        assert_eq!(
+            ids(&page(&events, 1, 3, Some("-bytes"), None)),
+            vec![1, 5, 4]
+        );
+
+        // Filtered and sorted
+        let filtered = page(
+            &events,
+            0,
+            2,
+            Some("-bytes"),
+            Some("eventName = 'GetObject'"),
+        );
+        assert_eq!(ids(&filtered), vec![3, 1]);
+        assert_eq!(filtered.matched_events, 4);
+        assert_eq!(filtered.total_events, 6);
+        assert!(filtered.has_more);
+    }
+}
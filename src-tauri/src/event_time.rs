@@ -0,0 +1,217 @@
+//! Event timestamp extraction.
+//!
+//! Locates and parses the timestamp of a log event, using the well-known
+//! timestamp field of each log type (e.g. `eventTime` for CloudTrail) or an
+//! explicitly configured field.
+
+use chrono::{DateTime, NaiveDateTime, TimeZone, Utc};
+
+use crate::models::{LogType, SiemError, TimeRange};
+
+/// Timestamp fields tried, in order, for flat JSON logs.
+const COMMON_TIMESTAMP_FIELDS: [&str; 7] = [
+    "@timestamp",
+    "timestamp",
+    "eventTime",
+    "time",
+    "ts",
+    "datetime",
+    "date",
+];
+
+/// Get the candidate timestamp fields for a log type.
+pub fn default_timestamp_fields(log_type: &LogType) -> &'static [&'static str] {
+    match log_type {
+        LogType::CloudTrail => &["eventTime"],
+        LogType::Suricata => &["timestamp"],
+        // REST/Graph exports, then diagnostic settings exports
+        LogType::AzureActivity => &["eventTimestamp", "time", "submissionTimestamp"],
+        LogType::EntraSignIn => &["createdDateTime", "time"],
+        LogType::Okta => &["published"],
+        LogType::Windows | LogType::WindowsXml => &["TimeCreated", "@timestamp", "UtcTime"],
+        LogType::FlatJson | LogType::Kv | LogType::Plugin(_) | LogType::Text(_) => {
+            &COMMON_TIMESTAMP_FIELDS
+        }
+    }
+}
+
+/// Get the timestamp of an event.
+/// Uses `field` if given, otherwise the default fields for the log type.
+pub fn event_time(
+    event: &serde_json::Value,
+    field: Option<&str>,
+    log_type: &LogType,
+) -> Option<DateTime<Utc>> {
+    match field {
+        Some(field) => lookup_path(event, field).and_then(parse_timestamp),
+        None => default_timestamp_fields(log_type)
+            .iter()
+            .find_map(|f| lookup_path(event, f).and_then(parse_timestamp)),
+    }
+}
+
+/// Find the default timestamp field that holds a parseable timestamp in an
+/// event, for logs whose timestamp field isn't known up front.
+pub fn detect_timestamp_field(
+    event: &serde_json::Value,
+    log_type: &LogType,
+) -> Option<&'static str> {
+    default_timestamp_fields(log_type)
+        .iter()
+        .copied()
+        .find(|f| lookup_path(event, f).and_then(parse_timestamp).is_some())
+}
+
+/// Parse a JSON timestamp value.
+/// Supports RFC 3339 strings, "YYYY-MM-DD HH:MM:SS[.f]" (assumed UTC),
+/// and epoch seconds or milliseconds.
+pub fn parse_timestamp(value: &serde_json::Value) -> Option<DateTime<Utc>> {
+    match value {
+        serde_json::Value::String(s) => parse_timestamp_str(s),
+        serde_json::Value::Number(n) => {
+            let n = n.as_f64()?;
+            // Values this large are milliseconds since epoch
+            let millis = if n.abs() > 1e11 { n } else { n * 1000.0 };
+            Utc.timestamp_millis_opt(millis as i64).single()
+        }
+        _ => None,
+    }
+}
+
+/// Parse a timestamp string.
+pub fn parse_timestamp_str(s: &str) -> Option<DateTime<Utc>> {
+    let s = s.trim();
+
+    if let Ok(dt) = DateTime::parse_from_rfc3339(s) {
+        return Some(dt.with_timezone(&Utc));
+    }
+
+    for format in ["%Y-%m-%d %H:%M:%S%.f", "%Y-%m-%dT%H:%M:%S%.f"] {
+        if let Ok(naive) = NaiveDateTime::parse_from_str(s, format) {
+            return Some(Utc.from_utc_datetime(&naive));
+        }
+    }
+
+    None
+}
+
+/// Keeps the events whose timestamp falls within a time range.
+pub struct TimeFilter {
+    start: Option<DateTime<Utc>>,
+    end: Option<DateTime<Utc>>,
+    field: Option<String>,
+    log_type: LogType,
+}
+
+impl TimeFilter {
+    /// Parse a time range; `None` when it has no bounds.
+    pub fn new(range: &TimeRange, log_type: &LogType) -> Result<Option<Self>, SiemError> {
+        let parse_bound = |bound: &Option<String>| {
+            bound
+                .as_deref()
+                .map(|s| {
+                    parse_timestamp_str(s)
+                        .ok_or_else(|| SiemError::Query(format!("Invalid time bound '{}'", s)))
+                })
+                .transpose()
+        };
+        let start = parse_bound(&range.start_time)?;
+        let end = parse_bound(&range.end_time)?;
+        if let (Some(start), Some(end)) = (start, end) {
+            if start > end {
+                return Err(SiemError::Query(
+                    "Time range start is after its end".to_string(),
+                ));
+            }
+        }
+        if start.is_none() && end.is_none() {
+            return Ok(None);
+        }
+
+        Ok(Some(TimeFilter {
+            start,
+            end,
+            field: range.timestamp_field.clone(),
+            log_type: log_type.clone(),
+        }))
+    }
+
+    /// Whether an event's timestamp is within the range.
+    pub fn contains(&self, event: &serde_json::Value) -> bool {
+        event_time(event, self.field.as_deref(), &self.log_type).is_some_and(|ts| {
+            self.start.is_none_or(|start| ts >= start) && self.end.is_none_or(|end| ts <= end)
+        })
+    }
+
+    /// Drop the events outside the range.
+    pub fn retain(&self, events: &mut Vec<serde_json::Value>) {
+        events.retain(|event| self.contains(event));
+    }
+}
+
+/// Follow a dot-separated path through nested JSON objects.
+fn lookup_path<'a>(event: &'a serde_json::Value, path: &str) -> Option<&'a serde_json::Value> {
+    path.split('.')
+        .try_fold(event, |current, part| current.get(part))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cloudtrail_event_time() {
+        let event = serde_json::json!({ "eventTime": "2025-12-16T11:05:00Z" });
+        let ts = event_time(&event, None, &LogType::CloudTrail).unwrap();
+        assert_eq!(ts.to_rfc3339(), "2025-12-16T11:05:00+00:00");
+    }
+
+    #[test]
+    fn test_flat_json_fallback_fields_and_epoch() {
+        let event = serde_json::json!({ "ts": 1765883100 });
+        let ts = event_time(&event, None, &LogType::FlatJson).unwrap();
+        assert_eq!(ts.timestamp(), 1765883100);
+
+        let event = serde_json::json!({ "meta": { "created": 1765883100000u64 } });
+        let ts = event_time(&event, Some("meta.created"), &LogType::FlatJson).unwrap();
+        assert_eq!(ts.timestamp(), 1765883100);
+    }
+
+    #[test]
+    fn test_naive_timestamp_string() {
+        let ts = parse_timestamp_str("2025-12-16 11:05:00.250").unwrap();
+        assert_eq!(ts.timestamp_millis(), 1765883100250);
+        assert!(parse_timestamp_str("yesterday").is_none());
+    }
+
+    #[test]
+    fn test_time_filter_bounds() {
+        let range = TimeRange {
+            start_time: Some("2025-12-16T10:00:00Z".to_string()),
+            end_time: Some("2025-12-16 12:00:00".to_string()),
+            timestamp_field: None,
+        };
+        let filter = TimeFilter::new(&range, &LogType::CloudTrail)
+            .unwrap()
+            .unwrap();
+        let mut events = vec![
+            serde_json::json!({ "eventTime": "2025-12-16T09:59:59Z" }),
+            serde_json::json!({ "eventTime": "2025-12-16T10:00:00Z" }),
+            serde_json::json!({ "eventTime": "2025-12-16T12:00:00Z" }),
+            serde_json::json!({ "eventTime": "2025-12-16T12:00:01Z" }),
+            serde_json::json!({ "eventName": "NoTime" }),
+        ];
+        filter.retain(&mut events);
+        assert_eq!(events.len(), 2);
+
+        assert!(TimeFilter::new(&TimeRange::default(), &LogType::FlatJson)
+            .unwrap()
+            .is_none());
+        let reversed = TimeRange {
+            start_time: range.end_time.clone(),
+            end_time: range.start_time.clone(),
+            timestamp_field: None,
+        };
+        assert!(TimeFilter::new(&reversed, &LogType::CloudTrail).is_err());
+    }
+}
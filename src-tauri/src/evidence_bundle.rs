@@ -0,0 +1,378 @@
+//! Chain-of-custody evidence bundles for legal hand-off.
+//!
+//! A bundle packages the alerts of a case or a scan (all of them, or those
+//! selected by `scan_history::alert_key`) into a ZIP archive:
+//!
+//! - `alerts.jsonl`: the alerts;
+//! - `evidence.jsonl`: their evidence events, one per line with the alert
+//!   they belong to, and for a case the events attached to it;
+//! - `case.json` or `scan.json`: the case, or the scan's history entry;
+//! - `manifest.json`: who exported the bundle and when, the SHA-256 of every
+//!   source log file (recorded on import and recomputed at export, see
+//!   `log_hashes`) and of every other file of the bundle;
+//! - `manifest.sig`: with a signing key, the HMAC-SHA256 of `manifest.json`,
+//!   so anyone holding the key can check that the manifest (and through its
+//!   hashes, the whole bundle) is what was exported.
+
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeSet;
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+
+use crate::app_data;
+use crate::case_manager;
+use crate::log_hashes;
+use crate::models::{AlertEvent, CaseEvent, SiemError};
+use crate::scan_history;
+
+/// Version of the bundle layout, in the manifest.
+const BUNDLE_FORMAT: &str = "offline-siem-evidence/1";
+
+/// Settings of an evidence bundle export.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct EvidenceBundleOptions {
+    /// Name of the analyst handing the evidence over
+    pub analyst: String,
+    /// HMAC key signing the manifest; none leaves the bundle unsigned
+    #[serde(default)]
+    pub signing_key: Option<String>,
+    /// Alerts to include (`scan_history::alert_key`); none includes them all
+    #[serde(default)]
+    pub alert_keys: Option<Vec<String>>,
+}
+
+/// Hashes of a source log file of the evidence.
+#[derive(Debug, Serialize, Clone)]
+pub struct SourceFileHash {
+    pub path: String,
+    /// SHA-256 recorded when the file entered the library
+    pub recorded_sha256: Option<String>,
+    /// When the recorded hash was computed (ISO 8601)
+    pub hashed_at: Option<String>,
+    /// SHA-256 at export; None if the file is no longer available
+    pub current_sha256: Option<String>,
+    /// Whether both hashes are known and equal
+    pub intact: bool,
+}
+
+/// A file of the bundle, as listed in the manifest.
+#[derive(Debug, Serialize, Clone)]
+struct BundleFile {
+    name: String,
+    sha256: String,
+    size_bytes: usize,
+}
+
+/// Where the evidence comes from.
+#[derive(Debug, Serialize, Clone)]
+struct BundleSource {
+    /// "case" or "scan"
+    kind: &'static str,
+    id: String,
+}
+
+#[derive(Debug, Serialize, Clone)]
+struct Manifest {
+    format: &'static str,
+    source: BundleSource,
+    analyst: String,
+    exported_at: String,
+    alert_count: usize,
+    evidence_event_count: usize,
+    source_files: Vec<SourceFileHash>,
+    files: Vec<BundleFile>,
+}
+
+/// Summary of an evidence bundle export.
+#[derive(Debug, Serialize, Clone)]
+pub struct EvidenceBundleSummary {
+    pub dest_path: String,
+    /// Names of the files written into the bundle
+    pub files: Vec<String>,
+    pub alert_count: usize,
+    /// SHA-256 of `manifest.json`, to note in the hand-off paperwork
+    pub manifest_sha256: String,
+    pub signed: bool,
+}
+
+fn sha256_hex(content: &[u8]) -> String {
+    app_data::hex(&Sha256::digest(content))
+}
+
+fn to_json<T: Serialize>(value: &T) -> Result<Vec<u8>, SiemError> {
+    serde_json::to_vec_pretty(value)
+        .map_err(|e| SiemError::Serialization(format!("Cannot serialize evidence: {}", e)))
+}
+
+/// HMAC-SHA256 of a manifest as hex.
+fn sign(key: &str, manifest: &[u8]) -> Result<String, SiemError> {
+    if key.is_empty() {
+        return Err(SiemError::Query("Signing key cannot be empty".to_string()));
+    }
+    let mut mac = Hmac::<Sha256>::new_from_slice(key.as_bytes())
+        .map_err(|e| SiemError::Query(format!("Invalid signing key: {}", e)))?;
+    mac.update(manifest);
+    Ok(app_data::hex(&mac.finalize().into_bytes()))
+}
+
+/// Hashes of the source log files, recorded and current.
+fn source_file_hashes(
+    app_handle: &tauri::AppHandle,
+    paths: &BTreeSet<String>,
+) -> Vec<SourceFileHash> {
+    let records = log_hashes::load_records(app_handle);
+    paths
+        .iter()
+        .map(|path| {
+            let filename = Path::new(path)
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default();
+            let record = records.get(&filename);
+            let current_sha256 = log_hashes::hash_file(Path::new(path)).ok();
+            SourceFileHash {
+                path: path.clone(),
+                intact: record.is_some_and(|r| current_sha256.as_ref() == Some(&r.sha256)),
+                recorded_sha256: record.map(|r| r.sha256.clone()),
+                hashed_at: record.map(|r| r.hashed_at.clone()),
+                current_sha256,
+            }
+        })
+        .collect()
+}
+
+/// The files of a bundle: the content files, the manifest listing their
+/// hashes and, with a signing key, the manifest's signature.
+fn bundle_entries(
+    source: BundleSource,
+    source_json: Vec<u8>,
+    alerts: &[AlertEvent],
+    case_events: &[CaseEvent],
+    source_files: Vec<SourceFileHash>,
+    analyst: &str,
+    signing_key: Option<&str>,
+) -> Result<Vec<(String, Vec<u8>)>, SiemError> {
+    let mut alerts_jsonl = Vec::new();
+    let mut evidence_jsonl = Vec::new();
+    let mut evidence_event_count = 0;
+    for alert in alerts {
+        let key = scan_history::alert_key(alert);
+        serde_json::to_writer(&mut alerts_jsonl, alert)
+            .map_err(|e| SiemError::Serialization(e.to_string()))?;
+        alerts_jsonl.push(b'\n');
+        for event in &alert.evidence {
+            let line = serde_json::json!({
+                "alert_key": key,
+                "rule_id": alert.rule_id,
+                "source_file": alert.source_file,
+                "event": event,
+            });
+            evidence_jsonl.extend(line.to_string().into_bytes());
+            evidence_jsonl.push(b'\n');
+            evidence_event_count += 1;
+        }
+    }
+    for attached in case_events {
+        let line = serde_json::json!({
+            "source_file": attached.file_path,
+            "record_index": attached.record_index,
+            "note": attached.note,
+            "event": attached.event,
+        });
+        evidence_jsonl.extend(line.to_string().into_bytes());
+        evidence_jsonl.push(b'\n');
+        evidence_event_count += 1;
+    }
+
+    let mut entries = vec![
+        ("alerts.jsonl".to_string(), alerts_jsonl),
+        ("evidence.jsonl".to_string(), evidence_jsonl),
+        (format!("{}.json", source.kind), source_json),
+    ];
+
+    let manifest = Manifest {
+        format: BUNDLE_FORMAT,
+        source,
+        analyst: analyst.to_string(),
+        exported_at: chrono::Utc::now().to_rfc3339(),
+        alert_count: alerts.len(),
+        evidence_event_count,
+        source_files,
+        files: entries
+            .iter()
+            .map(|(name, content)| BundleFile {
+                name: name.clone(),
+                sha256: sha256_hex(content),
+                size_bytes: content.len(),
+            })
+            .collect(),
+    };
+    let manifest = to_json(&manifest)?;
+    if let Some(key) = signing_key {
+        entries.push((
+            "manifest.sig".to_string(),
+            sign(key, &manifest)?.into_bytes(),
+        ));
+    }
+    entries.push(("manifest.json".to_string(), manifest));
+    Ok(entries)
+}
+
+/// Export the evidence of a case, or of a scan, as a bundle. The scan
+/// history keeps no evidence events, so the alerts of a scan are those given
+/// (as shown after the scan).
+pub fn export_evidence_bundle(
+    app_handle: &tauri::AppHandle,
+    case_id: Option<&str>,
+    scan_id: Option<&str>,
+    scan_alerts: Vec<AlertEvent>,
+    dest_path: &str,
+    options: &EvidenceBundleOptions,
+) -> Result<EvidenceBundleSummary, SiemError> {
+    use zip::write::FileOptions;
+
+    if options.analyst.trim().is_empty() {
+        return Err(SiemError::Query(
+            "The analyst exporting the evidence must be named".to_string(),
+        ));
+    }
+
+    let (source, source_json, mut alerts, case_events, mut paths) = match (case_id, scan_id) {
+        (Some(case_id), None) => {
+            let case = case_manager::get_case(app_handle, case_id)?;
+            let paths: BTreeSet<String> = case
+                .log_files
+                .iter()
+                .chain(case.events.iter().map(|e| &e.file_path))
+                .cloned()
+                .collect();
+            let source = BundleSource {
+                kind: "case",
+                id: case.id.clone(),
+            };
+            (source, to_json(&case)?, case.alerts, case.events, paths)
+        }
+        (None, Some(scan_id)) => {
+            let entry = scan_history::load_history(app_handle)?
+                .into_iter()
+                .find(|entry| entry.scan_id == scan_id)
+                .ok_or_else(|| SiemError::Query(format!("Scan not found: {}", scan_id)))?;
+            let paths = entry.files.iter().cloned().collect();
+            let source = BundleSource {
+                kind: "scan",
+                id: entry.scan_id.clone(),
+            };
+            (source, to_json(&entry)?, scan_alerts, Vec::new(), paths)
+        }
+        _ => {
+            return Err(SiemError::Query(
+                "Export the evidence of either a case or a scan".to_string(),
+            ))
+        }
+    };
+
+    if let Some(keys) = &options.alert_keys {
+        alerts.retain(|alert| keys.contains(&scan_history::alert_key(alert)));
+    }
+    paths.extend(alerts.iter().filter_map(|alert| alert.source_file.clone()));
+
+    let source_files = source_file_hashes(app_handle, &paths);
+    let entries = bundle_entries(
+        source,
+        source_json,
+        &alerts,
+        &case_events,
+        source_files,
+        options.analyst.trim(),
+        options.signing_key.as_deref(),
+    )?;
+
+    let file = fs::File::create(dest_path)
+        .map_err(|e| SiemError::FileIO(format!("Cannot create ZIP file: {}", e)))?;
+    let mut zip = zip::ZipWriter::new(file);
+    let zip_options = FileOptions::default()
+        .compression_method(zip::CompressionMethod::Deflated)
+        .unix_permissions(0o644);
+
+    let mut files = Vec::new();
+    let mut manifest_sha256 = String::new();
+    for (name, content) in entries {
+        if name == "manifest.json" {
+            manifest_sha256 = sha256_hex(&content);
+        }
+        zip.start_file(name.as_str(), zip_options)
+            .map_err(|e| SiemError::FileIO(format!("Cannot add file to ZIP: {}", e)))?;
+        zip.write_all(&content)
+            .map_err(|e| SiemError::FileIO(format!("Cannot write to ZIP: {}", e)))?;
+        files.push(name);
+    }
+    zip.finish()
+        .map_err(|e| SiemError::FileIO(format!("Cannot finalize ZIP: {}", e)))?;
+
+    Ok(EvidenceBundleSummary {
+        dest_path: dest_path.to_string(),
+        signed: files.iter().any(|name| name == "manifest.sig"),
+        files,
+        alert_count: alerts.len(),
+        manifest_sha256,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bundle_manifest_hashes_and_signature() {
+        let alert: AlertEvent = serde_json::from_value(serde_json::json!({
+            "rule_id": "r1",
+            "rule_title": "Rule",
+            "severity": "high",
+            "timestamp": "2024-03-01T10:00:00Z",
+            "match_count": 2,
+            "evidence": [{ "eventName": "A" }, { "eventName": "B" }],
+            "source_file": "/logs/trail.json"
+        }))
+        .unwrap();
+        let source = BundleSource {
+            kind: "scan",
+            id: "s1".to_string(),
+        };
+        let entries = bundle_entries(
+            source,
+            b"{}".to_vec(),
+            &[alert],
+            &[],
+            Vec::new(),
+            "Alice",
+            Some("key"),
+        )
+        .unwrap();
+
+        let names: Vec<&str> = entries.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(
+            names,
+            vec![
+                "alerts.jsonl",
+                "evidence.jsonl",
+                "scan.json",
+                "manifest.sig",
+                "manifest.json"
+            ]
+        );
+
+        let manifest = &entries[4].1;
+        let parsed: serde_json::Value = serde_json::from_slice(manifest).unwrap();
+        assert_eq!(parsed["analyst"], "Alice");
+        assert_eq!(parsed["evidence_event_count"], 2);
+        assert_eq!(parsed["files"][1]["sha256"], sha256_hex(&entries[1].1));
+        assert_eq!(entries[3].1, sign("key", manifest).unwrap().into_bytes());
+        assert_ne!(
+            sign("other", manifest).unwrap(),
+            sign("key", manifest).unwrap()
+        );
+    }
+}
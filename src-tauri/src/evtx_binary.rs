@@ -0,0 +1,60 @@
+//! Binary Windows event log files (`.evtx`).
+//!
+//! Records are read with the `evtx` crate and written as NDJSON in the shape
+//! of `evtx_dump` (`{"Event": {"System": {...}, "EventData": {...}}}`), which
+//! the `Windows` log type normalizes (see `windows_events`). Records that
+//! can't be parsed, e.g. in a damaged chunk of a file copied from a live
+//! system, are skipped with a warning like `evtx_dump` does.
+
+use evtx::EvtxParser;
+use std::io::Write;
+use std::path::Path;
+
+use crate::models::SiemError;
+
+/// Convert an EVTX file to NDJSON, one event per line. Returns the number of
+/// events written.
+pub fn to_ndjson<W: Write>(path: &Path, mut writer: W) -> Result<usize, SiemError> {
+    let write_error = |e: std::io::Error| SiemError::FileIO(format!("Cannot write events: {}", e));
+    let mut parser = EvtxParser::from_path(path)
+        .map_err(|e| SiemError::FileIO(format!("Not a readable EVTX file: {}", e)))?;
+
+    let mut events = 0;
+    let mut skipped = 0;
+    for record in parser.records_json_value() {
+        match record {
+            Ok(record) => {
+                serde_json::to_writer(&mut writer, &record.data)
+                    .map_err(|e| SiemError::Serialization(e.to_string()))?;
+                writeln!(writer).map_err(write_error)?;
+                events += 1;
+            }
+            Err(_) => skipped += 1,
+        }
+    }
+    writer.flush().map_err(write_error)?;
+
+    if skipped > 0 {
+        eprintln!(
+            "Warning: Skipped {} unreadable record(s) of {:?}",
+            skipped, path
+        );
+    }
+    Ok(events)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rejects_non_evtx_file() {
+        let path = std::env::temp_dir().join("offline_siem_test_not_evtx.evtx");
+        std::fs::write(&path, "{\"EventID\": 4624}\n").unwrap();
+        let mut out = Vec::new();
+        let result = to_ndjson(&path, &mut out);
+        std::fs::remove_file(&path).unwrap();
+        assert!(result.is_err());
+        assert!(out.is_empty());
+    }
+}
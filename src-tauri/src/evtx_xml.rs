@@ -0,0 +1,209 @@
+//! Windows event logs exported as XML (`LogType::WindowsXml`).
+//!
+//! Event Viewer's "Save as XML" writes `<Events><Event>...</Event></Events>`
+//! and `wevtutil qe /f:xml` writes bare `<Event>` elements one after another.
+//! Each `<Event>` is converted to JSON in the shape of `evtx_dump`
+//! (`{"System": {...}, "EventData": {"Image": "..."}}`, `<Data Name="x">`
+//! becoming the field `x`) and then normalized like the other Windows
+//! exports (see `windows_events`), so the same rules apply to both. The file
+//! is read event by event, never whole.
+
+use quick_xml::events::{BytesStart, Event};
+use serde_json::{Map, Value};
+use std::io::BufRead;
+
+use crate::models::SiemError;
+use crate::windows_events;
+
+/// An element being read.
+#[derive(Default)]
+struct Element {
+    name: String,
+    attributes: Map<String, Value>,
+    children: Map<String, Value>,
+    text: String,
+}
+
+impl Element {
+    fn start(start: &BytesStart) -> Result<Self, SiemError> {
+        let mut attributes = Map::new();
+        for attribute in start.attributes() {
+            let attribute = attribute.map_err(xml_error)?;
+            let key = String::from_utf8_lossy(attribute.key.local_name().as_ref()).to_string();
+            let value = attribute.unescape_value().map_err(xml_error)?;
+            attributes.insert(key, Value::from(value.to_string()));
+        }
+        Ok(Element {
+            name: String::from_utf8_lossy(start.local_name().as_ref()).to_string(),
+            attributes,
+            ..Element::default()
+        })
+    }
+
+    /// The key this element is stored under in its parent: `<Data Name="x">`
+    /// is stored as `x`.
+    fn key(&self) -> String {
+        match (self.name.as_str(), self.attributes.get("Name")) {
+            ("Data", Some(Value::String(name))) => name.clone(),
+            _ => self.name.clone(),
+        }
+    }
+
+    fn into_value(self) -> Value {
+        let text = self.text.trim();
+        if self.name == "Data" && self.attributes.contains_key("Name") {
+            return Value::from(text);
+        }
+        if self.children.is_empty() && self.attributes.is_empty() {
+            return Value::from(text);
+        }
+
+        let mut object = self.attributes;
+        object.extend(self.children);
+        if !text.is_empty() {
+            object.insert("#text".to_string(), Value::from(text));
+        }
+        Value::Object(object)
+    }
+
+    /// Add a child, turning repeated children into an array.
+    fn add_child(&mut self, key: String, value: Value) {
+        match self.children.get_mut(&key) {
+            Some(Value::Array(values)) => values.push(value),
+            Some(existing) => {
+                let first = existing.take();
+                *existing = Value::Array(vec![first, value]);
+            }
+            None => {
+                self.children.insert(key, value);
+            }
+        }
+    }
+}
+
+fn xml_error(e: impl std::fmt::Display) -> SiemError {
+    SiemError::Query(format!("Failed to parse XML: {}", e))
+}
+
+/// Whether a file's content looks like an XML export of Windows events.
+pub fn is_event_xml(content: &str) -> bool {
+    let content = content.trim_start_matches('\u{feff}').trim_start();
+    content.starts_with('<') && content.contains("<Event")
+}
+
+/// Read the events of an XML export in chunks of at most `chunk_size`
+/// normalized events. Returns the number of events.
+pub fn stream_events<R, F>(
+    reader: R,
+    chunk_size: usize,
+    on_chunk: &mut F,
+) -> Result<usize, SiemError>
+where
+    R: BufRead,
+    F: FnMut(Vec<Value>),
+{
+    let mut reader = quick_xml::Reader::from_reader(reader);
+    let mut buf = Vec::new();
+    let mut stack: Vec<Element> = Vec::new();
+    let mut chunk = Vec::new();
+    let mut total = 0;
+
+    loop {
+        let closed = match reader.read_event_into(&mut buf).map_err(xml_error)? {
+            Event::Start(start) => {
+                stack.push(Element::start(&start)?);
+                None
+            }
+            Event::Empty(start) => Some(Element::start(&start)?),
+            Event::End(_) => stack.pop(),
+            Event::Text(text) => {
+                if let Some(current) = stack.last_mut() {
+                    current.text.push_str(&text.unescape().map_err(xml_error)?);
+                }
+                None
+            }
+            Event::CData(data) => {
+                if let Some(current) = stack.last_mut() {
+                    current.text.push_str(&String::from_utf8_lossy(&data));
+                }
+                None
+            }
+            Event::Eof => break,
+            _ => None,
+        };
+
+        if let Some(element) = closed {
+            if element.name == "Event" {
+                // Events are emitted as soon as they close, never kept in <Events>
+                chunk.push(windows_events::normalize(element.into_value()));
+                total += 1;
+                if chunk.len() >= chunk_size {
+                    on_chunk(std::mem::take(&mut chunk));
+                }
+            } else if let Some(parent) = stack.last_mut() {
+                let key = element.key();
+                parent.add_child(key, element.into_value());
+            }
+        }
+        buf.clear();
+    }
+
+    if !chunk.is_empty() {
+        on_chunk(chunk);
+    }
+    Ok(total)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stream_xml_export() {
+        let xml = r#"<?xml version="1.0" encoding="utf-8"?>
+<Events>
+<Event xmlns="http://schemas.microsoft.com/win/2004/08/events/event">
+  <System>
+    <Provider Name="Microsoft-Windows-Sysmon" Guid="{5770385f}"/>
+    <EventID>1</EventID>
+    <TimeCreated SystemTime="2024-03-01T10:01:02.123Z"/>
+    <Channel>Microsoft-Windows-Sysmon/Operational</Channel>
+    <Computer>WS01</Computer>
+  </System>
+  <EventData>
+    <Data Name="Image">C:\Windows\System32\cmd.exe</Data>
+    <Data Name="CommandLine">cmd.exe /c "echo a &amp; b"</Data>
+    <Data Name="Hashes">MD5=AA,SHA256=BB</Data>
+  </EventData>
+</Event>
+<Event xmlns="http://schemas.microsoft.com/win/2004/08/events/event">
+  <System>
+    <Provider Name="Service Control Manager"/>
+    <EventID Qualifiers="16384">7045</EventID>
+    <Channel>System</Channel>
+  </System>
+  <EventData><Data Name="ServiceName">evil</Data><Data Name="ImagePath"><![CDATA[C:\evil.exe]]></Data></EventData>
+</Event>
+</Events>"#;
+
+        let mut events = Vec::new();
+        let total = stream_events(xml.as_bytes(), 1, &mut |chunk| events.extend(chunk)).unwrap();
+        assert_eq!(total, 2);
+
+        assert_eq!(events[0]["EventID"], "1");
+        assert_eq!(events[0]["category"], "process_creation");
+        assert_eq!(events[0]["TimeCreated"], "2024-03-01T10:01:02.123Z");
+        assert_eq!(events[0]["Provider_Name"], "Microsoft-Windows-Sysmon");
+        assert_eq!(events[0]["CommandLine"], "cmd.exe /c \"echo a & b\"");
+        assert_eq!(events[0]["md5"], "AA");
+
+        assert_eq!(events[1]["EventID"], "7045");
+        assert_eq!(events[1]["ServiceName"], "evil");
+        assert_eq!(events[1]["ImagePath"], "C:\\evil.exe");
+
+        assert!(is_event_xml(
+            "\u{feff}<?xml version=\"1.0\"?><Events><Event>"
+        ));
+        assert!(!is_event_xml("{\"EventID\": 1}"));
+    }
+}
@@ -0,0 +1,208 @@
+//! Field value statistics ("stacking").
+//!
+//! Counts the values of one field across a log file: the most frequent
+//! values, the number of distinct values and the share of events without the
+//! field. CloudTrail files and ingested sources are aggregated inside DuckDB
+//! with a GROUP BY. Flat JSON files, field functions and fields DuckDB can't
+//! read like the Rust evaluator (reached through arrays, floating-point
+//! values) are counted in Rust with `db_engine::get_field_values` instead.
+
+use duckdb::Connection;
+use std::collections::HashMap;
+
+use crate::db_engine;
+use crate::models::{FieldStats, FieldValueCount, SiemError};
+use crate::sql_compiler::{FieldPath, EXACT_SCALAR_TYPES};
+
+/// Number of top values returned by default.
+pub const DEFAULT_TOP_N: usize = 10;
+
+fn null_percentage(null_count: usize, total_events: usize) -> f64 {
+    if total_events == 0 {
+        0.0
+    } else {
+        null_count as f64 * 100.0 / total_events as f64
+    }
+}
+
+/// Compute the statistics of a field over an event subquery (a JSON column
+/// named `event`) in DuckDB. Returns None if the field has to be counted in
+/// Rust.
+pub fn sql_field_stats(
+    conn: &Connection,
+    records: &str,
+    field: &str,
+    top_n: usize,
+) -> Result<Option<FieldStats>, SiemError> {
+    let Some(path) = FieldPath::parse(field) else {
+        return Ok(None);
+    };
+    let map_err = |e: duckdb::Error| SiemError::Query(format!("Failed to execute query: {}", e));
+
+    // Scalar values only, as `get_field_values` ignores objects and nulls
+    let values = format!(
+        "SELECT event, CASE WHEN json_type(event, {path}) IN {types} THEN json_extract_string(event, {path}) END AS value FROM ({records})",
+        path = path.path,
+        types = EXACT_SCALAR_TYPES,
+        records = records
+    );
+
+    let (undecided, total, non_null, cardinality) = conn
+        .query_row(
+            &format!(
+                "SELECT COUNT(*) FILTER (WHERE {}), COUNT(*), COUNT(value), COUNT(DISTINCT value) FROM ({})",
+                path.undecided, values
+            ),
+            [],
+            |row| {
+                Ok((
+                    row.get::<_, i64>(0)?,
+                    row.get::<_, i64>(1)? as usize,
+                    row.get::<_, i64>(2)? as usize,
+                    row.get::<_, i64>(3)? as usize,
+                ))
+            },
+        )
+        .map_err(map_err)?;
+    if undecided > 0 {
+        return Ok(None);
+    }
+
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT value, COUNT(*) AS n FROM ({}) WHERE value IS NOT NULL GROUP BY value ORDER BY n DESC, value LIMIT {}",
+            values, top_n
+        ))
+        .map_err(map_err)?;
+    let top_values = stmt
+        .query_map([], |row| {
+            Ok(FieldValueCount {
+                value: row.get(0)?,
+                count: row.get::<_, i64>(1)? as usize,
+            })
+        })
+        .map_err(map_err)?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(map_err)?;
+
+    Ok(Some(FieldStats {
+        field: field.to_string(),
+        total_events: total,
+        null_count: total - non_null,
+        null_percentage: null_percentage(total - non_null, total),
+        cardinality,
+        top_values,
+    }))
+}
+
+/// Counts the values of a field over a stream of events.
+pub struct FieldCounter<'a> {
+    field: &'a str,
+    counts: HashMap<String, usize>,
+    total_events: usize,
+    null_count: usize,
+}
+
+impl<'a> FieldCounter<'a> {
+    pub fn new(field: &'a str) -> Self {
+        FieldCounter {
+            field,
+            counts: HashMap::new(),
+            total_events: 0,
+            null_count: 0,
+        }
+    }
+
+    /// Count the values of an event. Every element of an array is counted.
+    pub fn observe(&mut self, event: &serde_json::Value) {
+        self.total_events += 1;
+        let values = db_engine::get_field_values(event, self.field);
+        if values.is_empty() {
+            self.null_count += 1;
+        }
+        for value in values {
+            *self.counts.entry(value).or_insert(0) += 1;
+        }
+    }
+
+    pub fn finish(self, top_n: usize) -> FieldStats {
+        let cardinality = self.counts.len();
+        let mut top_values: Vec<FieldValueCount> = self
+            .counts
+            .into_iter()
+            .map(|(value, count)| FieldValueCount { value, count })
+            .collect();
+        top_values.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.value.cmp(&b.value)));
+        top_values.truncate(top_n);
+
+        FieldStats {
+            field: self.field.to_string(),
+            total_events: self.total_events,
+            null_count: self.null_count,
+            null_percentage: null_percentage(self.null_count, self.total_events),
+            cardinality,
+            top_values,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sql_and_rust_field_stats_agree() {
+        let events = [
+            r#"{"eventName": "GetObject", "userIdentity": {"type": "IAMUser"}}"#,
+            r#"{"eventName": "GetObject", "userIdentity": {"type": "Root"}}"#,
+            r#"{"eventName": "ConsoleLogin", "userIdentity": {"type": "IAMUser"}}"#,
+            r#"{"eventName": "GetObject", "userIdentity": null}"#,
+            r#"{"eventName": "ListBuckets"}"#,
+        ];
+        let records = format!(
+            "SELECT CAST(e AS JSON) AS event FROM (VALUES {}) t(e)",
+            events
+                .iter()
+                .map(|e| format!("({})", db_engine::sql_string(e)))
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+        let conn = db_engine::create_connection().unwrap();
+
+        for field in ["eventName", "userIdentity.type"] {
+            let sql = sql_field_stats(&conn, &records, field, 2).unwrap().unwrap();
+
+            let mut counter = FieldCounter::new(field);
+            for event in events {
+                counter.observe(&serde_json::from_str(event).unwrap());
+            }
+            let rust = counter.finish(2);
+
+            assert_eq!(sql.total_events, rust.total_events);
+            assert_eq!(sql.null_count, rust.null_count);
+            assert_eq!(sql.cardinality, rust.cardinality);
+            assert_eq!(sql.top_values, rust.top_values);
+        }
+
+        let stats = sql_field_stats(&conn, &records, "userIdentity.type", 2)
+            .unwrap()
+            .unwrap();
+        assert_eq!(stats.null_count, 2);
+        assert_eq!(stats.null_percentage, 40.0);
+        assert_eq!(stats.cardinality, 2);
+        assert_eq!(
+            stats.top_values[0],
+            FieldValueCount {
+                value: "IAMUser".to_string(),
+                count: 2
+            }
+        );
+
+        // Field functions are counted in Rust
+        assert!(
+            sql_field_stats(&conn, &records, "normalize_path(eventName)", 2)
+                .unwrap()
+                .is_none()
+        );
+    }
+}
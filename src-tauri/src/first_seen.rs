@@ -0,0 +1,390 @@
+//! First-seen entity detection across scans.
+//!
+//! Values of the fields tracked by first-seen rules (users, source addresses,
+//! user agents, ...) are remembered in the persistent store, a table of the
+//! ingestion cache database, with the time of their first event. A rule with
+//! a `first_seen` block alerts on values of its field that aren't in the
+//! store yet and records them, so every value alerts once.
+//!
+//! New values only mean something once the field has a baseline: with a
+//! `baseline` period, values first seen within that period of the field's
+//! earliest tracked event are learned silently; without one, the first scan
+//! tracking the field only learns.
+
+use chrono::{DateTime, Duration, Utc};
+use duckdb::{params, Connection};
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+
+use crate::aggregation;
+use crate::db_engine;
+use crate::event_time;
+use crate::ingest_cache;
+use crate::models::{
+    AlertEvent, FirstSeen, FirstSeenFieldInfo, FirstSeenMatch, LogType, RuleYaml, SiemError,
+};
+
+/// Table of the known values.
+const VALUES_TABLE: &str = "first_seen_values";
+
+/// A value observed by a rule during a scan, at its earliest event.
+pub struct Observation {
+    pub value: String,
+    pub first_seen: Option<DateTime<Utc>>,
+    pub event: serde_json::Value,
+}
+
+/// Known values, loaded from the store and kept in sync with it.
+pub struct Baseline {
+    conn: Connection,
+    /// First event time of each known value, per field
+    values: HashMap<String, HashMap<String, Option<DateTime<Utc>>>>,
+}
+
+impl Baseline {
+    /// Load the known values, creating the table if needed.
+    pub fn load(conn: Connection) -> Result<Self, SiemError> {
+        let map_err =
+            |e: duckdb::Error| SiemError::Query(format!("Cannot load first-seen baseline: {}", e));
+        conn.execute_batch(&format!(
+            "CREATE TABLE IF NOT EXISTS {} (
+                field VARCHAR NOT NULL,
+                value VARCHAR NOT NULL,
+                first_seen VARCHAR,
+                recorded_at VARCHAR NOT NULL,
+                PRIMARY KEY (field, value)
+            )",
+            VALUES_TABLE
+        ))
+        .map_err(map_err)?;
+
+        let mut values: HashMap<String, HashMap<String, Option<DateTime<Utc>>>> = HashMap::new();
+        {
+            let mut stmt = conn
+                .prepare(&format!(
+                    "SELECT field, value, first_seen FROM {}",
+                    VALUES_TABLE
+                ))
+                .map_err(map_err)?;
+            let rows = stmt
+                .query_map([], |row| {
+                    Ok((
+                        row.get::<_, String>(0)?,
+                        row.get::<_, String>(1)?,
+                        row.get::<_, Option<String>>(2)?,
+                    ))
+                })
+                .map_err(map_err)?;
+            for row in rows {
+                let (field, value, first_seen) = row.map_err(map_err)?;
+                let first_seen = first_seen.and_then(|ts| event_time::parse_timestamp_str(&ts));
+                values.entry(field).or_default().insert(value, first_seen);
+            }
+        }
+
+        Ok(Baseline { conn, values })
+    }
+
+    /// Record the values a rule observed and return those to alert on,
+    /// earliest first.
+    pub fn learn(
+        &mut self,
+        field: &str,
+        observed: Vec<Observation>,
+        baseline_secs: Option<i64>,
+    ) -> Result<Vec<Observation>, SiemError> {
+        let known = self.values.entry(field.to_string()).or_default();
+        let previously_tracked = !known.is_empty();
+
+        let mut new: Vec<Observation> = observed
+            .into_iter()
+            .filter(|observation| !known.contains_key(&observation.value))
+            .collect();
+        if new.is_empty() {
+            return Ok(new);
+        }
+        new.sort_by(|a, b| {
+            a.first_seen
+                .cmp(&b.first_seen)
+                .then_with(|| a.value.cmp(&b.value))
+        });
+
+        let tracking_since = known
+            .values()
+            .flatten()
+            .chain(new.iter().filter_map(|o| o.first_seen.as_ref()))
+            .min()
+            .copied();
+
+        let map_err =
+            |e: duckdb::Error| SiemError::Query(format!("Cannot record first-seen values: {}", e));
+        let recorded_at = Utc::now().to_rfc3339();
+        {
+            let mut appender = self.conn.appender(VALUES_TABLE).map_err(map_err)?;
+            for observation in &new {
+                appender
+                    .append_row(params![
+                        field,
+                        observation.value,
+                        observation.first_seen.map(|ts| ts.to_rfc3339()),
+                        recorded_at
+                    ])
+                    .map_err(map_err)?;
+            }
+            appender.flush().map_err(map_err)?;
+        }
+        for observation in &new {
+            known.insert(observation.value.clone(), observation.first_seen);
+        }
+
+        new.retain(|observation| match baseline_secs {
+            Some(secs) => matches!(
+                (observation.first_seen, tracking_since),
+                (Some(ts), Some(since)) if ts >= since + Duration::seconds(secs)
+            ),
+            None => previously_tracked,
+        });
+        Ok(new)
+    }
+
+    /// Tracked fields, by name.
+    pub fn fields(&self) -> Vec<FirstSeenFieldInfo> {
+        let mut fields: Vec<FirstSeenFieldInfo> = self
+            .values
+            .iter()
+            .filter(|(_, values)| !values.is_empty())
+            .map(|(field, values)| FirstSeenFieldInfo {
+                field: field.clone(),
+                value_count: values.len(),
+                tracking_since: values.values().flatten().min().map(|ts| ts.to_rfc3339()),
+            })
+            .collect();
+        fields.sort_by(|a, b| a.field.cmp(&b.field));
+        fields
+    }
+
+    /// Forget the values of a field, or of every field.
+    pub fn reset(&mut self, field: Option<&str>) -> Result<(), SiemError> {
+        let result = match field {
+            Some(field) => self.conn.execute(
+                &format!("DELETE FROM {} WHERE field = ?", VALUES_TABLE),
+                params![field],
+            ),
+            None => self
+                .conn
+                .execute(&format!("DELETE FROM {}", VALUES_TABLE), []),
+        };
+        result.map_err(|e| SiemError::Query(format!("Cannot reset first-seen baseline: {}", e)))?;
+
+        match field {
+            Some(field) => {
+                self.values.remove(field);
+            }
+            None => self.values.clear(),
+        }
+        Ok(())
+    }
+}
+
+fn baseline() -> &'static Mutex<Option<Baseline>> {
+    static BASELINE: OnceLock<Mutex<Option<Baseline>>> = OnceLock::new();
+    BASELINE.get_or_init(|| Mutex::new(None))
+}
+
+/// Run `f` on the baseline, once `open` succeeded.
+fn with_baseline<T>(f: impl FnOnce(&mut Baseline) -> Result<T, SiemError>) -> Result<T, SiemError> {
+    let mut guard = baseline()
+        .lock()
+        .map_err(|_| SiemError::Query("First-seen baseline is unavailable".to_string()))?;
+    let baseline = guard
+        .as_mut()
+        .ok_or_else(|| SiemError::Query("First-seen baseline is unavailable".to_string()))?;
+    f(baseline)
+}
+
+/// Load the baseline from the persistent store.
+pub fn open(app_handle: &tauri::AppHandle) -> Result<(), SiemError> {
+    let loaded = Baseline::load(ingest_cache::open(app_handle)?)?;
+    if let Ok(mut guard) = baseline().lock() {
+        *guard = Some(loaded);
+    }
+    Ok(())
+}
+
+/// Tracked fields, by name.
+pub fn list_fields() -> Result<Vec<FirstSeenFieldInfo>, SiemError> {
+    with_baseline(|baseline| Ok(baseline.fields()))
+}
+
+/// Forget the values of a field, or of every field.
+pub fn reset(field: Option<&str>) -> Result<(), SiemError> {
+    with_baseline(|baseline| baseline.reset(field))
+}
+
+/// Incremental state of a first-seen rule: the earliest event of every value
+/// of its field among the matching events.
+pub struct FirstSeenTracker<'r> {
+    rule: &'r RuleYaml,
+    first_seen: &'r FirstSeen,
+    log_type: LogType,
+    baseline_secs: Option<i64>,
+    observed: HashMap<String, Observation>,
+}
+
+impl<'r> FirstSeenTracker<'r> {
+    pub fn new(
+        rule: &'r RuleYaml,
+        first_seen: &'r FirstSeen,
+        log_type: &LogType,
+    ) -> Result<Self, SiemError> {
+        Ok(FirstSeenTracker {
+            rule,
+            first_seen,
+            log_type: log_type.clone(),
+            baseline_secs: first_seen
+                .baseline
+                .as_deref()
+                .map(aggregation::parse_window)
+                .transpose()?,
+            observed: HashMap::new(),
+        })
+    }
+
+    pub fn rule(&self) -> &'r RuleYaml {
+        self.rule
+    }
+
+    pub fn observe(&mut self, event: &serde_json::Value) {
+        if !db_engine::matches_detection(event, &self.rule.detection) {
+            return;
+        }
+        let value = aggregation::group_value(event, &self.first_seen.field);
+        if value.is_empty() {
+            return;
+        }
+
+        let ts = event_time::event_time(
+            event,
+            self.first_seen.timestamp_field.as_deref(),
+            &self.log_type,
+        );
+        match self.observed.get_mut(&value) {
+            Some(earliest) => {
+                if ts.is_some_and(|ts| earliest.first_seen.is_none_or(|first| ts < first)) {
+                    earliest.first_seen = ts;
+                    earliest.event = event.clone();
+                }
+            }
+            None => {
+                self.observed.insert(
+                    value.clone(),
+                    Observation {
+                        value,
+                        first_seen: ts,
+                        event: event.clone(),
+                    },
+                );
+            }
+        }
+    }
+
+    /// Record the observed values in the baseline and produce one alert per
+    /// new value, with its first event as evidence.
+    pub fn finish(self, source_file: Option<&str>) -> Result<Vec<AlertEvent>, SiemError> {
+        let field = &self.first_seen.field;
+        let observed = self.observed.into_values().collect();
+        let new = with_baseline(|baseline| baseline.learn(field, observed, self.baseline_secs))?;
+
+        Ok(new
+            .into_iter()
+            .map(|observation| {
+                let mut alert = AlertEvent::from_rule(
+                    self.rule,
+                    vec![observation.event],
+                    source_file.map(str::to_string),
+                );
+                alert.first_seen = Some(FirstSeenMatch {
+                    field: field.clone(),
+                    value: observation.value,
+                    first_seen: observation.first_seen.map(|ts| ts.to_rfc3339()),
+                });
+                alert
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn observation(value: &str, ts: &str) -> Observation {
+        Observation {
+            value: value.to_string(),
+            first_seen: event_time::parse_timestamp_str(ts),
+            event: serde_json::json!({ "userAgent": value, "eventTime": ts }),
+        }
+    }
+
+    fn values(observations: &[Observation]) -> Vec<&str> {
+        observations.iter().map(|o| o.value.as_str()).collect()
+    }
+
+    #[test]
+    fn test_baseline_learns_then_alerts() {
+        let conn = Connection::open_in_memory().unwrap();
+        let mut baseline = Baseline::load(conn.try_clone().unwrap()).unwrap();
+
+        // The first scan of a field only learns
+        let new = baseline
+            .learn(
+                "userAgent",
+                vec![
+                    observation("aws-cli", "2025-12-01T00:00:00Z"),
+                    observation("console", "2025-12-02T00:00:00Z"),
+                ],
+                None,
+            )
+            .unwrap();
+        assert!(new.is_empty());
+
+        // Later scans alert on unknown values only, once
+        let batch = || {
+            vec![
+                observation("aws-cli", "2025-12-10T00:00:00Z"),
+                observation("python-requests", "2025-12-10T00:00:00Z"),
+            ]
+        };
+        let new = baseline.learn("userAgent", batch(), None).unwrap();
+        assert_eq!(values(&new), vec!["python-requests"]);
+        assert!(baseline
+            .learn("userAgent", batch(), None)
+            .unwrap()
+            .is_empty());
+
+        // Values survive a reload from the store
+        let mut reloaded = Baseline::load(conn).unwrap();
+        assert_eq!(reloaded.fields()[0].value_count, 3);
+        assert_eq!(
+            reloaded.fields()[0].tracking_since.as_deref(),
+            Some("2025-12-01T00:00:00+00:00")
+        );
+
+        // With a baseline period, values inside it are learned silently
+        let new = reloaded
+            .learn(
+                "sourceIPAddress",
+                vec![
+                    observation("203.0.113.7", "2025-12-01T00:00:00Z"),
+                    observation("198.51.100.1", "2025-12-05T00:00:00Z"),
+                    observation("192.0.2.1", "2025-12-09T00:00:00Z"),
+                ],
+                Some(7 * 86400),
+            )
+            .unwrap();
+        assert_eq!(values(&new), vec!["192.0.2.1"]);
+
+        reloaded.reset(Some("userAgent")).unwrap();
+        assert_eq!(reloaded.fields().len(), 1);
+    }
+}
@@ -0,0 +1,349 @@
+//! Folder watch mode: automatic scans of newly dropped log files.
+//!
+//! The watched directory (the log library by default) is watched for file
+//! system events through `notify`, debounced so a file is only picked up once
+//! it was left alone for `SETTLE_TIME` and files still being copied are not
+//! read half-written. Every format the import accepts is picked up: JSON
+//! logs, Windows XML exports, key=value logs, files matched by a text parser
+//! and ZIP archives, whose log files are imported into the library and
+//! scanned one by one. Each scan's alerts are emitted on `WATCH_EVENT`.
+//! Files present when the watch starts are not scanned, and neither are
+//! later changes to a file already scanned.
+
+use notify_debouncer_mini::notify::RecursiveMode;
+use notify_debouncer_mini::{new_debouncer, DebounceEventResult};
+use serde::Serialize;
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
+use tauri::Emitter;
+
+use crate::change_feed::{self, ChangeKind};
+use crate::db_engine;
+use crate::log_manager;
+use crate::models::{AlertEvent, LogType, SiemError};
+use crate::text_parser;
+
+/// Tauri event carrying a `WatchScanEvent`.
+pub const WATCH_EVENT: &str = "watch://scanned";
+
+/// How long a file must go without changes before it is scanned.
+const SETTLE_TIME: Duration = Duration::from_secs(2);
+
+/// How often the watch thread checks whether it was stopped.
+const STOP_CHECK_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Extensions of the log files picked up, besides text parser patterns.
+const WATCHED_EXTENSIONS: [&str; 4] = ["json", "xml", "log", "zip"];
+
+/// Scans one file with the active rules, returning the alerts delivered.
+pub type ScanFn = fn(&tauri::AppHandle, &str) -> Result<Vec<AlertEvent>, SiemError>;
+
+/// State of the folder watch.
+#[derive(Debug, Serialize, Clone, Default)]
+pub struct WatchStatus {
+    pub running: bool,
+    /// Watched directory
+    pub directory: Option<String>,
+    /// When the watch started (ISO 8601)
+    pub started_at: Option<String>,
+    /// Files scanned since the watch started
+    pub files_scanned: usize,
+    /// Alerts produced since the watch started
+    pub alerts_emitted: usize,
+}
+
+/// Payload of a watch scan notification.
+#[derive(Debug, Serialize, Clone)]
+pub struct WatchScanEvent {
+    pub file_name: String,
+    pub file_path: String,
+    pub alerts: Vec<AlertEvent>,
+    /// Why the file couldn't be scanned
+    pub error: Option<String>,
+    /// When the scan finished (ISO 8601)
+    pub scanned_at: String,
+}
+
+struct Watch {
+    stop: Arc<AtomicBool>,
+    status: WatchStatus,
+}
+
+fn watch_state() -> &'static Mutex<Option<Watch>> {
+    static STATE: OnceLock<Mutex<Option<Watch>>> = OnceLock::new();
+    STATE.get_or_init(|| Mutex::new(None))
+}
+
+/// Whether a file is a log file the watch picks up.
+fn is_watched_file(path: &Path) -> bool {
+    let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+        return false;
+    };
+    if log_manager::is_system_file(name) {
+        return false;
+    }
+    let extension = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .unwrap_or_default()
+        .to_lowercase();
+    WATCHED_EXTENSIONS.contains(&extension.as_str())
+        || text_parser::log_type_for_file(name).is_some()
+}
+
+/// Whether a file is a ZIP archive, imported rather than scanned in place.
+fn is_archive(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("zip"))
+}
+
+/// Log files already in a directory.
+fn existing_files(dir: &Path) -> HashSet<PathBuf> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return HashSet::new();
+    };
+    entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file() && is_watched_file(path))
+        .collect()
+}
+
+/// Files to scan among the paths of settled file system events: watched log
+/// files not handled yet. They become handled; handled files that are gone
+/// are forgotten, so a file dropped again is scanned again.
+fn ready_files(
+    handled: &mut HashSet<PathBuf>,
+    changed: impl IntoIterator<Item = PathBuf>,
+) -> Vec<PathBuf> {
+    let mut ready = Vec::new();
+    for path in changed {
+        if !path.is_file() {
+            handled.remove(&path);
+            continue;
+        }
+        if is_watched_file(&path) && handled.insert(path.clone()) {
+            ready.push(path);
+        }
+    }
+    ready.sort();
+    ready
+}
+
+/// Import the log files of an archive into the library, with the log types
+/// detected from their content. Returns their paths.
+fn import_archive(app_handle: &tauri::AppHandle, path: &Path) -> Result<Vec<PathBuf>, SiemError> {
+    let imported =
+        log_manager::import_log_file(app_handle, &path.to_string_lossy(), LogType::FlatJson)?;
+    for info in &imported {
+        if info.log_type != Some(LogType::FlatJson) {
+            continue;
+        }
+        if let Ok(log_type) = db_engine::detect_log_type(&info.path) {
+            log_manager::set_log_type(app_handle, &info.filename, log_type)?;
+        }
+    }
+    change_feed::notify(
+        app_handle,
+        ChangeKind::Logs,
+        imported.iter().map(|info| info.filename.clone()).collect(),
+    );
+    Ok(imported
+        .iter()
+        .map(|info| PathBuf::from(&info.path))
+        .collect())
+}
+
+/// Count a file's scan in the status and emit its results.
+fn emit_scan(
+    app_handle: &tauri::AppHandle,
+    path: &Path,
+    result: Result<Vec<AlertEvent>, SiemError>,
+    stop_flag: &Arc<AtomicBool>,
+) {
+    let file_path = path.to_string_lossy().to_string();
+    let (alerts, error) = match result {
+        Ok(alerts) => (alerts, None),
+        Err(e) => {
+            eprintln!("Warning: Watch scan of '{}' failed: {}", file_path, e);
+            (Vec::new(), Some(e.to_string()))
+        }
+    };
+
+    if let Ok(mut guard) = watch_state().lock() {
+        if let Some(watch) = guard.as_mut().filter(|w| Arc::ptr_eq(&w.stop, stop_flag)) {
+            watch.status.files_scanned += 1;
+            watch.status.alerts_emitted += alerts.len();
+        }
+    }
+
+    let event = WatchScanEvent {
+        file_name: path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default(),
+        file_path,
+        alerts,
+        error,
+        scanned_at: chrono::Utc::now().to_rfc3339(),
+    };
+    if let Err(e) = app_handle.emit(WATCH_EVENT, event) {
+        eprintln!("Warning: Cannot emit watch event: {}", e);
+    }
+}
+
+/// Current state of the folder watch.
+pub fn status() -> WatchStatus {
+    watch_state()
+        .lock()
+        .ok()
+        .and_then(|guard| guard.as_ref().map(|watch| watch.status.clone()))
+        .unwrap_or_default()
+}
+
+/// Stop the folder watch, if running.
+pub fn stop() {
+    if let Ok(mut guard) = watch_state().lock() {
+        if let Some(watch) = guard.take() {
+            watch.stop.store(true, Ordering::Relaxed);
+        }
+    }
+}
+
+/// Watch a directory, scanning new log files with `scan`. A running watch is
+/// stopped first.
+pub fn start(
+    app_handle: tauri::AppHandle,
+    directory: PathBuf,
+    scan: ScanFn,
+) -> Result<WatchStatus, SiemError> {
+    if !directory.is_dir() {
+        return Err(SiemError::FileIO(format!(
+            "Cannot watch {}: not a directory",
+            directory.display()
+        )));
+    }
+    stop();
+
+    let (sender, receiver) = mpsc::channel::<DebounceEventResult>();
+    let mut debouncer = new_debouncer(SETTLE_TIME, sender)
+        .map_err(|e| SiemError::FileIO(format!("Cannot start folder watch: {}", e)))?;
+    debouncer
+        .watcher()
+        .watch(&directory, RecursiveMode::NonRecursive)
+        .map_err(|e| SiemError::FileIO(format!("Cannot watch {}: {}", directory.display(), e)))?;
+
+    let stop_flag = Arc::new(AtomicBool::new(false));
+    let status = WatchStatus {
+        running: true,
+        directory: Some(directory.to_string_lossy().to_string()),
+        started_at: Some(chrono::Utc::now().to_rfc3339()),
+        files_scanned: 0,
+        alerts_emitted: 0,
+    };
+    {
+        let mut guard = watch_state()
+            .lock()
+            .map_err(|_| SiemError::FileIO("Folder watch state is poisoned".to_string()))?;
+        *guard = Some(Watch {
+            stop: stop_flag.clone(),
+            status: status.clone(),
+        });
+    }
+
+    let mut handled = existing_files(&directory);
+    std::thread::spawn(move || {
+        // Dropping the debouncer when the thread ends stops watching
+        let _debouncer = debouncer;
+        loop {
+            let changed = match receiver.recv_timeout(STOP_CHECK_INTERVAL) {
+                Ok(Ok(events)) => events.into_iter().map(|event| event.path).collect(),
+                Ok(Err(e)) => {
+                    eprintln!("Warning: Folder watch error: {}", e);
+                    Vec::new()
+                }
+                Err(RecvTimeoutError::Timeout) => Vec::new(),
+                Err(RecvTimeoutError::Disconnected) => break,
+            };
+            if stop_flag.load(Ordering::Relaxed) {
+                break;
+            }
+
+            for path in ready_files(&mut handled, changed) {
+                if stop_flag.load(Ordering::Relaxed) {
+                    break;
+                }
+                if !is_archive(&path) {
+                    emit_scan(
+                        &app_handle,
+                        &path,
+                        scan(&app_handle, &path.to_string_lossy()),
+                        &stop_flag,
+                    );
+                    continue;
+                }
+                match import_archive(&app_handle, &path) {
+                    Ok(imported) => {
+                        for log_path in imported {
+                            // Logs extracted into the watched folder aren't scanned twice
+                            handled.insert(log_path.clone());
+                            let result = scan(&app_handle, &log_path.to_string_lossy());
+                            emit_scan(&app_handle, &log_path, result, &stop_flag);
+                        }
+                    }
+                    Err(e) => emit_scan(&app_handle, &path, Err(e), &stop_flag),
+                }
+            }
+        }
+    });
+
+    Ok(status)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ready_files_picks_up_new_log_files_once() {
+        let dir = std::env::temp_dir().join("offline_siem_test_folder_watch");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let write = |name: &str| {
+            let path = dir.join(name);
+            fs::write(&path, "{}").unwrap();
+            path
+        };
+
+        let old = write("old.json");
+        let mut handled = existing_files(&dir);
+        let new = write("new.json");
+        let archive = write("bundle.ZIP");
+        let windows = write("security.xml");
+        let other = [write("notes.docx"), write("metadata.json")];
+
+        let changed = [&old, &new, &archive, &windows, &new]
+            .into_iter()
+            .chain(&other)
+            .cloned();
+        let ready = ready_files(&mut handled, changed);
+        assert_eq!(ready, vec![archive.clone(), new.clone(), windows]);
+        assert!(is_archive(&archive) && !is_archive(&new));
+
+        // Already scanned or pre-existing files are not scanned again
+        assert!(ready_files(&mut handled, [old.clone(), new.clone()]).is_empty());
+
+        // A removed file dropped again is new again
+        fs::remove_file(&old).unwrap();
+        assert!(ready_files(&mut handled, [old.clone()]).is_empty());
+        write("old.json");
+        assert_eq!(ready_files(&mut handled, [old.clone()]), vec![old]);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,184 @@
+//! Offline GeoIP enrichment from local MaxMind databases.
+//!
+//! The country database (GeoLite2-Country or -City) and the ASN database
+//! (GeoLite2-ASN) are `.mmdb` files whose paths are set in AppConfig; nothing
+//! is downloaded. The databases are loaded once and kept while the configured
+//! paths stay the same. They serve two purposes:
+//! - alert evidence: IP fields (configurable in AppConfig) are annotated with
+//!   the country and autonomous system of the address;
+//! - conditions: `geo_country(field)` and `geo_asn(field)` resolve a field's
+//!   addresses, e.g. `GEO_COUNTRY(sourceIPAddress) = 'RU'`.
+
+use maxminddb::{geoip2, Reader};
+use std::collections::HashSet;
+use std::net::IpAddr;
+use std::sync::{Arc, OnceLock, RwLock};
+
+use crate::config::AppConfig;
+use crate::db_engine;
+use crate::models::{AlertEvent, GeoAnnotation, SiemError};
+
+/// Loaded GeoIP databases.
+#[derive(Default)]
+pub struct GeoLookup {
+    country: Option<Reader<Vec<u8>>>,
+    asn: Option<Reader<Vec<u8>>>,
+}
+
+impl GeoLookup {
+    /// Open the given databases.
+    pub fn open(country_db: Option<&str>, asn_db: Option<&str>) -> Result<Self, SiemError> {
+        let open = |path: Option<&str>| {
+            path.map(|path| {
+                Reader::open_readfile(path).map_err(|e| {
+                    SiemError::FileIO(format!("Cannot open GeoIP database '{}': {}", path, e))
+                })
+            })
+            .transpose()
+        };
+        Ok(GeoLookup {
+            country: open(country_db)?,
+            asn: open(asn_db)?,
+        })
+    }
+
+    /// Check whether any database is loaded.
+    pub fn is_empty(&self) -> bool {
+        self.country.is_none() && self.asn.is_none()
+    }
+
+    /// ISO country code of an address.
+    pub fn country(&self, ip: &str) -> Option<String> {
+        let record: geoip2::Country = self.country.as_ref()?.lookup(parse_ip(ip)?).ok()?;
+        record
+            .country
+            .or(record.registered_country)?
+            .iso_code
+            .map(str::to_string)
+    }
+
+    /// Autonomous system number and organization of an address.
+    pub fn asn(&self, ip: &str) -> Option<(u32, Option<String>)> {
+        let record: geoip2::Asn = self.asn.as_ref()?.lookup(parse_ip(ip)?).ok()?;
+        Some((
+            record.autonomous_system_number?,
+            record.autonomous_system_organization.map(str::to_string),
+        ))
+    }
+}
+
+/// Parse an IP address field value. Non-address values (CloudTrail records
+/// AWS service names as source addresses) give None.
+fn parse_ip(value: &str) -> Option<IpAddr> {
+    value.trim().parse().ok()
+}
+
+/// Databases loaded for the configured paths.
+struct Loaded {
+    paths: (Option<String>, Option<String>),
+    lookup: Arc<GeoLookup>,
+}
+
+fn loaded() -> &'static RwLock<Option<Loaded>> {
+    static LOADED: OnceLock<RwLock<Option<Loaded>>> = OnceLock::new();
+    LOADED.get_or_init(|| RwLock::new(None))
+}
+
+/// Load the databases configured in `config`, unless already loaded.
+pub fn configure(config: &AppConfig) -> Result<Arc<GeoLookup>, SiemError> {
+    let non_empty = |path: &Option<String>| path.clone().filter(|p| !p.trim().is_empty());
+    let paths = (
+        non_empty(&config.geoip_country_db),
+        non_empty(&config.geoip_asn_db),
+    );
+
+    if let Ok(guard) = loaded().read() {
+        if let Some(current) = guard.as_ref().filter(|l| l.paths == paths) {
+            return Ok(current.lookup.clone());
+        }
+    }
+
+    let lookup = Arc::new(GeoLookup::open(paths.0.as_deref(), paths.1.as_deref())?);
+    if let Ok(mut guard) = loaded().write() {
+        *guard = Some(Loaded {
+            paths,
+            lookup: lookup.clone(),
+        });
+    }
+    Ok(lookup)
+}
+
+/// The databases loaded by the last `configure`, if any.
+pub fn active() -> Option<Arc<GeoLookup>> {
+    let guard = loaded().read().ok()?;
+    guard
+        .as_ref()
+        .map(|l| l.lookup.clone())
+        .filter(|lookup| !lookup.is_empty())
+}
+
+/// Annotate alerts with the location of every address found in the
+/// configured evidence fields.
+pub fn annotate_alerts(lookup: &GeoLookup, ip_fields: &[String], alerts: &mut [AlertEvent]) {
+    for alert in alerts.iter_mut() {
+        let mut seen = HashSet::new();
+
+        for event in &alert.evidence {
+            for field in ip_fields {
+                for value in db_engine::get_field_values(event, field) {
+                    let Some(ip) = parse_ip(&value) else {
+                        continue;
+                    };
+                    if !seen.insert((field.clone(), ip)) {
+                        continue;
+                    }
+
+                    let ip = ip.to_string();
+                    let asn = lookup.asn(&ip);
+                    alert.geo_annotations.push(GeoAnnotation {
+                        field: field.clone(),
+                        country: lookup.country(&ip),
+                        asn: asn.as_ref().map(|(number, _)| *number),
+                        as_organization: asn.and_then(|(_, org)| org),
+                        ip,
+                    });
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_annotate_alerts_without_databases() {
+        assert_eq!(parse_ip(" 203.0.113.7 "), "203.0.113.7".parse().ok());
+        assert_eq!(parse_ip("2001:db8::1"), "2001:db8::1".parse().ok());
+        assert_eq!(parse_ip("ec2.amazonaws.com"), None);
+
+        let mut alerts: Vec<AlertEvent> = vec![serde_json::from_value(serde_json::json!({
+            "rule_id": "r1",
+            "rule_title": "r1",
+            "severity": "high",
+            "timestamp": "2025-12-16T11:05:00+00:00",
+            "match_count": 3,
+            "evidence": [
+                { "sourceIPAddress": "203.0.113.7" },
+                { "sourceIPAddress": "203.0.113.7" },
+                { "sourceIPAddress": "ec2.amazonaws.com" }
+            ]
+        }))
+        .unwrap()];
+
+        // Addresses are annotated once each, unresolved without databases
+        let lookup = GeoLookup::default();
+        annotate_alerts(&lookup, &["sourceIPAddress".to_string()], &mut alerts);
+        let annotations = &alerts[0].geo_annotations;
+        assert_eq!(annotations.len(), 1);
+        assert_eq!(annotations[0].ip, "203.0.113.7");
+        assert_eq!(annotations[0].country, None);
+        assert_eq!(annotations[0].asn, None);
+    }
+}
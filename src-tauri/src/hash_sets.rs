@@ -0,0 +1,388 @@
+//! Hash set manager for annotating evidence with known-good / known-bad verdicts.
+//!
+//! Hash sets (NSRL-style known-good lists, known-bad malware hashes) are
+//! imported from text or CSV files into `hash_sets/` in the application's data
+//! directory. Every MD5, SHA-1 and SHA-256 found on a line is indexed, so both
+//! plain hash lists and NSRL CSV exports can be imported as-is.
+//!
+//! During scans, hash fields in evidence events (configurable in AppConfig)
+//! are looked up and each alert is annotated with a verdict per hash. The
+//! algorithm of an evidence hash comes from its `ALGO=` prefix (Sysmon
+//! `Hashes`) or its field name before falling back to its length, so an
+//! import hash (`IMPHASH=`, `Imphash`) is never looked up as an MD5.
+
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::PathBuf;
+
+use crate::app_data;
+use crate::db_engine;
+use crate::models::{AlertEvent, HashAnnotation, HashClassification, HashSetInfo, SiemError};
+
+/// Get the directory where hash sets are stored.
+fn get_hash_sets_dir(app_handle: &tauri::AppHandle) -> Result<PathBuf, SiemError> {
+    app_data::app_data_subdir(app_handle, "hash_sets")
+}
+
+/// Load the hash set index.
+fn load_index(app_handle: &tauri::AppHandle) -> Result<Vec<HashSetInfo>, SiemError> {
+    let path = get_hash_sets_dir(app_handle)?.join("index.json");
+
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let content = fs::read_to_string(&path)
+        .map_err(|e| SiemError::FileIO(format!("Cannot read hash set index: {}", e)))?;
+
+    serde_json::from_str(&content)
+        .map_err(|e| SiemError::Serialization(format!("Cannot parse hash set index: {}", e)))
+}
+
+/// Save the hash set index.
+fn save_index(app_handle: &tauri::AppHandle, index: &[HashSetInfo]) -> Result<(), SiemError> {
+    let path = get_hash_sets_dir(app_handle)?.join("index.json");
+
+    let content = serde_json::to_string_pretty(index)
+        .map_err(|e| SiemError::Serialization(format!("Cannot serialize hash set index: {}", e)))?;
+
+    fs::write(&path, content)
+        .map_err(|e| SiemError::FileIO(format!("Cannot write hash set index: {}", e)))
+}
+
+/// Validate a hash set name (used as a filename).
+fn validate_name(name: &str) -> Result<(), SiemError> {
+    if name.is_empty()
+        || !name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+    {
+        return Err(SiemError::FileIO(format!(
+            "Invalid hash set name '{}': use letters, digits, '_' or '-'",
+            name
+        )));
+    }
+    Ok(())
+}
+
+/// Extract every MD5 (32), SHA-1 (40) and SHA-256 (64) hex token from text.
+pub fn extract_hashes(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_ascii_hexdigit())
+        .filter(|token| matches!(token.len(), 32 | 40 | 64))
+        .map(|token| token.to_lowercase())
+        .collect()
+}
+
+/// Algorithm of a hash found in evidence.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HashKind {
+    Md5,
+    Sha1,
+    Sha256,
+    /// PE import table hash: 32 hex digits like an MD5, but not a file hash
+    Imphash,
+}
+
+impl HashKind {
+    /// Kind named by a field name or `ALGO=` prefix, if any.
+    fn from_name(name: &str) -> Option<Self> {
+        let name = name.to_lowercase().replace(['-', '_'], "");
+        if name.contains("imphash") {
+            Some(HashKind::Imphash)
+        } else if name.contains("sha256") {
+            Some(HashKind::Sha256)
+        } else if name.contains("sha1") {
+            Some(HashKind::Sha1)
+        } else if name.contains("md5") {
+            Some(HashKind::Md5)
+        } else {
+            None
+        }
+    }
+
+    /// Number of hex digits of a hash of this kind.
+    fn len(self) -> usize {
+        match self {
+            HashKind::Md5 | HashKind::Imphash => 32,
+            HashKind::Sha1 => 40,
+            HashKind::Sha256 => 64,
+        }
+    }
+
+    /// File hash kind of a hex token of `len` digits.
+    fn from_len(len: usize) -> Self {
+        match len {
+            32 => HashKind::Md5,
+            40 => HashKind::Sha1,
+            _ => HashKind::Sha256,
+        }
+    }
+}
+
+/// Hashes in the value of a field, with their algorithm. A segment's
+/// `ALGO=` prefix (as in Sysmon's `SHA1=...,MD5=...,IMPHASH=...`) names the
+/// algorithm, then the field's last path segment (`Imphash`, `md5`); the
+/// length decides only when neither does or the length doesn't fit.
+pub fn extract_typed_hashes(field: &str, text: &str) -> Vec<(HashKind, String)> {
+    let field_kind = HashKind::from_name(field.rsplit('.').next().unwrap_or(field));
+    text.split(|c: char| c == ',' || c == ';' || c.is_whitespace())
+        .flat_map(|segment| {
+            let (kind, value) = match segment.split_once('=') {
+                Some((name, value)) => (HashKind::from_name(name).or(field_kind), value),
+                None => (field_kind, segment),
+            };
+            extract_hashes(value).into_iter().map(move |hash| {
+                let kind = kind
+                    .filter(|kind| kind.len() == hash.len())
+                    .unwrap_or_else(|| HashKind::from_len(hash.len()));
+                (kind, hash)
+            })
+        })
+        .collect()
+}
+
+/// Import a hash set from a text or CSV file.
+pub fn import_hash_set(
+    app_handle: &tauri::AppHandle,
+    source_path: &str,
+    name: &str,
+    classification: HashClassification,
+) -> Result<HashSetInfo, SiemError> {
+    validate_name(name)?;
+
+    let content = fs::read_to_string(source_path)
+        .map_err(|e| SiemError::FileIO(format!("Cannot read hash set file: {}", e)))?;
+
+    let hashes: HashSet<String> = content.lines().flat_map(extract_hashes).collect();
+
+    if hashes.is_empty() {
+        return Err(SiemError::FileIO(
+            "No MD5, SHA-1 or SHA-256 hashes found in file".to_string(),
+        ));
+    }
+
+    let mut sorted: Vec<&String> = hashes.iter().collect();
+    sorted.sort();
+    let body: Vec<&str> = sorted.iter().map(|h| h.as_str()).collect();
+
+    let dir = get_hash_sets_dir(app_handle)?;
+    fs::write(dir.join(format!("{}.txt", name)), body.join("\n"))
+        .map_err(|e| SiemError::FileIO(format!("Cannot write hash set: {}", e)))?;
+
+    let info = HashSetInfo {
+        name: name.to_string(),
+        classification,
+        hash_count: hashes.len(),
+        imported_at: chrono::Utc::now().to_rfc3339(),
+    };
+
+    let mut index = load_index(app_handle)?;
+    index.retain(|i| i.name != name);
+    index.push(info.clone());
+    index.sort_by(|a, b| a.name.cmp(&b.name));
+    save_index(app_handle, &index)?;
+
+    Ok(info)
+}
+
+/// List imported hash sets.
+pub fn list_hash_sets(app_handle: &tauri::AppHandle) -> Result<Vec<HashSetInfo>, SiemError> {
+    load_index(app_handle)
+}
+
+/// Delete an imported hash set.
+pub fn delete_hash_set(app_handle: &tauri::AppHandle, name: &str) -> Result<(), SiemError> {
+    validate_name(name)?;
+
+    let mut index = load_index(app_handle)?;
+    let before = index.len();
+    index.retain(|i| i.name != name);
+
+    if index.len() == before {
+        return Err(SiemError::FileIO(format!("Hash set not found: {}", name)));
+    }
+
+    let path = get_hash_sets_dir(app_handle)?.join(format!("{}.txt", name));
+    if path.exists() {
+        fs::remove_file(&path)
+            .map_err(|e| SiemError::FileIO(format!("Cannot delete hash set: {}", e)))?;
+    }
+
+    save_index(app_handle, &index)
+}
+
+/// In-memory lookup table built from all imported hash sets.
+#[derive(Default)]
+pub struct HashLookup {
+    entries: HashMap<String, (HashClassification, String)>,
+}
+
+impl HashLookup {
+    /// Load every imported hash set. Known-bad entries take precedence over known-good.
+    pub fn load(app_handle: &tauri::AppHandle) -> Result<Self, SiemError> {
+        let dir = get_hash_sets_dir(app_handle)?;
+        let mut lookup = HashLookup::default();
+
+        for info in load_index(app_handle)? {
+            let content = match fs::read_to_string(dir.join(format!("{}.txt", info.name))) {
+                Ok(content) => content,
+                Err(e) => {
+                    eprintln!("Warning: Cannot read hash set '{}': {}", info.name, e);
+                    continue;
+                }
+            };
+
+            for hash in content.lines() {
+                lookup.insert(hash, info.classification.clone(), &info.name);
+            }
+        }
+
+        Ok(lookup)
+    }
+
+    /// Add a hash, keeping an existing known-bad entry over a known-good one.
+    pub fn insert(&mut self, hash: &str, classification: HashClassification, source: &str) {
+        let hash = hash.trim().to_lowercase();
+        if hash.is_empty() {
+            return;
+        }
+
+        let existing_bad = matches!(
+            self.entries.get(&hash),
+            Some((HashClassification::KnownBad, _))
+        );
+        if !existing_bad {
+            self.entries
+                .insert(hash, (classification, source.to_string()));
+        }
+    }
+
+    /// Check whether any hashes are loaded.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Look up a hash, returning its classification and source set.
+    pub fn classify(&self, hash: &str) -> (HashClassification, Option<String>) {
+        match self.entries.get(&hash.to_lowercase()) {
+            Some((classification, source)) => (classification.clone(), Some(source.clone())),
+            None => (HashClassification::Unknown, None),
+        }
+    }
+}
+
+/// Annotate alerts with verdicts for every file hash found in the configured
+/// evidence fields. Import hashes aren't file hashes and are skipped.
+pub fn annotate_alerts(lookup: &HashLookup, hash_fields: &[String], alerts: &mut [AlertEvent]) {
+    for alert in alerts.iter_mut() {
+        let mut seen = HashSet::new();
+
+        for event in &alert.evidence {
+            for field in hash_fields {
+                for value in db_engine::get_field_values(event, field) {
+                    for (kind, hash) in extract_typed_hashes(field, &value) {
+                        if kind == HashKind::Imphash {
+                            continue;
+                        }
+                        if !seen.insert((field.clone(), hash.clone())) {
+                            continue;
+                        }
+
+                        let (verdict, source) = lookup.classify(&hash);
+                        alert.hash_annotations.push(HashAnnotation {
+                            field: field.clone(),
+                            hash,
+                            verdict,
+                            source,
+                        });
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_hashes_from_sysmon_and_nsrl() {
+        let sysmon =
+            "SHA1=3F7A5C6E1D2B4A8F9E0C1D2B3A4F5E6D7C8B9A0F,MD5=0123456789ABCDEF0123456789ABCDEF";
+        let hashes = extract_hashes(sysmon);
+        assert_eq!(hashes.len(), 2);
+        assert_eq!(hashes[1], "0123456789abcdef0123456789abcdef");
+
+        let nsrl = r#""0000002D9D62AEBE1E0E9DB6C4C4C7C16A163D2C","1D6EBB5A789ABD108FF578263E1F40F3","x.exe""#;
+        assert_eq!(extract_hashes(nsrl).len(), 2);
+
+        assert!(extract_hashes("eventName=AssumeRole").is_empty());
+    }
+
+    #[test]
+    fn test_typed_hashes_use_prefix_and_field_name() {
+        let imphash = "f34d5f2d4577ed6d9ceec516c1f5a744";
+        let sysmon = format!(
+            "SHA1=DA39A3EE5E6B4B0D3255BFEF95601890AFD80709,IMPHASH={}",
+            imphash.to_uppercase()
+        );
+        assert_eq!(
+            extract_typed_hashes("Hashes", &sysmon),
+            vec![
+                (
+                    HashKind::Sha1,
+                    "da39a3ee5e6b4b0d3255bfef95601890afd80709".to_string()
+                ),
+                (HashKind::Imphash, imphash.to_string()),
+            ]
+        );
+        assert_eq!(
+            extract_typed_hashes("EventData.Imphash", imphash),
+            vec![(HashKind::Imphash, imphash.to_string())]
+        );
+        assert_eq!(
+            extract_typed_hashes("hash", imphash),
+            vec![(HashKind::Md5, imphash.to_string())]
+        );
+
+        let mut lookup = HashLookup::default();
+        lookup.insert(imphash, HashClassification::KnownBad, "malware");
+        let mut alerts: Vec<AlertEvent> = vec![serde_json::from_value(serde_json::json!({
+            "rule_id": "r1",
+            "rule_title": "Test",
+            "severity": "high",
+            "timestamp": "2025-12-16T11:05:00+00:00",
+            "match_count": 1,
+            "evidence": [{ "Hashes": sysmon, "Imphash": imphash }]
+        }))
+        .unwrap()];
+        annotate_alerts(
+            &lookup,
+            &["Hashes".to_string(), "Imphash".to_string()],
+            &mut alerts,
+        );
+        let annotated: Vec<&str> = alerts[0]
+            .hash_annotations
+            .iter()
+            .map(|a| a.hash.as_str())
+            .collect();
+        assert_eq!(annotated, vec!["da39a3ee5e6b4b0d3255bfef95601890afd80709"]);
+    }
+
+    #[test]
+    fn test_known_bad_takes_precedence() {
+        let hash = "0123456789abcdef0123456789abcdef";
+        let mut lookup = HashLookup::default();
+        lookup.insert(hash, HashClassification::KnownBad, "malware");
+        lookup.insert(hash, HashClassification::KnownGood, "nsrl");
+
+        let (verdict, source) = lookup.classify(&hash.to_uppercase());
+        assert_eq!(verdict, HashClassification::KnownBad);
+        assert_eq!(source.as_deref(), Some("malware"));
+        assert_eq!(
+            lookup.classify("ffffffffffffffffffffffffffffffff").0,
+            HashClassification::Unknown
+        );
+    }
+}
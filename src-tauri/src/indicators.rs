@@ -0,0 +1,188 @@
+//! Indicators (IOCs) extracted from alert evidence.
+//!
+//! Exporters for threat intelligence formats (STIX, MISP) need the
+//! observables behind an alert rather than raw events. Every string value of
+//! the evidence events is checked: IP addresses and AWS ARNs are recognized by
+//! their value, user names by their field name, and file hashes by either a
+//! hash-like field name or a value that is exactly an MD5/SHA-1/SHA-256.
+//! A hash's algorithm comes from its `ALGO=` prefix or field name before its
+//! length, so import hashes (`IMPHASH=`, `Imphash`) aren't reported as MD5s.
+
+use serde::Serialize;
+use std::net::IpAddr;
+
+use crate::hash_sets::{self, HashKind};
+use crate::models::AlertEvent;
+
+/// Field names (last path segment, case-insensitive) holding user names.
+const USER_FIELDS: [&str; 4] = ["username", "user_name", "user", "account_name"];
+
+/// Field name fragments marking a field as holding file hashes.
+const HASH_FIELD_HINTS: [&str; 4] = ["hash", "md5", "sha1", "sha256"];
+
+/// Kind of an indicator.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IndicatorKind {
+    Ipv4,
+    Ipv6,
+    UserName,
+    Arn,
+    Md5,
+    Sha1,
+    Sha256,
+    Imphash,
+}
+
+/// An observable found in an alert's evidence.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Indicator {
+    pub kind: IndicatorKind,
+    pub value: String,
+    /// Dotted path of the first evidence field it was found in
+    pub field: String,
+}
+
+/// Distinct indicators of an alert, in order of first appearance.
+pub fn extract_indicators(alert: &AlertEvent) -> Vec<Indicator> {
+    let mut indicators = Vec::new();
+    for event in &alert.evidence {
+        collect(event, "", &mut indicators);
+    }
+    indicators
+}
+
+/// Walk a JSON value, adding the indicators of its string leaves.
+fn collect(value: &serde_json::Value, path: &str, indicators: &mut Vec<Indicator>) {
+    match value {
+        serde_json::Value::Object(obj) => {
+            for (key, value) in obj {
+                let path = if path.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{}.{}", path, key)
+                };
+                collect(value, &path, indicators);
+            }
+        }
+        serde_json::Value::Array(values) => {
+            for value in values {
+                collect(value, path, indicators);
+            }
+        }
+        serde_json::Value::String(text) => {
+            for (kind, value) in classify(path, text.trim()) {
+                if !indicators
+                    .iter()
+                    .any(|i| i.kind == kind && i.value == value)
+                {
+                    indicators.push(Indicator {
+                        kind,
+                        value,
+                        field: path.to_string(),
+                    });
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Indicators in one field value.
+fn classify(path: &str, text: &str) -> Vec<(IndicatorKind, String)> {
+    if text.is_empty() {
+        return Vec::new();
+    }
+    if let Ok(ip) = text.parse::<IpAddr>() {
+        let kind = if ip.is_ipv4() {
+            IndicatorKind::Ipv4
+        } else {
+            IndicatorKind::Ipv6
+        };
+        return vec![(kind, ip.to_string())];
+    }
+    if text.starts_with("arn:") && text.split(':').count() >= 6 {
+        return vec![(IndicatorKind::Arn, text.to_string())];
+    }
+
+    let field = path.rsplit('.').next().unwrap_or(path).to_lowercase();
+    if USER_FIELDS.contains(&field.as_str()) {
+        return vec![(IndicatorKind::UserName, text.to_string())];
+    }
+
+    let hashes = hash_sets::extract_typed_hashes(path, text);
+    let hashes = if HASH_FIELD_HINTS.iter().any(|hint| field.contains(hint)) {
+        hashes
+    } else {
+        hashes
+            .into_iter()
+            .filter(|(_, hash)| hash.len() == text.len())
+            .collect()
+    };
+    hashes
+        .into_iter()
+        .map(|(kind, hash)| {
+            let kind = match kind {
+                HashKind::Md5 => IndicatorKind::Md5,
+                HashKind::Sha1 => IndicatorKind::Sha1,
+                HashKind::Sha256 => IndicatorKind::Sha256,
+                HashKind::Imphash => IndicatorKind::Imphash,
+            };
+            (kind, hash)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_indicators() {
+        let alert: AlertEvent = serde_json::from_value(serde_json::json!({
+            "rule_id": "r1",
+            "rule_title": "Test",
+            "severity": "high",
+            "timestamp": "2025-12-16T11:05:00+00:00",
+            "match_count": 2,
+            "evidence": [
+                {
+                    "sourceIPAddress": "203.0.113.7",
+                    "userIdentity": {
+                        "arn": "arn:aws:iam::123456789012:user/alice",
+                        "userName": "alice"
+                    },
+                    "requestID": "not-a-hash",
+                    "Hashes": "MD5=D41D8CD98F00B204E9800998ECF8427E,SHA1=da39a3ee5e6b4b0d3255bfef95601890afd80709",
+                    "Imphash": "f34d5f2d4577ed6d9ceec516c1f5a744"
+                },
+                { "sourceIPAddress": "203.0.113.7", "dst": ["2001:db8::1"] }
+            ]
+        }))
+        .unwrap();
+
+        let found: Vec<(IndicatorKind, String)> = extract_indicators(&alert)
+            .into_iter()
+            .map(|i| (i.kind, i.value))
+            .collect();
+        let expected = [
+            (IndicatorKind::Md5, "d41d8cd98f00b204e9800998ecf8427e"),
+            (
+                IndicatorKind::Sha1,
+                "da39a3ee5e6b4b0d3255bfef95601890afd80709",
+            ),
+            (IndicatorKind::Imphash, "f34d5f2d4577ed6d9ceec516c1f5a744"),
+            (IndicatorKind::Ipv4, "203.0.113.7"),
+            (IndicatorKind::Arn, "arn:aws:iam::123456789012:user/alice"),
+            (IndicatorKind::UserName, "alice"),
+            (IndicatorKind::Ipv6, "2001:db8::1"),
+        ];
+        assert_eq!(
+            found,
+            expected
+                .iter()
+                .map(|(kind, value)| (*kind, value.to_string()))
+                .collect::<Vec<_>>()
+        );
+    }
+}
@@ -0,0 +1,493 @@
+//! Persistent ingestion cache.
+//!
+//! Log files can be parsed once and written into a DuckDB database in the app
+//! data directory, one table per source file holding each event as a JSON
+//! column `event` (in file order, numbered by `seq`). Scans of an ingested
+//! file read its table instead of re-parsing the JSON, with rule conditions
+//! prefiltered in SQL for every log type, and ad-hoc queries can select from
+//! the tables directly.
+//!
+//! An ingested copy is only used while the file's size, modification time and
+//! log type match the ones recorded at ingestion; a changed file is read from
+//! disk again until it is re-ingested.
+
+use duckdb::{params, Connection};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+
+use crate::app_data;
+use crate::db_engine;
+use crate::models::{LogType, SiemError};
+
+/// Cache database file inside the app data dir.
+pub const CACHE_FILE: &str = "ingest_cache.duckdb";
+
+/// Table recording the ingested sources.
+const SOURCES_TABLE: &str = "ingested_sources";
+
+/// A log file ingested into the cache.
+#[derive(Debug, Serialize, Clone)]
+pub struct IngestedSource {
+    /// Path of the source log file
+    pub source_path: String,
+    /// Table holding its events
+    pub table_name: String,
+    pub log_type: LogType,
+    pub event_count: usize,
+    /// Size of the file when ingested
+    pub size_bytes: u64,
+    /// Modification time of the file when ingested (ISO 8601)
+    pub modified: String,
+    /// When the file was ingested (ISO 8601)
+    pub ingested_at: String,
+    /// Whether the file changed (or disappeared) since it was ingested
+    pub stale: bool,
+}
+
+/// An up-to-date ingested source, ready to be scanned.
+pub struct CachedSource {
+    conn: Connection,
+    source: IngestedSource,
+}
+
+impl CachedSource {
+    pub fn source(&self) -> &IngestedSource {
+        &self.source
+    }
+
+    /// Stream the cached events in file order, in chunks of `chunk_size`.
+    /// Returns the number of events read.
+    pub fn stream_events<F>(&self, chunk_size: usize, mut on_chunk: F) -> Result<usize, SiemError>
+    where
+        F: FnMut(Vec<serde_json::Value>),
+    {
+        let query = format!(
+            "SELECT CAST(event AS VARCHAR) FROM \"{}\" ORDER BY seq",
+            self.source.table_name
+        );
+        let mut stmt = self
+            .conn
+            .prepare(&query)
+            .map_err(|e| SiemError::Query(format!("Failed to prepare query: {}", e)))?;
+        let mut rows = stmt
+            .query([])
+            .map_err(|e| SiemError::Query(format!("Failed to execute query: {}", e)))?;
+
+        let chunk_size = chunk_size.max(1);
+        let mut chunk = Vec::new();
+        let mut total = 0;
+        while let Some(row) = rows
+            .next()
+            .map_err(|e| SiemError::Query(format!("Failed to fetch row: {}", e)))?
+        {
+            let text: String = row
+                .get(0)
+                .map_err(|e| SiemError::Query(format!("Failed to fetch row: {}", e)))?;
+            let event = serde_json::from_str(&text)
+                .map_err(|e| SiemError::Query(format!("Failed to parse JSON: {}", e)))?;
+
+            chunk.push(event);
+            total += 1;
+            if chunk.len() >= chunk_size {
+                on_chunk(std::mem::take(&mut chunk));
+            }
+        }
+        if !chunk.is_empty() {
+            on_chunk(chunk);
+        }
+
+        Ok(total)
+    }
+
+    /// Stream the cached events accepted by any of the given SQL predicates,
+    /// with one flag per predicate (see `db_engine::query_event_records`).
+    pub fn query_events<F>(
+        &self,
+        predicates: &[String],
+        chunk_size: usize,
+        on_chunk: F,
+    ) -> Result<(), SiemError>
+    where
+        F: FnMut(Vec<(serde_json::Value, Vec<bool>)>),
+    {
+        db_engine::query_event_records(
+            &self.conn,
+            &self.records_query(),
+            predicates,
+            chunk_size,
+            on_chunk,
+        )
+    }
+
+    /// Subquery yielding the cached events in file order, as a JSON column `event`.
+    pub fn records_query(&self) -> String {
+        format!(
+            "SELECT event FROM \"{}\" ORDER BY seq",
+            self.source.table_name
+        )
+    }
+
+    /// Connection to the cache database.
+    pub fn connection(&self) -> &Connection {
+        &self.conn
+    }
+}
+
+/// Get the path of the cache database.
+fn get_cache_path(app_handle: &tauri::AppHandle) -> Result<PathBuf, SiemError> {
+    app_data::app_data_file(app_handle, CACHE_FILE)
+}
+
+/// The cache database, opened once per process.
+/// Connections handed out are clones sharing this database instance.
+fn database() -> &'static Mutex<Option<Connection>> {
+    static DATABASE: OnceLock<Mutex<Option<Connection>>> = OnceLock::new();
+    DATABASE.get_or_init(|| Mutex::new(None))
+}
+
+/// Open a connection to the cache database.
+pub fn open(app_handle: &tauri::AppHandle) -> Result<Connection, SiemError> {
+    let mut database = database()
+        .lock()
+        .map_err(|_| SiemError::Query("Ingestion cache is unavailable".to_string()))?;
+
+    let conn = match database.take() {
+        Some(conn) => conn,
+        None => {
+            let path = get_cache_path(app_handle)?;
+            let conn = Connection::open(&path)
+                .map_err(|e| SiemError::Query(format!("Cannot open ingestion cache: {}", e)))?;
+            init_schema(&conn)?;
+            conn
+        }
+    };
+
+    let clone = conn
+        .try_clone()
+        .map_err(|e| SiemError::Query(format!("Cannot connect to ingestion cache: {}", e)));
+    *database = Some(conn);
+    clone
+}
+
+/// Create the sources table if needed.
+pub fn init_schema(conn: &Connection) -> Result<(), SiemError> {
+    conn.execute_batch(&format!(
+        "CREATE TABLE IF NOT EXISTS {} (
+            source_path VARCHAR PRIMARY KEY,
+            table_name VARCHAR NOT NULL,
+            log_type VARCHAR NOT NULL,
+            event_count BIGINT NOT NULL,
+            size_bytes BIGINT NOT NULL,
+            modified VARCHAR NOT NULL,
+            ingested_at VARCHAR NOT NULL
+        )",
+        SOURCES_TABLE
+    ))
+    .map_err(|e| SiemError::Query(format!("Cannot initialize ingestion cache: {}", e)))
+}
+
+/// Parse a log file and write its events into the cache, replacing any
+/// previous copy of the same file.
+pub fn ingest_file(
+    conn: &Connection,
+    log_path: &str,
+    log_type: &LogType,
+) -> Result<IngestedSource, SiemError> {
+    let (size_bytes, modified) = file_fingerprint(log_path)?;
+    let table_name = table_name(log_path);
+
+    conn.execute_batch("BEGIN TRANSACTION")
+        .map_err(|e| SiemError::Query(format!("Failed to start transaction: {}", e)))?;
+    let result = write_source(conn, log_path, log_type, &table_name, size_bytes, &modified);
+    let end = if result.is_ok() { "COMMIT" } else { "ROLLBACK" };
+    conn.execute_batch(end)
+        .map_err(|e| SiemError::Query(format!("Failed to finish transaction: {}", e)))?;
+
+    Ok(IngestedSource {
+        source_path: log_path.to_string(),
+        table_name,
+        log_type: log_type.clone(),
+        event_count: result?,
+        size_bytes,
+        modified,
+        ingested_at: chrono::Utc::now().to_rfc3339(),
+        stale: false,
+    })
+}
+
+/// (Re)create a source table, fill it and record it. Returns the event count.
+fn write_source(
+    conn: &Connection,
+    log_path: &str,
+    log_type: &LogType,
+    table_name: &str,
+    size_bytes: u64,
+    modified: &str,
+) -> Result<usize, SiemError> {
+    let map_err = |e: duckdb::Error| SiemError::Query(format!("Failed to ingest log file: {}", e));
+
+    conn.execute_batch(&format!(
+        "DROP TABLE IF EXISTS \"{0}\"; CREATE TABLE \"{0}\" (seq BIGINT, event JSON)",
+        table_name
+    ))
+    .map_err(map_err)?;
+
+    let mut appender = conn.appender(table_name).map_err(map_err)?;
+    let mut append_error = None;
+    let mut seq: i64 = 0;
+    let event_count =
+        db_engine::stream_events(log_path, log_type, db_engine::STREAM_CHUNK_SIZE, |chunk| {
+            for event in chunk {
+                if append_error.is_some() {
+                    return;
+                }
+                if let Err(e) = appender.append_row(params![seq, event.to_string()]) {
+                    append_error = Some(e);
+                }
+                seq += 1;
+            }
+        })?;
+    if let Some(e) = append_error {
+        return Err(map_err(e));
+    }
+    appender.flush().map_err(map_err)?;
+    drop(appender);
+
+    conn.execute(
+        &format!("DELETE FROM {} WHERE source_path = ?", SOURCES_TABLE),
+        params![log_path],
+    )
+    .map_err(map_err)?;
+    conn.execute(
+        &format!("INSERT INTO {} VALUES (?, ?, ?, ?, ?, ?, ?)", SOURCES_TABLE),
+        params![
+            log_path,
+            table_name,
+            log_type_name(log_type),
+            event_count as i64,
+            size_bytes as i64,
+            modified,
+            chrono::Utc::now().to_rfc3339(),
+        ],
+    )
+    .map_err(map_err)?;
+
+    Ok(event_count)
+}
+
+/// List every ingested source, flagging the ones whose file changed.
+pub fn list_sources(conn: &Connection) -> Result<Vec<IngestedSource>, SiemError> {
+    let query = format!(
+        "SELECT source_path, table_name, log_type, event_count, size_bytes, modified, ingested_at
+         FROM {} ORDER BY source_path",
+        SOURCES_TABLE
+    );
+    let mut stmt = conn
+        .prepare(&query)
+        .map_err(|e| SiemError::Query(format!("Failed to prepare query: {}", e)))?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, i64>(3)?,
+                row.get::<_, i64>(4)?,
+                row.get::<_, String>(5)?,
+                row.get::<_, String>(6)?,
+            ))
+        })
+        .map_err(|e| SiemError::Query(format!("Failed to execute query: {}", e)))?;
+
+    let mut sources = Vec::new();
+    for row in rows {
+        let (source_path, table_name, log_type, event_count, size_bytes, modified, ingested_at) =
+            row.map_err(|e| SiemError::Query(format!("Failed to fetch row: {}", e)))?;
+        let Some(log_type) = parse_log_type(&log_type) else {
+            continue;
+        };
+        let stale = file_fingerprint(&source_path).map_or(true, |current| {
+            current != (size_bytes as u64, modified.clone())
+        });
+
+        sources.push(IngestedSource {
+            source_path,
+            table_name,
+            log_type,
+            event_count: event_count as usize,
+            size_bytes: size_bytes as u64,
+            modified,
+            ingested_at,
+            stale,
+        });
+    }
+
+    Ok(sources)
+}
+
+/// Get the cached copy of a log file, if it is up to date for this log type.
+pub fn lookup(
+    conn: &Connection,
+    log_path: &str,
+    log_type: &LogType,
+) -> Result<Option<CachedSource>, SiemError> {
+    let source = list_sources(conn)?
+        .into_iter()
+        .find(|source| source.source_path == log_path);
+
+    match source {
+        Some(source) if !source.stale && source.log_type == *log_type => {
+            let conn = conn.try_clone().map_err(|e| {
+                SiemError::Query(format!("Cannot connect to ingestion cache: {}", e))
+            })?;
+            Ok(Some(CachedSource { conn, source }))
+        }
+        _ => Ok(None),
+    }
+}
+
+/// Drop the cached copy of a log file. Returns whether one existed.
+pub fn remove_source(conn: &Connection, log_path: &str) -> Result<bool, SiemError> {
+    let Some(source) = list_sources(conn)?
+        .into_iter()
+        .find(|source| source.source_path == log_path)
+    else {
+        return Ok(false);
+    };
+
+    conn.execute_batch(&format!("DROP TABLE IF EXISTS \"{}\"", source.table_name))
+        .and_then(|_| {
+            conn.execute(
+                &format!("DELETE FROM {} WHERE source_path = ?", SOURCES_TABLE),
+                params![log_path],
+            )
+        })
+        .map_err(|e| SiemError::Query(format!("Failed to remove cached log: {}", e)))?;
+
+    Ok(true)
+}
+
+/// Size and modification time of a file, identifying the ingested version.
+fn file_fingerprint(path: &str) -> Result<(u64, String), SiemError> {
+    let metadata = std::fs::metadata(Path::new(path))
+        .map_err(|e| SiemError::FileIO(format!("Cannot read file metadata: {}", e)))?;
+    let modified = metadata
+        .modified()
+        .map(|modified| chrono::DateTime::<chrono::Utc>::from(modified).to_rfc3339())
+        .unwrap_or_default();
+    Ok((metadata.len(), modified))
+}
+
+/// Table name for a source: its sanitized file stem plus a hash of the full
+/// path, so files with the same name in different folders don't collide.
+fn table_name(log_path: &str) -> String {
+    let stem: String = Path::new(log_path)
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_lowercase())
+        .unwrap_or_default()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .take(40)
+        .collect();
+    let digest = Sha256::digest(log_path.as_bytes());
+    format!("log_{}_{}", stem, app_data::hex(&digest[..4]))
+}
+
+pub fn log_type_name(log_type: &LogType) -> String {
+    match log_type {
+        LogType::CloudTrail => "cloudtrail".to_string(),
+        LogType::FlatJson => "flatjson".to_string(),
+        LogType::Kv => "kv".to_string(),
+        LogType::Suricata => "suricata".to_string(),
+        LogType::AzureActivity => "azureactivity".to_string(),
+        LogType::EntraSignIn => "entrasignin".to_string(),
+        LogType::Okta => "okta".to_string(),
+        LogType::Windows => "windows".to_string(),
+        LogType::WindowsXml => "windowsxml".to_string(),
+        LogType::Plugin(name) => format!("plugin:{}", name),
+        LogType::Text(name) => format!("text:{}", name),
+    }
+}
+
+pub fn parse_log_type(name: &str) -> Option<LogType> {
+    match name {
+        "cloudtrail" => Some(LogType::CloudTrail),
+        "flatjson" => Some(LogType::FlatJson),
+        "kv" => Some(LogType::Kv),
+        "suricata" => Some(LogType::Suricata),
+        "azureactivity" => Some(LogType::AzureActivity),
+        "entrasignin" => Some(LogType::EntraSignIn),
+        "okta" => Some(LogType::Okta),
+        "windows" => Some(LogType::Windows),
+        "windowsxml" => Some(LogType::WindowsXml),
+        _ => name
+            .strip_prefix("plugin:")
+            .map(|plugin| LogType::Plugin(plugin.to_string()))
+            .or_else(|| {
+                name.strip_prefix("text:")
+                    .map(|parser| LogType::Text(parser.to_string()))
+            }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_table_name_is_sanitized_and_unique() {
+        let a = table_name("/logs/a/Prod Trail-01.json");
+        let b = table_name("/logs/b/Prod Trail-01.json");
+        assert!(a.starts_with("log_prod_trail_01_"));
+        assert_ne!(a, b);
+        assert!(a.chars().all(|c| c.is_ascii_alphanumeric() || c == '_'));
+    }
+
+    #[test]
+    fn test_ingest_and_scan_cached_source() {
+        let path = std::env::temp_dir().join("offline_siem_test_ingest.json");
+        std::fs::write(
+            &path,
+            "{\"user\": \"alice\", \"action\": \"login\"}\n{\"user\": \"bob\", \"action\": \"logout\"}\n",
+        )
+        .unwrap();
+        let log_path = path.to_string_lossy().to_string();
+
+        let conn = Connection::open_in_memory().unwrap();
+        init_schema(&conn).unwrap();
+        let source = ingest_file(&conn, &log_path, &LogType::FlatJson).unwrap();
+        assert_eq!(source.event_count, 2);
+
+        assert!(lookup(&conn, &log_path, &LogType::CloudTrail)
+            .unwrap()
+            .is_none());
+        let cached = lookup(&conn, &log_path, &LogType::FlatJson)
+            .unwrap()
+            .unwrap();
+
+        let mut events = Vec::new();
+        let read = cached
+            .stream_events(1, |chunk| events.extend(chunk))
+            .unwrap();
+        assert_eq!(read, 2);
+        assert_eq!(events[0]["user"], "alice");
+        assert_eq!(events[1]["action"], "logout");
+
+        let mut matched = Vec::new();
+        cached
+            .query_events(
+                &["json_extract_string(event, '$.user') = 'bob'".to_string()],
+                10,
+                |chunk| matched.extend(chunk),
+            )
+            .unwrap();
+        assert_eq!(matched.len(), 1);
+        assert_eq!(matched[0].0["user"], "bob");
+
+        assert!(remove_source(&conn, &log_path).unwrap());
+        assert!(list_sources(&conn).unwrap().is_empty());
+        std::fs::remove_file(&path).ok();
+    }
+}
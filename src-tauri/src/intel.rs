@@ -0,0 +1,460 @@
+//! Offline threat intelligence: named IOC lists.
+//!
+//! IOC lists are imported from plain text or CSV feeds into `intel/` in the
+//! application's data directory, one normalized indicator per line. IP
+//! addresses, domains (also taken from URLs, defanged forms accepted), file
+//! hashes and AWS ARNs are recognized; anything else on a line is ignored.
+//!
+//! Lists are used in three ways:
+//! - conditions: `sourceIPAddress IN_LIST 'known_bad_ips'`, or
+//!   `IN_LIST 'ioc:known_bad_ips'` to name the store (see `term_sets`);
+//! - enrichment: after each scan, every string value of the evidence is
+//!   looked up in all lists and alerts are flagged with the IOCs they contain;
+//! - sweeps: every log file of the library is searched for the indicators of
+//!   a list, in any field ("is this IP anywhere in our logs?").
+
+use rayon::prelude::*;
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::fs;
+use std::net::IpAddr;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Instant;
+
+use crate::app_data;
+use crate::db_engine;
+use crate::log_manager;
+use crate::models::{
+    AlertEvent, FailedFileScan, IocFileHits, IocListInfo, IocMatch, IocSweepHit, IocSweepResult,
+    LogFileInfo, SiemError,
+};
+use crate::term_sets::{self, TermSet};
+
+/// Sample events kept per indicator and file in a sweep.
+const MAX_SWEEP_SAMPLES: usize = 5;
+
+/// Get the directory where IOC lists are stored.
+pub fn get_intel_dir(app_handle: &tauri::AppHandle) -> Result<PathBuf, SiemError> {
+    app_data::app_data_subdir(app_handle, "intel")
+}
+
+/// Load the IOC list index.
+fn load_index(app_handle: &tauri::AppHandle) -> Result<Vec<IocListInfo>, SiemError> {
+    let path = get_intel_dir(app_handle)?.join("index.json");
+
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let content = fs::read_to_string(&path)
+        .map_err(|e| SiemError::FileIO(format!("Cannot read IOC list index: {}", e)))?;
+
+    serde_json::from_str(&content)
+        .map_err(|e| SiemError::Serialization(format!("Cannot parse IOC list index: {}", e)))
+}
+
+/// Save the IOC list index.
+fn save_index(app_handle: &tauri::AppHandle, index: &[IocListInfo]) -> Result<(), SiemError> {
+    let path = get_intel_dir(app_handle)?.join("index.json");
+
+    let content = serde_json::to_string_pretty(index)
+        .map_err(|e| SiemError::Serialization(format!("Cannot serialize IOC list index: {}", e)))?;
+
+    fs::write(&path, content)
+        .map_err(|e| SiemError::FileIO(format!("Cannot write IOC list index: {}", e)))
+}
+
+/// Normalize a token into an indicator, if it is one.
+fn parse_indicator(token: &str) -> Option<String> {
+    let token = token
+        .trim()
+        .trim_matches(|c: char| matches!(c, '"' | '\'' | '<' | '>' | '(' | ')'))
+        .replace("[.]", ".")
+        .replace("(.)", ".");
+    if token.is_empty() {
+        return None;
+    }
+
+    if token.starts_with("arn:") && token.split(':').count() >= 6 {
+        return Some(token);
+    }
+    if let Ok(ip) = token.parse::<IpAddr>() {
+        return Some(ip.to_string());
+    }
+    if matches!(token.len(), 32 | 40 | 64) && token.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Some(token.to_lowercase());
+    }
+
+    // Domains, possibly inside a URL (hxxp://evil[.]com/path)
+    let host = match token.split_once("://") {
+        Some((_, rest)) => rest.split(['/', '?', '#', ':']).next().unwrap_or(""),
+        None => token.as_str(),
+    };
+    let host = host.trim_end_matches('.').to_lowercase();
+    let labels: Vec<&str> = host.split('.').collect();
+    let valid_labels = labels.iter().all(|label| {
+        !label.is_empty() && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+    });
+    let tld_alphabetic = labels
+        .last()
+        .is_some_and(|tld| tld.chars().all(|c| c.is_ascii_alphabetic()));
+    (labels.len() >= 2 && valid_labels && tld_alphabetic).then_some(host)
+}
+
+/// Extract every indicator of an IOC feed (plain text or CSV).
+pub fn extract_indicators(text: &str) -> HashSet<String> {
+    text.lines()
+        .filter(|line| !line.trim_start().starts_with('#'))
+        .flat_map(|line| line.split(|c: char| c == ',' || c == ';' || c.is_whitespace()))
+        .filter_map(parse_indicator)
+        .collect()
+}
+
+/// Import an IOC list from a text or CSV file, replacing a list of the same name.
+pub fn import_ioc_list(
+    app_handle: &tauri::AppHandle,
+    source_path: &str,
+    name: &str,
+    description: String,
+) -> Result<IocListInfo, SiemError> {
+    term_sets::validate_list_name(name)?;
+    let dir = get_intel_dir(app_handle)?;
+    if term_sets::find_named_list(&format!("{}:{}", term_sets::LOOKUP_LISTS, name)).is_some() {
+        return Err(SiemError::FileIO(format!(
+            "List name '{}' is already used by a lookup list",
+            name
+        )));
+    }
+
+    let content = fs::read_to_string(source_path)
+        .map_err(|e| SiemError::FileIO(format!("Cannot read IOC file: {}", e)))?;
+
+    let indicators = extract_indicators(&content);
+    if indicators.is_empty() {
+        return Err(SiemError::FileIO(
+            "No IP addresses, domains, hashes or ARNs found in file".to_string(),
+        ));
+    }
+
+    let mut sorted: Vec<&str> = indicators.iter().map(String::as_str).collect();
+    sorted.sort();
+
+    fs::write(dir.join(format!("{}.txt", name)), sorted.join("\n"))
+        .map_err(|e| SiemError::FileIO(format!("Cannot write IOC list: {}", e)))?;
+
+    let info = IocListInfo {
+        name: name.to_string(),
+        description,
+        indicator_count: indicators.len(),
+        imported_at: chrono::Utc::now().to_rfc3339(),
+    };
+
+    let mut index = load_index(app_handle)?;
+    index.retain(|i| i.name != name);
+    index.push(info.clone());
+    index.sort_by(|a, b| a.name.cmp(&b.name));
+    save_index(app_handle, &index)?;
+
+    Ok(info)
+}
+
+/// List imported IOC lists.
+pub fn list_ioc_lists(app_handle: &tauri::AppHandle) -> Result<Vec<IocListInfo>, SiemError> {
+    load_index(app_handle)
+}
+
+/// Delete an imported IOC list.
+pub fn delete_ioc_list(app_handle: &tauri::AppHandle, name: &str) -> Result<(), SiemError> {
+    term_sets::validate_list_name(name)?;
+
+    let mut index = load_index(app_handle)?;
+    let before = index.len();
+    index.retain(|i| i.name != name);
+
+    if index.len() == before {
+        return Err(SiemError::FileIO(format!("IOC list not found: {}", name)));
+    }
+
+    let path = get_intel_dir(app_handle)?.join(format!("{}.txt", name));
+    if path.exists() {
+        fs::remove_file(&path)
+            .map_err(|e| SiemError::FileIO(format!("Cannot delete IOC list: {}", e)))?;
+    }
+
+    save_index(app_handle, &index)
+}
+
+/// Load an IOC list's indicators.
+pub fn load_ioc_list(app_handle: &tauri::AppHandle, name: &str) -> Result<Arc<TermSet>, SiemError> {
+    term_sets::validate_list_name(name)?;
+    let path = get_intel_dir(app_handle)?.join(format!("{}.txt", name));
+    if !path.exists() {
+        return Err(SiemError::FileIO(format!("IOC list not found: {}", name)));
+    }
+    term_sets::load_term_set(&path.to_string_lossy())
+}
+
+/// Load every imported IOC list, skipping (and logging) unreadable ones.
+pub fn load_all(app_handle: &tauri::AppHandle) -> Result<Vec<(String, Arc<TermSet>)>, SiemError> {
+    Ok(load_index(app_handle)?
+        .into_iter()
+        .filter_map(|info| match load_ioc_list(app_handle, &info.name) {
+            Ok(set) => Some((info.name, set)),
+            Err(e) => {
+                eprintln!("Warning: Cannot load IOC list '{}': {}", info.name, e);
+                None
+            }
+        })
+        .collect())
+}
+
+/// Collect the string leaves of a JSON value with their dotted paths.
+pub fn string_leaves(value: &serde_json::Value, path: &str, leaves: &mut Vec<(String, String)>) {
+    match value {
+        serde_json::Value::Object(obj) => {
+            for (key, value) in obj {
+                let path = if path.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{}.{}", path, key)
+                };
+                string_leaves(value, &path, leaves);
+            }
+        }
+        serde_json::Value::Array(items) => {
+            for item in items {
+                string_leaves(item, path, leaves);
+            }
+        }
+        serde_json::Value::String(text) => leaves.push((path.to_string(), text.clone())),
+        _ => {}
+    }
+}
+
+/// Flag alerts whose evidence contains indicators from the given lists.
+pub fn annotate_alerts(lists: &[(String, Arc<TermSet>)], alerts: &mut [AlertEvent]) {
+    if lists.is_empty() {
+        return;
+    }
+
+    for alert in alerts.iter_mut() {
+        let mut leaves = Vec::new();
+        for event in &alert.evidence {
+            string_leaves(event, "", &mut leaves);
+        }
+
+        let mut seen = HashSet::new();
+        for (field, value) in leaves {
+            let value = value.trim();
+            for (list, set) in lists {
+                if set.contains_exact(value) && seen.insert((field.clone(), list.clone())) {
+                    alert.ioc_matches.push(IocMatch {
+                        field: field.clone(),
+                        value: value.to_string(),
+                        list: list.clone(),
+                    });
+                }
+            }
+        }
+    }
+}
+
+/// Occurrences of the indicators of a list in a set of events.
+#[derive(Default)]
+struct FileSweep {
+    /// indicator -> (event count, fields, sample events)
+    hits: BTreeMap<String, (usize, BTreeSet<String>, Vec<serde_json::Value>)>,
+    events: usize,
+}
+
+impl FileSweep {
+    /// Search the string values of events for indicators, as whole tokens.
+    fn observe(&mut self, set: &TermSet, events: Vec<serde_json::Value>) {
+        self.events += events.len();
+        for event in events {
+            let mut leaves = Vec::new();
+            string_leaves(&event, "", &mut leaves);
+
+            let mut found: BTreeMap<&str, BTreeSet<String>> = BTreeMap::new();
+            for (field, value) in &leaves {
+                for term in set.find_terms(value) {
+                    found.entry(term).or_default().insert(field.clone());
+                }
+            }
+            for (term, fields) in found {
+                let (count, all_fields, samples) = self.hits.entry(term.to_string()).or_default();
+                *count += 1;
+                all_fields.extend(fields);
+                if samples.len() < MAX_SWEEP_SAMPLES {
+                    samples.push(event.clone());
+                }
+            }
+        }
+    }
+}
+
+/// Sweep one log file.
+fn sweep_file(set: &TermSet, file: &LogFileInfo) -> Result<FileSweep, SiemError> {
+    let log_type = match file.log_type.clone() {
+        Some(log_type) => log_type,
+        None => db_engine::detect_log_type(&file.path)?,
+    };
+    let mut sweep = FileSweep::default();
+    db_engine::stream_events(
+        &file.path,
+        &log_type,
+        db_engine::STREAM_CHUNK_SIZE,
+        |chunk| sweep.observe(set, chunk),
+    )?;
+    Ok(sweep)
+}
+
+/// Search every file of the log library for the indicators of an IOC list,
+/// in any field. Hits are grouped by indicator, then by file.
+pub fn sweep_iocs(app_handle: &tauri::AppHandle, name: &str) -> Result<IocSweepResult, SiemError> {
+    let start = Instant::now();
+    let set = load_ioc_list(app_handle, name)?;
+    let files = log_manager::list_log_files(app_handle)?;
+
+    let sweeps: Vec<(LogFileInfo, Result<FileSweep, SiemError>)> = files
+        .into_par_iter()
+        .map(|file| {
+            let sweep = sweep_file(&set, &file);
+            (file, sweep)
+        })
+        .collect();
+
+    let mut indicators: BTreeMap<String, IocSweepHit> = BTreeMap::new();
+    let mut failed_files = Vec::new();
+    let mut files_scanned = 0;
+    let mut events_scanned = 0;
+    for (file, sweep) in sweeps {
+        let sweep = match sweep {
+            Ok(sweep) => sweep,
+            Err(e) => {
+                failed_files.push(FailedFileScan {
+                    file_name: file.filename,
+                    file_path: file.path,
+                    error: e.to_string(),
+                });
+                continue;
+            }
+        };
+        files_scanned += 1;
+        events_scanned += sweep.events;
+
+        for (indicator, (hit_count, fields, sample_events)) in sweep.hits {
+            let hit = indicators
+                .entry(indicator.clone())
+                .or_insert_with(|| IocSweepHit {
+                    indicator,
+                    hit_count: 0,
+                    files: Vec::new(),
+                });
+            hit.hit_count += hit_count;
+            hit.files.push(IocFileHits {
+                file_name: file.filename.clone(),
+                file_path: file.path.clone(),
+                hit_count,
+                fields: fields.into_iter().collect(),
+                sample_events,
+            });
+        }
+    }
+
+    let mut indicators: Vec<IocSweepHit> = indicators.into_values().collect();
+    indicators.sort_by(|a, b| b.hit_count.cmp(&a.hit_count));
+    for hit in &mut indicators {
+        hit.files.sort_by(|a, b| b.hit_count.cmp(&a.hit_count));
+    }
+
+    Ok(IocSweepResult {
+        list: name.to_string(),
+        indicators,
+        files_scanned,
+        events_scanned,
+        failed_files,
+        sweep_time_ms: start.elapsed().as_millis() as u64,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_indicators_from_feed() {
+        let feed = "# indicator,type\n\
+            203.0.113.7,ip\n\
+            \"hxxp://Evil-Domain[.]com/payload.exe\",url\n\
+            arn:aws:iam::123456789012:user/mallory\n\
+            44D88612FEA8A8F36DE82E1278ABB02F md5\n\
+            not_an_indicator; 1.5; localhost";
+        let mut indicators: Vec<String> = extract_indicators(feed).into_iter().collect();
+        indicators.sort();
+        assert_eq!(
+            indicators,
+            vec![
+                "203.0.113.7",
+                "44d88612fea8a8f36de82e1278abb02f",
+                "arn:aws:iam::123456789012:user/mallory",
+                "evil-domain.com",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_sweep_groups_hits_by_indicator() {
+        let set =
+            TermSet::from_terms(vec!["203.0.113.7".to_string(), "evil.com".to_string()]).unwrap();
+        let mut sweep = FileSweep::default();
+        sweep.observe(
+            &set,
+            vec![
+                serde_json::json!({ "sourceIPAddress": "203.0.113.7", "note": "from 203.0.113.7" }),
+                serde_json::json!({ "requestParameters": { "url": "https://cdn.evil.com/x" } }),
+                serde_json::json!({ "sourceIPAddress": "203.0.113.70", "host": "notevil.com" }),
+            ],
+        );
+
+        assert_eq!(sweep.events, 3);
+        let (count, fields, samples) = &sweep.hits["203.0.113.7"];
+        assert_eq!(*count, 1);
+        assert_eq!(
+            fields.iter().map(String::as_str).collect::<Vec<_>>(),
+            vec!["note", "sourceIPAddress"]
+        );
+        assert_eq!(samples.len(), 1);
+        let (count, fields, _) = &sweep.hits["evil.com"];
+        assert_eq!(*count, 1);
+        assert!(fields.contains("requestParameters.url"));
+    }
+
+    #[test]
+    fn test_annotate_alerts_flags_known_iocs() {
+        let lists = vec![(
+            "bad_ips".to_string(),
+            Arc::new(TermSet::from_terms(vec!["203.0.113.7".to_string()]).unwrap()),
+        )];
+        let mut alerts: Vec<AlertEvent> = vec![serde_json::from_value(serde_json::json!({
+            "rule_id": "r1",
+            "rule_title": "r1",
+            "severity": "high",
+            "timestamp": "2025-12-16T11:05:00+00:00",
+            "match_count": 2,
+            "evidence": [
+                { "sourceIPAddress": "203.0.113.7", "tlsDetails": { "clientProvidedHostHeader": "x" } },
+                { "sourceIPAddress": "203.0.113.7" }
+            ]
+        }))
+        .unwrap()];
+
+        annotate_alerts(&lists, &mut alerts);
+        let matches = &alerts[0].ioc_matches;
+        assert_eq!(matches.len(), 1);
+        assert_eq!(
+            (matches[0].field.as_str(), matches[0].list.as_str()),
+            ("sourceIPAddress", "bad_ips")
+        );
+    }
+}
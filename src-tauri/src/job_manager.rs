@@ -0,0 +1,328 @@
+//! Background scan jobs.
+//!
+//! `enqueue` queues a scan of one or more log files and returns at once; jobs
+//! run on background threads, at most `MAX_RUNNING_JOBS` at a time, in the
+//! order they were queued. Each file is scanned with the `ScanFn` the manager
+//! was created with, so a job's alerts are suppressed, routed and recorded
+//! like those of an interactive scan. Cancelling a queued job drops it;
+//! cancelling a running one stops it before its next file. The manager lives
+//! in Tauri state and keeps the last `MAX_FINISHED_JOBS` finished jobs.
+
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use tauri::Manager;
+
+use crate::folder_watch::ScanFn;
+use crate::models::{AlertEvent, SiemError};
+
+/// Jobs running at the same time; further jobs wait in the queue.
+const MAX_RUNNING_JOBS: usize = 2;
+
+/// Finished jobs kept for `get_job_status` and `list_jobs`.
+const MAX_FINISHED_JOBS: usize = 50;
+
+/// Lifecycle of a job.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum JobState {
+    Queued,
+    Running,
+    /// All files were scanned (some may have failed)
+    Completed,
+    /// No file could be scanned
+    Failed,
+    Cancelled,
+}
+
+impl JobState {
+    fn is_finished(self) -> bool {
+        !matches!(self, JobState::Queued | JobState::Running)
+    }
+}
+
+/// Outcome of one file of a job.
+#[derive(Debug, Serialize, Clone)]
+pub struct JobFileResult {
+    pub file_path: String,
+    pub alerts: Vec<AlertEvent>,
+    /// Why the file couldn't be scanned
+    pub error: Option<String>,
+}
+
+/// A queued, running or finished scan job.
+#[derive(Debug, Serialize, Clone)]
+pub struct ScanJob {
+    pub id: String,
+    pub state: JobState,
+    /// Files to scan, in order
+    pub log_paths: Vec<String>,
+    /// When the job was queued, started and finished (ISO 8601)
+    pub created_at: String,
+    pub started_at: Option<String>,
+    pub finished_at: Option<String>,
+    /// Files scanned so far (failed ones included)
+    pub files_done: usize,
+    pub alert_count: usize,
+    /// Per-file outcomes, in scan order
+    pub results: Vec<JobFileResult>,
+}
+
+struct Job {
+    info: ScanJob,
+    cancel: Arc<AtomicBool>,
+}
+
+/// Queue of scan jobs, stored in Tauri state.
+pub struct JobManager {
+    jobs: Mutex<Vec<Job>>,
+    scan: ScanFn,
+}
+
+impl JobManager {
+    pub fn new(scan: ScanFn) -> Self {
+        JobManager {
+            jobs: Mutex::new(Vec::new()),
+            scan,
+        }
+    }
+
+    fn lock(&self) -> Result<std::sync::MutexGuard<'_, Vec<Job>>, SiemError> {
+        self.jobs
+            .lock()
+            .map_err(|_| SiemError::Query("Job queue is unavailable".to_string()))
+    }
+
+    /// Get a job by ID.
+    pub fn get(&self, job_id: &str) -> Result<ScanJob, SiemError> {
+        self.lock()?
+            .iter()
+            .find(|job| job.info.id == job_id)
+            .map(|job| job.info.clone())
+            .ok_or_else(|| SiemError::Query(format!("Job not found: {}", job_id)))
+    }
+
+    /// List jobs, newest first, without their per-file alerts.
+    pub fn list(&self) -> Result<Vec<ScanJob>, SiemError> {
+        Ok(self
+            .lock()?
+            .iter()
+            .rev()
+            .map(|job| ScanJob {
+                results: Vec::new(),
+                ..job.info.clone()
+            })
+            .collect())
+    }
+}
+
+/// Queue a scan of `log_paths` and start it if a slot is free.
+pub fn enqueue(
+    app_handle: &tauri::AppHandle,
+    log_paths: Vec<String>,
+) -> Result<ScanJob, SiemError> {
+    if log_paths.is_empty() {
+        return Err(SiemError::FileIO("No log files to scan".to_string()));
+    }
+
+    let manager = app_handle.state::<JobManager>();
+    let info = ScanJob {
+        id: uuid::Uuid::new_v4().to_string(),
+        state: JobState::Queued,
+        log_paths,
+        created_at: chrono::Utc::now().to_rfc3339(),
+        started_at: None,
+        finished_at: None,
+        files_done: 0,
+        alert_count: 0,
+        results: Vec::new(),
+    };
+    manager.lock()?.push(Job {
+        info: info.clone(),
+        cancel: Arc::new(AtomicBool::new(false)),
+    });
+
+    dispatch(app_handle);
+    manager.get(&info.id)
+}
+
+/// Cancel a job. A running job stops once its current file is scanned.
+pub fn cancel(app_handle: &tauri::AppHandle, job_id: &str) -> Result<ScanJob, SiemError> {
+    let manager = app_handle.state::<JobManager>();
+    let mut jobs = manager.lock()?;
+    let job = jobs
+        .iter_mut()
+        .find(|job| job.info.id == job_id)
+        .ok_or_else(|| SiemError::Query(format!("Job not found: {}", job_id)))?;
+
+    match job.info.state {
+        JobState::Queued => {
+            job.info.state = JobState::Cancelled;
+            job.info.finished_at = Some(chrono::Utc::now().to_rfc3339());
+        }
+        JobState::Running => job.cancel.store(true, Ordering::Relaxed),
+        state => {
+            return Err(SiemError::Query(format!(
+                "Job {} already finished ({:?})",
+                job_id, state
+            )))
+        }
+    }
+    Ok(job.info.clone())
+}
+
+/// Indices of the queued jobs to start so that at most `max_running` run.
+fn jobs_to_start(jobs: &[Job], max_running: usize) -> Vec<usize> {
+    let running = jobs
+        .iter()
+        .filter(|job| job.info.state == JobState::Running)
+        .count();
+    jobs.iter()
+        .enumerate()
+        .filter(|(_, job)| job.info.state == JobState::Queued)
+        .map(|(index, _)| index)
+        .take(max_running.saturating_sub(running))
+        .collect()
+}
+
+/// Drop the oldest finished jobs beyond `max_finished`.
+fn prune_finished(jobs: &mut Vec<Job>, max_finished: usize) {
+    let finished = jobs
+        .iter()
+        .filter(|job| job.info.state.is_finished())
+        .count();
+    let mut excess = finished.saturating_sub(max_finished);
+    jobs.retain(|job| {
+        if excess > 0 && job.info.state.is_finished() {
+            excess -= 1;
+            return false;
+        }
+        true
+    });
+}
+
+/// Start queued jobs while running slots are free.
+fn dispatch(app_handle: &tauri::AppHandle) {
+    let manager = app_handle.state::<JobManager>();
+    let Ok(mut jobs) = manager.lock() else {
+        return;
+    };
+
+    for index in jobs_to_start(&jobs, MAX_RUNNING_JOBS) {
+        let job = &mut jobs[index];
+        job.info.state = JobState::Running;
+        job.info.started_at = Some(chrono::Utc::now().to_rfc3339());
+
+        let app_handle = app_handle.clone();
+        let job_id = job.info.id.clone();
+        let log_paths = job.info.log_paths.clone();
+        let cancel = job.cancel.clone();
+        let scan = manager.scan;
+        std::thread::spawn(move || run_job(&app_handle, &job_id, log_paths, &cancel, scan));
+    }
+}
+
+/// Scan a job's files one after the other, then start the next queued jobs.
+fn run_job(
+    app_handle: &tauri::AppHandle,
+    job_id: &str,
+    log_paths: Vec<String>,
+    cancel: &AtomicBool,
+    scan: ScanFn,
+) {
+    let manager = app_handle.state::<JobManager>();
+    let update = |apply: &dyn Fn(&mut ScanJob)| {
+        if let Ok(mut jobs) = manager.lock() {
+            if let Some(job) = jobs.iter_mut().find(|job| job.info.id == job_id) {
+                apply(&mut job.info);
+            }
+        }
+    };
+
+    for file_path in log_paths {
+        if cancel.load(Ordering::Relaxed) {
+            break;
+        }
+        let result = match scan(app_handle, &file_path) {
+            Ok(alerts) => JobFileResult {
+                file_path,
+                alerts,
+                error: None,
+            },
+            Err(e) => {
+                eprintln!("Warning: Job {} cannot scan '{}': {}", job_id, file_path, e);
+                JobFileResult {
+                    file_path,
+                    alerts: Vec::new(),
+                    error: Some(e.to_string()),
+                }
+            }
+        };
+        update(&|job| {
+            job.files_done += 1;
+            job.alert_count += result.alerts.len();
+            job.results.push(result.clone());
+        });
+    }
+
+    let cancelled = cancel.load(Ordering::Relaxed);
+    update(&|job| {
+        job.state = if cancelled {
+            JobState::Cancelled
+        } else if job.results.iter().all(|r| r.error.is_some()) {
+            JobState::Failed
+        } else {
+            JobState::Completed
+        };
+        job.finished_at = Some(chrono::Utc::now().to_rfc3339());
+    });
+    if let Ok(mut jobs) = manager.lock() {
+        prune_finished(&mut jobs, MAX_FINISHED_JOBS);
+    }
+
+    dispatch(app_handle);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn job(id: &str, state: JobState) -> Job {
+        Job {
+            info: ScanJob {
+                id: id.to_string(),
+                state,
+                log_paths: vec![format!("{}.json", id)],
+                created_at: String::new(),
+                started_at: None,
+                finished_at: None,
+                files_done: 0,
+                alert_count: 0,
+                results: Vec::new(),
+            },
+            cancel: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    #[test]
+    fn test_job_scheduling() {
+        let mut jobs = vec![
+            job("done", JobState::Completed),
+            job("running", JobState::Running),
+            job("q1", JobState::Queued),
+            job("cancelled", JobState::Cancelled),
+            job("q2", JobState::Queued),
+            job("q3", JobState::Queued),
+        ];
+
+        // Queued jobs start in order, in the free slots only
+        assert_eq!(jobs_to_start(&jobs, 2), vec![2]);
+        assert_eq!(jobs_to_start(&jobs, 3), vec![2, 4]);
+        assert!(jobs_to_start(&jobs, 1).is_empty());
+
+        // The oldest finished jobs go first; unfinished ones are kept
+        prune_finished(&mut jobs, 1);
+        let ids: Vec<&str> = jobs.iter().map(|j| j.info.id.as_str()).collect();
+        assert_eq!(ids, vec!["running", "q1", "cancelled", "q2", "q3"]);
+    }
+}
@@ -0,0 +1,147 @@
+//! Key=value logs (`LogType::Kv`).
+//!
+//! Firewall and audit logs often write one event per line as
+//! `key=value key2="quoted value"` pairs:
+//!
+//! ```text
+//! date=2024-03-01 time=10:01:02 action="deny" srcip=10.0.0.5 srcport=52114 msg="Port scan"
+//! ```
+//!
+//! Each pair becomes a field of the event. Values may be double- or
+//! single-quoted (with `\` escapes) or bare up to the next space; bare
+//! integers become numbers. Words without `=` are ignored, and lines without
+//! any pair are skipped.
+
+use std::io::BufRead;
+
+use crate::models::SiemError;
+
+/// Parse a line of `key=value` pairs into an event, or None if it has none.
+pub fn parse_line(line: &str) -> Option<serde_json::Value> {
+    let mut event = serde_json::Map::new();
+    let mut chars = line.chars().peekable();
+
+    loop {
+        // Key: up to '=' or whitespace
+        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+        if chars.peek().is_none() {
+            break;
+        }
+        let mut key = String::new();
+        while let Some(c) = chars.next_if(|c| *c != '=' && !c.is_whitespace()) {
+            key.push(c);
+        }
+        if chars.next_if_eq(&'=').is_none() || key.is_empty() {
+            // A word without a value, or a stray '=': skip it
+            continue;
+        }
+
+        // Value: quoted, or bare up to whitespace
+        let value = match chars.next_if(|c| *c == '"' || *c == '\'') {
+            Some(quote) => {
+                let mut value = String::new();
+                while let Some(c) = chars.next() {
+                    match c {
+                        '\\' => value.extend(chars.next()),
+                        c if c == quote => break,
+                        c => value.push(c),
+                    }
+                }
+                serde_json::Value::from(value)
+            }
+            None => {
+                let mut value = String::new();
+                while let Some(c) = chars.next_if(|c| !c.is_whitespace()) {
+                    value.push(c);
+                }
+                bare_value(value)
+            }
+        };
+        event.insert(key, value);
+    }
+
+    (!event.is_empty()).then_some(serde_json::Value::Object(event))
+}
+
+/// A bare value: a number if it is a plain integer, otherwise a string.
+fn bare_value(value: String) -> serde_json::Value {
+    match value.parse::<i64>() {
+        // Keep values like "007" or "+1" as written
+        Ok(number) if number.to_string() == value => serde_json::Value::from(number),
+        _ => serde_json::Value::from(value),
+    }
+}
+
+/// Whether a line looks like a key=value log line (for log type detection).
+pub fn looks_like_kv(line: &str) -> bool {
+    let line = line.trim_start();
+    !line.starts_with('{')
+        && !line.starts_with('[')
+        && parse_line(line)
+            .and_then(|event| event.as_object().map(|fields| fields.len() >= 2))
+            .unwrap_or(false)
+}
+
+/// Read a key=value file line by line in chunks of at most `chunk_size`
+/// events. Returns the number of events.
+pub fn stream_events<R, F>(
+    reader: R,
+    chunk_size: usize,
+    on_chunk: &mut F,
+) -> Result<usize, SiemError>
+where
+    R: BufRead,
+    F: FnMut(Vec<serde_json::Value>),
+{
+    let mut chunk = Vec::new();
+    let mut total = 0;
+    for line in reader.lines() {
+        let line = line.map_err(|e| SiemError::Query(format!("Failed to read log file: {}", e)))?;
+        if let Some(event) = parse_line(&line) {
+            chunk.push(event);
+            total += 1;
+            if chunk.len() >= chunk_size {
+                on_chunk(std::mem::take(&mut chunk));
+            }
+        }
+    }
+    if !chunk.is_empty() {
+        on_chunk(chunk);
+    }
+    Ok(total)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_kv_line() {
+        let event = parse_line(
+            r#"date=2024-03-01 action="deny" srcport=52114 zip=007 msg="Port \"scan\"" note='a b' flag empty="""#,
+        )
+        .unwrap();
+        assert_eq!(event["date"], "2024-03-01");
+        assert_eq!(event["action"], "deny");
+        assert_eq!(event["srcport"], 52114);
+        assert_eq!(event["zip"], "007");
+        assert_eq!(event["msg"], "Port \"scan\"");
+        assert_eq!(event["note"], "a b");
+        assert_eq!(event["empty"], "");
+        assert!(event.get("flag").is_none());
+
+        assert!(parse_line("   ").is_none());
+        assert!(parse_line("no pairs here").is_none());
+
+        assert!(looks_like_kv("src=1.2.3.4 dst=5.6.7.8"));
+        assert!(!looks_like_kv(r#"{"a": "b=c d=e"}"#));
+
+        let mut chunks = Vec::new();
+        let total = stream_events(&b"a=1 b=2\n\nc=3\nnothing\n"[..], 1, &mut |chunk| {
+            chunks.push(chunk)
+        })
+        .unwrap();
+        assert_eq!(total, 2);
+        assert_eq!(chunks.len(), 2);
+    }
+}
@@ -4,18 +4,87 @@
 
 #![allow(non_snake_case)]
 
+mod absence;
+mod aggregation;
+mod alert_export;
+mod alert_router;
+mod alert_template;
+mod alert_triage;
+mod annotation_manager;
+mod anonymize;
+mod app_backup;
+mod app_data;
+mod attack;
+mod audit_log;
+mod azure;
+mod case_manager;
+mod change_feed;
 mod config;
+mod correlation;
+mod dataset_export;
 mod db_engine;
+mod db_pool;
+mod default_rules;
+mod entity;
+mod event_page;
+mod event_time;
+mod evidence_bundle;
+mod evtx_binary;
+mod evtx_xml;
+mod field_stats;
+mod first_seen;
+mod folder_watch;
+mod geoip;
+mod hash_sets;
+mod indicators;
+mod ingest_cache;
+mod intel;
+mod ioc_extract;
+mod job_manager;
+mod kv_log;
+mod log_hashes;
+mod log_integrity;
 mod log_manager;
+mod log_schema;
+mod log_search;
+mod log_views;
+mod lookup_lists;
+mod meta_rules;
 mod models;
+mod okta;
+mod parser_plugins;
+mod query_control;
+mod query_manager;
+mod rarity;
+mod report;
+mod rest_api;
 mod rule_manager;
+mod rule_profiler;
+mod safe_regex;
+mod scan_history;
+mod scan_progress;
+mod scanner;
+mod script_rules;
+mod sql_compiler;
+mod suppression_manager;
+mod suricata;
+mod term_sets;
 mod test_rule;
-
+mod text_parser;
+mod timeline;
+mod triage_import;
+mod windows_events;
+mod workspace;
+mod workspace_lock;
+
+use change_feed::ChangeKind;
 use models::{
-    AlertEvent, BulkScanResponse, FailedFileScan, FileScanResult, ImportSummary, LogFileInfo,
+    AlertEvent, AuditAction, AuditActor, BulkScanResponse, Case, CaseSummary, EventAnnotation,
+    FailedFileScan, FileScanResult, HashSetInfo, ImportSummary, LogFileInfo, LogSetScanResponse,
     QueryResult, RuleYaml, ScanResponse, SiemError,
 };
 use std::time::Instant;
+use tauri::{Emitter, Manager};
 
 // ============================================================================
 // Rule Management Commands
@@ -27,6 +96,25 @@ async fn list_rules(app_handle: tauri::AppHandle) -> Result<Vec<RuleYaml>, SiemE
     rule_manager::list_rules(&app_handle)
 }
 
+/// Search rules by words in their title, description, tags or conditions,
+/// filtered by tags, severity and status.
+#[tauri::command]
+async fn search_rules(
+    app_handle: tauri::AppHandle,
+    query: Option<String>,
+    tags: Option<Vec<String>>,
+    severity: Option<String>,
+    status: Option<String>,
+) -> Result<Vec<RuleYaml>, SiemError> {
+    let search = rule_manager::RuleSearch {
+        query,
+        tags: tags.unwrap_or_default(),
+        severity,
+        status,
+    };
+    rule_manager::search_rules(&app_handle, &search)
+}
+
 /// Get a single rule by ID.
 #[tauri::command]
 async fn get_rule(app_handle: tauri::AppHandle, ruleId: String) -> Result<RuleYaml, SiemError> {
@@ -36,13 +124,112 @@ async fn get_rule(app_handle: tauri::AppHandle, ruleId: String) -> Result<RuleYa
 /// Save a rule (create or update).
 #[tauri::command]
 async fn save_rule(app_handle: tauri::AppHandle, rule: RuleYaml) -> Result<RuleYaml, SiemError> {
-    rule_manager::save_rule(&app_handle, rule)
+    workspace_lock::ensure_writable(&app_handle)?;
+    let rule = rule_manager::save_rule(&app_handle, rule)?;
+    audit_log::record(
+        &app_handle,
+        AuditAction::RuleSaved,
+        &rule.id,
+        serde_json::json!({ "title": rule.title }),
+    );
+    change_feed::notify(&app_handle, ChangeKind::Rules, vec![rule.id.clone()]);
+    Ok(rule)
+}
+
+/// Copy a rule under a new ID, keeping its detection logic and tags.
+#[tauri::command]
+async fn duplicate_rule(
+    app_handle: tauri::AppHandle,
+    ruleId: String,
+) -> Result<RuleYaml, SiemError> {
+    workspace_lock::ensure_writable(&app_handle)?;
+    let rule = rule_manager::duplicate_rule(&app_handle, &ruleId)?;
+    audit_log::record(
+        &app_handle,
+        AuditAction::RuleSaved,
+        &rule.id,
+        serde_json::json!({ "duplicated_from": ruleId }),
+    );
+    change_feed::notify(&app_handle, ChangeKind::Rules, vec![rule.id.clone()]);
+    Ok(rule)
+}
+
+/// Append an exception filter to a rule. Events matching the filter no longer
+/// trigger the rule; its base condition is left untouched.
+#[tauri::command]
+async fn add_rule_filter(
+    app_handle: tauri::AppHandle,
+    ruleId: String,
+    filter: String,
+) -> Result<RuleYaml, SiemError> {
+    workspace_lock::ensure_writable(&app_handle)?;
+    let rule = rule_manager::add_rule_filter(&app_handle, &ruleId, &filter)?;
+    audit_log::record(
+        &app_handle,
+        AuditAction::RuleSaved,
+        &rule.id,
+        serde_json::json!({ "filter_added": filter }),
+    );
+    change_feed::notify(&app_handle, ChangeKind::Rules, vec![rule.id.clone()]);
+    Ok(rule)
+}
+
+/// Tune the rule of an alert so events with the same values of `fields` as
+/// the alert's evidence (e.g. a service account) no longer trigger it.
+#[tauri::command]
+async fn add_alert_filter(
+    app_handle: tauri::AppHandle,
+    alert: AlertEvent,
+    fields: Vec<String>,
+) -> Result<RuleYaml, SiemError> {
+    workspace_lock::ensure_writable(&app_handle)?;
+    let filter = rule_manager::alert_filter(&alert, &fields)?;
+    let rule = rule_manager::add_rule_filter(&app_handle, &alert.rule_id, &filter)?;
+    audit_log::record(
+        &app_handle,
+        AuditAction::RuleSaved,
+        &rule.id,
+        serde_json::json!({ "filter_added": filter }),
+    );
+    change_feed::notify(&app_handle, ChangeKind::Rules, vec![rule.id.clone()]);
+    Ok(rule)
+}
+
+/// Set the status (active, disabled, experimental, deprecated) of several
+/// rules at once. Returns the IDs of the rules that changed.
+#[tauri::command]
+async fn set_rules_status(
+    app_handle: tauri::AppHandle,
+    ruleIds: Vec<String>,
+    status: String,
+) -> Result<Vec<String>, SiemError> {
+    workspace_lock::ensure_writable(&app_handle)?;
+    let changed = rule_manager::set_rules_status(&app_handle, &ruleIds, &status)?;
+    if !changed.is_empty() {
+        audit_log::record(
+            &app_handle,
+            AuditAction::RuleSaved,
+            changed.join(", "),
+            serde_json::json!({ "status": status }),
+        );
+    }
+    change_feed::notify(&app_handle, ChangeKind::Rules, changed.clone());
+    Ok(changed)
 }
 
 /// Delete a rule by ID.
 #[tauri::command]
 async fn delete_rule(app_handle: tauri::AppHandle, ruleId: String) -> Result<(), SiemError> {
-    rule_manager::delete_rule(&app_handle, &ruleId)
+    workspace_lock::ensure_writable(&app_handle)?;
+    rule_manager::delete_rule(&app_handle, &ruleId)?;
+    audit_log::record(
+        &app_handle,
+        AuditAction::RuleDeleted,
+        &ruleId,
+        serde_json::json!({}),
+    );
+    change_feed::notify(&app_handle, ChangeKind::Rules, vec![ruleId]);
+    Ok(())
 }
 
 /// Export a single rule to a YAML file.
@@ -64,6 +251,57 @@ async fn export_all_rules(
     rule_manager::export_all_rules(&app_handle, &destPath)
 }
 
+/// Export rules as a rule pack: a ZIP archive of their YAML files with a
+/// manifest (name, version, author).
+#[tauri::command]
+async fn export_rule_pack(
+    app_handle: tauri::AppHandle,
+    ruleIds: Vec<String>,
+    destPath: String,
+    manifest: rule_manager::RulePackManifest,
+) -> Result<rule_manager::RulePackManifest, SiemError> {
+    rule_manager::export_rule_pack(&app_handle, &ruleIds, manifest, &destPath)
+}
+
+/// Import a rule pack, overwriting, skipping or renaming (new ID) rules whose
+/// ID already exists.
+#[tauri::command]
+async fn import_rule_pack(
+    app_handle: tauri::AppHandle,
+    packPath: String,
+    onCollision: rule_manager::CollisionPolicy,
+) -> Result<rule_manager::RulePackImportSummary, SiemError> {
+    workspace_lock::ensure_writable(&app_handle)?;
+    let summary = rule_manager::import_rule_pack(&app_handle, &packPath, onCollision)?;
+    audit_log::record(
+        &app_handle,
+        AuditAction::RuleSaved,
+        summary.imported.join(", "),
+        serde_json::json!({ "imported_from": packPath }),
+    );
+    change_feed::notify(&app_handle, ChangeKind::Rules, summary.imported.clone());
+    Ok(summary)
+}
+
+/// Install the built-in starter rules (CloudTrail and Windows), skipping
+/// rules that already exist unless `overwrite` is set.
+#[tauri::command]
+async fn install_default_rules(
+    app_handle: tauri::AppHandle,
+    overwrite: bool,
+) -> Result<rule_manager::ImportSummary, SiemError> {
+    workspace_lock::ensure_writable(&app_handle)?;
+    let summary = default_rules::install_default_rules(&app_handle, overwrite)?;
+    audit_log::record(
+        &app_handle,
+        AuditAction::RuleSaved,
+        "default rules",
+        serde_json::json!({ "imported": summary.success_count, "overwrite": overwrite }),
+    );
+    change_feed::notify(&app_handle, ChangeKind::Rules, vec![]);
+    Ok(summary)
+}
+
 /// Import a single rule from a YAML file.
 #[tauri::command]
 async fn import_rule(
@@ -71,7 +309,16 @@ async fn import_rule(
     sourcePath: String,
     overwrite: bool,
 ) -> Result<RuleYaml, SiemError> {
-    rule_manager::import_rule(&app_handle, &sourcePath, overwrite)
+    workspace_lock::ensure_writable(&app_handle)?;
+    let rule = rule_manager::import_rule(&app_handle, &sourcePath, overwrite)?;
+    audit_log::record(
+        &app_handle,
+        AuditAction::RuleSaved,
+        &rule.id,
+        serde_json::json!({ "imported_from": sourcePath }),
+    );
+    change_feed::notify(&app_handle, ChangeKind::Rules, vec![rule.id.clone()]);
+    Ok(rule)
 }
 
 /// Import multiple rules from a ZIP archive.
@@ -81,7 +328,16 @@ async fn import_rules_zip(
     zipPath: String,
     overwrite: bool,
 ) -> Result<rule_manager::ImportSummary, SiemError> {
-    rule_manager::import_rules_zip(&app_handle, &zipPath, overwrite)
+    workspace_lock::ensure_writable(&app_handle)?;
+    let summary = rule_manager::import_rules_zip(&app_handle, &zipPath, overwrite)?;
+    audit_log::record(
+        &app_handle,
+        AuditAction::RuleSaved,
+        &zipPath,
+        serde_json::json!({ "imported": summary.success_count, "overwrite": overwrite }),
+    );
+    change_feed::notify(&app_handle, ChangeKind::Rules, vec![]);
+    Ok(summary)
 }
 
 /// Import multiple rules from a list of YAML file paths.
@@ -91,77 +347,107 @@ async fn import_multiple_rules(
     filePaths: Vec<String>,
     overwrite: bool,
 ) -> Result<rule_manager::ImportSummary, SiemError> {
-    rule_manager::import_multiple_rules(&app_handle, filePaths, overwrite)
+    workspace_lock::ensure_writable(&app_handle)?;
+    let summary = rule_manager::import_multiple_rules(&app_handle, filePaths.clone(), overwrite)?;
+    audit_log::record(
+        &app_handle,
+        AuditAction::RuleSaved,
+        filePaths.join(", "),
+        serde_json::json!({ "imported": summary.success_count, "overwrite": overwrite }),
+    );
+    change_feed::notify(&app_handle, ChangeKind::Rules, vec![]);
+    Ok(summary)
 }
 
 // ============================================================================
 // Scanning Commands
 // ============================================================================
 
-/// Scan a log file with all active rules.
+/// Scan a log file with all active rules, or a subset of them.
 ///
 /// This is the core SIEM functionality:
-/// 1. Create in-memory DuckDB connection
-/// 2. Load all active rules
+/// 1. Validate the log file with a pooled DuckDB connection
+/// 2. Load all active rules, narrowed to `ruleIds`, `tags` and `severity`
+///    when given
 /// 3. Execute each rule's condition against the log file
 /// 4. Collect and return matching alerts
+///
+/// Progress is reported on `scan://progress` while rules are evaluated.
+/// With `timeRange`, only the events within it are evaluated.
 #[tauri::command]
 async fn scan_logs(
     app_handle: tauri::AppHandle,
+    pool: tauri::State<'_, db_pool::ConnectionPool>,
     logPath: String,
     logType: models::LogType,
+    ruleIds: Option<Vec<String>>,
+    tags: Option<Vec<String>>,
+    severity: Option<String>,
+    timeRange: Option<models::TimeRange>,
 ) -> Result<ScanResponse, SiemError> {
     let start = Instant::now();
 
-    // Create in-memory DuckDB connection
-    let conn = db_engine::create_connection()?;
-
     // Validate log file first
-    db_engine::validate_log_file(&conn, &logPath)?;
-
-    // Load all active rules
-    let active_rules = rule_manager::list_active_rules(&app_handle)?;
+    db_engine::validate_log_file(&pool.get()?, &logPath)?;
+    let time_filter = timeRange
+        .map(|range| event_time::TimeFilter::new(&range, &logType))
+        .transpose()?
+        .flatten();
+
+    // Load configuration and the selected active rules
+    let config = config::load_config(&app_handle)?;
+    let mut active_rules = rule_manager::select_rules(
+        rule_manager::list_active_rules(&app_handle)?,
+        &ruleIds.unwrap_or_default(),
+        &tags.unwrap_or_default(),
+        severity.as_deref(),
+    )?;
+    scanner::apply_default_grouping(&mut active_rules, config.alert_grouping);
+    scanner::apply_default_alert_cap(&mut active_rules, config.max_alerts_per_scan);
     let rules_count = active_rules.len();
 
-    let mut alerts: Vec<AlertEvent> = Vec::new();
-
-    // Execute each rule
-    for rule in active_rules {
-        // Get all matching events for this rule
-        let matching_events = db_engine::execute_scan_query(
-            &conn,
-            &logPath,
-            &rule.detection.condition,
-            1000, // Get up to 1000 matches
-            logType.clone(),
-        );
-
-        match matching_events {
-            Ok(events) => {
-                // Only create alert if there are matching events
-                if !events.is_empty() {
-                    let match_count = events.len();
-                    let alert = AlertEvent {
-                        rule_id: rule.id.clone(),
-                        rule_title: rule.title.clone(),
-                        severity: rule.detection.severity.clone(),
-                        timestamp: chrono::Utc::now().to_rfc3339(),
-                        match_count,       // Total number of matched events
-                        evidence: events,  // All matched events as evidence
-                        source_file: None, // Single file scan doesn't need source tracking
-                    };
-                    alerts.push(alert);
-                }
-            }
-            Err(e) => {
-                // Log error but continue with other rules
-                eprintln!("Warning: Rule '{}' failed: {}", rule.title, e);
-            }
-        }
-    }
+    let cached = cached_source(&app_handle, &logPath, &logType);
+    let mut progress = scan_progress::ProgressReporter::new(&app_handle, &logPath, rules_count);
+
+    // Stream the file once through the rules whose logsource applies to it,
+    // evaluated in parallel (meta-rules run afterwards over the resulting alerts)
+    let rules: Vec<&models::RuleYaml> = active_rules
+        .iter()
+        .filter(|r| !meta_rules::is_meta_rule(r))
+        .filter(|r| scanner::rule_applies_to(r, &logType))
+        .collect();
+    let source = scanner::ScanSource {
+        log_path: &logPath,
+        log_type: &logType,
+        cached: cached.as_ref(),
+        time_filter: time_filter.as_ref(),
+    };
+    let scan = scanner::scan_file(source, &rules, None, config.scan_workers, |events| {
+        progress.chunk_completed(events)
+    })?;
+    let mut alerts = scan.alerts;
+
+    // Second pass: correlate the alerts across rules
+    let meta_alerts = meta_rules::evaluate_meta_rules(&active_rules, &alerts);
+    alerts.extend(meta_alerts);
+    progress.finish(alerts.len());
 
     // Sort alerts by severity (critical first)
     alerts.sort_by(|a, b| severity_order(&b.severity).cmp(&severity_order(&a.severity)));
+    let (suppressed_alerts, routing) = finish_alerts(
+        &app_handle,
+        &config,
+        &load_suppressions(&app_handle),
+        &active_rules,
+        &mut alerts,
+    );
+    record_scan_history(
+        &app_handle,
+        vec![logPath.clone()],
+        &active_rules,
+        start.elapsed().as_millis() as u64,
+        &alerts,
+    );
 
     let scan_time = start.elapsed().as_millis() as u64;
 
@@ -169,6 +455,9 @@ async fn scan_logs(
         alerts,
         rules_evaluated: rules_count,
         scan_time_ms: scan_time,
+        suppressed_alerts,
+        rule_results: scan.rule_results,
+        routing,
     })
 }
 
@@ -177,10 +466,14 @@ async fn scan_logs(
 /// This is the bulk scanning functionality:
 /// 1. List all log files in the monitored logs folder
 /// 2. For each log file, run the same scan logic as scan_logs
-/// 3. Aggregate results and track failures
-/// 4. Return comprehensive bulk scan response
+/// 3. Run the meta-rules once over the alerts of all files
+/// 4. Aggregate results and track failures
+/// 5. Return comprehensive bulk scan response
 #[tauri::command]
-async fn scan_all_logs(app_handle: tauri::AppHandle) -> Result<BulkScanResponse, SiemError> {
+async fn scan_all_logs(
+    app_handle: tauri::AppHandle,
+    pool: tauri::State<'_, db_pool::ConnectionPool>,
+) -> Result<BulkScanResponse, SiemError> {
     let start = Instant::now();
 
     // Get all log files from the library
@@ -193,17 +486,25 @@ async fn scan_all_logs(app_handle: tauri::AppHandle) -> Result<BulkScanResponse,
             total_scan_time_ms: start.elapsed().as_millis() as u64,
             rules_evaluated: 0,
             file_results: vec![],
+            meta_alerts: vec![],
             failed_files: vec![],
+            suppressed_alerts: 0,
+            routing: models::RoutingSummary::default(),
         });
     }
 
-    // Load all active rules once (shared across all file scans)
-    let active_rules = rule_manager::list_active_rules(&app_handle)?;
+    // Load configuration and all active rules once (shared across all file scans)
+    let config = config::load_config(&app_handle)?;
+    let mut active_rules = rule_manager::list_active_rules(&app_handle)?;
+    scanner::apply_default_grouping(&mut active_rules, config.alert_grouping);
+    scanner::apply_default_alert_cap(&mut active_rules, config.max_alerts_per_scan);
     let rules_count = active_rules.len();
+    let suppressions = load_suppressions(&app_handle);
 
     let mut file_results: Vec<FileScanResult> = Vec::new();
     let mut failed_files: Vec<FailedFileScan> = Vec::new();
     let mut total_alerts = 0;
+    let mut suppressed_alerts = 0;
 
     // Scan each log file
     for log_file in log_files {
@@ -234,21 +535,24 @@ async fn scan_all_logs(app_handle: tauri::AppHandle) -> Result<BulkScanResponse,
         };
 
         // Try to scan this file
+        let cached = cached_source(&app_handle, &log_file.path, &log_type);
         match scan_single_file_internal(
+            &pool.get()?,
             &log_file.path,
             log_type,
             &active_rules,
             Some(&log_file.filename),
+            cached.as_ref(),
+            config.scan_workers,
         ) {
-            Ok(alerts) => {
+            Ok((alerts, rule_results)) => {
                 let file_scan_time = file_start.elapsed().as_millis() as u64;
-                total_alerts += alerts.len();
-
                 file_results.push(FileScanResult {
                     file_name: log_file.filename.clone(),
                     file_path: log_file.path.clone(),
                     alerts,
                     scan_time_ms: file_scan_time,
+                    rule_results,
                 });
             }
             Err(e) => {
@@ -263,6 +567,42 @@ async fn scan_all_logs(app_handle: tauri::AppHandle) -> Result<BulkScanResponse,
         }
     }
 
+    // Second pass: correlate the alerts of every file at once, so meta-rules
+    // see activity spread across files
+    let file_alerts: Vec<AlertEvent> = file_results
+        .iter()
+        .flat_map(|r| r.alerts.iter().cloned())
+        .collect();
+    let mut meta_alerts = meta_rules::evaluate_meta_rules(&active_rules, &file_alerts);
+    meta_alerts.sort_by(|a, b| severity_order(&b.severity).cmp(&severity_order(&a.severity)));
+
+    let mut routing = models::RoutingSummary::default();
+    let alert_sets = file_results
+        .iter_mut()
+        .map(|r| &mut r.alerts)
+        .chain(std::iter::once(&mut meta_alerts));
+    for alerts in alert_sets {
+        let (set_suppressed, set_routing) =
+            finish_alerts(&app_handle, &config, &suppressions, &active_rules, alerts);
+        suppressed_alerts += set_suppressed;
+        total_alerts += alerts.len();
+        routing.delivered += set_routing.delivered;
+        routing.errors.extend(set_routing.errors);
+    }
+    let scanned_files: Vec<String> = file_results.iter().map(|r| r.file_path.clone()).collect();
+    let all_alerts: Vec<AlertEvent> = file_results
+        .iter()
+        .flat_map(|r| r.alerts.iter().cloned())
+        .chain(meta_alerts.iter().cloned())
+        .collect();
+    record_scan_history(
+        &app_handle,
+        scanned_files,
+        &active_rules,
+        start.elapsed().as_millis() as u64,
+        &all_alerts,
+    );
+
     let total_scan_time = start.elapsed().as_millis() as u64;
 
     Ok(BulkScanResponse {
@@ -271,65 +611,493 @@ async fn scan_all_logs(app_handle: tauri::AppHandle) -> Result<BulkScanResponse,
         total_scan_time_ms: total_scan_time,
         rules_evaluated: rules_count,
         file_results,
+        meta_alerts,
         failed_files,
+        suppressed_alerts,
+        routing,
     })
 }
 
+/// Scan a selected set of log files of mixed types in one pass.
+///
+/// Each file is loaded once, log types come from the library metadata or are
+/// auto-detected, and only rules whose logsource matches a file's type are
+/// evaluated against it. Results are merged into one deduplicated alert set.
+#[tauri::command]
+async fn scan_log_files(
+    app_handle: tauri::AppHandle,
+    filePaths: Vec<String>,
+) -> Result<LogSetScanResponse, SiemError> {
+    let config = config::load_config(&app_handle)?;
+    let mut active_rules = rule_manager::list_active_rules(&app_handle)?;
+    scanner::apply_default_grouping(&mut active_rules, config.alert_grouping);
+    scanner::apply_default_alert_cap(&mut active_rules, config.max_alerts_per_scan);
+
+    let mut targets = Vec::new();
+    let mut failed_files = Vec::new();
+
+    for file_path in filePaths {
+        let file_name = std::path::Path::new(&file_path)
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or(&file_path)
+            .to_string();
+
+        // Determine log type: use metadata if available, otherwise auto-detect
+        let log_type = match log_manager::get_log_type(&app_handle, &file_path) {
+            Some(lt) => lt,
+            None => match db_engine::detect_log_type(&file_path) {
+                Ok(detected_type) => detected_type,
+                Err(e) => {
+                    failed_files.push(FailedFileScan {
+                        file_name,
+                        file_path,
+                        error: format!("Failed to detect log type: {}", e),
+                    });
+                    continue;
+                }
+            },
+        };
+
+        let cached = cached_source(&app_handle, &file_path, &log_type);
+        targets.push(scanner::ScanTarget {
+            file_name,
+            file_path,
+            log_type,
+            cached,
+        });
+    }
+
+    let mut response = scanner::scan_log_set(targets, &active_rules, config.scan_workers);
+    failed_files.append(&mut response.failed_files);
+    response.failed_files = failed_files;
+    (response.suppressed_alerts, response.routing) = finish_alerts(
+        &app_handle,
+        &config,
+        &load_suppressions(&app_handle),
+        &active_rules,
+        &mut response.alerts,
+    );
+    let scanned_files: Vec<String> = response
+        .files_scanned
+        .iter()
+        .map(|f| f.file_path.clone())
+        .collect();
+    record_scan_history(
+        &app_handle,
+        scanned_files,
+        &active_rules,
+        response.scan_time_ms,
+        &response.alerts,
+    );
+
+    Ok(response)
+}
+
+// ============================================================================
+// Scan Job Commands
+// ============================================================================
+
+/// Queue a background scan of log files (default: every file in the library)
+/// with the active rules. Returns the job at once; poll `get_job_status`.
+#[tauri::command]
+async fn enqueue_scan(
+    app_handle: tauri::AppHandle,
+    logPaths: Option<Vec<String>>,
+) -> Result<job_manager::ScanJob, SiemError> {
+    let log_paths = match logPaths {
+        Some(paths) => paths,
+        None => log_manager::list_log_files(&app_handle)?
+            .into_iter()
+            .map(|file| file.path)
+            .collect(),
+    };
+    job_manager::enqueue(&app_handle, log_paths)
+}
+
+/// Get a scan job, with the alerts of the files scanned so far.
+#[tauri::command]
+async fn get_job_status(
+    jobs: tauri::State<'_, job_manager::JobManager>,
+    jobId: String,
+) -> Result<job_manager::ScanJob, SiemError> {
+    jobs.get(&jobId)
+}
+
+/// List scan jobs, newest first, without their alerts.
+#[tauri::command]
+async fn list_jobs(
+    jobs: tauri::State<'_, job_manager::JobManager>,
+) -> Result<Vec<job_manager::ScanJob>, SiemError> {
+    jobs.list()
+}
+
+/// Cancel a queued or running scan job.
+#[tauri::command]
+async fn cancel_job(
+    app_handle: tauri::AppHandle,
+    jobId: String,
+) -> Result<job_manager::ScanJob, SiemError> {
+    job_manager::cancel(&app_handle, &jobId)
+}
+
+// ============================================================================
+// Folder Watch Commands
+// ============================================================================
+
+/// Watch a directory (default: `watch_directory` from config, else the log
+/// library) and scan new log files dropped into it with the active rules.
+/// Results are emitted on `watch://scanned`.
+#[tauri::command]
+async fn start_folder_watch(
+    app_handle: tauri::AppHandle,
+    directory: Option<String>,
+) -> Result<folder_watch::WatchStatus, SiemError> {
+    workspace_lock::ensure_writable(&app_handle)?;
+    let directory = watch_directory(&app_handle, directory)?;
+    folder_watch::start(app_handle, directory, scan_background_file)
+}
+
+/// Stop the folder watch.
+#[tauri::command]
+async fn stop_folder_watch() -> Result<folder_watch::WatchStatus, SiemError> {
+    folder_watch::stop();
+    Ok(folder_watch::status())
+}
+
+/// State of the folder watch.
+#[tauri::command]
+async fn get_folder_watch_status() -> Result<folder_watch::WatchStatus, SiemError> {
+    Ok(folder_watch::status())
+}
+
+/// Directory to watch: the one given, the configured one, or the log library.
+fn watch_directory(
+    app_handle: &tauri::AppHandle,
+    directory: Option<String>,
+) -> Result<std::path::PathBuf, SiemError> {
+    let configured = config::load_config(app_handle)?.watch_directory;
+    match directory.or(configured).filter(|d| !d.trim().is_empty()) {
+        Some(directory) => Ok(std::path::PathBuf::from(directory)),
+        None => log_manager::get_logs_dir(app_handle),
+    }
+}
+
+/// Scan a file picked up by the folder watch or a scan job like `scan_logs`
+/// does: suppress, enrich, route and record the alerts. The routing summary
+/// is emitted on `alert://routed`. Log types come from the library metadata
+/// or are auto-detected.
+fn scan_background_file(
+    app_handle: &tauri::AppHandle,
+    log_path: &str,
+) -> Result<Vec<AlertEvent>, SiemError> {
+    let start = Instant::now();
+    let config = config::load_config(app_handle)?;
+    let mut active_rules = rule_manager::list_active_rules(app_handle)?;
+    scanner::apply_default_grouping(&mut active_rules, config.alert_grouping);
+    scanner::apply_default_alert_cap(&mut active_rules, config.max_alerts_per_scan);
+
+    let file_name = std::path::Path::new(log_path)
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| log_path.to_string());
+    let log_type = match log_manager::get_log_type(app_handle, log_path) {
+        Some(log_type) => log_type,
+        None => db_engine::detect_log_type(log_path)?,
+    };
+    let cached = cached_source(app_handle, log_path, &log_type);
+    let pool = app_handle.state::<db_pool::ConnectionPool>();
+    let (mut alerts, _) = scan_single_file_internal(
+        &pool.get()?,
+        log_path,
+        log_type,
+        &active_rules,
+        Some(&file_name),
+        cached.as_ref(),
+        config.scan_workers,
+    )?;
+    let meta_alerts = meta_rules::evaluate_meta_rules(&active_rules, &alerts);
+    alerts.extend(meta_alerts);
+    alerts.sort_by(|a, b| severity_order(&b.severity).cmp(&severity_order(&a.severity)));
+    let (_, routing) = finish_alerts(
+        app_handle,
+        &config,
+        &load_suppressions(app_handle),
+        &active_rules,
+        &mut alerts,
+    );
+    if let Err(e) = app_handle.emit(alert_router::ROUTING_EVENT, &routing) {
+        eprintln!("Warning: Cannot emit routing summary: {}", e);
+    }
+    record_scan_history(
+        app_handle,
+        vec![log_path.to_string()],
+        &active_rules,
+        start.elapsed().as_millis() as u64,
+        &alerts,
+    );
+    Ok(alerts)
+}
+
 /// Internal helper function to scan a single file.
 /// Used by both scan_logs and scan_all_logs to avoid code duplication.
+/// Returns the alerts and the outcome of each rule. Meta-rules are left to
+/// the caller, which may correlate the alerts of several files.
 fn scan_single_file_internal(
+    conn: &duckdb::Connection,
     log_path: &str,
     log_type: models::LogType,
     active_rules: &[models::RuleYaml],
     source_filename: Option<&str>,
-) -> Result<Vec<AlertEvent>, SiemError> {
-    // Create in-memory DuckDB connection
-    let conn = db_engine::create_connection()?;
-
+    cached: Option<&ingest_cache::CachedSource>,
+    workers: usize,
+) -> Result<(Vec<AlertEvent>, Vec<models::RuleRunStats>), SiemError> {
     // Validate log file first
-    db_engine::validate_log_file(&conn, log_path)?;
-
-    let mut alerts: Vec<AlertEvent> = Vec::new();
-
-    // Execute each rule
-    for rule in active_rules {
-        // Get all matching events for this rule
-        let matching_events = db_engine::execute_scan_query(
-            &conn,
-            log_path,
-            &rule.detection.condition,
-            1000, // Get up to 1000 matches
-            log_type.clone(),
-        );
+    db_engine::validate_log_file(conn, log_path)?;
+
+    // Stream the file once through the rules whose logsource applies to it,
+    // evaluated in parallel (meta-rules run afterwards over the resulting alerts)
+    let rules: Vec<&models::RuleYaml> = active_rules
+        .iter()
+        .filter(|r| !meta_rules::is_meta_rule(r))
+        .filter(|r| scanner::rule_applies_to(r, &log_type))
+        .collect();
+    let source = scanner::ScanSource {
+        log_path,
+        log_type: &log_type,
+        cached,
+        time_filter: None,
+    };
+    let scan = scanner::scan_file(source, &rules, source_filename, workers, |_| {})?;
+    let mut alerts = scan.alerts;
 
-        match matching_events {
-            Ok(events) => {
-                // Only create alert if there are matching events
-                if !events.is_empty() {
-                    let match_count = events.len();
-                    let alert = AlertEvent {
-                        rule_id: rule.id.clone(),
-                        rule_title: rule.title.clone(),
-                        severity: rule.detection.severity.clone(),
-                        timestamp: chrono::Utc::now().to_rfc3339(),
-                        match_count,      // Total number of matched events
-                        evidence: events, // All matched events as evidence
-                        source_file: source_filename.map(|s| s.to_string()),
-                    };
-                    alerts.push(alert);
-                }
-            }
-            Err(e) => {
-                // Log error but continue with other rules
-                eprintln!("Warning: Rule '{}' failed: {}", rule.title, e);
+    // Sort alerts by severity (critical first)
+    alerts.sort_by(|a, b| severity_order(&b.severity).cmp(&severity_order(&a.severity)));
+
+    Ok((alerts, scan.rule_results))
+}
+
+/// Get the up-to-date ingested copy of a log file, if any.
+/// Cache failures are logged and the file is read from disk instead.
+fn cached_source(
+    app_handle: &tauri::AppHandle,
+    log_path: &str,
+    log_type: &models::LogType,
+) -> Option<ingest_cache::CachedSource> {
+    ingest_cache::open(app_handle)
+        .and_then(|conn| ingest_cache::lookup(&conn, log_path, log_type))
+        .unwrap_or_else(|e| {
+            eprintln!("Warning: Cannot read ingestion cache: {}", e);
+            None
+        })
+}
+
+/// Load the suppression list consulted by scans.
+/// Failures are logged and suppress nothing.
+fn load_suppressions(app_handle: &tauri::AppHandle) -> Vec<models::SuppressionEntry> {
+    suppression_manager::list_suppressions(app_handle, false).unwrap_or_else(|e| {
+        eprintln!("Warning: Cannot load suppressions: {}", e);
+        Vec::new()
+    })
+}
+
+/// What every scan does with the alerts it found, in order: drop the
+/// suppressed ones, annotate the evidence, keep the rules' evidence fields and
+/// deliver the alerts to the sinks configured for their severity. Returns how
+/// many alerts were suppressed and the routing summary.
+fn finish_alerts(
+    app_handle: &tauri::AppHandle,
+    config: &config::AppConfig,
+    suppressions: &[models::SuppressionEntry],
+    active_rules: &[RuleYaml],
+    alerts: &mut Vec<AlertEvent>,
+) -> (usize, models::RoutingSummary) {
+    let suppressed =
+        suppression_manager::apply_suppressions(suppressions, alerts, chrono::Utc::now());
+    enrich_alerts(app_handle, config, alerts);
+    scanner::project_evidence(active_rules, alerts);
+    let routing = alert_router::route_alerts(app_handle, config, alerts);
+    (suppressed, routing)
+}
+
+/// Annotate alert evidence with local enrichment data (hash set verdicts,
+/// GeoIP locations, known IOCs). Enrichment failures are logged and never
+/// fail the scan.
+fn enrich_alerts(
+    app_handle: &tauri::AppHandle,
+    config: &config::AppConfig,
+    alerts: &mut [AlertEvent],
+) {
+    match hash_sets::HashLookup::load(app_handle) {
+        Ok(lookup) if !lookup.is_empty() => {
+            hash_sets::annotate_alerts(&lookup, &config.hash_fields, alerts)
+        }
+        Ok(_) => {}
+        Err(e) => eprintln!("Warning: Cannot load hash sets: {}", e),
+    }
+    if let Some(lookup) = geoip::active() {
+        geoip::annotate_alerts(&lookup, &config.ip_fields, alerts);
+    }
+    match intel::load_all(app_handle) {
+        Ok(lists) => intel::annotate_alerts(&lists, alerts),
+        Err(e) => eprintln!("Warning: Cannot load IOC lists: {}", e),
+    }
+}
+
+/// Record a completed scan in the history used for trend analytics and scan
+/// comparison, and tell listeners the alerts of its files changed. Failures
+/// are logged and never fail the scan.
+fn record_scan_history(
+    app_handle: &tauri::AppHandle,
+    files: Vec<String>,
+    rules: &[RuleYaml],
+    duration_ms: u64,
+    alerts: &[AlertEvent],
+) {
+    audit_log::record(
+        app_handle,
+        AuditAction::ScanRun,
+        files.join(", "),
+        serde_json::json!({
+            "rule_ids": rules.iter().map(|rule| &rule.id).collect::<Vec<_>>(),
+            "alerts": alerts.len(),
+            "duration_ms": duration_ms,
+        }),
+    );
+    match workspace_lock::ensure_writable(app_handle) {
+        Ok(()) => {
+            if let Err(e) =
+                scan_history::record_scan(app_handle, files.clone(), rules, duration_ms, alerts)
+            {
+                eprintln!("Warning: Cannot record scan history: {}", e);
             }
         }
+        Err(e) => eprintln!("Warning: Scan history not recorded: {}", e),
     }
+    change_feed::notify(app_handle, ChangeKind::Alerts, files);
+}
 
-    // Sort alerts by severity (critical first)
-    alerts.sort_by(|a, b| severity_order(&b.severity).cmp(&severity_order(&a.severity)));
+/// MITRE ATT&CK matrix of the techniques covered by active rules and of those
+/// whose rules fired in the last recorded scan.
+#[tauri::command]
+async fn get_attack_coverage(
+    app_handle: tauri::AppHandle,
+) -> Result<models::AttackCoverage, SiemError> {
+    let rules = rule_manager::list_rules(&app_handle)?;
+    let history = scan_history::load_history(&app_handle)?;
+    Ok(attack::compute_coverage(&rules, history.last()))
+}
 
-    Ok(alerts)
+/// Alert counts per rule and severity across the scan history, by day or by
+/// scan, with deltas against the previous period.
+#[tauri::command]
+async fn get_alert_trends(
+    app_handle: tauri::AppHandle,
+    granularity: Option<models::TrendGranularity>,
+    limit: Option<usize>,
+) -> Result<models::AlertTrends, SiemError> {
+    let history = scan_history::load_history(&app_handle)?;
+    Ok(scan_history::compute_trends(
+        &history,
+        granularity.unwrap_or(models::TrendGranularity::Day),
+        limit,
+    ))
+}
+
+/// Recorded scans (files, rules run, alert counts, duration), newest first.
+#[tauri::command]
+async fn list_scan_history(
+    app_handle: tauri::AppHandle,
+    limit: Option<usize>,
+) -> Result<Vec<models::ScanHistoryEntry>, SiemError> {
+    let history = scan_history::load_history(&app_handle)?;
+    Ok(scan_history::list_scans(history, limit))
+}
+
+/// Alerts that appeared (new) or went away (resolved) between two recorded
+/// scans, e.g. before and after a remediation.
+#[tauri::command]
+async fn diff_scans(
+    app_handle: tauri::AppHandle,
+    scanA: String,
+    scanB: String,
+) -> Result<models::ScanDiff, SiemError> {
+    let history = scan_history::load_history(&app_handle)?;
+    scan_history::diff_scans(&history, &scanA, &scanB)
+}
+
+/// Analyst actions (rule changes, scans, queries, triage changes) from the
+/// audit log, newest first, optionally only those of one `action`.
+#[tauri::command]
+async fn get_audit_log(
+    app_handle: tauri::AppHandle,
+    limit: Option<usize>,
+    action: Option<AuditAction>,
+) -> Result<Vec<models::AuditEntry>, SiemError> {
+    audit_log::get_audit_log(&app_handle, limit, action)
+}
+
+/// Set the triage verdict of an alert (true positive, benign or false
+/// positive), or clear it without a status. Verdicts follow the alert across
+/// later scans.
+#[tauri::command]
+async fn set_alert_triage(
+    app_handle: tauri::AppHandle,
+    alert: AlertEvent,
+    status: Option<models::TriageStatus>,
+    note: Option<String>,
+) -> Result<Option<models::AlertTriage>, SiemError> {
+    workspace_lock::ensure_writable(&app_handle)?;
+    let triage = alert_triage::set_triage(&app_handle, &alert, status, note.unwrap_or_default())?;
+    audit_log::record(
+        &app_handle,
+        AuditAction::AlertStatusChanged,
+        &alert.rule_id,
+        serde_json::json!({
+            "alert": scan_history::alert_key(&alert),
+            "status": status,
+            "note": triage.as_ref().map(|triage| &triage.note),
+        }),
+    );
+    Ok(triage)
+}
+
+/// All alert triage verdicts.
+#[tauri::command]
+async fn list_alert_triage(
+    app_handle: tauri::AppHandle,
+) -> Result<Vec<models::AlertTriage>, SiemError> {
+    alert_triage::load_triage(&app_handle)
+}
+
+/// Lifetime statistics of every rule over the scan history: scans run and
+/// fired, alert and match counts, first and last firing and false-positive
+/// rate from triage verdicts, so dead or noisy rules can be retired.
+#[tauri::command]
+async fn get_rule_statistics(
+    app_handle: tauri::AppHandle,
+) -> Result<Vec<models::RuleStatistics>, SiemError> {
+    let history = scan_history::load_history(&app_handle)?;
+    let rules = rule_manager::list_rules(&app_handle)?;
+    let triage = alert_triage::load_triage(&app_handle)?;
+    Ok(scan_history::rule_statistics(&history, &rules, &triage))
+}
+
+/// Fields tracked by first-seen rules, with how many values are known.
+#[tauri::command]
+async fn list_first_seen_fields() -> Result<Vec<models::FirstSeenFieldInfo>, SiemError> {
+    first_seen::list_fields()
+}
+
+/// Forget the known values of a field (or of every field), so first-seen
+/// rules learn it again.
+#[tauri::command]
+async fn reset_first_seen(
+    app_handle: tauri::AppHandle,
+    field: Option<String>,
+) -> Result<(), SiemError> {
+    workspace_lock::ensure_writable(&app_handle)?;
+    first_seen::reset(field.as_deref())
 }
 
 /// Convert severity string to numeric order for sorting.
@@ -357,113 +1125,1293 @@ fn severity_order(severity: &str) -> u8 {
 /// ORDER BY timestamp DESC
 /// LIMIT 100
 /// ```
+///
+/// Queries run against the ingestion cache, so ingested logs can also be
+/// selected from their tables (see `list_ingested_sources`), and log files
+/// registered as views by name (see `register_log_view`), which lets one
+/// query join several files.
 #[tauri::command]
-async fn run_query(query: String) -> Result<QueryResult, SiemError> {
-    let conn = db_engine::create_connection()?;
+async fn run_query(
+    app_handle: tauri::AppHandle,
+    pool: tauri::State<'_, db_pool::ConnectionPool>,
+    query: String,
+    queryId: Option<String>,
+) -> Result<QueryResult, SiemError> {
     let start = std::time::Instant::now();
-    let results = db_engine::execute_adhoc_query(&conn, &query)?;
+    let results = with_query_connection(&app_handle, &pool, queryId.as_deref(), |conn| {
+        db_engine::execute_adhoc_query(conn, &query)
+    });
+    audit_query(
+        &app_handle,
+        AuditActor::App,
+        &query,
+        results.as_ref().map(|results| results.rows.len()),
+    );
+    let results = results?;
     let execution_time = start.elapsed().as_millis() as u64;
 
     Ok(QueryResult {
-        query: query.clone(),
-        columns: vec![], // DuckDB doesn't easily expose column names
-        rows: results.clone(),
-        row_count: results.len(),
+        query,
+        columns: results.columns,
+        column_types: results.column_types,
+        row_count: results.rows.len(),
+        rows: results.rows,
         execution_time_ms: execution_time,
     })
 }
 
-/// Load all events from a log file for viewing.
+/// Execute an ad-hoc SQL query and return one page of its results, so large
+/// result sets can be browsed without transferring every row at once.
+/// `limit` defaults to 500 rows and is capped at 10,000.
 #[tauri::command]
-async fn load_log_events(
-    logPath: String,
-    logType: models::LogType,
-) -> Result<Vec<serde_json::Value>, SiemError> {
-    let conn = db_engine::create_connection()?;
-    db_engine::load_all_events(&conn, &logPath, logType)
+async fn run_query_paged(
+    app_handle: tauri::AppHandle,
+    pool: tauri::State<'_, db_pool::ConnectionPool>,
+    query: String,
+    offset: Option<usize>,
+    limit: Option<usize>,
+    queryId: Option<String>,
+) -> Result<models::QueryPage, SiemError> {
+    let offset = offset.unwrap_or(0);
+    let limit = limit.unwrap_or(500).clamp(1, db_engine::MAX_PAGE_SIZE);
+
+    let start = std::time::Instant::now();
+    let page = with_query_connection(&app_handle, &pool, queryId.as_deref(), |conn| {
+        db_engine::execute_paged_query(conn, &query, offset, limit)
+    });
+    audit_query(
+        &app_handle,
+        AuditActor::App,
+        &query,
+        page.as_ref().map(|(results, _)| results.rows.len()),
+    );
+    let (results, has_more) = page?;
+
+    Ok(models::QueryPage {
+        query,
+        columns: results.columns,
+        column_types: results.column_types,
+        rows: results.rows,
+        offset,
+        limit,
+        has_more,
+        execution_time_ms: start.elapsed().as_millis() as u64,
+    })
 }
 
-/// Validate that a log file can be read by DuckDB.
+/// Record a query run, with its row count or error, in the audit log.
+fn audit_query(
+    app_handle: &tauri::AppHandle,
+    actor: AuditActor,
+    query: &str,
+    outcome: Result<usize, &SiemError>,
+) {
+    let details = match outcome {
+        Ok(rows) => serde_json::json!({ "rows": rows }),
+        Err(e) => serde_json::json!({ "error": e.to_string() }),
+    };
+    audit_log::record_as(app_handle, actor, AuditAction::QueryRun, query, details);
+}
+
+/// Interrupt a running ad-hoc query started with this `queryId`.
+/// Returns whether such a query was running.
 #[tauri::command]
-async fn validate_log_file(logPath: String) -> Result<bool, SiemError> {
-    let conn = db_engine::create_connection()?;
-    db_engine::validate_log_file(&conn, &logPath)
+async fn cancel_query(queryId: String) -> Result<bool, SiemError> {
+    Ok(query_control::cancel(&queryId))
 }
 
 // ============================================================================
-// Rule Testing Commands
+// Saved Query Commands
 // ============================================================================
 
-/// Test a rule condition against loaded events
+/// List saved queries, optionally only those with a tag.
 #[tauri::command]
-async fn test_rule(
-    condition: String,
-    log_path: String,
-    log_type: models::LogType,
-) -> Result<models::TestRuleResult, SiemError> {
-    test_rule::test_rule(&log_path, &condition, log_type)
+async fn list_saved_queries(
+    app_handle: tauri::AppHandle,
+    tag: Option<String>,
+) -> Result<Vec<models::SavedQuery>, SiemError> {
+    query_manager::list_queries(&app_handle, tag.as_deref())
 }
 
-/// Validate rule condition syntax
+/// Save a named query (create or update).
 #[tauri::command]
-async fn validate_condition(condition: String) -> Result<models::ValidationResult, SiemError> {
-    Ok(test_rule::validate_condition(&condition))
+async fn save_query(
+    app_handle: tauri::AppHandle,
+    query: models::SavedQuery,
+) -> Result<models::SavedQuery, SiemError> {
+    workspace_lock::ensure_writable(&app_handle)?;
+    let saved = query_manager::save_query(&app_handle, query)?;
+    change_feed::notify(&app_handle, ChangeKind::Queries, vec![saved.id.clone()]);
+    Ok(saved)
 }
 
-/// Get field suggestions for autocomplete
+/// Replace the tags of a saved query.
 #[tauri::command]
-async fn get_field_suggestions(
-    log_path: String,
-    log_type: models::LogType,
-    prefix: String,
-) -> Result<Vec<models::FieldSuggestion>, SiemError> {
-    test_rule::get_field_suggestions(&log_path, log_type, &prefix)
+async fn tag_saved_query(
+    app_handle: tauri::AppHandle,
+    queryId: String,
+    tags: Vec<String>,
+) -> Result<models::SavedQuery, SiemError> {
+    workspace_lock::ensure_writable(&app_handle)?;
+    let query = query_manager::tag_query(&app_handle, &queryId, &tags)?;
+    change_feed::notify(&app_handle, ChangeKind::Queries, vec![queryId]);
+    Ok(query)
 }
 
-// ============================================================================
-// Log File Management Commands
-// ============================================================================
+/// Run a saved query by name, filling its `{{placeholders}}` with `params`.
+/// Values are checked against the declared variable types and escaped as SQL
+/// literals, so they can't change the query.
+#[tauri::command]
+async fn run_query_template(
+    app_handle: tauri::AppHandle,
+    pool: tauri::State<'_, db_pool::ConnectionPool>,
+    name: String,
+    params: std::collections::HashMap<String, serde_json::Value>,
+    queryId: Option<String>,
+) -> Result<QueryResult, SiemError> {
+    let template = query_manager::find_query_by_name(&app_handle, &name)?;
+    let query = query_manager::render_saved_query(&template, &params)?;
 
-/// List all JSON log files in the monitored logs folder.
+    let start = std::time::Instant::now();
+    let results = with_query_connection(&app_handle, &pool, queryId.as_deref(), |conn| {
+        db_engine::execute_adhoc_query(conn, &query)
+    });
+    audit_query(
+        &app_handle,
+        AuditActor::App,
+        &query,
+        results.as_ref().map(|results| results.rows.len()),
+    );
+    let results = results?;
+
+    Ok(QueryResult {
+        query,
+        columns: results.columns,
+        column_types: results.column_types,
+        row_count: results.rows.len(),
+        rows: results.rows,
+        execution_time_ms: start.elapsed().as_millis() as u64,
+    })
+}
+
+/// Delete a saved query.
 #[tauri::command]
-async fn list_log_files(app_handle: tauri::AppHandle) -> Result<Vec<LogFileInfo>, SiemError> {
-    log_manager::list_log_files(&app_handle)
+async fn delete_saved_query(
+    app_handle: tauri::AppHandle,
+    queryId: String,
+) -> Result<(), SiemError> {
+    workspace_lock::ensure_writable(&app_handle)?;
+    query_manager::delete_query(&app_handle, &queryId)?;
+    change_feed::notify(&app_handle, ChangeKind::Queries, vec![queryId]);
+    Ok(())
+}
+
+/// Run ad-hoc SQL on the ingestion cache, or on a pooled in-memory
+/// connection when the cache can't be opened. The query can be cancelled by
+/// ID and is interrupted after the configured query timeout.
+fn with_query_connection<T>(
+    app_handle: &tauri::AppHandle,
+    pool: &db_pool::ConnectionPool,
+    query_id: Option<&str>,
+    run: impl FnOnce(&duckdb::Connection) -> Result<T, SiemError>,
+) -> Result<T, SiemError> {
+    let timeout_secs = config::load_config(app_handle)
+        .unwrap_or_default()
+        .query_timeout_secs;
+    let timeout = (timeout_secs > 0).then(|| std::time::Duration::from_secs(timeout_secs));
+
+    match ingest_cache::open(app_handle) {
+        Ok(conn) => query_control::run_interruptible(&conn, query_id, timeout, run),
+        Err(e) => {
+            eprintln!("Warning: Cannot open ingestion cache: {}", e);
+            query_control::run_interruptible(&pool.get()?, query_id, timeout, run)
+        }
+    }
 }
 
-/// Import an external log file by copying it to the monitored folder.
+/// Load all events from a log file for viewing, or only those within
+/// `timeRange`.
 #[tauri::command]
-async fn import_log_file(
+async fn load_log_events(
     app_handle: tauri::AppHandle,
-    sourcePath: String,
+    pool: tauri::State<'_, db_pool::ConnectionPool>,
+    logPath: String,
     logType: models::LogType,
-) -> Result<LogFileInfo, SiemError> {
-    log_manager::import_log_file(&app_handle, &sourcePath, logType)
+    timeRange: Option<models::TimeRange>,
+) -> Result<Vec<serde_json::Value>, SiemError> {
+    let time_filter = timeRange
+        .map(|range| event_time::TimeFilter::new(&range, &logType))
+        .transpose()?
+        .flatten();
+    let keep = |mut chunk: Vec<serde_json::Value>| {
+        if let Some(time_filter) = &time_filter {
+            time_filter.retain(&mut chunk);
+        }
+        chunk
+    };
+
+    if let Some(cached) = cached_source(&app_handle, &logPath, &logType) {
+        let mut events = Vec::new();
+        cached.stream_events(db_engine::STREAM_CHUNK_SIZE, |chunk| {
+            events.extend(keep(chunk))
+        })?;
+        return Ok(events);
+    }
+    db_engine::load_all_events(&pool.get()?, &logPath, logType).map(keep)
 }
 
-/// Delete a log file from the monitored folder.
+/// Load one page of a log file's events, so the event viewer can scroll large
+/// files without transferring them whole. Events can be restricted by a
+/// `filter` condition and sorted by `sortField` (descending with a leading
+/// `-`, e.g. `-eventTime`). `limit` defaults to 500 events and is capped at
+/// 10,000.
 #[tauri::command]
-async fn delete_log_file(app_handle: tauri::AppHandle, filename: String) -> Result<(), SiemError> {
-    log_manager::delete_log_file(&app_handle, &filename)
+async fn load_log_events_page(
+    app_handle: tauri::AppHandle,
+    logPath: String,
+    logType: models::LogType,
+    offset: Option<usize>,
+    limit: Option<usize>,
+    sortField: Option<String>,
+    filter: Option<String>,
+) -> Result<models::EventPage, SiemError> {
+    let cached = cached_source(&app_handle, &logPath, &logType);
+    let stream = |observe: &mut dyn FnMut(Vec<serde_json::Value>)| match &cached {
+        Some(cached) => cached.stream_events(db_engine::STREAM_CHUNK_SIZE, observe),
+        None => db_engine::stream_events(&logPath, &logType, db_engine::STREAM_CHUNK_SIZE, observe),
+    };
+    event_page::read_page(
+        stream,
+        offset.unwrap_or(0),
+        limit.unwrap_or(500),
+        sortField.as_deref(),
+        filter.as_deref(),
+    )
 }
 
-/// Update the log type for a specific log file.
+/// Parse a log file into the persistent ingestion cache, so later scans and
+/// queries read its table instead of the JSON file.
 #[tauri::command]
-async fn update_log_type(
+async fn ingest_log_file(
     app_handle: tauri::AppHandle,
-    filename: String,
+    logPath: String,
     logType: models::LogType,
-) -> Result<(), SiemError> {
-    log_manager::set_log_type(&app_handle, &filename, logType)
+) -> Result<ingest_cache::IngestedSource, SiemError> {
+    workspace_lock::ensure_writable(&app_handle)?;
+    let conn = ingest_cache::open(&app_handle)?;
+    let source = ingest_cache::ingest_file(&conn, &logPath, &logType)?;
+    change_feed::notify(&app_handle, ChangeKind::Logs, vec![logPath]);
+    Ok(source)
 }
 
-/// Import multiple log files at once with the same log type.
+/// List the log files in the ingestion cache.
 #[tauri::command]
-async fn import_multiple_log_files(
+async fn list_ingested_sources(
     app_handle: tauri::AppHandle,
-    sourcePaths: Vec<String>,
-    logType: models::LogType,
-) -> Result<ImportSummary, SiemError> {
-    log_manager::import_multiple_log_files(&app_handle, sourcePaths, logType)
+) -> Result<Vec<ingest_cache::IngestedSource>, SiemError> {
+    let conn = ingest_cache::open(&app_handle)?;
+    ingest_cache::list_sources(&conn)
+}
+
+/// Drop the cached copy of a log file.
+#[tauri::command]
+async fn remove_ingested_source(
+    app_handle: tauri::AppHandle,
+    logPath: String,
+) -> Result<bool, SiemError> {
+    workspace_lock::ensure_writable(&app_handle)?;
+    let conn = ingest_cache::open(&app_handle)?;
+    let removed = ingest_cache::remove_source(&conn, &logPath)?;
+    change_feed::notify(&app_handle, ChangeKind::Logs, vec![logPath]);
+    Ok(removed)
+}
+
+/// Register a log file as a named view, so ad-hoc queries can select from it
+/// and join it with other registered files:
+/// `SELECT ... FROM trail t JOIN flows f ON t.sourceIPAddress = f.src_ip`.
+/// Registering an existing name replaces its view.
+#[tauri::command]
+async fn register_log_view(
+    app_handle: tauri::AppHandle,
+    name: String,
+    logPath: String,
+    logType: models::LogType,
+) -> Result<log_views::LogView, SiemError> {
+    workspace_lock::ensure_writable(&app_handle)?;
+    let conn = ingest_cache::open(&app_handle)?;
+    log_views::register_view(&conn, &name, &logPath, &logType)
+}
+
+/// List the log files registered as views.
+#[tauri::command]
+async fn list_log_views(
+    app_handle: tauri::AppHandle,
+) -> Result<Vec<log_views::LogView>, SiemError> {
+    let conn = ingest_cache::open(&app_handle)?;
+    log_views::list_views(&conn)
+}
+
+/// Drop a registered log view.
+#[tauri::command]
+async fn drop_log_view(app_handle: tauri::AppHandle, name: String) -> Result<bool, SiemError> {
+    workspace_lock::ensure_writable(&app_handle)?;
+    let conn = ingest_cache::open(&app_handle)?;
+    log_views::drop_view(&conn, &name)
+}
+
+/// Export a log file as a normalized CSV or Parquet dataset.
+#[tauri::command]
+async fn export_dataset(
+    pool: tauri::State<'_, db_pool::ConnectionPool>,
+    logPath: String,
+    logType: models::LogType,
+    destPath: String,
+    format: dataset_export::DatasetFormat,
+    anonymize: Option<anonymize::AnonymizeOptions>,
+) -> Result<dataset_export::DatasetExportSummary, SiemError> {
+    dataset_export::export_dataset(
+        &pool.get()?,
+        &logPath,
+        logType,
+        &destPath,
+        format,
+        anonymize.as_ref(),
+    )
+}
+
+/// Check a log file for gaps, out-of-order bursts and sequence breaks.
+#[tauri::command]
+async fn analyze_log_integrity(
+    pool: tauri::State<'_, db_pool::ConnectionPool>,
+    logPath: String,
+    logType: models::LogType,
+    options: Option<log_integrity::IntegrityOptions>,
+) -> Result<models::IntegrityReport, SiemError> {
+    log_integrity::analyze_log_file(
+        &pool.get()?,
+        &logPath,
+        logType,
+        &options.unwrap_or_default(),
+    )
+}
+
+/// Count a log file's events per minute, hour or day, optionally only those
+/// matching a condition (e.g. "eventName = 'ConsoleLogin'").
+#[tauri::command]
+async fn get_event_timeline(
+    app_handle: tauri::AppHandle,
+    logPath: String,
+    logType: models::LogType,
+    bucket: Option<models::TimelineBucket>,
+    fieldFilter: Option<String>,
+) -> Result<models::EventTimeline, SiemError> {
+    if let Some(filter) = fieldFilter.as_deref().filter(|f| !f.trim().is_empty()) {
+        db_engine::resolve_condition_term_sets(filter, db_engine::CaseMode::Default)?;
+    }
+    let mut builder = timeline::TimelineBuilder::new(
+        &logType,
+        bucket.unwrap_or(models::TimelineBucket::Hour),
+        fieldFilter.as_deref(),
+    );
+    let observe = |chunk: Vec<serde_json::Value>| chunk.iter().for_each(|e| builder.observe(e));
+    match cached_source(&app_handle, &logPath, &logType) {
+        Some(cached) => cached.stream_events(db_engine::STREAM_CHUNK_SIZE, observe)?,
+        None => {
+            db_engine::stream_events(&logPath, &logType, db_engine::STREAM_CHUNK_SIZE, observe)?
+        }
+    };
+    Ok(builder.finish())
+}
+
+/// Stack a field of a log file: its most frequent values, distinct
+/// cardinality and share of events without it.
+#[tauri::command]
+async fn get_field_stats(
+    app_handle: tauri::AppHandle,
+    pool: tauri::State<'_, db_pool::ConnectionPool>,
+    logPath: String,
+    logType: models::LogType,
+    field: String,
+    topN: Option<usize>,
+) -> Result<models::FieldStats, SiemError> {
+    let top_n = topN.unwrap_or(field_stats::DEFAULT_TOP_N);
+    let cached = cached_source(&app_handle, &logPath, &logType);
+
+    let stats = match (&cached, &logType) {
+        (Some(cached), _) => field_stats::sql_field_stats(
+            cached.connection(),
+            &cached.records_query(),
+            &field,
+            top_n,
+        )?,
+        (None, models::LogType::CloudTrail) => field_stats::sql_field_stats(
+            &pool.get()?,
+            &db_engine::cloudtrail_records_query(&logPath)?,
+            &field,
+            top_n,
+        )?,
+        (
+            None,
+            models::LogType::FlatJson
+            | models::LogType::Kv
+            | models::LogType::Suricata
+            | models::LogType::AzureActivity
+            | models::LogType::EntraSignIn
+            | models::LogType::Okta
+            | models::LogType::Windows
+            | models::LogType::WindowsXml
+            | models::LogType::Plugin(_)
+            | models::LogType::Text(_),
+        ) => None,
+    };
+    if let Some(stats) = stats {
+        return Ok(stats);
+    }
+
+    let mut counter = field_stats::FieldCounter::new(&field);
+    let observe = |chunk: Vec<serde_json::Value>| chunk.iter().for_each(|e| counter.observe(e));
+    match &cached {
+        Some(cached) => cached.stream_events(db_engine::STREAM_CHUNK_SIZE, observe)?,
+        None => {
+            db_engine::stream_events(&logPath, &logType, db_engine::STREAM_CHUNK_SIZE, observe)?
+        }
+    };
+    Ok(counter.finish(top_n))
+}
+
+/// Values of a field seen fewer than `threshold` times (default 2) in a log
+/// file, rarest first, with their events.
+#[tauri::command]
+async fn find_rare_values(
+    app_handle: tauri::AppHandle,
+    logPath: String,
+    logType: models::LogType,
+    field: String,
+    threshold: Option<usize>,
+) -> Result<Vec<models::RareValue>, SiemError> {
+    let mut counter = rarity::RareValueCounter::new(&field, threshold.unwrap_or(2))?;
+    let observe = |chunk: Vec<serde_json::Value>| chunk.iter().for_each(|e| counter.observe(e));
+    match cached_source(&app_handle, &logPath, &logType) {
+        Some(cached) => cached.stream_events(db_engine::STREAM_CHUNK_SIZE, observe)?,
+        None => {
+            db_engine::stream_events(&logPath, &logType, db_engine::STREAM_CHUNK_SIZE, observe)?
+        }
+    };
+    Ok(counter.finish())
+}
+
+/// Summarize one entity of a log file (events over time, actions, related
+/// users and IP addresses, first and last seen) for pivoting.
+#[tauri::command]
+async fn get_entity_summary(
+    app_handle: tauri::AppHandle,
+    logPath: String,
+    logType: models::LogType,
+    field: String,
+    value: String,
+    bucket: Option<models::TimelineBucket>,
+) -> Result<models::EntitySummary, SiemError> {
+    let config = config::load_config(&app_handle)?;
+    let mut builder = entity::EntitySummaryBuilder::new(
+        &field,
+        &value,
+        &logType,
+        &config.ip_fields,
+        bucket.unwrap_or(models::TimelineBucket::Hour),
+    );
+    let observe = |chunk: Vec<serde_json::Value>| chunk.iter().for_each(|e| builder.observe(e));
+    match cached_source(&app_handle, &logPath, &logType) {
+        Some(cached) => cached.stream_events(db_engine::STREAM_CHUNK_SIZE, observe)?,
+        None => {
+            db_engine::stream_events(&logPath, &logType, db_engine::STREAM_CHUNK_SIZE, observe)?
+        }
+    };
+    Ok(builder.finish())
+}
+
+/// Validate that a log file can be read by DuckDB.
+#[tauri::command]
+async fn validate_log_file(
+    pool: tauri::State<'_, db_pool::ConnectionPool>,
+    logPath: String,
+) -> Result<bool, SiemError> {
+    db_engine::validate_log_file(&pool.get()?, &logPath)
+}
+
+// ============================================================================
+// Rule Testing Commands
+// ============================================================================
+
+/// Test a rule condition against loaded events
+#[tauri::command]
+async fn test_rule(
+    pool: tauri::State<'_, db_pool::ConnectionPool>,
+    condition: String,
+    log_path: String,
+    log_type: models::LogType,
+    case_sensitive: Option<bool>,
+) -> Result<models::TestRuleResult, SiemError> {
+    test_rule::test_rule(
+        &pool.get()?,
+        &log_path,
+        &condition,
+        log_type,
+        case_sensitive,
+    )
+}
+
+/// Test a rule condition against sample events pasted by the user, without
+/// a log file
+#[tauri::command]
+async fn test_rule_inline(
+    condition: String,
+    eventsJson: String,
+    caseSensitive: Option<bool>,
+) -> Result<models::TestRuleResult, SiemError> {
+    test_rule::test_rule_inline(&condition, &eventsJson, caseSensitive)
+}
+
+/// Time every active rule against a log file and rank them, slowest first,
+/// flagging rules that match an excessive share of the events. Only the
+/// detection logic is timed and nothing is recorded.
+#[tauri::command]
+async fn profile_rules(
+    app_handle: tauri::AppHandle,
+    logPath: String,
+) -> Result<models::RuleProfileReport, SiemError> {
+    let log_type = match log_manager::get_log_type(&app_handle, &logPath) {
+        Some(log_type) => log_type,
+        None => db_engine::detect_log_type(&logPath)?,
+    };
+
+    let active_rules = rule_manager::list_active_rules(&app_handle)?;
+    let rules: Vec<&models::RuleYaml> = active_rules
+        .iter()
+        .filter(|r| !meta_rules::is_meta_rule(r))
+        .filter(|r| scanner::rule_applies_to(r, &log_type))
+        .collect();
+
+    let mut profiler = rule_profiler::RuleProfiler::new(&rules)?;
+    let observe = |chunk: Vec<serde_json::Value>| profiler.observe(&chunk);
+    match cached_source(&app_handle, &logPath, &log_type) {
+        Some(cached) => cached.stream_events(db_engine::STREAM_CHUNK_SIZE, observe)?,
+        None => {
+            db_engine::stream_events(&logPath, &log_type, db_engine::STREAM_CHUNK_SIZE, observe)?
+        }
+    };
+    Ok(profiler.finish(&logPath))
+}
+
+/// Validate rule condition syntax, or script syntax for script rules
+/// (`detectionType: "script"`).
+#[tauri::command]
+async fn validate_condition(
+    condition: String,
+    detectionType: Option<models::DetectionType>,
+) -> Result<models::ValidationResult, SiemError> {
+    Ok(match detectionType.unwrap_or_default() {
+        models::DetectionType::Condition => test_rule::validate_condition(&condition),
+        models::DetectionType::Script => script_rules::validate_script(&condition),
+    })
+}
+
+/// Get field suggestions for autocomplete
+#[tauri::command]
+async fn get_field_suggestions(
+    pool: tauri::State<'_, db_pool::ConnectionPool>,
+    log_path: String,
+    log_type: models::LogType,
+    prefix: String,
+) -> Result<Vec<models::FieldSuggestion>, SiemError> {
+    test_rule::get_field_suggestions(&pool.get()?, &log_path, log_type, &prefix)
+}
+
+/// Get the most frequent values of a field starting with `prefix`, for value
+/// autocomplete in the rule editor
+#[tauri::command]
+async fn get_value_suggestions(
+    logPath: String,
+    logType: models::LogType,
+    field: String,
+    prefix: String,
+) -> Result<Vec<models::FieldValueCount>, SiemError> {
+    test_rule::get_value_suggestions(&logPath, &logType, &field, &prefix)
+}
+
+/// Infer the full field tree of a log file: every field path with its types,
+/// sample values and share of events that have it.
+#[tauri::command]
+async fn get_log_schema(
+    app_handle: tauri::AppHandle,
+    logPath: String,
+    logType: models::LogType,
+) -> Result<models::LogSchema, SiemError> {
+    let mut builder = log_schema::SchemaBuilder::new();
+    let observe = |chunk: Vec<serde_json::Value>| chunk.iter().for_each(|e| builder.observe(e));
+    match cached_source(&app_handle, &logPath, &logType) {
+        Some(cached) => cached.stream_events(db_engine::STREAM_CHUNK_SIZE, observe)?,
+        None => {
+            db_engine::stream_events(&logPath, &logType, db_engine::STREAM_CHUNK_SIZE, observe)?
+        }
+    };
+    Ok(builder.finish())
+}
+
+// ============================================================================
+// Log File Management Commands
+// ============================================================================
+
+/// List all JSON log files in the monitored logs folder.
+#[tauri::command]
+async fn list_log_files(app_handle: tauri::AppHandle) -> Result<Vec<LogFileInfo>, SiemError> {
+    log_manager::list_log_files(&app_handle)
+}
+
+/// Search the raw content of every log file in the monitored folder for a
+/// keyword (a substring, or a regular expression with `regex`). Files are
+/// streamed event by event; matching events are returned with their file
+/// and position, up to `log_search::MAX_SEARCH_HITS`.
+#[tauri::command]
+async fn grep_logs(
+    app_handle: tauri::AppHandle,
+    keyword: String,
+    caseSensitive: Option<bool>,
+    regex: Option<bool>,
+) -> Result<models::LogSearchResult, SiemError> {
+    let mut search = log_search::LogSearch::new(
+        &keyword,
+        caseSensitive.unwrap_or(false),
+        regex.unwrap_or(false),
+    )?;
+    for file in log_manager::list_log_files(&app_handle)? {
+        search.search_file(&file);
+    }
+    Ok(search.finish())
+}
+
+/// Import an external log file by copying it to the monitored folder, or the
+/// log files of a ZIP archive by extracting them.
+#[tauri::command]
+async fn import_log_file(
+    app_handle: tauri::AppHandle,
+    sourcePath: String,
+    logType: models::LogType,
+) -> Result<Vec<LogFileInfo>, SiemError> {
+    workspace_lock::ensure_writable(&app_handle)?;
+    let infos = log_manager::import_log_file(&app_handle, &sourcePath, logType.clone())?;
+    for info in &infos {
+        let log_type = info.log_type.as_ref().unwrap_or(&logType);
+        ingest_imported(&app_handle, &[info.path.clone()], log_type);
+    }
+    let filenames = infos.iter().map(|info| info.filename.clone()).collect();
+    change_feed::notify(&app_handle, ChangeKind::Logs, filenames);
+    Ok(infos)
+}
+
+/// Register an external log file in place, without copying it into the
+/// monitored folder.
+#[tauri::command]
+async fn register_log_file(
+    app_handle: tauri::AppHandle,
+    sourcePath: String,
+    logType: models::LogType,
+) -> Result<LogFileInfo, SiemError> {
+    workspace_lock::ensure_writable(&app_handle)?;
+    let info = log_manager::register_log_file(&app_handle, &sourcePath, logType.clone())?;
+    ingest_imported(&app_handle, &[info.path.clone()], &logType);
+    change_feed::notify(&app_handle, ChangeKind::Logs, vec![info.filename.clone()]);
+    Ok(info)
+}
+
+/// Delete a log file from the monitored folder (or forget one registered in
+/// place), with its cached copy.
+#[tauri::command]
+async fn delete_log_file(app_handle: tauri::AppHandle, filename: String) -> Result<(), SiemError> {
+    workspace_lock::ensure_writable(&app_handle)?;
+    let log_path = log_manager::delete_log_file(&app_handle, &filename)?;
+    if let Err(e) = ingest_cache::open(&app_handle)
+        .and_then(|conn| ingest_cache::remove_source(&conn, &log_path.to_string_lossy()))
+    {
+        eprintln!("Warning: Cannot remove cached log: {}", e);
+    }
+    change_feed::notify(&app_handle, ChangeKind::Logs, vec![filename]);
+    Ok(())
+}
+
+/// Re-hash a library log file and compare it with the SHA-256 recorded when
+/// it was imported, flagging any change since.
+#[tauri::command]
+async fn verify_log_integrity(
+    app_handle: tauri::AppHandle,
+    filename: String,
+) -> Result<models::HashVerification, SiemError> {
+    log_hashes::verify(&app_handle, &filename)
+}
+
+/// Update the log type for a specific log file.
+#[tauri::command]
+async fn update_log_type(
+    app_handle: tauri::AppHandle,
+    filename: String,
+    logType: models::LogType,
+) -> Result<(), SiemError> {
+    workspace_lock::ensure_writable(&app_handle)?;
+    log_manager::set_log_type(&app_handle, &filename, logType)?;
+    change_feed::notify(&app_handle, ChangeKind::Logs, vec![filename]);
+    Ok(())
+}
+
+/// Import multiple log files at once with the same log type.
+#[tauri::command]
+async fn import_multiple_log_files(
+    app_handle: tauri::AppHandle,
+    sourcePaths: Vec<String>,
+    logType: models::LogType,
+) -> Result<ImportSummary, SiemError> {
+    workspace_lock::ensure_writable(&app_handle)?;
+    let summary =
+        log_manager::import_multiple_log_files(&app_handle, sourcePaths, logType.clone())?;
+    // Files extracted from ZIP archives may be of another log type
+    for info in &summary.imported_files {
+        let log_type = info.log_type.as_ref().unwrap_or(&logType);
+        ingest_imported(&app_handle, &[info.path.clone()], log_type);
+    }
+    change_feed::notify(&app_handle, ChangeKind::Logs, vec![]);
+    Ok(summary)
+}
+
+/// Import the log artifacts of a Velociraptor collection (zip) or a KAPE
+/// output folder, each as a log file of its detected log type.
+#[tauri::command]
+async fn import_triage_collection(
+    app_handle: tauri::AppHandle,
+    sourcePath: String,
+) -> Result<ImportSummary, SiemError> {
+    workspace_lock::ensure_writable(&app_handle)?;
+    let summary = triage_import::import_collection(&app_handle, &sourcePath)?;
+    for info in &summary.imported_files {
+        let log_type = info.log_type.clone().unwrap_or(models::LogType::FlatJson);
+        ingest_imported(&app_handle, &[info.path.clone()], &log_type);
+    }
+    change_feed::notify(&app_handle, ChangeKind::Logs, vec![]);
+    Ok(summary)
+}
+
+/// Ingest freshly imported log files when ingestion on import is enabled.
+/// Failures are logged; the files are then read from disk when scanned.
+fn ingest_imported(app_handle: &tauri::AppHandle, paths: &[String], log_type: &models::LogType) {
+    let enabled = config::load_config(app_handle).is_ok_and(|config| config.ingest_on_import);
+    if !enabled || paths.is_empty() {
+        return;
+    }
+    let conn = match ingest_cache::open(app_handle) {
+        Ok(conn) => conn,
+        Err(e) => {
+            eprintln!("Warning: Cannot open ingestion cache: {}", e);
+            return;
+        }
+    };
+    for path in paths {
+        if let Err(e) = ingest_cache::ingest_file(&conn, path, log_type) {
+            eprintln!("Warning: Cannot ingest '{}': {}", path, e);
+        }
+    }
+}
+
+/// List the parser plugins (`plugins/*.wasm` in the app data directory) for
+/// custom log formats, with why a plugin can't be loaded if it can't.
+#[tauri::command]
+async fn list_parser_plugins(
+    app_handle: tauri::AppHandle,
+) -> Result<Vec<parser_plugins::ParserPlugin>, SiemError> {
+    parser_plugins::list_plugins(&app_handle)
+}
+
+/// List the text parsers (grok/regex patterns) for text log formats.
+#[tauri::command]
+async fn list_text_parsers(
+    app_handle: tauri::AppHandle,
+) -> Result<Vec<text_parser::TextParser>, SiemError> {
+    text_parser::load_parsers(&app_handle)
+}
+
+/// Create or replace a text parser; its patterns must compile.
+#[tauri::command]
+async fn save_text_parser(
+    app_handle: tauri::AppHandle,
+    parser: text_parser::TextParser,
+) -> Result<text_parser::TextParser, SiemError> {
+    workspace_lock::ensure_writable(&app_handle)?;
+    text_parser::save_parser(&app_handle, parser)
+}
+
+/// Delete a text parser.
+#[tauri::command]
+async fn delete_text_parser(app_handle: tauri::AppHandle, name: String) -> Result<(), SiemError> {
+    workspace_lock::ensure_writable(&app_handle)?;
+    text_parser::delete_parser(&app_handle, &name)
+}
+
+/// Preview a text parser on sample lines: the events it produces.
+#[tauri::command]
+async fn test_text_parser(
+    parser: text_parser::TextParser,
+    sample: String,
+) -> Result<Vec<serde_json::Value>, SiemError> {
+    let compiled = parser.compile()?;
+    Ok(sample
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .filter_map(|line| compiled.parse_line(line))
+        .collect())
+}
+
+// ============================================================================
+// Alert Export Commands
+// ============================================================================
+
+/// List the available alert export formats.
+#[tauri::command]
+async fn list_export_sinks() -> Result<Vec<alert_export::ExportSinkInfo>, SiemError> {
+    Ok(alert_export::list_sinks())
+}
+
+/// Export alerts with a registered sink (csv, jsonl, stix, misp, html, syslog, elastic_bulk).
+#[tauri::command]
+async fn export_alerts(
+    sink: String,
+    options: alert_export::ExportOptions,
+    alerts: Vec<AlertEvent>,
+) -> Result<alert_export::AlertExportSummary, SiemError> {
+    alert_export::export_alerts(&sink, &alerts, &options)
+}
+
+/// Export the alerts of a recorded scan (from the scan history) as a MISP
+/// event. The alerts themselves come from the caller, as only counts are kept
+/// in the history.
+#[tauri::command]
+async fn export_alerts_misp(
+    app_handle: tauri::AppHandle,
+    scanId: String,
+    alerts: Vec<AlertEvent>,
+    options: alert_export::ExportOptions,
+) -> Result<alert_export::AlertExportSummary, SiemError> {
+    let scan = scan_history::load_history(&app_handle)?
+        .into_iter()
+        .find(|entry| entry.scan_id == scanId)
+        .ok_or_else(|| SiemError::FileIO(format!("Scan not found: {}", scanId)))?;
+    alert_export::export_alerts_misp(&scan, &alerts, &options)
+}
+
+/// Write a self-contained HTML report of a scan, with the descriptions of
+/// the rules that fired.
+#[tauri::command]
+async fn export_scan_report(
+    app_handle: tauri::AppHandle,
+    scan: ScanResponse,
+    options: report::ReportOptions,
+) -> Result<report::ReportSummary, SiemError> {
+    let rules = rule_manager::list_rules(&app_handle)?;
+    report::write_scan_report(&scan, &rules, &options)
+}
+
+/// Extract IOCs (addresses, URLs, e-mails, domains, hashes) from the evidence
+/// of a scan's alerts or from a log file, optionally writing them as CSV.
+/// Scans are given by their alerts, as only counts are kept in the history.
+#[tauri::command]
+async fn extract_iocs(
+    app_handle: tauri::AppHandle,
+    alerts: Option<Vec<AlertEvent>>,
+    logPath: Option<String>,
+    logType: Option<models::LogType>,
+    destPath: Option<String>,
+) -> Result<Vec<ioc_extract::ExtractedIoc>, SiemError> {
+    let mut collector = ioc_extract::IocCollector::default();
+    match (alerts, logPath) {
+        (_, Some(log_path)) => {
+            let log_type = match logType {
+                Some(log_type) => log_type,
+                None => db_engine::detect_log_type(&log_path)?,
+            };
+            db_engine::stream_events(
+                &log_path,
+                &log_type,
+                db_engine::STREAM_CHUNK_SIZE,
+                |chunk| chunk.iter().for_each(|event| collector.observe(event)),
+            )?;
+        }
+        (Some(alerts), None) => {
+            for event in alerts.iter().flat_map(|alert| &alert.evidence) {
+                collector.observe(event);
+            }
+        }
+        (None, None) => {
+            return Err(SiemError::Query(
+                "Either alerts or a log path is required".to_string(),
+            ))
+        }
+    }
+
+    let iocs = collector.finish();
+    if let Some(dest_path) = destPath {
+        ioc_extract::write_csv(&iocs, &dest_path)?;
+    }
+    Ok(iocs)
+}
+
+// ============================================================================
+// Hash Set Commands
+// ============================================================================
+
+/// Import a known-good or known-bad hash set from a text/CSV file.
+#[tauri::command]
+async fn import_hash_set(
+    app_handle: tauri::AppHandle,
+    sourcePath: String,
+    name: String,
+    classification: models::HashClassification,
+) -> Result<HashSetInfo, SiemError> {
+    workspace_lock::ensure_writable(&app_handle)?;
+    hash_sets::import_hash_set(&app_handle, &sourcePath, &name, classification)
+}
+
+/// List imported hash sets.
+#[tauri::command]
+async fn list_hash_sets(app_handle: tauri::AppHandle) -> Result<Vec<HashSetInfo>, SiemError> {
+    hash_sets::list_hash_sets(&app_handle)
+}
+
+/// Delete an imported hash set.
+#[tauri::command]
+async fn delete_hash_set(app_handle: tauri::AppHandle, name: String) -> Result<(), SiemError> {
+    workspace_lock::ensure_writable(&app_handle)?;
+    hash_sets::delete_hash_set(&app_handle, &name)
+}
+
+// ============================================================================
+// Threat Intel Commands
+// ============================================================================
+
+/// Import an IOC list (IPs, domains, hashes, ARNs) from a text/CSV file.
+#[tauri::command]
+async fn import_ioc_list(
+    app_handle: tauri::AppHandle,
+    sourcePath: String,
+    name: String,
+    description: Option<String>,
+) -> Result<models::IocListInfo, SiemError> {
+    workspace_lock::ensure_writable(&app_handle)?;
+    intel::import_ioc_list(
+        &app_handle,
+        &sourcePath,
+        &name,
+        description.unwrap_or_default(),
+    )
+}
+
+/// List imported IOC lists.
+#[tauri::command]
+async fn list_ioc_lists(
+    app_handle: tauri::AppHandle,
+) -> Result<Vec<models::IocListInfo>, SiemError> {
+    intel::list_ioc_lists(&app_handle)
+}
+
+/// Search every log file of the library for the indicators of an IOC list,
+/// in any field. Hits are grouped by indicator and file.
+#[tauri::command]
+async fn sweep_iocs(
+    app_handle: tauri::AppHandle,
+    iocListId: String,
+) -> Result<models::IocSweepResult, SiemError> {
+    intel::sweep_iocs(&app_handle, &iocListId)
+}
+
+/// Delete an imported IOC list.
+#[tauri::command]
+async fn delete_ioc_list(app_handle: tauri::AppHandle, name: String) -> Result<(), SiemError> {
+    workspace_lock::ensure_writable(&app_handle)?;
+    intel::delete_ioc_list(&app_handle, &name)
+}
+
+// ============================================================================
+// Lookup List Commands
+// ============================================================================
+
+/// List the lookup lists usable in `IN_LIST` conditions.
+#[tauri::command]
+async fn list_lookup_lists(
+    app_handle: tauri::AppHandle,
+) -> Result<Vec<models::LookupListInfo>, SiemError> {
+    lookup_lists::list_lookup_lists(&app_handle)
+}
+
+/// Get the values of a lookup list.
+#[tauri::command]
+async fn get_lookup_list(
+    app_handle: tauri::AppHandle,
+    name: String,
+) -> Result<Vec<String>, SiemError> {
+    lookup_lists::get_lookup_list(&app_handle, &name)
+}
+
+/// Create or replace a lookup list.
+#[tauri::command]
+async fn save_lookup_list(
+    app_handle: tauri::AppHandle,
+    name: String,
+    entries: Vec<String>,
+) -> Result<models::LookupListInfo, SiemError> {
+    workspace_lock::ensure_writable(&app_handle)?;
+    lookup_lists::save_lookup_list(&app_handle, &name, entries)
+}
+
+/// Delete a lookup list.
+#[tauri::command]
+async fn delete_lookup_list(app_handle: tauri::AppHandle, name: String) -> Result<(), SiemError> {
+    workspace_lock::ensure_writable(&app_handle)?;
+    lookup_lists::delete_lookup_list(&app_handle, &name)
+}
+
+// ============================================================================
+// Event Annotation Commands
+// ============================================================================
+
+/// Flag an event with a label and comment.
+#[tauri::command]
+async fn flag_event(
+    app_handle: tauri::AppHandle,
+    pool: tauri::State<'_, db_pool::ConnectionPool>,
+    logPath: String,
+    logType: models::LogType,
+    recordIndex: usize,
+    label: String,
+    comment: String,
+) -> Result<EventAnnotation, SiemError> {
+    workspace_lock::ensure_writable(&app_handle)?;
+    annotation_manager::flag_event(
+        &app_handle,
+        &pool.get()?,
+        &logPath,
+        logType,
+        recordIndex,
+        label,
+        comment,
+    )
+}
+
+/// Remove a flag from an event.
+#[tauri::command]
+async fn unflag_event(app_handle: tauri::AppHandle, annotationId: String) -> Result<(), SiemError> {
+    workspace_lock::ensure_writable(&app_handle)?;
+    annotation_manager::unflag_event(&app_handle, &annotationId)
+}
+
+/// List flagged events, optionally for a single log file.
+#[tauri::command]
+async fn list_flagged_events(
+    app_handle: tauri::AppHandle,
+    logPath: Option<String>,
+) -> Result<Vec<EventAnnotation>, SiemError> {
+    annotation_manager::list_flagged_events(&app_handle, logPath.as_deref())
+}
+
+// ============================================================================
+// Suppression Commands
+// ============================================================================
+
+/// Suppress alerts of a rule ("*" for every rule) whose evidence matches all
+/// field/value patterns, until the optional expiry date.
+#[tauri::command]
+async fn add_suppression(
+    app_handle: tauri::AppHandle,
+    ruleId: String,
+    patterns: Vec<models::SuppressionPattern>,
+    expiresAt: Option<String>,
+    reason: Option<String>,
+) -> Result<models::SuppressionEntry, SiemError> {
+    workspace_lock::ensure_writable(&app_handle)?;
+    suppression_manager::add_suppression(
+        &app_handle,
+        ruleId,
+        patterns,
+        expiresAt,
+        reason.unwrap_or_default(),
+    )
+}
+
+/// Remove a suppression entry.
+#[tauri::command]
+async fn remove_suppression(
+    app_handle: tauri::AppHandle,
+    suppressionId: String,
+) -> Result<(), SiemError> {
+    workspace_lock::ensure_writable(&app_handle)?;
+    suppression_manager::remove_suppression(&app_handle, &suppressionId)
+}
+
+/// List suppression entries, including expired ones if asked.
+#[tauri::command]
+async fn list_suppressions(
+    app_handle: tauri::AppHandle,
+    includeExpired: Option<bool>,
+) -> Result<Vec<models::SuppressionEntry>, SiemError> {
+    suppression_manager::list_suppressions(&app_handle, includeExpired.unwrap_or(false))
+}
+
+// ============================================================================
+// Case Management Commands
+// ============================================================================
+
+/// Create a new investigation case.
+#[tauri::command]
+async fn create_case(
+    app_handle: tauri::AppHandle,
+    name: String,
+    description: String,
+) -> Result<Case, SiemError> {
+    workspace_lock::ensure_writable(&app_handle)?;
+    let case = case_manager::create_case(&app_handle, name, description)?;
+    change_feed::notify(&app_handle, ChangeKind::Cases, vec![case.id.clone()]);
+    Ok(case)
+}
+
+/// List all cases.
+#[tauri::command]
+async fn list_cases(app_handle: tauri::AppHandle) -> Result<Vec<CaseSummary>, SiemError> {
+    case_manager::list_cases(&app_handle)
+}
+
+/// Get a case with its attachments and timeline.
+#[tauri::command]
+async fn get_case(app_handle: tauri::AppHandle, caseId: String) -> Result<Case, SiemError> {
+    case_manager::get_case(&app_handle, &caseId)
+}
+
+/// Delete a case.
+#[tauri::command]
+async fn delete_case(app_handle: tauri::AppHandle, caseId: String) -> Result<(), SiemError> {
+    workspace_lock::ensure_writable(&app_handle)?;
+    case_manager::delete_case(&app_handle, &caseId)?;
+    change_feed::notify(&app_handle, ChangeKind::Cases, vec![caseId]);
+    Ok(())
+}
+
+/// Change the status of a case.
+#[tauri::command]
+async fn set_case_status(
+    app_handle: tauri::AppHandle,
+    caseId: String,
+    status: models::CaseStatus,
+) -> Result<Case, SiemError> {
+    workspace_lock::ensure_writable(&app_handle)?;
+    let case = case_manager::set_case_status(&app_handle, &caseId, status)?;
+    change_feed::notify(&app_handle, ChangeKind::Cases, vec![caseId]);
+    Ok(case)
+}
+
+/// Attach alerts to a case.
+#[tauri::command]
+async fn attach_alerts_to_case(
+    app_handle: tauri::AppHandle,
+    caseId: String,
+    alerts: Vec<AlertEvent>,
+) -> Result<Case, SiemError> {
+    workspace_lock::ensure_writable(&app_handle)?;
+    let case = case_manager::attach_alerts(&app_handle, &caseId, alerts)?;
+    change_feed::notify(&app_handle, ChangeKind::Cases, vec![caseId]);
+    Ok(case)
+}
+
+/// Attach a single log event to a case.
+#[tauri::command]
+async fn attach_event_to_case(
+    app_handle: tauri::AppHandle,
+    pool: tauri::State<'_, db_pool::ConnectionPool>,
+    caseId: String,
+    logPath: String,
+    logType: models::LogType,
+    recordIndex: usize,
+    note: String,
+) -> Result<Case, SiemError> {
+    workspace_lock::ensure_writable(&app_handle)?;
+    let case = case_manager::attach_event(
+        &app_handle,
+        &pool.get()?,
+        &caseId,
+        &logPath,
+        logType,
+        recordIndex,
+        note,
+    )?;
+    change_feed::notify(&app_handle, ChangeKind::Cases, vec![caseId]);
+    Ok(case)
+}
+
+/// Attach a log file to a case.
+#[tauri::command]
+async fn attach_log_file_to_case(
+    app_handle: tauri::AppHandle,
+    caseId: String,
+    logPath: String,
+) -> Result<Case, SiemError> {
+    workspace_lock::ensure_writable(&app_handle)?;
+    let case = case_manager::attach_log_file(&app_handle, &caseId, &logPath)?;
+    change_feed::notify(&app_handle, ChangeKind::Cases, vec![caseId]);
+    Ok(case)
+}
+
+/// Add a note to a case timeline.
+#[tauri::command]
+async fn add_case_note(
+    app_handle: tauri::AppHandle,
+    caseId: String,
+    message: String,
+) -> Result<Case, SiemError> {
+    workspace_lock::ensure_writable(&app_handle)?;
+    let case = case_manager::add_case_note(&app_handle, &caseId, message)?;
+    change_feed::notify(&app_handle, ChangeKind::Cases, vec![caseId]);
+    Ok(case)
+}
+
+/// Export a case as a ZIP bundle.
+#[tauri::command]
+async fn export_case_bundle(
+    app_handle: tauri::AppHandle,
+    caseId: String,
+    destPath: String,
+    includeLogs: bool,
+    anonymize: Option<anonymize::AnonymizeOptions>,
+) -> Result<case_manager::CaseExportSummary, SiemError> {
+    case_manager::export_case_bundle(
+        &app_handle,
+        &caseId,
+        &destPath,
+        includeLogs,
+        anonymize.as_ref(),
+    )
+}
+
+/// Export the evidence of a case or a scan as a chain-of-custody bundle:
+/// alerts, evidence events, source file hashes, analyst and timestamps, with
+/// a signed manifest. The alerts of a scan are those given.
+#[tauri::command]
+async fn export_evidence_bundle(
+    app_handle: tauri::AppHandle,
+    caseId: Option<String>,
+    scanId: Option<String>,
+    alerts: Option<Vec<AlertEvent>>,
+    destPath: String,
+    options: evidence_bundle::EvidenceBundleOptions,
+) -> Result<evidence_bundle::EvidenceBundleSummary, SiemError> {
+    evidence_bundle::export_evidence_bundle(
+        &app_handle,
+        caseId.as_deref(),
+        scanId.as_deref(),
+        alerts.unwrap_or_default(),
+        &destPath,
+        &options,
+    )
 }
 
 // ============================================================================
@@ -482,7 +2430,12 @@ async fn save_config(
     app_handle: tauri::AppHandle,
     config_data: config::AppConfig,
 ) -> Result<(), SiemError> {
-    config::save_config(&app_handle, &config_data)
+    workspace_lock::ensure_writable(&app_handle)?;
+    // Reject GeoIP databases that can't be opened before saving their paths
+    geoip::configure(&config_data)?;
+    config::save_config(&app_handle, &config_data)?;
+    change_feed::notify(&app_handle, ChangeKind::Config, vec![]);
+    Ok(())
 }
 
 /// Set custom rules directory.
@@ -491,7 +2444,14 @@ async fn set_rules_directory(
     app_handle: tauri::AppHandle,
     directory: Option<String>,
 ) -> Result<config::AppConfig, SiemError> {
-    config::set_rules_directory(&app_handle, directory)
+    workspace_lock::ensure_writable(&app_handle)?;
+    let config = config::set_rules_directory(&app_handle, directory)?;
+    match config::get_rules_directory(&app_handle) {
+        Ok(dir) => term_sets::register_rules_dir(dir),
+        Err(e) => eprintln!("Warning: Cannot open rules directory: {}", e),
+    }
+    change_feed::notify(&app_handle, ChangeKind::Config, vec![]);
+    Ok(config)
 }
 
 /// Set default logs directory.
@@ -500,7 +2460,10 @@ async fn set_logs_directory(
     app_handle: tauri::AppHandle,
     directory: Option<String>,
 ) -> Result<config::AppConfig, SiemError> {
-    config::set_logs_directory(&app_handle, directory)
+    workspace_lock::ensure_writable(&app_handle)?;
+    let config = config::set_logs_directory(&app_handle, directory)?;
+    change_feed::notify(&app_handle, ChangeKind::Config, vec![]);
+    Ok(config)
 }
 
 /// Add a log file to recent files list.
@@ -509,13 +2472,19 @@ async fn add_recent_log_file(
     app_handle: tauri::AppHandle,
     file_path: String,
 ) -> Result<config::AppConfig, SiemError> {
-    config::add_recent_log_file(&app_handle, file_path)
+    workspace_lock::ensure_writable(&app_handle)?;
+    let config = config::add_recent_log_file(&app_handle, file_path)?;
+    change_feed::notify(&app_handle, ChangeKind::Config, vec![]);
+    Ok(config)
 }
 
 /// Clear recent log files.
 #[tauri::command]
 async fn clear_recent_files(app_handle: tauri::AppHandle) -> Result<config::AppConfig, SiemError> {
-    config::clear_recent_files(&app_handle)
+    workspace_lock::ensure_writable(&app_handle)?;
+    let config = config::clear_recent_files(&app_handle)?;
+    change_feed::notify(&app_handle, ChangeKind::Config, vec![]);
+    Ok(config)
 }
 
 /// Get the current rules directory path.
@@ -525,6 +2494,191 @@ async fn get_rules_directory(app_handle: tauri::AppHandle) -> Result<String, Sie
     Ok(path.to_string_lossy().to_string())
 }
 
+// ============================================================================
+// App Data Backup Commands
+// ============================================================================
+
+/// Record a restore, with the number of files written or its error, in the
+/// audit log.
+fn audit_restore(
+    app_handle: &tauri::AppHandle,
+    kind: &str,
+    path: &str,
+    outcome: Result<usize, &SiemError>,
+) {
+    let details = match outcome {
+        Ok(files) => serde_json::json!({ "kind": kind, "files_restored": files }),
+        Err(e) => serde_json::json!({ "kind": kind, "error": e.to_string() }),
+    };
+    audit_log::record(app_handle, AuditAction::DataRestored, path, details);
+}
+
+/// Back up rules, configuration, saved queries, the alert store and lookup
+/// lists into a single file, to set up another machine the same way.
+#[tauri::command]
+async fn backup_app_data(
+    app_handle: tauri::AppHandle,
+    destPath: String,
+) -> Result<app_backup::BackupManifest, SiemError> {
+    app_backup::backup_app_data(&app_handle, &destPath)
+}
+
+/// Restore a backup made with `backup_app_data`. Logs, cases and other
+/// investigation data are left untouched.
+#[tauri::command]
+async fn restore_app_data(
+    app_handle: tauri::AppHandle,
+    srcPath: String,
+) -> Result<app_backup::BackupRestoreSummary, SiemError> {
+    workspace_lock::ensure_writable(&app_handle)?;
+    let result = app_backup::restore_app_data(&app_handle, &srcPath);
+    audit_restore(
+        &app_handle,
+        "app_backup",
+        &srcPath,
+        result.as_ref().map(|summary| summary.files_restored),
+    );
+    let summary = result?;
+    for kind in [
+        ChangeKind::Rules,
+        ChangeKind::Alerts,
+        ChangeKind::Config,
+        ChangeKind::Queries,
+    ] {
+        change_feed::notify(&app_handle, kind, vec![]);
+    }
+    Ok(summary)
+}
+
+// ============================================================================
+// Workspace Snapshot Commands
+// ============================================================================
+
+/// Snapshot rules, configuration and workspace data into a ZIP archive.
+/// Raw log files are only included when `includeLogs` is set.
+#[tauri::command]
+async fn create_workspace_snapshot(
+    app_handle: tauri::AppHandle,
+    destPath: String,
+    includeLogs: bool,
+) -> Result<workspace::SnapshotManifest, SiemError> {
+    workspace::create_snapshot(&app_handle, &destPath, includeLogs)
+}
+
+/// Roll the workspace back to a snapshot.
+#[tauri::command]
+async fn restore_workspace_snapshot(
+    app_handle: tauri::AppHandle,
+    snapshotPath: String,
+) -> Result<workspace::RestoreSummary, SiemError> {
+    workspace_lock::ensure_writable(&app_handle)?;
+    let result = workspace::restore_snapshot(&app_handle, &snapshotPath);
+    audit_restore(
+        &app_handle,
+        "workspace_snapshot",
+        &snapshotPath,
+        result.as_ref().map(|summary| summary.files_restored),
+    );
+    let summary = result?;
+    for kind in [
+        ChangeKind::Rules,
+        ChangeKind::Logs,
+        ChangeKind::Alerts,
+        ChangeKind::Config,
+        ChangeKind::Cases,
+    ] {
+        change_feed::notify(&app_handle, kind, vec![]);
+    }
+    Ok(summary)
+}
+
+/// Report whether this instance may write to the workspace, retrying the lock
+/// if another instance held it.
+#[tauri::command]
+async fn get_workspace_lock(
+    app_handle: tauri::AppHandle,
+) -> Result<workspace_lock::LockStatus, SiemError> {
+    workspace_lock::acquire(&app_handle)
+}
+
+// ============================================================================
+// REST API Commands
+// ============================================================================
+
+/// Start the local REST API on `127.0.0.1` (default port: `api_port` from
+/// config), so scripts on this machine can manage rules, scan and query.
+/// A token is generated and saved in the config on first start.
+#[tauri::command]
+async fn start_rest_api(
+    app_handle: tauri::AppHandle,
+    port: Option<u16>,
+) -> Result<rest_api::ApiStatus, SiemError> {
+    start_rest_api_server(&app_handle, port)
+}
+
+/// Stop the local REST API.
+#[tauri::command]
+async fn stop_rest_api() -> Result<rest_api::ApiStatus, SiemError> {
+    rest_api::stop();
+    Ok(rest_api::status())
+}
+
+/// State of the local REST API, with its URL and token.
+#[tauri::command]
+async fn get_rest_api_status() -> Result<rest_api::ApiStatus, SiemError> {
+    Ok(rest_api::status())
+}
+
+/// Start the REST API with the configured port and token, generating the
+/// token if there is none yet.
+fn start_rest_api_server(
+    app_handle: &tauri::AppHandle,
+    port: Option<u16>,
+) -> Result<rest_api::ApiStatus, SiemError> {
+    let mut config = config::load_config(app_handle)?;
+    let token = match config.api_token.clone().filter(|t| !t.is_empty()) {
+        Some(token) => token,
+        None => {
+            let token = uuid::Uuid::new_v4().simple().to_string();
+            config.api_token = Some(token.clone());
+            config::save_config(app_handle, &config)?;
+            token
+        }
+    };
+    rest_api::start(
+        app_handle.clone(),
+        port.unwrap_or(config.api_port),
+        token,
+        scan_background_file,
+        run_api_query,
+    )
+}
+
+/// Run an ad-hoc query for the REST API, like `run_query`.
+fn run_api_query(app_handle: &tauri::AppHandle, query: &str) -> Result<QueryResult, SiemError> {
+    let pool = app_handle.state::<db_pool::ConnectionPool>();
+    let start = std::time::Instant::now();
+    let results = with_query_connection(app_handle, &pool, None, |conn| {
+        db_engine::execute_adhoc_query(conn, query)
+    });
+    audit_query(
+        app_handle,
+        AuditActor::Api,
+        query,
+        results.as_ref().map(|results| results.rows.len()),
+    );
+    let results = results?;
+
+    Ok(QueryResult {
+        query: query.to_string(),
+        columns: results.columns,
+        column_types: results.column_types,
+        row_count: results.rows.len(),
+        rows: results.rows,
+        execution_time_ms: start.elapsed().as_millis() as u64,
+    })
+}
+
 // ============================================================================
 // Tauri Application Builder
 // ============================================================================
@@ -534,34 +2688,211 @@ pub fn run() {
     tauri::Builder::default()
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_fs::init())
+        .setup(|app| {
+            // Shared DuckDB connections for queries and rule testing
+            app.manage(db_pool::ConnectionPool::new()?);
+            // Background scan jobs
+            app.manage(job_manager::JobManager::new(scan_background_file));
+            // Report external edits to rule files to the frontend
+            change_feed::start_rules_watcher(app.handle().clone());
+            // Become the workspace writer unless another instance already is
+            match workspace_lock::acquire(app.handle()) {
+                Ok(status) if !status.writable => {
+                    eprintln!("Warning: Workspace is locked by another instance, opening read-only")
+                }
+                Ok(_) => {}
+                Err(e) => eprintln!("Warning: Cannot lock workspace: {}", e),
+            }
+            // Give new installations the starter rules
+            if workspace_lock::ensure_writable(app.handle()).is_ok() {
+                if let Err(e) = default_rules::install_on_first_run(app.handle()) {
+                    eprintln!("Warning: Cannot install default rules: {}", e);
+                }
+            }
+            // Make lookup lists and imported IOC lists available to IN_LIST conditions
+            match lookup_lists::get_lookup_lists_dir(app.handle()) {
+                Ok(dir) => term_sets::register_list_dir(term_sets::LOOKUP_LISTS, dir),
+                Err(e) => eprintln!("Warning: Cannot open lookup lists: {}", e),
+            }
+            match intel::get_intel_dir(app.handle()) {
+                Ok(dir) => term_sets::register_list_dir(term_sets::IOC_LISTS, dir),
+                Err(e) => eprintln!("Warning: Cannot open IOC lists: {}", e),
+            }
+            // Relative IN_FILE / CONTAINS_FILE paths are taken from the rules directory
+            match config::get_rules_directory(app.handle()) {
+                Ok(dir) => term_sets::register_rules_dir(dir),
+                Err(e) => eprintln!("Warning: Cannot open rules directory: {}", e),
+            }
+            // Read custom log formats through the installed parser plugins
+            match parser_plugins::get_plugins_dir(app.handle()) {
+                Ok(dir) => parser_plugins::register_plugin_dir(dir),
+                Err(e) => eprintln!("Warning: Cannot open parser plugins: {}", e),
+            }
+            // Read text logs through the configured text parsers
+            match text_parser::get_parsers_path(app.handle()) {
+                Ok(path) => text_parser::register_parsers_file(path),
+                Err(e) => eprintln!("Warning: Cannot open text parsers: {}", e),
+            }
+            // Values already seen by first-seen rules
+            if let Err(e) = first_seen::open(app.handle()) {
+                eprintln!("Warning: Cannot load first-seen baseline: {}", e);
+            }
+            // Open the configured GeoIP databases for enrichment and conditions
+            if let Err(e) = config::load_config(app.handle()).and_then(|c| geoip::configure(&c)) {
+                eprintln!("Warning: Cannot load GeoIP databases: {}", e);
+            }
+            // Scan log files dropped into the watched folder, if enabled
+            let watch_on_startup = config::load_config(app.handle())
+                .map(|config| config.watch_on_startup)
+                .unwrap_or(false);
+            if watch_on_startup && workspace_lock::ensure_writable(app.handle()).is_ok() {
+                let started = watch_directory(app.handle(), None).and_then(|directory| {
+                    folder_watch::start(app.handle().clone(), directory, scan_background_file)
+                });
+                if let Err(e) = started {
+                    eprintln!("Warning: Cannot start folder watch: {}", e);
+                }
+            }
+            // Serve the local REST API, if enabled
+            let api_enabled = config::load_config(app.handle())
+                .map(|config| config.api_enabled)
+                .unwrap_or(false);
+            if api_enabled {
+                if let Err(e) = start_rest_api_server(app.handle(), None) {
+                    eprintln!("Warning: Cannot start REST API: {}", e);
+                }
+            }
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
             // Rule management
             list_rules,
             get_rule,
             save_rule, // Kept original save_rule
+            search_rules,
+            duplicate_rule,
+            set_rules_status,
+            add_rule_filter,
+            add_alert_filter,
             delete_rule,
             export_rule,
             export_all_rules,
             import_rule,
             import_rules_zip,
             import_multiple_rules,
+            export_rule_pack,
+            import_rule_pack,
+            install_default_rules,
             // Scanning
             scan_logs,
             scan_all_logs,
+            scan_log_files,
+            enqueue_scan,
+            get_job_status,
+            list_jobs,
+            cancel_job,
+            start_folder_watch,
+            stop_folder_watch,
+            get_folder_watch_status,
+            start_rest_api,
+            stop_rest_api,
+            get_rest_api_status,
+            get_alert_trends,
+            list_scan_history,
+            diff_scans,
+            set_alert_triage,
+            list_alert_triage,
+            get_audit_log,
+            get_rule_statistics,
+            list_first_seen_fields,
+            reset_first_seen,
+            get_attack_coverage,
             // Ad-hoc queries
             run_query,
+            run_query_paged,
+            cancel_query,
+            // Saved queries
+            list_saved_queries,
+            save_query,
+            tag_saved_query,
+            delete_saved_query,
+            run_query_template,
             load_log_events,
+            load_log_events_page,
+            ingest_log_file,
+            list_ingested_sources,
+            remove_ingested_source,
+            register_log_view,
+            list_log_views,
+            drop_log_view,
             validate_log_file,
+            export_dataset,
+            analyze_log_integrity,
+            get_event_timeline,
+            get_field_stats,
+            find_rare_values,
+            get_entity_summary,
             // Rule Testing
             test_rule,
+            test_rule_inline,
+            profile_rules,
             validate_condition,
             get_field_suggestions,
+            get_value_suggestions,
+            get_log_schema,
+            list_parser_plugins,
+            list_text_parsers,
+            save_text_parser,
+            delete_text_parser,
+            test_text_parser,
             // Log File Management
             list_log_files,
             import_log_file,
             import_multiple_log_files,
+            import_triage_collection,
+            register_log_file,
             delete_log_file,
+            verify_log_integrity,
             update_log_type,
+            grep_logs,
+            // Alert Export
+            list_export_sinks,
+            export_alerts,
+            export_alerts_misp,
+            extract_iocs,
+            export_scan_report,
+            // Hash Sets
+            import_hash_set,
+            list_hash_sets,
+            delete_hash_set,
+            import_ioc_list,
+            list_ioc_lists,
+            sweep_iocs,
+            delete_ioc_list,
+            list_lookup_lists,
+            get_lookup_list,
+            save_lookup_list,
+            delete_lookup_list,
+            // Event Annotations
+            flag_event,
+            unflag_event,
+            list_flagged_events,
+            // Suppressions
+            add_suppression,
+            remove_suppression,
+            list_suppressions,
+            // Case Management
+            create_case,
+            list_cases,
+            get_case,
+            delete_case,
+            set_case_status,
+            attach_alerts_to_case,
+            attach_event_to_case,
+            attach_log_file_to_case,
+            add_case_note,
+            export_case_bundle,
+            export_evidence_bundle,
             // Configuration Management
             get_config,
             save_config,
@@ -570,6 +2901,12 @@ pub fn run() {
             add_recent_log_file,
             clear_recent_files,
             get_rules_directory,
+            // Workspace Snapshots
+            backup_app_data,
+            restore_app_data,
+            create_workspace_snapshot,
+            restore_workspace_snapshot,
+            get_workspace_lock,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
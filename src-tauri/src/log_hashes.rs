@@ -0,0 +1,143 @@
+//! SHA-256 hashes of the log library, for chain of custody.
+//!
+//! Where `log_integrity` looks for gaps in the events of a log, this module
+//! checks the file itself: every log file gets its SHA-256 hash recorded when it enters the library
+//! (copied, extracted from an archive, converted from CSV or registered in
+//! place) in `logs/hashes.json`. Converted files are hashed as stored, since
+//! that is what gets scanned. Verifying a file re-hashes it and compares
+//! against the record, flagging any change since import.
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fs;
+use std::io::Read;
+use std::path::Path;
+
+use crate::app_data;
+use crate::log_manager;
+use crate::models::{HashVerification, SiemError};
+
+/// Integrity records of the library (filename -> record), in the logs directory.
+pub const HASHES_FILE: &str = "hashes.json";
+
+/// Hash of a log file when it entered the library.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct HashRecord {
+    pub sha256: String,
+    /// When the hash was computed (ISO 8601)
+    pub hashed_at: String,
+}
+
+/// Recorded hashes of the library, by filename.
+pub fn load_records(app_handle: &tauri::AppHandle) -> HashMap<String, HashRecord> {
+    log_manager::get_logs_dir(app_handle)
+        .ok()
+        .and_then(|dir| fs::read_to_string(dir.join(HASHES_FILE)).ok())
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+fn save_records(
+    app_handle: &tauri::AppHandle,
+    records: &HashMap<String, HashRecord>,
+) -> Result<(), SiemError> {
+    let path = log_manager::get_logs_dir(app_handle)?.join(HASHES_FILE);
+    let content = serde_json::to_string_pretty(records).map_err(|e| {
+        SiemError::Serialization(format!("Cannot serialize integrity records: {}", e))
+    })?;
+    fs::write(&path, content)
+        .map_err(|e| SiemError::FileIO(format!("Cannot write integrity records: {}", e)))
+}
+
+/// SHA-256 of a file as lowercase hex, read in blocks.
+pub fn hash_file(path: &Path) -> Result<String, SiemError> {
+    let mut file = fs::File::open(path)
+        .map_err(|e| SiemError::FileIO(format!("Cannot open {:?}: {}", path, e)))?;
+    let mut hasher = Sha256::new();
+    let mut buf = vec![0u8; 1 << 20];
+    loop {
+        let read = file
+            .read(&mut buf)
+            .map_err(|e| SiemError::FileIO(format!("Cannot read {:?}: {}", path, e)))?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buf[..read]);
+    }
+    Ok(app_data::hex(&hasher.finalize()))
+}
+
+/// Hash a file entering the library and record it. Returns the hash.
+pub fn record_hash(
+    app_handle: &tauri::AppHandle,
+    filename: &str,
+    path: &Path,
+) -> Result<String, SiemError> {
+    let sha256 = hash_file(path)?;
+    let mut records = load_records(app_handle);
+    records.insert(
+        filename.to_string(),
+        HashRecord {
+            sha256: sha256.clone(),
+            hashed_at: chrono::Utc::now().to_rfc3339(),
+        },
+    );
+    save_records(app_handle, &records)?;
+    Ok(sha256)
+}
+
+/// Recorded hashes of the library (filename -> SHA-256).
+pub fn recorded_hashes(app_handle: &tauri::AppHandle) -> HashMap<String, String> {
+    load_records(app_handle)
+        .into_iter()
+        .map(|(filename, record)| (filename, record.sha256))
+        .collect()
+}
+
+/// Forget the record of a file leaving the library.
+pub fn forget(app_handle: &tauri::AppHandle, filename: &str) -> Result<(), SiemError> {
+    let mut records = load_records(app_handle);
+    if records.remove(filename).is_some() {
+        save_records(app_handle, &records)?;
+    }
+    Ok(())
+}
+
+/// Re-hash a library file and compare against its record.
+pub fn verify(
+    app_handle: &tauri::AppHandle,
+    filename: &str,
+) -> Result<HashVerification, SiemError> {
+    let path = log_manager::log_file_path(app_handle, filename)?;
+    let record = load_records(app_handle).remove(filename);
+    let current_sha256 = hash_file(&path)?;
+
+    Ok(HashVerification {
+        filename: filename.to_string(),
+        intact: record
+            .as_ref()
+            .is_some_and(|record| record.sha256 == current_sha256),
+        recorded_sha256: record.as_ref().map(|record| record.sha256.clone()),
+        hashed_at: record.map(|record| record.hashed_at),
+        current_sha256,
+        verified_at: chrono::Utc::now().to_rfc3339(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hash_file() {
+        let path = std::env::temp_dir().join(format!("siem-hash-{}.json", uuid::Uuid::new_v4()));
+        fs::write(&path, "abc").unwrap();
+        assert_eq!(
+            hash_file(&path).unwrap(),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        fs::remove_file(&path).unwrap();
+        assert!(hash_file(&path).is_err());
+    }
+}
@@ -0,0 +1,499 @@
+//! Log gap and tampering analysis.
+//!
+//! Deleted or truncated logs are a finding in themselves. This analytic
+//! checks a log file's timestamp continuity and, where the log has one, its
+//! record sequence field:
+//! - gaps: silent periods much longer than the usual spacing of events
+//! - out-of-order bursts: runs of events older than events before them
+//! - sequence gaps and regressions: missing, repeated or reset record numbers
+//! - truncated coverage: events starting late or ending early compared to
+//!   the expected coverage window
+//! - events without a parseable timestamp
+
+use chrono::{DateTime, Utc};
+use duckdb::Connection;
+use serde::Deserialize;
+
+use crate::db_engine;
+use crate::event_time;
+use crate::models::{IntegrityFinding, IntegrityFindingKind, IntegrityReport, LogType, SiemError};
+
+/// Gaps longer than this are reported as high severity.
+const HIGH_SEVERITY_GAP_SECS: i64 = 86400;
+
+/// Tuning of the integrity analysis.
+#[derive(Debug, Clone, Deserialize)]
+pub struct IntegrityOptions {
+    /// Minimum silent period reported as a gap, in seconds; also the slack
+    /// allowed at the edges of the expected coverage window
+    #[serde(default = "default_min_gap_secs")]
+    pub min_gap_secs: i64,
+    /// A gap must also exceed this multiple of the median event spacing
+    #[serde(default = "default_gap_factor")]
+    pub gap_factor: f64,
+    /// How far an event may lag behind earlier events before it counts as out of order
+    #[serde(default = "default_out_of_order_tolerance_secs")]
+    pub out_of_order_tolerance_secs: i64,
+    /// Numeric record sequence field (e.g. "EventRecordID"), if the log has one
+    #[serde(default)]
+    pub sequence_field: Option<String>,
+    /// Timestamp field (defaults to the log type's standard timestamp field)
+    #[serde(default)]
+    pub timestamp_field: Option<String>,
+    /// Expected start of coverage (ISO 8601)
+    #[serde(default)]
+    pub expected_start: Option<String>,
+    /// Expected end of coverage (ISO 8601)
+    #[serde(default)]
+    pub expected_end: Option<String>,
+}
+
+fn default_min_gap_secs() -> i64 {
+    3600
+}
+
+fn default_gap_factor() -> f64 {
+    20.0
+}
+
+fn default_out_of_order_tolerance_secs() -> i64 {
+    300
+}
+
+impl Default for IntegrityOptions {
+    fn default() -> Self {
+        IntegrityOptions {
+            min_gap_secs: default_min_gap_secs(),
+            gap_factor: default_gap_factor(),
+            out_of_order_tolerance_secs: default_out_of_order_tolerance_secs(),
+            sequence_field: None,
+            timestamp_field: None,
+            expected_start: None,
+            expected_end: None,
+        }
+    }
+}
+
+/// Analyze a log file.
+pub fn analyze_log_file(
+    conn: &Connection,
+    log_path: &str,
+    log_type: LogType,
+    options: &IntegrityOptions,
+) -> Result<IntegrityReport, SiemError> {
+    let events = db_engine::load_all_events(conn, log_path, log_type.clone())?;
+    analyze_events(&events, &log_type, options)
+}
+
+/// Analyze events in file order.
+pub fn analyze_events(
+    events: &[serde_json::Value],
+    log_type: &LogType,
+    options: &IntegrityOptions,
+) -> Result<IntegrityReport, SiemError> {
+    let parse_expected = |value: &Option<String>| -> Result<Option<DateTime<Utc>>, SiemError> {
+        value
+            .as_deref()
+            .map(|s| {
+                event_time::parse_timestamp_str(s)
+                    .ok_or_else(|| SiemError::Query(format!("Invalid timestamp: '{}'", s)))
+            })
+            .transpose()
+    };
+    let expected_start = parse_expected(&options.expected_start)?;
+    let expected_end = parse_expected(&options.expected_end)?;
+
+    // (record index, timestamp) in file order
+    let timeline: Vec<(usize, DateTime<Utc>)> = events
+        .iter()
+        .enumerate()
+        .filter_map(|(index, event)| {
+            event_time::event_time(event, options.timestamp_field.as_deref(), log_type)
+                .map(|ts| (index, ts))
+        })
+        .collect();
+
+    let mut findings = Vec::new();
+
+    let missing = events.len() - timeline.len();
+    if missing > 0 {
+        findings.push(IntegrityFinding {
+            kind: IntegrityFindingKind::MissingTimestamps,
+            severity: "low".to_string(),
+            message: format!("{} event(s) have no parseable timestamp", missing),
+            start: None,
+            end: None,
+            record_index: None,
+            event_count: missing,
+        });
+    }
+
+    let mut sorted: Vec<DateTime<Utc>> = timeline.iter().map(|(_, ts)| *ts).collect();
+    sorted.sort();
+    let median_interval_secs = median_interval(&sorted);
+
+    findings.extend(find_gaps(&sorted, median_interval_secs, options));
+    findings.extend(find_out_of_order(&timeline, options));
+    if let Some(field) = &options.sequence_field {
+        findings.extend(find_sequence_breaks(events, field));
+    }
+    findings.extend(find_truncation(
+        sorted.first().copied(),
+        sorted.last().copied(),
+        expected_start,
+        expected_end,
+        options,
+    ));
+
+    Ok(IntegrityReport {
+        event_count: events.len(),
+        timestamped_events: timeline.len(),
+        first_event: sorted.first().map(|ts| ts.to_rfc3339()),
+        last_event: sorted.last().map(|ts| ts.to_rfc3339()),
+        median_interval_secs,
+        findings,
+    })
+}
+
+/// Median spacing between consecutive (sorted) timestamps, in seconds.
+fn median_interval(sorted: &[DateTime<Utc>]) -> Option<f64> {
+    let mut intervals: Vec<i64> = sorted
+        .windows(2)
+        .map(|pair| (pair[1] - pair[0]).num_seconds())
+        .collect();
+    if intervals.is_empty() {
+        return None;
+    }
+
+    intervals.sort_unstable();
+    let mid = intervals.len() / 2;
+    Some(if intervals.len().is_multiple_of(2) {
+        (intervals[mid - 1] + intervals[mid]) as f64 / 2.0
+    } else {
+        intervals[mid] as f64
+    })
+}
+
+/// Silent periods longer than both the minimum gap and `gap_factor` x the median spacing.
+fn find_gaps(
+    sorted: &[DateTime<Utc>],
+    median_interval_secs: Option<f64>,
+    options: &IntegrityOptions,
+) -> Vec<IntegrityFinding> {
+    let relative = median_interval_secs.unwrap_or(0.0) * options.gap_factor;
+    let threshold = (options.min_gap_secs as f64).max(relative);
+
+    sorted
+        .windows(2)
+        .filter_map(|pair| {
+            let secs = (pair[1] - pair[0]).num_seconds();
+            if (secs as f64) <= threshold {
+                return None;
+            }
+            Some(IntegrityFinding {
+                kind: IntegrityFindingKind::Gap,
+                severity: if secs > HIGH_SEVERITY_GAP_SECS {
+                    "high"
+                } else {
+                    "medium"
+                }
+                .to_string(),
+                message: format!("No events for {}", format_duration(secs)),
+                start: Some(pair[0].to_rfc3339()),
+                end: Some(pair[1].to_rfc3339()),
+                record_index: None,
+                event_count: 0,
+            })
+        })
+        .collect()
+}
+
+/// Runs of consecutive events lagging behind the latest earlier event.
+fn find_out_of_order(
+    timeline: &[(usize, DateTime<Utc>)],
+    options: &IntegrityOptions,
+) -> Vec<IntegrityFinding> {
+    let mut findings = Vec::new();
+    let mut latest: Option<DateTime<Utc>> = None;
+    // (first record index, oldest timestamp, event count, max lag)
+    let mut burst: Option<(usize, DateTime<Utc>, usize, i64)> = None;
+
+    let close = |burst: (usize, DateTime<Utc>, usize, i64), latest: DateTime<Utc>| {
+        let (index, oldest, count, lag) = burst;
+        IntegrityFinding {
+            kind: IntegrityFindingKind::OutOfOrder,
+            severity: "medium".to_string(),
+            message: format!(
+                "{} event(s) up to {} older than preceding events",
+                count,
+                format_duration(lag)
+            ),
+            start: Some(oldest.to_rfc3339()),
+            end: Some(latest.to_rfc3339()),
+            record_index: Some(index),
+            event_count: count,
+        }
+    };
+
+    for &(index, ts) in timeline {
+        let lag = latest.map(|l| (l - ts).num_seconds()).unwrap_or(0);
+        if lag > options.out_of_order_tolerance_secs {
+            burst = Some(match burst {
+                Some((first, oldest, count, max_lag)) => {
+                    (first, oldest.min(ts), count + 1, max_lag.max(lag))
+                }
+                None => (index, ts, 1, lag),
+            });
+            continue;
+        }
+
+        if let (Some(b), Some(l)) = (burst.take(), latest) {
+            findings.push(close(b, l));
+        }
+        latest = Some(latest.map_or(ts, |l| l.max(ts)));
+    }
+
+    if let (Some(b), Some(l)) = (burst, latest) {
+        findings.push(close(b, l));
+    }
+
+    findings
+}
+
+/// Missing, repeated or reset record numbers.
+fn find_sequence_breaks(events: &[serde_json::Value], field: &str) -> Vec<IntegrityFinding> {
+    let mut findings = Vec::new();
+    let mut previous: Option<i64> = None;
+
+    for (index, event) in events.iter().enumerate() {
+        let Some(current) = db_engine::get_field_values(event, field)
+            .first()
+            .and_then(|v| v.trim().parse::<i64>().ok())
+        else {
+            continue;
+        };
+
+        if let Some(prev) = previous {
+            if current > prev + 1 {
+                let missing = current - prev - 1;
+                findings.push(IntegrityFinding {
+                    kind: IntegrityFindingKind::SequenceGap,
+                    severity: "high".to_string(),
+                    message: format!(
+                        "{} record(s) missing between {} {} and {}",
+                        missing, field, prev, current
+                    ),
+                    start: None,
+                    end: None,
+                    record_index: Some(index),
+                    event_count: missing as usize,
+                });
+            } else if current <= prev {
+                findings.push(IntegrityFinding {
+                    kind: IntegrityFindingKind::SequenceRegression,
+                    severity: "medium".to_string(),
+                    message: format!("{} went from {} back to {}", field, prev, current),
+                    start: None,
+                    end: None,
+                    record_index: Some(index),
+                    event_count: 1,
+                });
+            }
+        }
+        previous = Some(current);
+    }
+
+    findings
+}
+
+/// Coverage starting late or ending early compared to the expected window.
+fn find_truncation(
+    first: Option<DateTime<Utc>>,
+    last: Option<DateTime<Utc>>,
+    expected_start: Option<DateTime<Utc>>,
+    expected_end: Option<DateTime<Utc>>,
+    options: &IntegrityOptions,
+) -> Vec<IntegrityFinding> {
+    let mut findings = Vec::new();
+
+    if let Some(expected) = expected_start {
+        let actual = first.unwrap_or(expected_end.unwrap_or(expected));
+        let secs = (actual - expected).num_seconds();
+        if first.is_none() || secs > options.min_gap_secs {
+            findings.push(IntegrityFinding {
+                kind: IntegrityFindingKind::TruncatedStart,
+                severity: "high".to_string(),
+                message: match first {
+                    Some(_) => format!("Coverage starts {} late", format_duration(secs)),
+                    None => "No timestamped events in the expected window".to_string(),
+                },
+                start: Some(expected.to_rfc3339()),
+                end: first.map(|ts| ts.to_rfc3339()),
+                record_index: None,
+                event_count: 0,
+            });
+        }
+    }
+
+    if let (Some(expected), Some(last)) = (expected_end, last) {
+        let secs = (expected - last).num_seconds();
+        if secs > options.min_gap_secs {
+            findings.push(IntegrityFinding {
+                kind: IntegrityFindingKind::TruncatedEnd,
+                severity: "high".to_string(),
+                message: format!("Coverage ends {} early", format_duration(secs)),
+                start: Some(last.to_rfc3339()),
+                end: Some(expected.to_rfc3339()),
+                record_index: None,
+                event_count: 0,
+            });
+        }
+    }
+
+    findings
+}
+
+/// Human-readable duration such as "2h 5m".
+fn format_duration(secs: i64) -> String {
+    let (days, rest) = (secs / 86400, secs % 86400);
+    let (hours, rest) = (rest / 3600, rest % 3600);
+    let (minutes, seconds) = (rest / 60, rest % 60);
+
+    let parts: Vec<String> = [(days, "d"), (hours, "h"), (minutes, "m"), (seconds, "s")]
+        .iter()
+        .filter(|(value, _)| *value > 0)
+        .map(|(value, unit)| format!("{}{}", value, unit))
+        .collect();
+
+    if parts.is_empty() {
+        "0s".to_string()
+    } else {
+        parts.join(" ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn events(times: &[&str]) -> Vec<serde_json::Value> {
+        times
+            .iter()
+            .map(|t| serde_json::json!({ "eventTime": t }))
+            .collect()
+    }
+
+    fn kinds(report: &IntegrityReport) -> Vec<IntegrityFindingKind> {
+        report.findings.iter().map(|f| f.kind).collect()
+    }
+
+    #[test]
+    fn test_continuous_log_is_clean() {
+        let events = events(&[
+            "2025-12-16T11:00:00Z",
+            "2025-12-16T11:01:00Z",
+            "2025-12-16T11:02:30Z",
+            "2025-12-16T11:03:00Z",
+        ]);
+        let report =
+            analyze_events(&events, &LogType::CloudTrail, &IntegrityOptions::default()).unwrap();
+
+        assert!(report.findings.is_empty());
+        assert_eq!(report.timestamped_events, 4);
+        assert_eq!(report.median_interval_secs, Some(60.0));
+    }
+
+    #[test]
+    fn test_gap_and_missing_timestamps() {
+        let mut events = events(&[
+            "2025-12-16T08:00:00Z",
+            "2025-12-16T08:01:00Z",
+            "2025-12-16T08:02:00Z",
+            "2025-12-16T14:00:00Z",
+        ]);
+        events.push(serde_json::json!({ "eventName": "NoTime" }));
+
+        let report =
+            analyze_events(&events, &LogType::CloudTrail, &IntegrityOptions::default()).unwrap();
+
+        assert_eq!(
+            kinds(&report),
+            vec![
+                IntegrityFindingKind::MissingTimestamps,
+                IntegrityFindingKind::Gap
+            ]
+        );
+        let gap = &report.findings[1];
+        assert_eq!(gap.severity, "medium");
+        assert_eq!(gap.message, "No events for 5h 58m");
+    }
+
+    #[test]
+    fn test_out_of_order_burst() {
+        let events = events(&[
+            "2025-12-16T11:00:00Z",
+            "2025-12-16T11:30:00Z",
+            "2025-12-16T11:05:00Z",
+            "2025-12-16T11:06:00Z",
+            "2025-12-16T11:31:00Z",
+            // Within the tolerance
+            "2025-12-16T11:29:00Z",
+        ]);
+        let options = IntegrityOptions {
+            min_gap_secs: 86400,
+            ..Default::default()
+        };
+
+        let report = analyze_events(&events, &LogType::CloudTrail, &options).unwrap();
+
+        assert_eq!(kinds(&report), vec![IntegrityFindingKind::OutOfOrder]);
+        let burst = &report.findings[0];
+        assert_eq!(burst.record_index, Some(2));
+        assert_eq!(burst.event_count, 2);
+        assert_eq!(
+            burst.message,
+            "2 event(s) up to 25m older than preceding events"
+        );
+    }
+
+    #[test]
+    fn test_sequence_breaks() {
+        let events: Vec<serde_json::Value> = [1, 2, 5, 6, 6, 7]
+            .iter()
+            .map(|n| serde_json::json!({ "EventRecordID": n }))
+            .collect();
+        let options = IntegrityOptions {
+            sequence_field: Some("EventRecordID".to_string()),
+            ..Default::default()
+        };
+
+        let report = analyze_events(&events, &LogType::FlatJson, &options).unwrap();
+        let breaks: Vec<&IntegrityFinding> = report
+            .findings
+            .iter()
+            .filter(|f| f.kind != IntegrityFindingKind::MissingTimestamps)
+            .collect();
+
+        assert_eq!(breaks.len(), 2);
+        assert_eq!(breaks[0].kind, IntegrityFindingKind::SequenceGap);
+        assert_eq!(breaks[0].event_count, 2);
+        assert_eq!(breaks[1].kind, IntegrityFindingKind::SequenceRegression);
+        assert_eq!(breaks[1].record_index, Some(4));
+    }
+
+    #[test]
+    fn test_truncated_coverage() {
+        let events = events(&["2025-12-16T11:00:00Z", "2025-12-16T11:10:00Z"]);
+        let options = IntegrityOptions {
+            expected_start: Some("2025-12-16T00:00:00Z".to_string()),
+            expected_end: Some("2025-12-16T11:30:00Z".to_string()),
+            ..Default::default()
+        };
+
+        let report = analyze_events(&events, &LogType::CloudTrail, &options).unwrap();
+
+        // 11h late start is reported, the 20m early end is within the slack
+        assert_eq!(kinds(&report), vec![IntegrityFindingKind::TruncatedStart]);
+        assert_eq!(report.findings[0].message, "Coverage starts 11h late");
+    }
+}
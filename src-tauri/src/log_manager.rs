@@ -2,36 +2,34 @@
 //!
 //! This module provides functionality to:
 //! - List all JSON log files in the monitored logs directory
-//! - Import external log files by copying them to the monitored folder
+//! - Import external log files by copying them to the monitored folder, or
+//!   the log files of a ZIP archive by extracting them
+//! - Register external log files in place, without copying them
 //! - Delete log files from the monitored folder
-//! - Get metadata about log files (size, modified date, event count)
+//! - Get metadata about log files (size, modified date, event count, SHA-256
+//!   recorded on import, see `log_hashes`)
 
 use std::collections::HashMap;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::time::SystemTime;
 
+use crate::app_data;
+use crate::evtx_binary;
+use crate::log_hashes;
 use crate::models::{ImportSummary, LogFileInfo, LogType, SiemError};
-use tauri::Manager;
+use crate::text_parser;
+use crate::triage_import;
 
 /// Get the directory path where log files are stored.
 /// Creates the directory if it doesn't exist.
 pub fn get_logs_dir(app_handle: &tauri::AppHandle) -> Result<PathBuf, SiemError> {
-    let app_data_dir = app_handle
-        .path()
-        .app_data_dir()
-        .map_err(|e| SiemError::FileIO(format!("Cannot get app data dir: {}", e)))?;
-
-    let logs_dir = app_data_dir.join("logs");
-
-    if !logs_dir.exists() {
-        fs::create_dir_all(&logs_dir)
-            .map_err(|e| SiemError::FileIO(format!("Cannot create logs dir: {}", e)))?;
-    }
-
-    Ok(logs_dir)
+    app_data::app_data_subdir(app_handle, "logs")
 }
 
+/// Log files registered in place (filename -> path), in the logs directory.
+const REFERENCES_FILE: &str = "references.json";
+
 /// Get the path to the metadata file.
 fn get_metadata_path(app_handle: &tauri::AppHandle) -> Result<PathBuf, SiemError> {
     let logs_dir = get_logs_dir(app_handle)?;
@@ -55,6 +53,27 @@ fn load_metadata(app_handle: &tauri::AppHandle) -> HashMap<String, LogType> {
     }
 }
 
+/// Load the log files registered in place.
+fn load_references(app_handle: &tauri::AppHandle) -> HashMap<String, String> {
+    get_logs_dir(app_handle)
+        .ok()
+        .and_then(|dir| fs::read_to_string(dir.join(REFERENCES_FILE)).ok())
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+/// Save the log files registered in place.
+fn save_references(
+    app_handle: &tauri::AppHandle,
+    references: &HashMap<String, String>,
+) -> Result<(), SiemError> {
+    let path = get_logs_dir(app_handle)?.join(REFERENCES_FILE);
+    let content = serde_json::to_string_pretty(references)
+        .map_err(|e| SiemError::Serialization(format!("Cannot serialize references: {}", e)))?;
+    fs::write(&path, content)
+        .map_err(|e| SiemError::FileIO(format!("Cannot write references: {}", e)))
+}
+
 /// Save metadata to file.
 fn save_metadata(
     app_handle: &tauri::AppHandle,
@@ -70,6 +89,12 @@ fn save_metadata(
     Ok(())
 }
 
+/// Whether a file of the logs directory is the library's own bookkeeping
+/// rather than a log file.
+pub fn is_system_file(filename: &str) -> bool {
+    ["metadata.json", REFERENCES_FILE, log_hashes::HASHES_FILE].contains(&filename)
+}
+
 /// Set log type for a specific file.
 pub fn set_log_type(
     app_handle: &tauri::AppHandle,
@@ -82,10 +107,29 @@ pub fn set_log_type(
     Ok(())
 }
 
-/// Get log type for a specific file.
-pub fn get_log_type(app_handle: &tauri::AppHandle, filename: &str) -> Option<LogType> {
-    let metadata = load_metadata(app_handle);
-    metadata.get(filename).cloned()
+/// Get the log type recorded for a log file given by path. Metadata is keyed
+/// by library filename, so only a file stored in the logs directory or
+/// registered in place has one; another file that merely shares its name
+/// doesn't inherit it.
+pub fn get_log_type(app_handle: &tauri::AppHandle, path: &str) -> Option<LogType> {
+    let filename = library_filename(app_handle, Path::new(path))?;
+    load_metadata(app_handle).get(&filename).cloned()
+}
+
+/// Library filename of the log file stored at `path`, if it is one.
+fn library_filename(app_handle: &tauri::AppHandle, path: &Path) -> Option<String> {
+    let canonical = |p: &Path| fs::canonicalize(p).unwrap_or_else(|_| p.to_path_buf());
+    let path = canonical(path);
+    let logs_dir = canonical(&get_logs_dir(app_handle).ok()?);
+    if path.parent() == Some(logs_dir.as_path()) {
+        return path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string());
+    }
+    load_references(app_handle)
+        .into_iter()
+        .find(|(_, referenced)| canonical(Path::new(referenced)) == path)
+        .map(|(filename, _)| filename)
 }
 
 /// List all JSON log files in the monitored folder.
@@ -110,8 +154,14 @@ pub fn list_log_files(app_handle: &tauri::AppHandle) -> Result<Vec<LogFileInfo>,
         // Get filename for checking
         let filename = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
 
-        // Only include .json files, but exclude metadata.json (system file)
-        if path.extension().map_or(false, |ext| ext == "json") && filename != "metadata.json" {
+        // Only include .json files, but exclude metadata.json (system file),
+        // and other formats (key=value, parser plugins, text parsers)
+        let is_json = path.extension().map_or(false, |ext| ext == "json");
+        let is_other_log = metadata
+            .get(filename)
+            .is_some_and(|log_type| !log_type.is_json())
+            || text_parser::log_type_for_file(filename).is_some();
+        if (is_json && !is_system_file(filename)) || is_other_log {
             match get_log_file_info_with_metadata(&path, &metadata) {
                 Ok(info) => log_files.push(info),
                 Err(e) => {
@@ -122,19 +172,40 @@ pub fn list_log_files(app_handle: &tauri::AppHandle) -> Result<Vec<LogFileInfo>,
         }
     }
 
+    // Files registered in place are read from where they are
+    for path in load_references(app_handle).into_values() {
+        let path = PathBuf::from(path);
+        match get_log_file_info_with_metadata(&path, &metadata) {
+            Ok(info) => log_files.push(LogFileInfo {
+                referenced: true,
+                ..info
+            }),
+            Err(e) => eprintln!(
+                "Warning: Registered log file {:?} is unavailable: {}",
+                path, e
+            ),
+        }
+    }
+
+    let hashes = log_hashes::recorded_hashes(app_handle);
+    for info in &mut log_files {
+        info.sha256 = hashes.get(&info.filename).cloned();
+    }
+
     // Sort by filename for consistent ordering
     log_files.sort_by(|a, b| a.filename.cmp(&b.filename));
 
     Ok(log_files)
 }
 
-/// Import an external log file by copying it to the monitored folder.
-/// Returns the new LogFileInfo for the imported file.
+/// Import an external log file by copying it to the monitored folder, or the
+/// log files of a ZIP archive (see `import_zip_archive`).
+/// Returns the new LogFileInfo of each imported file.
 pub fn import_log_file(
     app_handle: &tauri::AppHandle,
     source_path: &str,
     log_type: LogType,
-) -> Result<LogFileInfo, SiemError> {
+) -> Result<Vec<LogFileInfo>, SiemError> {
     let source = PathBuf::from(source_path);
 
     // Validate source file exists
@@ -145,10 +216,18 @@ pub fn import_log_file(
         )));
     }
 
-    // Validate it's a JSON file
-    if !source.extension().map_or(false, |ext| ext == "json") {
+    if source
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("zip"))
+    {
+        return import_zip_archive(app_handle, &source, log_type);
+    }
+
+    // Validate it's a JSON file, unless it is imported as another format
+    if log_type.is_json() && !source.extension().map_or(false, |ext| ext == "json") {
         return Err(SiemError::FileIO(
-            "Only JSON files can be imported".to_string(),
+            "Only JSON files can be imported as JSON (choose key=value, a text parser or a parser plugin for other formats)"
+                .to_string(),
         ));
     }
 
@@ -163,8 +242,8 @@ pub fn import_log_file(
 
     let dest_path = logs_dir.join(&filename);
 
-    // Check if file already exists
-    if dest_path.exists() {
+    // Check if file already exists (or one registered in place has its name)
+    if dest_path.exists() || load_references(app_handle).contains_key(&filename) {
         return Err(SiemError::FileIO(format!(
             "File already exists in logs folder: {}",
             filename
@@ -178,12 +257,286 @@ pub fn import_log_file(
     // Save log type to metadata
     set_log_type(app_handle, &filename, log_type.clone())?;
 
-    // Return info about the newly imported file with log type
+    // Return info about the newly imported file with log type and hash
     let mut info = get_log_file_info(&dest_path)?;
     info.log_type = Some(log_type);
+    info.sha256 = Some(log_hashes::record_hash(app_handle, &filename, &dest_path)?);
+    Ok(vec![info])
+}
+
+/// Register an external log file in place: it is listed and scanned where it
+/// is, without a copy in the logs folder (for evidence too large to
+/// duplicate). Deleting it from the library only forgets it.
+pub fn register_log_file(
+    app_handle: &tauri::AppHandle,
+    source_path: &str,
+    log_type: LogType,
+) -> Result<LogFileInfo, SiemError> {
+    let source = PathBuf::from(source_path);
+    if !source.is_file() {
+        return Err(SiemError::FileIO(format!(
+            "Source file does not exist: {}",
+            source_path
+        )));
+    }
+    if source
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("zip"))
+    {
+        return Err(SiemError::FileIO(
+            "ZIP archives can't be read in place: import them to extract their log files"
+                .to_string(),
+        ));
+    }
+    if log_type.is_json() && !source.extension().map_or(false, |ext| ext == "json") {
+        return Err(SiemError::FileIO(
+            "Only JSON files can be registered as JSON (choose key=value, a text parser or a parser plugin for other formats)"
+                .to_string(),
+        ));
+    }
+
+    let filename = source
+        .file_name()
+        .ok_or_else(|| SiemError::FileIO("Invalid filename".to_string()))?
+        .to_string_lossy()
+        .to_string();
+
+    // Files are known by name: it must not be taken by a copied or registered file
+    let mut references = load_references(app_handle);
+    if references.contains_key(&filename) || get_logs_dir(app_handle)?.join(&filename).exists() {
+        return Err(SiemError::FileIO(format!(
+            "File already exists in logs folder: {}",
+            filename
+        )));
+    }
+    references.insert(filename.clone(), source_path.to_string());
+    save_references(app_handle, &references)?;
+    set_log_type(app_handle, &filename, log_type.clone())?;
+
+    let mut info = get_log_file_info(&source)?;
+    info.log_type = Some(log_type);
+    info.referenced = true;
+    info.sha256 = Some(log_hashes::record_hash(app_handle, &filename, &source)?);
     Ok(info)
 }
 
+/// Log files found in ZIP archives, by extension.
+#[derive(Clone, Copy, PartialEq)]
+enum ArchivedLog {
+    Json,
+    Csv,
+    Xml,
+    Evtx,
+}
+
+fn archived_log(name: &str) -> Option<ArchivedLog> {
+    let (_, extension) = name.rsplit_once('.')?;
+    match extension.to_lowercase().as_str() {
+        "json" | "jsonl" => Some(ArchivedLog::Json),
+        "csv" => Some(ArchivedLog::Csv),
+        "xml" => Some(ArchivedLog::Xml),
+        "evtx" => Some(ArchivedLog::Evtx),
+        _ => None,
+    }
+}
+
+/// A filename not taken yet: `name`, else `stem_2.ext`, `stem_3.ext`, ...
+fn unique_filename(taken: impl Fn(&str) -> bool, name: &str) -> String {
+    let (stem, extension) = name.rsplit_once('.').unwrap_or((name, ""));
+    let mut filename = name.to_string();
+    let mut n = 1;
+    while taken(&filename) {
+        n += 1;
+        filename = format!("{}_{}.{}", stem, n, extension);
+    }
+    filename
+}
+
+/// Folder of the app data dir where archives are extracted before their log
+/// files are moved into the logs folder.
+const IMPORT_STAGING_DIR: &str = "import_staging";
+
+/// A log file extracted from an archive into the staging folder.
+struct StagedLog {
+    filename: String,
+    log_type: LogType,
+}
+
+/// Extract the log files of an archive into `staging`, under names taken
+/// neither in the logs folder nor by a registered file. Entry names are
+/// reduced to a safe file name, and entries escaping the archive rejected.
+fn extract_archive(
+    archive: &mut zip::ZipArchive<fs::File>,
+    staging: &Path,
+    logs_dir: &Path,
+    references: &HashMap<String, String>,
+    log_type: &LogType,
+) -> Result<Vec<StagedLog>, SiemError> {
+    use std::io::{BufReader, BufWriter, Write};
+
+    let mut staged = Vec::new();
+    for i in 0..archive.len() {
+        let mut entry = archive
+            .by_index(i)
+            .map_err(|e| SiemError::FileIO(format!("Cannot read ZIP entry: {}", e)))?;
+        let entry_name = entry.name().to_string();
+        if entry.is_dir() || entry_name.starts_with("__MACOSX/") {
+            continue;
+        }
+        let Some(path) = entry.enclosed_name().map(|p| p.to_path_buf()) else {
+            return Err(SiemError::FileIO(format!(
+                "Unsafe path in archive: {}",
+                entry_name
+            )));
+        };
+        // Backslashes aren't separators in ZIP names, but are on Windows
+        let Some(name) = path
+            .file_name()
+            .map(|name| triage_import::file_safe(&name.to_string_lossy()))
+        else {
+            continue;
+        };
+        let Some(kind) = archived_log(&name) else {
+            continue;
+        };
+        let stem = name
+            .rsplit_once('.')
+            .map_or(name.as_str(), |(stem, _)| stem);
+
+        // Converted and NDJSON files are stored as .json to be listed as JSON
+        let (filename, entry_type) = match kind {
+            ArchivedLog::Json if log_type.is_json() => (format!("{}.json", stem), log_type.clone()),
+            ArchivedLog::Json | ArchivedLog::Csv => (format!("{}.json", stem), LogType::FlatJson),
+            ArchivedLog::Xml => (name.clone(), LogType::WindowsXml),
+            ArchivedLog::Evtx => (format!("{}.json", stem), LogType::Windows),
+        };
+        let filename = unique_filename(
+            |name| {
+                logs_dir.join(name).exists()
+                    || staging.join(name).exists()
+                    || references.contains_key(name)
+            },
+            &filename,
+        );
+        let dest_path = staging.join(&filename);
+
+        let file = fs::File::create(&dest_path)
+            .map_err(|e| SiemError::FileIO(format!("Cannot create log file: {}", e)))?;
+        let mut writer = BufWriter::new(file);
+        let extract_error =
+            |e: std::io::Error| SiemError::FileIO(format!("Cannot extract {}: {}", entry_name, e));
+        match kind {
+            ArchivedLog::Csv => {
+                triage_import::csv_to_ndjson(BufReader::new(&mut entry), &mut writer)?;
+            }
+            ArchivedLog::Evtx => {
+                // EVTX is read by seeking, which ZIP entries can't do
+                let raw_path = staging.join(format!("{}.evtx.part", filename));
+                let mut raw = fs::File::create(&raw_path).map_err(extract_error)?;
+                std::io::copy(&mut entry, &mut raw).map_err(extract_error)?;
+                drop(raw);
+                let converted = evtx_binary::to_ndjson(&raw_path, &mut writer);
+                let _ = fs::remove_file(&raw_path);
+                converted.map_err(|e| {
+                    SiemError::FileIO(format!("Cannot convert {}: {}", entry_name, e))
+                })?;
+            }
+            ArchivedLog::Json | ArchivedLog::Xml => {
+                std::io::copy(&mut entry, &mut writer)
+                    .and_then(|_| writer.flush())
+                    .map_err(extract_error)?;
+            }
+        }
+
+        staged.push(StagedLog {
+            filename,
+            log_type: entry_type,
+        });
+    }
+    Ok(staged)
+}
+
+/// Move staged log files into the logs folder and record their type and
+/// hash. On failure, the files already moved are removed again.
+fn commit_staged(
+    app_handle: &tauri::AppHandle,
+    staging: &Path,
+    logs_dir: &Path,
+    staged: Vec<StagedLog>,
+) -> Result<Vec<LogFileInfo>, SiemError> {
+    let mut moved: Vec<&StagedLog> = Vec::new();
+    let result = staged.iter().try_fold(Vec::new(), |mut imported, log| {
+        let dest_path = logs_dir.join(&log.filename);
+        fs::rename(staging.join(&log.filename), &dest_path).map_err(|e| {
+            SiemError::FileIO(format!(
+                "Cannot move {} into the logs folder: {}",
+                log.filename, e
+            ))
+        })?;
+        moved.push(log);
+
+        set_log_type(app_handle, &log.filename, log.log_type.clone())?;
+        let mut info = get_log_file_info(&dest_path)?;
+        info.log_type = Some(log.log_type.clone());
+        info.sha256 = Some(log_hashes::record_hash(
+            app_handle,
+            &log.filename,
+            &dest_path,
+        )?);
+        imported.push(info);
+        Ok(imported)
+    });
+
+    if result.is_err() {
+        for log in moved {
+            let _ = fs::remove_file(logs_dir.join(&log.filename));
+            let _ = log_hashes::forget(app_handle, &log.filename);
+        }
+    }
+    result
+}
+
+/// Import the log files of a ZIP archive into the logs folder, under names
+/// not taken yet. JSON files get `log_type` (flat JSON if it isn't a JSON
+/// type), CSV files are converted to NDJSON and imported as flat JSON, XML
+/// files as Windows XML exports and binary EVTX files are converted to
+/// `evtx_dump` JSON and imported as Windows events.
+///
+/// The archive is extracted into a staging folder first, so an import that
+/// fails partway leaves nothing behind in the logs folder.
+fn import_zip_archive(
+    app_handle: &tauri::AppHandle,
+    source: &Path,
+    log_type: LogType,
+) -> Result<Vec<LogFileInfo>, SiemError> {
+    let file = fs::File::open(source)
+        .map_err(|e| SiemError::FileIO(format!("Cannot open ZIP archive: {}", e)))?;
+    let mut archive = zip::ZipArchive::new(file)
+        .map_err(|e| SiemError::FileIO(format!("Cannot read ZIP archive: {}", e)))?;
+    let logs_dir = get_logs_dir(app_handle)?;
+    let references = load_references(app_handle);
+
+    let staging = logs_dir
+        .parent()
+        .unwrap_or(&logs_dir)
+        .join(IMPORT_STAGING_DIR)
+        .join(uuid::Uuid::new_v4().to_string());
+    fs::create_dir_all(&staging)
+        .map_err(|e| SiemError::FileIO(format!("Cannot create staging folder: {}", e)))?;
+
+    let result = extract_archive(&mut archive, &staging, &logs_dir, &references, &log_type)
+        .and_then(|staged| {
+            if staged.is_empty() {
+                return Err(SiemError::FileIO(
+                    "No JSON, CSV, XML or EVTX log files found in the archive".to_string(),
+                ));
+            }
+            commit_staged(app_handle, &staging, &logs_dir, staged)
+        });
+    let _ = fs::remove_dir_all(&staging);
+    result
+}
+
 /// Import multiple log files at once with the same log type.
 /// Returns a summary of the import operation.
 pub fn import_multiple_log_files(
@@ -199,9 +552,9 @@ pub fn import_multiple_log_files(
 
     for source_path in source_paths {
         match import_log_file(app_handle, &source_path, log_type.clone()) {
-            Ok(file_info) => {
+            Ok(file_infos) => {
                 succeeded += 1;
-                imported_files.push(file_info);
+                imported_files.extend(file_infos);
             }
             Err(e) => {
                 failed += 1;
@@ -223,8 +576,19 @@ pub fn import_multiple_log_files(
     })
 }
 
-/// Delete a log file from the monitored folder.
-pub fn delete_log_file(app_handle: &tauri::AppHandle, filename: &str) -> Result<(), SiemError> {
+/// Delete a log file from the monitored folder, or forget a file registered
+/// in place (leaving it where it is). Returns the path of the file.
+pub fn delete_log_file(
+    app_handle: &tauri::AppHandle,
+    filename: &str,
+) -> Result<PathBuf, SiemError> {
+    let mut references = load_references(app_handle);
+    if let Some(path) = references.remove(filename) {
+        save_references(app_handle, &references)?;
+        log_hashes::forget(app_handle, filename)?;
+        return Ok(PathBuf::from(path));
+    }
+
     let logs_dir = get_logs_dir(app_handle)?;
     let file_path = logs_dir.join(filename);
 
@@ -241,8 +605,21 @@ pub fn delete_log_file(app_handle: &tauri::AppHandle, filename: &str) -> Result<
 
     fs::remove_file(&file_path)
         .map_err(|e| SiemError::FileIO(format!("Cannot delete file: {}", e)))?;
+    log_hashes::forget(app_handle, filename)?;
 
-    Ok(())
+    Ok(file_path)
+}
+
+/// Path of a library file: in the logs folder, or where it was registered.
+pub fn log_file_path(app_handle: &tauri::AppHandle, filename: &str) -> Result<PathBuf, SiemError> {
+    let path = match load_references(app_handle).remove(filename) {
+        Some(path) => PathBuf::from(path),
+        None => get_logs_dir(app_handle)?.join(filename),
+    };
+    if !path.is_file() {
+        return Err(SiemError::FileIO(format!("File not found: {}", filename)));
+    }
+    Ok(path)
 }
 
 /// Get detailed information about a specific log file with metadata.
@@ -272,11 +649,35 @@ fn get_log_file_info_with_metadata(
         size_bytes: metadata.len(),
         modified: modified_str,
         log_type,
+        referenced: false,
+        sha256: None,
     })
 }
 
 /// Get detailed information about a specific log file (without app_handle).
-/// Used by import_log_file which doesn't have access to metadata yet.
-fn get_log_file_info(path: &PathBuf) -> Result<LogFileInfo, SiemError> {
+/// Used by the imports, which don't have access to metadata yet.
+pub fn get_log_file_info(path: &PathBuf) -> Result<LogFileInfo, SiemError> {
     get_log_file_info_with_metadata(path, &HashMap::new())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_archived_logs_get_unique_names() {
+        assert!(archived_log("results/Pslist.JSONL") == Some(ArchivedLog::Json));
+        assert!(archived_log("Security.evtx") == Some(ArchivedLog::Evtx));
+        assert!(archived_log("README.md").is_none());
+
+        let taken = |name: &str| ["events.json", "events_2.json"].contains(&name);
+        assert_eq!(unique_filename(taken, "trail.json"), "trail.json");
+        assert_eq!(unique_filename(taken, "events.json"), "events_3.json");
+
+        // A Windows path in an entry name can't leave the logs folder
+        assert_eq!(
+            triage_import::file_safe("..\\..\\evil.json"),
+            ".._.._evil.json"
+        );
+    }
+}
@@ -0,0 +1,229 @@
+//! Schema inference for log files.
+//!
+//! Walks every event of a file and builds the tree of its fields: for each
+//! field path, the JSON types seen, a few sample values and the share of
+//! events that have it. Arrays are transparent, as in rule conditions: the
+//! fields of objects inside an array appear under the array's path
+//! (`resources.ARN`).
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::models::{LogSchema, SchemaField};
+
+/// Distinct sample values kept per field.
+const MAX_SAMPLES: usize = 3;
+
+/// Characters kept of a sample value.
+const MAX_SAMPLE_LEN: usize = 100;
+
+fn type_name(value: &serde_json::Value) -> &'static str {
+    match value {
+        serde_json::Value::Null => "null",
+        serde_json::Value::Bool(_) => "boolean",
+        serde_json::Value::Number(_) => "number",
+        serde_json::Value::String(_) => "string",
+        serde_json::Value::Array(_) => "array",
+        serde_json::Value::Object(_) => "object",
+    }
+}
+
+/// What has been seen of one field.
+#[derive(Default)]
+struct FieldNode {
+    /// Events with the field
+    count: usize,
+    /// Index of the last event counted, so repeated array elements count once
+    last_event: Option<usize>,
+    types: BTreeSet<String>,
+    samples: Vec<String>,
+    children: BTreeMap<String, FieldNode>,
+}
+
+impl FieldNode {
+    fn observe(&mut self, value: &serde_json::Value, event_index: usize) {
+        if self.last_event != Some(event_index) {
+            self.last_event = Some(event_index);
+            self.count += 1;
+        }
+
+        match value {
+            serde_json::Value::Object(map) => {
+                self.types.insert("object".to_string());
+                observe_object(&mut self.children, map, event_index);
+            }
+            serde_json::Value::Array(items) => {
+                if items.is_empty() {
+                    self.types.insert("array".to_string());
+                }
+                for item in items {
+                    self.types.insert(format!("array<{}>", type_name(item)));
+                    match item {
+                        serde_json::Value::Object(map) => {
+                            observe_object(&mut self.children, map, event_index)
+                        }
+                        _ => self.sample(item),
+                    }
+                }
+            }
+            _ => {
+                self.types.insert(type_name(value).to_string());
+                self.sample(value);
+            }
+        }
+    }
+
+    fn sample(&mut self, value: &serde_json::Value) {
+        if self.samples.len() >= MAX_SAMPLES || value.is_null() {
+            return;
+        }
+        let sample: String = match value {
+            serde_json::Value::String(s) => s.chars().take(MAX_SAMPLE_LEN).collect(),
+            other => other.to_string(),
+        };
+        if !self.samples.contains(&sample) {
+            self.samples.push(sample);
+        }
+    }
+
+    fn into_field(self, name: String, path: String, total_events: usize) -> SchemaField {
+        let children = self
+            .children
+            .into_iter()
+            .map(|(child, node)| {
+                let child_path = format!("{}.{}", path, child);
+                node.into_field(child, child_path, total_events)
+            })
+            .collect();
+        SchemaField {
+            name,
+            path,
+            types: self.types.into_iter().collect(),
+            sample_values: self.samples,
+            event_count: self.count,
+            presence_percentage: if total_events == 0 {
+                0.0
+            } else {
+                self.count as f64 * 100.0 / total_events as f64
+            },
+            children,
+        }
+    }
+}
+
+fn observe_object(
+    fields: &mut BTreeMap<String, FieldNode>,
+    map: &serde_json::Map<String, serde_json::Value>,
+    event_index: usize,
+) {
+    for (key, value) in map {
+        fields
+            .entry(key.clone())
+            .or_default()
+            .observe(value, event_index);
+    }
+}
+
+/// Accumulates the schema of a stream of events.
+#[derive(Default)]
+pub struct SchemaBuilder {
+    fields: BTreeMap<String, FieldNode>,
+    total_events: usize,
+}
+
+impl SchemaBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn observe(&mut self, event: &serde_json::Value) {
+        if let serde_json::Value::Object(map) = event {
+            observe_object(&mut self.fields, map, self.total_events);
+        }
+        self.total_events += 1;
+    }
+
+    /// The field tree, fields sorted by name at every level.
+    pub fn finish(self) -> LogSchema {
+        fn count(fields: &[SchemaField]) -> usize {
+            fields.iter().map(|f| 1 + count(&f.children)).sum()
+        }
+
+        let total_events = self.total_events;
+        let fields: Vec<SchemaField> = self
+            .fields
+            .into_iter()
+            .map(|(name, node)| node.into_field(name.clone(), name, total_events))
+            .collect();
+        LogSchema {
+            total_events,
+            field_count: count(&fields),
+            fields,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn find<'a>(fields: &'a [SchemaField], path: &str) -> &'a SchemaField {
+        fields
+            .iter()
+            .find(|f| path == f.path || path.starts_with(&format!("{}.", f.path)))
+            .map(|f| {
+                if f.path == path {
+                    f
+                } else {
+                    find(&f.children, path)
+                }
+            })
+            .unwrap()
+    }
+
+    #[test]
+    fn test_schema_tree() {
+        let mut builder = SchemaBuilder::new();
+        for event in [
+            serde_json::json!({
+                "eventName": "GetObject",
+                "userIdentity": { "type": "IAMUser", "userName": "alice" },
+                "resources": [{ "ARN": "arn:a" }, { "ARN": "arn:b" }],
+                "tags": ["x", "y"]
+            }),
+            serde_json::json!({
+                "eventName": "ConsoleLogin",
+                "userIdentity": { "type": "Root" },
+                "errorCode": null
+            }),
+            serde_json::json!({ "eventName": 42 }),
+            serde_json::json!({ "eventName": "GetObject" }),
+        ] {
+            builder.observe(&event);
+        }
+        let schema = builder.finish();
+        assert_eq!(schema.total_events, 4);
+
+        let field = |path: &str| find(&schema.fields, path);
+
+        let event_name = field("eventName");
+        assert_eq!(event_name.types, vec!["number", "string"]);
+        assert_eq!(
+            event_name.sample_values,
+            vec!["GetObject", "ConsoleLogin", "42"]
+        );
+        assert_eq!(event_name.presence_percentage, 100.0);
+
+        assert_eq!(field("userIdentity").types, vec!["object"]);
+        assert_eq!(field("userIdentity.userName").presence_percentage, 25.0);
+        assert_eq!(field("userIdentity.type").event_count, 2);
+
+        // Array elements are counted once per event
+        assert_eq!(field("resources").types, vec!["array<object>"]);
+        assert_eq!(field("resources.ARN").event_count, 1);
+        assert_eq!(field("resources.ARN").sample_values, vec!["arn:a", "arn:b"]);
+        assert_eq!(field("tags").types, vec!["array<string>"]);
+        assert_eq!(field("errorCode").types, vec!["null"]);
+
+        assert_eq!(schema.field_count, 8);
+    }
+}
@@ -0,0 +1,198 @@
+//! Keyword search across the log files of the monitored folder.
+//!
+//! Every file is streamed event by event and each event is matched on its
+//! compact JSON text, so a keyword is found in any field name or value
+//! without knowing where it appears. Keywords are plain substrings or, on
+//! request, regular expressions (bounded as in rule conditions).
+
+use regex::Regex;
+use std::io::Read;
+use std::sync::Arc;
+
+use crate::db_engine;
+use crate::models::{LogFileInfo, LogSearchHit, LogSearchResult, LogType, SiemError};
+use crate::safe_regex;
+
+/// Most hits returned by a search; later matches are only counted.
+pub const MAX_SEARCH_HITS: usize = 1000;
+
+/// Bytes read from the start of a file to tell CloudTrail from flat JSON.
+const SNIFF_LEN: u64 = 512;
+
+/// A compiled search keyword.
+pub struct KeywordMatcher {
+    regex: Arc<Regex>,
+}
+
+impl KeywordMatcher {
+    pub fn new(keyword: &str, case_sensitive: bool, regex: bool) -> Result<Self, SiemError> {
+        if keyword.is_empty() {
+            return Err(SiemError::Query(
+                "Search keyword cannot be empty".to_string(),
+            ));
+        }
+        let pattern = if regex {
+            keyword.to_string()
+        } else {
+            regex::escape(keyword)
+        };
+        Ok(KeywordMatcher {
+            regex: safe_regex::compile(&pattern, !case_sensitive)?,
+        })
+    }
+
+    pub fn is_match(&self, text: &str) -> bool {
+        self.regex.is_match(text)
+    }
+}
+
+/// Accumulates the hits of a search across files.
+pub struct LogSearch {
+    matcher: KeywordMatcher,
+    result: LogSearchResult,
+}
+
+impl LogSearch {
+    pub fn new(keyword: &str, case_sensitive: bool, regex: bool) -> Result<Self, SiemError> {
+        Ok(LogSearch {
+            matcher: KeywordMatcher::new(keyword, case_sensitive, regex)?,
+            result: LogSearchResult {
+                keyword: keyword.to_string(),
+                hits: Vec::new(),
+                total_matches: 0,
+                files_searched: 0,
+                events_searched: 0,
+                truncated: false,
+                failed_files: Vec::new(),
+            },
+        })
+    }
+
+    /// Search one log file, streaming its events.
+    pub fn search_file(&mut self, file: &LogFileInfo) {
+        let log_type = file
+            .log_type
+            .clone()
+            .unwrap_or_else(|| sniff_log_type(&file.path));
+
+        let mut event_index = 0;
+        let matcher = &self.matcher;
+        let result = &mut self.result;
+        let streamed = db_engine::stream_events(
+            &file.path,
+            &log_type,
+            db_engine::STREAM_CHUNK_SIZE,
+            |chunk| {
+                for event in chunk {
+                    let text = event.to_string();
+                    if matcher.is_match(&text) {
+                        result.total_matches += 1;
+                        if result.hits.len() < MAX_SEARCH_HITS {
+                            result.hits.push(LogSearchHit {
+                                filename: file.filename.clone(),
+                                log_path: file.path.clone(),
+                                event_index,
+                                event,
+                            });
+                        } else {
+                            result.truncated = true;
+                        }
+                    }
+                    event_index += 1;
+                }
+            },
+        );
+
+        result.events_searched += event_index;
+        match streamed {
+            Ok(_) => result.files_searched += 1,
+            Err(e) => result
+                .failed_files
+                .push(format!("{}: {}", file.filename, e)),
+        }
+    }
+
+    pub fn finish(self) -> LogSearchResult {
+        self.result
+    }
+}
+
+/// Log type of a file without a recorded one: CloudTrail when it opens with
+/// a `Records` key, flat JSON otherwise.
+fn sniff_log_type(path: &str) -> LogType {
+    let mut head = String::new();
+    let _ = std::fs::File::open(path).map(|file| file.take(SNIFF_LEN).read_to_string(&mut head));
+    let head = head.trim_start();
+    match head.strip_prefix('{') {
+        Some(rest) if rest.trim_start().starts_with("\"Records\"") => LogType::CloudTrail,
+        _ => LogType::FlatJson,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn log_file(name: &str, content: &str, log_type: Option<LogType>) -> LogFileInfo {
+        let path = std::env::temp_dir().join(name);
+        std::fs::write(&path, content).unwrap();
+        LogFileInfo {
+            filename: name.to_string(),
+            path: path.to_string_lossy().to_string(),
+            size_bytes: content.len() as u64,
+            modified: String::new(),
+            log_type,
+            referenced: false,
+            sha256: None,
+        }
+    }
+
+    #[test]
+    fn test_search_across_files() {
+        let trail = log_file(
+            "offline_siem_test_search_trail.json",
+            r#"{"Records": [
+                {"eventName": "ConsoleLogin", "sourceIPAddress": "203.0.113.7"},
+                {"eventName": "GetObject", "sourceIPAddress": "198.51.100.1"}
+            ]}"#,
+            None,
+        );
+        let flows = log_file(
+            "offline_siem_test_search_flows.json",
+            "{\"src_ip\": \"192.0.2.1\"}\n{\"src_ip\": \"203.0.113.7\", \"action\": \"REJECT\"}\n",
+            Some(LogType::FlatJson),
+        );
+
+        let mut search = LogSearch::new("203.0.113.7", true, false).unwrap();
+        search.search_file(&trail);
+        search.search_file(&flows);
+        let result = search.finish();
+        assert_eq!(result.files_searched, 2);
+        assert_eq!(result.events_searched, 4);
+        let hits: Vec<(&str, usize)> = result
+            .hits
+            .iter()
+            .map(|hit| (hit.filename.as_str(), hit.event_index))
+            .collect();
+        assert_eq!(
+            hits,
+            vec![
+                ("offline_siem_test_search_trail.json", 0),
+                ("offline_siem_test_search_flows.json", 1)
+            ]
+        );
+
+        // Case-insensitive keywords and regular expressions
+        let mut search = LogSearch::new("reject", false, false).unwrap();
+        search.search_file(&flows);
+        assert_eq!(search.finish().total_matches, 1);
+        let mut search = LogSearch::new(r"^\{.*GetObj", true, true).unwrap();
+        search.search_file(&trail);
+        assert_eq!(search.finish().hits[0].event_index, 1);
+
+        // Literal keywords aren't patterns
+        let mut search = LogSearch::new("203.0.113.*", true, false).unwrap();
+        search.search_file(&flows);
+        assert_eq!(search.finish().total_matches, 0);
+    }
+}
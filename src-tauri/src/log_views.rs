@@ -0,0 +1,245 @@
+//! Named log views for cross-file queries.
+//!
+//! A log file registered under a name becomes a DuckDB view in the ingestion
+//! cache database, where ad-hoc queries run, so several files can be joined
+//! in one query (e.g. CloudTrail against VPC flow logs):
+//!
+//! ```sql
+//! SELECT t.eventName, f.dst_port FROM trail t JOIN flows f ON t.sourceIPAddress = f.src_ip
+//! ```
+//!
+//! CloudTrail views have one row per record and one column per top-level
+//! record field (nested fields are structs: `userIdentity.arn`); flat JSON
+//! views have one column per field. Views read the file when queried, so
+//! they always reflect its current content, and they are kept across
+//! sessions until dropped.
+
+use duckdb::{params, Connection};
+use serde::Serialize;
+
+use crate::db_engine::{self, sql_string};
+use crate::ingest_cache;
+use crate::models::{LogType, SiemError};
+
+/// Table recording the registered views.
+const VIEWS_TABLE: &str = "log_views";
+
+/// Longest view name accepted.
+const MAX_VIEW_NAME_LEN: usize = 64;
+
+/// A log file registered as a named view.
+#[derive(Debug, Serialize, Clone)]
+pub struct LogView {
+    pub name: String,
+    pub log_path: String,
+    pub log_type: LogType,
+    /// When the view was registered (ISO 8601)
+    pub created_at: String,
+}
+
+/// Create the views table if needed.
+fn init_schema(conn: &Connection) -> Result<(), SiemError> {
+    conn.execute_batch(&format!(
+        "CREATE TABLE IF NOT EXISTS {} (
+            name VARCHAR PRIMARY KEY,
+            log_path VARCHAR NOT NULL,
+            log_type VARCHAR NOT NULL,
+            created_at VARCHAR NOT NULL
+        )",
+        VIEWS_TABLE
+    ))
+    .map_err(|e| SiemError::Query(format!("Cannot initialize log views: {}", e)))
+}
+
+/// Check that a view name is a plain SQL identifier.
+fn validate_view_name(name: &str) -> Result<(), SiemError> {
+    let valid = name.len() <= MAX_VIEW_NAME_LEN
+        && name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if !valid {
+        return Err(SiemError::Query(format!(
+            "Invalid view name '{}': use letters, digits and underscores",
+            name
+        )));
+    }
+    Ok(())
+}
+
+/// Query defining the view of a log file.
+fn view_query(log_path: &str, log_type: &LogType) -> Result<String, SiemError> {
+    Ok(match log_type {
+        LogType::CloudTrail => format!(
+            "SELECT unnest(record) FROM (SELECT unnest(Records) AS record FROM read_json_auto({}, maximum_object_size = {}))",
+            sql_string(log_path),
+            db_engine::cloudtrail_object_size(log_path)?
+        ),
+        LogType::FlatJson | LogType::Suricata | LogType::Okta => format!(
+            "SELECT * FROM read_json_auto({}, sample_size = -1)",
+            sql_string(log_path)
+        ),
+        LogType::AzureActivity
+        | LogType::EntraSignIn
+        | LogType::Windows
+        | LogType::WindowsXml
+        | LogType::Kv
+        | LogType::Plugin(_)
+        | LogType::Text(_) => {
+            return Err(SiemError::Query(
+                "Only CloudTrail, flat JSON, Suricata and Okta logs can be views: ingest them and query their table"
+                    .to_string(),
+            ))
+        }
+    })
+}
+
+/// Create (or replace) the view `name` over a log file.
+pub fn register_view(
+    conn: &Connection,
+    name: &str,
+    log_path: &str,
+    log_type: &LogType,
+) -> Result<LogView, SiemError> {
+    validate_view_name(name)?;
+    init_schema(conn)?;
+    let map_err = |e: duckdb::Error| SiemError::Query(format!("Cannot register log view: {}", e));
+
+    conn.execute_batch(&format!(
+        "CREATE OR REPLACE VIEW \"{}\" AS {}",
+        name,
+        view_query(log_path, log_type)?
+    ))
+    .map_err(map_err)?;
+
+    let view = LogView {
+        name: name.to_string(),
+        log_path: log_path.to_string(),
+        log_type: log_type.clone(),
+        created_at: chrono::Utc::now().to_rfc3339(),
+    };
+    conn.execute(
+        &format!("INSERT OR REPLACE INTO {} VALUES (?, ?, ?, ?)", VIEWS_TABLE),
+        params![
+            view.name,
+            view.log_path,
+            ingest_cache::log_type_name(log_type),
+            view.created_at
+        ],
+    )
+    .map_err(map_err)?;
+
+    Ok(view)
+}
+
+/// List the registered views, by name.
+pub fn list_views(conn: &Connection) -> Result<Vec<LogView>, SiemError> {
+    init_schema(conn)?;
+    let map_err = |e: duckdb::Error| SiemError::Query(format!("Cannot list log views: {}", e));
+
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT name, log_path, log_type, created_at FROM {} ORDER BY name",
+            VIEWS_TABLE
+        ))
+        .map_err(map_err)?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, String>(3)?,
+            ))
+        })
+        .map_err(map_err)?;
+
+    let mut views = Vec::new();
+    for row in rows {
+        let (name, log_path, log_type, created_at) = row.map_err(map_err)?;
+        let Some(log_type) = ingest_cache::parse_log_type(&log_type) else {
+            continue;
+        };
+        views.push(LogView {
+            name,
+            log_path,
+            log_type,
+            created_at,
+        });
+    }
+    Ok(views)
+}
+
+/// Drop a registered view. Returns whether it existed.
+pub fn drop_view(conn: &Connection, name: &str) -> Result<bool, SiemError> {
+    validate_view_name(name)?;
+    init_schema(conn)?;
+    let map_err = |e: duckdb::Error| SiemError::Query(format!("Cannot drop log view: {}", e));
+
+    let removed = conn
+        .execute(
+            &format!("DELETE FROM {} WHERE name = ?", VIEWS_TABLE),
+            params![name],
+        )
+        .map_err(map_err)?;
+    if removed == 0 {
+        return Ok(false);
+    }
+    conn.execute_batch(&format!("DROP VIEW IF EXISTS \"{}\"", name))
+        .map_err(map_err)?;
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_join_registered_views() {
+        let dir = std::env::temp_dir();
+        let trail = dir.join("offline_siem_test_view_trail.json");
+        let flows = dir.join("offline_siem_test_view_flows.json");
+        std::fs::write(
+            &trail,
+            r#"{"Records": [
+                {"eventName": "ConsoleLogin", "sourceIPAddress": "203.0.113.7", "userIdentity": {"arn": "arn:alice"}},
+                {"eventName": "GetObject", "sourceIPAddress": "198.51.100.1", "userIdentity": {"arn": "arn:bob"}}
+            ]}"#,
+        )
+        .unwrap();
+        std::fs::write(
+            &flows,
+            "{\"src_ip\": \"203.0.113.7\", \"dst_port\": 22}\n{\"src_ip\": \"192.0.2.1\", \"dst_port\": 443}\n",
+        )
+        .unwrap();
+
+        let conn = Connection::open_in_memory().unwrap();
+        register_view(
+            &conn,
+            "trail",
+            &trail.to_string_lossy(),
+            &LogType::CloudTrail,
+        )
+        .unwrap();
+        register_view(&conn, "flows", &flows.to_string_lossy(), &LogType::FlatJson).unwrap();
+
+        let (arn, port): (String, i64) = conn
+            .query_row(
+                "SELECT t.userIdentity.arn, f.dst_port FROM trail t JOIN flows f ON t.sourceIPAddress = f.src_ip",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!((arn.as_str(), port), ("arn:alice", 22));
+
+        let names: Vec<String> = list_views(&conn)
+            .unwrap()
+            .into_iter()
+            .map(|view| view.name)
+            .collect();
+        assert_eq!(names, vec!["flows", "trail"]);
+
+        assert!(drop_view(&conn, "flows").unwrap());
+        assert!(!drop_view(&conn, "flows").unwrap());
+        assert!(conn.execute_batch("SELECT * FROM flows").is_err());
+        assert!(register_view(&conn, "x; DROP TABLE y", "a.json", &LogType::FlatJson).is_err());
+    }
+}
@@ -0,0 +1,150 @@
+//! User-maintained lookup lists for rule conditions.
+//!
+//! Lookup lists (admin users, approved regions, ...) live in `lookup_lists/`
+//! in the application's data directory as `<name>.txt`, one value per line,
+//! and are referenced from conditions by name:
+//! `awsRegion NOT IN_LIST 'approved_regions'`, or
+//! `IN_LIST 'lookup:approved_regions'` to name the store. Unlike IOC lists
+//! they are edited in the app rather than imported from feeds. A name can
+//! only be used by one of them, so bare names stay unambiguous.
+
+use std::fs;
+use std::path::PathBuf;
+
+use crate::app_data;
+use crate::models::{LookupListInfo, SiemError};
+use crate::term_sets;
+
+/// Get the directory where lookup lists are stored.
+pub fn get_lookup_lists_dir(app_handle: &tauri::AppHandle) -> Result<PathBuf, SiemError> {
+    app_data::app_data_subdir(app_handle, "lookup_lists")
+}
+
+/// Path of a lookup list file.
+fn list_path(app_handle: &tauri::AppHandle, name: &str) -> Result<PathBuf, SiemError> {
+    term_sets::validate_list_name(name)?;
+    Ok(get_lookup_lists_dir(app_handle)?.join(format!("{}.txt", name)))
+}
+
+/// Values of a list file: trimmed, non-empty, non-comment lines.
+fn parse_entries(content: &str) -> Vec<String> {
+    content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(str::to_string)
+        .collect()
+}
+
+/// List the lookup lists, by name.
+pub fn list_lookup_lists(app_handle: &tauri::AppHandle) -> Result<Vec<LookupListInfo>, SiemError> {
+    let dir = get_lookup_lists_dir(app_handle)?;
+    let entries = fs::read_dir(&dir)
+        .map_err(|e| SiemError::FileIO(format!("Cannot read lookup lists dir: {}", e)))?;
+
+    let mut lists = Vec::new();
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.extension().is_none_or(|ext| ext != "txt") {
+            continue;
+        }
+        let Some(name) = path.file_stem().map(|s| s.to_string_lossy().to_string()) else {
+            continue;
+        };
+        let content = match fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(e) => {
+                eprintln!("Warning: Cannot read lookup list '{}': {}", name, e);
+                continue;
+            }
+        };
+        let modified = entry
+            .metadata()
+            .and_then(|m| m.modified())
+            .map(|t| chrono::DateTime::<chrono::Utc>::from(t).to_rfc3339())
+            .unwrap_or_default();
+
+        lists.push(LookupListInfo {
+            name,
+            entry_count: parse_entries(&content).len(),
+            modified,
+        });
+    }
+
+    lists.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(lists)
+}
+
+/// Get the values of a lookup list.
+pub fn get_lookup_list(
+    app_handle: &tauri::AppHandle,
+    name: &str,
+) -> Result<Vec<String>, SiemError> {
+    let path = list_path(app_handle, name)?;
+    if !path.exists() {
+        return Err(SiemError::FileIO(format!(
+            "Lookup list not found: {}",
+            name
+        )));
+    }
+
+    let content = fs::read_to_string(&path)
+        .map_err(|e| SiemError::FileIO(format!("Cannot read lookup list: {}", e)))?;
+    Ok(parse_entries(&content))
+}
+
+/// Create or replace a lookup list. Blank and duplicate values are dropped.
+pub fn save_lookup_list(
+    app_handle: &tauri::AppHandle,
+    name: &str,
+    entries: Vec<String>,
+) -> Result<LookupListInfo, SiemError> {
+    let path = list_path(app_handle, name)?;
+    if term_sets::find_named_list(&format!("{}:{}", term_sets::IOC_LISTS, name)).is_some() {
+        return Err(SiemError::FileIO(format!(
+            "List name '{}' is already used by an IOC list",
+            name
+        )));
+    }
+
+    let mut values: Vec<String> = Vec::with_capacity(entries.len());
+    for entry in parse_entries(&entries.join("\n")) {
+        if !values.contains(&entry) {
+            values.push(entry);
+        }
+    }
+
+    fs::write(&path, values.join("\n"))
+        .map_err(|e| SiemError::FileIO(format!("Cannot write lookup list: {}", e)))?;
+
+    Ok(LookupListInfo {
+        name: name.to_string(),
+        entry_count: values.len(),
+        modified: chrono::Utc::now().to_rfc3339(),
+    })
+}
+
+/// Delete a lookup list.
+pub fn delete_lookup_list(app_handle: &tauri::AppHandle, name: &str) -> Result<(), SiemError> {
+    let path = list_path(app_handle, name)?;
+    if !path.exists() {
+        return Err(SiemError::FileIO(format!(
+            "Lookup list not found: {}",
+            name
+        )));
+    }
+
+    fs::remove_file(&path)
+        .map_err(|e| SiemError::FileIO(format!("Cannot delete lookup list: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_entries() {
+        let content = "# Approved regions\nus-east-1\n\n  eu-west-1  \n#ap-southeast-1\n";
+        assert_eq!(parse_entries(content), vec!["us-east-1", "eu-west-1"]);
+    }
+}
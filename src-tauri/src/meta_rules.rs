@@ -0,0 +1,182 @@
+//! Cross-rule correlation (meta-rules).
+//!
+//! A rule with a `meta` block has no event condition of its own. After the
+//! regular rules of a scan have produced their alerts, each meta-rule groups
+//! the alerts of its referenced rules by entity (values of the `by` fields in
+//! the alerts' evidence) and fires once per entity for which the number of
+//! distinct alerting rules meets the threshold.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::aggregation;
+use crate::models::{AlertEvent, MetaMatch, MetaRule, RuleYaml};
+
+/// Maximum number of evidence events kept per meta-rule alert.
+const MAX_META_EVIDENCE: usize = 1000;
+
+/// Alerts contributing to one entity.
+#[derive(Default)]
+struct EntityHits {
+    rule_ids: BTreeSet<String>,
+    alerts: BTreeSet<usize>,
+    evidence: Vec<serde_json::Value>,
+}
+
+/// Check whether a rule is a meta-rule (evaluated over alerts, not events).
+pub fn is_meta_rule(rule: &RuleYaml) -> bool {
+    rule.detection.meta.is_some()
+}
+
+/// Run the meta-rules among `rules` over the alerts of one scan.
+pub fn evaluate_meta_rules(rules: &[RuleYaml], alerts: &[AlertEvent]) -> Vec<AlertEvent> {
+    let mut meta_alerts = Vec::new();
+
+    for rule in rules {
+        let Some(meta) = &rule.detection.meta else {
+            continue;
+        };
+
+        let threshold = match aggregation::parse_threshold(&meta.threshold) {
+            Ok(threshold) => threshold,
+            Err(e) => {
+                eprintln!("Warning: Rule '{}' failed: {}", rule.title, e);
+                continue;
+            }
+        };
+
+        for (entity, hits) in group_by_entity(rule, meta, alerts) {
+            if !threshold.is_met(hits.rule_ids.len()) {
+                continue;
+            }
+
+            // Keep the source file only if every contributing alert shares it
+            let sources: BTreeSet<Option<&String>> = hits
+                .alerts
+                .iter()
+                .map(|&i| alerts[i].source_file.as_ref())
+                .collect();
+            let source_file = match sources.into_iter().collect::<Vec<_>>().as_slice() {
+                [single] => single.cloned(),
+                _ => None,
+            };
+
+            let mut alert = AlertEvent::from_rule(rule, hits.evidence, source_file);
+            alert.meta = Some(MetaMatch {
+                entity: meta.by.iter().cloned().zip(entity).collect(),
+                rule_ids: hits.rule_ids.into_iter().collect(),
+                alert_count: hits.alerts.len(),
+            });
+            meta_alerts.push(alert);
+        }
+    }
+
+    meta_alerts
+}
+
+/// Collect the referenced alerts per entity.
+fn group_by_entity(
+    rule: &RuleYaml,
+    meta: &MetaRule,
+    alerts: &[AlertEvent],
+) -> BTreeMap<Vec<String>, EntityHits> {
+    let min_severity = meta.min_severity.as_deref().map(crate::severity_order);
+    let mut entities: BTreeMap<Vec<String>, EntityHits> = BTreeMap::new();
+
+    for (index, alert) in alerts.iter().enumerate() {
+        // Never correlate meta-rule alerts, including this rule's own
+        if alert.meta.is_some() || alert.rule_id == rule.id {
+            continue;
+        }
+        if !meta.rules.is_empty() && !meta.rules.contains(&alert.rule_id) {
+            continue;
+        }
+        if min_severity.is_some_and(|min| crate::severity_order(&alert.severity) < min) {
+            continue;
+        }
+
+        for event in &alert.evidence {
+            let entity: Vec<String> = meta
+                .by
+                .iter()
+                .map(|field| aggregation::group_value(event, field))
+                .collect();
+            if entity.iter().any(|value| value.is_empty()) {
+                continue;
+            }
+
+            let hits = entities.entry(entity).or_default();
+            hits.rule_ids.insert(alert.rule_id.clone());
+            hits.alerts.insert(index);
+            if hits.evidence.len() < MAX_META_EVIDENCE {
+                hits.evidence.push(event.clone());
+            }
+        }
+    }
+
+    entities
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(id: &str, severity: &str) -> RuleYaml {
+        let yaml = format!(
+            "id: {}\ntitle: {}\ndescription: ''\nauthor: ''\nstatus: active\ndate: ''\ndetection:\n  severity: {}\n  condition: \"a = '1'\"\n",
+            id, id, severity
+        );
+        serde_yaml::from_str(&yaml).unwrap()
+    }
+
+    fn meta_rule(threshold: &str, min_severity: &str) -> RuleYaml {
+        let yaml = format!(
+            "id: meta\ntitle: Noisy user\ndescription: ''\nauthor: ''\nstatus: active\ndate: ''\ndetection:\n  severity: critical\n  meta:\n    by: user\n    min_severity: {}\n    threshold: \"{}\"\n",
+            min_severity, threshold
+        );
+        serde_yaml::from_str(&yaml).unwrap()
+    }
+
+    fn alert(rule_id: &str, severity: &str, users: &[&str]) -> AlertEvent {
+        let evidence = users
+            .iter()
+            .map(|u| serde_json::json!({ "user": u }))
+            .collect();
+        AlertEvent::from_rule(
+            &rule(rule_id, severity),
+            evidence,
+            Some("a.json".to_string()),
+        )
+    }
+
+    #[test]
+    fn test_meta_rule_fires_per_entity() {
+        let alerts = vec![
+            alert("r1", "medium", &["alice", "bob"]),
+            alert("r2", "high", &["alice"]),
+            alert("r3", "medium", &["alice", "bob"]),
+            alert("r4", "low", &["bob"]),
+        ];
+        let rules = vec![rule("r1", "medium"), meta_rule(">= 3", "medium")];
+
+        let meta_alerts = evaluate_meta_rules(&rules, &alerts);
+        assert_eq!(meta_alerts.len(), 1);
+
+        let meta = meta_alerts[0].meta.as_ref().unwrap();
+        assert_eq!(meta.entity["user"], "alice");
+        assert_eq!(meta.rule_ids, vec!["r1", "r2", "r3"]);
+        assert_eq!(meta_alerts[0].severity, "critical");
+        assert_eq!(meta_alerts[0].source_file.as_deref(), Some("a.json"));
+    }
+
+    #[test]
+    fn test_meta_rule_restricted_to_referenced_rules() {
+        let alerts = vec![
+            alert("r1", "medium", &["alice"]),
+            alert("r2", "medium", &["alice"]),
+        ];
+        let mut meta = meta_rule(">= 2", "low");
+        meta.detection.meta.as_mut().unwrap().rules = vec!["r1".to_string(), "r9".to_string()];
+
+        assert!(evaluate_meta_rules(&[meta], &alerts).is_empty());
+    }
+}
@@ -6,11 +6,47 @@
 use serde::{Deserialize, Serialize};
 
 /// Log file format type
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum LogType {
     CloudTrail,
     FlatJson,
+    /// Lines of `key=value` pairs (see `kv_log`)
+    Kv,
+    /// Suricata EVE JSON (`eve.json`, see `suricata`)
+    Suricata,
+    /// Azure Activity Log export (see `azure`)
+    AzureActivity,
+    /// Entra ID (Azure AD) sign-in log export (see `azure`)
+    EntraSignIn,
+    /// Okta System Log export (see `okta`)
+    Okta,
+    /// Windows event export (Sysmon, Security, ...), normalized (see
+    /// `windows_events`)
+    Windows,
+    /// Windows events exported as XML by Event Viewer or wevtutil, converted
+    /// and normalized like `Windows` (see `evtx_xml`)
+    WindowsXml,
+    /// Read through a parser plugin (`plugins/<name>.wasm`, see `parser_plugins`)
+    Plugin(String),
+    /// Read through a text parser (grok/regex patterns, see `text_parser`)
+    Text(String),
+}
+
+impl LogType {
+    /// Whether files of this type are JSON files.
+    pub fn is_json(&self) -> bool {
+        matches!(
+            self,
+            LogType::CloudTrail
+                | LogType::FlatJson
+                | LogType::Suricata
+                | LogType::AzureActivity
+                | LogType::EntraSignIn
+                | LogType::Okta
+                | LogType::Windows
+        )
+    }
 }
 
 // ============================================================================
@@ -36,6 +72,22 @@ pub struct RuleYaml {
     /// Tags for filtering and categorization
     #[serde(default)]
     pub tags: Vec<String>,
+    /// MITRE ATT&CK technique IDs (e.g. "T1078.004"), including those
+    /// tagged as `attack.t1078.004`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub attack_techniques: Vec<String>,
+    /// External references (URLs, ticket IDs, ATT&CK pages)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub references: Vec<String>,
+    /// Known benign causes of matches
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub falsepositives: Vec<String>,
+    /// Recommended response steps when the rule fires
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub remediation: Option<String>,
+    /// Log sources this rule applies to (if None, applies to all log types)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub logsource: Option<LogSource>,
     /// Core detection logic
     pub detection: DetectionLogic,
     /// Optional output configuration
@@ -43,22 +95,279 @@ pub struct RuleYaml {
     pub output: Option<OutputConfig>,
 }
 
+/// Log source targeting for a rule.
+/// Unset fields match any source.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct LogSource {
+    /// Product that produced the logs (Sigma style), e.g. "aws", "windows"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub product: Option<String>,
+    /// Service of the product, e.g. "cloudtrail", "security"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub service: Option<String>,
+    /// Log format the rule is written for
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub log_type: Option<LogType>,
+}
+
 /// Detection logic containing the SQL condition.
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct DetectionLogic {
     /// Severity level: "info", "low", "medium", "high", "critical"
     pub severity: String,
+    /// How `condition` is written (condition language or script)
+    #[serde(
+        default,
+        rename = "type",
+        skip_serializing_if = "DetectionType::is_condition"
+    )]
+    pub detection_type: DetectionType,
     /// SQL WHERE clause compatible with DuckDB
     /// Example: "event_id = 4625 AND username = 'admin'"
+    /// For sequence rules this is an optional filter applied to every step.
+    #[serde(default)]
     pub condition: String,
+    /// Exception conditions: events matching any of them never match the rule.
+    /// Tuning goes here so the base condition stays untouched.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub filters: Vec<String>,
+    /// Case sensitivity of string comparisons.
+    /// true = all exact, false = all case-insensitive,
+    /// unset = exact equality/IN, case-insensitive CONTAINS/STARTSWITH/ENDSWITH/MATCH
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub case_sensitive: Option<bool>,
+    /// Optional threshold aggregation over matching events
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub aggregation: Option<Aggregation>,
+    /// Ordered sequence of conditions (makes this a correlation rule)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sequence: Option<Sequence>,
+    /// Correlation over other rules' alerts (makes this a meta-rule)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub meta: Option<MetaRule>,
+    /// Expected events (makes this an "alert if absent" rule)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub absence: Option<Absence>,
+    /// Long-tail values (makes this a rarity rule)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rarity: Option<Rarity>,
+    /// Values never seen before (makes this a first-seen rule)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub first_seen: Option<FirstSeen>,
+    /// How matches become alerts (defaults to the global setting in config)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub grouping: Option<AlertGrouping>,
+    /// Alerts allowed per scan before they are merged into one summary alert
+    /// (0 = no limit, unset = the global setting in config)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_alerts_per_scan: Option<usize>,
+}
+
+/// Language of a rule's `condition`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DetectionType {
+    /// SQL-like condition (`eventName = 'AssumeRole'`)
+    #[default]
+    Condition,
+    /// Rhai script returning a boolean (see `script_rules`)
+    Script,
+}
+
+impl DetectionType {
+    pub fn is_condition(&self) -> bool {
+        *self == DetectionType::Condition
+    }
+}
+
+/// How a rule's matching events are turned into alerts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AlertGrouping {
+    /// One alert per rule with the total match count and capped, deduplicated evidence
+    #[default]
+    PerRule,
+    /// One alert per matching event
+    PerEvent,
+}
+
+/// Meta-rule: alert when enough other rules fired for the same entity during
+/// one scan (e.g. 3 or more medium alerts for the same IAM user).
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MetaRule {
+    /// Rule IDs to correlate (empty = every non-meta rule)
+    #[serde(
+        default,
+        skip_serializing_if = "Vec::is_empty",
+        deserialize_with = "deserialize_string_or_vec"
+    )]
+    pub rules: Vec<String>,
+    /// Only count alerts of at least this severity (e.g., "medium")
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_severity: Option<String>,
+    /// Evidence fields identifying the entity (e.g., ["userIdentity.arn"]).
+    /// A single string is also accepted.
+    #[serde(deserialize_with = "deserialize_string_or_vec")]
+    pub by: Vec<String>,
+    /// Threshold on the number of distinct rules that alerted for the entity, e.g. ">= 3"
+    pub threshold: String,
+}
+
+/// Expectation rule: alert when the condition matches fewer than `min_count`
+/// events in a window (e.g. no heartbeat from a host, no successful
+/// ConsoleLogin of a backup service account in 24h).
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Absence {
+    /// Window size, e.g. "1h", "24h" (unset = the whole dataset coverage)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub window: Option<String>,
+    /// Minimum number of matching events expected per window
+    #[serde(default = "default_min_count")]
+    pub min_count: usize,
+    /// Field identifying the entity expected to appear (e.g. "host")
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub by: Option<String>,
+    /// Values of `by` expected to appear (empty = every value seen in the dataset)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub expected: Vec<String>,
+    /// Timestamp field (defaults to the log type's standard timestamp field)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timestamp_field: Option<String>,
+}
+
+fn default_min_count() -> usize {
+    1
+}
+
+/// Rarity: alert on values of a field that appear among the matching events
+/// of a log file fewer than `threshold` times (e.g. an API call made once).
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Rarity {
+    /// Field whose values are counted (e.g. "eventName")
+    pub field: String,
+    /// Values seen fewer times than this alert
+    #[serde(default = "default_rarity_threshold")]
+    pub threshold: usize,
+}
+
+fn default_rarity_threshold() -> usize {
+    2
+}
+
+/// First-seen: alert when a value of a field appears among the matching
+/// events for the first time across scans (e.g. a new userAgent for the
+/// account), once the field has a baseline.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct FirstSeen {
+    /// Field whose values are tracked (e.g. "userIdentity.arn")
+    pub field: String,
+    /// Learning period, e.g. "7d": values first seen less than this long after
+    /// the field's earliest tracked event are learned without alerting.
+    /// Unset = learn the first scan of the field, alert from the next one on
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub baseline: Option<String>,
+    /// Timestamp field (defaults to the log type's standard timestamp field)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timestamp_field: Option<String>,
+}
+
+/// Correlation: alert when events match the steps in order, sharing a key,
+/// within one time window (e.g. ConsoleLogin without MFA followed by
+/// CreateAccessKey by the same principal within 30m).
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Sequence {
+    /// Steps that must match in order (at least two)
+    pub steps: Vec<SequenceStep>,
+    /// Maximum time between the first and last step, e.g. "30m"
+    pub window: String,
+    /// Fields whose values must be equal across all steps
+    /// (e.g., ["userIdentity.arn"]). A single string is also accepted.
+    #[serde(
+        default,
+        skip_serializing_if = "Vec::is_empty",
+        deserialize_with = "deserialize_string_or_vec"
+    )]
+    pub by: Vec<String>,
+    /// Timestamp field (defaults to the log type's standard timestamp field)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timestamp_field: Option<String>,
+}
+
+/// One step of a sequence.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SequenceStep {
+    /// Optional label shown in alerts (defaults to "step N")
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// Condition an event must match for this step
+    pub condition: String,
+}
+
+/// Threshold aggregation: alert when enough matching events fall into one time window.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Aggregation {
+    /// Whether aggregation is applied (defaults to true when the block is present)
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// Window size, e.g. "30s", "5m", "1h", "1d"
+    pub window: String,
+    /// Threshold on the number of events per window, e.g. "> 5", ">= 10"
+    /// (may be empty when `having` is set)
+    #[serde(default)]
+    pub threshold: String,
+    /// Fields to group events by before counting (e.g., ["sourceIPAddress"]),
+    /// so thresholds apply per entity. A single string is also accepted.
+    #[serde(
+        default,
+        skip_serializing_if = "Vec::is_empty",
+        deserialize_with = "deserialize_string_or_vec"
+    )]
+    pub group_by: Vec<String>,
+    /// Count distinct values of this field instead of events
+    /// (e.g., "eventName" to detect one principal enumerating many APIs)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub distinct_field: Option<String>,
+    /// Timestamp field (defaults to the log type's standard timestamp field)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timestamp_field: Option<String>,
+    /// Condition on several aggregates per bucket, combined with AND/OR,
+    /// e.g. "count(distinct eventName) > 10 AND count > 100"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub having: Option<String>,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// Accept either a single string or a list of strings.
+fn deserialize_string_or_vec<'de, D>(deserializer: D) -> Result<Vec<String>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum OneOrMany {
+        One(String),
+        Many(Vec<String>),
+    }
+
+    Ok(match OneOrMany::deserialize(deserializer)? {
+        OneOrMany::One(value) => vec![value],
+        OneOrMany::Many(values) => values,
+    })
 }
 
 /// Output configuration for alert formatting.
 #[derive(Debug, Serialize, Deserialize, Clone, Default)]
 pub struct OutputConfig {
-    /// Template for alert title with variable substitution (e.g., "Alert: {{username}}")
+    /// Template for alert title with variable substitution (e.g., "Alert: {{username}}"),
+    /// rendered against the first matched event
     #[serde(default)]
     pub alert_title: String,
+    /// Fields of matched events kept as evidence (dot paths); all fields if empty
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub evidence_fields: Vec<String>,
 }
 
 // ============================================================================
@@ -66,12 +375,19 @@ pub struct OutputConfig {
 // ============================================================================
 
 /// Alert generated when a rule matches log entries.
-#[derive(Debug, Serialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct AlertEvent {
     /// ID of the rule that triggered this alert
     pub rule_id: String,
     /// Title of the rule that triggered this alert
     pub rule_title: String,
+    /// Title rendered from the rule's `output.alert_title` template
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub alert_title: Option<String>,
+    /// The `output.alert_title` template, kept to render the title again from
+    /// pseudonymized evidence
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub alert_title_template: Option<String>,
     /// Severity level from the rule
     pub severity: String,
     /// Timestamp when the scan was performed (ISO 8601)
@@ -84,6 +400,303 @@ pub struct AlertEvent {
     /// Source log file that generated this alert (optional, used in bulk scans)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub source_file: Option<String>,
+    /// Aggregation bucket that triggered this alert (aggregation rules only)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub aggregation: Option<AggregationBucket>,
+    /// Matched sequence that triggered this alert (sequence rules only)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub correlation: Option<CorrelationMatch>,
+    /// Entity and contributing rules that triggered this alert (meta-rules only)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub meta: Option<MetaMatch>,
+    /// Window and entity lacking expected events (absence rules only)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub absence: Option<AbsenceMatch>,
+    /// Rare value that triggered this alert (rarity rules only)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rarity: Option<RarityMatch>,
+    /// New value that triggered this alert (first-seen rules only)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub first_seen: Option<FirstSeenMatch>,
+    /// Set when the rule's alerts exceeded its cap and were merged into this one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub truncation: Option<AlertTruncation>,
+    /// Verdicts for file hashes found in the evidence
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub hash_annotations: Vec<HashAnnotation>,
+    /// Country and autonomous system of IP addresses found in the evidence
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub geo_annotations: Vec<GeoAnnotation>,
+    /// Known IOCs from imported threat intel lists found in the evidence
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub ioc_matches: Vec<IocMatch>,
+    /// External references from the rule
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub references: Vec<String>,
+    /// Known false positives from the rule
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub falsepositives: Vec<String>,
+    /// Recommended response steps from the rule
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub remediation: Option<String>,
+}
+
+impl AlertEvent {
+    /// Build an alert for a rule from its matched events.
+    pub fn from_rule(
+        rule: &RuleYaml,
+        evidence: Vec<serde_json::Value>,
+        source_file: Option<String>,
+    ) -> Self {
+        let alert_title_template = rule
+            .output
+            .as_ref()
+            .filter(|output| !output.alert_title.trim().is_empty())
+            .map(|output| output.alert_title.clone());
+        let alert_title = alert_title_template
+            .as_ref()
+            .map(|template| crate::alert_template::render(template, evidence.first()));
+        AlertEvent {
+            rule_id: rule.id.clone(),
+            rule_title: rule.title.clone(),
+            alert_title,
+            alert_title_template,
+            severity: rule.detection.severity.clone(),
+            timestamp: chrono::Utc::now().to_rfc3339(),
+            match_count: evidence.len(),
+            evidence,
+            source_file,
+            aggregation: None,
+            correlation: None,
+            meta: None,
+            absence: None,
+            rarity: None,
+            first_seen: None,
+            truncation: None,
+            hash_annotations: Vec::new(),
+            geo_annotations: Vec::new(),
+            ioc_matches: Vec::new(),
+            references: rule.references.clone(),
+            falsepositives: rule.falsepositives.clone(),
+            remediation: rule.remediation.clone(),
+        }
+    }
+}
+
+/// Group and time window of an aggregated alert.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AggregationBucket {
+    /// Values of the group_by fields (empty if not grouped)
+    pub group: std::collections::BTreeMap<String, String>,
+    /// Window start (ISO 8601, None for events without timestamps)
+    pub window_start: Option<String>,
+    /// Window end (ISO 8601, None for events without timestamps)
+    pub window_end: Option<String>,
+    /// Number of events in the bucket
+    pub count: usize,
+    /// Number of distinct values of the distinct_field (distinct aggregations only)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub distinct_count: Option<usize>,
+    /// Values of the aggregates used by the `having` condition
+    #[serde(default, skip_serializing_if = "std::collections::BTreeMap::is_empty")]
+    pub measures: std::collections::BTreeMap<String, usize>,
+}
+
+/// Shared key and time span of a correlated alert.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CorrelationMatch {
+    /// Values of the sequence's `by` fields
+    pub key: std::collections::BTreeMap<String, String>,
+    /// Step names, in the order of the evidence events
+    pub steps: Vec<String>,
+    /// Timestamp of the first step (ISO 8601)
+    pub first_seen: Option<String>,
+    /// Timestamp of the last step (ISO 8601)
+    pub last_seen: Option<String>,
+}
+
+/// Window and entity of an absence alert.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AbsenceMatch {
+    /// Value of the rule's `by` field (empty if not grouped)
+    pub entity: std::collections::BTreeMap<String, String>,
+    /// Window start (ISO 8601, None if the dataset has no timestamps)
+    pub window_start: Option<String>,
+    /// Window end (ISO 8601, None if the dataset has no timestamps)
+    pub window_end: Option<String>,
+    /// Matching events observed in the window
+    pub observed: usize,
+    /// Matching events expected in the window
+    pub expected: usize,
+}
+
+/// Value of a rarity alert.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RarityMatch {
+    pub field: String,
+    pub value: String,
+    /// Matching events with the value
+    pub count: usize,
+    pub threshold: usize,
+}
+
+/// Value of a first-seen alert.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct FirstSeenMatch {
+    pub field: String,
+    pub value: String,
+    /// Time of the value's first event (ISO 8601, None without timestamps)
+    pub first_seen: Option<String>,
+}
+
+/// Values tracked for one field by first-seen rules.
+#[derive(Debug, Serialize, Clone)]
+pub struct FirstSeenFieldInfo {
+    pub field: String,
+    pub value_count: usize,
+    /// Earliest tracked event of the field (ISO 8601)
+    pub tracking_since: Option<String>,
+}
+
+/// Summary of the alerts merged into one when a rule exceeded its cap.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AlertTruncation {
+    /// Alerts the rule produced
+    pub alert_count: usize,
+    /// The rule's `max_alerts_per_scan`
+    pub limit: usize,
+    /// Human-readable notice shown with the alert
+    pub notice: String,
+}
+
+/// Entity and contributing alerts of a meta-rule alert.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MetaMatch {
+    /// Values of the meta-rule's `by` fields
+    pub entity: std::collections::BTreeMap<String, String>,
+    /// IDs of the rules that alerted for the entity
+    pub rule_ids: Vec<String>,
+    /// Number of contributing alerts
+    pub alert_count: usize,
+}
+
+// ============================================================================
+// Hash Set Structures
+// ============================================================================
+
+/// Classification of a file hash.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HashClassification {
+    KnownGood,
+    KnownBad,
+    Unknown,
+}
+
+/// Metadata about an imported hash set.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct HashSetInfo {
+    /// Unique set name
+    pub name: String,
+    /// Classification applied to every hash in the set
+    pub classification: HashClassification,
+    /// Number of distinct hashes
+    pub hash_count: usize,
+    /// Import timestamp (ISO 8601)
+    pub imported_at: String,
+}
+
+/// Verdict for a hash found in alert evidence.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct HashAnnotation {
+    /// Evidence field the hash was found in
+    pub field: String,
+    /// Lowercase hex hash
+    pub hash: String,
+    /// Lookup verdict
+    pub verdict: HashClassification,
+    /// Name of the set the hash was found in (None if unknown)
+    pub source: Option<String>,
+}
+
+/// Metadata about an imported threat intel IOC list.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct IocListInfo {
+    /// Unique list name, used in `IN_LIST` conditions
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    /// Number of distinct indicators
+    pub indicator_count: usize,
+    /// Import timestamp (ISO 8601)
+    pub imported_at: String,
+}
+
+/// Result of sweeping the log library for the indicators of an IOC list.
+#[derive(Debug, Serialize, Clone)]
+pub struct IocSweepResult {
+    pub list: String,
+    /// Indicators found, most hits first
+    pub indicators: Vec<IocSweepHit>,
+    pub files_scanned: usize,
+    pub events_scanned: usize,
+    pub failed_files: Vec<FailedFileScan>,
+    pub sweep_time_ms: u64,
+}
+
+/// Occurrences of one indicator across the log library.
+#[derive(Debug, Serialize, Clone)]
+pub struct IocSweepHit {
+    pub indicator: String,
+    /// Number of events containing it, all files together
+    pub hit_count: usize,
+    pub files: Vec<IocFileHits>,
+}
+
+/// Occurrences of one indicator in one log file.
+#[derive(Debug, Serialize, Clone)]
+pub struct IocFileHits {
+    pub file_name: String,
+    pub file_path: String,
+    /// Number of events containing the indicator
+    pub hit_count: usize,
+    /// Fields it was found in
+    pub fields: Vec<String>,
+    /// First events containing it
+    pub sample_events: Vec<serde_json::Value>,
+}
+
+/// Summary of a lookup list.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct LookupListInfo {
+    /// List name, used in `IN_LIST` conditions
+    pub name: String,
+    pub entry_count: usize,
+    /// Last modification (ISO 8601)
+    pub modified: String,
+}
+
+/// A known IOC found in alert evidence.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct IocMatch {
+    /// Evidence field the indicator was found in
+    pub field: String,
+    pub value: String,
+    /// Name of the IOC list holding it
+    pub list: String,
+}
+
+/// Location of an IP address found in alert evidence.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct GeoAnnotation {
+    /// Evidence field the address was found in
+    pub field: String,
+    pub ip: String,
+    /// ISO country code (None if not in the country database)
+    pub country: Option<String>,
+    /// Autonomous system number (None if not in the ASN database)
+    pub asn: Option<u32>,
+    pub as_organization: Option<String>,
 }
 
 // ============================================================================
@@ -97,6 +710,8 @@ pub struct QueryResult {
     pub query: String,
     /// Column names from the result set
     pub columns: Vec<String>,
+    /// DuckDB type of each column (e.g. BIGINT, VARCHAR, TIMESTAMP)
+    pub column_types: Vec<String>,
     /// Rows as JSON values (each row is an object)
     pub rows: Vec<serde_json::Value>,
     /// Total row count returned
@@ -105,6 +720,90 @@ pub struct QueryResult {
     pub execution_time_ms: u64,
 }
 
+/// One page of an ad-hoc query's results.
+#[derive(Debug, Serialize, Clone)]
+pub struct QueryPage {
+    /// The query that was executed
+    pub query: String,
+    /// Column names from the result set
+    pub columns: Vec<String>,
+    /// DuckDB type of each column
+    pub column_types: Vec<String>,
+    /// Rows of this page as JSON values (each row is an object)
+    pub rows: Vec<serde_json::Value>,
+    /// Index of the first row of this page
+    pub offset: usize,
+    /// Page size used (after clamping)
+    pub limit: usize,
+    /// Whether rows follow this page
+    pub has_more: bool,
+    /// Execution time in milliseconds
+    pub execution_time_ms: u64,
+}
+
+// ============================================================================
+// Saved Query Structures
+// ============================================================================
+
+/// A named ad-hoc SQL query saved for reuse.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SavedQuery {
+    /// Unique identifier (UUID); empty when saving a new query
+    #[serde(default)]
+    pub id: String,
+    /// Unique display name
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    /// SQL text
+    pub sql: String,
+    /// Lowercase labels grouping queries into playbooks
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// `{{name}}` placeholders to fill in when running the query
+    #[serde(default)]
+    pub placeholders: Vec<String>,
+    /// Declared type and prompt of each placeholder, shown when the query is
+    /// run and checked before its values are substituted
+    #[serde(default)]
+    pub variables: Vec<QueryVariable>,
+    /// Creation timestamp (ISO 8601)
+    #[serde(default)]
+    pub created_at: String,
+    /// Last modification timestamp (ISO 8601)
+    #[serde(default)]
+    pub updated_at: String,
+}
+
+/// Type of a saved query variable. Values are checked, or converted from
+/// text, before they are substituted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VariableType {
+    #[default]
+    String,
+    Integer,
+    Number,
+    Boolean,
+    /// RFC 3339 date and time, substituted in UTC
+    Timestamp,
+    /// IPv4 or IPv6 address
+    Ip,
+    /// Values for `IN`, given as an array or comma-separated text
+    List,
+}
+
+/// A declared `{{name}}` variable of a saved query.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct QueryVariable {
+    pub name: String,
+    #[serde(rename = "type", default)]
+    pub var_type: VariableType,
+    /// Prompt shown when asking for the value
+    #[serde(default)]
+    pub prompt: String,
+}
+
 // ============================================================================
 // Error Types
 // ============================================================================
@@ -123,6 +822,9 @@ pub enum SiemError {
 
     #[error("Serialization error: {0}")]
     Serialization(String),
+
+    #[error("Workspace locked: {0}")]
+    Locked(String),
 }
 
 // Implement conversion for Tauri IPC
@@ -153,6 +855,27 @@ pub struct LogFileInfo {
     /// Log format type (CloudTrail or FlatJson)
     #[serde(default)]
     pub log_type: Option<LogType>,
+    /// Registered in place: `path` is outside the logs folder
+    #[serde(default)]
+    pub referenced: bool,
+    /// SHA-256 recorded when the file entered the library (hex)
+    #[serde(default)]
+    pub sha256: Option<String>,
+}
+
+/// Result of re-hashing a log file against its recorded hash.
+#[derive(Debug, Serialize, Clone)]
+pub struct HashVerification {
+    pub filename: String,
+    /// Hash recorded at import; None for files imported before hashing
+    pub recorded_sha256: Option<String>,
+    /// When the recorded hash was computed (ISO 8601)
+    pub hashed_at: Option<String>,
+    pub current_sha256: String,
+    /// Whether the file still has its recorded hash (false without one)
+    pub intact: bool,
+    /// When the file was re-hashed (ISO 8601)
+    pub verified_at: String,
 }
 
 /// Summary of batch import operation.
@@ -174,8 +897,22 @@ pub struct ImportSummary {
 // Scan Response Structures
 // ============================================================================
 
+/// Time window of the events to view or scan, e.g. an incident window.
+/// Bounds are inclusive timestamps (RFC 3339 or "YYYY-MM-DD HH:MM:SS", UTC);
+/// events without a parseable timestamp fall outside any window.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct TimeRange {
+    #[serde(default)]
+    pub start_time: Option<String>,
+    #[serde(default)]
+    pub end_time: Option<String>,
+    /// Timestamp field of the events (defaults to the log type's usual fields)
+    #[serde(default)]
+    pub timestamp_field: Option<String>,
+}
+
 /// Response from a scan operation.
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct ScanResponse {
     /// List of alerts generated
     pub alerts: Vec<AlertEvent>,
@@ -183,6 +920,55 @@ pub struct ScanResponse {
     pub rules_evaluated: usize,
     /// Total scan time in milliseconds
     pub scan_time_ms: u64,
+    /// Alerts dropped by the suppression list
+    #[serde(default)]
+    pub suppressed_alerts: usize,
+    /// Outcome of each rule run against the file
+    #[serde(default)]
+    pub rule_results: Vec<RuleRunStats>,
+    /// Deliveries of the alerts to the configured sinks
+    #[serde(default)]
+    pub routing: RoutingSummary,
+}
+
+/// Summary of alert deliveries after routing (see `alert_router`).
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct RoutingSummary {
+    /// Number of successful sink deliveries
+    pub delivered: usize,
+    /// Error messages from failed deliveries
+    pub errors: Vec<String>,
+}
+
+/// Outcome of one rule in a scan of a file.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RuleRunStats {
+    pub rule_id: String,
+    pub rule_title: String,
+    /// Events the rule matched
+    pub match_count: usize,
+    /// Alerts the rule produced
+    pub alert_count: usize,
+    /// Time spent evaluating the rule in milliseconds (DuckDB prefiltering,
+    /// shared by all rules, is not included)
+    pub execution_time_ms: f64,
+    /// Why the rule failed; it produced no alerts
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl RuleRunStats {
+    /// Stats of a rule that hasn't matched anything yet.
+    pub fn from_rule(rule: &RuleYaml, error: Option<String>) -> Self {
+        RuleRunStats {
+            rule_id: rule.id.clone(),
+            rule_title: rule.title.clone(),
+            match_count: 0,
+            alert_count: 0,
+            execution_time_ms: 0.0,
+            error,
+        }
+    }
 }
 
 /// Response from a bulk scan operation (scanning all logs in library).
@@ -198,8 +984,14 @@ pub struct BulkScanResponse {
     pub rules_evaluated: usize,
     /// Results grouped by file
     pub file_results: Vec<FileScanResult>,
+    /// Meta-rule alerts, correlating the alerts of all files
+    pub meta_alerts: Vec<AlertEvent>,
     /// Files that failed to scan
     pub failed_files: Vec<FailedFileScan>,
+    /// Alerts dropped by the suppression list
+    pub suppressed_alerts: usize,
+    /// Deliveries of the alerts to the configured sinks
+    pub routing: RoutingSummary,
 }
 
 /// Scan result for a single file in a bulk scan.
@@ -213,6 +1005,8 @@ pub struct FileScanResult {
     pub alerts: Vec<AlertEvent>,
     /// Scan time for this file in milliseconds
     pub scan_time_ms: u64,
+    /// Outcome of each rule run against the file
+    pub rule_results: Vec<RuleRunStats>,
 }
 
 /// Information about a file that failed to scan.
@@ -226,6 +1020,613 @@ pub struct FailedFileScan {
     pub error: String,
 }
 
+/// Response from scanning a selected set of files of mixed log types.
+#[derive(Debug, Serialize)]
+pub struct LogSetScanResponse {
+    /// Unified, deduplicated alerts across all files
+    pub alerts: Vec<AlertEvent>,
+    /// Files that were loaded and scanned
+    pub files_scanned: Vec<ScannedFile>,
+    /// Files that failed to load
+    pub failed_files: Vec<FailedFileScan>,
+    /// Number of rules considered for routing
+    pub rules_evaluated: usize,
+    /// Files listed more than once (the same file on disk), scanned once
+    pub duplicate_files_skipped: usize,
+    /// Alerts dropped by the suppression list
+    pub suppressed_alerts: usize,
+    /// Total scan time in milliseconds
+    pub scan_time_ms: u64,
+    /// Deliveries of the alerts to the configured sinks
+    pub routing: RoutingSummary,
+}
+
+/// A file scanned as part of a log-set scan.
+#[derive(Debug, Serialize, Clone)]
+pub struct ScannedFile {
+    /// Filename without path
+    pub file_name: String,
+    /// Full path to the file
+    pub file_path: String,
+    /// Log type used to parse the file
+    pub log_type: LogType,
+    /// Number of events loaded from the file
+    pub events_loaded: usize,
+    /// Number of rules routed to this file
+    pub rules_applied: usize,
+    /// Outcome of each rule run against the file
+    pub rule_results: Vec<RuleRunStats>,
+}
+
+// ============================================================================
+// Scan History Structures
+// ============================================================================
+
+/// Alert counts recorded for one completed scan.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ScanHistoryEntry {
+    /// Unique scan ID (UUID)
+    pub scan_id: String,
+    /// When the scan finished (ISO 8601)
+    pub scanned_at: String,
+    /// Paths of the scanned log files
+    pub files: Vec<String>,
+    /// Total number of alerts
+    pub total_alerts: usize,
+    /// Alerts per rule ID
+    pub by_rule: std::collections::BTreeMap<String, usize>,
+    /// Alerts per severity
+    pub by_severity: std::collections::BTreeMap<String, usize>,
+    /// Rule titles at scan time, keyed by rule ID
+    #[serde(default)]
+    pub rule_titles: std::collections::BTreeMap<String, String>,
+    /// IDs of the active rules the scan ran
+    #[serde(default)]
+    pub rule_ids: Vec<String>,
+    /// Scan duration in milliseconds
+    #[serde(default)]
+    pub duration_ms: u64,
+    /// The alerts, for comparing scans (empty in entries recorded before
+    /// alerts were kept)
+    #[serde(default)]
+    pub alerts: Vec<ScanAlertRecord>,
+}
+
+/// An alert as recorded in the scan history.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ScanAlertRecord {
+    /// Identity of the alert across scans (see `scan_history::alert_key`)
+    pub key: String,
+    pub rule_id: String,
+    pub rule_title: String,
+    pub severity: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_file: Option<String>,
+    pub match_count: usize,
+}
+
+/// Alerts that appeared or disappeared between two scans.
+#[derive(Debug, Serialize, Clone)]
+pub struct ScanDiff {
+    /// The earlier scan
+    pub base_scan_id: String,
+    /// The later scan
+    pub compare_scan_id: String,
+    /// Whether both scans covered the same files
+    pub same_files: bool,
+    /// Alerts of the later scan missing from the earlier one
+    pub new_alerts: Vec<ScanAlertRecord>,
+    /// Alerts of the earlier scan missing from the later one
+    pub resolved_alerts: Vec<ScanAlertRecord>,
+    /// Alerts present in both scans
+    pub unchanged_alerts: usize,
+    /// Change in alerts per rule ID (rules without change are omitted)
+    pub rule_deltas: std::collections::BTreeMap<String, i64>,
+}
+
+/// Time granularity of alert trends.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TrendGranularity {
+    /// One bucket per calendar day (UTC)
+    Day,
+    /// One bucket per scan
+    Scan,
+}
+
+/// Alert counts for one period, with changes relative to the previous period.
+#[derive(Debug, Serialize, Clone)]
+pub struct TrendBucket {
+    /// Day (YYYY-MM-DD) or scan timestamp
+    pub period: String,
+    /// Number of scans in the period
+    pub scan_count: usize,
+    /// Total number of alerts
+    pub total_alerts: usize,
+    /// Alerts per rule ID
+    pub by_rule: std::collections::BTreeMap<String, usize>,
+    /// Alerts per severity
+    pub by_severity: std::collections::BTreeMap<String, usize>,
+    /// Change in total alerts since the previous period (None for the first)
+    pub total_delta: Option<i64>,
+    /// Change per rule ID since the previous period (rules absent in both are omitted)
+    pub rule_deltas: std::collections::BTreeMap<String, i64>,
+    /// Change per severity since the previous period
+    pub severity_deltas: std::collections::BTreeMap<String, i64>,
+}
+
+/// Alert trends across the persisted scan history.
+#[derive(Debug, Serialize, Clone)]
+pub struct AlertTrends {
+    pub granularity: TrendGranularity,
+    /// Periods in chronological order
+    pub buckets: Vec<TrendBucket>,
+    /// Latest known title per rule ID
+    pub rule_titles: std::collections::BTreeMap<String, String>,
+}
+
+/// Analyst verdict on an alert.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TriageStatus {
+    /// Malicious or unwanted activity
+    TruePositive,
+    /// Real activity the rule targets, but expected here
+    Benign,
+    /// The rule matched activity it shouldn't
+    FalsePositive,
+}
+
+/// Triage verdict of an alert, kept across scans.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AlertTriage {
+    /// Identity of the alert across scans (see `scan_history::alert_key`)
+    pub key: String,
+    pub rule_id: String,
+    pub status: TriageStatus,
+    /// Analyst comment
+    #[serde(default)]
+    pub note: String,
+    /// When the verdict was set (ISO 8601)
+    pub updated_at: String,
+}
+
+/// Lifetime activity of a rule across the scan history.
+#[derive(Debug, Serialize, Clone)]
+pub struct RuleStatistics {
+    pub rule_id: String,
+    pub rule_title: String,
+    /// Current status of the rule (None if it no longer exists)
+    pub status: Option<String>,
+    /// Recorded scans that ran the rule
+    pub scans_run: usize,
+    /// Recorded scans where the rule raised alerts
+    pub scans_fired: usize,
+    pub total_alerts: usize,
+    /// Events matched by the rule's alerts
+    pub total_matches: usize,
+    /// First and last scan where the rule raised alerts (ISO 8601)
+    pub first_fired: Option<String>,
+    pub last_fired: Option<String>,
+    /// Distinct alerts of the rule with a triage verdict
+    pub triaged_alerts: usize,
+    pub false_positives: usize,
+    /// Share of the triaged alerts that are false positives (None if none
+    /// was triaged)
+    pub false_positive_rate: Option<f64>,
+}
+
+// ============================================================================
+// ATT&CK Coverage Structures
+// ============================================================================
+
+/// Rules covering an ATT&CK technique, and those that fired in the last scan.
+#[derive(Debug, Serialize, Clone)]
+pub struct TechniqueCoverage {
+    /// Technique ID (e.g. "T1078")
+    pub technique: String,
+    /// Active rules detecting the technique
+    pub rule_ids: Vec<String>,
+    /// Rules for the technique that raised alerts in the last scan
+    pub fired_rule_ids: Vec<String>,
+}
+
+/// Techniques covered under one ATT&CK tactic.
+#[derive(Debug, Serialize, Clone)]
+pub struct TacticCoverage {
+    /// Tactic as used in tags (e.g. "initial_access"), or "unknown"
+    pub tactic: String,
+    pub techniques: Vec<TechniqueCoverage>,
+}
+
+/// ATT&CK coverage matrix of the rule set.
+#[derive(Debug, Serialize, Clone)]
+pub struct AttackCoverage {
+    /// Tactics in matrix order (only those with techniques)
+    pub tactics: Vec<TacticCoverage>,
+    /// Distinct techniques detected by active rules
+    pub covered_techniques: usize,
+    /// Distinct techniques whose rules fired in the last scan
+    pub fired_techniques: usize,
+    pub last_scan_id: Option<String>,
+    pub last_scan_at: Option<String>,
+}
+
+// ============================================================================
+// Event Annotation Structures
+// ============================================================================
+
+/// A flag placed on an individual event during review.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct EventAnnotation {
+    /// Unique identifier (UUID v4)
+    pub id: String,
+    /// Full path to the log file containing the event
+    pub file_path: String,
+    /// Position of the event within the file
+    pub record_index: usize,
+    /// Short label (e.g., "suspicious", "benign", "follow-up")
+    pub label: String,
+    /// Analyst comment
+    #[serde(default)]
+    pub comment: String,
+    /// Creation timestamp (ISO 8601)
+    pub created_at: String,
+    /// Last update timestamp (ISO 8601)
+    pub updated_at: String,
+    /// Snapshot of the flagged event
+    pub event: serde_json::Value,
+}
+
+// ============================================================================
+// Suppression Structures
+// ============================================================================
+
+/// An entry of the global suppression list.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SuppressionEntry {
+    /// Unique identifier (UUID v4)
+    pub id: String,
+    /// Rule whose alerts are suppressed, or "*" for every rule
+    pub rule_id: String,
+    /// Patterns every evidence event must match; none = the whole rule
+    #[serde(default)]
+    pub patterns: Vec<SuppressionPattern>,
+    /// Expiry date (YYYY-MM-DD, inclusive) or RFC 3339 timestamp
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<String>,
+    /// Why the alerts are suppressed
+    #[serde(default)]
+    pub reason: String,
+    /// Creation timestamp (ISO 8601)
+    pub created_at: String,
+}
+
+/// A field/value pattern of a suppression entry.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SuppressionPattern {
+    /// Field path (dot notation, e.g. "userIdentity.userName")
+    pub field: String,
+    /// Value pattern, case-insensitive, with * and ? wildcards
+    pub pattern: String,
+}
+
+// ============================================================================
+// Case Management Structures
+// ============================================================================
+
+/// Lifecycle status of a case.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CaseStatus {
+    Open,
+    InProgress,
+    Closed,
+}
+
+/// Investigation case grouping findings across scans and log files.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Case {
+    /// Unique identifier (UUID v4)
+    pub id: String,
+    /// Case name
+    pub name: String,
+    /// Free-form description
+    #[serde(default)]
+    pub description: String,
+    /// Current status
+    pub status: CaseStatus,
+    /// Creation timestamp (ISO 8601)
+    pub created_at: String,
+    /// Last update timestamp (ISO 8601)
+    pub updated_at: String,
+    /// Alerts attached to the case
+    #[serde(default)]
+    pub alerts: Vec<AlertEvent>,
+    /// Individual events attached to the case
+    #[serde(default)]
+    pub events: Vec<CaseEvent>,
+    /// Log files attached to the case (full paths)
+    #[serde(default)]
+    pub log_files: Vec<String>,
+    /// Chronological record of case activity
+    #[serde(default)]
+    pub timeline: Vec<CaseTimelineEntry>,
+}
+
+/// Event attached to a case, with a snapshot of the record.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CaseEvent {
+    /// Full path to the log file containing the event
+    pub file_path: String,
+    /// Position of the event within the file
+    pub record_index: usize,
+    /// Analyst note
+    #[serde(default)]
+    pub note: String,
+    /// Snapshot of the event
+    pub event: serde_json::Value,
+}
+
+/// Kind of case timeline entry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CaseTimelineKind {
+    Created,
+    AlertAttached,
+    EventAttached,
+    LogFileAttached,
+    StatusChanged,
+    Note,
+}
+
+/// Entry in a case timeline.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CaseTimelineEntry {
+    /// When the entry was recorded (ISO 8601)
+    pub timestamp: String,
+    /// Entry kind
+    pub kind: CaseTimelineKind,
+    /// Human-readable description
+    pub message: String,
+}
+
+/// Case overview for listings.
+#[derive(Debug, Serialize, Clone)]
+pub struct CaseSummary {
+    pub id: String,
+    pub name: String,
+    pub status: CaseStatus,
+    pub created_at: String,
+    pub updated_at: String,
+    pub alert_count: usize,
+    pub event_count: usize,
+    pub log_file_count: usize,
+}
+
+// ============================================================================
+// Log Integrity Structures
+// ============================================================================
+
+/// Kind of log integrity finding.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IntegrityFindingKind {
+    /// Silent period much longer than the usual event spacing
+    Gap,
+    /// Run of events older than events before them
+    OutOfOrder,
+    /// Record numbers skipped
+    SequenceGap,
+    /// Record number repeated or reset
+    SequenceRegression,
+    /// Coverage starts after the expected start
+    TruncatedStart,
+    /// Coverage ends before the expected end
+    TruncatedEnd,
+    /// Events without a parseable timestamp
+    MissingTimestamps,
+}
+
+/// Suspicious discontinuity in a log file.
+#[derive(Debug, Serialize, Clone)]
+pub struct IntegrityFinding {
+    pub kind: IntegrityFindingKind,
+    /// Severity level: "low", "medium", "high"
+    pub severity: String,
+    /// Human-readable description
+    pub message: String,
+    /// Start of the affected period (ISO 8601)
+    pub start: Option<String>,
+    /// End of the affected period (ISO 8601)
+    pub end: Option<String>,
+    /// First affected record, in file order
+    pub record_index: Option<usize>,
+    /// Number of affected (or missing) records
+    pub event_count: usize,
+}
+
+/// Result of a log integrity analysis.
+#[derive(Debug, Serialize, Clone)]
+pub struct IntegrityReport {
+    pub event_count: usize,
+    pub timestamped_events: usize,
+    /// Earliest event timestamp (ISO 8601)
+    pub first_event: Option<String>,
+    /// Latest event timestamp (ISO 8601)
+    pub last_event: Option<String>,
+    /// Median spacing between consecutive events
+    pub median_interval_secs: Option<f64>,
+    pub findings: Vec<IntegrityFinding>,
+}
+
+// ============================================================================
+// Event Timeline Structures
+// ============================================================================
+
+/// Bucket width of an event timeline.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TimelineBucket {
+    Minute,
+    Hour,
+    Day,
+}
+
+/// Number of events in one timeline bucket.
+#[derive(Debug, Serialize, Clone)]
+pub struct TimelineCount {
+    /// Bucket start (ISO 8601, UTC)
+    pub start: String,
+    pub count: usize,
+}
+
+/// Event counts over time for a log file.
+#[derive(Debug, Serialize, Clone)]
+pub struct EventTimeline {
+    pub bucket: TimelineBucket,
+    /// Timestamp field the events were bucketed by, if one was found
+    pub timestamp_field: Option<String>,
+    /// Non-empty buckets in chronological order
+    pub buckets: Vec<TimelineCount>,
+    pub total_events: usize,
+    /// Events passing the filter
+    pub matched_events: usize,
+    /// Matched events without a parseable timestamp
+    pub untimed_events: usize,
+}
+
+// ============================================================================
+// Field Statistics Structures
+// ============================================================================
+
+/// Number of occurrences of one field value.
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct FieldValueCount {
+    pub value: String,
+    pub count: usize,
+}
+
+/// A field value seen fewer times than a threshold.
+#[derive(Debug, Serialize, Clone)]
+pub struct RareValue {
+    pub value: String,
+    pub count: usize,
+    /// The events with the value
+    pub events: Vec<serde_json::Value>,
+}
+
+/// Value statistics of a field across a log file.
+#[derive(Debug, Serialize, Clone)]
+pub struct FieldStats {
+    pub field: String,
+    pub total_events: usize,
+    /// Events without a value for the field
+    pub null_count: usize,
+    pub null_percentage: f64,
+    /// Number of distinct values
+    pub cardinality: usize,
+    /// Most frequent values, most frequent first
+    pub top_values: Vec<FieldValueCount>,
+}
+
+/// A field of an inferred log schema, with its nested fields.
+#[derive(Debug, Serialize, Clone)]
+pub struct SchemaField {
+    pub name: String,
+    /// Dotted path, as used in rule conditions
+    pub path: String,
+    /// JSON types seen ("string", "number", "object", "array<string>", ...)
+    pub types: Vec<String>,
+    /// A few distinct values seen
+    pub sample_values: Vec<String>,
+    /// Events with the field
+    pub event_count: usize,
+    pub presence_percentage: f64,
+    /// Fields nested in the field's objects, by name
+    pub children: Vec<SchemaField>,
+}
+
+/// Inferred field tree of a log file.
+#[derive(Debug, Serialize, Clone)]
+pub struct LogSchema {
+    pub total_events: usize,
+    /// Fields at every level of the tree
+    pub field_count: usize,
+    /// Top-level fields, by name
+    pub fields: Vec<SchemaField>,
+}
+
+// ============================================================================
+// Entity Summary Structures
+// ============================================================================
+
+/// Everything a log file says about one entity (a user, an IP address, ...).
+#[derive(Debug, Serialize, Clone)]
+pub struct EntitySummary {
+    pub field: String,
+    pub value: String,
+    /// Events where the field has the value
+    pub event_count: usize,
+    pub total_events: usize,
+    /// Earliest event of the entity (ISO 8601)
+    pub first_seen: Option<String>,
+    /// Latest event of the entity (ISO 8601)
+    pub last_seen: Option<String>,
+    /// The entity's events over time
+    pub timeline: EventTimeline,
+    /// Actions taken (event names), most frequent first
+    pub actions: Vec<FieldValueCount>,
+    pub distinct_actions: usize,
+    /// Users in the entity's events, most frequent first
+    pub related_users: Vec<FieldValueCount>,
+    /// IP addresses in the entity's events, most frequent first
+    pub related_ips: Vec<FieldValueCount>,
+}
+
+/// An event of a log file matching a keyword search.
+#[derive(Debug, Serialize, Clone)]
+pub struct LogSearchHit {
+    pub filename: String,
+    pub log_path: String,
+    /// Position of the event in its file (0-based)
+    pub event_index: usize,
+    pub event: serde_json::Value,
+}
+
+/// Result of a keyword search across the log files.
+#[derive(Debug, Serialize, Clone)]
+pub struct LogSearchResult {
+    pub keyword: String,
+    /// Matching events, in file order
+    pub hits: Vec<LogSearchHit>,
+    /// Matching events, including those past the hit limit
+    pub total_matches: usize,
+    pub files_searched: usize,
+    pub events_searched: usize,
+    /// Whether hits were dropped past the hit limit
+    pub truncated: bool,
+    /// Files that could not be read, with the reason
+    pub failed_files: Vec<String>,
+}
+
+/// One page of a log file's events.
+#[derive(Debug, Serialize, Clone)]
+pub struct EventPage {
+    /// Events of this page, in file or sort order
+    pub events: Vec<serde_json::Value>,
+    /// Index of the first event of this page among the matching events
+    pub offset: usize,
+    /// Page size used (after clamping)
+    pub limit: usize,
+    /// Events matching the filter
+    pub matched_events: usize,
+    /// Events in the file
+    pub total_events: usize,
+    /// Whether matching events follow this page
+    pub has_more: bool,
+}
+
 // ============================================================================
 // Rule Testing Structures
 // ============================================================================
@@ -259,3 +1660,75 @@ pub struct ValidationResult {
     pub error_position: Option<usize>,
     pub suggestions: Vec<String>,
 }
+
+/// Cost and matches of one rule's detection logic over a log file
+#[derive(Debug, Serialize, Clone)]
+pub struct RuleProfile {
+    pub rule_id: String,
+    pub rule_title: String,
+    /// Time spent evaluating the rule in milliseconds
+    pub execution_time_ms: f64,
+    /// Average time per event in microseconds
+    pub time_per_event_us: f64,
+    pub match_count: usize,
+    pub match_percentage: f64,
+    /// Whether the rule matches an excessive share of the events
+    pub noisy: bool,
+}
+
+/// Performance report of the active rules against a log file
+#[derive(Debug, Serialize, Clone)]
+pub struct RuleProfileReport {
+    pub log_path: String,
+    pub total_events: usize,
+    /// Wall time of the profiling run, reading the file included
+    pub total_time_ms: f64,
+    /// Time spent evaluating rules
+    pub rules_evaluation_time_ms: f64,
+    /// Rules, slowest first
+    pub rules: Vec<RuleProfile>,
+    /// IDs of the noisy rules
+    pub noisy_rules: Vec<String>,
+}
+
+/// Kind of analyst action recorded in the audit log.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditAction {
+    /// A rule was created, edited, imported or had its status changed
+    RuleSaved,
+    RuleDeleted,
+    ScanRun,
+    QueryRun,
+    /// An alert's triage verdict was set or cleared
+    AlertStatusChanged,
+    /// A workspace snapshot or an app data backup was restored
+    DataRestored,
+}
+
+/// Who performed an audited action.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditActor {
+    /// The analyst, in the app (including its folder watch and scan jobs)
+    #[default]
+    App,
+    /// A client of the local REST API
+    Api,
+}
+
+/// One entry of the analyst action audit log.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditEntry {
+    /// When the action happened (ISO 8601)
+    pub timestamp: String,
+    #[serde(default)]
+    pub actor: AuditActor,
+    pub action: AuditAction,
+    /// What the action applied to: rule IDs, scanned files, the query or the
+    /// alert's rule
+    pub target: String,
+    /// Action-specific details (new status, alert counts, row count, error...)
+    #[serde(default)]
+    pub details: serde_json::Value,
+}
@@ -0,0 +1,97 @@
+//! Okta System Log profile (`LogType::Okta`).
+//!
+//! The System Log API (`/api/v1/logs`) and its exports return a JSON array,
+//! or NDJSON, of events such as:
+//!
+//! ```text
+//! {"published": "...", "eventType": "user.session.start",
+//!  "actor": {"alternateId": "alice@example.com", "type": "User"},
+//!  "client": {"ipAddress": "203.0.113.7", "userAgent": {"rawUserAgent": "..."}},
+//!  "outcome": {"result": "FAILURE", "reason": "INVALID_CREDENTIALS"},
+//!  "target": [{"type": "AppInstance", "displayName": "Salesforce"}, ...]}
+//! ```
+//!
+//! `target` is an array: conditions match it element-wise, written
+//! `target[].type = 'AppInstance'` (or `target.type`), and `target[0].type`
+//! reads the first target only.
+
+/// Fields of Okta System Log events.
+pub const FIELDS: &[&str] = &[
+    "published",
+    "eventType",
+    "displayMessage",
+    "severity",
+    "actor.alternateId",
+    "actor.displayName",
+    "actor.type",
+    "actor.id",
+    "client.ipAddress",
+    "client.userAgent.rawUserAgent",
+    "client.userAgent.os",
+    "client.userAgent.browser",
+    "client.device",
+    "client.geographicalContext.country",
+    "client.geographicalContext.city",
+    "outcome.result",
+    "outcome.reason",
+    "target.type",
+    "target.alternateId",
+    "target.displayName",
+    "target.id",
+    "authenticationContext.credentialType",
+    "authenticationContext.externalSessionId",
+    "securityContext.isProxy",
+    "securityContext.asOrg",
+    "debugContext.debugData.behaviors",
+    "debugContext.debugData.threatSuspected",
+    "transaction.id",
+    "uuid",
+];
+
+/// Whether an event is an Okta System Log event.
+pub fn is_okta_event(event: &serde_json::Value) -> bool {
+    event.get("eventType").is_some_and(|t| t.is_string())
+        && event.get("published").is_some()
+        && event.get("actor").is_some_and(|actor| actor.is_object())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db_engine;
+
+    #[test]
+    fn test_okta_events_and_targets() {
+        let event = serde_json::json!({
+            "published": "2024-03-01T10:01:02.000Z",
+            "eventType": "user.authentication.sso",
+            "actor": { "alternateId": "alice@example.com", "type": "User" },
+            "outcome": { "result": "SUCCESS" },
+            "target": [
+                { "type": "AppInstance", "displayName": "Salesforce" },
+                { "type": "AppUser", "displayName": "alice" }
+            ]
+        });
+        assert!(is_okta_event(&event));
+        assert!(!is_okta_event(
+            &serde_json::json!({ "eventType": "x", "published": "y" })
+        ));
+
+        assert!(db_engine::matches_condition(
+            &event,
+            "eventType = 'user.authentication.sso' AND target[].type = 'AppUser'"
+        ));
+        assert!(db_engine::matches_condition(
+            &event,
+            "target[0].displayName = 'Salesforce'"
+        ));
+        assert!(!db_engine::matches_condition(
+            &event,
+            "target[1].displayName = 'Salesforce'"
+        ));
+        assert_eq!(
+            db_engine::get_field_values(&event, "target[].type"),
+            vec!["AppInstance", "AppUser"]
+        );
+    }
+}
@@ -0,0 +1,364 @@
+//! Parser plugins for custom log formats.
+//!
+//! A parser plugin is a WebAssembly module dropped into the `plugins/`
+//! directory of the application's data directory. Files of the log type
+//! `{"plugin": "<name>"}` are read through `plugins/<name>.wasm`, which turns
+//! their raw lines into JSON events, so proprietary appliance formats can be
+//! scanned without changing the application.
+//!
+//! Plugins run sandboxed: they get no imports (no file, network or clock
+//! access), a bounded amount of fuel per chunk and a capped linear memory, so
+//! a plugin stuck in a loop or growing without bound fails the read instead
+//! of hanging it or exhausting the machine's memory. A plugin exports:
+//!
+//! - `memory`: its linear memory;
+//! - `alloc(len: i32) -> i32`: a buffer of `len` bytes for the input;
+//! - `parse(ptr: i32, len: i32) -> i64`: parse a chunk of whole lines (UTF-8,
+//!   each ending with `\n`) and return `(out_ptr << 32) | out_len`, the
+//!   location of its output: a JSON array of events or NDJSON (0 = none);
+//! - `finish() -> i64` (optional): same output, called once at the end of the
+//!   file for plugins that buffer multi-line records across chunks.
+//!
+//! One instance reads a whole file, so plugins may keep state across chunks.
+
+use serde::Serialize;
+use std::fs;
+use std::io::BufRead;
+use std::path::PathBuf;
+use std::sync::{Mutex, OnceLock};
+
+use crate::app_data;
+use crate::models::SiemError;
+
+/// Input handed to a plugin at once, at most (whole lines, so a longer line
+/// is passed alone).
+const PLUGIN_CHUNK_BYTES: usize = 1024 * 1024;
+
+/// Fuel (roughly, wasm instructions) a plugin may burn per input byte.
+const FUEL_PER_BYTE: u64 = 2_000;
+
+/// Fuel for a call regardless of its input (covers `finish`).
+const BASE_FUEL: u64 = 100_000_000;
+
+/// Linear memory a plugin may use, at most.
+const MAX_PLUGIN_MEMORY_BYTES: usize = 256 * 1024 * 1024;
+
+/// A parser plugin found in the plugins directory.
+#[derive(Debug, Serialize, Clone)]
+pub struct ParserPlugin {
+    /// Name used in the log type (file stem)
+    pub name: String,
+    pub path: String,
+    pub size_bytes: u64,
+    /// Why the module can't be used, if it can't
+    pub error: Option<String>,
+}
+
+/// Get the plugins directory, creating it if needed.
+pub fn get_plugins_dir(app_handle: &tauri::AppHandle) -> Result<PathBuf, SiemError> {
+    app_data::app_data_subdir(app_handle, "plugins")
+}
+
+/// Directory plugins are loaded from (set at startup).
+fn plugin_dir() -> &'static Mutex<Option<PathBuf>> {
+    static DIR: OnceLock<Mutex<Option<PathBuf>>> = OnceLock::new();
+    DIR.get_or_init(|| Mutex::new(None))
+}
+
+/// Load parser plugins from a directory.
+pub fn register_plugin_dir(dir: PathBuf) {
+    if let Ok(mut current) = plugin_dir().lock() {
+        *current = Some(dir);
+    }
+}
+
+/// Validate a plugin name (used as a filename).
+pub fn validate_plugin_name(name: &str) -> Result<(), SiemError> {
+    if name.is_empty()
+        || !name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+    {
+        return Err(SiemError::FileIO(format!(
+            "Invalid plugin name '{}': use letters, digits, '_' and '-'",
+            name
+        )));
+    }
+    Ok(())
+}
+
+fn plugin_path(name: &str) -> Result<PathBuf, SiemError> {
+    validate_plugin_name(name)?;
+    let dir = plugin_dir()
+        .lock()
+        .ok()
+        .and_then(|dir| dir.clone())
+        .ok_or_else(|| SiemError::FileIO("Plugins directory is not available".to_string()))?;
+    Ok(dir.join(format!("{}.wasm", name)))
+}
+
+/// List the plugins of the plugins directory, checking their exports.
+pub fn list_plugins(app_handle: &tauri::AppHandle) -> Result<Vec<ParserPlugin>, SiemError> {
+    let dir = get_plugins_dir(app_handle)?;
+    let entries = fs::read_dir(&dir)
+        .map_err(|e| SiemError::FileIO(format!("Cannot read plugins dir: {}", e)))?;
+
+    let mut plugins: Vec<ParserPlugin> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "wasm"))
+        .filter_map(|path| {
+            let name = path.file_stem()?.to_string_lossy().to_string();
+            let error = validate_plugin_name(&name)
+                .and_then(|_| PluginInstance::load(&path))
+                .err()
+                .map(|e| e.to_string());
+            Some(ParserPlugin {
+                name,
+                size_bytes: fs::metadata(&path).map(|m| m.len()).unwrap_or(0),
+                path: path.to_string_lossy().to_string(),
+                error,
+            })
+        })
+        .collect();
+    plugins.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(plugins)
+}
+
+fn plugin_error(context: &str, e: impl std::fmt::Display) -> SiemError {
+    SiemError::Query(format!("Parser plugin {}: {}", context, e))
+}
+
+/// A plugin instantiated for reading one file.
+struct PluginInstance {
+    store: wasmi::Store<wasmi::StoreLimits>,
+    memory: wasmi::Memory,
+    alloc: wasmi::TypedFunc<i32, i32>,
+    parse: wasmi::TypedFunc<(i32, i32), i64>,
+    finish: Option<wasmi::TypedFunc<(), i64>>,
+}
+
+impl PluginInstance {
+    fn load(path: &std::path::Path) -> Result<Self, SiemError> {
+        let bytes = fs::read(path).map_err(|e| plugin_error("cannot be read", e))?;
+        Self::from_bytes(&bytes)
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Result<Self, SiemError> {
+        let mut config = wasmi::Config::default();
+        config.consume_fuel(true);
+        let engine = wasmi::Engine::new(&config);
+        let module =
+            wasmi::Module::new(&engine, bytes).map_err(|e| plugin_error("is invalid", e))?;
+
+        // No imports: plugins can't reach anything outside their memory, and
+        // growing it past the cap traps rather than returning -1
+        let limits = wasmi::StoreLimitsBuilder::new()
+            .memory_size(MAX_PLUGIN_MEMORY_BYTES)
+            .memories(1)
+            .instances(1)
+            .trap_on_grow_failure(true)
+            .build();
+        let mut store = wasmi::Store::new(&engine, limits);
+        store.limiter(|limits| limits);
+        store
+            .add_fuel(BASE_FUEL)
+            .map_err(|e| plugin_error("cannot be started", e))?;
+        let linker = wasmi::Linker::<wasmi::StoreLimits>::new(&engine);
+        let instance = linker
+            .instantiate(&mut store, &module)
+            .and_then(|instance| instance.start(&mut store))
+            .map_err(|e| plugin_error("cannot be started", e))?;
+
+        let memory = instance
+            .get_memory(&store, "memory")
+            .ok_or_else(|| plugin_error("is invalid", "missing `memory` export"))?;
+        let alloc = instance
+            .get_typed_func::<i32, i32>(&store, "alloc")
+            .map_err(|e| plugin_error("is invalid (alloc)", e))?;
+        let parse = instance
+            .get_typed_func::<(i32, i32), i64>(&store, "parse")
+            .map_err(|e| plugin_error("is invalid (parse)", e))?;
+        let finish = instance.get_typed_func::<(), i64>(&store, "finish").ok();
+
+        Ok(PluginInstance {
+            store,
+            memory,
+            alloc,
+            parse,
+            finish,
+        })
+    }
+
+    /// Top the plugin's fuel up to the budget of `input_len` bytes.
+    fn refuel(&mut self, input_len: usize) -> Result<(), SiemError> {
+        let fuel = BASE_FUEL.saturating_add(FUEL_PER_BYTE.saturating_mul(input_len as u64));
+        let remaining = self
+            .store
+            .consume_fuel(0)
+            .map_err(|e| plugin_error("cannot be refueled", e))?;
+        if fuel > remaining {
+            self.store
+                .add_fuel(fuel - remaining)
+                .map_err(|e| plugin_error("cannot be refueled", e))?;
+        }
+        Ok(())
+    }
+
+    /// Parse a chunk of lines.
+    fn parse(&mut self, input: &[u8]) -> Result<Vec<serde_json::Value>, SiemError> {
+        self.refuel(input.len())?;
+        let len = i32::try_from(input.len()).map_err(|e| plugin_error("input", e))?;
+        let ptr = self
+            .alloc
+            .call(&mut self.store, len)
+            .map_err(|e| plugin_error("failed to allocate", e))?;
+        self.memory
+            .write(&mut self.store, ptr as u32 as usize, input)
+            .map_err(|e| plugin_error("returned a bad buffer", e))?;
+        let packed = self
+            .parse
+            .call(&mut self.store, (ptr, len))
+            .map_err(|e| plugin_error("failed", e))?;
+        self.output(packed)
+    }
+
+    /// Flush the events a plugin buffered.
+    fn finish(&mut self) -> Result<Vec<serde_json::Value>, SiemError> {
+        let Some(finish) = self.finish.as_ref() else {
+            return Ok(Vec::new());
+        };
+        self.refuel(0)?;
+        let packed = finish
+            .call(&mut self.store, ())
+            .map_err(|e| plugin_error("failed", e))?;
+        self.output(packed)
+    }
+
+    /// Read the output a plugin call points to.
+    fn output(&self, packed: i64) -> Result<Vec<serde_json::Value>, SiemError> {
+        let (ptr, len) = unpack(packed);
+        if len == 0 {
+            return Ok(Vec::new());
+        }
+        let data = self.memory.data(&self.store);
+        let output = data
+            .get(ptr..ptr.saturating_add(len))
+            .ok_or_else(|| plugin_error("returned a bad buffer", "out of bounds"))?;
+        parse_output(output)
+    }
+}
+
+/// Split a plugin's `(ptr << 32) | len` result.
+fn unpack(packed: i64) -> (usize, usize) {
+    let packed = packed as u64;
+    ((packed >> 32) as usize, (packed & 0xffff_ffff) as usize)
+}
+
+/// Events of a plugin's output: a JSON array, a single object or NDJSON.
+fn parse_output(output: &[u8]) -> Result<Vec<serde_json::Value>, SiemError> {
+    let text = std::str::from_utf8(output).map_err(|e| plugin_error("returned bad UTF-8", e))?;
+    match serde_json::from_str::<serde_json::Value>(text) {
+        Ok(serde_json::Value::Array(events)) => return Ok(events),
+        Ok(event @ serde_json::Value::Object(_)) => return Ok(vec![event]),
+        _ => {}
+    }
+    text.lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            serde_json::from_str(line).map_err(|e| plugin_error("returned invalid JSON", e))
+        })
+        .collect()
+}
+
+/// Read a file through a plugin in chunks of at most `chunk_size` events.
+/// Returns the number of events.
+pub fn stream_events<R, F>(
+    name: &str,
+    mut reader: R,
+    chunk_size: usize,
+    on_chunk: &mut F,
+) -> Result<usize, SiemError>
+where
+    R: BufRead,
+    F: FnMut(Vec<serde_json::Value>),
+{
+    let mut plugin = PluginInstance::load(&plugin_path(name)?)?;
+
+    let mut total = 0;
+    let mut emit = |events: Vec<serde_json::Value>| {
+        total += events.len();
+        for chunk in events.chunks(chunk_size) {
+            on_chunk(chunk.to_vec());
+        }
+    };
+
+    let mut input = Vec::new();
+    loop {
+        let read = reader
+            .read_until(b'\n', &mut input)
+            .map_err(|e| SiemError::Query(format!("Failed to read log file: {}", e)))?;
+        if read > 0 && !input.ends_with(b"\n") {
+            input.push(b'\n');
+        }
+        if read == 0 || input.len() >= PLUGIN_CHUNK_BYTES {
+            if !input.is_empty() {
+                emit(plugin.parse(&input)?);
+                input.clear();
+            }
+            if read == 0 {
+                break;
+            }
+        }
+    }
+    emit(plugin.finish()?);
+
+    Ok(total)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plugin_output() {
+        assert_eq!(unpack((1024 << 32) | 17), (1024, 17));
+
+        let array = parse_output(br#"[{"a": 1}, {"a": 2}]"#).unwrap();
+        assert_eq!(array.len(), 2);
+        let ndjson = parse_output(b"{\"a\": 1}\n\n{\"a\": 2}\n{\"a\": 3}\n").unwrap();
+        assert_eq!(ndjson[2]["a"], 3);
+        assert_eq!(parse_output(br#"{"a": 1}"#).unwrap().len(), 1);
+        assert!(parse_output(b"{\"a\": 1}\nnot json").is_err());
+
+        assert!(validate_plugin_name("fortigate-v7").is_ok());
+        assert!(validate_plugin_name("../evil").is_err());
+    }
+
+    /// A plugin whose `parse` grows its memory by `pages` 64 KiB pages.
+    fn growing_plugin(initial_pages: u32, pages: u32) -> Vec<u8> {
+        wat::parse_str(format!(
+            r#"(module
+                (memory (export "memory") {initial_pages})
+                (func (export "alloc") (param i32) (result i32) i32.const 0)
+                (func (export "parse") (param i32 i32) (result i64)
+                    (drop (memory.grow (i32.const {pages})))
+                    i64.const 0))"#
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_plugin_memory_is_capped() {
+        let page = 64 * 1024;
+        let max_pages = (MAX_PLUGIN_MEMORY_BYTES / page) as u32;
+
+        let mut plugin = PluginInstance::from_bytes(&growing_plugin(1, 16)).unwrap();
+        assert!(plugin.parse(b"line\n").unwrap().is_empty());
+
+        let mut plugin = PluginInstance::from_bytes(&growing_plugin(1, max_pages)).unwrap();
+        assert!(plugin.parse(b"line\n").is_err());
+
+        assert!(PluginInstance::from_bytes(&growing_plugin(max_pages + 1, 0)).is_err());
+    }
+}
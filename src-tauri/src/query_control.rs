@@ -0,0 +1,146 @@
+//! Cancellation and timeouts for ad-hoc queries.
+//!
+//! A query started with an ID can be interrupted from another command with
+//! `cancel`, and any query can be given a timeout after which a watchdog
+//! thread interrupts it. Either way DuckDB aborts the running statement and
+//! the query fails with an error saying why.
+
+use duckdb::{Connection, InterruptHandle};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::thread;
+use std::time::Duration;
+
+use crate::models::SiemError;
+
+/// A query that can be cancelled by ID.
+struct RunningQuery {
+    handle: Arc<InterruptHandle>,
+    cancelled: Arc<AtomicBool>,
+}
+
+fn running() -> &'static Mutex<HashMap<String, RunningQuery>> {
+    static RUNNING: OnceLock<Mutex<HashMap<String, RunningQuery>>> = OnceLock::new();
+    RUNNING.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Run a query on a connection, interruptible through `cancel(query_id)` and
+/// after `timeout`.
+pub fn run_interruptible<T>(
+    conn: &Connection,
+    query_id: Option<&str>,
+    timeout: Option<Duration>,
+    run: impl FnOnce(&Connection) -> Result<T, SiemError>,
+) -> Result<T, SiemError> {
+    let handle = conn.interrupt_handle();
+    let cancelled = Arc::new(AtomicBool::new(false));
+    if let (Some(id), Ok(mut running)) = (query_id, running().lock()) {
+        running.insert(
+            id.to_string(),
+            RunningQuery {
+                handle: handle.clone(),
+                cancelled: cancelled.clone(),
+            },
+        );
+    }
+
+    // The watchdog interrupts the query unless told it finished in time
+    let timed_out = Arc::new(AtomicBool::new(false));
+    let watchdog = timeout.map(|timeout| {
+        let (done, wait) = mpsc::channel::<()>();
+        let handle = handle.clone();
+        let timed_out = timed_out.clone();
+        let thread = thread::spawn(move || {
+            if let Err(RecvTimeoutError::Timeout) = wait.recv_timeout(timeout) {
+                timed_out.store(true, Ordering::SeqCst);
+                handle.interrupt();
+            }
+        });
+        (done, thread)
+    });
+
+    let result = run(conn);
+
+    if let Some((done, thread)) = watchdog {
+        drop(done);
+        let _ = thread.join();
+    }
+    if let (Some(id), Ok(mut running)) = (query_id, running().lock()) {
+        running.remove(id);
+    }
+
+    match result {
+        Err(_) if cancelled.load(Ordering::SeqCst) => {
+            Err(SiemError::Query("Query cancelled".to_string()))
+        }
+        Err(_) if timed_out.load(Ordering::SeqCst) => Err(SiemError::Query(format!(
+            "Query timed out after {:?}",
+            timeout.unwrap_or_default()
+        ))),
+        result => result,
+    }
+}
+
+/// Interrupt a running query. Returns whether a query with this ID was running.
+pub fn cancel(query_id: &str) -> bool {
+    let Ok(running) = running().lock() else {
+        return false;
+    };
+    match running.get(query_id) {
+        Some(query) => {
+            query.cancelled.store(true, Ordering::SeqCst);
+            query.handle.interrupt();
+            true
+        }
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db_engine;
+
+    const RUNAWAY_QUERY: &str = "SELECT count(*) FROM range(100000) a, range(100000) b";
+
+    #[test]
+    fn test_query_timeout() {
+        let conn = db_engine::create_connection().unwrap();
+        let result = run_interruptible(&conn, None, Some(Duration::from_millis(100)), |conn| {
+            db_engine::execute_adhoc_query(conn, RUNAWAY_QUERY)
+        });
+        let error = result.err().unwrap().to_string();
+        assert!(error.contains("timed out"), "{}", error);
+
+        // The connection stays usable
+        let rows = db_engine::execute_adhoc_query(&conn, "SELECT 1 AS one").unwrap();
+        assert_eq!(rows.rows[0]["one"], 1);
+    }
+
+    #[test]
+    fn test_cancel_running_query() {
+        assert!(!cancel("test-cancel-query"));
+
+        let canceller = thread::spawn(|| {
+            for _ in 0..100 {
+                thread::sleep(Duration::from_millis(20));
+                if cancel("test-cancel-query") {
+                    return true;
+                }
+            }
+            false
+        });
+
+        let conn = db_engine::create_connection().unwrap();
+        let result = run_interruptible(&conn, Some("test-cancel-query"), None, |conn| {
+            db_engine::execute_adhoc_query(conn, RUNAWAY_QUERY)
+        });
+        assert!(canceller.join().unwrap());
+        assert_eq!(
+            result.err().unwrap().to_string(),
+            "Query error: Query cancelled"
+        );
+    }
+}
@@ -0,0 +1,604 @@
+//! Saved query library for ad-hoc SQL investigations.
+//!
+//! Queries are stored as individual JSON files in the `queries` directory of
+//! the application's data directory, named after their UUID: `{query_id}.json`.
+//! Names are unique (case-insensitive) and tags group queries into playbooks.
+//!
+//! A query can be a template with `{{name}}` placeholders, filled in when it
+//! is run. Values are always substituted as SQL literals, escaped here rather
+//! than by the caller: outside quotes a string becomes a quoted literal
+//! (`'O''Brien'`) and an array a parenthesized list for `IN`; inside a quoted
+//! string or identifier only the escaped text is inserted. Placeholders in
+//! comments are left alone.
+//!
+//! Each placeholder is declared as a variable with a type and a prompt for the
+//! frontend (undeclared ones are text). Values are checked against the type,
+//! or converted from the text of a prompt, before they are substituted.
+
+use std::collections::HashMap;
+use std::fs;
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
+
+use crate::app_data;
+use crate::db_engine;
+use crate::models::{QueryVariable, SavedQuery, SiemError, VariableType};
+
+/// Get the directory where saved queries are stored.
+/// Creates the directory if it doesn't exist.
+fn get_queries_dir(app_handle: &tauri::AppHandle) -> Result<PathBuf, SiemError> {
+    app_data::app_data_subdir(app_handle, "queries")
+}
+
+/// Get the file path of a saved query, rejecting IDs that aren't plain names.
+fn query_path(app_handle: &tauri::AppHandle, query_id: &str) -> Result<PathBuf, SiemError> {
+    if query_id.is_empty() || query_id.contains(['/', '\\', '.']) {
+        return Err(SiemError::Query(format!("Invalid query ID: {}", query_id)));
+    }
+    Ok(get_queries_dir(app_handle)?.join(format!("{}.json", query_id)))
+}
+
+/// Save a query (create or update).
+/// If the query has no ID, a new UUID is generated.
+pub fn save_query(
+    app_handle: &tauri::AppHandle,
+    mut query: SavedQuery,
+) -> Result<SavedQuery, SiemError> {
+    query.name = query.name.trim().to_string();
+    if query.name.is_empty() {
+        return Err(SiemError::Query("Query name cannot be empty".to_string()));
+    }
+    if query.sql.trim().is_empty() {
+        return Err(SiemError::Query("Query SQL cannot be empty".to_string()));
+    }
+
+    let existing = list_queries(app_handle, None)?;
+    if existing
+        .iter()
+        .any(|q| q.id != query.id && q.name.eq_ignore_ascii_case(&query.name))
+    {
+        return Err(SiemError::Query(format!(
+            "A query named '{}' already exists",
+            query.name
+        )));
+    }
+
+    let now = chrono::Utc::now().to_rfc3339();
+    if query.id.is_empty() {
+        query.id = uuid::Uuid::new_v4().to_string();
+    }
+    query.created_at = existing
+        .iter()
+        .find(|q| q.id == query.id)
+        .map_or_else(|| now.clone(), |q| q.created_at.clone());
+    query.updated_at = now;
+    query.tags = normalize_tags(&query.tags);
+    query.placeholders = placeholders(&query.sql);
+    query.variables = declare_variables(&query.placeholders, &query.variables)?;
+
+    write_query(app_handle, &query)?;
+    Ok(query)
+}
+
+/// List saved queries sorted by name, optionally only those with a tag.
+pub fn list_queries(
+    app_handle: &tauri::AppHandle,
+    tag: Option<&str>,
+) -> Result<Vec<SavedQuery>, SiemError> {
+    let queries_dir = get_queries_dir(app_handle)?;
+    let tag = tag.map(|tag| tag.trim().to_lowercase());
+    let mut queries = Vec::new();
+
+    let entries = fs::read_dir(&queries_dir)
+        .map_err(|e| SiemError::FileIO(format!("Cannot read queries dir: {}", e)))?;
+
+    for entry in entries {
+        let entry = entry.map_err(|e| SiemError::FileIO(format!("Cannot read entry: {}", e)))?;
+        let path = entry.path();
+
+        if path.extension().is_some_and(|ext| ext == "json") {
+            match load_query_from_path(&path) {
+                Ok(query) => {
+                    if tag.as_ref().is_none_or(|tag| query.tags.contains(tag)) {
+                        queries.push(query);
+                    }
+                }
+                Err(e) => {
+                    // Log error but continue loading other queries
+                    eprintln!("Warning: Failed to load query {:?}: {}", path, e);
+                }
+            }
+        }
+    }
+
+    queries.sort_by(|a, b| a.name.to_lowercase().cmp(&b.name.to_lowercase()));
+
+    Ok(queries)
+}
+
+/// Get a single saved query by ID.
+pub fn get_query(app_handle: &tauri::AppHandle, query_id: &str) -> Result<SavedQuery, SiemError> {
+    let file_path = query_path(app_handle, query_id)?;
+
+    if !file_path.exists() {
+        return Err(SiemError::Query(format!("Query not found: {}", query_id)));
+    }
+
+    load_query_from_path(&file_path)
+}
+
+/// Get a saved query by name (case-insensitive).
+pub fn find_query_by_name(
+    app_handle: &tauri::AppHandle,
+    name: &str,
+) -> Result<SavedQuery, SiemError> {
+    list_queries(app_handle, None)?
+        .into_iter()
+        .find(|q| q.name.eq_ignore_ascii_case(name.trim()))
+        .ok_or_else(|| SiemError::Query(format!("Query not found: {}", name)))
+}
+
+/// Replace the tags of a saved query.
+pub fn tag_query(
+    app_handle: &tauri::AppHandle,
+    query_id: &str,
+    tags: &[String],
+) -> Result<SavedQuery, SiemError> {
+    let mut query = get_query(app_handle, query_id)?;
+    query.tags = normalize_tags(tags);
+    query.updated_at = chrono::Utc::now().to_rfc3339();
+
+    write_query(app_handle, &query)?;
+    Ok(query)
+}
+
+/// Delete a saved query by ID.
+pub fn delete_query(app_handle: &tauri::AppHandle, query_id: &str) -> Result<(), SiemError> {
+    let file_path = query_path(app_handle, query_id)?;
+
+    if !file_path.exists() {
+        return Err(SiemError::Query(format!("Query not found: {}", query_id)));
+    }
+
+    fs::remove_file(&file_path)
+        .map_err(|e| SiemError::FileIO(format!("Cannot delete query: {}", e)))?;
+
+    Ok(())
+}
+
+fn write_query(app_handle: &tauri::AppHandle, query: &SavedQuery) -> Result<(), SiemError> {
+    let file_path = query_path(app_handle, &query.id)?;
+
+    let content = serde_json::to_string_pretty(query)
+        .map_err(|e| SiemError::Serialization(format!("Cannot serialize query: {}", e)))?;
+
+    fs::write(&file_path, content)
+        .map_err(|e| SiemError::FileIO(format!("Cannot write query file: {}", e)))
+}
+
+fn load_query_from_path(path: &Path) -> Result<SavedQuery, SiemError> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| SiemError::FileIO(format!("Cannot read query file: {}", e)))?;
+
+    serde_json::from_str(&content)
+        .map_err(|e| SiemError::Serialization(format!("Cannot parse query: {}", e)))
+}
+
+/// Quoting context of a position in SQL text.
+#[derive(Clone, Copy, PartialEq)]
+enum SqlContext {
+    Code,
+    String,
+    Identifier,
+}
+
+/// A `{{name}}` placeholder at `start..end` in SQL text.
+struct Placeholder<'a> {
+    name: &'a str,
+    start: usize,
+    end: usize,
+    context: SqlContext,
+}
+
+/// Find the placeholders of a template, skipping comments.
+fn find_placeholders(sql: &str) -> Vec<Placeholder<'_>> {
+    let bytes = sql.as_bytes();
+    let mut found = Vec::new();
+    let mut context = SqlContext::Code;
+    let mut i = 0;
+
+    while i < bytes.len() {
+        match (context, bytes[i]) {
+            (SqlContext::Code, b'-') if bytes.get(i + 1) == Some(&b'-') => {
+                i = sql[i..].find('\n').map_or(bytes.len(), |end| i + end);
+                continue;
+            }
+            (SqlContext::Code, b'/') if bytes.get(i + 1) == Some(&b'*') => {
+                i = sql[i + 2..]
+                    .find("*/")
+                    .map_or(bytes.len(), |end| i + 2 + end + 2);
+                continue;
+            }
+            (SqlContext::Code, b'\'') => context = SqlContext::String,
+            (SqlContext::Code, b'"') => context = SqlContext::Identifier,
+            (SqlContext::String, b'\'') | (SqlContext::Identifier, b'"') => {
+                context = SqlContext::Code
+            }
+            (_, b'{') if bytes.get(i + 1) == Some(&b'{') => {
+                if let Some(close) = sql[i + 2..].find("}}") {
+                    let name = sql[i + 2..i + 2 + close].trim();
+                    if !name.is_empty()
+                        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+                    {
+                        let end = i + 2 + close + 2;
+                        found.push(Placeholder {
+                            name,
+                            start: i,
+                            end,
+                            context,
+                        });
+                        i = end;
+                        continue;
+                    }
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+
+    found
+}
+
+/// Names of the placeholders of a template, in order of first use.
+pub fn placeholders(sql: &str) -> Vec<String> {
+    let mut names: Vec<String> = Vec::new();
+    for placeholder in find_placeholders(sql) {
+        if !names.iter().any(|name| name == placeholder.name) {
+            names.push(placeholder.name.to_string());
+        }
+    }
+    names
+}
+
+/// One variable per placeholder, in placeholder order: the declared ones, and
+/// text variables for the others. Declarations must name a placeholder.
+fn declare_variables(
+    placeholders: &[String],
+    declared: &[QueryVariable],
+) -> Result<Vec<QueryVariable>, SiemError> {
+    for (i, variable) in declared.iter().enumerate() {
+        if !placeholders.contains(&variable.name) {
+            return Err(SiemError::Query(format!(
+                "Variable '{}' is not used in the query",
+                variable.name
+            )));
+        }
+        if declared[..i]
+            .iter()
+            .any(|other| other.name == variable.name)
+        {
+            return Err(SiemError::Query(format!(
+                "Variable '{}' is declared twice",
+                variable.name
+            )));
+        }
+    }
+
+    Ok(placeholders
+        .iter()
+        .map(|name| {
+            declared
+                .iter()
+                .find(|variable| &variable.name == name)
+                .cloned()
+                .unwrap_or_else(|| QueryVariable {
+                    name: name.clone(),
+                    var_type: VariableType::String,
+                    prompt: String::new(),
+                })
+        })
+        .collect())
+}
+
+/// A value checked against a variable type, converted from text if needed.
+fn coerce_value(
+    var_type: VariableType,
+    value: &serde_json::Value,
+) -> Result<serde_json::Value, &'static str> {
+    use serde_json::Value;
+
+    let text = value.as_str().map(str::trim);
+    match var_type {
+        VariableType::String => match value {
+            Value::String(_) => Ok(value.clone()),
+            Value::Number(n) => Ok(Value::from(n.to_string())),
+            Value::Bool(b) => Ok(Value::from(b.to_string())),
+            _ => Err("expected text"),
+        },
+        VariableType::Integer => match (value, text) {
+            (Value::Number(n), _) if n.is_i64() || n.is_u64() => Ok(value.clone()),
+            (_, Some(text)) => text
+                .parse::<i64>()
+                .map(Value::from)
+                .map_err(|_| "expected an integer"),
+            _ => Err("expected an integer"),
+        },
+        VariableType::Number => match (value, text) {
+            (Value::Number(_), _) => Ok(value.clone()),
+            (_, Some(text)) => text
+                .parse::<f64>()
+                .ok()
+                .and_then(serde_json::Number::from_f64)
+                .map(Value::Number)
+                .ok_or("expected a number"),
+            _ => Err("expected a number"),
+        },
+        VariableType::Boolean => match (value, text) {
+            (Value::Bool(_), _) => Ok(value.clone()),
+            (_, Some(text)) if text.eq_ignore_ascii_case("true") => Ok(Value::Bool(true)),
+            (_, Some(text)) if text.eq_ignore_ascii_case("false") => Ok(Value::Bool(false)),
+            _ => Err("expected true or false"),
+        },
+        VariableType::Timestamp => text
+            .and_then(|text| chrono::DateTime::parse_from_rfc3339(text).ok())
+            .map(|time| {
+                Value::from(
+                    time.with_timezone(&chrono::Utc)
+                        .format("%Y-%m-%d %H:%M:%S%.f")
+                        .to_string(),
+                )
+            })
+            .ok_or("expected an RFC 3339 timestamp such as 2024-03-01T12:00:00Z"),
+        VariableType::Ip => text
+            .and_then(|text| text.parse::<IpAddr>().ok())
+            .map(|ip| Value::from(ip.to_string()))
+            .ok_or("expected an IP address"),
+        VariableType::List => match (value, text) {
+            (Value::Array(_), _) => Ok(value.clone()),
+            (_, Some(text)) => Ok(Value::Array(
+                text.split(',')
+                    .map(str::trim)
+                    .filter(|item| !item.is_empty())
+                    .map(Value::from)
+                    .collect(),
+            )),
+            _ => Err("expected a list"),
+        },
+    }
+}
+
+/// Render a saved query with values checked against its declared variables.
+pub fn render_saved_query(
+    query: &SavedQuery,
+    params: &HashMap<String, serde_json::Value>,
+) -> Result<String, SiemError> {
+    let mut checked = params.clone();
+    for variable in &query.variables {
+        let Some(value) = params.get(&variable.name) else {
+            continue;
+        };
+        let value = coerce_value(variable.var_type, value).map_err(|reason| {
+            SiemError::Query(format!(
+                "Invalid value for variable '{}': {}",
+                variable.name, reason
+            ))
+        })?;
+        checked.insert(variable.name.clone(), value);
+    }
+    render_template(&query.sql, &checked)
+}
+
+/// Fill in the placeholders of a template with escaped values.
+pub fn render_template(
+    sql: &str,
+    params: &HashMap<String, serde_json::Value>,
+) -> Result<String, SiemError> {
+    let mut rendered = String::with_capacity(sql.len());
+    let mut last = 0;
+
+    for placeholder in find_placeholders(sql) {
+        let value = params.get(placeholder.name).ok_or_else(|| {
+            SiemError::Query(format!(
+                "Missing value for placeholder '{}'",
+                placeholder.name
+            ))
+        })?;
+        let text = match placeholder.context {
+            SqlContext::Code => sql_literal(value),
+            SqlContext::String => quoted_text(value).map(|text| text.replace('\'', "''")),
+            SqlContext::Identifier => quoted_text(value).map(|text| text.replace('"', "\"\"")),
+        }
+        .map_err(|reason| {
+            SiemError::Query(format!(
+                "Invalid value for placeholder '{}': {}",
+                placeholder.name, reason
+            ))
+        })?;
+
+        rendered.push_str(&sql[last..placeholder.start]);
+        rendered.push_str(&text);
+        last = placeholder.end;
+    }
+    rendered.push_str(&sql[last..]);
+
+    Ok(rendered)
+}
+
+/// A JSON value as a SQL literal.
+fn sql_literal(value: &serde_json::Value) -> Result<String, &'static str> {
+    match value {
+        serde_json::Value::Null => Ok("NULL".to_string()),
+        serde_json::Value::Bool(b) => Ok(if *b { "TRUE" } else { "FALSE" }.to_string()),
+        serde_json::Value::Number(n) => Ok(n.to_string()),
+        serde_json::Value::String(s) => {
+            if s.contains('\0') {
+                return Err("text cannot contain NUL characters");
+            }
+            Ok(db_engine::sql_string(s))
+        }
+        serde_json::Value::Array(values) => {
+            if values.is_empty() {
+                return Err("lists cannot be empty");
+            }
+            if values.iter().any(|v| v.is_array() || v.is_object()) {
+                return Err("lists can only hold plain values");
+            }
+            let items = values
+                .iter()
+                .map(sql_literal)
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok(format!("({})", items.join(", ")))
+        }
+        serde_json::Value::Object(_) => Err("objects are not supported"),
+    }
+}
+
+/// A plain JSON value as text for use inside a quoted string.
+fn quoted_text(value: &serde_json::Value) -> Result<String, &'static str> {
+    let text = match value {
+        serde_json::Value::String(s) => s.clone(),
+        serde_json::Value::Number(n) => n.to_string(),
+        serde_json::Value::Bool(b) => b.to_string(),
+        _ => return Err("only text, numbers and booleans can be used inside quotes"),
+    };
+    if text.contains('\0') {
+        return Err("text cannot contain NUL characters");
+    }
+    Ok(text)
+}
+
+/// Trim, lowercase, deduplicate and sort tags, dropping empty ones.
+fn normalize_tags(tags: &[String]) -> Vec<String> {
+    let mut tags: Vec<String> = tags
+        .iter()
+        .map(|tag| tag.trim().to_lowercase())
+        .filter(|tag| !tag.is_empty())
+        .collect();
+    tags.sort();
+    tags.dedup();
+    tags
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_tags() {
+        let tags = vec![
+            " IAM ".to_string(),
+            "persistence".to_string(),
+            "iam".to_string(),
+            "  ".to_string(),
+        ];
+        assert_eq!(normalize_tags(&tags), vec!["iam", "persistence"]);
+    }
+
+    fn params(values: serde_json::Value) -> HashMap<String, serde_json::Value> {
+        serde_json::from_value(values).unwrap()
+    }
+
+    #[test]
+    fn test_render_template_escapes_values() {
+        let sql = "SELECT * FROM read_json_auto('{{file}}') \
+                   WHERE user = {{user}} AND code IN {{codes}} AND ok = {{ok}}";
+        let rendered = render_template(
+            sql,
+            &params(serde_json::json!({
+                "file": "/logs/it's.json",
+                "user": "x' OR '1'='1",
+                "codes": [403, "denied"],
+                "ok": true,
+            })),
+        )
+        .unwrap();
+
+        assert!(rendered.contains("read_json_auto('/logs/it''s.json')"));
+        assert!(rendered.contains("user = 'x'' OR ''1''=''1'"));
+        assert!(rendered.contains("code IN (403, 'denied')"));
+        assert!(rendered.contains("ok = TRUE"));
+    }
+
+    #[test]
+    fn test_template_placeholders_and_errors() {
+        let sql =
+            "-- user's hunt {{ignored}}\nSELECT \"{{col}}\" FROM t WHERE a = {{a}} OR b = {{ a }}";
+        assert_eq!(placeholders(sql), vec!["col", "a"]);
+
+        let missing = render_template(sql, &params(serde_json::json!({ "col": "x" })));
+        assert!(missing.unwrap_err().to_string().contains("'a'"));
+
+        let rendered = render_template(
+            sql,
+            &params(serde_json::json!({ "col": "we\"ird", "a": 1 })),
+        )
+        .unwrap();
+        assert!(rendered.starts_with("-- user's hunt {{ignored}}\n"));
+        assert!(rendered.contains("SELECT \"we\"\"ird\" FROM t WHERE a = 1 OR b = 1"));
+
+        let nested = render_template(
+            "SELECT {{a}}",
+            &params(serde_json::json!({ "a": { "b": 1 } })),
+        );
+        assert!(nested.is_err());
+    }
+
+    #[test]
+    fn test_typed_variables() {
+        let sql = "SELECT * FROM t WHERE ip = {{ip}} AND ts >= {{start_time}} \
+                   AND code IN {{codes}} AND n > {{n}} AND user = {{user}}";
+        let declared: Vec<QueryVariable> = serde_json::from_value(serde_json::json!([
+            { "name": "ip", "type": "ip", "prompt": "Source IP" },
+            { "name": "start_time", "type": "timestamp" },
+            { "name": "codes", "type": "list" },
+            { "name": "n", "type": "integer" },
+        ]))
+        .unwrap();
+
+        let variables = declare_variables(&placeholders(sql), &declared).unwrap();
+        assert_eq!(variables.len(), 5);
+        assert_eq!(variables[0].prompt, "Source IP");
+        assert_eq!(variables[4].var_type, VariableType::String);
+        let undeclared = vec![QueryVariable {
+            name: "other".to_string(),
+            var_type: VariableType::Ip,
+            prompt: String::new(),
+        }];
+        assert!(declare_variables(&placeholders(sql), &undeclared).is_err());
+
+        let query: SavedQuery = serde_json::from_value(serde_json::json!({
+            "name": "hunt",
+            "sql": sql,
+            "variables": variables,
+        }))
+        .unwrap();
+        let rendered = render_saved_query(
+            &query,
+            &params(serde_json::json!({
+                "ip": " 10.0.0.1 ",
+                "start_time": "2024-03-01T14:00:00+02:00",
+                "codes": "403, denied",
+                "n": "5",
+                "user": "bob",
+            })),
+        )
+        .unwrap();
+        assert!(rendered.contains("ip = '10.0.0.1'"));
+        assert!(rendered.contains("ts >= '2024-03-01 12:00:00'"));
+        assert!(rendered.contains("code IN ('403', 'denied')"));
+        assert!(rendered.contains("n > 5 AND user = 'bob'"));
+
+        let injected = render_saved_query(
+            &query,
+            &params(serde_json::json!({
+                "ip": "10.0.0.1' OR 1=1 --",
+                "start_time": "2024-03-01T12:00:00Z",
+                "codes": [1],
+                "n": 1,
+                "user": "bob",
+            })),
+        );
+        assert!(injected.unwrap_err().to_string().contains("'ip'"));
+    }
+}
@@ -0,0 +1,172 @@
+//! Rare-value ("long tail") detection.
+//!
+//! Counts the values of a field across the events of a log file and singles
+//! out those seen fewer than `threshold` times, such as an API call made only
+//! once, without writing a condition for it. `find_rare_values` lists them
+//! while hunting; a rule with a `rarity` block alerts on them, counting only
+//! the events matching its condition, with one alert per rare value. Events
+//! without the field are not counted.
+
+use std::collections::HashMap;
+
+use crate::aggregation;
+use crate::db_engine;
+use crate::models::{AlertEvent, RareValue, Rarity, RarityMatch, RuleYaml, SiemError};
+
+/// Counts the values of a field, keeping the events of values that are still rare.
+pub struct RareValueCounter<'a> {
+    field: &'a str,
+    threshold: usize,
+    /// Occurrences of each value, with its events while it is rare
+    values: HashMap<String, (usize, Vec<serde_json::Value>)>,
+}
+
+impl<'a> RareValueCounter<'a> {
+    pub fn new(field: &'a str, threshold: usize) -> Result<Self, SiemError> {
+        validate_threshold(threshold)?;
+        Ok(RareValueCounter {
+            field,
+            threshold,
+            values: HashMap::new(),
+        })
+    }
+
+    pub fn observe(&mut self, event: &serde_json::Value) {
+        let value = aggregation::group_value(event, self.field);
+        if value.is_empty() {
+            return;
+        }
+
+        let (count, events) = self.values.entry(value).or_default();
+        *count += 1;
+        if *count < self.threshold {
+            events.push(event.clone());
+        } else if !events.is_empty() {
+            *events = Vec::new();
+        }
+    }
+
+    /// The rare values, rarest first.
+    pub fn finish(self) -> Vec<RareValue> {
+        let threshold = self.threshold;
+        let mut rare: Vec<RareValue> = self
+            .values
+            .into_iter()
+            .filter(|(_, (count, _))| *count < threshold)
+            .map(|(value, (count, events))| RareValue {
+                value,
+                count,
+                events,
+            })
+            .collect();
+        rare.sort_by(|a, b| a.count.cmp(&b.count).then_with(|| a.value.cmp(&b.value)));
+        rare
+    }
+}
+
+/// Incremental state of a rarity rule.
+pub struct RarityTracker<'r> {
+    rule: &'r RuleYaml,
+    rarity: &'r Rarity,
+    counter: RareValueCounter<'r>,
+}
+
+impl<'r> RarityTracker<'r> {
+    pub fn new(rule: &'r RuleYaml, rarity: &'r Rarity) -> Result<Self, SiemError> {
+        Ok(RarityTracker {
+            rule,
+            rarity,
+            counter: RareValueCounter::new(&rarity.field, rarity.threshold)?,
+        })
+    }
+
+    pub fn rule(&self) -> &'r RuleYaml {
+        self.rule
+    }
+
+    pub fn observe(&mut self, event: &serde_json::Value) {
+        if db_engine::matches_detection(event, &self.rule.detection) {
+            self.counter.observe(event);
+        }
+    }
+
+    /// Produce one alert per rare value, with its events as evidence.
+    pub fn finish(self, source_file: Option<&str>) -> Vec<AlertEvent> {
+        self.counter
+            .finish()
+            .into_iter()
+            .map(|rare| {
+                let mut alert =
+                    AlertEvent::from_rule(self.rule, rare.events, source_file.map(str::to_string));
+                alert.rarity = Some(RarityMatch {
+                    field: self.rarity.field.clone(),
+                    value: rare.value,
+                    count: rare.count,
+                    threshold: self.rarity.threshold,
+                });
+                alert
+            })
+            .collect()
+    }
+}
+
+/// Check a rarity threshold: values seen fewer than 1 time don't exist.
+pub fn validate_threshold(threshold: usize) -> Result<(), SiemError> {
+    if threshold < 2 {
+        return Err(SiemError::Rule(
+            "Rarity needs a threshold of at least 2".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_rarity_rule() {
+        let rule: RuleYaml = serde_yaml::from_str(
+            "id: rare-iam\ntitle: Rare IAM call\ndescription: ''\nauthor: ''\nstatus: active\ndate: ''\ndetection:\n  severity: low\n  condition: \"eventSource = 'iam.amazonaws.com'\"\n  rarity:\n    field: eventName\n",
+        )
+        .unwrap();
+        let events = vec![
+            json!({ "eventSource": "iam.amazonaws.com", "eventName": "ListUsers" }),
+            json!({ "eventSource": "iam.amazonaws.com", "eventName": "ListUsers" }),
+            json!({ "eventSource": "iam.amazonaws.com", "eventName": "CreateLoginProfile" }),
+            json!({ "eventSource": "iam.amazonaws.com" }),
+            json!({ "eventSource": "s3.amazonaws.com", "eventName": "GetObject" }),
+        ];
+
+        let rarity = rule.detection.rarity.as_ref().unwrap();
+        let mut tracker = RarityTracker::new(&rule, rarity).unwrap();
+        events.iter().for_each(|event| tracker.observe(event));
+        let alerts = tracker.finish(Some("trail.json"));
+
+        assert_eq!(alerts.len(), 1);
+        let rare = alerts[0].rarity.as_ref().unwrap();
+        assert_eq!(rare.value, "CreateLoginProfile");
+        assert_eq!(rare.count, 1);
+        assert_eq!(alerts[0].evidence, vec![events[2].clone()]);
+
+        // Without the rule's condition every event counts
+        let mut counter = RareValueCounter::new("eventName", 3).unwrap();
+        events.iter().for_each(|event| counter.observe(event));
+        let rare: Vec<(String, usize)> = counter
+            .finish()
+            .into_iter()
+            .map(|rare| (rare.value, rare.count))
+            .collect();
+        assert_eq!(
+            rare,
+            vec![
+                ("CreateLoginProfile".to_string(), 1),
+                ("GetObject".to_string(), 1),
+                ("ListUsers".to_string(), 2),
+            ]
+        );
+
+        assert!(RareValueCounter::new("eventName", 1).is_err());
+    }
+}
@@ -0,0 +1,368 @@
+//! Self-contained HTML scan reports.
+//!
+//! A report renders one scan: summary statistics, a severity breakdown, and
+//! the alerts grouped by rule (most severe first) with the rule's description,
+//! guidance and a few evidence samples. Styles are inlined and nothing is
+//! loaded from elsewhere, so the single file opens anywhere and can be
+//! attached to an incident ticket from an air-gapped machine. Markup comes
+//! from a minijinja template with HTML auto-escaping, so no value from a log
+//! or rule reaches the page unescaped.
+
+use minijinja::{context, Environment};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+
+use crate::alert_export::is_url;
+use crate::models::{AlertEvent, RuleYaml, ScanResponse, SiemError};
+
+/// Evidence events shown per alert by default.
+const DEFAULT_EVIDENCE_SAMPLES: usize = 3;
+
+/// Severities listed in the summary, most severe first.
+const SEVERITIES: [&str; 5] = ["critical", "high", "medium", "low", "info"];
+
+const STYLE: &str = "body{font-family:sans-serif;margin:2em;color:#222}\
+table{border-collapse:collapse;margin-bottom:1em}\
+td,th{border:1px solid #ccc;padding:4px 8px;text-align:left;vertical-align:top}\
+section{border-top:2px solid #ddd;margin-top:2em}\
+pre{background:#f6f6f6;padding:8px;white-space:pre-wrap;word-break:break-all}\
+.sev{display:inline-block;padding:2px 8px;border-radius:4px;color:#fff;background:#777}\
+.critical{background:#8b0000}.high{background:#d9534f}.medium{background:#f0ad4e}\
+.low{background:#5bc0de}.info{background:#5cb85c}";
+
+/// Report template. The `.html` name turns on auto-escaping.
+const TEMPLATE_NAME: &str = "scan_report.html";
+
+const TEMPLATE: &str = r#"<!DOCTYPE html>
+<html><head><meta charset="utf-8"><title>{{ title }}</title><style>{{ style | safe }}</style></head><body>
+{%- macro badge(severity) %}<span class="sev {{ severity.class }}">{{ severity.label }}</span>{% endmacro %}
+<h1>{{ title }}</h1>
+<table><tbody>
+<tr><th>Generated</th><td>{{ generated }}</td></tr>
+<tr><th>Rules evaluated</th><td>{{ rules_evaluated }}</td></tr>
+<tr><th>Scan time</th><td>{{ scan_time_ms }} ms</td></tr>
+<tr><th>Alerts</th><td>{{ alert_count }}</td></tr>
+<tr><th>Rules triggered</th><td>{{ groups | length }}</td></tr>
+<tr><th>Matched events</th><td>{{ match_count }}</td></tr>
+<tr><th>Source files</th><td>{{ files | join(", ") if files else "-" }}</td></tr>
+</tbody></table>
+<h2>Severity</h2><table><thead><tr><th>Severity</th><th>Alerts</th><th>Matched events</th></tr></thead><tbody>
+{%- for row in severities %}
+<tr><td>{{ badge(row.severity) }}</td><td>{{ row.alert_count }}</td><td>{{ row.match_count }}</td></tr>
+{%- endfor %}
+</tbody></table>
+{%- if not groups %}
+<p>No alerts.</p>
+{%- endif %}
+{%- for group in groups %}
+<section><h2>{{ badge(group.severity) }} {{ group.title }}</h2><p>{{ group.alerts | length }} alert(s), {{ group.match_count }} matched event(s) &middot; rule <code>{{ group.rule_id }}</code></p>
+{%- if group.description %}
+<p>{{ group.description }}</p>
+{%- endif %}
+{%- if group.remediation %}
+<p><b>Remediation:</b> {{ group.remediation }}</p>
+{%- endif %}
+{%- if group.falsepositives %}
+<b>False positives:</b><ul>{% for fp in group.falsepositives %}<li>{{ fp }}</li>{% endfor %}</ul>
+{%- endif %}
+{%- if group.references %}
+<b>References:</b><ul>{% for r in group.references %}<li>{% if r.url %}<a href="{{ r.text }}">{{ r.text }}</a>{% else %}{{ r.text }}{% endif %}</li>{% endfor %}</ul>
+{%- endif %}
+{%- for alert in group.alerts %}
+<h3>{{ alert.source_file }} &middot; {{ alert.match_count }} match(es) &middot; detected {{ alert.timestamp }}</h3>
+{%- for event in alert.evidence %}
+<pre>{{ event }}</pre>
+{%- endfor %}
+{%- if alert.hidden %}
+<p>{{ alert.hidden }} more evidence event(s) not shown.</p>
+{%- endif %}
+{%- endfor %}
+</section>
+{%- endfor %}
+</body></html>
+"#;
+
+/// Options of a scan report.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ReportOptions {
+    /// Destination file path
+    pub dest_path: String,
+    /// Report title
+    #[serde(default)]
+    pub title: Option<String>,
+    /// Evidence events shown per alert
+    #[serde(default = "default_evidence_samples")]
+    pub evidence_samples: usize,
+}
+
+fn default_evidence_samples() -> usize {
+    DEFAULT_EVIDENCE_SAMPLES
+}
+
+/// Result of writing a report.
+#[derive(Debug, Serialize, Clone)]
+pub struct ReportSummary {
+    pub dest_path: String,
+    pub alert_count: usize,
+    pub rule_count: usize,
+}
+
+/// Severity label and its badge class (empty for unknown severities).
+#[derive(Serialize)]
+struct SeverityView<'a> {
+    label: &'a str,
+    class: &'static str,
+}
+
+/// A row of the severity breakdown.
+#[derive(Serialize)]
+struct SeverityRow<'a> {
+    severity: SeverityView<'a>,
+    alert_count: usize,
+    match_count: usize,
+}
+
+/// A reference of a rule; URLs are rendered as links.
+#[derive(Serialize)]
+struct ReferenceView<'a> {
+    text: &'a str,
+    url: bool,
+}
+
+/// The alerts of one rule.
+#[derive(Serialize)]
+struct RuleGroupView<'a> {
+    rule_id: &'a str,
+    title: &'a str,
+    severity: SeverityView<'a>,
+    description: Option<&'a str>,
+    match_count: usize,
+    remediation: Option<&'a str>,
+    falsepositives: &'a [String],
+    references: Vec<ReferenceView<'a>>,
+    alerts: Vec<AlertView<'a>>,
+}
+
+/// One alert with its evidence samples, pretty-printed.
+#[derive(Serialize)]
+struct AlertView<'a> {
+    source_file: &'a str,
+    match_count: usize,
+    timestamp: &'a str,
+    evidence: Vec<String>,
+    /// Evidence events beyond the samples
+    hidden: usize,
+}
+
+/// Render a scan as HTML and write it to `options.dest_path`. `rules` supply
+/// descriptions for the rules that fired (missing ones are skipped).
+pub fn write_scan_report(
+    scan: &ScanResponse,
+    rules: &[RuleYaml],
+    options: &ReportOptions,
+) -> Result<ReportSummary, SiemError> {
+    let html = render_scan_report(scan, rules, options)?;
+    fs::write(&options.dest_path, html)
+        .map_err(|e| SiemError::FileIO(format!("Cannot write {}: {}", options.dest_path, e)))?;
+
+    Ok(ReportSummary {
+        dest_path: options.dest_path.clone(),
+        alert_count: scan.alerts.len(),
+        rule_count: group_by_rule(&scan.alerts).len(),
+    })
+}
+
+/// Render a scan as a single HTML document.
+pub fn render_scan_report(
+    scan: &ScanResponse,
+    rules: &[RuleYaml],
+    options: &ReportOptions,
+) -> Result<String, SiemError> {
+    let mut files: Vec<&str> = scan
+        .alerts
+        .iter()
+        .filter_map(|a| a.source_file.as_deref())
+        .collect();
+    files.sort_unstable();
+    files.dedup();
+
+    // Alerts per severity
+    let mut by_severity: BTreeMap<String, (usize, usize)> = BTreeMap::new();
+    for alert in &scan.alerts {
+        let entry = by_severity
+            .entry(alert.severity.to_lowercase())
+            .or_default();
+        entry.0 += 1;
+        entry.1 += alert.match_count;
+    }
+    let mut severities: Vec<SeverityRow> = by_severity
+        .iter()
+        .map(|(severity, (alerts, matches))| SeverityRow {
+            severity: severity_view(severity),
+            alert_count: *alerts,
+            match_count: *matches,
+        })
+        .collect();
+    severities.sort_by_key(|row| std::cmp::Reverse(crate::severity_order(row.severity.label)));
+
+    // Alerts grouped by rule
+    let groups: Vec<RuleGroupView> = group_by_rule(&scan.alerts)
+        .into_iter()
+        .map(|(rule_id, alerts)| {
+            let first = alerts[0];
+            let rule = rules.iter().find(|rule| rule.id == rule_id);
+            RuleGroupView {
+                rule_id: &first.rule_id,
+                title: &first.rule_title,
+                severity: severity_view(&first.severity),
+                description: rule.map(|r| r.description.trim()).filter(|d| !d.is_empty()),
+                match_count: alerts.iter().map(|a| a.match_count).sum(),
+                remediation: first.remediation.as_deref(),
+                falsepositives: &first.falsepositives,
+                references: first
+                    .references
+                    .iter()
+                    .map(|r| ReferenceView {
+                        text: r,
+                        url: is_url(r),
+                    })
+                    .collect(),
+                alerts: alerts
+                    .iter()
+                    .map(|alert| AlertView {
+                        source_file: alert.source_file.as_deref().unwrap_or("-"),
+                        match_count: alert.match_count,
+                        timestamp: &alert.timestamp,
+                        evidence: alert
+                            .evidence
+                            .iter()
+                            .take(options.evidence_samples)
+                            .map(|event| serde_json::to_string_pretty(event).unwrap_or_default())
+                            .collect(),
+                        hidden: alert
+                            .evidence
+                            .len()
+                            .saturating_sub(options.evidence_samples),
+                    })
+                    .collect(),
+            }
+        })
+        .collect();
+
+    let mut env = Environment::new();
+    env.add_template(TEMPLATE_NAME, TEMPLATE)
+        .map_err(|e| SiemError::Query(format!("Invalid report template: {}", e)))?;
+    env.get_template(TEMPLATE_NAME)
+        .and_then(|template| {
+            template.render(context! {
+                title => options.title.as_deref().unwrap_or("Offline SIEM Scan Report"),
+                style => STYLE,
+                generated => chrono::Utc::now().to_rfc3339(),
+                rules_evaluated => scan.rules_evaluated,
+                scan_time_ms => scan.scan_time_ms,
+                alert_count => scan.alerts.len(),
+                match_count => scan.alerts.iter().map(|a| a.match_count).sum::<usize>(),
+                files => files,
+                severities => severities,
+                groups => groups,
+            })
+        })
+        .map_err(|e| SiemError::Query(format!("Cannot render report: {}", e)))
+}
+
+/// Alerts grouped by rule ID, most severe rules first, then by title.
+fn group_by_rule(alerts: &[AlertEvent]) -> Vec<(String, Vec<&AlertEvent>)> {
+    let mut groups: Vec<(String, Vec<&AlertEvent>)> = Vec::new();
+    for alert in alerts {
+        match groups.iter_mut().find(|(id, _)| *id == alert.rule_id) {
+            Some((_, group)) => group.push(alert),
+            None => groups.push((alert.rule_id.clone(), vec![alert])),
+        }
+    }
+    groups.sort_by(|(_, a), (_, b)| {
+        crate::severity_order(&b[0].severity)
+            .cmp(&crate::severity_order(&a[0].severity))
+            .then_with(|| a[0].rule_title.cmp(&b[0].rule_title))
+    });
+    groups
+}
+
+/// Severity label, with a badge color for the known severities.
+fn severity_view(severity: &str) -> SeverityView<'_> {
+    let lower = severity.to_lowercase();
+    SeverityView {
+        label: severity,
+        class: SEVERITIES
+            .iter()
+            .find(|known| **known == lower)
+            .copied()
+            .unwrap_or(""),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn alert(rule_id: &str, severity: &str, evidence: usize) -> AlertEvent {
+        AlertEvent {
+            rule_id: rule_id.to_string(),
+            rule_title: format!("Rule <{}>", rule_id),
+            alert_title: None,
+            alert_title_template: None,
+            severity: severity.to_string(),
+            timestamp: "2025-12-16T11:05:00+00:00".to_string(),
+            match_count: evidence,
+            evidence: (0..evidence)
+                .map(|i| serde_json::json!({ "eventName": format!("<Event{}>", i) }))
+                .collect(),
+            source_file: Some("trail.json".to_string()),
+            aggregation: None,
+            correlation: None,
+            meta: None,
+            absence: None,
+            rarity: None,
+            first_seen: None,
+            truncation: None,
+            hash_annotations: vec![],
+            geo_annotations: vec![],
+            ioc_matches: vec![],
+            references: vec![],
+            falsepositives: vec![],
+            remediation: None,
+        }
+    }
+
+    #[test]
+    fn test_report_groups_alerts_by_rule_and_escapes() {
+        let scan = ScanResponse {
+            alerts: vec![
+                alert("low-1", "low", 1),
+                alert("crit-1", "critical", 5),
+                alert("low-1", "low", 2),
+            ],
+            rules_evaluated: 10,
+            scan_time_ms: 42,
+            suppressed_alerts: 0,
+            rule_results: vec![],
+            routing: Default::default(),
+        };
+        let options = ReportOptions {
+            dest_path: "unused".to_string(),
+            title: None,
+            evidence_samples: 3,
+        };
+        let html = render_scan_report(&scan, &[], &options).unwrap();
+
+        assert!(
+            html.find("Rule &lt;crit-1&gt;").unwrap() < html.find("Rule &lt;low-1&gt;").unwrap()
+        );
+        assert!(html.contains("2 alert(s), 3 matched event(s)"));
+        assert!(html.contains("&lt;Event2&gt;"));
+        assert!(!html.contains("&lt;Event3&gt;"));
+        assert!(html.contains("2 more evidence event(s) not shown."));
+        assert!(!html.contains("<Event"));
+        assert!(!html.contains("src="));
+    }
+}
@@ -0,0 +1,561 @@
+//! Local REST API for automation.
+//!
+//! An optional HTTP server bound to `127.0.0.1` lets other tools on the
+//! analysis box (scripts, Jupyter notebooks) drive the SIEM: list and edit
+//! rules, scan log files and run queries. It is off unless enabled in the
+//! config (`api_enabled`) or started from the settings page.
+//!
+//! Every request but `GET /api/health` needs the `Authorization: Bearer
+//! <token>` header with the token from the config, so web pages opened in a
+//! browser on the same machine can't use the API. The token is compared in
+//! constant time. At most `MAX_CONNECTIONS` requests are served at once;
+//! further connections get `503`. Bodies and responses are JSON; errors are
+//! `{"error": "..."}`.
+//!
+//! Rule changes and queries made through the API are recorded in the audit
+//! log with the `api` actor.
+//!
+//! | Method   | Path              | Body                  | Response          |
+//! |----------|-------------------|-----------------------|-------------------|
+//! | `GET`    | `/api/health`     |                       | version           |
+//! | `GET`    | `/api/rules`      |                       | all rules         |
+//! | `GET`    | `/api/rules/{id}` |                       | the rule          |
+//! | `POST`   | `/api/rules`      | rule                  | the saved rule    |
+//! | `DELETE` | `/api/rules/{id}` |                       |                   |
+//! | `POST`   | `/api/scan`       | `{"log_path": "..."}` | alerts            |
+//! | `POST`   | `/api/query`      | `{"query": "..."}`    | query result      |
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{Ipv4Addr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
+use subtle::ConstantTimeEq;
+
+use crate::change_feed::{self, ChangeKind};
+use crate::folder_watch::ScanFn;
+use crate::models::{AuditAction, AuditActor, QueryResult, RuleYaml, SiemError};
+use crate::{audit_log, rule_manager, workspace_lock};
+
+/// Runs an ad-hoc SQL query the way the query page does.
+pub type QueryFn = fn(&tauri::AppHandle, &str) -> Result<QueryResult, SiemError>;
+
+/// Largest request body accepted (rules, queries and paths are small).
+const MAX_BODY_SIZE: usize = 1024 * 1024;
+
+/// Largest request head (request line and headers) accepted.
+const MAX_HEAD_SIZE: usize = 16 * 1024;
+
+/// How long a client may take to send its request.
+const READ_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// How often the accept loop checks whether the server was stopped.
+const ACCEPT_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Most connections served at once; each has its own thread.
+const MAX_CONNECTIONS: usize = 8;
+
+/// How long a rejected client gets to take its `503` response.
+const REJECT_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// State of the REST API server.
+#[derive(Debug, Serialize, Clone, Default)]
+pub struct ApiStatus {
+    pub running: bool,
+    /// Base URL of the API, e.g. `http://127.0.0.1:8765/api`
+    pub url: Option<String>,
+    /// Token clients must send as `Authorization: Bearer <token>`
+    pub token: Option<String>,
+    /// When the server started (ISO 8601)
+    pub started_at: Option<String>,
+    /// Requests handled since the server started
+    pub requests_served: usize,
+}
+
+struct Server {
+    stop: Arc<AtomicBool>,
+    requests: Arc<AtomicUsize>,
+    status: ApiStatus,
+}
+
+fn server_state() -> &'static Mutex<Option<Server>> {
+    static STATE: OnceLock<Mutex<Option<Server>>> = OnceLock::new();
+    STATE.get_or_init(|| Mutex::new(None))
+}
+
+/// State of the server.
+pub fn status() -> ApiStatus {
+    server_state()
+        .lock()
+        .ok()
+        .and_then(|guard| {
+            guard.as_ref().map(|server| ApiStatus {
+                requests_served: server.requests.load(Ordering::Relaxed),
+                ..server.status.clone()
+            })
+        })
+        .unwrap_or_default()
+}
+
+/// Stop the server, if running.
+pub fn stop() {
+    if let Ok(mut guard) = server_state().lock() {
+        if let Some(server) = guard.take() {
+            server.stop.store(true, Ordering::Relaxed);
+        }
+    }
+}
+
+/// Serve the API on `127.0.0.1:port`, accepting `token`. A running server is
+/// stopped first.
+pub fn start(
+    app_handle: tauri::AppHandle,
+    port: u16,
+    token: String,
+    scan: ScanFn,
+    query: QueryFn,
+) -> Result<ApiStatus, SiemError> {
+    if token.is_empty() {
+        return Err(SiemError::Rule("The REST API needs a token".to_string()));
+    }
+    stop();
+
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, port))
+        .map_err(|e| SiemError::FileIO(format!("Cannot listen on port {}: {}", port, e)))?;
+    listener
+        .set_nonblocking(true)
+        .map_err(|e| SiemError::FileIO(format!("Cannot configure listener: {}", e)))?;
+
+    let stop_flag = Arc::new(AtomicBool::new(false));
+    let requests = Arc::new(AtomicUsize::new(0));
+    let status = ApiStatus {
+        running: true,
+        url: Some(format!("http://127.0.0.1:{}/api", port)),
+        token: Some(token.clone()),
+        started_at: Some(chrono::Utc::now().to_rfc3339()),
+        requests_served: 0,
+    };
+    {
+        let mut guard = server_state()
+            .lock()
+            .map_err(|_| SiemError::FileIO("REST API state is poisoned".to_string()))?;
+        *guard = Some(Server {
+            stop: stop_flag.clone(),
+            requests: requests.clone(),
+            status: status.clone(),
+        });
+    }
+
+    let token = Arc::new(token);
+    let active = Arc::new(AtomicUsize::new(0));
+    std::thread::spawn(move || {
+        while !stop_flag.load(Ordering::Relaxed) {
+            let stream = match listener.accept() {
+                Ok((stream, _)) => stream,
+                Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => {
+                    std::thread::sleep(ACCEPT_INTERVAL);
+                    continue;
+                }
+                Err(e) => {
+                    eprintln!("Warning: REST API cannot accept connection: {}", e);
+                    continue;
+                }
+            };
+
+            // Scans can take a while, so each connection gets its own
+            // thread, up to MAX_CONNECTIONS
+            let Some(slot) = ConnectionSlot::acquire(&active) else {
+                if let Err(e) = reject(stream) {
+                    eprintln!("Warning: REST API cannot reject connection: {}", e);
+                }
+                continue;
+            };
+            let app_handle = app_handle.clone();
+            let token = token.clone();
+            let requests = requests.clone();
+            std::thread::spawn(move || {
+                let _slot = slot;
+                requests.fetch_add(1, Ordering::Relaxed);
+                if let Err(e) = serve(&app_handle, stream, &token, scan, query) {
+                    eprintln!("Warning: REST API request failed: {}", e);
+                }
+            });
+        }
+    });
+
+    Ok(status)
+}
+
+/// A parsed HTTP request.
+#[derive(Debug)]
+struct Request {
+    method: String,
+    path: String,
+    /// Bearer token from the `Authorization` header
+    token: Option<String>,
+    body: Vec<u8>,
+}
+
+/// Read an HTTP/1.1 request (no chunked bodies).
+fn read_request(stream: impl Read) -> Result<Request, String> {
+    let mut reader = BufReader::new(stream.take((MAX_HEAD_SIZE + MAX_BODY_SIZE) as u64));
+
+    let mut head_size = 0;
+    let mut read_line = |reader: &mut BufReader<_>| -> Result<String, String> {
+        let mut line = String::new();
+        reader
+            .read_line(&mut line)
+            .map_err(|e| format!("Cannot read request: {}", e))?;
+        head_size += line.len();
+        if head_size > MAX_HEAD_SIZE {
+            return Err("Request headers too large".to_string());
+        }
+        Ok(line.trim_end().to_string())
+    };
+
+    let request_line = read_line(&mut reader)?;
+    let mut parts = request_line.split_whitespace();
+    let (Some(method), Some(target)) = (parts.next(), parts.next()) else {
+        return Err("Malformed request line".to_string());
+    };
+    // Query strings aren't used by any endpoint
+    let path = target.split('?').next().unwrap_or_default().to_string();
+    let method = method.to_uppercase();
+
+    let mut content_length = 0;
+    let mut token = None;
+    loop {
+        let line = read_line(&mut reader)?;
+        if line.is_empty() {
+            break;
+        }
+        let Some((name, value)) = line.split_once(':') else {
+            continue;
+        };
+        let value = value.trim();
+        match name.trim().to_ascii_lowercase().as_str() {
+            "content-length" => {
+                content_length = value
+                    .parse()
+                    .map_err(|_| "Invalid Content-Length".to_string())?;
+            }
+            "authorization" => {
+                token = value
+                    .strip_prefix("Bearer ")
+                    .map(|token| token.trim().to_string());
+            }
+            _ => {}
+        }
+    }
+
+    if content_length > MAX_BODY_SIZE {
+        return Err("Request body too large".to_string());
+    }
+    let mut body = vec![0; content_length];
+    reader
+        .read_exact(&mut body)
+        .map_err(|e| format!("Cannot read request body: {}", e))?;
+
+    Ok(Request {
+        method,
+        path,
+        token,
+        body,
+    })
+}
+
+/// Endpoints of the API.
+#[derive(Debug, PartialEq)]
+enum Route {
+    Health,
+    ListRules,
+    GetRule(String),
+    SaveRule,
+    DeleteRule(String),
+    Scan,
+    Query,
+}
+
+fn route(method: &str, path: &str) -> Option<Route> {
+    let segments: Vec<&str> = path
+        .trim_matches('/')
+        .split('/')
+        .filter(|segment| !segment.is_empty())
+        .collect();
+    match (method, segments.as_slice()) {
+        ("GET", ["api", "health"]) => Some(Route::Health),
+        ("GET", ["api", "rules"]) => Some(Route::ListRules),
+        ("POST", ["api", "rules"]) => Some(Route::SaveRule),
+        ("GET", ["api", "rules", id]) => Some(Route::GetRule(id.to_string())),
+        ("DELETE", ["api", "rules", id]) => Some(Route::DeleteRule(id.to_string())),
+        ("POST", ["api", "scan"]) => Some(Route::Scan),
+        ("POST", ["api", "query"]) => Some(Route::Query),
+        _ => None,
+    }
+}
+
+#[derive(Deserialize)]
+struct ScanRequest {
+    log_path: String,
+}
+
+#[derive(Deserialize)]
+struct QueryRequest {
+    query: String,
+}
+
+/// HTTP status of an error.
+fn error_status(error: &SiemError) -> u16 {
+    match error {
+        SiemError::Locked(_) => 423,
+        SiemError::Rule(_) | SiemError::Query(_) | SiemError::Serialization(_) => 400,
+        SiemError::FileIO(_) => 500,
+    }
+}
+
+fn parse_body<T: serde::de::DeserializeOwned>(body: &[u8]) -> Result<T, SiemError> {
+    serde_json::from_slice(body)
+        .map_err(|e| SiemError::Serialization(format!("Invalid request body: {}", e)))
+}
+
+fn to_json<T: Serialize>(value: &T) -> Result<serde_json::Value, SiemError> {
+    serde_json::to_value(value)
+        .map_err(|e| SiemError::Serialization(format!("Cannot serialize response: {}", e)))
+}
+
+/// Run an endpoint, returning the JSON response.
+fn handle(
+    app_handle: &tauri::AppHandle,
+    route: Route,
+    body: &[u8],
+    scan: ScanFn,
+    query: QueryFn,
+) -> Result<serde_json::Value, SiemError> {
+    match route {
+        Route::Health => Ok(serde_json::json!({
+            "status": "ok",
+            "version": env!("CARGO_PKG_VERSION"),
+        })),
+        Route::ListRules => to_json(&rule_manager::list_rules(app_handle)?),
+        Route::GetRule(id) => {
+            rule_manager::validate_rule_id(&id)?;
+            to_json(&rule_manager::get_rule(app_handle, &id)?)
+        }
+        Route::SaveRule => {
+            workspace_lock::ensure_writable(app_handle)?;
+            let rule: RuleYaml = parse_body(body)?;
+            // An empty ID gets a new one
+            if !rule.id.is_empty() {
+                rule_manager::validate_rule_id(&rule.id)?;
+            }
+            let rule = rule_manager::save_rule(app_handle, rule)?;
+            audit_log::record_as(
+                app_handle,
+                AuditActor::Api,
+                AuditAction::RuleSaved,
+                &rule.id,
+                serde_json::json!({ "title": rule.title }),
+            );
+            change_feed::notify(app_handle, ChangeKind::Rules, vec![rule.id.clone()]);
+            to_json(&rule)
+        }
+        Route::DeleteRule(id) => {
+            rule_manager::validate_rule_id(&id)?;
+            workspace_lock::ensure_writable(app_handle)?;
+            rule_manager::delete_rule(app_handle, &id)?;
+            audit_log::record_as(
+                app_handle,
+                AuditActor::Api,
+                AuditAction::RuleDeleted,
+                &id,
+                serde_json::json!({}),
+            );
+            change_feed::notify(app_handle, ChangeKind::Rules, vec![id]);
+            Ok(serde_json::Value::Null)
+        }
+        Route::Scan => {
+            // Scans record history and first-seen values
+            workspace_lock::ensure_writable(app_handle)?;
+            let request: ScanRequest = parse_body(body)?;
+            to_json(&scan(app_handle, &request.log_path)?)
+        }
+        Route::Query => {
+            let request: QueryRequest = parse_body(body)?;
+            to_json(&query(app_handle, &request.query)?)
+        }
+    }
+}
+
+/// A connection being served, counted in the active connections until dropped.
+struct ConnectionSlot(Arc<AtomicUsize>);
+
+impl ConnectionSlot {
+    /// Take a slot, unless MAX_CONNECTIONS connections are already served.
+    fn acquire(active: &Arc<AtomicUsize>) -> Option<Self> {
+        active
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |count| {
+                (count < MAX_CONNECTIONS).then_some(count + 1)
+            })
+            .ok()
+            .map(|_| ConnectionSlot(active.clone()))
+    }
+}
+
+impl Drop for ConnectionSlot {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+/// Turn away a connection over the limit without reading its request.
+fn reject(mut stream: TcpStream) -> std::io::Result<()> {
+    stream.set_nonblocking(false)?;
+    stream.set_write_timeout(Some(REJECT_TIMEOUT))?;
+    write_response(
+        &mut stream,
+        503,
+        &serde_json::json!({ "error": "Too many concurrent requests" }),
+    )
+}
+
+/// Check the token a request sent, in constant time. Both sides are hashed
+/// first so the comparison doesn't reveal the token's length either.
+fn token_matches(sent: Option<&str>, token: &str) -> bool {
+    let Some(sent) = sent else {
+        return false;
+    };
+    let sent = Sha256::digest(sent.as_bytes());
+    let expected = Sha256::digest(token.as_bytes());
+    sent.as_slice().ct_eq(expected.as_slice()).into()
+}
+
+/// Answer one connection.
+fn serve(
+    app_handle: &tauri::AppHandle,
+    mut stream: TcpStream,
+    token: &str,
+    scan: ScanFn,
+    query: QueryFn,
+) -> std::io::Result<()> {
+    // The listener is non-blocking; connections must not be
+    stream.set_nonblocking(false)?;
+    stream.set_read_timeout(Some(READ_TIMEOUT))?;
+
+    let (status, body) = match read_request(&stream) {
+        Err(e) => (400, serde_json::json!({ "error": e })),
+        Ok(request) => match route(&request.method, &request.path) {
+            None => (404, serde_json::json!({ "error": "Not found" })),
+            Some(route)
+                if route != Route::Health && !token_matches(request.token.as_deref(), token) =>
+            {
+                (
+                    401,
+                    serde_json::json!({ "error": "Missing or invalid token" }),
+                )
+            }
+            Some(route) => match handle(app_handle, route, &request.body, scan, query) {
+                Ok(value) => (200, value),
+                Err(e) => (error_status(&e), serde_json::json!({ "error": e })),
+            },
+        },
+    };
+
+    write_response(&mut stream, status, &body)
+}
+
+fn write_response(
+    stream: &mut impl Write,
+    status: u16,
+    body: &serde_json::Value,
+) -> std::io::Result<()> {
+    let reason = match status {
+        200 => "OK",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        404 => "Not Found",
+        423 => "Locked",
+        503 => "Service Unavailable",
+        _ => "Internal Server Error",
+    };
+    let body = body.to_string();
+    write!(
+        stream,
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        reason,
+        body.len(),
+        body
+    )?;
+    stream.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_and_route_requests() {
+        let raw = "POST /api/scan?verbose=1 HTTP/1.1\r\nHost: localhost\r\nauthorization: Bearer secret\r\nContent-Length: 28\r\n\r\n{\"log_path\": \"/logs/a.json\"}";
+        let request = read_request(raw.as_bytes()).unwrap();
+        assert_eq!(request.method, "POST");
+        assert_eq!(request.path, "/api/scan");
+        assert_eq!(request.token.as_deref(), Some("secret"));
+        let scan: ScanRequest = parse_body(&request.body).unwrap();
+        assert_eq!(scan.log_path, "/logs/a.json");
+
+        let request = read_request("GET /api/rules/abc/ HTTP/1.1\r\n\r\n".as_bytes()).unwrap();
+        assert_eq!(request.token, None);
+        assert_eq!(
+            route(&request.method, &request.path),
+            Some(Route::GetRule("abc".to_string()))
+        );
+        assert_eq!(
+            route("DELETE", "/api/rules/abc"),
+            Some(Route::DeleteRule("abc".to_string()))
+        );
+        assert_eq!(route("PUT", "/api/rules"), None);
+
+        // Backslashes are not separators: the ID is rejected with a 400
+        let Some(Route::DeleteRule(id)) = route("DELETE", "/api/rules/..\\..\\foo") else {
+            panic!("not a rule deletion");
+        };
+        let error = rule_manager::validate_rule_id(&id).unwrap_err();
+        assert_eq!(error_status(&error), 400);
+        assert_eq!(route("GET", "/api/rules/abc/extra"), None);
+
+        let too_large = format!(
+            "POST /api/query HTTP/1.1\r\nContent-Length: {}\r\n\r\n",
+            MAX_BODY_SIZE + 1
+        );
+        assert!(read_request(too_large.as_bytes()).is_err());
+    }
+
+    #[test]
+    fn test_token_and_connection_limits() {
+        assert!(token_matches(Some("secret"), "secret"));
+        assert!(!token_matches(Some("secreT"), "secret"));
+        assert!(!token_matches(Some("secret2"), "secret"));
+        assert!(!token_matches(None, "secret"));
+
+        let active = Arc::new(AtomicUsize::new(0));
+        let mut slots: Vec<ConnectionSlot> = (0..MAX_CONNECTIONS)
+            .map(|_| ConnectionSlot::acquire(&active).unwrap())
+            .collect();
+        assert!(ConnectionSlot::acquire(&active).is_none());
+        slots.pop();
+        assert_eq!(active.load(Ordering::Acquire), MAX_CONNECTIONS - 1);
+        assert!(ConnectionSlot::acquire(&active).is_some());
+    }
+
+    #[test]
+    fn test_write_response() {
+        let mut out = Vec::new();
+        write_response(&mut out, 404, &serde_json::json!({ "error": "Not found" })).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.starts_with("HTTP/1.1 404 Not Found\r\n"));
+        assert!(out.contains("Content-Length: 21\r\n"));
+        assert!(out.ends_with("\r\n\r\n{\"error\":\"Not found\"}"));
+    }
+}
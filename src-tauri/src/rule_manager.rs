@@ -4,9 +4,12 @@
 //! Each rule file is named after its UUID: `{rule_id}.yaml`
 
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
-use crate::models::{RuleYaml, SiemError};
+use crate::models::{AlertEvent, RuleYaml, SiemError};
+
+/// Valid values of a rule's status.
+pub const RULE_STATUSES: [&str; 4] = ["active", "disabled", "experimental", "deprecated"];
 
 /// Get the directory path where rules are stored.
 /// Uses custom directory from config if set, otherwise uses default app data dir.
@@ -15,9 +18,36 @@ pub fn get_rules_dir(app_handle: &tauri::AppHandle) -> Result<PathBuf, SiemError
     crate::config::get_rules_directory(app_handle)
 }
 
+/// Validate a rule ID (used as a filename).
+pub fn validate_rule_id(rule_id: &str) -> Result<(), SiemError> {
+    if rule_id.is_empty()
+        || rule_id.starts_with('.')
+        || !rule_id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-' || c == '.')
+    {
+        return Err(SiemError::Rule(format!(
+            "Invalid rule ID '{}': use letters, digits, '_', '-' or '.'",
+            rule_id
+        )));
+    }
+    Ok(())
+}
+
+/// Path of the file of a rule, checking its ID.
+fn rule_path(rules_dir: &Path, rule_id: &str) -> Result<PathBuf, SiemError> {
+    validate_rule_id(rule_id)?;
+    Ok(rules_dir.join(format!("{}.yaml", rule_id)))
+}
+
 /// Save a rule to a YAML file.
 /// If the rule has no ID, a new UUID is generated.
-pub fn save_rule(app_handle: &tauri::AppHandle, mut rule: RuleYaml) -> Result<RuleYaml, SiemError> {
+pub fn save_rule(app_handle: &tauri::AppHandle, rule: RuleYaml) -> Result<RuleYaml, SiemError> {
+    write_rule(&get_rules_dir(app_handle)?, rule)
+}
+
+/// Save a rule to a YAML file in `rules_dir`.
+fn write_rule(rules_dir: &Path, mut rule: RuleYaml) -> Result<RuleYaml, SiemError> {
     // Generate ID if empty
     if rule.id.is_empty() {
         rule.id = uuid::Uuid::new_v4().to_string();
@@ -25,9 +55,9 @@ pub fn save_rule(app_handle: &tauri::AppHandle, mut rule: RuleYaml) -> Result<Ru
 
     // Update date to current time
     rule.date = chrono::Utc::now().format("%Y-%m-%d").to_string();
+    crate::attack::tag_rule(&mut rule);
 
-    let rules_dir = get_rules_dir(app_handle)?;
-    let file_path = rules_dir.join(format!("{}.yaml", rule.id));
+    let file_path = rule_path(rules_dir, &rule.id)?;
 
     let yaml_content = serde_yaml::to_string(&rule)
         .map_err(|e| SiemError::Serialization(format!("Cannot serialize rule: {}", e)))?;
@@ -74,10 +104,92 @@ pub fn list_rules(app_handle: &tauri::AppHandle) -> Result<Vec<RuleYaml>, SiemEr
     Ok(rules)
 }
 
+/// Full-text query and filters for `search_rules`.
+#[derive(serde::Deserialize, Default, Debug)]
+pub struct RuleSearch {
+    /// Words that must all appear in the ID, title, description, tags or
+    /// conditions (case-insensitive)
+    #[serde(default)]
+    pub query: Option<String>,
+    /// Tags the rule must all have
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub severity: Option<String>,
+    #[serde(default)]
+    pub status: Option<String>,
+}
+
+/// Search rules, best matches first: rules matching in the title rank
+/// above those matching only elsewhere, then by title.
+pub fn search_rules(
+    app_handle: &tauri::AppHandle,
+    search: &RuleSearch,
+) -> Result<Vec<RuleYaml>, SiemError> {
+    Ok(filter_rules(list_rules(app_handle)?, search))
+}
+
+/// Rules matching a search, best matches first.
+fn filter_rules(rules: Vec<RuleYaml>, search: &RuleSearch) -> Vec<RuleYaml> {
+    let words: Vec<String> = search
+        .query
+        .as_deref()
+        .unwrap_or_default()
+        .split_whitespace()
+        .map(str::to_lowercase)
+        .collect();
+    let tags: Vec<String> = search
+        .tags
+        .iter()
+        .map(|t| t.trim().to_lowercase())
+        .collect();
+    let same = |filter: &Option<String>, value: &str| {
+        filter
+            .as_deref()
+            .is_none_or(|filter| filter.trim().eq_ignore_ascii_case(value))
+    };
+
+    let mut matches: Vec<(usize, RuleYaml)> = rules
+        .into_iter()
+        .filter(|rule| {
+            same(&search.severity, &rule.detection.severity)
+                && same(&search.status, &rule.status)
+                && tags
+                    .iter()
+                    .all(|tag| rule.tags.iter().any(|t| t.to_lowercase() == *tag))
+        })
+        .filter_map(|rule| {
+            let title = rule.title.to_lowercase();
+            let mut text = vec![
+                rule.id.to_lowercase(),
+                rule.description.to_lowercase(),
+                rule.detection.condition.to_lowercase(),
+            ];
+            text.extend(rule.tags.iter().map(|t| t.to_lowercase()));
+            if let Some(sequence) = &rule.detection.sequence {
+                text.extend(sequence.steps.iter().map(|s| s.condition.to_lowercase()));
+            }
+
+            let mut title_hits = 0;
+            for word in &words {
+                if title.contains(word.as_str()) {
+                    title_hits += 1;
+                } else if !text.iter().any(|t| t.contains(word.as_str())) {
+                    return None;
+                }
+            }
+            Some((title_hits, rule))
+        })
+        .collect();
+
+    matches
+        .sort_by(|(a_hits, a), (b_hits, b)| b_hits.cmp(a_hits).then_with(|| a.title.cmp(&b.title)));
+    matches.into_iter().map(|(_, rule)| rule).collect()
+}
+
 /// Get a single rule by ID.
 pub fn get_rule(app_handle: &tauri::AppHandle, rule_id: &str) -> Result<RuleYaml, SiemError> {
-    let rules_dir = get_rules_dir(app_handle)?;
-    let file_path = rules_dir.join(format!("{}.yaml", rule_id));
+    let file_path = rule_path(&get_rules_dir(app_handle)?, rule_id)?;
 
     if !file_path.exists() {
         return Err(SiemError::Rule(format!("Rule not found: {}", rule_id)));
@@ -86,10 +198,128 @@ pub fn get_rule(app_handle: &tauri::AppHandle, rule_id: &str) -> Result<RuleYaml
     load_rule_from_path(&file_path)
 }
 
+/// Save a copy of a rule under a new ID, titled "<title> (copy)".
+pub fn duplicate_rule(app_handle: &tauri::AppHandle, rule_id: &str) -> Result<RuleYaml, SiemError> {
+    let mut rule = get_rule(app_handle, rule_id)?;
+    rule.id = uuid::Uuid::new_v4().to_string();
+    rule.title = format!("{} (copy)", rule.title);
+    save_rule(app_handle, rule)
+}
+
+/// Append an exception filter to a rule, keeping its base condition.
+/// A filter the rule already has is not added twice.
+pub fn add_rule_filter(
+    app_handle: &tauri::AppHandle,
+    rule_id: &str,
+    filter: &str,
+) -> Result<RuleYaml, SiemError> {
+    let filter = filter.trim();
+    let validation = crate::test_rule::validate_condition(filter);
+    if !validation.valid {
+        return Err(SiemError::Rule(format!(
+            "Invalid filter: {}",
+            validation.error_message.unwrap_or_default()
+        )));
+    }
+
+    let mut rule = get_rule(app_handle, rule_id)?;
+    if rule.detection.filters.iter().any(|f| f.trim() == filter) {
+        return Ok(rule);
+    }
+    rule.detection.filters.push(filter.to_string());
+    save_rule(app_handle, rule)
+}
+
+/// Build a filter matching an alert's evidence on the given fields, e.g.
+/// `userIdentity.userName = 'svc-backup'`. A field with several values in
+/// the evidence matches any of them.
+pub fn alert_filter(alert: &AlertEvent, fields: &[String]) -> Result<String, SiemError> {
+    if fields.is_empty() {
+        return Err(SiemError::Rule(
+            "No fields selected for the filter".to_string(),
+        ));
+    }
+
+    let mut parts = Vec::new();
+    for field in fields {
+        let field = field.trim();
+        let mut values: Vec<String> = alert
+            .evidence
+            .iter()
+            .flat_map(|event| crate::db_engine::get_field_values(event, field))
+            .collect();
+        values.sort();
+        values.dedup();
+        if values.is_empty() {
+            return Err(SiemError::Rule(format!(
+                "Field '{}' not found in the alert evidence",
+                field
+            )));
+        }
+
+        let clauses = values
+            .iter()
+            .map(|value| {
+                // Conditions have no escapes: quote with whichever quote is unused
+                let quote = if !value.contains('\'') {
+                    '\''
+                } else if !value.contains('"') {
+                    '"'
+                } else {
+                    return Err(SiemError::Rule(format!(
+                        "Value of '{}' contains both quote characters",
+                        field
+                    )));
+                };
+                Ok(format!("{} = {}{}{}", field, quote, value, quote))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        parts.push(if clauses.len() == 1 {
+            clauses.into_iter().next().unwrap_or_default()
+        } else {
+            format!("({})", clauses.join(" OR "))
+        });
+    }
+
+    Ok(parts.join(" AND "))
+}
+
+/// Set the status of several rules at once. All rules are loaded before any
+/// is written, so an unknown ID changes nothing. Returns the IDs of the rules
+/// that changed.
+pub fn set_rules_status(
+    app_handle: &tauri::AppHandle,
+    rule_ids: &[String],
+    status: &str,
+) -> Result<Vec<String>, SiemError> {
+    if !RULE_STATUSES.contains(&status) {
+        return Err(SiemError::Rule(format!(
+            "Invalid rule status '{}': expected one of {}",
+            status,
+            RULE_STATUSES.join(", ")
+        )));
+    }
+
+    let rules = rule_ids
+        .iter()
+        .map(|id| get_rule(app_handle, id))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut changed = Vec::new();
+    for mut rule in rules {
+        if rule.status == status {
+            continue;
+        }
+        rule.status = status.to_string();
+        changed.push(save_rule(app_handle, rule)?.id);
+    }
+
+    Ok(changed)
+}
+
 /// Delete a rule by ID.
 pub fn delete_rule(app_handle: &tauri::AppHandle, rule_id: &str) -> Result<(), SiemError> {
-    let rules_dir = get_rules_dir(app_handle)?;
-    let file_path = rules_dir.join(format!("{}.yaml", rule_id));
+    let file_path = rule_path(&get_rules_dir(app_handle)?, rule_id)?;
 
     if !file_path.exists() {
         return Err(SiemError::Rule(format!("Rule not found: {}", rule_id)));
@@ -110,6 +340,49 @@ pub fn list_active_rules(app_handle: &tauri::AppHandle) -> Result<Vec<RuleYaml>,
         .collect())
 }
 
+/// Narrow the rules of a scan to a subset: the rules with the given IDs (all
+/// of which must be present), then those having all `tags` (case-insensitive)
+/// and the given severity. Empty filters keep every rule.
+pub fn select_rules(
+    rules: Vec<RuleYaml>,
+    rule_ids: &[String],
+    tags: &[String],
+    severity: Option<&str>,
+) -> Result<Vec<RuleYaml>, SiemError> {
+    if let Some(missing) = rule_ids
+        .iter()
+        .find(|id| !rules.iter().any(|rule| rule.id == **id))
+    {
+        return Err(SiemError::Rule(format!(
+            "Rule not found or not active: {}",
+            missing
+        )));
+    }
+
+    let selected: Vec<RuleYaml> = rules
+        .into_iter()
+        .filter(|rule| rule_ids.is_empty() || rule_ids.contains(&rule.id))
+        .filter(|rule| {
+            tags.iter()
+                .all(|tag| rule.tags.iter().any(|t| t.eq_ignore_ascii_case(tag.trim())))
+        })
+        .filter(|rule| {
+            severity.is_none_or(|severity| {
+                severity
+                    .trim()
+                    .eq_ignore_ascii_case(&rule.detection.severity)
+            })
+        })
+        .collect();
+
+    if selected.is_empty() {
+        return Err(SiemError::Rule(
+            "No active rule matches the selection".to_string(),
+        ));
+    }
+    Ok(selected)
+}
+
 /// Export a single rule to a YAML file.
 pub fn export_rule(
     app_handle: &tauri::AppHandle,
@@ -132,10 +405,24 @@ pub fn export_all_rules(
     app_handle: &tauri::AppHandle,
     dest_path: &str,
 ) -> Result<usize, SiemError> {
+    let rules = list_rules(app_handle)?;
+
+    let mut files = Vec::new();
+    for rule in &rules {
+        let yaml_content = serde_yaml::to_string(rule)
+            .map_err(|e| SiemError::Serialization(format!("Cannot serialize rule: {}", e)))?;
+        files.push((format!("{}.yaml", rule.id), yaml_content));
+    }
+    write_zip(dest_path, &files)?;
+
+    Ok(rules.len())
+}
+
+/// Write files (name, content) to a new ZIP archive.
+fn write_zip(dest_path: &str, files: &[(String, String)]) -> Result<(), SiemError> {
     use std::io::Write;
     use zip::write::FileOptions;
 
-    let rules = list_rules(app_handle)?;
     let file = fs::File::create(dest_path)
         .map_err(|e| SiemError::FileIO(format!("Cannot create ZIP file: {}", e)))?;
 
@@ -144,22 +431,225 @@ pub fn export_all_rules(
         .compression_method(zip::CompressionMethod::Deflated)
         .unix_permissions(0o755);
 
-    for rule in &rules {
-        let filename = format!("{}.yaml", rule.id);
-        let yaml_content = serde_yaml::to_string(rule)
-            .map_err(|e| SiemError::Serialization(format!("Cannot serialize rule: {}", e)))?;
-
+    for (filename, content) in files {
         zip.start_file(filename, options)
             .map_err(|e| SiemError::FileIO(format!("Cannot add file to ZIP: {}", e)))?;
 
-        zip.write_all(yaml_content.as_bytes())
+        zip.write_all(content.as_bytes())
             .map_err(|e| SiemError::FileIO(format!("Cannot write to ZIP: {}", e)))?;
     }
 
     zip.finish()
         .map_err(|e| SiemError::FileIO(format!("Cannot finalize ZIP: {}", e)))?;
 
-    Ok(rules.len())
+    Ok(())
+}
+
+// ============================================================================
+// Rule packs
+// ============================================================================
+
+/// Manifest file at the root of a rule pack.
+const PACK_MANIFEST: &str = "manifest.yaml";
+
+/// Folder holding the rule files of a rule pack.
+const PACK_RULES_DIR: &str = "rules/";
+
+/// Description of a rule pack, stored as `manifest.yaml` in the archive.
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug)]
+pub struct RulePackManifest {
+    pub name: String,
+    pub version: String,
+    pub author: String,
+    #[serde(default)]
+    pub description: String,
+    /// When the pack was exported (ISO 8601)
+    #[serde(default)]
+    pub created: String,
+    /// IDs of the rules in the pack
+    #[serde(default)]
+    pub rule_ids: Vec<String>,
+}
+
+/// What to do with a pack rule whose ID already exists.
+#[derive(serde::Deserialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum CollisionPolicy {
+    /// Replace the existing rule
+    Overwrite,
+    /// Keep the existing rule and ignore the pack's
+    Skip,
+    /// Import the pack's rule under a new ID
+    Rename,
+}
+
+/// Result of importing a rule pack.
+#[derive(serde::Serialize)]
+pub struct RulePackImportSummary {
+    pub manifest: RulePackManifest,
+    /// IDs of the imported rules (new IDs for renamed ones)
+    pub imported: Vec<String>,
+    /// IDs of the rules skipped because they already exist
+    pub skipped: Vec<String>,
+    /// Renamed rules, from pack ID to new ID
+    pub renamed: std::collections::BTreeMap<String, String>,
+    pub errors: Vec<String>,
+}
+
+/// Export rules with a manifest as a rule pack (ZIP archive).
+pub fn export_rule_pack(
+    app_handle: &tauri::AppHandle,
+    rule_ids: &[String],
+    manifest: RulePackManifest,
+    dest_path: &str,
+) -> Result<RulePackManifest, SiemError> {
+    let rules = rule_ids
+        .iter()
+        .map(|id| get_rule(app_handle, id))
+        .collect::<Result<Vec<_>, _>>()?;
+    write_rule_pack(dest_path, manifest, &rules)
+}
+
+/// Write a rule pack; the manifest's rule list and date are filled in.
+fn write_rule_pack(
+    dest_path: &str,
+    mut manifest: RulePackManifest,
+    rules: &[RuleYaml],
+) -> Result<RulePackManifest, SiemError> {
+    if manifest.name.trim().is_empty() {
+        return Err(SiemError::Rule(
+            "Rule pack name cannot be empty".to_string(),
+        ));
+    }
+    manifest.created = chrono::Utc::now().to_rfc3339();
+    manifest.rule_ids = rules.iter().map(|rule| rule.id.clone()).collect();
+
+    let mut files = vec![(
+        PACK_MANIFEST.to_string(),
+        serde_yaml::to_string(&manifest)
+            .map_err(|e| SiemError::Serialization(format!("Cannot serialize manifest: {}", e)))?,
+    )];
+    for rule in rules {
+        let yaml_content = serde_yaml::to_string(rule)
+            .map_err(|e| SiemError::Serialization(format!("Cannot serialize rule: {}", e)))?;
+        files.push((format!("{}{}.yaml", PACK_RULES_DIR, rule.id), yaml_content));
+    }
+    write_zip(dest_path, &files)?;
+
+    Ok(manifest)
+}
+
+/// Read a rule pack: its manifest and each rule file (name, parsed rule).
+fn read_rule_pack(
+    pack_path: &str,
+) -> Result<(RulePackManifest, Vec<(String, Result<RuleYaml, String>)>), SiemError> {
+    use std::io::Read;
+
+    let file = fs::File::open(pack_path)
+        .map_err(|e| SiemError::FileIO(format!("Cannot open rule pack: {}", e)))?;
+    let mut archive = zip::ZipArchive::new(file)
+        .map_err(|e| SiemError::FileIO(format!("Cannot read ZIP archive: {}", e)))?;
+
+    let mut manifest = None;
+    let mut rules = Vec::new();
+    for i in 0..archive.len() {
+        let mut file = archive
+            .by_index(i)
+            .map_err(|e| SiemError::FileIO(format!("Cannot read ZIP entry: {}", e)))?;
+        let filename = file.name().to_string();
+        let is_rule = filename.starts_with(PACK_RULES_DIR)
+            && (filename.ends_with(".yaml") || filename.ends_with(".yml"));
+        if file.is_dir() || (filename != PACK_MANIFEST && !is_rule) {
+            continue;
+        }
+
+        let mut content = String::new();
+        if let Err(e) = file.read_to_string(&mut content) {
+            rules.push((filename, Err(e.to_string())));
+            continue;
+        }
+
+        if filename == PACK_MANIFEST {
+            manifest = Some(serde_yaml::from_str(&content).map_err(|e| {
+                SiemError::Serialization(format!("Cannot parse rule pack manifest: {}", e))
+            })?);
+        } else {
+            let rule = serde_yaml::from_str(&content).map_err(|e| format!("Invalid YAML - {}", e));
+            rules.push((filename, rule));
+        }
+    }
+
+    let manifest = manifest.ok_or_else(|| {
+        SiemError::FileIO(format!("Not a rule pack: {} is missing", PACK_MANIFEST))
+    })?;
+    Ok((manifest, rules))
+}
+
+/// Import the rules of a rule pack, resolving ID collisions with `on_collision`.
+pub fn import_rule_pack(
+    app_handle: &tauri::AppHandle,
+    pack_path: &str,
+    on_collision: CollisionPolicy,
+) -> Result<RulePackImportSummary, SiemError> {
+    let (manifest, rules) = read_rule_pack(pack_path)?;
+    import_pack_rules(&get_rules_dir(app_handle)?, manifest, rules, on_collision)
+}
+
+/// Save the rules read from a pack into `rules_dir`. Rules with an invalid ID
+/// (e.g. one escaping the rules directory) are rejected.
+fn import_pack_rules(
+    rules_dir: &Path,
+    manifest: RulePackManifest,
+    rules: Vec<(String, Result<RuleYaml, String>)>,
+    on_collision: CollisionPolicy,
+) -> Result<RulePackImportSummary, SiemError> {
+    let mut summary = RulePackImportSummary {
+        manifest,
+        imported: Vec::new(),
+        skipped: Vec::new(),
+        renamed: std::collections::BTreeMap::new(),
+        errors: Vec::new(),
+    };
+
+    for (filename, rule) in rules {
+        let mut rule = match rule {
+            Ok(rule) => rule,
+            Err(e) => {
+                summary.errors.push(format!("{}: {}", filename, e));
+                continue;
+            }
+        };
+
+        if !rule.id.is_empty() {
+            if let Err(e) = validate_rule_id(&rule.id) {
+                summary.errors.push(format!("{}: {}", filename, e));
+                continue;
+            }
+        }
+
+        let exists = !rule.id.is_empty() && rules_dir.join(format!("{}.yaml", rule.id)).exists();
+        if exists {
+            match on_collision {
+                CollisionPolicy::Overwrite => {}
+                CollisionPolicy::Skip => {
+                    summary.skipped.push(rule.id);
+                    continue;
+                }
+                CollisionPolicy::Rename => {
+                    let new_id = uuid::Uuid::new_v4().to_string();
+                    summary.renamed.insert(rule.id.clone(), new_id.clone());
+                    rule.id = new_id;
+                }
+            }
+        }
+
+        match write_rule(rules_dir, rule) {
+            Ok(rule) => summary.imported.push(rule.id),
+            Err(e) => summary.errors.push(format!("{}: {}", filename, e)),
+        }
+    }
+
+    Ok(summary)
 }
 
 /// Import a single rule from a YAML file.
@@ -343,6 +833,200 @@ fn load_rule_from_path(path: &PathBuf) -> Result<RuleYaml, SiemError> {
     let content = fs::read_to_string(path)
         .map_err(|e| SiemError::FileIO(format!("Cannot read file: {}", e)))?;
 
-    serde_yaml::from_str(&content)
-        .map_err(|e| SiemError::Serialization(format!("Cannot parse YAML: {}", e)))
+    let mut rule: RuleYaml = serde_yaml::from_str(&content)
+        .map_err(|e| SiemError::Serialization(format!("Cannot parse YAML: {}", e)))?;
+    crate::attack::tag_rule(&mut rule);
+    Ok(rule)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(id: &str, title: &str, yaml_extra: &str) -> RuleYaml {
+        serde_yaml::from_str(&format!(
+            "id: {}\ntitle: {}\ndescription: Console login\nauthor: ''\ndate: ''\n{}",
+            id, title, yaml_extra
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_alert_filter() {
+        let alert: AlertEvent = serde_json::from_value(serde_json::json!({
+            "rule_id": "r1",
+            "rule_title": "Test",
+            "severity": "high",
+            "timestamp": "2025-12-16T11:05:00+00:00",
+            "match_count": 3,
+            "evidence": [
+                { "userIdentity": { "userName": "svc-backup" }, "sourceIPAddress": "10.0.0.1" },
+                { "userIdentity": { "userName": "svc-backup" }, "sourceIPAddress": "10.0.0.2" },
+                { "userIdentity": { "userName": "svc-backup" }, "sourceIPAddress": "o'brien" }
+            ]
+        }))
+        .unwrap();
+        let fields = |names: &[&str]| names.iter().map(|n| n.to_string()).collect::<Vec<_>>();
+
+        assert_eq!(
+            alert_filter(&alert, &fields(&["userIdentity.userName"])).unwrap(),
+            "userIdentity.userName = 'svc-backup'"
+        );
+        let filter = alert_filter(
+            &alert,
+            &fields(&["userIdentity.userName", "sourceIPAddress"]),
+        )
+        .unwrap();
+        assert_eq!(
+            filter,
+            "userIdentity.userName = 'svc-backup' AND (sourceIPAddress = '10.0.0.1' OR sourceIPAddress = '10.0.0.2' OR sourceIPAddress = \"o'brien\")"
+        );
+        for event in &alert.evidence {
+            assert!(crate::db_engine::matches_condition(event, &filter));
+        }
+        assert!(alert_filter(&alert, &fields(&["missing"])).is_err());
+    }
+
+    #[test]
+    fn test_select_rules() {
+        let rules = vec![
+            rule(
+                "r1",
+                "Ransomware note",
+                "status: active\ntags: [ransomware]\ndetection:\n  severity: critical\n  condition: \"a = 'b'\"\n",
+            ),
+            rule(
+                "r2",
+                "Access key created",
+                "status: active\ntags: [aws, IAM]\ndetection:\n  severity: high\n  condition: \"a = 'b'\"\n",
+            ),
+            rule(
+                "r3",
+                "Policy attached",
+                "status: active\ntags: [iam]\ndetection:\n  severity: medium\n  condition: \"a = 'b'\"\n",
+            ),
+        ];
+        let strings = |items: &[&str]| items.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        let select = |ids: &[&str], tags: &[&str], severity: Option<&str>| {
+            select_rules(rules.clone(), &strings(ids), &strings(tags), severity)
+                .map(|rules| rules.into_iter().map(|r| r.id).collect::<Vec<_>>())
+        };
+
+        assert_eq!(select(&[], &[], None).unwrap(), vec!["r1", "r2", "r3"]);
+        assert_eq!(select(&["r3", "r1"], &[], None).unwrap(), vec!["r1", "r3"]);
+        assert_eq!(select(&[], &["iam"], None).unwrap(), vec!["r2", "r3"]);
+        assert_eq!(select(&[], &["iam"], Some("HIGH")).unwrap(), vec!["r2"]);
+        assert!(select(&["r4"], &[], None).is_err());
+        assert!(select(&["r1"], &["iam"], None).is_err());
+    }
+
+    #[test]
+    fn test_filter_rules() {
+        let rules = vec![
+            rule(
+                "r1",
+                "Root activity",
+                "status: active\ntags: [aws, iam]\ndetection:\n  severity: high\n  condition: \"userIdentity.type = 'Root'\"\n",
+            ),
+            rule(
+                "r2",
+                "Console login without MFA",
+                "status: disabled\ntags: [aws]\ndetection:\n  severity: medium\n  condition: \"eventName = 'ConsoleLogin'\"\n",
+            ),
+        ];
+        let search = |query: &str, tags: &[&str], status: Option<&str>| {
+            let search = RuleSearch {
+                query: Some(query.to_string()),
+                tags: tags.iter().map(|t| t.to_string()).collect(),
+                severity: None,
+                status: status.map(str::to_string),
+            };
+            filter_rules(rules.clone(), &search)
+                .into_iter()
+                .map(|r| r.id)
+                .collect::<Vec<_>>()
+        };
+
+        // Title matches rank first; descriptions and conditions match too
+        assert_eq!(search("console", &[], None), vec!["r2", "r1"]);
+        assert_eq!(search("root CONSOLE", &[], None), vec!["r1"]);
+        assert_eq!(search("", &["IAM"], None), vec!["r1"]);
+        assert_eq!(search("", &[], Some("Disabled")), vec!["r2"]);
+        assert!(search("okta", &[], None).is_empty());
+    }
+
+    #[test]
+    fn test_rule_pack_round_trip() {
+        let rule: RuleYaml = serde_yaml::from_str(
+            "id: r1\ntitle: Root login\ndescription: ''\nauthor: ''\nstatus: active\ndate: ''\ndetection:\n  severity: high\n  condition: \"userIdentity.type = 'Root'\"\n",
+        )
+        .unwrap();
+        let manifest = RulePackManifest {
+            name: "AWS basics".to_string(),
+            version: "1.0.0".to_string(),
+            author: "SOC".to_string(),
+            description: String::new(),
+            created: String::new(),
+            rule_ids: Vec::new(),
+        };
+
+        let path = std::env::temp_dir().join("offline_siem_test_rule_pack.zip");
+        let path = path.to_string_lossy();
+        let written = write_rule_pack(&path, manifest, &[rule]).unwrap();
+        assert_eq!(written.rule_ids, vec!["r1"]);
+
+        let (manifest, rules) = read_rule_pack(&path).unwrap();
+        assert_eq!(manifest.name, "AWS basics");
+        assert_eq!(manifest.rule_ids, vec!["r1"]);
+        assert_eq!(rules.len(), 1);
+        assert_eq!(rules[0].0, "rules/r1.yaml");
+        assert_eq!(rules[0].1.as_ref().unwrap().title, "Root login");
+
+        // A plain rules ZIP is not a pack
+        write_zip(&path, &[("r1.yaml".to_string(), String::new())]).unwrap();
+        assert!(read_rule_pack(&path).is_err());
+        fs::remove_file(&*path).ok();
+    }
+
+    #[test]
+    fn test_rule_ids_cannot_escape_rules_dir() {
+        assert!(validate_rule_id("r1").is_ok());
+        assert!(validate_rule_id("3f2a-9c_win.proc").is_ok());
+        for id in ["", "..", "../../x", "..\\..\\x", "a/b", ".hidden"] {
+            assert!(validate_rule_id(id).is_err(), "{}", id);
+        }
+
+        let base = std::env::temp_dir().join("offline_siem_test_rule_pack_ids");
+        let _ = fs::remove_dir_all(&base);
+        let rules_dir = base.join("rules");
+        fs::create_dir_all(&rules_dir).unwrap();
+        let rule = |id: &str| -> RuleYaml {
+            serde_yaml::from_str(&format!(
+                "id: '{}'\ntitle: Root login\ndescription: ''\nauthor: ''\nstatus: active\ndate: ''\ndetection:\n  severity: high\n  condition: \"userIdentity.type = 'Root'\"\n",
+                id
+            ))
+            .unwrap()
+        };
+        let manifest = RulePackManifest {
+            name: "Shared".to_string(),
+            version: String::new(),
+            author: String::new(),
+            description: String::new(),
+            created: String::new(),
+            rule_ids: Vec::new(),
+        };
+
+        let path = base.join("pack.zip");
+        let path = path.to_string_lossy();
+        write_rule_pack(&path, manifest, &[rule("../x"), rule("r1")]).unwrap();
+        let (manifest, rules) = read_rule_pack(&path).unwrap();
+        let summary =
+            import_pack_rules(&rules_dir, manifest, rules, CollisionPolicy::Rename).unwrap();
+
+        assert_eq!(summary.imported, vec!["r1"]);
+        assert_eq!(summary.errors.len(), 1);
+        assert!(!base.join("x.yaml").exists());
+        assert!(rules_dir.join("r1.yaml").exists());
+        fs::remove_dir_all(&base).unwrap();
+    }
 }
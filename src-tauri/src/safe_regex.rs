@@ -0,0 +1,153 @@
+//! Bounded regular expressions for the `REGEX` condition operator.
+//!
+//! Patterns are compiled with the linear-time `regex` crate (no backtracking),
+//! so evaluation cost grows with input length only. On top of that:
+//! - patterns longer than `MAX_PATTERN_LEN` are rejected,
+//! - compiled programs and lazy DFAs are capped by `COMPILED_SIZE_LIMIT` /
+//!   `DFA_SIZE_LIMIT` (large counted repetitions like `(a{1000}){1000}` fail).
+//!
+//! Field values are always matched whole: a match is linear in the value's
+//! length, which is bounded by the event size. Cutting values short would
+//! make `NOT REGEX` hold for a value whose match lies past the cut.
+//!
+//! Compiled patterns are cached so a rule's regex is built once per process.
+
+use regex::{Regex, RegexBuilder};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, OnceLock};
+
+use crate::models::SiemError;
+
+/// Longest pattern accepted, in bytes.
+pub const MAX_PATTERN_LEN: usize = 1024;
+
+/// Upper bound on the compiled program size, in bytes.
+const COMPILED_SIZE_LIMIT: usize = 1 << 20;
+
+/// Upper bound on the lazy DFA cache, in bytes.
+const DFA_SIZE_LIMIT: usize = 2 << 20;
+
+/// Maximum nesting depth of groups in a pattern.
+const MAX_NESTING_DEPTH: usize = 16;
+
+type RegexCache = Mutex<HashMap<(String, bool), Arc<Regex>>>;
+
+fn cache() -> &'static RegexCache {
+    static CACHE: OnceLock<RegexCache> = OnceLock::new();
+    CACHE.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Check a pattern against the complexity bounds and compile it.
+pub fn compile(pattern: &str, case_insensitive: bool) -> Result<Arc<Regex>, SiemError> {
+    let key = (pattern.to_string(), case_insensitive);
+    if let Some(regex) = cache().lock().ok().and_then(|c| c.get(&key).cloned()) {
+        return Ok(regex);
+    }
+
+    check_complexity(pattern)?;
+
+    let regex = RegexBuilder::new(pattern)
+        .case_insensitive(case_insensitive)
+        .size_limit(COMPILED_SIZE_LIMIT)
+        .dfa_size_limit(DFA_SIZE_LIMIT)
+        .nest_limit(MAX_NESTING_DEPTH as u32)
+        .build()
+        .map(Arc::new)
+        .map_err(|e| SiemError::Rule(format!("Invalid regex '{}': {}", pattern, e)))?;
+
+    if let Ok(mut c) = cache().lock() {
+        c.insert(key, regex.clone());
+    }
+
+    Ok(regex)
+}
+
+/// Reject patterns exceeding the length or nesting bounds without compiling them.
+pub fn check_complexity(pattern: &str) -> Result<(), SiemError> {
+    if pattern.is_empty() {
+        return Err(SiemError::Rule("Regex pattern cannot be empty".to_string()));
+    }
+
+    if pattern.len() > MAX_PATTERN_LEN {
+        return Err(SiemError::Rule(format!(
+            "Regex pattern is {} bytes, limit is {}",
+            pattern.len(),
+            MAX_PATTERN_LEN
+        )));
+    }
+
+    let mut depth = 0usize;
+    let mut max_depth = 0usize;
+    let mut escaped = false;
+    for c in pattern.chars() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '(' => {
+                depth += 1;
+                max_depth = max_depth.max(depth);
+            }
+            ')' => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+    }
+
+    if max_depth > MAX_NESTING_DEPTH {
+        return Err(SiemError::Rule(format!(
+            "Regex pattern nests groups {} deep, limit is {}",
+            max_depth, MAX_NESTING_DEPTH
+        )));
+    }
+
+    Ok(())
+}
+
+/// Match a whole value.
+pub fn is_match(regex: &Regex, value: &str) -> bool {
+    regex.is_match(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compile_and_match() {
+        let regex = compile(r"^powershell(\.exe)?$", true).unwrap();
+        assert!(is_match(&regex, "PowerShell.exe"));
+        assert!(!is_match(&regex, "cmd.exe"));
+    }
+
+    #[test]
+    fn test_rejects_long_pattern() {
+        let pattern = "a".repeat(MAX_PATTERN_LEN + 1);
+        assert!(check_complexity(&pattern).is_err());
+    }
+
+    #[test]
+    fn test_rejects_deep_nesting() {
+        let pattern = format!("{}a{}", "(".repeat(20), ")".repeat(20));
+        assert!(check_complexity(&pattern).is_err());
+        // Escaped parentheses don't count as groups
+        assert!(check_complexity(&r"\(".repeat(20)).is_ok());
+    }
+
+    #[test]
+    fn test_rejects_oversized_program() {
+        assert!(compile("(a{1000}){1000}", false).is_err());
+    }
+
+    #[test]
+    fn test_catastrophic_pattern_is_linear() {
+        let regex = compile("(a+)+$", false).unwrap();
+        let input = format!("{}!", "a".repeat(50_000));
+        assert!(!is_match(&regex, &input));
+    }
+
+    #[test]
+    fn test_matches_past_64kb() {
+        let regex = compile("mimikatz", true).unwrap();
+        let value = format!("{}Mimikatz.exe", "x".repeat(100 * 1024));
+        assert!(is_match(&regex, &value));
+    }
+}
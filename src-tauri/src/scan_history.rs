@@ -0,0 +1,569 @@
+//! Persisted scan history, cross-scan alert trends and scan comparison.
+//!
+//! After every scan, the rules run, its duration, per-rule and per-severity
+//! alert counts and a record of each alert are appended to `scan_history.json`
+//! in the application's data directory (the latest `MAX_HISTORY_ENTRIES` scans
+//! are kept). Trends group these entries by day or by scan and report the
+//! change of each count against the previous period, so repeated scans of a
+//! monitored folder show regressions and new activity. Comparing two scans of
+//! the same files lists the alerts that appeared or went away, e.g. to verify
+//! a remediation.
+
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::fs;
+use std::path::PathBuf;
+
+use crate::app_data;
+use crate::models::{
+    AlertEvent, AlertTrends, AlertTriage, RuleStatistics, RuleYaml, ScanAlertRecord, ScanDiff,
+    ScanHistoryEntry, SiemError, TrendBucket, TrendGranularity, TriageStatus,
+};
+
+/// Maximum number of scans kept in the history.
+const MAX_HISTORY_ENTRIES: usize = 1000;
+
+/// Get the path to the scan history file.
+fn get_history_path(app_handle: &tauri::AppHandle) -> Result<PathBuf, SiemError> {
+    app_data::app_data_file(app_handle, "scan_history.json")
+}
+
+/// Load the scan history (oldest first).
+pub fn load_history(app_handle: &tauri::AppHandle) -> Result<Vec<ScanHistoryEntry>, SiemError> {
+    let path = get_history_path(app_handle)?;
+
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let content = fs::read_to_string(&path)
+        .map_err(|e| SiemError::FileIO(format!("Cannot read scan history: {}", e)))?;
+
+    serde_json::from_str(&content)
+        .map_err(|e| SiemError::Serialization(format!("Cannot parse scan history: {}", e)))
+}
+
+/// Save the scan history.
+fn save_history(
+    app_handle: &tauri::AppHandle,
+    history: &[ScanHistoryEntry],
+) -> Result<(), SiemError> {
+    let path = get_history_path(app_handle)?;
+
+    let content = serde_json::to_string_pretty(history)
+        .map_err(|e| SiemError::Serialization(format!("Cannot serialize scan history: {}", e)))?;
+
+    fs::write(&path, content)
+        .map_err(|e| SiemError::FileIO(format!("Cannot write scan history: {}", e)))?;
+
+    Ok(())
+}
+
+/// Summarize the alerts of a scan into a history entry.
+pub fn summarize_scan(files: Vec<String>, alerts: &[AlertEvent]) -> ScanHistoryEntry {
+    let mut by_rule: BTreeMap<String, usize> = BTreeMap::new();
+    let mut by_severity: BTreeMap<String, usize> = BTreeMap::new();
+    let mut rule_titles: BTreeMap<String, String> = BTreeMap::new();
+
+    for alert in alerts {
+        *by_rule.entry(alert.rule_id.clone()).or_default() += 1;
+        *by_severity
+            .entry(alert.severity.to_lowercase())
+            .or_default() += 1;
+        rule_titles
+            .entry(alert.rule_id.clone())
+            .or_insert_with(|| alert.rule_title.clone());
+    }
+
+    ScanHistoryEntry {
+        scan_id: uuid::Uuid::new_v4().to_string(),
+        scanned_at: chrono::Utc::now().to_rfc3339(),
+        files,
+        total_alerts: alerts.len(),
+        by_rule,
+        by_severity,
+        rule_titles,
+        rule_ids: Vec::new(),
+        duration_ms: 0,
+        alerts: alerts.iter().map(alert_record).collect(),
+    }
+}
+
+/// Identity of an alert across scans: its rule and source file, plus what
+/// sets it apart from the rule's other alerts in the file (aggregation group
+/// and window, correlation key, absence or meta-rule entity, rare or new
+/// value, or the event of a single-event alert). A rule's alerts grouped per rule share
+/// one identity per file, whatever their match count.
+pub fn alert_key(alert: &AlertEvent) -> String {
+    let detail = if let Some(bucket) = &alert.aggregation {
+        serde_json::json!([bucket.group, bucket.window_start])
+    } else if let Some(correlation) = &alert.correlation {
+        serde_json::json!([correlation.key, correlation.first_seen])
+    } else if let Some(absence) = &alert.absence {
+        serde_json::json!([absence.entity, absence.window_start])
+    } else if let Some(rarity) = &alert.rarity {
+        serde_json::json!([rarity.field, rarity.value])
+    } else if let Some(first_seen) = &alert.first_seen {
+        serde_json::json!([first_seen.field, first_seen.value])
+    } else if let Some(meta) = &alert.meta {
+        serde_json::json!(meta.entity)
+    } else if alert.truncation.is_none() && alert.match_count == 1 && alert.evidence.len() == 1 {
+        alert.evidence[0].clone()
+    } else {
+        serde_json::Value::Null
+    };
+
+    let digest = Sha256::digest(
+        serde_json::json!([
+            alert.rule_id,
+            alert.source_file.as_deref().unwrap_or(""),
+            detail
+        ])
+        .to_string()
+        .as_bytes(),
+    );
+    app_data::hex(&digest[..16])
+}
+
+/// Record of an alert kept in the history.
+fn alert_record(alert: &AlertEvent) -> ScanAlertRecord {
+    ScanAlertRecord {
+        key: alert_key(alert),
+        rule_id: alert.rule_id.clone(),
+        rule_title: alert.rule_title.clone(),
+        severity: alert.severity.clone(),
+        source_file: alert.source_file.clone(),
+        match_count: alert.match_count,
+    }
+}
+
+/// Append a completed scan to the history.
+pub fn record_scan(
+    app_handle: &tauri::AppHandle,
+    files: Vec<String>,
+    rules: &[RuleYaml],
+    duration_ms: u64,
+    alerts: &[AlertEvent],
+) -> Result<(), SiemError> {
+    let mut history = load_history(app_handle)?;
+    let mut entry = summarize_scan(files, alerts);
+    entry.rule_ids = rules.iter().map(|rule| rule.id.clone()).collect();
+    entry.duration_ms = duration_ms;
+    history.push(entry);
+
+    if history.len() > MAX_HISTORY_ENTRIES {
+        let excess = history.len() - MAX_HISTORY_ENTRIES;
+        history.drain(..excess);
+    }
+
+    save_history(app_handle, &history)
+}
+
+/// Recorded scans, newest first, optionally limited to the latest `limit`.
+pub fn list_scans(history: Vec<ScanHistoryEntry>, limit: Option<usize>) -> Vec<ScanHistoryEntry> {
+    let mut scans: Vec<ScanHistoryEntry> = history.into_iter().rev().collect();
+    if let Some(limit) = limit {
+        scans.truncate(limit);
+    }
+    scans
+}
+
+/// Compare two recorded scans: alerts of `compare` missing from `base` are
+/// new, alerts of `base` missing from `compare` are resolved.
+pub fn diff_scans(
+    history: &[ScanHistoryEntry],
+    base_scan_id: &str,
+    compare_scan_id: &str,
+) -> Result<ScanDiff, SiemError> {
+    let find = |scan_id: &str| {
+        let scan = history
+            .iter()
+            .find(|entry| entry.scan_id == scan_id)
+            .ok_or_else(|| SiemError::Query(format!("Scan not found: {}", scan_id)))?;
+        if scan.alerts.is_empty() && scan.total_alerts > 0 {
+            return Err(SiemError::Query(format!(
+                "Scan {} was recorded without alert details and can't be compared",
+                scan_id
+            )));
+        }
+        Ok(scan)
+    };
+    let base = find(base_scan_id)?;
+    let compare = find(compare_scan_id)?;
+
+    let keys = |scan: &ScanHistoryEntry| -> HashSet<String> {
+        scan.alerts.iter().map(|alert| alert.key.clone()).collect()
+    };
+    let (base_keys, compare_keys) = (keys(base), keys(compare));
+    let new_alerts: Vec<ScanAlertRecord> = compare
+        .alerts
+        .iter()
+        .filter(|alert| !base_keys.contains(&alert.key))
+        .cloned()
+        .collect();
+    let resolved_alerts: Vec<ScanAlertRecord> = base
+        .alerts
+        .iter()
+        .filter(|alert| !compare_keys.contains(&alert.key))
+        .cloned()
+        .collect();
+
+    let files = |scan: &ScanHistoryEntry| scan.files.iter().cloned().collect::<BTreeSet<_>>();
+    let mut rule_deltas = count_deltas(&base.by_rule, &compare.by_rule);
+    rule_deltas.retain(|_, delta| *delta != 0);
+
+    Ok(ScanDiff {
+        base_scan_id: base.scan_id.clone(),
+        compare_scan_id: compare.scan_id.clone(),
+        same_files: files(base) == files(compare),
+        unchanged_alerts: compare.alerts.len() - new_alerts.len(),
+        new_alerts,
+        resolved_alerts,
+        rule_deltas,
+    })
+}
+
+/// Compute alert trends over the history, optionally limited to the last `limit` periods.
+pub fn compute_trends(
+    history: &[ScanHistoryEntry],
+    granularity: TrendGranularity,
+    limit: Option<usize>,
+) -> AlertTrends {
+    // Group entries into periods, in chronological order
+    let mut periods: BTreeMap<String, Vec<&ScanHistoryEntry>> = BTreeMap::new();
+    for entry in history {
+        let period = match granularity {
+            TrendGranularity::Day => entry.scanned_at.get(..10).unwrap_or("").to_string(),
+            TrendGranularity::Scan => entry.scanned_at.clone(),
+        };
+        periods.entry(period).or_default().push(entry);
+    }
+
+    let mut buckets: Vec<TrendBucket> = Vec::new();
+    for (period, entries) in periods {
+        let mut by_rule: BTreeMap<String, usize> = BTreeMap::new();
+        let mut by_severity: BTreeMap<String, usize> = BTreeMap::new();
+        for entry in &entries {
+            for (rule_id, count) in &entry.by_rule {
+                *by_rule.entry(rule_id.clone()).or_default() += count;
+            }
+            for (severity, count) in &entry.by_severity {
+                *by_severity.entry(severity.clone()).or_default() += count;
+            }
+        }
+        let total_alerts = entries.iter().map(|e| e.total_alerts).sum();
+
+        let previous = buckets.last();
+        let bucket = TrendBucket {
+            period,
+            scan_count: entries.len(),
+            total_alerts,
+            total_delta: previous.map(|p| total_alerts as i64 - p.total_alerts as i64),
+            rule_deltas: previous
+                .map(|p| count_deltas(&p.by_rule, &by_rule))
+                .unwrap_or_default(),
+            severity_deltas: previous
+                .map(|p| count_deltas(&p.by_severity, &by_severity))
+                .unwrap_or_default(),
+            by_rule,
+            by_severity,
+        };
+        buckets.push(bucket);
+    }
+
+    if let Some(limit) = limit {
+        let skip = buckets.len().saturating_sub(limit);
+        buckets.drain(..skip);
+    }
+
+    // Later entries win so renamed rules show their current title
+    let rule_titles = history
+        .iter()
+        .flat_map(|entry| entry.rule_titles.clone())
+        .collect();
+
+    AlertTrends {
+        granularity,
+        buckets,
+        rule_titles,
+    }
+}
+
+/// Lifetime statistics of every rule that exists or appears in the history,
+/// with false-positive rates from the triage verdicts. Rules with the most
+/// alerts come first; rules that never fired follow, so dead rules stand out.
+pub fn rule_statistics(
+    history: &[ScanHistoryEntry],
+    rules: &[RuleYaml],
+    triage: &[AlertTriage],
+) -> Vec<RuleStatistics> {
+    let mut stats: BTreeMap<String, RuleStatistics> = BTreeMap::new();
+    let new_stats = |rule_id: &str, rule_title: &str| RuleStatistics {
+        rule_id: rule_id.to_string(),
+        rule_title: rule_title.to_string(),
+        status: None,
+        scans_run: 0,
+        scans_fired: 0,
+        total_alerts: 0,
+        total_matches: 0,
+        first_fired: None,
+        last_fired: None,
+        triaged_alerts: 0,
+        false_positives: 0,
+        false_positive_rate: None,
+    };
+
+    // History is oldest first, so later titles overwrite earlier ones
+    for entry in history {
+        let ran: BTreeSet<&String> = entry.rule_ids.iter().chain(entry.by_rule.keys()).collect();
+        for rule_id in ran {
+            let title = entry
+                .rule_titles
+                .get(rule_id)
+                .map_or(rule_id.as_str(), String::as_str);
+            let rule = stats
+                .entry(rule_id.clone())
+                .or_insert_with(|| new_stats(rule_id, title));
+            rule.rule_title = title.to_string();
+            rule.scans_run += 1;
+
+            let alerts = entry.by_rule.get(rule_id).copied().unwrap_or(0);
+            if alerts > 0 {
+                rule.scans_fired += 1;
+                rule.total_alerts += alerts;
+                rule.first_fired
+                    .get_or_insert_with(|| entry.scanned_at.clone());
+                rule.last_fired = Some(entry.scanned_at.clone());
+            }
+        }
+        for alert in &entry.alerts {
+            if let Some(rule) = stats.get_mut(&alert.rule_id) {
+                rule.total_matches += alert.match_count;
+            }
+        }
+    }
+
+    for rule in rules {
+        let current = stats
+            .entry(rule.id.clone())
+            .or_insert_with(|| new_stats(&rule.id, &rule.title));
+        current.rule_title = rule.title.clone();
+        current.status = Some(rule.status.clone());
+    }
+
+    for verdict in triage {
+        if let Some(rule) = stats.get_mut(&verdict.rule_id) {
+            rule.triaged_alerts += 1;
+            if verdict.status == TriageStatus::FalsePositive {
+                rule.false_positives += 1;
+            }
+        }
+    }
+
+    let mut stats: Vec<RuleStatistics> = stats
+        .into_values()
+        .map(|mut rule| {
+            if rule.triaged_alerts > 0 {
+                rule.false_positive_rate =
+                    Some(rule.false_positives as f64 / rule.triaged_alerts as f64);
+            }
+            rule
+        })
+        .collect();
+    stats.sort_by(|a, b| {
+        b.total_alerts
+            .cmp(&a.total_alerts)
+            .then_with(|| a.rule_id.cmp(&b.rule_id))
+    });
+    stats
+}
+
+/// Per-key change between two count maps (keys missing on one side count as 0).
+fn count_deltas(
+    previous: &BTreeMap<String, usize>,
+    current: &BTreeMap<String, usize>,
+) -> BTreeMap<String, i64> {
+    let keys: BTreeSet<&String> = previous.keys().chain(current.keys()).collect();
+    keys.into_iter()
+        .map(|key| {
+            let before = previous.get(key).copied().unwrap_or(0) as i64;
+            let after = current.get(key).copied().unwrap_or(0) as i64;
+            (key.clone(), after - before)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(scanned_at: &str, rules: &[(&str, &str, usize)]) -> ScanHistoryEntry {
+        let mut by_rule = BTreeMap::new();
+        let mut by_severity = BTreeMap::new();
+        for (rule_id, severity, count) in rules {
+            by_rule.insert(rule_id.to_string(), *count);
+            *by_severity.entry(severity.to_string()).or_default() += count;
+        }
+        ScanHistoryEntry {
+            scan_id: scanned_at.to_string(),
+            scanned_at: scanned_at.to_string(),
+            files: vec![],
+            total_alerts: rules.iter().map(|(_, _, c)| c).sum(),
+            by_rule,
+            by_severity,
+            rule_titles: BTreeMap::new(),
+            rule_ids: vec![],
+            duration_ms: 0,
+            alerts: vec![],
+        }
+    }
+
+    #[test]
+    fn test_daily_trends_with_deltas() {
+        let history = vec![
+            entry("2024-01-01T08:00:00+00:00", &[("r1", "high", 2)]),
+            entry("2024-01-01T20:00:00+00:00", &[("r1", "high", 1)]),
+            entry(
+                "2024-01-02T08:00:00+00:00",
+                &[("r1", "high", 1), ("r2", "low", 4)],
+            ),
+        ];
+
+        let trends = compute_trends(&history, TrendGranularity::Day, None);
+        assert_eq!(trends.buckets.len(), 2);
+
+        let first = &trends.buckets[0];
+        assert_eq!(first.period, "2024-01-01");
+        assert_eq!(first.scan_count, 2);
+        assert_eq!(first.by_rule["r1"], 3);
+        assert_eq!(first.total_delta, None);
+
+        let second = &trends.buckets[1];
+        assert_eq!(second.total_delta, Some(2));
+        assert_eq!(second.rule_deltas["r1"], -2);
+        assert_eq!(second.rule_deltas["r2"], 4);
+        assert_eq!(second.severity_deltas["low"], 4);
+    }
+
+    #[test]
+    fn test_diff_scans() {
+        let alert = |rule_id: &str, user: &str, file: &str| -> AlertEvent {
+            serde_json::from_value(serde_json::json!({
+                "rule_id": rule_id,
+                "rule_title": rule_id,
+                "severity": "high",
+                "timestamp": "2025-12-16T11:05:00+00:00",
+                "match_count": 1,
+                "evidence": [{ "user": user }],
+                "source_file": file
+            }))
+            .unwrap()
+        };
+        let files = vec!["trail.json".to_string()];
+        let base = summarize_scan(
+            files.clone(),
+            &[
+                alert("r1", "alice", "trail.json"),
+                alert("r1", "bob", "trail.json"),
+                alert("r2", "alice", "trail.json"),
+            ],
+        );
+        let compare = summarize_scan(
+            files,
+            &[
+                alert("r1", "bob", "trail.json"),
+                alert("r2", "alice", "trail.json"),
+                alert("r2", "carol", "trail.json"),
+            ],
+        );
+        let history = vec![base.clone(), compare.clone()];
+
+        let diff = diff_scans(&history, &base.scan_id, &compare.scan_id).unwrap();
+        assert!(diff.same_files);
+        assert_eq!(diff.unchanged_alerts, 2);
+        assert_eq!(diff.new_alerts.len(), 1);
+        assert_eq!(diff.new_alerts[0].rule_id, "r2");
+        assert_eq!(diff.resolved_alerts.len(), 1);
+        assert_eq!(diff.resolved_alerts[0].rule_id, "r1");
+        assert_eq!(diff.rule_deltas.len(), 2);
+
+        assert!(diff_scans(&history, &base.scan_id, "missing").is_err());
+        let listed = list_scans(history, Some(1));
+        assert_eq!(listed[0].scan_id, compare.scan_id);
+    }
+
+    #[test]
+    fn test_rule_statistics() {
+        let mut first = entry("2024-01-01T08:00:00+00:00", &[("noisy", "low", 3)]);
+        first.rule_ids = vec!["noisy".to_string(), "dead".to_string()];
+        let mut second = entry(
+            "2024-01-02T08:00:00+00:00",
+            &[("noisy", "low", 2), ("gone", "high", 1)],
+        );
+        second.rule_ids = vec!["noisy".to_string(), "dead".to_string(), "gone".to_string()];
+        let history = vec![first, second];
+
+        let rule = |id: &str| -> RuleYaml {
+            serde_yaml::from_str(&format!(
+                "id: {}\ntitle: {}\ndescription: ''\nauthor: ''\nstatus: active\ndate: ''\ndetection:\n  severity: low\n  condition: \"a = 'b'\"\n",
+                id, id
+            ))
+            .unwrap()
+        };
+        let verdict = |key: &str, status: TriageStatus| AlertTriage {
+            key: key.to_string(),
+            rule_id: "noisy".to_string(),
+            status,
+            note: String::new(),
+            updated_at: String::new(),
+        };
+        let triage = vec![
+            verdict("a", TriageStatus::FalsePositive),
+            verdict("b", TriageStatus::FalsePositive),
+            verdict("c", TriageStatus::FalsePositive),
+            verdict("d", TriageStatus::Benign),
+        ];
+
+        let stats = rule_statistics(&history, &[rule("noisy"), rule("dead")], &triage);
+        let ids: Vec<&str> = stats.iter().map(|s| s.rule_id.as_str()).collect();
+        assert_eq!(ids, vec!["noisy", "gone", "dead"]);
+
+        let noisy = &stats[0];
+        assert_eq!(
+            (noisy.scans_run, noisy.scans_fired, noisy.total_alerts),
+            (2, 2, 5)
+        );
+        assert_eq!(
+            noisy.first_fired.as_deref(),
+            Some("2024-01-01T08:00:00+00:00")
+        );
+        assert_eq!(
+            noisy.last_fired.as_deref(),
+            Some("2024-01-02T08:00:00+00:00")
+        );
+        assert_eq!(noisy.false_positive_rate, Some(0.75));
+
+        let gone = &stats[1];
+        assert_eq!(gone.status, None);
+        assert_eq!(gone.false_positive_rate, None);
+
+        let dead = &stats[2];
+        assert_eq!((dead.scans_run, dead.scans_fired), (2, 0));
+        assert_eq!(dead.status.as_deref(), Some("active"));
+        assert_eq!(dead.last_fired, None);
+    }
+
+    #[test]
+    fn test_per_scan_trends_limit() {
+        let history = vec![
+            entry("2024-01-01T08:00:00+00:00", &[("r1", "high", 1)]),
+            entry("2024-01-01T09:00:00+00:00", &[("r1", "high", 2)]),
+            entry("2024-01-01T10:00:00+00:00", &[("r1", "high", 5)]),
+        ];
+
+        let trends = compute_trends(&history, TrendGranularity::Scan, Some(2));
+        assert_eq!(trends.buckets.len(), 2);
+        assert_eq!(trends.buckets[0].total_delta, Some(1));
+        assert_eq!(trends.buckets[1].total_delta, Some(3));
+    }
+}
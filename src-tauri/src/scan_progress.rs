@@ -0,0 +1,88 @@
+//! Incremental scan progress for the frontend.
+//!
+//! Long scans emit `ScanProgress` payloads on `SCAN_PROGRESS_EVENT` as chunks
+//! of the log file are streamed through the rules, so the UI can show a
+//! progress bar. Updates are throttled to `MIN_EMIT_INTERVAL`; the first and
+//! the final update are always sent.
+
+use serde::Serialize;
+use std::time::{Duration, Instant};
+use tauri::Emitter;
+
+/// Tauri event carrying a `ScanProgress`.
+pub const SCAN_PROGRESS_EVENT: &str = "scan://progress";
+
+/// Minimum time between two intermediate progress events.
+const MIN_EMIT_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Payload of a progress notification.
+#[derive(Debug, Clone, Serialize)]
+pub struct ScanProgress {
+    /// Log file being scanned
+    pub log_path: String,
+    /// Number of rules to evaluate
+    pub rules_total: usize,
+    /// Number of rules evaluated so far (rules run together, so 0 until done)
+    pub rules_completed: usize,
+    /// Events read so far, each evaluated by every rule
+    pub events_processed: usize,
+    /// Alerts generated so far (known once the scan is done)
+    pub alerts_so_far: usize,
+    /// Whether the scan has finished
+    pub done: bool,
+}
+
+/// Emits throttled progress events for one scan.
+pub struct ProgressReporter<'a> {
+    app_handle: &'a tauri::AppHandle,
+    progress: ScanProgress,
+    last_emit: Option<Instant>,
+}
+
+impl<'a> ProgressReporter<'a> {
+    pub fn new(app_handle: &'a tauri::AppHandle, log_path: &str, rules_total: usize) -> Self {
+        ProgressReporter {
+            app_handle,
+            progress: ScanProgress {
+                log_path: log_path.to_string(),
+                rules_total,
+                rules_completed: 0,
+                events_processed: 0,
+                alerts_so_far: 0,
+                done: false,
+            },
+            last_emit: None,
+        }
+    }
+
+    /// Report that a chunk of `events` events was evaluated by every rule.
+    pub fn chunk_completed(&mut self, events: usize) {
+        self.progress.events_processed += events;
+        self.emit(false);
+    }
+
+    /// Report the end of the scan (meta-rules included).
+    pub fn finish(&mut self, total_alerts: usize) {
+        self.progress.rules_completed = self.progress.rules_total;
+        self.progress.alerts_so_far = total_alerts;
+        self.progress.done = true;
+        self.emit(true);
+    }
+
+    fn emit(&mut self, force: bool) {
+        let due = self
+            .last_emit
+            .is_none_or(|last| last.elapsed() >= MIN_EMIT_INTERVAL);
+        if !force && !due {
+            return;
+        }
+
+        self.last_emit = Some(Instant::now());
+        if let Err(e) = self
+            .app_handle
+            .emit(SCAN_PROGRESS_EVENT, self.progress.clone())
+        {
+            eprintln!("Warning: Cannot emit scan progress: {}", e);
+        }
+    }
+}
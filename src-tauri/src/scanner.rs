@@ -0,0 +1,1092 @@
+//! Multi-file scan orchestration.
+//!
+//! Scans a selected set of log files of mixed types in one pass: each file is
+//! streamed once in chunks, only the rules whose logsource applies to the file's type are
+//! evaluated against it, and the results are merged into a single
+//! deduplicated alert set.
+
+use rayon::prelude::*;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+use std::time::{Duration, Instant};
+
+use crate::absence;
+use crate::aggregation;
+use crate::correlation;
+use crate::db_engine;
+use crate::event_time::TimeFilter;
+use crate::first_seen;
+use crate::ingest_cache;
+use crate::meta_rules;
+use crate::models::{
+    AlertEvent, AlertGrouping, AlertTruncation, FailedFileScan, LogSetScanResponse, LogType,
+    RoutingSummary, RuleRunStats, RuleYaml, ScannedFile, SiemError,
+};
+use crate::rarity;
+use crate::sql_compiler;
+
+/// Maximum number of evidence events kept per alert.
+const MAX_EVIDENCE_PER_ALERT: usize = 1000;
+
+/// A log file selected for scanning, with its resolved log type.
+pub struct ScanTarget {
+    pub file_name: String,
+    pub file_path: String,
+    pub log_type: LogType,
+    /// Up-to-date copy in the ingestion cache, read instead of the file
+    pub cached: Option<ingest_cache::CachedSource>,
+}
+
+/// Result of streaming one file through a set of rules.
+pub struct FileScan {
+    /// Alerts, in rule order
+    pub alerts: Vec<AlertEvent>,
+    /// Number of events read
+    pub events_read: usize,
+    /// Outcome of each rule, in rule order
+    pub rule_results: Vec<RuleRunStats>,
+}
+
+/// The events a scan reads.
+pub struct ScanSource<'a> {
+    pub log_path: &'a str,
+    pub log_type: &'a LogType,
+    /// Up-to-date copy in the ingestion cache, read instead of the file
+    pub cached: Option<&'a ingest_cache::CachedSource>,
+    /// Only the events within this time range are evaluated
+    pub time_filter: Option<&'a TimeFilter>,
+}
+
+/// Product and service of the logs of a type, when the format implies them.
+/// Flat JSON, key=value files and custom formats can hold events of any product,
+/// Windows exports events of any channel (service).
+fn log_product_service(log_type: &LogType) -> Option<(&'static str, &'static str)> {
+    match log_type {
+        LogType::CloudTrail => Some(("aws", "cloudtrail")),
+        LogType::Suricata => Some(("suricata", "eve")),
+        LogType::AzureActivity => Some(("azure", "activitylogs")),
+        LogType::EntraSignIn => Some(("azure", "signinlogs")),
+        LogType::Okta => Some(("okta", "okta")),
+        LogType::FlatJson
+        | LogType::Kv
+        | LogType::Windows
+        | LogType::WindowsXml
+        | LogType::Plugin(_)
+        | LogType::Text(_) => None,
+    }
+}
+
+/// Check whether a rule applies to a log type.
+/// Rules without a logsource apply to every log type. A product or service
+/// is only checked when the log type implies one (CloudTrail is aws/cloudtrail,
+/// Suricata is suricata/eve, Azure logs are azure/activitylogs and
+/// azure/signinlogs and Okta is okta/okta as in Sigma).
+pub fn rule_applies_to(rule: &RuleYaml, log_type: &LogType) -> bool {
+    let Some(logsource) = rule.logsource.as_ref() else {
+        return true;
+    };
+    if logsource
+        .log_type
+        .as_ref()
+        .is_some_and(|expected| expected != log_type)
+    {
+        return false;
+    }
+
+    let Some((product, service)) = log_product_service(log_type) else {
+        return true;
+    };
+    let matches = |expected: &Option<String>, actual: &str| {
+        expected
+            .as_deref()
+            .is_none_or(|expected| expected.trim().eq_ignore_ascii_case(actual))
+    };
+    matches(&logsource.product, product) && matches(&logsource.service, service)
+}
+
+/// Apply the global alert grouping to rules that don't set their own.
+pub fn apply_default_grouping(rules: &mut [RuleYaml], grouping: AlertGrouping) {
+    for rule in rules {
+        rule.detection.grouping.get_or_insert(grouping);
+    }
+}
+
+/// Apply the global alert cap to rules that don't set their own.
+pub fn apply_default_alert_cap(rules: &mut [RuleYaml], max_alerts_per_scan: usize) {
+    for rule in rules {
+        rule.detection
+            .max_alerts_per_scan
+            .get_or_insert(max_alerts_per_scan);
+    }
+}
+
+/// Merge the alerts of each rule that produced more than its
+/// `max_alerts_per_scan` into one summary alert carrying a truncation
+/// notice, so a mis-tuned rule can't bury the alerts of the others. The
+/// summary takes the place of the rule's first alert.
+pub fn cap_alerts<'r, I>(rules: I, alerts: Vec<AlertEvent>) -> Vec<AlertEvent>
+where
+    I: IntoIterator<Item = &'r RuleYaml>,
+{
+    let limits: HashMap<&str, usize> = rules
+        .into_iter()
+        .filter_map(|rule| {
+            let limit = rule
+                .detection
+                .max_alerts_per_scan
+                .filter(|limit| *limit > 0)?;
+            Some((rule.id.as_str(), limit))
+        })
+        .collect();
+
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    for alert in &alerts {
+        let produced = alert.truncation.as_ref().map_or(1, |t| t.alert_count);
+        *counts.entry(alert.rule_id.as_str()).or_default() += produced;
+    }
+    let over: HashMap<String, (usize, usize)> = counts
+        .into_iter()
+        .filter_map(|(rule_id, count)| {
+            let limit = *limits.get(rule_id)?;
+            (count > limit).then(|| (rule_id.to_string(), (count, limit)))
+        })
+        .collect();
+    if over.is_empty() {
+        return alerts;
+    }
+
+    let mut capped: Vec<AlertEvent> = Vec::with_capacity(alerts.len());
+    let mut summaries: HashMap<String, usize> = HashMap::new();
+    for alert in alerts {
+        let Some(&(count, limit)) = over.get(&alert.rule_id) else {
+            capped.push(alert);
+            continue;
+        };
+        match summaries.get(&alert.rule_id) {
+            Some(&index) => {
+                let summary = &mut capped[index];
+                summary.match_count += alert.match_count;
+                let room = MAX_EVIDENCE_PER_ALERT.saturating_sub(summary.evidence.len());
+                summary
+                    .evidence
+                    .extend(alert.evidence.into_iter().take(room));
+                if summary.source_file != alert.source_file {
+                    summary.source_file = None;
+                }
+            }
+            None => {
+                let mut summary = alert;
+                summary.aggregation = None;
+                summary.correlation = None;
+                summary.meta = None;
+                summary.absence = None;
+                summary.rarity = None;
+                summary.first_seen = None;
+                summary.evidence.truncate(MAX_EVIDENCE_PER_ALERT);
+                summary.truncation = Some(AlertTruncation {
+                    alert_count: count,
+                    limit,
+                    notice: format!(
+                        "Rule produced {} alerts, more than its limit of {} per scan; they were merged into this alert. Consider tuning the rule.",
+                        count, limit
+                    ),
+                });
+                summaries.insert(summary.rule_id.clone(), capped.len());
+                capped.push(summary);
+            }
+        }
+    }
+    capped
+}
+
+/// Reduce the evidence of alerts whose rule sets `output.evidence_fields` to
+/// those fields. Dot paths keep their nesting, so the projected events still
+/// answer the same field lookups; arrays along a path are kept whole.
+pub fn project_evidence(rules: &[RuleYaml], alerts: &mut [AlertEvent]) {
+    let projections: HashMap<&str, &[String]> = rules
+        .iter()
+        .filter_map(|rule| {
+            let fields = &rule.output.as_ref()?.evidence_fields;
+            (!fields.is_empty()).then_some((rule.id.as_str(), fields.as_slice()))
+        })
+        .collect();
+    if projections.is_empty() {
+        return;
+    }
+
+    for alert in alerts {
+        if let Some(fields) = projections.get(alert.rule_id.as_str()) {
+            for event in &mut alert.evidence {
+                *event = project_event(event, fields);
+            }
+        }
+    }
+}
+
+/// Keep only the given fields of an event.
+fn project_event(event: &serde_json::Value, fields: &[String]) -> serde_json::Value {
+    let mut projected = serde_json::Map::new();
+    for field in fields {
+        let parts: Vec<&str> = field.trim().split('.').collect();
+        copy_field(event, &parts, &mut projected);
+    }
+    serde_json::Value::Object(projected)
+}
+
+/// Copy the value at a path from `source` into `target`, creating the
+/// enclosing objects.
+fn copy_field(
+    source: &serde_json::Value,
+    parts: &[&str],
+    target: &mut serde_json::Map<String, serde_json::Value>,
+) {
+    let Some((part, rest)) = parts.split_first() else {
+        return;
+    };
+    let Some(value) = source.get(*part) else {
+        return;
+    };
+    if rest.is_empty() || value.is_array() {
+        target.insert(part.to_string(), value.clone());
+        return;
+    }
+    if !value.is_object() {
+        return;
+    }
+
+    let child = target
+        .entry(part.to_string())
+        .or_insert_with(|| serde_json::Value::Object(serde_json::Map::new()));
+    if let serde_json::Value::Object(child) = child {
+        copy_field(value, rest, child);
+        if child.is_empty() {
+            target.remove(*part);
+        }
+    }
+}
+
+/// Maximum number of events a rule may match during a scan.
+/// Grouped, aggregation and sequence rules need every match to report
+/// counts, fill buckets or correlate steps correctly; per-event rules are
+/// capped to bound the number of alerts.
+pub fn match_limit(rule: &RuleYaml) -> usize {
+    let aggregated = rule
+        .detection
+        .aggregation
+        .as_ref()
+        .is_some_and(|aggregation| aggregation.enabled);
+    if rule.detection.sequence.is_some() || aggregated {
+        return usize::MAX;
+    }
+    match rule.detection.grouping.unwrap_or_default() {
+        AlertGrouping::PerRule => usize::MAX,
+        AlertGrouping::PerEvent => MAX_EVIDENCE_PER_ALERT,
+    }
+}
+
+/// Turn a rule's matching events into alerts.
+///
+/// Plain rules produce a single alert holding all matches. Rules with an
+/// enabled aggregation produce one alert per (group, time window) bucket that
+/// meets the threshold. Sequence rules produce one alert per completed
+/// sequence, with the step events as evidence.
+pub fn build_alerts(
+    rule: &RuleYaml,
+    events: Vec<serde_json::Value>,
+    log_type: &LogType,
+    source_file: Option<String>,
+) -> Result<Vec<AlertEvent>, SiemError> {
+    if events.is_empty() {
+        return Ok(vec![]);
+    }
+
+    if let Some(sequence) = &rule.detection.sequence {
+        let case_mode = db_engine::CaseMode::from_flag(rule.detection.case_sensitive);
+        let matches = correlation::correlate_events(sequence, case_mode, events, log_type)?;
+        return Ok(matches
+            .into_iter()
+            .map(|(correlation, evidence)| {
+                let mut alert = AlertEvent::from_rule(rule, evidence, source_file.clone());
+                alert.correlation = Some(correlation);
+                alert
+            })
+            .collect());
+    }
+
+    match &rule.detection.aggregation {
+        Some(aggregation) if aggregation.enabled => {
+            let buckets = aggregation::aggregate_events(aggregation, events, log_type)?;
+            Ok(buckets
+                .into_iter()
+                .map(|(bucket, evidence)| {
+                    let mut alert = AlertEvent::from_rule(rule, evidence, source_file.clone());
+                    alert.aggregation = Some(bucket);
+                    alert
+                })
+                .collect())
+        }
+        _ => Ok(group_matches(rule, events, source_file)),
+    }
+}
+
+/// Turn plain matches into alerts according to the rule's grouping.
+fn group_matches(
+    rule: &RuleYaml,
+    events: Vec<serde_json::Value>,
+    source_file: Option<String>,
+) -> Vec<AlertEvent> {
+    match rule.detection.grouping.unwrap_or_default() {
+        AlertGrouping::PerEvent => events
+            .into_iter()
+            .map(|event| AlertEvent::from_rule(rule, vec![event], source_file.clone()))
+            .collect(),
+        AlertGrouping::PerRule => {
+            let mut sample = EvidenceSample::default();
+            for event in &events {
+                sample.add(event);
+            }
+            vec![sample.into_alert(rule, source_file)]
+        }
+    }
+}
+
+/// Matches of a rule grouped per rule: every match is counted (repeated
+/// identical events such as brute-force retries included), a capped sample of
+/// distinct events is kept as evidence.
+#[derive(Default)]
+struct EvidenceSample {
+    matches: usize,
+    seen: HashSet<u64>,
+    evidence: Vec<serde_json::Value>,
+}
+
+impl EvidenceSample {
+    fn add(&mut self, event: &serde_json::Value) {
+        self.matches += 1;
+        // Collapse identical events in the evidence
+        let mut hasher = DefaultHasher::new();
+        event.to_string().hash(&mut hasher);
+        if self.seen.insert(hasher.finish()) && self.evidence.len() < MAX_EVIDENCE_PER_ALERT {
+            self.evidence.push(event.clone());
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.matches == 0
+    }
+
+    fn into_alert(self, rule: &RuleYaml, source_file: Option<String>) -> AlertEvent {
+        let mut alert = AlertEvent::from_rule(rule, self.evidence, source_file);
+        alert.match_count = self.matches;
+        alert
+    }
+}
+
+/// A rule's matches in a file scan, and the time spent evaluating it.
+struct RuleRun<'r> {
+    /// Position of the rule in the scanned rule list
+    index: usize,
+    matches: RuleMatches<'r>,
+    elapsed: Duration,
+}
+
+impl<'r> RuleRun<'r> {
+    fn rule(&self) -> &'r RuleYaml {
+        self.matches.rule()
+    }
+
+    fn observe<'e, I>(&mut self, events: I)
+    where
+        I: IntoIterator<Item = &'e serde_json::Value>,
+    {
+        let start = Instant::now();
+        self.matches.observe(events);
+        self.elapsed += start.elapsed();
+    }
+
+    /// Build the rule's alerts and report how the rule ran.
+    fn finish(
+        self,
+        log_type: &LogType,
+        source_file: Option<&str>,
+    ) -> (Vec<AlertEvent>, RuleRunStats) {
+        let rule = self.rule();
+        let start = Instant::now();
+        let result = self.matches.finish(log_type, source_file);
+        let elapsed = self.elapsed + start.elapsed();
+
+        let (alerts, error) = match result {
+            Ok(alerts) => (alerts, None),
+            Err(e) => {
+                // Log error but continue with other rules
+                eprintln!("Warning: Rule '{}' failed: {}", rule.title, e);
+                (Vec::new(), Some(e.to_string()))
+            }
+        };
+        let mut stats = RuleRunStats::from_rule(rule, error);
+        stats.match_count = alerts.iter().map(|a| a.match_count).sum();
+        stats.alert_count = alerts.len();
+        stats.execution_time_ms = elapsed.as_secs_f64() * 1000.0;
+        (alerts, stats)
+    }
+}
+
+/// What a rule keeps from the events it is fed, chunk by chunk.
+enum RuleMatches<'r> {
+    /// Matching events, up to `match_limit` (aggregation, sequence and per-event rules)
+    Collected {
+        rule: &'r RuleYaml,
+        events: Vec<serde_json::Value>,
+    },
+    /// Count and evidence sample of plain rules grouped per rule
+    Sampled {
+        rule: &'r RuleYaml,
+        sample: EvidenceSample,
+    },
+    /// Absence rules look at every event, not only the matches
+    Absence(absence::AbsenceTracker<'r>),
+    /// Rarity rules count the values of the matches
+    Rarity(rarity::RarityTracker<'r>),
+    /// First-seen rules keep the earliest match of every value
+    FirstSeen(first_seen::FirstSeenTracker<'r>),
+}
+
+impl<'r> RuleMatches<'r> {
+    fn new(rule: &'r RuleYaml, log_type: &LogType) -> Result<Self, SiemError> {
+        db_engine::resolve_term_sets(&rule.detection)?;
+        if let Some(expectation) = &rule.detection.absence {
+            return Ok(RuleMatches::Absence(absence::AbsenceTracker::new(
+                rule,
+                expectation,
+                log_type,
+            )?));
+        }
+        if let Some(rarity) = &rule.detection.rarity {
+            return Ok(RuleMatches::Rarity(rarity::RarityTracker::new(
+                rule, rarity,
+            )?));
+        }
+        if let Some(expectation) = &rule.detection.first_seen {
+            return Ok(RuleMatches::FirstSeen(first_seen::FirstSeenTracker::new(
+                rule,
+                expectation,
+                log_type,
+            )?));
+        }
+
+        let aggregated = rule
+            .detection
+            .aggregation
+            .as_ref()
+            .is_some_and(|aggregation| aggregation.enabled);
+        let per_rule = rule.detection.grouping.unwrap_or_default() == AlertGrouping::PerRule;
+        if per_rule && !aggregated && rule.detection.sequence.is_none() {
+            return Ok(RuleMatches::Sampled {
+                rule,
+                sample: EvidenceSample::default(),
+            });
+        }
+
+        Ok(RuleMatches::Collected {
+            rule,
+            events: Vec::new(),
+        })
+    }
+
+    fn rule(&self) -> &'r RuleYaml {
+        match self {
+            RuleMatches::Collected { rule, .. } | RuleMatches::Sampled { rule, .. } => *rule,
+            RuleMatches::Absence(tracker) => tracker.rule(),
+            RuleMatches::Rarity(tracker) => tracker.rule(),
+            RuleMatches::FirstSeen(tracker) => tracker.rule(),
+        }
+    }
+
+    fn observe<'e, I>(&mut self, events: I)
+    where
+        I: IntoIterator<Item = &'e serde_json::Value>,
+    {
+        match self {
+            RuleMatches::Collected {
+                rule,
+                events: matched,
+            } => {
+                let limit = match_limit(rule);
+                for event in events {
+                    if matched.len() >= limit {
+                        break;
+                    }
+                    if db_engine::matches_detection(event, &rule.detection) {
+                        matched.push(event.clone());
+                    }
+                }
+            }
+            RuleMatches::Sampled { rule, sample } => {
+                for event in events {
+                    if db_engine::matches_detection(event, &rule.detection) {
+                        sample.add(event);
+                    }
+                }
+            }
+            RuleMatches::Absence(tracker) => {
+                for event in events {
+                    tracker.observe(event);
+                }
+            }
+            RuleMatches::Rarity(tracker) => {
+                for event in events {
+                    tracker.observe(event);
+                }
+            }
+            RuleMatches::FirstSeen(tracker) => {
+                for event in events {
+                    tracker.observe(event);
+                }
+            }
+        }
+    }
+
+    fn finish(
+        self,
+        log_type: &LogType,
+        source_file: Option<&str>,
+    ) -> Result<Vec<AlertEvent>, SiemError> {
+        match self {
+            RuleMatches::Collected { rule, events } => {
+                build_alerts(rule, events, log_type, source_file.map(|s| s.to_string()))
+            }
+            RuleMatches::Sampled { sample, .. } if sample.is_empty() => Ok(vec![]),
+            RuleMatches::Sampled { rule, sample } => Ok(vec![
+                sample.into_alert(rule, source_file.map(|s| s.to_string()))
+            ]),
+            RuleMatches::Absence(tracker) => Ok(tracker.finish(source_file)),
+            RuleMatches::Rarity(tracker) => Ok(tracker.finish(source_file)),
+            RuleMatches::FirstSeen(tracker) => tracker.finish(source_file),
+        }
+    }
+}
+
+/// Stream a log file through rules without loading it whole.
+///
+/// The file is read in chunks; each chunk is evaluated by all rules on a pool
+/// of `workers` threads (0 = one per CPU core) and rules keep only what they
+/// need from it (matches, counts or an evidence sample). When the file has an
+/// up-to-date copy in the ingestion cache (`source.cached`), events are read
+/// from its table instead of the file. For cached and CloudTrail files, rules
+/// whose condition compiles to SQL are filtered inside DuckDB first and only
+/// see the candidate records. With a time filter, rules only see the events
+/// within its range. `on_chunk` is called with the number of events read as
+/// the scan advances. A rule that fails doesn't stop the scan; its error is
+/// reported in the rule results.
+pub fn scan_file<F>(
+    source: ScanSource,
+    rules: &[&RuleYaml],
+    source_file: Option<&str>,
+    workers: usize,
+    mut on_chunk: F,
+) -> Result<FileScan, SiemError>
+where
+    F: FnMut(usize),
+{
+    let ScanSource {
+        log_path,
+        log_type,
+        cached,
+        time_filter,
+    } = source;
+    let mut rule_results: Vec<Option<RuleRunStats>> = vec![None; rules.len()];
+    let new_states = |rule_results: &mut [Option<RuleRunStats>]| {
+        rules
+            .iter()
+            .enumerate()
+            .filter_map(|(index, rule)| match RuleMatches::new(rule, log_type) {
+                Ok(matches) => Some(RuleRun {
+                    index,
+                    matches,
+                    elapsed: Duration::ZERO,
+                }),
+                Err(e) => {
+                    eprintln!("Warning: Rule '{}' failed: {}", rule.title, e);
+                    rule_results[index] = Some(RuleRunStats::from_rule(rule, Some(e.to_string())));
+                    None
+                }
+            })
+            .collect::<Vec<_>>()
+    };
+    let mut states = new_states(&mut rule_results);
+
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(workers)
+        .build()
+        .map_err(|e| {
+            eprintln!(
+                "Warning: Cannot start scan workers, scanning serially: {}",
+                e
+            )
+        })
+        .ok();
+
+    let mut filtered_in_sql = vec![false; states.len()];
+    if cached.is_some() || *log_type == LogType::CloudTrail {
+        match prefilter_sql(log_path, cached, time_filter, &mut states, pool.as_ref()) {
+            Ok(filtered) => filtered_in_sql = filtered,
+            Err(e) => {
+                eprintln!("Warning: SQL prefilter failed, evaluating in Rust: {}", e);
+                states = new_states(&mut rule_results);
+            }
+        }
+    }
+
+    let events_read = if filtered_in_sql.iter().any(|filtered| *filtered)
+        && filtered_in_sql.iter().all(|filtered| *filtered)
+    {
+        // Every rule ran in DuckDB, the file only needs counting
+        let count = match cached {
+            Some(cached) => cached.source().event_count,
+            None => db_engine::create_connection()
+                .and_then(|conn| db_engine::count_cloudtrail_records(&conn, log_path))?,
+        };
+        on_chunk(count);
+        count
+    } else {
+        let evaluate_chunk = |mut chunk: Vec<serde_json::Value>| {
+            let read = chunk.len();
+            if let Some(time_filter) = time_filter {
+                time_filter.retain(&mut chunk);
+            }
+            let observe = |(state, filtered): (&mut RuleRun, &bool)| {
+                if !*filtered {
+                    state.observe(&chunk);
+                }
+            };
+            match &pool {
+                Some(pool) => pool.install(|| {
+                    states
+                        .par_iter_mut()
+                        .zip(filtered_in_sql.par_iter())
+                        .for_each(observe)
+                }),
+                None => states
+                    .iter_mut()
+                    .zip(filtered_in_sql.iter())
+                    .for_each(observe),
+            }
+            on_chunk(read);
+        };
+        match cached {
+            Some(cached) => cached.stream_events(db_engine::STREAM_CHUNK_SIZE, evaluate_chunk)?,
+            None => db_engine::stream_events(
+                log_path,
+                log_type,
+                db_engine::STREAM_CHUNK_SIZE,
+                evaluate_chunk,
+            )?,
+        }
+    };
+
+    let mut alerts = Vec::new();
+    for state in states {
+        let index = state.index;
+        let (rule_alerts, stats) = state.finish(log_type, source_file);
+        alerts.extend(rule_alerts);
+        rule_results[index] = Some(stats);
+    }
+
+    Ok(FileScan {
+        alerts: cap_alerts(rules.iter().copied(), alerts),
+        events_read,
+        rule_results: rule_results.into_iter().flatten().collect(),
+    })
+}
+
+/// Filter a cached source or a CloudTrail file inside DuckDB for the rules
+/// whose condition compiles to SQL, feeding them the candidate records
+/// (`observe` confirms each one with the Rust evaluator), within the time
+/// filter's range. Returns which states were fed.
+fn prefilter_sql(
+    log_path: &str,
+    cached: Option<&ingest_cache::CachedSource>,
+    time_filter: Option<&TimeFilter>,
+    states: &mut [RuleRun],
+    pool: Option<&rayon::ThreadPool>,
+) -> Result<Vec<bool>, SiemError> {
+    let mut predicates = Vec::new();
+    let predicate_index: Vec<Option<usize>> = states
+        .iter()
+        .map(|state| {
+            sql_compiler::compile_detection(&state.rule().detection).map(|predicate| {
+                predicates.push(predicate);
+                predicates.len() - 1
+            })
+        })
+        .collect();
+    if predicates.is_empty() {
+        return Ok(vec![false; states.len()]);
+    }
+
+    let feed_chunk = |mut chunk: Vec<(serde_json::Value, Vec<bool>)>| {
+        if let Some(time_filter) = time_filter {
+            chunk.retain(|(event, _)| time_filter.contains(event));
+        }
+        let observe = |(state, index): (&mut RuleRun, &Option<usize>)| {
+            if let Some(index) = *index {
+                state.observe(
+                    chunk
+                        .iter()
+                        .filter(|(_, flags)| flags[index])
+                        .map(|(event, _)| event),
+                );
+            }
+        };
+        match pool {
+            Some(pool) => pool.install(|| {
+                states
+                    .par_iter_mut()
+                    .zip(predicate_index.par_iter())
+                    .for_each(observe)
+            }),
+            None => states
+                .iter_mut()
+                .zip(predicate_index.iter())
+                .for_each(observe),
+        }
+    };
+    match cached {
+        Some(cached) => {
+            cached.query_events(&predicates, db_engine::STREAM_CHUNK_SIZE, feed_chunk)?
+        }
+        None => {
+            let conn = db_engine::create_connection()?;
+            db_engine::query_cloudtrail_records(
+                &conn,
+                log_path,
+                &predicates,
+                db_engine::STREAM_CHUNK_SIZE,
+                feed_chunk,
+            )?
+        }
+    }
+
+    Ok(predicate_index.iter().map(Option::is_some).collect())
+}
+
+/// Identity of a file on disk, so the same file listed under two paths
+/// (relative, through a symlink, ...) is recognized.
+fn file_identity(path: &str) -> std::path::PathBuf {
+    std::fs::canonicalize(path).unwrap_or_else(|_| std::path::PathBuf::from(path))
+}
+
+/// Scan a set of files of mixed log types with the given rules.
+///
+/// An event is identified by its file and its position in it, so identical
+/// events of different files (or repeated in one file) are all reported; a
+/// file listed more than once is scanned once.
+pub fn scan_log_set(
+    targets: Vec<ScanTarget>,
+    rules: &[RuleYaml],
+    workers: usize,
+) -> LogSetScanResponse {
+    let start = Instant::now();
+
+    let mut alerts: Vec<AlertEvent> = Vec::new();
+    let mut files_scanned: Vec<ScannedFile> = Vec::new();
+    let mut failed_files: Vec<FailedFileScan> = Vec::new();
+    let mut seen_files: HashSet<std::path::PathBuf> = HashSet::new();
+    let mut duplicate_files_skipped = 0;
+
+    for target in targets {
+        if !seen_files.insert(file_identity(&target.file_path)) {
+            duplicate_files_skipped += 1;
+            continue;
+        }
+
+        // Route only the rules targeting this log type (meta-rules run after all files)
+        let applicable: Vec<&RuleYaml> = rules
+            .iter()
+            .filter(|rule| !meta_rules::is_meta_rule(rule))
+            .filter(|rule| rule_applies_to(rule, &target.log_type))
+            .collect();
+
+        // Stream the file once through all applicable rules
+        let source = ScanSource {
+            log_path: &target.file_path,
+            log_type: &target.log_type,
+            cached: target.cached.as_ref(),
+            time_filter: None,
+        };
+        let scan = match scan_file(
+            source,
+            &applicable,
+            Some(&target.file_name),
+            workers,
+            |_| {},
+        ) {
+            Ok(result) => result,
+            Err(e) => {
+                eprintln!("Failed to load file '{}': {}", target.file_name, e);
+                failed_files.push(FailedFileScan {
+                    file_name: target.file_name,
+                    file_path: target.file_path,
+                    error: e.to_string(),
+                });
+                continue;
+            }
+        };
+
+        alerts.extend(scan.alerts);
+
+        files_scanned.push(ScannedFile {
+            file_name: target.file_name,
+            file_path: target.file_path,
+            log_type: target.log_type,
+            events_loaded: scan.events_read,
+            rules_applied: applicable.len(),
+            rule_results: scan.rule_results,
+        });
+    }
+
+    // Files are capped one by one; cap the rules again over the whole set
+    let mut alerts = cap_alerts(rules, alerts);
+
+    // Second pass: correlate the merged alerts across rules and files
+    let meta_alerts = meta_rules::evaluate_meta_rules(rules, &alerts);
+    alerts.extend(meta_alerts);
+
+    // Sort alerts by severity (critical first)
+    alerts.sort_by(|a, b| {
+        crate::severity_order(&b.severity).cmp(&crate::severity_order(&a.severity))
+    });
+
+    LogSetScanResponse {
+        alerts,
+        files_scanned,
+        failed_files,
+        rules_evaluated: rules.len(),
+        duplicate_files_skipped,
+        suppressed_alerts: 0,
+        scan_time_ms: start.elapsed().as_millis() as u64,
+        routing: RoutingSummary::default(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Evaluate rules against already-loaded events, as one streamed chunk.
+    fn evaluate_rules(
+        events: &[serde_json::Value],
+        rules: &[&RuleYaml],
+        log_type: &LogType,
+        source_file: Option<&str>,
+    ) -> Vec<AlertEvent> {
+        let mut alerts = Vec::new();
+        for rule in rules {
+            let mut matches = RuleMatches::new(rule, log_type).unwrap();
+            matches.observe(events);
+            alerts.extend(matches.finish(log_type, source_file).unwrap());
+        }
+        alerts
+    }
+
+    fn rule(id: &str, condition: &str, log_type: Option<&str>) -> RuleYaml {
+        let logsource = log_type
+            .map(|lt| format!("logsource:\n  log_type: {}\n", lt))
+            .unwrap_or_default();
+        let yaml = format!(
+            "id: {}\ntitle: {}\ndescription: ''\nauthor: ''\nstatus: active\ndate: ''\n{}detection:\n  severity: high\n  condition: \"{}\"\n",
+            id, id, logsource, condition
+        );
+        serde_yaml::from_str(&yaml).unwrap()
+    }
+
+    #[test]
+    fn test_file_identity_resolves_paths() {
+        let dir = std::env::temp_dir().join("offline_siem_test_file_identity");
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("trail.json");
+        std::fs::write(&path, "{}").unwrap();
+
+        let direct = path.to_string_lossy().to_string();
+        let indirect = dir
+            .join("..")
+            .join("offline_siem_test_file_identity")
+            .join("trail.json")
+            .to_string_lossy()
+            .to_string();
+        assert_eq!(file_identity(&direct), file_identity(&indirect));
+        assert_ne!(
+            file_identity(&direct),
+            file_identity(&dir.join("other.json").to_string_lossy())
+        );
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_rule_routing_by_log_type() {
+        let any = rule("any", "a = '1'", None);
+        let trail = rule("trail", "a = '1'", Some("cloudtrail"));
+
+        assert!(rule_applies_to(&any, &LogType::FlatJson));
+        assert!(rule_applies_to(&trail, &LogType::CloudTrail));
+        assert!(!rule_applies_to(&trail, &LogType::FlatJson));
+
+        let mut windows = rule("windows", "a = '1'", None);
+        windows.logsource = serde_yaml::from_str("product: windows\nservice: security").ok();
+        assert!(!rule_applies_to(&windows, &LogType::CloudTrail));
+        assert!(rule_applies_to(&windows, &LogType::FlatJson));
+
+        let mut aws = rule("aws", "a = '1'", None);
+        aws.logsource = serde_yaml::from_str("product: AWS").ok();
+        assert!(rule_applies_to(&aws, &LogType::CloudTrail));
+    }
+
+    #[test]
+    fn test_evaluate_rules_one_alert_per_rule() {
+        let events = vec![
+            serde_json::json!({ "eventName": "AssumeRole" }),
+            serde_json::json!({ "eventName": "AssumeRole" }),
+            serde_json::json!({ "eventName": "GetObject" }),
+        ];
+        let assume = rule("assume", "eventName = 'AssumeRole'", None);
+        let delete = rule("delete", "eventName = 'DeleteTrail'", None);
+
+        let alerts = evaluate_rules(
+            &events,
+            &[&assume, &delete],
+            &LogType::FlatJson,
+            Some("a.json"),
+        );
+        assert_eq!(alerts.len(), 1);
+        assert_eq!(alerts[0].rule_id, "assume");
+        assert_eq!(alerts[0].match_count, 2);
+        assert_eq!(alerts[0].source_file.as_deref(), Some("a.json"));
+    }
+
+    #[test]
+    fn test_alert_grouping_modes() {
+        let events: Vec<serde_json::Value> = (0..1500)
+            .map(|i| serde_json::json!({ "eventName": "AssumeRole", "n": i }))
+            .chain(std::iter::once(
+                serde_json::json!({ "eventName": "AssumeRole", "n": 0 }),
+            ))
+            .collect();
+        let mut grouped = rule("grouped", "eventName = 'AssumeRole'", None);
+        let mut per_event = rule("per_event", "eventName = 'AssumeRole'", None);
+        per_event.detection.grouping = Some(AlertGrouping::PerEvent);
+        apply_default_grouping(std::slice::from_mut(&mut grouped), AlertGrouping::PerRule);
+        apply_default_grouping(std::slice::from_mut(&mut per_event), AlertGrouping::PerRule);
+
+        let alerts = evaluate_rules(&events, &[&grouped], &LogType::FlatJson, None);
+        assert_eq!(alerts.len(), 1);
+        // The repeated event counts as a match but isn't kept twice
+        assert_eq!(alerts[0].match_count, 1501);
+        assert_eq!(alerts[0].evidence.len(), MAX_EVIDENCE_PER_ALERT);
+
+        let retries = vec![serde_json::json!({ "eventName": "AssumeRole" }); 50];
+        let alerts = evaluate_rules(&retries, &[&grouped], &LogType::FlatJson, None);
+        assert_eq!(alerts[0].match_count, 50);
+        assert_eq!(alerts[0].evidence.len(), 1);
+
+        let alerts = evaluate_rules(&events, &[&per_event], &LogType::FlatJson, None);
+        assert_eq!(alerts.len(), MAX_EVIDENCE_PER_ALERT);
+        assert!(alerts.iter().all(|a| a.match_count == 1));
+    }
+
+    #[test]
+    fn test_cap_alerts_merges_rules_over_their_limit() {
+        let events: Vec<serde_json::Value> = (0..5)
+            .map(|i| serde_json::json!({ "eventName": "AssumeRole", "n": i }))
+            .collect();
+        let mut noisy = rule("noisy", "eventName = 'AssumeRole'", None);
+        let mut quiet = rule("quiet", "eventName = 'AssumeRole'", None);
+        noisy.detection.grouping = Some(AlertGrouping::PerEvent);
+        quiet.detection.grouping = Some(AlertGrouping::PerEvent);
+        quiet.detection.max_alerts_per_scan = Some(0);
+        apply_default_alert_cap(std::slice::from_mut(&mut noisy), 3);
+        apply_default_alert_cap(std::slice::from_mut(&mut quiet), 3);
+
+        let alerts = evaluate_rules(&events, &[&noisy, &quiet], &LogType::FlatJson, None);
+        let alerts = cap_alerts([&noisy, &quiet], alerts);
+        assert_eq!(alerts.len(), 6);
+        assert_eq!(alerts[0].rule_id, "noisy");
+        assert_eq!(alerts[0].match_count, 5);
+        assert_eq!(alerts[0].evidence.len(), 5);
+        let truncation = alerts[0].truncation.as_ref().unwrap();
+        assert_eq!((truncation.alert_count, truncation.limit), (5, 3));
+        assert!(alerts[1..].iter().all(|a| a.rule_id == "quiet"));
+
+        // Capping again (e.g. over several files) keeps the original count
+        let alerts = cap_alerts([&noisy, &quiet], alerts);
+        assert_eq!(alerts[0].truncation.as_ref().unwrap().alert_count, 5);
+    }
+
+    #[test]
+    fn test_project_evidence() {
+        let event = serde_json::json!({
+            "eventName": "AssumeRole",
+            "userIdentity": { "arn": "arn:aws:iam::1:user/alice", "accountId": "1" },
+            "resources": [{ "ARN": "r1" }],
+            "requestParameters": { "roleArn": "admin" }
+        });
+        let mut projected = rule("projected", "eventName = 'AssumeRole'", None);
+        projected.output = serde_yaml::from_str(
+            "evidence_fields: [userIdentity.arn, resources.ARN, missing.field, eventName.x]",
+        )
+        .ok();
+        let raw = rule("raw", "eventName = 'AssumeRole'", None);
+
+        let mut alerts = evaluate_rules(
+            std::slice::from_ref(&event),
+            &[&projected, &raw],
+            &LogType::FlatJson,
+            None,
+        );
+        project_evidence(&[projected, raw], &mut alerts);
+        assert_eq!(
+            alerts[0].evidence[0],
+            serde_json::json!({
+                "userIdentity": { "arn": "arn:aws:iam::1:user/alice" },
+                "resources": [{ "ARN": "r1" }]
+            })
+        );
+        assert_eq!(alerts[1].evidence[0], event);
+    }
+
+    #[test]
+    fn test_sequence_rule_produces_correlated_alert() {
+        let yaml = r#"
+id: seq
+title: Access key after login
+description: ''
+author: ''
+status: active
+date: ''
+detection:
+  severity: high
+  sequence:
+    window: 30m
+    by: userIdentity.arn
+    steps:
+      - name: login
+        condition: eventName = 'ConsoleLogin'
+      - name: key
+        condition: eventName = 'CreateAccessKey'
+"#;
+        let seq: RuleYaml = serde_yaml::from_str(yaml).unwrap();
+        let events = vec![
+            serde_json::json!({ "eventName": "ConsoleLogin", "eventTime": "2024-01-01T10:00:00Z", "userIdentity": { "arn": "a" } }),
+            serde_json::json!({ "eventName": "GetObject", "eventTime": "2024-01-01T10:01:00Z", "userIdentity": { "arn": "a" } }),
+            serde_json::json!({ "eventName": "CreateAccessKey", "eventTime": "2024-01-01T10:02:00Z", "userIdentity": { "arn": "a" } }),
+        ];
+
+        let alerts = evaluate_rules(&events, &[&seq], &LogType::CloudTrail, None);
+        assert_eq!(alerts.len(), 1);
+        assert_eq!(alerts[0].match_count, 2);
+        let correlation = alerts[0].correlation.as_ref().unwrap();
+        assert_eq!(correlation.steps, vec!["login", "key"]);
+        assert_eq!(correlation.key["userIdentity.arn"], "a");
+    }
+}
@@ -0,0 +1,176 @@
+//! Script rules (`detection.type: script`).
+//!
+//! For detections the condition language can't express (string math,
+//! heuristics over several fields), a rule's condition can be a small Rhai
+//! script. The event is available as the `event` object map, so fields are
+//! read as `event.userIdentity.type`; missing fields are `()`. The script
+//! returns `true` for matching events:
+//!
+//! ```text
+//! let agent = event.userAgent;
+//! agent != () && agent.len() > 200 && !agent.contains("aws-sdk")
+//! ```
+//!
+//! Scripts run sandboxed: no file or network access, and bounded operations,
+//! depth and sizes, so a runaway loop stops instead of hanging a scan. A
+//! script that fails or doesn't return a boolean doesn't match.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, OnceLock};
+
+use crate::models::{SiemError, ValidationResult};
+
+/// Operations a script may run per event.
+const MAX_OPERATIONS: u64 = 100_000;
+
+/// Compiled scripts kept in memory, at most.
+const MAX_CACHED_SCRIPTS: usize = 256;
+
+fn engine() -> &'static rhai::Engine {
+    static ENGINE: OnceLock<rhai::Engine> = OnceLock::new();
+    ENGINE.get_or_init(|| {
+        let mut engine = rhai::Engine::new();
+        engine
+            .set_max_operations(MAX_OPERATIONS)
+            .set_max_call_levels(32)
+            .set_max_expr_depths(64, 32)
+            .set_max_string_size(1024 * 1024)
+            .set_max_array_size(10_000)
+            .set_max_map_size(10_000);
+        // Scripts have no console to print to
+        engine.on_print(|_| {});
+        engine.on_debug(|_, _, _| {});
+        engine
+    })
+}
+
+/// Compiled scripts (or their syntax error) by source.
+type ScriptCache = HashMap<String, Arc<Result<rhai::AST, String>>>;
+
+fn script_cache() -> &'static Mutex<ScriptCache> {
+    static CACHE: OnceLock<Mutex<ScriptCache>> = OnceLock::new();
+    CACHE.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Compile a script once; every event of a scan reuses it.
+fn compiled(script: &str) -> Arc<Result<rhai::AST, String>> {
+    let mut cache = match script_cache().lock() {
+        Ok(cache) => cache,
+        Err(poisoned) => poisoned.into_inner(),
+    };
+    if let Some(ast) = cache.get(script) {
+        return ast.clone();
+    }
+    if cache.len() >= MAX_CACHED_SCRIPTS {
+        cache.clear();
+    }
+    let ast = Arc::new(engine().compile(script).map_err(|e| e.to_string()));
+    cache.insert(script.to_string(), ast.clone());
+    ast
+}
+
+/// Run a script against an event.
+pub fn eval_script(event: &serde_json::Value, script: &str) -> Result<bool, SiemError> {
+    let ast = compiled(script);
+    let ast = ast
+        .as_ref()
+        .as_ref()
+        .map_err(|e| SiemError::Rule(format!("Invalid script: {}", e)))?;
+
+    let event = rhai::serde::to_dynamic(event)
+        .map_err(|e| SiemError::Serialization(format!("Cannot pass event to script: {}", e)))?;
+    let mut scope = rhai::Scope::new();
+    scope.push("event", event);
+
+    engine()
+        .eval_ast_with_scope::<bool>(&mut scope, ast)
+        .map_err(|e| SiemError::Rule(format!("Script failed: {}", e)))
+}
+
+/// Whether a script matches an event; failing scripts don't match.
+pub fn matches_script(event: &serde_json::Value, script: &str) -> bool {
+    eval_script(event, script).unwrap_or(false)
+}
+
+/// Check a script's syntax.
+pub fn validate_script(script: &str) -> ValidationResult {
+    if script.trim().is_empty() {
+        return ValidationResult {
+            valid: false,
+            error_message: Some("Script cannot be empty".to_string()),
+            error_position: Some(0),
+            suggestions: vec!["Example: event.eventName == \"AssumeRole\"".to_string()],
+        };
+    }
+
+    match engine().compile(script) {
+        Ok(_) => ValidationResult {
+            valid: true,
+            error_message: None,
+            error_position: None,
+            suggestions: vec![],
+        },
+        Err(e) => {
+            let position = e.1;
+            let error_position = position.line().map(|line| {
+                let column = position.position().unwrap_or(1);
+                script
+                    .split_inclusive('\n')
+                    .take(line - 1)
+                    .map(str::len)
+                    .sum::<usize>()
+                    + column.saturating_sub(1)
+            });
+            ValidationResult {
+                valid: false,
+                error_message: Some(e.0.to_string()),
+                error_position,
+                suggestions: vec![
+                    "The event is available as `event`, e.g. event.userIdentity.type".to_string(),
+                    "The script must return true or false".to_string(),
+                ],
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_script_matches() {
+        let event = serde_json::json!({
+            "eventName": "GetObject",
+            "userAgent": "curl/8.0",
+            "bytes": 4096,
+            "userIdentity": { "type": "IAMUser" }
+        });
+
+        assert!(matches_script(
+            &event,
+            r#"event.userIdentity.type == "IAMUser" && event.bytes > 1024"#
+        ));
+        assert!(matches_script(
+            &event,
+            r#"let agent = event.userAgent; agent.starts_with("curl") && agent.len() < 20"#
+        ));
+        assert!(!matches_script(&event, "event.missing == \"x\""));
+
+        // Errors, non-boolean results and runaway loops don't match
+        assert!(!matches_script(&event, "event.eventName +"));
+        assert!(!matches_script(&event, "event.bytes"));
+        assert!(!matches_script(&event, "loop {}"));
+        assert!(eval_script(&event, "loop {}").is_err());
+    }
+
+    #[test]
+    fn test_validate_script() {
+        assert!(validate_script("event.eventName == \"x\"").valid);
+        assert!(!validate_script("  ").valid);
+
+        let result = validate_script("let x = 1;\nx == (");
+        assert!(!result.valid);
+        assert!(result.error_message.is_some());
+    }
+}
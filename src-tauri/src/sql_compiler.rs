@@ -0,0 +1,450 @@
+//! Translation of rule conditions to DuckDB SQL.
+//!
+//! Large CloudTrail files scan faster when conditions run vectorised inside
+//! DuckDB instead of event by event in Rust. A condition compiles to a
+//! predicate over a JSON column named `event` holding one record.
+//!
+//! The predicate is a prefilter: it accepts every event the Rust evaluator
+//! (`db_engine::matches_condition_with_case`) accepts, and possibly more, so
+//! candidates are confirmed in Rust. Parts SQL can't express the same way
+//! (REGEX, MATCH, term files, field functions, fields reached through arrays,
+//! floating-point values) compile to TRUE and are left to the Rust check.
+
+use crate::db_engine::{self, sql_string, CaseMode};
+use crate::models::{DetectionLogic, DetectionType};
+
+/// Predicate accepting every event.
+const ANY: &str = "TRUE";
+/// Predicate accepting no event.
+const NONE: &str = "FALSE";
+
+/// JSON types whose text form is the same in DuckDB and in the Rust evaluator.
+pub const EXACT_SCALAR_TYPES: &str = "('VARCHAR', 'BIGINT', 'UBIGINT', 'BOOLEAN')";
+
+/// Compile the event filter of a rule's detection logic.
+///
+/// Returns None when SQL can't narrow the events down: meta-rules and absence
+/// rules (which need every event), script rules, or conditions that compile
+/// to TRUE.
+/// Filters are left to the Rust check: the negation of a prefilter could
+/// drop events the Rust evaluator keeps.
+pub fn compile_detection(detection: &DetectionLogic) -> Option<String> {
+    if detection.meta.is_some()
+        || detection.absence.is_some()
+        || detection.detection_type == DetectionType::Script
+    {
+        return None;
+    }
+
+    let case_mode = CaseMode::from_flag(detection.case_sensitive);
+    let mut parts = Vec::new();
+    if detection.sequence.is_none() || !detection.condition.trim().is_empty() {
+        parts.push(compile_condition(&detection.condition, case_mode));
+    }
+    if let Some(sequence) = &detection.sequence {
+        parts.push(or_all(
+            sequence
+                .steps
+                .iter()
+                .map(|step| compile_condition(&step.condition, case_mode))
+                .collect(),
+        ));
+    }
+
+    let predicate = and_all(parts);
+    (predicate != ANY).then_some(predicate)
+}
+
+/// Compile a condition, following the structure the Rust evaluator gives it.
+pub fn compile_condition(condition: &str, case_mode: CaseMode) -> String {
+    Compiler {
+        case_mode,
+        groups: Vec::new(),
+    }
+    .condition(condition)
+}
+
+/// Compiles one condition; `groups` holds the SQL of parenthesized groups
+/// replaced by placeholders.
+struct Compiler {
+    case_mode: CaseMode,
+    groups: Vec<String>,
+}
+
+impl Compiler {
+    fn condition(&mut self, condition: &str) -> String {
+        let condition = condition.trim();
+
+        // Same dispatch as matches_condition_with_case
+        let upper = condition.to_uppercase();
+        if upper.contains(" IN (") || upper.contains(" NOT IN (") {
+            return self.single(condition);
+        }
+
+        if db_engine::find_grouping_parens(condition).is_some() {
+            let mut rest = condition.to_string();
+            while let Some((start, end)) = db_engine::find_grouping_parens(&rest) {
+                let inner = compile_condition(&rest[start + 1..end], self.case_mode);
+                let placeholder = format!("__group{}__", self.groups.len());
+                self.groups.push(inner);
+                rest = format!("{}{}{}", &rest[..start], placeholder, &rest[end + 1..]);
+            }
+            return self.or_expression(&rest);
+        }
+
+        self.or_expression(condition)
+    }
+
+    fn or_expression(&mut self, condition: &str) -> String {
+        if condition.to_uppercase().contains(" OR ") {
+            let parts = db_engine::split_by_keyword_safe(condition, "OR");
+            return or_all(parts.iter().map(|part| self.and_expression(part)).collect());
+        }
+        self.and_expression(condition)
+    }
+
+    fn and_expression(&mut self, condition: &str) -> String {
+        if condition.to_uppercase().contains(" AND ") {
+            let parts = db_engine::split_by_keyword_safe(condition, "AND");
+            return and_all(parts.iter().map(|part| self.single(part)).collect());
+        }
+        self.single(condition)
+    }
+
+    /// Compile a single comparison, detecting the operator in the same order
+    /// as `matches_single_condition`.
+    fn single(&mut self, condition: &str) -> String {
+        let condition = condition.trim();
+        if let Some(index) = condition
+            .strip_prefix("__group")
+            .and_then(|rest| rest.strip_suffix("__"))
+            .and_then(|index| index.parse::<usize>().ok())
+        {
+            return self
+                .groups
+                .get(index)
+                .cloned()
+                .unwrap_or_else(|| ANY.to_string());
+        }
+
+        let upper = condition.to_uppercase();
+        let term_file_operators = [" IN_FILE ", " CONTAINS_FILE ", " IN_LIST "];
+        if term_file_operators.iter().any(|op| upper.contains(op))
+            || db_engine::find_regex_operator(condition).is_some()
+        {
+            return ANY.to_string();
+        }
+
+        for (keyword, negated) in [(" NOT IN ", true), (" IN ", false)] {
+            let Some(pos) = upper.find(keyword) else {
+                continue;
+            };
+            let (Some(field), Some(list)) =
+                (condition.get(..pos), condition.get(pos + keyword.len()..))
+            else {
+                return ANY.to_string();
+            };
+            if let Some(values) = db_engine::parse_in_list(list) {
+                let fold = self.case_mode.folds(false);
+                let list = values
+                    .iter()
+                    .map(|value| case(&sql_string(value), fold))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                let not = if negated { "NOT " } else { "" };
+                return any_value(field, |v| format!("{} {}IN ({})", case(v, fold), not, list));
+            }
+        }
+
+        for (keyword, negated) in [(" NOT CONTAINS ", true), (" CONTAINS ", false)] {
+            if let Some((field, value)) = split_operator(condition, &upper, keyword) {
+                let fold = self.case_mode.folds(true);
+                return any_value(field, |v| {
+                    negate(
+                        format!(
+                            "contains({}, {})",
+                            case(v, fold),
+                            case(&sql_string(value), fold)
+                        ),
+                        negated,
+                    )
+                });
+            }
+        }
+
+        if let Some(pos) = db_engine::find_unquoted(condition, "=~") {
+            let value = unquote(&condition[pos + 2..]);
+            return any_value(&condition[..pos], |v| {
+                format!("lower({}) = lower({})", v, sql_string(value))
+            });
+        }
+
+        for operator in ["!=", "<>"] {
+            if let Some(pos) = condition.find(operator) {
+                let value = unquote(&condition[pos + 2..]);
+                let fold = self.case_mode.folds(false);
+                return any_value(&condition[..pos], |v| {
+                    format!("{} <> {}", case(v, fold), case(&sql_string(value), fold))
+                });
+            }
+        }
+
+        // An equality on a missing field falls through to the other operators
+        let equality = condition.find('=').map(|pos| {
+            let value = unquote(&condition[pos + 1..]);
+            let fold = self.case_mode.folds(false);
+            any_value(&condition[..pos], |v| {
+                format!("{} = {}", case(v, fold), case(&sql_string(value), fold))
+            })
+        });
+
+        let affix_operators = [
+            (" NOT STARTSWITH ", "starts_with", true),
+            (" STARTSWITH ", "starts_with", false),
+            (" NOT ENDSWITH ", "suffix", true),
+            (" ENDSWITH ", "suffix", false),
+        ];
+        let mut rest = NONE.to_string();
+        for (keyword, function, negated) in affix_operators {
+            if let Some((field, value)) = split_operator(condition, &upper, keyword) {
+                let fold = self.case_mode.folds(true);
+                rest = any_value(field, |v| {
+                    negate(
+                        format!(
+                            "{}({}, {})",
+                            function,
+                            case(v, fold),
+                            case(&sql_string(value), fold)
+                        ),
+                        negated,
+                    )
+                });
+                break;
+            }
+        }
+        if rest == NONE && upper.contains(" MATCH ") {
+            rest = ANY.to_string();
+        }
+
+        match equality {
+            Some(equality) => or_all(vec![equality, rest]),
+            None => rest,
+        }
+    }
+}
+
+/// Split "field KEYWORD 'value'" around a keyword found in the uppercased
+/// condition (positions are checked against the original text).
+fn split_operator<'a>(
+    condition: &'a str,
+    upper: &str,
+    keyword: &str,
+) -> Option<(&'a str, &'a str)> {
+    let pos = upper.find(keyword)?;
+    let field = condition.get(..pos)?;
+    let value = condition.get(pos + keyword.len()..)?;
+    Some((field, unquote(value)))
+}
+
+/// SQL form of a plain dotted field path over the `event` column.
+pub struct FieldPath {
+    /// JSON path literal, e.g. `'$.userIdentity.arn'`
+    pub path: String,
+    /// Predicate true when SQL can't read the field like the Rust evaluator:
+    /// it is reached through an array or holds a floating-point value
+    pub undecided: String,
+}
+
+impl FieldPath {
+    /// Parse a field; None if it isn't a plain dotted path.
+    pub fn parse(field: &str) -> Option<Self> {
+        let segments: Vec<&str> = field.trim().split('.').collect();
+        let plain = segments.iter().all(|segment| {
+            !segment.is_empty()
+                && segment
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '_')
+        });
+        if !plain {
+            return None;
+        }
+
+        let path = |depth: usize| format!("'$.{}'", segments[..depth].join("."));
+        let full = path(segments.len());
+
+        let undecided = std::iter::once("json_type(event) = 'ARRAY'".to_string())
+            .chain(
+                (1..=segments.len())
+                    .map(|depth| format!("json_type(event, {}) = 'ARRAY'", path(depth))),
+            )
+            .chain(std::iter::once(format!(
+                "json_type(event, {}) = 'DOUBLE'",
+                full
+            )))
+            .collect::<Vec<_>>()
+            .join(" OR ");
+
+        Some(FieldPath {
+            path: full,
+            undecided,
+        })
+    }
+}
+
+/// Predicate true if a value of `field` satisfies `predicate` (given the SQL of the value).
+///
+/// Fields reached through an array, floating-point values and fields that
+/// aren't plain dotted paths are undecided in SQL and compile to TRUE.
+fn any_value<F>(field: &str, predicate: F) -> String
+where
+    F: Fn(&str) -> String,
+{
+    let Some(field) = FieldPath::parse(field) else {
+        return ANY.to_string();
+    };
+
+    format!(
+        "(CASE WHEN {} THEN TRUE ELSE coalesce(json_type(event, {}) IN {} AND {}, FALSE) END)",
+        field.undecided,
+        field.path,
+        EXACT_SCALAR_TYPES,
+        predicate(&format!("json_extract_string(event, {})", field.path))
+    )
+}
+
+/// Remove surrounding quotes from a value, as the Rust evaluator does.
+fn unquote(value: &str) -> &str {
+    value.trim().trim_matches('\'').trim_matches('"')
+}
+
+/// Lowercase an SQL expression if case folding is enabled.
+fn case(expr: &str, fold: bool) -> String {
+    if fold {
+        format!("lower({})", expr)
+    } else {
+        expr.to_string()
+    }
+}
+
+fn negate(expr: String, negated: bool) -> String {
+    if negated {
+        format!("NOT {}", expr)
+    } else {
+        expr
+    }
+}
+
+/// AND predicates together, simplifying TRUE and FALSE.
+fn and_all(parts: Vec<String>) -> String {
+    if parts.iter().any(|part| part == NONE) {
+        return NONE.to_string();
+    }
+    let parts: Vec<String> = parts.into_iter().filter(|part| part != ANY).collect();
+    match parts.len() {
+        0 => ANY.to_string(),
+        1 => parts.into_iter().next().unwrap_or_default(),
+        _ => format!("({})", parts.join(" AND ")),
+    }
+}
+
+/// OR predicates together, simplifying TRUE and FALSE.
+fn or_all(parts: Vec<String>) -> String {
+    if parts.iter().any(|part| part == ANY) {
+        return ANY.to_string();
+    }
+    let parts: Vec<String> = parts.into_iter().filter(|part| part != NONE).collect();
+    match parts.len() {
+        0 => NONE.to_string(),
+        1 => parts.into_iter().next().unwrap_or_default(),
+        _ => format!("({})", parts.join(" OR ")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compile_equality_and_boolean_structure() {
+        let sql = compile_condition(
+            "eventName = 'ConsoleLogin' AND (userIdentity.type = 'Root' OR awsRegion != 'us-east-1')",
+            CaseMode::Default,
+        );
+        assert!(sql.contains("json_extract_string(event, '$.eventName') = 'ConsoleLogin'"));
+        assert!(sql.contains("json_type(event, '$.userIdentity') = 'ARRAY'"));
+        assert!(sql.contains("json_extract_string(event, '$.awsRegion') <> 'us-east-1'"));
+        assert!(sql.contains(" AND "));
+        assert!(sql.contains(" OR "));
+    }
+
+    #[test]
+    fn test_quoted_values_containing_case_insensitive_operator() {
+        let sql = compile_condition("url = 'a=~b'", CaseMode::Default);
+        assert!(sql.contains("json_extract_string(event, '$.url') = 'a=~b'"));
+        assert!(!sql.contains("lower("));
+        let sql = compile_condition("path != 'x=~y'", CaseMode::Default);
+        assert!(sql.contains("json_extract_string(event, '$.path') <> 'x=~y'"));
+        assert!(!sql.contains("lower("));
+    }
+
+    #[test]
+    fn test_compile_case_folding_and_lists() {
+        let sql = compile_condition("userName CONTAINS \"o'brien\"", CaseMode::Default);
+        assert!(sql.contains(
+            "contains(lower(json_extract_string(event, '$.userName')), lower('o''brien'))"
+        ));
+
+        let sql = compile_condition("eventName NOT IN ('A', 'B')", CaseMode::Insensitive);
+        assert!(sql.contains(
+            "lower(json_extract_string(event, '$.eventName')) NOT IN (lower('A'), lower('B'))"
+        ));
+    }
+
+    #[test]
+    fn test_condition_values_cannot_inject_sql() {
+        let sql = compile_condition("eventName = \"x'; DROP TABLE t; --\"", CaseMode::Default);
+        assert!(sql.contains("') = 'x''; DROP TABLE t; --'"));
+
+        let sql = compile_condition("userName IN (\"o'brien\", 'b')", CaseMode::Default);
+        assert!(sql.contains("IN ('o''brien', 'b')"));
+
+        // Fields are only compiled as plain dotted paths
+        assert_eq!(
+            compile_condition("a') = 'x' OR TRUE --.b = 'x'", CaseMode::Default),
+            ANY
+        );
+    }
+
+    #[test]
+    fn test_unsupported_operators_are_left_to_rust() {
+        assert_eq!(
+            compile_condition("userAgent REGEX '^aws-cli/'", CaseMode::Default),
+            ANY
+        );
+        assert_eq!(
+            compile_condition("normalize_path(Image) = 'c:/x'", CaseMode::Default),
+            ANY
+        );
+        // An unsupported OR branch makes the whole OR undecided...
+        assert_eq!(
+            compile_condition("a = '1' OR b MATCH 'x*'", CaseMode::Default),
+            ANY
+        );
+        // ...while an AND keeps the part SQL can check
+        let sql = compile_condition("a = '1' AND b MATCH 'x*'", CaseMode::Default);
+        assert!(sql.contains("'$.a') = '1'"));
+    }
+
+    #[test]
+    fn test_compile_detection_skips_rules_needing_every_event() {
+        let detection: DetectionLogic = serde_yaml::from_str(
+            "severity: low\ncondition: \"type = 'heartbeat'\"\nabsence:\n  min_count: 1\n",
+        )
+        .unwrap();
+        assert!(compile_detection(&detection).is_none());
+
+        let detection: DetectionLogic =
+            serde_yaml::from_str("severity: low\ncondition: \"eventName = 'X'\"\n").unwrap();
+        assert!(compile_detection(&detection).is_some());
+    }
+}
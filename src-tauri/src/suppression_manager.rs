@@ -0,0 +1,263 @@
+//! Suppression list for silencing known-benign alerts across scans.
+//!
+//! Entries are stored in `suppressions.json` in the application's data
+//! directory. An entry names a rule (or `*` for every rule), optional
+//! field/value wildcard patterns and an optional expiry date. An alert is
+//! suppressed when every one of its evidence events matches all patterns of
+//! an unexpired entry for its rule, so an alert that also carries unrelated
+//! events is still reported. Unlike rule filters, entries leave rule files
+//! untouched and lapse on their own.
+
+use chrono::{DateTime, NaiveDate, Utc};
+use std::fs;
+use std::path::PathBuf;
+
+use crate::app_data;
+use crate::db_engine;
+use crate::models::{AlertEvent, SiemError, SuppressionEntry, SuppressionPattern};
+
+/// Rule ID of entries applying to every rule.
+pub const ANY_RULE: &str = "*";
+
+/// Get the path to the suppressions file.
+fn get_suppressions_path(app_handle: &tauri::AppHandle) -> Result<PathBuf, SiemError> {
+    app_data::app_data_file(app_handle, "suppressions.json")
+}
+
+/// Load all suppression entries from disk.
+fn load_suppressions(app_handle: &tauri::AppHandle) -> Result<Vec<SuppressionEntry>, SiemError> {
+    let path = get_suppressions_path(app_handle)?;
+
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let content = fs::read_to_string(&path)
+        .map_err(|e| SiemError::FileIO(format!("Cannot read suppressions: {}", e)))?;
+
+    serde_json::from_str(&content)
+        .map_err(|e| SiemError::Serialization(format!("Cannot parse suppressions: {}", e)))
+}
+
+/// Save all suppression entries to disk.
+fn save_suppressions(
+    app_handle: &tauri::AppHandle,
+    entries: &[SuppressionEntry],
+) -> Result<(), SiemError> {
+    let path = get_suppressions_path(app_handle)?;
+
+    let content = serde_json::to_string_pretty(entries)
+        .map_err(|e| SiemError::Serialization(format!("Cannot serialize suppressions: {}", e)))?;
+
+    fs::write(&path, content)
+        .map_err(|e| SiemError::FileIO(format!("Cannot write suppressions: {}", e)))?;
+
+    Ok(())
+}
+
+/// Parse an expiry: a date (the entry lasts through that day, UTC) or an
+/// RFC 3339 timestamp.
+fn parse_expiry(expires_at: &str) -> Option<DateTime<Utc>> {
+    let expires_at = expires_at.trim();
+    if let Ok(date) = NaiveDate::parse_from_str(expires_at, "%Y-%m-%d") {
+        return date
+            .succ_opt()
+            .and_then(|next| next.and_hms_opt(0, 0, 0))
+            .map(|end| end.and_utc());
+    }
+    DateTime::parse_from_rfc3339(expires_at)
+        .ok()
+        .map(|t| t.with_timezone(&Utc))
+}
+
+/// Whether an entry has expired at `now`.
+fn is_expired(entry: &SuppressionEntry, now: DateTime<Utc>) -> bool {
+    entry
+        .expires_at
+        .as_deref()
+        .and_then(parse_expiry)
+        .is_some_and(|expiry| expiry <= now)
+}
+
+/// Add a suppression entry.
+pub fn add_suppression(
+    app_handle: &tauri::AppHandle,
+    rule_id: String,
+    patterns: Vec<SuppressionPattern>,
+    expires_at: Option<String>,
+    reason: String,
+) -> Result<SuppressionEntry, SiemError> {
+    let rule_id = rule_id.trim().to_string();
+    if rule_id.is_empty() {
+        return Err(SiemError::Rule(format!(
+            "A suppression needs a rule ID (or '{}' for every rule)",
+            ANY_RULE
+        )));
+    }
+    if let Some(pattern) = patterns.iter().find(|p| p.field.trim().is_empty()) {
+        return Err(SiemError::Rule(format!(
+            "Suppression pattern '{}' has no field",
+            pattern.pattern
+        )));
+    }
+    let expires_at = expires_at.filter(|e| !e.trim().is_empty());
+    if let Some(expiry) = &expires_at {
+        if parse_expiry(expiry).is_none() {
+            return Err(SiemError::Rule(format!(
+                "Invalid expiry '{}': expected YYYY-MM-DD or an RFC 3339 timestamp",
+                expiry
+            )));
+        }
+    }
+
+    let entry = SuppressionEntry {
+        id: uuid::Uuid::new_v4().to_string(),
+        rule_id,
+        patterns,
+        expires_at,
+        reason,
+        created_at: Utc::now().to_rfc3339(),
+    };
+
+    let mut entries = load_suppressions(app_handle)?;
+    entries.push(entry.clone());
+    save_suppressions(app_handle, &entries)?;
+    Ok(entry)
+}
+
+/// Remove a suppression entry by ID.
+pub fn remove_suppression(app_handle: &tauri::AppHandle, entry_id: &str) -> Result<(), SiemError> {
+    let mut entries = load_suppressions(app_handle)?;
+    let before = entries.len();
+    entries.retain(|e| e.id != entry_id);
+
+    if entries.len() == before {
+        return Err(SiemError::Query(format!(
+            "Suppression not found: {}",
+            entry_id
+        )));
+    }
+
+    save_suppressions(app_handle, &entries)
+}
+
+/// List suppression entries, newest first; expired ones only if asked.
+pub fn list_suppressions(
+    app_handle: &tauri::AppHandle,
+    include_expired: bool,
+) -> Result<Vec<SuppressionEntry>, SiemError> {
+    let mut entries = load_suppressions(app_handle)?;
+    if !include_expired {
+        let now = Utc::now();
+        entries.retain(|e| !is_expired(e, now));
+    }
+    entries.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+    Ok(entries)
+}
+
+/// Whether an event matches all patterns (case-insensitive wildcards).
+fn event_matches(event: &serde_json::Value, patterns: &[SuppressionPattern]) -> bool {
+    patterns.iter().all(|p| {
+        db_engine::get_field_values(event, p.field.trim())
+            .iter()
+            .any(|value| db_engine::wildcard_match(value, &p.pattern, true))
+    })
+}
+
+/// Whether an entry suppresses an alert.
+fn suppresses(entry: &SuppressionEntry, alert: &AlertEvent) -> bool {
+    if entry.rule_id != ANY_RULE && entry.rule_id != alert.rule_id {
+        return false;
+    }
+    if alert.evidence.is_empty() {
+        // Alerts without evidence (absence) can only be silenced rule-wide
+        return entry.patterns.is_empty();
+    }
+    alert
+        .evidence
+        .iter()
+        .all(|event| event_matches(event, &entry.patterns))
+}
+
+/// Drop the alerts suppressed by an unexpired entry at `now`.
+/// Returns the number of alerts removed.
+pub fn apply_suppressions(
+    entries: &[SuppressionEntry],
+    alerts: &mut Vec<AlertEvent>,
+    now: DateTime<Utc>,
+) -> usize {
+    let active: Vec<&SuppressionEntry> = entries.iter().filter(|e| !is_expired(e, now)).collect();
+    if active.is_empty() {
+        return 0;
+    }
+
+    let before = alerts.len();
+    alerts.retain(|alert| !active.iter().any(|entry| suppresses(entry, alert)));
+    before - alerts.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn alert(rule_id: &str, users: &[&str]) -> AlertEvent {
+        serde_json::from_value(serde_json::json!({
+            "rule_id": rule_id,
+            "rule_title": rule_id,
+            "severity": "high",
+            "timestamp": "2025-12-16T11:05:00+00:00",
+            "match_count": users.len(),
+            "evidence": users
+                .iter()
+                .map(|u| serde_json::json!({ "userIdentity": { "userName": u } }))
+                .collect::<Vec<_>>()
+        }))
+        .unwrap()
+    }
+
+    fn entry(rule_id: &str, pattern: &str, expires_at: Option<&str>) -> SuppressionEntry {
+        SuppressionEntry {
+            id: "s1".to_string(),
+            rule_id: rule_id.to_string(),
+            patterns: vec![SuppressionPattern {
+                field: "userIdentity.userName".to_string(),
+                pattern: pattern.to_string(),
+            }],
+            expires_at: expires_at.map(str::to_string),
+            reason: String::new(),
+            created_at: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_apply_suppressions() {
+        let now = "2025-12-16T12:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        let mut alerts = vec![
+            alert("r1", &["svc-backup"]),
+            alert("r1", &["SVC-deploy", "svc-backup"]),
+            alert("r1", &["svc-backup", "alice"]),
+            alert("r2", &["svc-backup"]),
+        ];
+
+        // Expired entries suppress nothing; the expiry date itself still counts
+        let expired = entry("*", "svc-*", Some("2025-12-15"));
+        assert_eq!(apply_suppressions(&[expired], &mut alerts, now), 0);
+
+        let suppressed = apply_suppressions(
+            &[entry("r1", "svc-*", Some("2025-12-16"))],
+            &mut alerts,
+            now,
+        );
+        assert_eq!(suppressed, 2);
+        let remaining: Vec<(&str, usize)> = alerts
+            .iter()
+            .map(|a| (a.rule_id.as_str(), a.evidence.len()))
+            .collect();
+        assert_eq!(remaining, vec![("r1", 2), ("r2", 1)]);
+
+        assert_eq!(
+            apply_suppressions(&[entry(ANY_RULE, "svc-backup", None)], &mut alerts, now),
+            1
+        );
+    }
+}
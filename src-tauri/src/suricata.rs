@@ -0,0 +1,141 @@
+//! Suricata EVE JSON logs (`LogType::Suricata`).
+//!
+//! `eve.json` is NDJSON where every record has an `event_type` (`alert`,
+//! `dns`, `http`, `tls`, `flow`, ...) deciding which object holds its
+//! details: an alert's signature is under `alert.signature`, a DNS query
+//! under `dns.rrname`. This module knows those fields, for detection of EVE
+//! files and for field suggestions in the rule editor.
+
+/// Fields every EVE record has (or most do).
+const COMMON_FIELDS: &[&str] = &[
+    "timestamp",
+    "event_type",
+    "src_ip",
+    "src_port",
+    "dest_ip",
+    "dest_port",
+    "proto",
+    "app_proto",
+    "flow_id",
+    "in_iface",
+    "community_id",
+];
+
+/// Fields of the records of each event type.
+const EVENT_TYPE_FIELDS: &[(&str, &[&str])] = &[
+    (
+        "alert",
+        &[
+            "alert.signature",
+            "alert.signature_id",
+            "alert.category",
+            "alert.severity",
+            "alert.action",
+            "alert.gid",
+            "alert.rev",
+        ],
+    ),
+    (
+        "dns",
+        &[
+            "dns.type",
+            "dns.rrname",
+            "dns.rrtype",
+            "dns.rcode",
+            "dns.rdata",
+        ],
+    ),
+    (
+        "http",
+        &[
+            "http.hostname",
+            "http.url",
+            "http.http_method",
+            "http.http_user_agent",
+            "http.status",
+            "http.http_content_type",
+            "http.length",
+        ],
+    ),
+    (
+        "tls",
+        &[
+            "tls.sni",
+            "tls.version",
+            "tls.subject",
+            "tls.issuerdn",
+            "tls.fingerprint",
+            "tls.ja3.hash",
+            "tls.ja3s.hash",
+        ],
+    ),
+    (
+        "flow",
+        &[
+            "flow.state",
+            "flow.reason",
+            "flow.bytes_toserver",
+            "flow.bytes_toclient",
+            "flow.pkts_toserver",
+            "flow.pkts_toclient",
+        ],
+    ),
+    (
+        "fileinfo",
+        &[
+            "fileinfo.filename",
+            "fileinfo.magic",
+            "fileinfo.size",
+            "fileinfo.md5",
+            "fileinfo.sha256",
+        ],
+    ),
+    (
+        "ssh",
+        &[
+            "ssh.client.software_version",
+            "ssh.server.software_version",
+            "ssh.client.proto_version",
+        ],
+    ),
+    ("smtp", &["smtp.helo", "smtp.mail_from", "smtp.rcpt_to"]),
+    (
+        "anomaly",
+        &["anomaly.type", "anomaly.event", "anomaly.layer"],
+    ),
+];
+
+/// Whether an event is a Suricata EVE record.
+pub fn is_eve_record(event: &serde_json::Value) -> bool {
+    event.get("event_type").is_some_and(|t| t.is_string()) && event.get("timestamp").is_some()
+}
+
+/// The EVE fields an event of the given type can have, common ones first.
+pub fn known_fields(event_type: Option<&str>) -> Vec<&'static str> {
+    let mut fields = COMMON_FIELDS.to_vec();
+    for (kind, kind_fields) in EVENT_TYPE_FIELDS {
+        if event_type.is_none_or(|event_type| event_type == *kind) {
+            fields.extend_from_slice(kind_fields);
+        }
+    }
+    fields
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_eve_fields() {
+        assert!(is_eve_record(&serde_json::json!({
+            "timestamp": "2024-03-01T10:01:02.000000+0000",
+            "event_type": "alert",
+            "alert": { "signature": "ET SCAN Nmap" }
+        })));
+        assert!(!is_eve_record(&serde_json::json!({ "eventName": "x" })));
+
+        let dns = known_fields(Some("dns"));
+        assert!(dns.contains(&"dns.rrname"));
+        assert!(!dns.contains(&"alert.signature"));
+    }
+}
@@ -0,0 +1,408 @@
+//! Term sets: keyword lists loaded from plain text files.
+//!
+//! Used by the `IN_FILE` and `CONTAINS_FILE` condition operators so rules can
+//! reference large keyword lists (process names, phishing keywords) without
+//! embedding them in YAML. Files contain one term per line; blank lines and
+//! lines starting with `#` are ignored. Matching ignores case unless the rule
+//! is case-sensitive. A relative path is resolved against the rules directory.
+//!
+//! `IN_LIST 'name'` refers to a list by name instead of path: `name.txt` in
+//! one of the directories registered at startup (lookup lists, imported IOC
+//! lists). `IN_LIST 'ioc:name'` and `IN_LIST 'lookup:name'` pick the store; a
+//! bare name found in both is rejected as ambiguous.
+//!
+//! Loaded sets are cached per path and reloaded when the file changes, and
+//! conditions always look their sets up through that cache (see `resolve`),
+//! so an edited list is picked up by the next evaluation. A scan also
+//! resolves the sets its rules reference up front, so a missing file or list
+//! is reported as a rule failure rather than silently never matching.
+
+use aho_corasick::AhoCorasick;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::SystemTime;
+
+use crate::models::SiemError;
+
+/// A set of terms supporting exact and substring lookups.
+pub struct TermSet {
+    /// Whether terms and values are compared ignoring case
+    fold: bool,
+    /// Terms for exact membership checks (lowercase when folding)
+    terms: HashSet<String>,
+    /// Terms in automaton pattern order
+    patterns: Vec<String>,
+    /// Aho-Corasick automaton for "contains any term" checks
+    matcher: AhoCorasick,
+}
+
+impl TermSet {
+    /// Build a case-insensitive term set from raw terms.
+    pub fn from_terms<I>(terms: I) -> Result<Self, SiemError>
+    where
+        I: IntoIterator<Item = String>,
+    {
+        Self::from_terms_with_case(terms, true)
+    }
+
+    /// Build a term set from raw terms, ignoring case when `fold` is set.
+    pub fn from_terms_with_case<I>(terms: I, fold: bool) -> Result<Self, SiemError>
+    where
+        I: IntoIterator<Item = String>,
+    {
+        let terms: HashSet<String> = terms
+            .into_iter()
+            .map(|t| fold_term(t.trim(), fold))
+            .filter(|t| !t.is_empty())
+            .collect();
+
+        let patterns: Vec<String> = terms.iter().cloned().collect();
+        let matcher = AhoCorasick::new(&patterns)
+            .map_err(|e| SiemError::Rule(format!("Cannot build term matcher: {}", e)))?;
+
+        Ok(Self {
+            fold,
+            terms,
+            patterns,
+            matcher,
+        })
+    }
+
+    /// Check whether the value equals one of the terms.
+    pub fn contains_exact(&self, value: &str) -> bool {
+        self.terms.contains(&fold_term(value, self.fold))
+    }
+
+    /// Check whether the value contains any of the terms as a substring.
+    pub fn contains_any(&self, value: &str) -> bool {
+        self.matcher.is_match(&fold_term(value, self.fold))
+    }
+
+    /// Terms occurring in the value as whole tokens: not directly preceded or
+    /// followed by a letter or digit ("1.2.3.4" is not found in "11.2.3.45",
+    /// "evil.com" is found in "cdn.evil.com/x").
+    pub fn find_terms(&self, value: &str) -> Vec<&str> {
+        let value = fold_term(value, self.fold);
+        let bytes = value.as_bytes();
+        let mut found: Vec<&str> = Vec::new();
+        for m in self.matcher.find_overlapping_iter(&value) {
+            let before = m.start().checked_sub(1).map(|i| bytes[i]);
+            let after = bytes.get(m.end()).copied();
+            if before.is_some_and(|b| b.is_ascii_alphanumeric())
+                || after.is_some_and(|b| b.is_ascii_alphanumeric())
+            {
+                continue;
+            }
+            let term = self.patterns[m.pattern().as_usize()].as_str();
+            if !found.contains(&term) {
+                found.push(term);
+            }
+        }
+        found
+    }
+}
+
+fn fold_term(term: &str, fold: bool) -> String {
+    if fold {
+        term.to_lowercase()
+    } else {
+        term.to_string()
+    }
+}
+
+type TermSetCache = Mutex<HashMap<(PathBuf, bool), (SystemTime, Arc<TermSet>)>>;
+
+fn cache() -> &'static TermSetCache {
+    static CACHE: OnceLock<TermSetCache> = OnceLock::new();
+    CACHE.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Load a case-insensitive term set from a file, using the cached copy if the
+/// file is unchanged.
+pub fn load_term_set(path: &str) -> Result<Arc<TermSet>, SiemError> {
+    load_term_set_with_case(path, true)
+}
+
+/// Load a term set from a file, ignoring case when `fold` is set.
+pub fn load_term_set_with_case(path: &str, fold: bool) -> Result<Arc<TermSet>, SiemError> {
+    let path = PathBuf::from(path);
+
+    let modified = fs::metadata(&path)
+        .and_then(|m| m.modified())
+        .map_err(|e| SiemError::FileIO(format!("Cannot read term file {:?}: {}", path, e)))?;
+
+    let key = (path, fold);
+    if let Ok(cache) = cache().lock() {
+        if let Some((cached_modified, set)) = cache.get(&key) {
+            if *cached_modified == modified {
+                return Ok(set.clone());
+            }
+        }
+    }
+
+    let content = fs::read_to_string(&key.0)
+        .map_err(|e| SiemError::FileIO(format!("Cannot read term file {:?}: {}", key.0, e)))?;
+
+    let set = Arc::new(TermSet::from_terms_with_case(
+        content
+            .lines()
+            .filter(|line| !line.trim_start().starts_with('#'))
+            .map(|line| line.to_string()),
+        fold,
+    )?);
+
+    if let Ok(mut cache) = cache().lock() {
+        cache.insert(key, (modified, set.clone()));
+    }
+
+    Ok(set)
+}
+
+/// What a term-set operator refers to.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum TermSource {
+    /// `IN_FILE` / `CONTAINS_FILE`: a file path
+    File(String),
+    /// `IN_LIST`: a named list
+    List(String),
+}
+
+impl TermSource {
+    /// Load the referenced set, ignoring case when `fold` is set.
+    fn load(&self, fold: bool) -> Result<Arc<TermSet>, SiemError> {
+        match self {
+            TermSource::File(path) => {
+                load_term_set_with_case(&resolve_term_path(path).to_string_lossy(), fold)
+            }
+            TermSource::List(reference) => {
+                load_term_set_with_case(&named_list_path(reference)?.to_string_lossy(), fold)
+            }
+        }
+    }
+}
+
+/// The current content of a referenced set: the cached copy while the file
+/// is unchanged, reloaded once it was edited.
+pub fn resolve(source: &TermSource, fold: bool) -> Result<Arc<TermSet>, SiemError> {
+    source.load(fold)
+}
+
+/// Directory that relative term file paths are resolved against.
+fn rules_dir() -> &'static Mutex<Option<PathBuf>> {
+    static DIR: OnceLock<Mutex<Option<PathBuf>>> = OnceLock::new();
+    DIR.get_or_init(|| Mutex::new(None))
+}
+
+/// Resolve relative term file paths against the rules directory.
+pub fn register_rules_dir(dir: PathBuf) {
+    if let Ok(mut current) = rules_dir().lock() {
+        *current = Some(dir);
+    }
+}
+
+/// Absolute path of a term file: relative paths are taken from the rules
+/// directory, so rules and their term files can be shared together.
+pub fn resolve_term_path(path: &str) -> PathBuf {
+    let path = PathBuf::from(path);
+    if path.is_absolute() {
+        return path;
+    }
+    match rules_dir().lock().ok().and_then(|dir| dir.clone()) {
+        Some(dir) => dir.join(path),
+        None => path,
+    }
+}
+
+/// Namespace of imported IOC lists (`IN_LIST 'ioc:name'`).
+pub const IOC_LISTS: &str = "ioc";
+/// Namespace of lookup lists (`IN_LIST 'lookup:name'`).
+pub const LOOKUP_LISTS: &str = "lookup";
+
+/// Directories holding named lists, with their namespace, in registration order.
+fn list_dirs() -> &'static Mutex<Vec<(&'static str, PathBuf)>> {
+    static DIRS: OnceLock<Mutex<Vec<(&'static str, PathBuf)>>> = OnceLock::new();
+    DIRS.get_or_init(|| Mutex::new(Vec::new()))
+}
+
+/// Make the lists of a directory available to `IN_LIST` under a namespace.
+pub fn register_list_dir(namespace: &'static str, dir: PathBuf) {
+    if let Ok(mut dirs) = list_dirs().lock() {
+        if !dirs.iter().any(|(_, registered)| *registered == dir) {
+            dirs.push((namespace, dir));
+        }
+    }
+}
+
+/// Validate a list name (used as a filename).
+pub fn validate_list_name(name: &str) -> Result<(), SiemError> {
+    if name.is_empty()
+        || !name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+    {
+        return Err(SiemError::FileIO(format!(
+            "Invalid list name '{}': use letters, digits, '_' or '-'",
+            name
+        )));
+    }
+    Ok(())
+}
+
+/// Existing lists a reference can mean, with their namespace: `namespace:name`
+/// only looks in that namespace, a bare name in all of them.
+fn named_list_candidates(reference: &str) -> Result<Vec<(&'static str, PathBuf)>, SiemError> {
+    let (namespace, name) = match reference.split_once(':') {
+        Some((namespace, name)) => (Some(namespace), name),
+        None => (None, reference),
+    };
+    validate_list_name(name)?;
+    let dirs = list_dirs()
+        .lock()
+        .map_err(|_| SiemError::FileIO("List directories unavailable".to_string()))?;
+    if let Some(namespace) = namespace {
+        if !dirs.iter().any(|(registered, _)| *registered == namespace) {
+            return Err(SiemError::FileIO(format!(
+                "Unknown list namespace '{}': use {} or {}",
+                namespace, IOC_LISTS, LOOKUP_LISTS
+            )));
+        }
+    }
+    Ok(dirs
+        .iter()
+        .filter(|(registered, _)| namespace.is_none_or(|namespace| namespace == *registered))
+        .map(|(registered, dir)| (*registered, dir.join(format!("{}.txt", name))))
+        .filter(|(_, path)| path.is_file())
+        .collect())
+}
+
+/// Path of the list a reference (`name` or `namespace:name`) names, if it exists.
+pub fn find_named_list(reference: &str) -> Option<PathBuf> {
+    named_list_candidates(reference)
+        .ok()?
+        .into_iter()
+        .next()
+        .map(|(_, path)| path)
+}
+
+/// Path of the list a reference names. A bare name used by lists of several
+/// namespaces is an error, since the rule could mean either.
+pub fn named_list_path(reference: &str) -> Result<PathBuf, SiemError> {
+    let mut candidates = named_list_candidates(reference)?;
+    match candidates.len() {
+        0 => Err(SiemError::FileIO(format!("List not found: {}", reference))),
+        1 => Ok(candidates.remove(0).1),
+        _ => Err(SiemError::Rule(format!(
+            "List name '{}' is ambiguous: use {}",
+            reference,
+            candidates
+                .iter()
+                .map(|(namespace, _)| format!("'{}:{}'", namespace, reference))
+                .collect::<Vec<_>>()
+                .join(" or ")
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_term_set_exact_and_substring() {
+        let set = TermSet::from_terms(vec![
+            "mimikatz.exe".to_string(),
+            "  PsExec.exe ".to_string(),
+            "".to_string(),
+        ])
+        .unwrap();
+
+        assert!(set.contains_exact("Mimikatz.exe"));
+        assert!(set.contains_exact("psexec.exe"));
+        assert!(!set.contains_exact("c:\\tools\\mimikatz.exe"));
+        assert!(set.contains_any("C:\\Tools\\MIMIKATZ.EXE"));
+        assert!(!set.contains_any("notepad.exe"));
+        assert_eq!(
+            set.find_terms("run MIMIKATZ.EXE then psexec.exe"),
+            vec!["mimikatz.exe", "psexec.exe"]
+        );
+        assert!(set.find_terms("xmimikatz.exe").is_empty());
+
+        let exact = TermSet::from_terms_with_case(vec!["Mimikatz.exe".to_string()], false).unwrap();
+        assert!(exact.contains_exact("Mimikatz.exe"));
+        assert!(!exact.contains_exact("mimikatz.exe"));
+        assert!(!exact.contains_any("C:\\Tools\\MIMIKATZ.EXE"));
+    }
+
+    #[test]
+    fn test_namespaced_list_references() {
+        let root = std::env::temp_dir().join("offline_siem_test_list_namespaces");
+        let (ioc_dir, lookup_dir) = (root.join("intel"), root.join("lookup_lists"));
+        fs::create_dir_all(&ioc_dir).unwrap();
+        fs::create_dir_all(&lookup_dir).unwrap();
+        fs::write(ioc_dir.join("ns_test_shared.txt"), "203.0.113.7\n").unwrap();
+        fs::write(lookup_dir.join("ns_test_shared.txt"), "10.0.0.1\n").unwrap();
+        fs::write(lookup_dir.join("ns_test_admins.txt"), "alice\n").unwrap();
+        register_list_dir(IOC_LISTS, ioc_dir.clone());
+        register_list_dir(LOOKUP_LISTS, lookup_dir.clone());
+
+        let ambiguous = named_list_path("ns_test_shared").unwrap_err().to_string();
+        assert!(ambiguous.contains("'ioc:ns_test_shared' or 'lookup:ns_test_shared'"));
+        assert_eq!(
+            named_list_path("ioc:ns_test_shared").unwrap(),
+            ioc_dir.join("ns_test_shared.txt")
+        );
+        assert_eq!(
+            named_list_path("lookup:ns_test_shared").unwrap(),
+            lookup_dir.join("ns_test_shared.txt")
+        );
+        assert_eq!(
+            named_list_path("ns_test_admins").unwrap(),
+            lookup_dir.join("ns_test_admins.txt")
+        );
+        assert!(named_list_path("ioc:ns_test_admins").is_err());
+        assert!(named_list_path("feeds:ns_test_admins").is_err());
+
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_edited_term_file_is_reloaded() {
+        let path = std::env::temp_dir().join("offline_siem_test_edited_terms.txt");
+        fs::write(&path, "mimikatz.exe\n").unwrap();
+        let source = TermSource::File(path.to_string_lossy().to_string());
+        assert!(resolve(&source, true)
+            .unwrap()
+            .contains_exact("mimikatz.exe"));
+
+        fs::write(&path, "psexec.exe\n").unwrap();
+        let later = SystemTime::now() + std::time::Duration::from_secs(10);
+        fs::File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(later)
+            .unwrap();
+        let set = resolve(&source, true).unwrap();
+        assert!(set.contains_exact("psexec.exe"));
+        assert!(!set.contains_exact("mimikatz.exe"));
+
+        fs::remove_file(&path).unwrap();
+        assert!(resolve(&source, true).is_err());
+    }
+
+    #[test]
+    fn test_relative_paths_resolve_against_rules_dir() {
+        let absolute = std::env::temp_dir().join("terms.txt");
+        assert_eq!(resolve_term_path(&absolute.to_string_lossy()), absolute);
+
+        register_rules_dir(std::env::temp_dir().join("offline_siem_test_rules"));
+        assert_eq!(
+            resolve_term_path("lists/tools.txt"),
+            std::env::temp_dir()
+                .join("offline_siem_test_rules")
+                .join("lists/tools.txt")
+        );
+    }
+}
@@ -1,41 +1,163 @@
+use crate::azure;
 use crate::db_engine;
-use crate::models::{FieldSuggestion, LogType, SiemError, TestRuleResult, ValidationResult};
+use crate::models::{
+    FieldSuggestion, FieldValueCount, LogType, SiemError, TestRuleResult, ValidationResult,
+};
+use crate::okta;
+use crate::safe_regex;
+use crate::suricata;
+use crate::windows_events;
+use duckdb::Connection;
 use serde_json::Value;
 use std::collections::HashMap;
-use std::time::Instant;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Instant, SystemTime};
+
+/// Field path -> (type, sample value, frequency)
+type FieldMap = HashMap<String, (String, String, usize)>;
+
+/// Number of log files whose field map is kept.
+const MAX_CACHED_FIELD_MAPS: usize = 8;
+
+/// Field map of a log file, valid while the file keeps its modification time.
+struct CachedFieldMap {
+    modified: SystemTime,
+    last_used: Instant,
+    fields: Arc<FieldMap>,
+}
+
+fn field_map_cache() -> &'static Mutex<HashMap<String, CachedFieldMap>> {
+    static CACHE: OnceLock<Mutex<HashMap<String, CachedFieldMap>>> = OnceLock::new();
+    CACHE.get_or_init(|| Mutex::new(HashMap::new()))
+}
 
 /// Test a rule condition against loaded events
 pub fn test_rule(
+    conn: &Connection,
     log_path: &str,
     condition: &str,
     log_type: LogType,
+    case_sensitive: Option<bool>,
 ) -> Result<TestRuleResult, SiemError> {
     let start = Instant::now();
 
     // First validate syntax
-    let validation = validate_condition(condition);
-    if !validation.valid {
-        return Ok(TestRuleResult {
-            matched_count: 0,
-            total_count: 0,
-            matched_events: vec![],
-            sample_non_matched: vec![],
-            syntax_valid: false,
-            syntax_error: validation.error_message,
-            execution_time_ms: start.elapsed().as_millis() as u64,
-        });
+    if let Some(invalid) = syntax_error_result(condition, start) {
+        return Ok(invalid);
     }
 
+    db_engine::resolve_condition_term_sets(
+        condition,
+        db_engine::CaseMode::from_flag(case_sensitive),
+    )?;
+
     // Load events
-    let conn = db_engine::create_connection()?;
-    let all_events = db_engine::load_all_events(&conn, log_path, log_type)?;
+    let all_events = db_engine::load_all_events(conn, log_path, log_type)?;
+
+    Ok(evaluate_events(
+        &all_events,
+        condition,
+        case_sensitive,
+        start,
+    ))
+}
+
+/// Test a rule condition against sample events pasted by the user: a JSON
+/// event, an array of events, a CloudTrail `{"Records": [...]}` document or
+/// one event per line (NDJSON)
+pub fn test_rule_inline(
+    condition: &str,
+    events_json: &str,
+    case_sensitive: Option<bool>,
+) -> Result<TestRuleResult, SiemError> {
+    let start = Instant::now();
+
+    if let Some(invalid) = syntax_error_result(condition, start) {
+        return Ok(invalid);
+    }
 
+    db_engine::resolve_condition_term_sets(
+        condition,
+        db_engine::CaseMode::from_flag(case_sensitive),
+    )?;
+    let events = parse_sample_events(events_json)?;
+
+    Ok(evaluate_events(&events, condition, case_sensitive, start))
+}
+
+/// Parse pasted sample events
+fn parse_sample_events(events_json: &str) -> Result<Vec<Value>, SiemError> {
+    let events_json = events_json.trim();
+    if events_json.is_empty() {
+        return Err(SiemError::Serialization(
+            "No sample events given".to_string(),
+        ));
+    }
+
+    match serde_json::from_str::<Value>(events_json) {
+        Ok(Value::Array(events)) => Ok(events),
+        Ok(Value::Object(mut document)) => match document.remove("Records") {
+            Some(Value::Array(records)) => Ok(records),
+            Some(other) => {
+                document.insert("Records".to_string(), other);
+                Ok(vec![Value::Object(document)])
+            }
+            None => Ok(vec![Value::Object(document)]),
+        },
+        Ok(other) => Err(SiemError::Serialization(format!(
+            "Sample events must be JSON objects, got: {}",
+            other
+        ))),
+        // Not a single document: one event per line
+        Err(_) => events_json
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .enumerate()
+            .map(|(index, line)| {
+                serde_json::from_str(line).map_err(|e| {
+                    SiemError::Serialization(format!(
+                        "Invalid sample event on line {}: {}",
+                        index + 1,
+                        e
+                    ))
+                })
+            })
+            .collect(),
+    }
+}
+
+/// Result reported for a condition with a syntax error, if it has one
+fn syntax_error_result(condition: &str, start: Instant) -> Option<TestRuleResult> {
+    let validation = validate_condition(condition);
+    if validation.valid {
+        return None;
+    }
+    Some(TestRuleResult {
+        matched_count: 0,
+        total_count: 0,
+        matched_events: vec![],
+        sample_non_matched: vec![],
+        syntax_valid: false,
+        syntax_error: validation.error_message,
+        execution_time_ms: start.elapsed().as_millis() as u64,
+    })
+}
+
+/// Evaluate a valid condition against events
+fn evaluate_events(
+    all_events: &[Value],
+    condition: &str,
+    case_sensitive: Option<bool>,
+    start: Instant,
+) -> TestRuleResult {
     // Test condition against each event
+    let case_mode = db_engine::CaseMode::from_flag(case_sensitive);
     let mut matched = Vec::new();
     let mut non_matched = Vec::new();
 
     for event in all_events.iter() {
-        if db_engine::matches_condition(event, condition) {
+        if db_engine::matches_condition_with_case(event, condition, case_mode) {
             matched.push(event.clone());
         } else {
             // Keep sample of non-matched (max 5)
@@ -47,7 +169,7 @@ pub fn test_rule(
 
     let execution_time = start.elapsed().as_millis() as u64;
 
-    Ok(TestRuleResult {
+    TestRuleResult {
         matched_count: matched.len(),
         total_count: all_events.len(),
         matched_events: matched,
@@ -55,7 +177,7 @@ pub fn test_rule(
         syntax_valid: true,
         syntax_error: None,
         execution_time_ms: execution_time,
-    })
+    }
 }
 
 /// Validate rule condition syntax
@@ -104,7 +226,11 @@ pub fn validate_condition(condition: &str) -> ValidationResult {
         || upper_cond.contains(" IN ")
         || upper_cond.contains(" STARTSWITH ")
         || upper_cond.contains(" ENDSWITH ")
-        || upper_cond.contains(" MATCH ");
+        || upper_cond.contains(" MATCH ")
+        || upper_cond.contains(" IN_FILE ")
+        || upper_cond.contains(" CONTAINS_FILE ")
+        || upper_cond.contains(" IN_LIST ")
+        || upper_cond.contains(" REGEX ");
 
     if !has_operator {
         return ValidationResult {
@@ -121,7 +247,22 @@ pub fn validate_condition(condition: &str) -> ValidationResult {
         };
     }
 
-    // 3. Check for balanced AND/OR
+    // 3. Check regex patterns against the complexity bounds
+    for pattern in regex_patterns(condition) {
+        if let Err(e) = safe_regex::compile(pattern, false) {
+            return ValidationResult {
+                valid: false,
+                error_message: Some(e.to_string()),
+                error_position: condition.find(pattern),
+                suggestions: vec![
+                    format!("Keep patterns under {} bytes", safe_regex::MAX_PATTERN_LEN),
+                    "Avoid large counted repetitions like (a{1000}){1000}".to_string(),
+                ],
+            };
+        }
+    }
+
+    // 4. Check for balanced AND/OR
     let upper = condition.to_uppercase();
     if upper.contains(" AND ") || upper.contains(" OR ") {
         // Basic check - make sure there's something before and after
@@ -152,39 +293,55 @@ pub fn validate_condition(condition: &str) -> ValidationResult {
     }
 }
 
+/// Extract the quoted patterns following each REGEX operator.
+fn regex_patterns(condition: &str) -> Vec<&str> {
+    let mut patterns = Vec::new();
+    let mut rest = condition;
+
+    while let Some((pos, keyword, _)) = db_engine::find_regex_operator(rest) {
+        let value = rest[pos + keyword.len()..].trim_start();
+        let Some(quote) = value.chars().next().filter(|c| *c == '\'' || *c == '"') else {
+            break;
+        };
+        let Some(end) = value[1..].find(quote) else {
+            break;
+        };
+        patterns.push(&value[1..end + 1]);
+        rest = &value[end + 2..];
+    }
+
+    patterns
+}
+
 /// Get field suggestions from loaded events for autocomplete
 pub fn get_field_suggestions(
+    conn: &Connection,
     log_path: &str,
     log_type: LogType,
     prefix: &str,
 ) -> Result<Vec<FieldSuggestion>, SiemError> {
-    let conn = db_engine::create_connection()?;
-    let events = db_engine::load_all_events(&conn, log_path, log_type)?;
-
-    // Collect all field paths from events
-    let mut field_map: HashMap<String, (String, String, usize)> = HashMap::new();
-
-    for event in events.iter().take(100) {
-        // Sample first 100 events
-        collect_fields(event, "", &mut field_map);
-    }
+    let known = known_fields(&log_type);
+    let field_map = field_map(conn, log_path, log_type)?;
 
     // Filter by prefix and convert to suggestions
     let mut suggestions: Vec<FieldSuggestion> = field_map
-        .into_iter()
+        .iter()
         .filter(|(path, _)| path.to_lowercase().starts_with(&prefix.to_lowercase()))
         .map(
             |(path, (field_type, sample_value, frequency))| FieldSuggestion {
-                field_path: path,
-                field_type,
-                sample_value,
-                frequency,
+                field_path: path.clone(),
+                field_type: field_type.clone(),
+                sample_value: sample_value.clone(),
+                frequency: *frequency,
             },
         )
         .collect();
 
     // Sort by frequency (most common first)
     suggestions.sort_by(|a, b| b.frequency.cmp(&a.frequency));
+    if let Some(known) = known {
+        tailor_suggestions(&mut suggestions, &known, prefix);
+    }
 
     // Limit to top 20
     suggestions.truncate(20);
@@ -192,12 +349,137 @@ pub fn get_field_suggestions(
     Ok(suggestions)
 }
 
-/// Recursively collect field paths from JSON
-fn collect_fields(
-    value: &Value,
+/// Well-known fields of the log types with a known schema.
+fn known_fields(log_type: &LogType) -> Option<Vec<&'static str>> {
+    match log_type {
+        LogType::Suricata => Some(suricata::known_fields(None)),
+        LogType::AzureActivity => Some(azure::ACTIVITY_FIELDS.to_vec()),
+        LogType::EntraSignIn => Some(azure::SIGNIN_FIELDS.to_vec()),
+        LogType::Okta => Some(okta::FIELDS.to_vec()),
+        LogType::Windows | LogType::WindowsXml => Some(windows_events::FIELDS.to_vec()),
+        _ => None,
+    }
+}
+
+/// Tailor field suggestions to a log type's known fields: they come first
+/// (in the given order), and those the sampled events lack are suggested
+/// too, so e.g. `alert.` completes to the EVE alert fields before any alert
+/// is loaded.
+fn tailor_suggestions(suggestions: &mut Vec<FieldSuggestion>, known: &[&str], prefix: &str) {
+    let prefix = prefix.to_lowercase();
+    let rank = |field: &str| known.iter().position(|known| *known == field);
+
+    for field in known {
+        if field.to_lowercase().starts_with(&prefix)
+            && !suggestions.iter().any(|s| s.field_path == *field)
+        {
+            suggestions.push(FieldSuggestion {
+                field_path: field.to_string(),
+                field_type: "string".to_string(),
+                sample_value: String::new(),
+                frequency: 0,
+            });
+        }
+    }
+
+    // Known fields first, then the others by frequency
+    suggestions.sort_by(|a, b| match (rank(&a.field_path), rank(&b.field_path)) {
+        (Some(a), Some(b)) => a.cmp(&b),
+        (Some(_), None) => std::cmp::Ordering::Less,
+        (None, Some(_)) => std::cmp::Ordering::Greater,
+        (None, None) => b.frequency.cmp(&a.frequency),
+    });
+}
+
+/// Get the field map of a log file, collected once per file version: the
+/// rule editor asks for suggestions on every keystroke
+fn field_map(
+    conn: &Connection,
+    log_path: &str,
+    log_type: LogType,
+) -> Result<Arc<FieldMap>, SiemError> {
+    let modified = std::fs::metadata(log_path)
+        .and_then(|metadata| metadata.modified())
+        .ok();
+    if let (Some(modified), Ok(mut cache)) = (modified, field_map_cache().lock()) {
+        if let Some(cached) = cache.get_mut(log_path) {
+            if cached.modified == modified {
+                cached.last_used = Instant::now();
+                return Ok(cached.fields.clone());
+            }
+        }
+    }
+
+    let events = db_engine::load_all_events(conn, log_path, log_type)?;
+
+    // Collect all field paths from events
+    let mut field_map: FieldMap = HashMap::new();
+
+    for event in events.iter().take(100) {
+        // Sample first 100 events
+        collect_fields(event, "", &mut field_map);
+    }
+    let field_map = Arc::new(field_map);
+
+    if let (Some(modified), Ok(mut cache)) = (modified, field_map_cache().lock()) {
+        if cache.len() >= MAX_CACHED_FIELD_MAPS && !cache.contains_key(log_path) {
+            let oldest = cache
+                .iter()
+                .min_by_key(|(_, cached)| cached.last_used)
+                .map(|(path, _)| path.clone());
+            if let Some(oldest) = oldest {
+                cache.remove(&oldest);
+            }
+        }
+        cache.insert(
+            log_path.to_string(),
+            CachedFieldMap {
+                modified,
+                last_used: Instant::now(),
+                fields: field_map.clone(),
+            },
+        );
+    }
+
+    Ok(field_map)
+}
+
+/// Get the most frequent values of a field starting with a prefix, for value
+/// autocomplete (e.g. eventName, userAgent)
+pub fn get_value_suggestions(
+    log_path: &str,
+    log_type: &LogType,
+    field: &str,
     prefix: &str,
-    field_map: &mut HashMap<String, (String, String, usize)>,
-) {
+) -> Result<Vec<FieldValueCount>, SiemError> {
+    let prefix = prefix.to_lowercase();
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    db_engine::stream_events(log_path, log_type, db_engine::STREAM_CHUNK_SIZE, |chunk| {
+        for event in &chunk {
+            for value in db_engine::get_field_values(event, field) {
+                if value.to_lowercase().starts_with(&prefix) {
+                    *counts.entry(value).or_insert(0) += 1;
+                }
+            }
+        }
+    })?;
+
+    let mut suggestions: Vec<FieldValueCount> = counts
+        .into_iter()
+        .map(|(value, count)| FieldValueCount { value, count })
+        .collect();
+
+    // Most frequent first, ties by value
+    suggestions.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.value.cmp(&b.value)));
+
+    // Limit to top 20
+    suggestions.truncate(20);
+
+    Ok(suggestions)
+}
+
+/// Recursively collect field paths from JSON
+fn collect_fields(value: &Value, prefix: &str, field_map: &mut FieldMap) {
     match value {
         Value::Object(map) => {
             for (key, val) in map {
@@ -253,6 +535,107 @@ fn collect_fields(
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_rule_inline_sample_formats() {
+        let condition = "eventName = 'ConsoleLogin'";
+        let single = r#"{"eventName": "ConsoleLogin", "sourceIPAddress": "203.0.113.7"}"#;
+        let result = test_rule_inline(condition, single, None).unwrap();
+        assert_eq!((result.matched_count, result.total_count), (1, 1));
+
+        let records = r#"{"Records": [{"eventName": "ConsoleLogin"}, {"eventName": "GetObject"}]}"#;
+        let result = test_rule_inline(condition, records, None).unwrap();
+        assert_eq!((result.matched_count, result.total_count), (1, 2));
+
+        let ndjson = "{\"eventName\": \"GetObject\"}\n\n{\"eventName\": \"ConsoleLogin\"}\n";
+        let result = test_rule_inline(condition, ndjson, None).unwrap();
+        assert_eq!((result.matched_count, result.total_count), (1, 2));
+
+        assert!(test_rule_inline(condition, "{\"eventName\": ", None).is_err());
+        assert!(
+            !test_rule_inline("eventName AssumeRole", single, None)
+                .unwrap()
+                .syntax_valid
+        );
+    }
+
+    #[test]
+    fn test_field_map_cached_per_file_version() {
+        let path = std::env::temp_dir().join("offline_siem_test_field_map_cache.json");
+        let log_path = path.to_string_lossy().to_string();
+        std::fs::write(&path, "{\"eventName\": \"GetObject\"}\n").unwrap();
+        let conn = Connection::open_in_memory().unwrap();
+
+        let first = field_map(&conn, &log_path, LogType::FlatJson).unwrap();
+        let second = field_map(&conn, &log_path, LogType::FlatJson).unwrap();
+        assert!(Arc::ptr_eq(&first, &second));
+
+        // A modified file is collected again
+        std::fs::write(&path, "{\"userAgent\": \"aws-cli\"}\n").unwrap();
+        field_map_cache()
+            .lock()
+            .unwrap()
+            .get_mut(&log_path)
+            .unwrap()
+            .modified = SystemTime::UNIX_EPOCH;
+        let third = field_map(&conn, &log_path, LogType::FlatJson).unwrap();
+        assert!(third.contains_key("userAgent"));
+    }
+
+    #[test]
+    fn test_tailor_suggestions() {
+        let suggestion = |path: &str, frequency| FieldSuggestion {
+            field_path: path.to_string(),
+            field_type: "string".to_string(),
+            sample_value: "x".to_string(),
+            frequency,
+        };
+        let mut suggestions = vec![
+            suggestion("alert.custom", 9),
+            suggestion("alert.severity", 5),
+        ];
+        let known = known_fields(&LogType::Suricata).unwrap();
+        tailor_suggestions(&mut suggestions, &known, "alert.");
+        let paths: Vec<&str> = suggestions.iter().map(|s| s.field_path.as_str()).collect();
+        assert_eq!(paths[0], "alert.signature");
+        assert_eq!(paths.last(), Some(&"alert.custom"));
+        assert_eq!(
+            suggestions
+                .iter()
+                .find(|s| s.field_path == "alert.severity")
+                .map(|s| s.frequency),
+            Some(5)
+        );
+
+        let mut suggestions = Vec::new();
+        let known = known_fields(&LogType::EntraSignIn).unwrap();
+        tailor_suggestions(&mut suggestions, &known, "user");
+        assert_eq!(suggestions[0].field_path, "userPrincipalName");
+        assert!(known_fields(&LogType::FlatJson).is_none());
+    }
+
+    #[test]
+    fn test_value_suggestions() {
+        let path = std::env::temp_dir().join("offline_siem_test_value_suggestions.json");
+        std::fs::write(
+            &path,
+            "{\"eventName\": \"GetObject\"}\n{\"eventName\": \"GetBucketAcl\"}\n{\"eventName\": \"GetObject\"}\n{\"eventName\": \"PutObject\"}\n",
+        )
+        .unwrap();
+
+        let suggestions = get_value_suggestions(
+            &path.to_string_lossy(),
+            &LogType::FlatJson,
+            "eventName",
+            "get",
+        )
+        .unwrap();
+        let values: Vec<(&str, usize)> = suggestions
+            .iter()
+            .map(|s| (s.value.as_str(), s.count))
+            .collect();
+        assert_eq!(values, vec![("GetObject", 2), ("GetBucketAcl", 1)]);
+    }
+
     #[test]
     fn test_validate_condition_valid() {
         let result = validate_condition("eventName = 'AssumeRole'");
@@ -272,6 +655,19 @@ mod tests {
         assert!(!result.valid);
     }
 
+    #[test]
+    fn test_validate_condition_regex() {
+        assert!(validate_condition("userAgent REGEX '^aws-cli/[0-9]+'").valid);
+        assert!(!validate_condition("userAgent REGEX '(a{1000}){1000}'").valid);
+        assert!(!validate_condition("eventName = 'x' AND userAgent NOT REGEX '(unclosed'").valid);
+    }
+
+    #[test]
+    fn test_regex_patterns_extraction() {
+        let condition = "a REGEX 'x=1' AND b = 'c' OR d NOT REGEX \"(y|z)\"";
+        assert_eq!(regex_patterns(condition), vec!["x=1", "(y|z)"]);
+    }
+
     #[test]
     fn test_validate_condition_in() {
         let result = validate_condition("eventName IN ('A', 'B')");
@@ -0,0 +1,547 @@
+//! Config-driven parsers for text logs (auth.log, proxy logs, ...).
+//!
+//! A text parser has file patterns (`*.log`, `auth.log*`) choosing the files
+//! it reads, and line patterns: grok-style (`%{IP:client} %{WORD:method}`)
+//! or raw regular expressions with named captures (`(?P<client>\S+)`). Each
+//! line is matched against the patterns in order and the first match becomes
+//! an event, captures becoming fields (dotted names nest: `source.ip`), with
+//! the raw line kept as `message`. Files of the log type `{"text": "<name>"}`
+//! are read this way; files matching a parser's file patterns get that type
+//! when detected.
+//!
+//! Parsers are stored in `text_parsers.json` in the application's data
+//! directory.
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::BufRead;
+use std::path::PathBuf;
+use std::sync::{Mutex, OnceLock};
+
+use crate::app_data;
+use crate::models::{LogType, SiemError};
+
+/// Size limit of a compiled line pattern (grok patterns expand a lot).
+const COMPILED_SIZE_LIMIT: usize = 10 * 1024 * 1024;
+
+/// Depth of `%{NAME}` references inside pattern definitions, at most.
+const MAX_EXPANSION_DEPTH: usize = 16;
+
+/// Built-in grok patterns.
+const GROK_PATTERNS: &[(&str, &str)] = &[
+    ("USERNAME", r"[a-zA-Z0-9._-]+"),
+    ("USER", r"%{USERNAME}"),
+    ("INT", r"[+-]?[0-9]+"),
+    ("POSINT", r"\b[1-9][0-9]*\b"),
+    ("NONNEGINT", r"\b[0-9]+\b"),
+    ("NUMBER", r"[+-]?(?:[0-9]+(?:\.[0-9]+)?|\.[0-9]+)"),
+    ("BASE16NUM", r"(?:0[xX])?[0-9A-Fa-f]+"),
+    ("WORD", r"\b\w+\b"),
+    ("NOTSPACE", r"\S+"),
+    ("SPACE", r"\s*"),
+    ("DATA", r".*?"),
+    ("GREEDYDATA", r".*"),
+    ("QUOTEDSTRING", r#""(?:[^"\\]|\\.)*"|'(?:[^'\\]|\\.)*'"#),
+    ("QS", r"%{QUOTEDSTRING}"),
+    (
+        "UUID",
+        r"[A-Fa-f0-9]{8}-[A-Fa-f0-9]{4}-[A-Fa-f0-9]{4}-[A-Fa-f0-9]{4}-[A-Fa-f0-9]{12}",
+    ),
+    ("MAC", r"(?:[A-Fa-f0-9]{2}[:-]){5}[A-Fa-f0-9]{2}"),
+    (
+        "IPV4",
+        r"(?:(?:25[0-5]|2[0-4][0-9]|1?[0-9]{1,2})\.){3}(?:25[0-5]|2[0-4][0-9]|1?[0-9]{1,2})",
+    ),
+    ("IPV6", r"(?:[0-9A-Fa-f]{0,4}:){2,7}[0-9A-Fa-f]{0,4}"),
+    ("IP", r"(?:%{IPV6}|%{IPV4})"),
+    (
+        "HOSTNAME",
+        r"\b[0-9A-Za-z][0-9A-Za-z-]{0,62}(?:\.[0-9A-Za-z][0-9A-Za-z-]{0,62})*\.?\b",
+    ),
+    ("IPORHOST", r"(?:%{IP}|%{HOSTNAME})"),
+    ("HOSTPORT", r"%{IPORHOST}:%{POSINT}"),
+    (
+        "EMAILADDRESS",
+        r"[a-zA-Z0-9_.+-]+@[a-zA-Z0-9-]+(?:\.[a-zA-Z0-9-]+)*",
+    ),
+    ("PATH", r"(?:/[^\s]*)+|(?:[A-Za-z]:)?(?:\\[^\s\\]*)+"),
+    ("URIPROTO", r"[A-Za-z][A-Za-z0-9+.-]+"),
+    ("URIPATH", r"(?:/[A-Za-z0-9$.+!*'(){},~:;=@#%&_-]*)+"),
+    ("URIPARAM", r"\?[A-Za-z0-9$.+!*'|(){},~@#%&/=:;_?\[\]<>-]*"),
+    ("URIPATHPARAM", r"%{URIPATH}(?:%{URIPARAM})?"),
+    (
+        "URI",
+        r"%{URIPROTO}://(?:%{USER}(?::[^@]*)?@)?(?:%{IPORHOST})?(?::%{POSINT})?(?:%{URIPATHPARAM})?",
+    ),
+    (
+        "MONTH",
+        r"\b(?:Jan(?:uary)?|Feb(?:ruary)?|Mar(?:ch)?|Apr(?:il)?|May|Jun(?:e)?|Jul(?:y)?|Aug(?:ust)?|Sep(?:tember)?|Oct(?:ober)?|Nov(?:ember)?|Dec(?:ember)?)\b",
+    ),
+    ("MONTHNUM", r"(?:0?[1-9]|1[0-2])"),
+    ("MONTHDAY", r"(?:0[1-9]|[12][0-9]|3[01]|[1-9])"),
+    (
+        "DAY",
+        r"(?:Mon(?:day)?|Tue(?:sday)?|Wed(?:nesday)?|Thu(?:rsday)?|Fri(?:day)?|Sat(?:urday)?|Sun(?:day)?)",
+    ),
+    ("YEAR", r"(?:\d\d){1,2}"),
+    ("HOUR", r"(?:2[0123]|[01]?[0-9])"),
+    ("MINUTE", r"(?:[0-5][0-9])"),
+    ("SECOND", r"(?:(?:[0-5]?[0-9]|60)(?:[:.,][0-9]+)?)"),
+    ("TIME", r"%{HOUR}:%{MINUTE}:%{SECOND}"),
+    ("ISO8601_TIMEZONE", r"(?:Z|[+-]%{HOUR}(?::?%{MINUTE}))"),
+    (
+        "TIMESTAMP_ISO8601",
+        r"%{YEAR}-%{MONTHNUM}-%{MONTHDAY}[T ]%{HOUR}:?%{MINUTE}(?::?%{SECOND})?%{ISO8601_TIMEZONE}?",
+    ),
+    ("SYSLOGTIMESTAMP", r"%{MONTH} +%{MONTHDAY} %{TIME}"),
+    ("HTTPDATE", r"%{MONTHDAY}/%{MONTH}/%{YEAR}:%{TIME} %{INT}"),
+    ("PROG", r"[\x21-\x5a\x5c\x5e-\x7e]+"),
+    ("SYSLOGPROG", r"%{PROG:program}(?:\[%{POSINT:pid:int}\])?"),
+    ("SYSLOGHOST", r"%{IPORHOST}"),
+    (
+        "LOGLEVEL",
+        r"(?:[Aa]lert|ALERT|[Tt]race|TRACE|[Dd]ebug|DEBUG|[Nn]otice|NOTICE|[Ii]nfo|INFO|[Ww]arn(?:ing)?|WARN(?:ING)?|[Ee]rr(?:or)?|ERR(?:OR)?|[Cc]rit(?:ical)?|CRIT(?:ICAL)?|[Ff]atal|FATAL|[Ss]evere|SEVERE|[Ee]merg(?:ency)?|EMERG(?:ENCY)?)",
+    ),
+];
+
+/// A user-defined parser for text logs.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TextParser {
+    /// Name used in the log type
+    pub name: String,
+    /// File names the parser reads, with `*` and `?` wildcards
+    /// (case-insensitive), e.g. `*.log`, `auth.log*`
+    #[serde(default)]
+    pub file_patterns: Vec<String>,
+    /// Line patterns tried in order: grok (`%{IP:client}`, `%{INT:port:int}`)
+    /// and/or regular expressions with named captures
+    pub patterns: Vec<String>,
+    /// Extra grok definitions usable as `%{NAME}` in the patterns
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub custom_patterns: BTreeMap<String, String>,
+    /// Keep lines no pattern matches as events with only `message`
+    #[serde(default)]
+    pub keep_unmatched: bool,
+}
+
+/// Type a captured value is converted to.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum FieldKind {
+    Text,
+    Int,
+    Float,
+}
+
+/// A capture group and the field it fills.
+#[derive(Debug)]
+struct Capture {
+    group: String,
+    field: String,
+    kind: FieldKind,
+}
+
+/// A line pattern ready for matching.
+#[derive(Debug)]
+struct LinePattern {
+    regex: Regex,
+    captures: Vec<Capture>,
+}
+
+/// A text parser ready for reading files.
+#[derive(Debug)]
+pub struct CompiledParser {
+    patterns: Vec<LinePattern>,
+    keep_unmatched: bool,
+}
+
+/// Expand the `%{NAME}`, `%{NAME:field}` and `%{NAME:field:type}` references
+/// of a grok pattern into a regular expression, collecting the captures.
+fn expand_grok(
+    pattern: &str,
+    custom: &BTreeMap<String, String>,
+    captures: &mut Vec<Capture>,
+    depth: usize,
+) -> Result<String, SiemError> {
+    if depth > MAX_EXPANSION_DEPTH {
+        return Err(SiemError::Rule(
+            "Grok patterns are nested too deeply (recursive definition?)".to_string(),
+        ));
+    }
+
+    let mut expanded = String::new();
+    let mut rest = pattern;
+    while let Some(start) = rest.find("%{") {
+        expanded.push_str(&rest[..start]);
+        let reference = &rest[start + 2..];
+        let end = reference
+            .find('}')
+            .ok_or_else(|| SiemError::Rule(format!("Unclosed grok reference in '{}'", pattern)))?;
+        let mut parts = reference[..end].split(':');
+        let name = parts.next().unwrap_or_default().trim();
+        let field = parts.next().map(str::trim).filter(|f| !f.is_empty());
+        let kind = match parts.next().map(str::trim) {
+            None | Some("string") => FieldKind::Text,
+            Some("int") => FieldKind::Int,
+            Some("float") => FieldKind::Float,
+            Some(other) => {
+                return Err(SiemError::Rule(format!(
+                    "Unknown grok type '{}' (use int or float)",
+                    other
+                )))
+            }
+        };
+
+        let definition = custom
+            .get(name)
+            .map(String::as_str)
+            .or_else(|| {
+                GROK_PATTERNS
+                    .iter()
+                    .find(|(builtin, _)| *builtin == name)
+                    .map(|(_, definition)| *definition)
+            })
+            .ok_or_else(|| SiemError::Rule(format!("Unknown grok pattern '{}'", name)))?;
+        let inner = expand_grok(definition, custom, captures, depth + 1)?;
+
+        match field {
+            Some(field) => {
+                let group = format!("g{}", captures.len());
+                expanded.push_str(&format!("(?P<{}>{})", group, inner));
+                captures.push(Capture {
+                    group,
+                    field: field.to_string(),
+                    kind,
+                });
+            }
+            None => expanded.push_str(&format!("(?:{})", inner)),
+        }
+        rest = &reference[end + 1..];
+    }
+    expanded.push_str(rest);
+    Ok(expanded)
+}
+
+impl TextParser {
+    /// Expand and compile the line patterns.
+    pub fn compile(&self) -> Result<CompiledParser, SiemError> {
+        if self.patterns.is_empty() {
+            return Err(SiemError::Rule(format!(
+                "Parser '{}' has no line patterns",
+                self.name
+            )));
+        }
+
+        let patterns = self
+            .patterns
+            .iter()
+            .map(|pattern| {
+                let mut captures = Vec::new();
+                let expanded = expand_grok(pattern, &self.custom_patterns, &mut captures, 0)?;
+                let regex = regex::RegexBuilder::new(&expanded)
+                    .size_limit(COMPILED_SIZE_LIMIT)
+                    .build()
+                    .map_err(|e| {
+                        SiemError::Rule(format!("Invalid pattern '{}': {}", pattern, e))
+                    })?;
+
+                // Named captures written as raw regex fill the field of their name
+                let grok_groups: Vec<String> = captures.iter().map(|c| c.group.clone()).collect();
+                for name in regex.capture_names().flatten() {
+                    if !grok_groups.iter().any(|group| group == name) {
+                        captures.push(Capture {
+                            group: name.to_string(),
+                            field: name.to_string(),
+                            kind: FieldKind::Text,
+                        });
+                    }
+                }
+                Ok(LinePattern { regex, captures })
+            })
+            .collect::<Result<Vec<_>, SiemError>>()?;
+
+        Ok(CompiledParser {
+            patterns,
+            keep_unmatched: self.keep_unmatched,
+        })
+    }
+
+    /// Whether the parser reads a file, by its name.
+    pub fn reads_file(&self, file_name: &str) -> bool {
+        self.file_patterns
+            .iter()
+            .any(|pattern| wildcard_match(&pattern.to_lowercase(), &file_name.to_lowercase()))
+    }
+}
+
+/// Match a name against a pattern with `*` (any run) and `?` (one character).
+pub fn wildcard_match(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    let (mut p, mut n) = (0, 0);
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while n < name.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == name[n]) {
+            p += 1;
+            n += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            backtrack = Some((p, n));
+            p += 1;
+        } else if let Some((star, matched)) = backtrack {
+            // Let the last `*` absorb one more character
+            p = star + 1;
+            n = matched + 1;
+            backtrack = Some((star, matched + 1));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+/// Set `value` at a dotted path of an object, creating intermediate objects.
+fn insert_field(
+    event: &mut serde_json::Map<String, serde_json::Value>,
+    path: &str,
+    value: serde_json::Value,
+) {
+    match path.split_once('.') {
+        Some((head, rest)) => {
+            let child = event
+                .entry(head.to_string())
+                .or_insert_with(|| serde_json::Value::Object(serde_json::Map::new()));
+            if let serde_json::Value::Object(child) = child {
+                insert_field(child, rest, value);
+            }
+        }
+        None => {
+            event.insert(path.to_string(), value);
+        }
+    }
+}
+
+impl CompiledParser {
+    /// Turn a line into an event, or None if no pattern matches (and unmatched
+    /// lines aren't kept).
+    pub fn parse_line(&self, line: &str) -> Option<serde_json::Value> {
+        let mut event = serde_json::Map::new();
+        event.insert("message".to_string(), serde_json::Value::from(line));
+
+        let Some((pattern, captures)) = self
+            .patterns
+            .iter()
+            .find_map(|pattern| pattern.regex.captures(line).map(|c| (pattern, c)))
+        else {
+            return self
+                .keep_unmatched
+                .then_some(serde_json::Value::Object(event));
+        };
+
+        for capture in &pattern.captures {
+            let Some(matched) = captures.name(&capture.group) else {
+                continue;
+            };
+            let text = matched.as_str();
+            let value = match capture.kind {
+                FieldKind::Int => text.parse::<i64>().map(serde_json::Value::from).ok(),
+                FieldKind::Float => text
+                    .parse::<f64>()
+                    .ok()
+                    .and_then(|f| serde_json::Number::from_f64(f).map(serde_json::Value::Number)),
+                FieldKind::Text => None,
+            }
+            .unwrap_or_else(|| serde_json::Value::from(text));
+            insert_field(&mut event, &capture.field, value);
+        }
+        Some(serde_json::Value::Object(event))
+    }
+
+    /// Read the lines of a file in chunks of at most `chunk_size` events.
+    /// Returns the number of events.
+    pub fn stream_events<R, F>(
+        &self,
+        reader: R,
+        chunk_size: usize,
+        on_chunk: &mut F,
+    ) -> Result<usize, SiemError>
+    where
+        R: BufRead,
+        F: FnMut(Vec<serde_json::Value>),
+    {
+        let mut chunk = Vec::new();
+        let mut total = 0;
+        for line in reader.lines() {
+            let line =
+                line.map_err(|e| SiemError::Query(format!("Failed to read log file: {}", e)))?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            if let Some(event) = self.parse_line(&line) {
+                chunk.push(event);
+                total += 1;
+                if chunk.len() >= chunk_size {
+                    on_chunk(std::mem::take(&mut chunk));
+                }
+            }
+        }
+        if !chunk.is_empty() {
+            on_chunk(chunk);
+        }
+        Ok(total)
+    }
+}
+
+/// Get the path to the text parsers file.
+pub fn get_parsers_path(app_handle: &tauri::AppHandle) -> Result<PathBuf, SiemError> {
+    app_data::app_data_file(app_handle, "text_parsers.json")
+}
+
+/// Parsers file used when reading logs (set at startup).
+fn parsers_file() -> &'static Mutex<Option<PathBuf>> {
+    static FILE: OnceLock<Mutex<Option<PathBuf>>> = OnceLock::new();
+    FILE.get_or_init(|| Mutex::new(None))
+}
+
+/// Read logs with the parsers of a parsers file.
+pub fn register_parsers_file(path: PathBuf) {
+    if let Ok(mut file) = parsers_file().lock() {
+        *file = Some(path);
+    }
+}
+
+fn load_parsers_from(path: &std::path::Path) -> Result<Vec<TextParser>, SiemError> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let content = fs::read_to_string(path)
+        .map_err(|e| SiemError::FileIO(format!("Cannot read text parsers: {}", e)))?;
+
+    serde_json::from_str(&content)
+        .map_err(|e| SiemError::Serialization(format!("Cannot parse text parsers: {}", e)))
+}
+
+/// Parsers of the registered parsers file.
+fn registered_parsers() -> Vec<TextParser> {
+    let path = parsers_file().lock().ok().and_then(|file| file.clone());
+    path.and_then(|path| load_parsers_from(&path).ok())
+        .unwrap_or_default()
+}
+
+/// Load all text parsers.
+pub fn load_parsers(app_handle: &tauri::AppHandle) -> Result<Vec<TextParser>, SiemError> {
+    load_parsers_from(&get_parsers_path(app_handle)?)
+}
+
+fn save_parsers(app_handle: &tauri::AppHandle, parsers: &[TextParser]) -> Result<(), SiemError> {
+    let path = get_parsers_path(app_handle)?;
+
+    let content = serde_json::to_string_pretty(parsers)
+        .map_err(|e| SiemError::Serialization(format!("Cannot serialize text parsers: {}", e)))?;
+
+    fs::write(&path, content)
+        .map_err(|e| SiemError::FileIO(format!("Cannot write text parsers: {}", e)))?;
+
+    Ok(())
+}
+
+/// Save a parser (create, or replace the one with the same name) after
+/// checking that its patterns compile.
+pub fn save_parser(
+    app_handle: &tauri::AppHandle,
+    parser: TextParser,
+) -> Result<TextParser, SiemError> {
+    crate::parser_plugins::validate_plugin_name(&parser.name)?;
+    parser.compile()?;
+
+    let mut parsers = load_parsers(app_handle)?;
+    match parsers.iter_mut().find(|p| p.name == parser.name) {
+        Some(existing) => *existing = parser.clone(),
+        None => parsers.push(parser.clone()),
+    }
+    save_parsers(app_handle, &parsers)?;
+    Ok(parser)
+}
+
+/// Delete a parser by name.
+pub fn delete_parser(app_handle: &tauri::AppHandle, name: &str) -> Result<(), SiemError> {
+    let mut parsers = load_parsers(app_handle)?;
+    let before = parsers.len();
+    parsers.retain(|p| p.name != name);
+    if parsers.len() == before {
+        return Err(SiemError::FileIO(format!(
+            "Text parser not found: {}",
+            name
+        )));
+    }
+    save_parsers(app_handle, &parsers)
+}
+
+/// Compile a registered parser by name.
+pub fn compiled_parser(name: &str) -> Result<CompiledParser, SiemError> {
+    registered_parsers()
+        .into_iter()
+        .find(|parser| parser.name == name)
+        .ok_or_else(|| SiemError::Query(format!("Text parser not found: {}", name)))?
+        .compile()
+}
+
+/// Log type of a file read by a text parser, from its file name.
+pub fn log_type_for_file(file_name: &str) -> Option<LogType> {
+    let file_name = std::path::Path::new(file_name)
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())?;
+    registered_parsers()
+        .into_iter()
+        .find(|parser| parser.reads_file(&file_name))
+        .map(|parser| LogType::Text(parser.name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_grok_and_regex_patterns() {
+        let parser = TextParser {
+            name: "auth".to_string(),
+            file_patterns: vec!["auth.log*".to_string(), "*.secure".to_string()],
+            patterns: vec![
+                r"%{SYSLOGTIMESTAMP:timestamp} %{HOSTNAME:host} %{SYSLOGPROG}: Failed password for %{USER:user.name} from %{IP:source.ip} port %{INT:source.port:int}".to_string(),
+                r"^(?P<level>[A-Z]+) %{GREEDYDATA:text}$".to_string(),
+            ],
+            custom_patterns: BTreeMap::new(),
+            keep_unmatched: false,
+        };
+        let compiled = parser.compile().unwrap();
+
+        let event = compiled
+            .parse_line("Mar  3 10:01:02 web01 sshd[4242]: Failed password for root from 10.0.0.5 port 52114 ssh2")
+            .unwrap();
+        assert_eq!(event["timestamp"], "Mar  3 10:01:02");
+        assert_eq!(event["program"], "sshd");
+        assert_eq!(event["pid"], 4242);
+        assert_eq!(event["user"]["name"], "root");
+        assert_eq!(event["source"]["ip"], "10.0.0.5");
+        assert_eq!(event["source"]["port"], 52114);
+
+        let event = compiled.parse_line("WARN disk almost full").unwrap();
+        assert_eq!(event["level"], "WARN");
+        assert_eq!(event["text"], "disk almost full");
+        assert_eq!(event["message"], "WARN disk almost full");
+
+        assert!(compiled.parse_line("unrelated line").is_none());
+
+        assert!(parser.reads_file("auth.log.1"));
+        assert!(parser.reads_file("HOST.SECURE"));
+        assert!(!parser.reads_file("syslog"));
+
+        let unknown = TextParser {
+            patterns: vec!["%{NOPE:x}".to_string()],
+            ..parser
+        };
+        assert!(unknown.compile().is_err());
+    }
+}
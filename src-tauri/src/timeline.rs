@@ -0,0 +1,153 @@
+//! Event timeline histograms.
+//!
+//! Counts the events of a log file per minute, hour or day (UTC) so the UI can
+//! draw an activity timeline and bursts stand out. The timestamp field is the
+//! first of the log type's standard fields found in the events (see
+//! `event_time`), and an optional condition restricts the events counted.
+
+use chrono::{DateTime, TimeZone, Utc};
+use std::collections::BTreeMap;
+
+use crate::db_engine;
+use crate::event_time;
+use crate::models::{EventTimeline, LogType, TimelineBucket, TimelineCount};
+
+impl TimelineBucket {
+    /// Bucket width in seconds.
+    fn seconds(self) -> i64 {
+        match self {
+            TimelineBucket::Minute => 60,
+            TimelineBucket::Hour => 3600,
+            TimelineBucket::Day => 86400,
+        }
+    }
+}
+
+/// Start of the bucket containing a timestamp.
+fn bucket_start(ts: DateTime<Utc>, bucket: TimelineBucket) -> i64 {
+    let width = bucket.seconds();
+    ts.timestamp().div_euclid(width) * width
+}
+
+/// Accumulates the timeline of a stream of events.
+pub struct TimelineBuilder<'a> {
+    log_type: &'a LogType,
+    bucket: TimelineBucket,
+    filter: Option<&'a str>,
+    timestamp_field: Option<&'static str>,
+    counts: BTreeMap<i64, usize>,
+    total_events: usize,
+    matched_events: usize,
+    untimed_events: usize,
+}
+
+impl<'a> TimelineBuilder<'a> {
+    pub fn new(log_type: &'a LogType, bucket: TimelineBucket, filter: Option<&'a str>) -> Self {
+        TimelineBuilder {
+            log_type,
+            bucket,
+            filter: filter.filter(|f| !f.trim().is_empty()),
+            timestamp_field: None,
+            counts: BTreeMap::new(),
+            total_events: 0,
+            matched_events: 0,
+            untimed_events: 0,
+        }
+    }
+
+    /// Count an event.
+    pub fn observe(&mut self, event: &serde_json::Value) {
+        self.total_events += 1;
+        if self
+            .filter
+            .is_some_and(|filter| !db_engine::matches_condition(event, filter))
+        {
+            return;
+        }
+        self.matched_events += 1;
+
+        // The field is detected on the first event that has one and then
+        // kept, so all buckets count the same field
+        if self.timestamp_field.is_none() {
+            self.timestamp_field = event_time::detect_timestamp_field(event, self.log_type);
+        }
+        let ts = self
+            .timestamp_field
+            .and_then(|field| event_time::event_time(event, Some(field), self.log_type));
+        match ts {
+            Some(ts) => {
+                *self
+                    .counts
+                    .entry(bucket_start(ts, self.bucket))
+                    .or_insert(0) += 1
+            }
+            None => self.untimed_events += 1,
+        }
+    }
+
+    pub fn finish(self) -> EventTimeline {
+        let bucket = self.bucket;
+        EventTimeline {
+            bucket,
+            timestamp_field: self.timestamp_field.map(str::to_string),
+            buckets: self
+                .counts
+                .into_iter()
+                .filter_map(|(start, count)| {
+                    let start = Utc.timestamp_opt(start, 0).single()?;
+                    Some(TimelineCount {
+                        start: start.to_rfc3339(),
+                        count,
+                    })
+                })
+                .collect(),
+            total_events: self.total_events,
+            matched_events: self.matched_events,
+            untimed_events: self.untimed_events,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_timeline_buckets() {
+        let events = [
+            serde_json::json!({ "eventTime": "2025-12-16T11:05:10Z", "eventName": "ConsoleLogin" }),
+            serde_json::json!({ "eventTime": "2025-12-16T11:05:50Z", "eventName": "ConsoleLogin" }),
+            serde_json::json!({ "eventTime": "2025-12-16T11:42:00Z", "eventName": "ConsoleLogin" }),
+            serde_json::json!({ "eventTime": "2025-12-16T13:00:00Z", "eventName": "GetObject" }),
+            serde_json::json!({ "eventName": "ConsoleLogin" }),
+        ];
+
+        let mut builder = TimelineBuilder::new(
+            &LogType::CloudTrail,
+            TimelineBucket::Hour,
+            Some("eventName = 'ConsoleLogin'"),
+        );
+        events.iter().for_each(|event| builder.observe(event));
+        let timeline = builder.finish();
+
+        assert_eq!(timeline.timestamp_field.as_deref(), Some("eventTime"));
+        assert_eq!(timeline.total_events, 5);
+        assert_eq!(timeline.matched_events, 4);
+        assert_eq!(timeline.untimed_events, 1);
+        let counts: Vec<(&str, usize)> = timeline
+            .buckets
+            .iter()
+            .map(|b| (b.start.as_str(), b.count))
+            .collect();
+        assert_eq!(counts, vec![("2025-12-16T11:00:00+00:00", 3)]);
+
+        // Minute buckets, no filter, flat JSON with an epoch timestamp field
+        let mut builder = TimelineBuilder::new(&LogType::FlatJson, TimelineBucket::Minute, None);
+        builder.observe(&serde_json::json!({ "ts": 1765883110 }));
+        builder.observe(&serde_json::json!({ "ts": 1765883170 }));
+        let timeline = builder.finish();
+        assert_eq!(timeline.timestamp_field.as_deref(), Some("ts"));
+        assert_eq!(timeline.buckets.len(), 2);
+        assert_eq!(timeline.buckets[0].start, "2025-12-16T11:05:00+00:00");
+    }
+}
@@ -0,0 +1,350 @@
+//! Triage collection import: Velociraptor offline collections (zip) and KAPE
+//! output folders.
+//!
+//! A collection holds many artifacts; the JSON and CSV ones holding logs are
+//! imported into the logs folder, each as its own log file named after the
+//! collection and the artifact:
+//!
+//! - Velociraptor writes the rows of each artifact to `results/<Artifact>.json`
+//!   (JSON lines), and to `results/<Artifact>.csv` when asked to; the JSON is
+//!   preferred. Collection metadata (`log.json`, `requests.json`, ...) and
+//!   `uploads/` (raw files such as .evtx) are not artifacts.
+//! - KAPE modules write CSV (or JSON) named after their tool, such as
+//!   `20240301120000_EvtxECmd_Output.csv`; the output of the tools in
+//!   `KAPE_OUTPUTS` is imported.
+//!
+//! JSON artifacts get their log type by detection (`db_engine::detect_log_type`),
+//! so e.g. Windows event exports are imported as `Windows`. CSV rows are
+//! converted to NDJSON objects keyed by the header and imported as flat JSON.
+//! Empty artifacts are skipped.
+
+use serde_json::{Map, Value};
+use std::fs;
+use std::io::{BufRead, BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+
+use crate::db_engine;
+use crate::log_hashes;
+use crate::log_manager;
+use crate::models::{ImportSummary, LogFileInfo, LogType, SiemError};
+use crate::text_parser;
+
+/// File names (lowercase, with `*` wildcards) of KAPE module output.
+const KAPE_OUTPUTS: &[&str] = &[
+    "*evtxecmd*",
+    "*mftecmd*",
+    "*pecmd*",
+    "*lecmd*",
+    "*recmd*",
+    "*sbecmd*",
+    "*srumecmd*",
+    "*sumecmd*",
+    "*rbcmd*",
+    "*wxtcmd*",
+    "*amcacheparser*",
+    "*appcompatcacheparser*",
+    "*hayabusa*",
+    "*chainsaw*",
+];
+
+/// Whether a path in a collection (with `/` or `\` separators) is a log
+/// artifact.
+fn is_artifact(path: &str) -> bool {
+    let path = path.replace('\\', "/").to_lowercase();
+    let mut segments: Vec<&str> = path.split('/').collect();
+    let name = segments.pop().unwrap_or_default();
+
+    let is_log_file = [".json", ".jsonl", ".csv"]
+        .iter()
+        .any(|ext| name.ends_with(ext));
+    if !is_log_file || segments.contains(&"uploads") {
+        return false;
+    }
+    segments.contains(&"results")
+        || KAPE_OUTPUTS
+            .iter()
+            .any(|pattern| text_parser::wildcard_match(pattern, name))
+}
+
+/// The artifacts among the paths of a collection, without the CSV copy of an
+/// artifact also written as JSON.
+pub fn select_artifacts(paths: &[String]) -> Vec<String> {
+    paths
+        .iter()
+        .filter(|path| is_artifact(path))
+        .filter(|path| match path.strip_suffix(".csv") {
+            Some(stem) => !paths.contains(&format!("{}.json", stem)),
+            None => true,
+        })
+        .cloned()
+        .collect()
+}
+
+/// Split a CSV record into its fields (RFC 4180: quoted fields may hold
+/// commas, line breaks and doubled quotes).
+fn split_csv_record(record: &str) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = record.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(std::mem::take(&mut field)),
+            _ => field.push(c),
+        }
+    }
+    fields.push(field);
+    fields
+}
+
+/// Convert CSV with a header line to NDJSON, one object per row. Empty cells
+/// are left out (the EZ tools write many). Returns the number of rows.
+pub fn csv_to_ndjson<R: BufRead, W: Write>(reader: R, mut writer: W) -> Result<usize, SiemError> {
+    let write_error = |e: std::io::Error| SiemError::FileIO(format!("Cannot write events: {}", e));
+    let mut header: Option<Vec<String>> = None;
+    let mut record = String::new();
+    let mut rows = 0;
+
+    for line in reader.lines() {
+        let line = line.map_err(|e| SiemError::FileIO(format!("Cannot read CSV: {}", e)))?;
+        if !record.is_empty() {
+            record.push('\n');
+        }
+        record.push_str(&line);
+        // A record goes on over the next line while a quoted field is open
+        if record.matches('"').count() % 2 == 1 {
+            continue;
+        }
+
+        let fields = split_csv_record(record.trim_start_matches('\u{feff}'));
+        record.clear();
+        let Some(names) = &header else {
+            header = Some(fields);
+            continue;
+        };
+        if fields.iter().all(|field| field.is_empty()) {
+            continue;
+        }
+
+        let row: Map<String, Value> = names
+            .iter()
+            .zip(fields)
+            .filter(|(_, value)| !value.is_empty())
+            .map(|(name, value)| (name.clone(), Value::from(value)))
+            .collect();
+        serde_json::to_writer(&mut writer, &row)
+            .map_err(|e| SiemError::Serialization(e.to_string()))?;
+        writeln!(writer).map_err(write_error)?;
+        rows += 1;
+    }
+    writer.flush().map_err(write_error)?;
+    Ok(rows)
+}
+
+/// A name made of letters, digits, `.`, `-` and `_` only.
+pub fn file_safe(name: &str) -> String {
+    name.replace("%2F", "_")
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+/// Import one artifact as `<collection>_<artifact>.json`. Returns None for an
+/// empty artifact.
+fn import_artifact(
+    app_handle: &tauri::AppHandle,
+    collection: &str,
+    path: &str,
+    reader: &mut dyn Read,
+) -> Result<Option<LogFileInfo>, SiemError> {
+    let name = path.rsplit(['/', '\\']).next().unwrap_or(path);
+    let (stem, extension) = name.rsplit_once('.').unwrap_or((name, ""));
+    let is_csv = extension.eq_ignore_ascii_case("csv");
+
+    let filename = format!("{}_{}.json", collection, file_safe(stem));
+    let dest_path = log_manager::get_logs_dir(app_handle)?.join(&filename);
+    if dest_path.exists() {
+        return Err(SiemError::FileIO(format!(
+            "File already exists in logs folder: {}",
+            filename
+        )));
+    }
+
+    let file = fs::File::create(&dest_path)
+        .map_err(|e| SiemError::FileIO(format!("Cannot create log file: {}", e)))?;
+    let written = if is_csv {
+        csv_to_ndjson(BufReader::new(reader), BufWriter::new(file))
+    } else {
+        let mut writer = BufWriter::new(file);
+        std::io::copy(reader, &mut writer)
+            .and_then(|bytes| writer.flush().map(|_| bytes as usize))
+            .map_err(|e| SiemError::FileIO(format!("Cannot copy artifact: {}", e)))
+    };
+    match written {
+        Ok(0) => {
+            let _ = fs::remove_file(&dest_path);
+            return Ok(None);
+        }
+        Ok(_) => {}
+        Err(e) => {
+            let _ = fs::remove_file(&dest_path);
+            return Err(e);
+        }
+    }
+
+    let log_type = if is_csv {
+        LogType::FlatJson
+    } else {
+        db_engine::detect_log_type(&dest_path.to_string_lossy()).unwrap_or(LogType::FlatJson)
+    };
+    log_manager::set_log_type(app_handle, &filename, log_type.clone())?;
+
+    let mut info = log_manager::get_log_file_info(&dest_path)?;
+    info.log_type = Some(log_type);
+    info.sha256 = Some(log_hashes::record_hash(app_handle, &filename, &dest_path)?);
+    Ok(Some(info))
+}
+
+/// Paths of the files under a folder, relative to it.
+fn walk(root: &Path, dir: &Path, paths: &mut Vec<String>) -> Result<(), SiemError> {
+    let entries = fs::read_dir(dir)
+        .map_err(|e| SiemError::FileIO(format!("Cannot read folder {:?}: {}", dir, e)))?;
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            walk(root, &path, paths)?;
+        } else if let Ok(relative) = path.strip_prefix(root) {
+            paths.push(relative.to_string_lossy().replace('\\', "/"));
+        }
+    }
+    Ok(())
+}
+
+/// Import the artifacts of a Velociraptor collection zip or a KAPE output
+/// folder as log files.
+pub fn import_collection(
+    app_handle: &tauri::AppHandle,
+    source_path: &str,
+) -> Result<ImportSummary, SiemError> {
+    let source = PathBuf::from(source_path);
+    if !source.exists() {
+        return Err(SiemError::FileIO(format!(
+            "Source does not exist: {}",
+            source_path
+        )));
+    }
+    let collection = file_safe(
+        &source
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or_else(|| "collection".to_string()),
+    );
+
+    let mut results = Vec::new();
+    if source.is_dir() {
+        let mut paths = Vec::new();
+        walk(&source, &source, &mut paths)?;
+        for path in select_artifacts(&paths) {
+            let result = fs::File::open(source.join(&path))
+                .map_err(|e| SiemError::FileIO(format!("Cannot open artifact: {}", e)))
+                .and_then(|mut file| import_artifact(app_handle, &collection, &path, &mut file));
+            results.push((path, result));
+        }
+    } else {
+        let file = fs::File::open(&source)
+            .map_err(|e| SiemError::FileIO(format!("Cannot open collection: {}", e)))?;
+        let mut archive = zip::ZipArchive::new(file)
+            .map_err(|e| SiemError::FileIO(format!("Cannot read ZIP archive: {}", e)))?;
+        let paths: Vec<String> = archive.file_names().map(String::from).collect();
+        for path in select_artifacts(&paths) {
+            let result = archive
+                .by_name(&path)
+                .map_err(|e| SiemError::FileIO(format!("Cannot read ZIP entry: {}", e)))
+                .and_then(|mut entry| import_artifact(app_handle, &collection, &path, &mut entry));
+            results.push((path, result));
+        }
+    }
+
+    let mut summary = ImportSummary {
+        total: 0,
+        succeeded: 0,
+        failed: 0,
+        imported_files: Vec::new(),
+        errors: Vec::new(),
+    };
+    for (path, result) in results {
+        match result {
+            Ok(Some(info)) => {
+                summary.succeeded += 1;
+                summary.imported_files.push(info);
+            }
+            Ok(None) => continue,
+            Err(e) => {
+                summary.failed += 1;
+                summary.errors.push(format!("{}: {}", path, e));
+            }
+        }
+        summary.total += 1;
+    }
+
+    if summary.total == 0 {
+        return Err(SiemError::FileIO(
+            "No log artifacts (Velociraptor results, KAPE module output) found in the collection"
+                .to_string(),
+        ));
+    }
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_select_artifacts_and_convert_csv() {
+        let paths: Vec<String> = [
+            "log.json",
+            "requests.json",
+            "results/Windows.System.Pslist.json",
+            "results/Windows.System.Pslist.csv",
+            "results/Windows.Network.Netstat.csv",
+            "results/Windows.EventLogs.Evtx.json.index",
+            "uploads/auto/C%3A/Windows/System32/winevt/Logs/Security.evtx",
+            "EventLogs/20240301120000_EvtxECmd_Output.csv",
+            "C/Users/alice/AppData/settings.json",
+        ]
+        .iter()
+        .map(|path| path.to_string())
+        .collect();
+        assert_eq!(
+            select_artifacts(&paths),
+            vec![
+                "results/Windows.System.Pslist.json",
+                "results/Windows.Network.Netstat.csv",
+                "EventLogs/20240301120000_EvtxECmd_Output.csv",
+            ]
+        );
+
+        let csv =
+            "\u{feff}EventId,Channel,Payload,UserName\n4688,Security,\"{\"\"a\"\"\n: 1}, x\",\n\n";
+        let mut out = Vec::new();
+        assert_eq!(csv_to_ndjson(csv.as_bytes(), &mut out).unwrap(), 1);
+        let row: Value = serde_json::from_slice(&out).unwrap();
+        assert_eq!(
+            row,
+            serde_json::json!({ "EventId": "4688", "Channel": "Security", "Payload": "{\"a\"\n: 1}, x" })
+        );
+    }
+}
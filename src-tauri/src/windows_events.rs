@@ -0,0 +1,399 @@
+//! Windows event profile for Sysmon and Security logs (`LogType::Windows`).
+//!
+//! Windows events are exported to JSON in several shapes: `evtx_dump`
+//! (`{"Event": {"System": {...}, "EventData": {...}}}`), Winlogbeat
+//! (`{"winlog": {"event_id", "channel", "event_data": {...}}}`),
+//! `Get-WinEvent | ConvertTo-Json` arrays and flat exports. Events of this
+//! log type are normalized to the flat shape Sigma rules are written
+//! against:
+//!
+//! - `System` and `EventData` fields become top-level fields (`EventID`,
+//!   `Channel`, `Computer`, `TimeCreated`, `Image`, `CommandLine`, ...);
+//! - the EventID is routed to the Sigma logsource category of the event
+//!   (`category`: `process_creation` for Sysmon 1 and Security 4688,
+//!   `network_connection` for Sysmon 3, ...), so a Sigma process-creation
+//!   rule becomes `category = 'process_creation' AND ...`;
+//! - Sysmon's `Hashes` (`SHA1=...,MD5=...,IMPHASH=...`) is split into `md5`,
+//!   `sha1`, `sha256` and `Imphash`;
+//! - Security 4688 process fields get their Sysmon names (`Image`,
+//!   `ParentImage`, `ProcessId`, `ParentProcessId`), so the same rules cover
+//!   both sources.
+//!
+//! Fields an event already has are never overwritten, except the 4688
+//! `ProcessId` (the creator process there, moved to `ParentProcessId`).
+
+use serde_json::{Map, Value};
+
+/// Sigma logsource category of each Sysmon event ID.
+const SYSMON_CATEGORIES: &[(u64, &str)] = &[
+    (1, "process_creation"),
+    (2, "file_change"),
+    (3, "network_connection"),
+    (5, "process_termination"),
+    (6, "driver_load"),
+    (7, "image_load"),
+    (8, "create_remote_thread"),
+    (9, "raw_access_thread"),
+    (10, "process_access"),
+    (11, "file_event"),
+    (12, "registry_add"),
+    (13, "registry_set"),
+    (14, "registry_rename"),
+    (15, "create_stream_hash"),
+    (17, "pipe_created"),
+    (18, "pipe_created"),
+    (19, "wmi_event"),
+    (20, "wmi_event"),
+    (21, "wmi_event"),
+    (22, "dns_query"),
+    (23, "file_delete"),
+    (25, "process_tampering"),
+    (26, "file_delete"),
+];
+
+/// Sigma logsource category of each Security event ID.
+const SECURITY_CATEGORIES: &[(u64, &str)] = &[(4688, "process_creation")];
+
+/// Sysmon names of the Security 4688 process fields.
+const PROCESS_CREATION_ALIASES: &[(&str, &str)] = &[
+    ("NewProcessName", "Image"),
+    ("ParentProcessName", "ParentImage"),
+    ("SubjectUserName", "User"),
+    ("MandatoryLabel", "IntegrityLevel"),
+];
+
+/// Fields of normalized Sysmon and Security events.
+pub const FIELDS: &[&str] = &[
+    "EventID",
+    "category",
+    "Channel",
+    "Computer",
+    "TimeCreated",
+    "Provider_Name",
+    "Image",
+    "CommandLine",
+    "ParentImage",
+    "ParentCommandLine",
+    "OriginalFileName",
+    "CurrentDirectory",
+    "User",
+    "IntegrityLevel",
+    "ProcessId",
+    "ParentProcessId",
+    "ProcessGuid",
+    "ParentProcessGuid",
+    "Hashes",
+    "md5",
+    "sha1",
+    "sha256",
+    "Imphash",
+    "Company",
+    "Product",
+    "Description",
+    "TargetFilename",
+    "TargetObject",
+    "Details",
+    "EventType",
+    "ImageLoaded",
+    "Signed",
+    "Signature",
+    "SourceImage",
+    "TargetImage",
+    "GrantedAccess",
+    "CallTrace",
+    "StartAddress",
+    "DestinationIp",
+    "DestinationPort",
+    "DestinationHostname",
+    "SourceIp",
+    "SourcePort",
+    "Initiated",
+    "Protocol",
+    "QueryName",
+    "QueryResults",
+    "PipeName",
+    "SubjectUserName",
+    "SubjectDomainName",
+    "TargetUserName",
+    "TargetDomainName",
+    "LogonType",
+    "IpAddress",
+    "WorkstationName",
+    "ServiceName",
+    "ImagePath",
+    "TaskName",
+    "NewProcessName",
+    "ParentProcessName",
+];
+
+/// A field's value without the `#text`/`Value` wrapper of XML conversions.
+fn text(value: &Value) -> &Value {
+    value
+        .get("#text")
+        .or_else(|| value.get("Value"))
+        .unwrap_or(value)
+}
+
+/// Copy fields to the top level, keeping those already there.
+fn lift(event: &mut Map<String, Value>, fields: &Map<String, Value>) {
+    for (key, value) in fields {
+        if !event.contains_key(key) {
+            event.insert(key.clone(), text(value).clone());
+        }
+    }
+}
+
+/// Copy the `EventData` of an event: a map of fields, or the
+/// `{"Data": [{"@Name": "Image", "#text": "..."}]}` XML conversion.
+fn lift_event_data(event: &mut Map<String, Value>, data: &Value) {
+    let named = data.get("Data").and_then(|d| d.as_array());
+    match (named, data.as_object()) {
+        (Some(items), _) => {
+            for item in items {
+                let name = item
+                    .get("@Name")
+                    .or_else(|| item.get("Name"))
+                    .and_then(|n| n.as_str());
+                if let Some(name) = name {
+                    event
+                        .entry(name.to_string())
+                        .or_insert_with(|| text(item).clone());
+                }
+            }
+        }
+        (None, Some(fields)) => lift(event, fields),
+        (None, None) => {}
+    }
+}
+
+/// Flatten the evtx_dump / Get-WinEvent shape (`Event.System`,
+/// `Event.EventData`) and the Winlogbeat shape (`winlog.*`).
+fn flatten(event: &mut Map<String, Value>) {
+    if let Some(Value::Object(inner)) = event.remove("Event") {
+        for (key, value) in inner {
+            event.entry(key).or_insert(value);
+        }
+    }
+
+    if let Some(Value::Object(system)) = event.remove("System") {
+        for (key, value) in &system {
+            let value = match key.as_str() {
+                // <Provider Name="..."/> and <TimeCreated SystemTime="..."/>
+                "Provider" => {
+                    let name = value
+                        .pointer("/#attributes/Name")
+                        .or_else(|| value.get("Name"));
+                    if let Some(name) = name {
+                        event
+                            .entry("Provider_Name".to_string())
+                            .or_insert(name.clone());
+                    }
+                    continue;
+                }
+                "TimeCreated" => value
+                    .pointer("/#attributes/SystemTime")
+                    .or_else(|| value.get("SystemTime"))
+                    .unwrap_or(value),
+                _ => text(value),
+            };
+            event.entry(key.clone()).or_insert_with(|| value.clone());
+        }
+    }
+    if let Some(data) = event.remove("EventData") {
+        lift_event_data(event, &data);
+    }
+    if let Some(data) = event.remove("UserData") {
+        // UserData holds one element named after the event
+        if let Some(inner) = data.as_object().and_then(|d| d.values().next()) {
+            lift_event_data(event, inner);
+        }
+    }
+
+    if let Some(Value::Object(winlog)) = event.get("winlog").cloned() {
+        let renames = [
+            ("event_id", "EventID"),
+            ("channel", "Channel"),
+            ("computer_name", "Computer"),
+            ("provider_name", "Provider_Name"),
+            ("record_id", "EventRecordID"),
+        ];
+        for (from, to) in renames {
+            if let Some(value) = winlog.get(from) {
+                event.entry(to.to_string()).or_insert(value.clone());
+            }
+        }
+        if let Some(Value::Object(data)) = winlog.get("event_data") {
+            lift(event, data);
+        }
+    }
+}
+
+/// The event ID of an event as a number (exports write it as a string too).
+fn event_id(event: &Map<String, Value>) -> Option<u64> {
+    let id = event.get("EventID")?;
+    id.as_u64()
+        .or_else(|| id.as_str().and_then(|s| s.trim().parse().ok()))
+}
+
+fn is_sysmon(event: &Map<String, Value>) -> bool {
+    let mentions_sysmon = |field: &str| {
+        event
+            .get(field)
+            .and_then(|v| v.as_str())
+            .is_some_and(|v| v.to_lowercase().contains("sysmon"))
+    };
+    mentions_sysmon("Channel") || mentions_sysmon("Provider_Name")
+}
+
+/// Split Sysmon's `Hashes` into one field per algorithm.
+fn split_hashes(event: &mut Map<String, Value>) {
+    let Some(hashes) = event
+        .get("Hashes")
+        .and_then(|h| h.as_str())
+        .map(str::to_string)
+    else {
+        return;
+    };
+    for pair in hashes.split(',') {
+        let Some((algorithm, hash)) = pair.split_once('=') else {
+            continue;
+        };
+        let field = match algorithm.trim().to_uppercase().as_str() {
+            "MD5" => "md5",
+            "SHA1" => "sha1",
+            "SHA256" => "sha256",
+            "IMPHASH" => "Imphash",
+            _ => continue,
+        };
+        event
+            .entry(field.to_string())
+            .or_insert_with(|| Value::from(hash.trim()));
+    }
+}
+
+/// Normalize a Windows event (see the module documentation).
+pub fn normalize(event: Value) -> Value {
+    let Value::Object(mut event) = event else {
+        return event;
+    };
+    flatten(&mut event);
+
+    let id = event_id(&event);
+    let categories = if is_sysmon(&event) {
+        SYSMON_CATEGORIES
+    } else {
+        SECURITY_CATEGORIES
+    };
+    let category = id.and_then(|id| {
+        categories
+            .iter()
+            .find(|(known, _)| *known == id)
+            .map(|(_, category)| *category)
+    });
+    // Sysmon 12 is both key creation and deletion
+    let category = match (category, event.get("EventType").and_then(|t| t.as_str())) {
+        (Some("registry_add"), Some(t)) if t.starts_with("Delete") => Some("registry_delete"),
+        (category, _) => category,
+    };
+    if let Some(category) = category {
+        event
+            .entry("category".to_string())
+            .or_insert_with(|| Value::from(category));
+    }
+
+    if id == Some(4688) && !is_sysmon(&event) {
+        if let Some(creator) = event.remove("ProcessId") {
+            event
+                .entry("ParentProcessId".to_string())
+                .or_insert(creator);
+        }
+        if let Some(new_process) = event.get("NewProcessId").cloned() {
+            event.insert("ProcessId".to_string(), new_process);
+        }
+        for (from, to) in PROCESS_CREATION_ALIASES {
+            if let Some(value) = event.get(*from).cloned() {
+                event.entry(to.to_string()).or_insert(value);
+            }
+        }
+    }
+
+    split_hashes(&mut event);
+    Value::Object(event)
+}
+
+/// Whether a record is a Windows event in one of the exported shapes.
+pub fn is_windows_event(event: &Value) -> bool {
+    event.pointer("/Event/System").is_some()
+        || event.pointer("/System/EventID").is_some()
+        || event.pointer("/winlog/event_id").is_some()
+        || (event.get("EventID").is_some()
+            && event.get("Channel").is_some()
+            && event.get("Computer").is_some())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_sysmon_and_security_events() {
+        let sysmon = normalize(serde_json::json!({
+            "Event": {
+                "System": {
+                    "EventID": 1,
+                    "Channel": "Microsoft-Windows-Sysmon/Operational",
+                    "Computer": "WS01",
+                    "Provider": { "#attributes": { "Name": "Microsoft-Windows-Sysmon" } },
+                    "TimeCreated": { "#attributes": { "SystemTime": "2024-03-01T10:01:02Z" } }
+                },
+                "EventData": {
+                    "Image": "C:\\Windows\\System32\\rundll32.exe",
+                    "ParentImage": "C:\\Windows\\explorer.exe",
+                    "Hashes": "SHA1=AA,MD5=BB,SHA256=CC,IMPHASH=DD"
+                }
+            }
+        }));
+        assert_eq!(sysmon["EventID"], 1);
+        assert_eq!(sysmon["category"], "process_creation");
+        assert_eq!(sysmon["TimeCreated"], "2024-03-01T10:01:02Z");
+        assert_eq!(sysmon["Provider_Name"], "Microsoft-Windows-Sysmon");
+        assert_eq!(sysmon["ParentImage"], "C:\\Windows\\explorer.exe");
+        assert_eq!(sysmon["md5"], "BB");
+        assert_eq!(sysmon["Imphash"], "DD");
+        assert!(sysmon.get("Event").is_none());
+
+        let security = normalize(serde_json::json!({
+            "winlog": {
+                "event_id": "4688",
+                "channel": "Security",
+                "event_data": {
+                    "NewProcessName": "C:\\Windows\\System32\\cmd.exe",
+                    "NewProcessId": "0x1a4",
+                    "ProcessId": "0x2b0",
+                    "ParentProcessName": "C:\\Windows\\System32\\wmiprvse.exe",
+                    "CommandLine": "cmd.exe /c whoami"
+                }
+            }
+        }));
+        assert_eq!(security["category"], "process_creation");
+        assert_eq!(security["Image"], "C:\\Windows\\System32\\cmd.exe");
+        assert_eq!(
+            security["ParentImage"],
+            "C:\\Windows\\System32\\wmiprvse.exe"
+        );
+        assert_eq!(security["ProcessId"], "0x1a4");
+        assert_eq!(security["ParentProcessId"], "0x2b0");
+
+        let xml = normalize(serde_json::json!({
+            "System": { "EventID": { "#text": 13 }, "Channel": "Microsoft-Windows-Sysmon/Operational" },
+            "EventData": { "Data": [ { "@Name": "TargetObject", "#text": "HKLM\\Run\\x" } ] }
+        }));
+        assert_eq!(xml["category"], "registry_set");
+        assert_eq!(xml["TargetObject"], "HKLM\\Run\\x");
+
+        assert!(is_windows_event(
+            &serde_json::json!({ "winlog": { "event_id": 1 } })
+        ));
+        assert!(!is_windows_event(&serde_json::json!({ "EventID": 1 })));
+    }
+}
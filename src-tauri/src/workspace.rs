@@ -0,0 +1,681 @@
+//! Point-in-time snapshots of the whole workspace.
+//!
+//! A snapshot is a ZIP archive holding:
+//! - `manifest.json`: snapshot metadata
+//! - `rules/`: every rule YAML file (from the effective rules directory)
+//! - `data/`: the application data directory (config, log metadata,
+//!   annotations, hash sets, ...), with raw log files only if requested and
+//!   never the ingestion cache, the workspace lock or the audit log
+//!
+//! Restoring replaces the current rules and data with the snapshot contents,
+//! so state created after the snapshot (e.g. a mass rule import) is rolled back.
+//! Raw log files are left untouched when the snapshot doesn't include them.
+//! The new contents are staged next to the current ones and swapped in at the
+//! end (see `StagedRestore`, shared with app data backups), so a restore that
+//! fails partway leaves the workspace as it was.
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::fs;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+
+use crate::app_data;
+use crate::audit_log;
+use crate::config;
+use crate::ingest_cache;
+use crate::models::SiemError;
+use crate::workspace_lock;
+
+/// Snapshot format version, bumped on incompatible layout changes.
+const SNAPSHOT_VERSION: u32 = 1;
+
+const MANIFEST_ENTRY: &str = "manifest.json";
+const RULES_PREFIX: &str = "rules/";
+const DATA_PREFIX: &str = "data/";
+
+/// Name of the log library directory inside the app data dir.
+const LOGS_DIR_NAME: &str = "logs";
+/// Log metadata file, always part of a snapshot.
+const LOG_METADATA_FILE: &str = "metadata.json";
+/// Log files registered in place, always part of a snapshot (unlike the
+/// files themselves).
+const LOG_REFERENCES_FILE: &str = "references.json";
+/// SHA-256 records of the log files, always part of a snapshot.
+const LOG_HASHES_FILE: &str = "hashes.json";
+
+/// Name prefix of the staging and set-aside folders of a restore.
+const RESTORE_DIR_PREFIX: &str = ".restore-";
+
+/// Metadata stored in a snapshot archive.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SnapshotManifest {
+    /// Snapshot format version
+    pub version: u32,
+    /// Creation timestamp (ISO 8601)
+    pub created_at: String,
+    /// Whether raw log files are included
+    pub includes_logs: bool,
+    /// Number of rule files
+    pub rule_count: usize,
+    /// Number of data files
+    pub data_file_count: usize,
+}
+
+/// Result of restoring a snapshot.
+#[derive(Debug, Serialize, Clone)]
+pub struct RestoreSummary {
+    /// Manifest of the restored snapshot
+    pub manifest: SnapshotManifest,
+    /// Number of files written
+    pub files_restored: usize,
+}
+
+/// Check whether a path relative to the app data dir is a raw log file.
+fn is_raw_log(relative: &Path) -> bool {
+    let mut components = relative.components();
+    let in_logs = components
+        .next()
+        .is_some_and(|c| c.as_os_str() == LOGS_DIR_NAME);
+    in_logs
+        && relative.file_name().is_some_and(|n| {
+            ![LOG_METADATA_FILE, LOG_REFERENCES_FILE, LOG_HASHES_FILE].contains(&n)
+        })
+}
+
+/// Check whether a path relative to the app data dir is state local to the
+/// running instance, never snapshotted nor replaced: the ingestion cache
+/// database (derived from the logs), the workspace lock, the append-only
+/// audit log and the folders of a restore in progress.
+fn is_local_state(relative: &Path) -> bool {
+    let in_restore = relative.components().next().is_some_and(|c| {
+        c.as_os_str()
+            .to_string_lossy()
+            .starts_with(RESTORE_DIR_PREFIX)
+    });
+    let instance_file = relative.parent() == Some(Path::new(""))
+        && relative.to_str().is_some_and(|name| {
+            name.starts_with(ingest_cache::CACHE_FILE)
+                || name == workspace_lock::LOCK_FILE
+                || name == audit_log::AUDIT_LOG_FILE
+        });
+    in_restore || instance_file
+}
+
+/// Recursively list files under `dir`, as paths relative to `base`.
+pub fn collect_files(base: &Path, dir: &Path, files: &mut Vec<PathBuf>) -> Result<(), SiemError> {
+    if !dir.exists() {
+        return Ok(());
+    }
+
+    let entries =
+        fs::read_dir(dir).map_err(|e| SiemError::FileIO(format!("Cannot read dir: {}", e)))?;
+
+    for entry in entries {
+        let entry = entry.map_err(|e| SiemError::FileIO(format!("Cannot read entry: {}", e)))?;
+        let path = entry.path();
+
+        if path.is_dir() {
+            collect_files(base, &path, files)?;
+        } else if let Ok(relative) = path.strip_prefix(base) {
+            files.push(relative.to_path_buf());
+        }
+    }
+
+    Ok(())
+}
+
+/// List the rule files in the rules directory.
+pub fn collect_rule_files(rules_dir: &Path) -> Result<Vec<PathBuf>, SiemError> {
+    let mut files = Vec::new();
+    collect_files(rules_dir, rules_dir, &mut files)?;
+    files.retain(|f| {
+        f.components().count() == 1
+            && f.extension()
+                .is_some_and(|ext| ext == "yaml" || ext == "yml")
+    });
+    Ok(files)
+}
+
+/// Convert a relative path to a ZIP entry name (always '/'-separated).
+pub fn entry_name(prefix: &str, relative: &Path) -> String {
+    let parts: Vec<String> = relative
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy().to_string())
+        .collect();
+    format!("{}{}", prefix, parts.join("/"))
+}
+
+/// Create a snapshot of rules, configuration and workspace data.
+pub fn create_snapshot(
+    app_handle: &tauri::AppHandle,
+    dest_path: &str,
+    include_logs: bool,
+) -> Result<SnapshotManifest, SiemError> {
+    use zip::write::FileOptions;
+
+    let data_dir = app_data::app_data_dir(app_handle)?;
+    let rules_dir = config::get_rules_directory(app_handle)?;
+
+    let rule_files = collect_rule_files(&rules_dir)?;
+
+    let mut data_files = Vec::new();
+    collect_files(&data_dir, &data_dir, &mut data_files)?;
+    // Rules inside the app data dir are stored under rules/ instead
+    if let Ok(rules_relative) = rules_dir.strip_prefix(&data_dir) {
+        data_files.retain(|f| !f.starts_with(rules_relative));
+    }
+    data_files.retain(|f| !is_local_state(f));
+    if !include_logs {
+        data_files.retain(|f| !is_raw_log(f));
+    }
+
+    let manifest = SnapshotManifest {
+        version: SNAPSHOT_VERSION,
+        created_at: chrono::Utc::now().to_rfc3339(),
+        includes_logs: include_logs,
+        rule_count: rule_files.len(),
+        data_file_count: data_files.len(),
+    };
+
+    let file = fs::File::create(dest_path)
+        .map_err(|e| SiemError::FileIO(format!("Cannot create snapshot file: {}", e)))?;
+    let mut zip = zip::ZipWriter::new(file);
+    let options = FileOptions::default()
+        .compression_method(zip::CompressionMethod::Deflated)
+        .unix_permissions(0o644);
+
+    let manifest_json = serde_json::to_string_pretty(&manifest)
+        .map_err(|e| SiemError::Serialization(format!("Cannot serialize manifest: {}", e)))?;
+    zip.start_file(MANIFEST_ENTRY, options)
+        .map_err(|e| SiemError::FileIO(format!("Cannot add file to ZIP: {}", e)))?;
+    zip.write_all(manifest_json.as_bytes())
+        .map_err(|e| SiemError::FileIO(format!("Cannot write to ZIP: {}", e)))?;
+
+    let entries = rule_files
+        .iter()
+        .map(|f| (entry_name(RULES_PREFIX, f), rules_dir.join(f)))
+        .chain(
+            data_files
+                .iter()
+                .map(|f| (entry_name(DATA_PREFIX, f), data_dir.join(f))),
+        );
+
+    for (name, path) in entries {
+        let content = fs::read(&path)
+            .map_err(|e| SiemError::FileIO(format!("Cannot read {:?}: {}", path, e)))?;
+        zip.start_file(name, options)
+            .map_err(|e| SiemError::FileIO(format!("Cannot add file to ZIP: {}", e)))?;
+        zip.write_all(&content)
+            .map_err(|e| SiemError::FileIO(format!("Cannot write to ZIP: {}", e)))?;
+    }
+
+    zip.finish()
+        .map_err(|e| SiemError::FileIO(format!("Cannot finalize ZIP: {}", e)))?;
+
+    Ok(manifest)
+}
+
+/// Entries of an archive with the snapshot layout, read whole.
+pub struct ArchiveEntries<M> {
+    pub manifest: M,
+    /// Rule files, relative to the rules directory
+    pub rules: Vec<(PathBuf, Vec<u8>)>,
+    /// Data files, relative to the app data dir
+    pub data: Vec<(PathBuf, Vec<u8>)>,
+}
+
+/// Read an archive with the snapshot layout (snapshots and app data backups).
+/// Every entry is read up front, so a corrupt archive is rejected before
+/// anything is replaced. `kind` names the archive in errors.
+pub fn read_archive<M: DeserializeOwned>(
+    path: &str,
+    kind: &str,
+    version: impl Fn(&M) -> u32,
+    supported_version: u32,
+) -> Result<ArchiveEntries<M>, SiemError> {
+    let file = fs::File::open(path)
+        .map_err(|e| SiemError::FileIO(format!("Cannot open {}: {}", kind, e)))?;
+    let mut archive = zip::ZipArchive::new(file)
+        .map_err(|e| SiemError::FileIO(format!("Cannot read ZIP archive: {}", e)))?;
+
+    let manifest: M = {
+        let mut entry = archive
+            .by_name(MANIFEST_ENTRY)
+            .map_err(|_| SiemError::FileIO(format!("Not a {}", kind)))?;
+        let mut content = String::new();
+        entry
+            .read_to_string(&mut content)
+            .map_err(|e| SiemError::FileIO(format!("Cannot read manifest: {}", e)))?;
+        serde_json::from_str(&content)
+            .map_err(|e| SiemError::Serialization(format!("Invalid {} manifest: {}", kind, e)))?
+    };
+
+    if version(&manifest) > supported_version {
+        return Err(SiemError::FileIO(format!(
+            "Version {} of the {} is newer than supported version {}",
+            version(&manifest),
+            kind,
+            supported_version
+        )));
+    }
+
+    let mut rules = Vec::new();
+    let mut data = Vec::new();
+    for i in 0..archive.len() {
+        let mut entry = archive
+            .by_index(i)
+            .map_err(|e| SiemError::FileIO(format!("Cannot read ZIP entry: {}", e)))?;
+        if entry.is_dir() {
+            continue;
+        }
+
+        // Reject entries escaping the target directory
+        let Some(path) = entry.enclosed_name().map(|p| p.to_path_buf()) else {
+            return Err(SiemError::FileIO(format!(
+                "Unsafe path in {}: {}",
+                kind,
+                entry.name()
+            )));
+        };
+
+        let mut content = Vec::new();
+        entry
+            .read_to_end(&mut content)
+            .map_err(|e| SiemError::FileIO(format!("Cannot read ZIP entry: {}", e)))?;
+
+        if let Ok(relative) = path.strip_prefix(RULES_PREFIX) {
+            rules.push((relative.to_path_buf(), content));
+        } else if let Ok(relative) = path.strip_prefix(DATA_PREFIX) {
+            data.push((relative.to_path_buf(), content));
+        }
+    }
+
+    Ok(ArchiveEntries {
+        manifest,
+        rules,
+        data,
+    })
+}
+
+/// First component of a relative path: the entry of its directory it lives in.
+pub fn top_entry(relative: &Path) -> Option<PathBuf> {
+    relative
+        .components()
+        .next()
+        .map(|c| PathBuf::from(c.as_os_str()))
+}
+
+/// A restore into a directory: new contents are written to a staging folder
+/// inside it, then swapped in entry by entry with renames, the replaced
+/// entries being moved aside. If a swap fails, or the restore is dropped
+/// before `finish`, every entry swapped so far is put back, so a failed
+/// restore leaves the directory as it was.
+pub struct StagedRestore {
+    target: PathBuf,
+    staging: PathBuf,
+    backup: PathBuf,
+    /// Entries (relative paths) to replace, removed if nothing is staged
+    replaced: BTreeSet<PathBuf>,
+    /// Entries moved aside so far
+    swapped: Vec<PathBuf>,
+    files_staged: usize,
+}
+
+impl StagedRestore {
+    /// Start a restore into `target`.
+    pub fn new(target: &Path) -> Result<Self, SiemError> {
+        let id = uuid::Uuid::new_v4();
+        let staging = target.join(format!("{}{}", RESTORE_DIR_PREFIX, id));
+        fs::create_dir_all(&staging)
+            .map_err(|e| SiemError::FileIO(format!("Cannot create staging folder: {}", e)))?;
+        Ok(StagedRestore {
+            target: target.to_path_buf(),
+            backup: target.join(format!("{}{}-old", RESTORE_DIR_PREFIX, id)),
+            staging,
+            replaced: BTreeSet::new(),
+            swapped: Vec::new(),
+            files_staged: 0,
+        })
+    }
+
+    /// Stage a file at `relative`.
+    pub fn write(&mut self, relative: &Path, content: &[u8]) -> Result<(), SiemError> {
+        write_file(&self.staging.join(relative), content)?;
+        self.files_staged += 1;
+        Ok(())
+    }
+
+    /// Replace the entry at `relative` with what is staged there (nothing
+    /// removes it).
+    pub fn replace(&mut self, relative: PathBuf) {
+        self.replaced.insert(relative);
+    }
+
+    /// Number of files staged.
+    pub fn files_staged(&self) -> usize {
+        self.files_staged
+    }
+
+    /// Swap the staged entries in. On failure, the directory is put back as
+    /// it was.
+    pub fn swap(&mut self) -> Result<(), SiemError> {
+        let replaced: Vec<PathBuf> = self.replaced.iter().cloned().collect();
+        for relative in replaced {
+            let result = self.swap_entry(&relative);
+            if let Err(e) = result {
+                self.rollback();
+                return Err(e);
+            }
+        }
+        Ok(())
+    }
+
+    fn swap_entry(&mut self, relative: &Path) -> Result<(), SiemError> {
+        let current = self.target.join(relative);
+        if current.exists() {
+            move_entry(&current, &self.backup.join(relative))?;
+        }
+        self.swapped.push(relative.to_path_buf());
+        let staged = self.staging.join(relative);
+        if staged.exists() {
+            move_entry(&staged, &current)?;
+        }
+        Ok(())
+    }
+
+    /// Put the entries swapped so far back.
+    fn rollback(&mut self) {
+        while let Some(relative) = self.swapped.pop() {
+            let current = self.target.join(&relative);
+            let _ = remove_entry(&current);
+            let old = self.backup.join(&relative);
+            if old.exists() {
+                if let Err(e) = move_entry(&old, &current) {
+                    eprintln!("Warning: Cannot put back {:?}: {}", current, e);
+                }
+            }
+        }
+    }
+
+    /// Keep the swapped-in entries and drop the replaced ones.
+    pub fn finish(mut self) {
+        self.swapped.clear();
+    }
+}
+
+impl Drop for StagedRestore {
+    fn drop(&mut self) {
+        self.rollback();
+        let _ = fs::remove_dir_all(&self.staging);
+        let _ = fs::remove_dir_all(&self.backup);
+    }
+}
+
+/// Move a file or directory, creating the destination's parent.
+fn move_entry(from: &Path, to: &Path) -> Result<(), SiemError> {
+    if let Some(parent) = to.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| SiemError::FileIO(format!("Cannot create dir {:?}: {}", parent, e)))?;
+    }
+    fs::rename(from, to).map_err(|e| SiemError::FileIO(format!("Cannot move {:?}: {}", from, e)))
+}
+
+/// Remove a file or directory, if present.
+fn remove_entry(path: &Path) -> std::io::Result<()> {
+    if path.is_dir() {
+        fs::remove_dir_all(path)
+    } else if path.exists() {
+        fs::remove_file(path)
+    } else {
+        Ok(())
+    }
+}
+
+/// Swap several restores in, all or none.
+pub fn swap_all(restores: Vec<StagedRestore>) -> Result<(), SiemError> {
+    let mut swapped = Vec::with_capacity(restores.len());
+    for mut restore in restores {
+        // On failure, the restores already swapped are rolled back when dropped
+        restore.swap()?;
+        swapped.push(restore);
+    }
+    swapped.into_iter().for_each(StagedRestore::finish);
+    Ok(())
+}
+
+/// The entry of the app data dir replaced to restore `relative`: its top
+/// entry, or the file itself for log bookkeeping files when the raw logs are
+/// kept (`None` for the raw logs then).
+fn data_restore_entry(relative: &Path, keep_raw_logs: bool) -> Option<PathBuf> {
+    if keep_raw_logs && relative.starts_with(LOGS_DIR_NAME) {
+        return (!is_raw_log(relative)).then(|| relative.to_path_buf());
+    }
+    top_entry(relative)
+}
+
+/// Restore a snapshot, replacing the current rules and workspace data. The
+/// new contents are staged first and swapped in at the end, so a restore
+/// that fails leaves the workspace as it was.
+pub fn restore_snapshot(
+    app_handle: &tauri::AppHandle,
+    snapshot_path: &str,
+) -> Result<RestoreSummary, SiemError> {
+    let archive = read_archive::<SnapshotManifest>(
+        snapshot_path,
+        "workspace snapshot",
+        |manifest| manifest.version,
+        SNAPSHOT_VERSION,
+    )?;
+    let keep_raw_logs = !archive.manifest.includes_logs;
+
+    let data_dir = app_data::app_data_dir(app_handle)?;
+    let old_rules_dir = config::get_rules_directory(app_handle)?;
+
+    // The restored config decides where rules live
+    let restored_config: config::AppConfig = archive
+        .data
+        .iter()
+        .find(|(relative, _)| relative == Path::new(config::CONFIG_FILE))
+        .map(|(_, content)| serde_json::from_slice(content))
+        .transpose()
+        .map_err(|e| SiemError::Serialization(format!("Cannot parse config: {}", e)))?
+        .unwrap_or_default();
+    let rules_dir = config::rules_directory_of(app_handle, &restored_config)?;
+
+    // Rules inside the app data dir are restored with the rules
+    let rules_entries: Vec<PathBuf> = [&old_rules_dir, &rules_dir]
+        .iter()
+        .filter_map(|dir| dir.strip_prefix(&data_dir).ok())
+        .filter_map(top_entry)
+        .collect();
+    let restored = |relative: &Path| {
+        !is_local_state(relative)
+            && data_restore_entry(relative, keep_raw_logs)
+                .is_some_and(|entry| !rules_entries.contains(&entry))
+    };
+
+    // Everything else is replaced (raw logs only if the snapshot carries them)
+    let mut data = StagedRestore::new(&data_dir)?;
+    let mut existing = Vec::new();
+    collect_files(&data_dir, &data_dir, &mut existing)?;
+    for relative in existing.iter().filter(|relative| restored(relative)) {
+        if let Some(entry) = data_restore_entry(relative, keep_raw_logs) {
+            data.replace(entry);
+        }
+    }
+    for (relative, content) in &archive.data {
+        if !restored(relative) {
+            continue;
+        }
+        data.write(relative, content)?;
+        if let Some(entry) = data_restore_entry(relative, keep_raw_logs) {
+            data.replace(entry);
+        }
+    }
+
+    let mut rules = StagedRestore::new(&rules_dir)?;
+    for relative in collect_rule_files(&rules_dir)? {
+        rules.replace(relative);
+    }
+    for (relative, content) in &archive.rules {
+        rules.write(relative, content)?;
+        if let Some(entry) = top_entry(relative) {
+            rules.replace(entry);
+        }
+    }
+    let files_restored = data.files_staged() + rules.files_staged();
+
+    let mut restores = vec![data, rules];
+    if rules_dir != old_rules_dir {
+        let mut old_rules = StagedRestore::new(&old_rules_dir)?;
+        for relative in collect_rule_files(&old_rules_dir)? {
+            old_rules.replace(relative);
+        }
+        restores.push(old_rules);
+    }
+    swap_all(restores)?;
+
+    Ok(RestoreSummary {
+        manifest: archive.manifest,
+        files_restored,
+    })
+}
+
+/// Write a file, creating parent directories as needed.
+pub fn write_file(path: &Path, content: &[u8]) -> Result<(), SiemError> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| SiemError::FileIO(format!("Cannot create dir {:?}: {}", parent, e)))?;
+    }
+    fs::write(path, content)
+        .map_err(|e| SiemError::FileIO(format!("Cannot write {:?}: {}", path, e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_raw_log_detection() {
+        assert!(is_raw_log(Path::new("logs/trail.json")));
+        assert!(!is_raw_log(Path::new("logs/metadata.json")));
+        assert!(!is_raw_log(Path::new("config.json")));
+        assert!(!is_raw_log(Path::new("hash_sets/logs.txt")));
+        assert!(is_local_state(Path::new("ingest_cache.duckdb.wal")));
+        assert!(is_local_state(Path::new("workspace.lock")));
+        assert!(is_local_state(Path::new("audit_log.jsonl")));
+        assert!(!is_local_state(Path::new("logs/ingest_cache.duckdb")));
+        assert!(is_local_state(Path::new(".restore-1234/config.json")));
+    }
+
+    #[test]
+    fn test_data_restore_entries() {
+        let entry = |path: &str, keep_raw_logs| {
+            data_restore_entry(Path::new(path), keep_raw_logs)
+                .map(|p| p.to_string_lossy().to_string())
+        };
+        assert_eq!(
+            entry("hash_sets/bad.txt", true).as_deref(),
+            Some("hash_sets")
+        );
+        assert_eq!(entry("logs/trail.json", true), None);
+        assert_eq!(
+            entry("logs/metadata.json", true).as_deref(),
+            Some("logs/metadata.json")
+        );
+        assert_eq!(entry("logs/trail.json", false).as_deref(), Some("logs"));
+    }
+
+    /// Directory of files `(relative path, content)` for restore tests.
+    fn test_dir(name: &str, files: &[(&str, &str)]) -> PathBuf {
+        let dir = std::env::temp_dir().join(name);
+        let _ = fs::remove_dir_all(&dir);
+        for (relative, content) in files {
+            write_file(&dir.join(relative), content.as_bytes()).unwrap();
+        }
+        dir
+    }
+
+    /// Files of a directory with their content, sorted, leaving out the
+    /// folders of a restore.
+    fn dir_contents(dir: &Path) -> Vec<(String, String)> {
+        let mut files = Vec::new();
+        collect_files(dir, dir, &mut files).unwrap();
+        let mut contents: Vec<(String, String)> = files
+            .iter()
+            .filter(|f| !entry_name("", f).starts_with(RESTORE_DIR_PREFIX))
+            .map(|f| (entry_name("", f), fs::read_to_string(dir.join(f)).unwrap()))
+            .collect();
+        contents.sort();
+        contents
+    }
+
+    #[test]
+    fn test_staged_restore_swaps_entries() {
+        let dir = test_dir(
+            "offline_siem_test_staged_restore",
+            &[
+                ("config.json", "old"),
+                ("intel/a.txt", "old"),
+                ("intel/b.txt", "old"),
+                ("gone.json", "old"),
+                ("audit_log.jsonl", "kept"),
+            ],
+        );
+
+        let mut restore = StagedRestore::new(&dir).unwrap();
+        restore.write(Path::new("config.json"), b"new").unwrap();
+        restore.write(Path::new("intel/c.txt"), b"new").unwrap();
+        for entry in ["config.json", "intel", "gone.json"] {
+            restore.replace(PathBuf::from(entry));
+        }
+        assert_eq!(restore.files_staged(), 2);
+        swap_all(vec![restore]).unwrap();
+
+        assert_eq!(
+            dir_contents(&dir),
+            vec![
+                ("audit_log.jsonl".to_string(), "kept".to_string()),
+                ("config.json".to_string(), "new".to_string()),
+                ("intel/c.txt".to_string(), "new".to_string()),
+            ]
+        );
+        // The staging and set-aside folders are gone
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 3);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_unfinished_restore_puts_entries_back() {
+        let files = [("config.json", "old"), ("intel/a.txt", "old")];
+        let dir = test_dir("offline_siem_test_unfinished_restore", &files);
+
+        let mut restore = StagedRestore::new(&dir).unwrap();
+        restore.write(Path::new("config.json"), b"new").unwrap();
+        restore.replace(PathBuf::from("config.json"));
+        restore.replace(PathBuf::from("intel"));
+        restore.swap().unwrap();
+        assert_eq!(
+            dir_contents(&dir),
+            vec![("config.json".to_string(), "new".to_string())]
+        );
+
+        // Dropped before finishing, e.g. because a later swap failed
+        drop(restore);
+        let expected: Vec<(String, String)> = files
+            .iter()
+            .map(|(name, content)| (name.to_string(), content.to_string()))
+            .collect();
+        assert_eq!(dir_contents(&dir), expected);
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 2);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_entry_name_uses_forward_slashes() {
+        let relative: PathBuf = ["hash_sets", "bad.txt"].iter().collect();
+        assert_eq!(entry_name(DATA_PREFIX, &relative), "data/hash_sets/bad.txt");
+    }
+}
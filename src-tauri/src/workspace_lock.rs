@@ -0,0 +1,163 @@
+//! Single-writer lock on the workspace (app data directory).
+//!
+//! The first app instance to start takes an exclusive OS lock on
+//! `workspace.lock` in the app data dir and holds it until it exits; the
+//! lock is released by the OS even if the process crashes, so it can't go
+//! stale. Other instances on the same workspace open it read-only: reading
+//! rules, logs and alerts works, but commands that write to the workspace
+//! fail until the writer exits (the lock is retried on every write).
+//!
+//! The lock file holds the owner's process ID and start time, so a blocked
+//! instance can say who is writing.
+
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File, OpenOptions, TryLockError};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+
+use crate::app_data;
+use crate::models::SiemError;
+
+/// Lock file inside the app data dir.
+pub const LOCK_FILE: &str = "workspace.lock";
+
+/// The instance holding the workspace lock.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct LockOwner {
+    pub pid: u32,
+    /// When the owner took the lock (ISO 8601)
+    pub acquired_at: String,
+}
+
+/// Whether this instance may write to the workspace.
+#[derive(Debug, Serialize, Clone)]
+pub struct LockStatus {
+    pub writable: bool,
+    /// Instance holding the lock (this one when writable), if known
+    pub owner: Option<LockOwner>,
+}
+
+/// Lock file held by this instance, with its owner record.
+fn held_lock() -> &'static Mutex<Option<(File, LockOwner)>> {
+    static LOCK: OnceLock<Mutex<Option<(File, LockOwner)>>> = OnceLock::new();
+    LOCK.get_or_init(|| Mutex::new(None))
+}
+
+/// Get the path of the lock file.
+fn get_lock_path(app_handle: &tauri::AppHandle) -> Result<PathBuf, SiemError> {
+    app_data::app_data_file(app_handle, LOCK_FILE)
+}
+
+/// Take the workspace lock if no other instance holds it.
+pub fn acquire(app_handle: &tauri::AppHandle) -> Result<LockStatus, SiemError> {
+    let mut held = held_lock()
+        .lock()
+        .map_err(|_| SiemError::FileIO("Workspace lock is unavailable".to_string()))?;
+    if let Some((_, owner)) = held.as_ref() {
+        return Ok(LockStatus {
+            writable: true,
+            owner: Some(owner.clone()),
+        });
+    }
+
+    match try_lock(&get_lock_path(app_handle)?)? {
+        Ok((file, owner)) => {
+            *held = Some((file, owner.clone()));
+            Ok(LockStatus {
+                writable: true,
+                owner: Some(owner),
+            })
+        }
+        Err(owner) => Ok(LockStatus {
+            writable: false,
+            owner,
+        }),
+    }
+}
+
+/// Fail unless this instance holds (or can now take) the workspace lock.
+pub fn ensure_writable(app_handle: &tauri::AppHandle) -> Result<(), SiemError> {
+    let status = acquire(app_handle)?;
+    if status.writable {
+        return Ok(());
+    }
+
+    let holder = match status.owner {
+        Some(owner) => format!(
+            "another instance (pid {}, since {})",
+            owner.pid, owner.acquired_at
+        ),
+        None => "another instance".to_string(),
+    };
+    Err(SiemError::Locked(format!(
+        "{} is writing to this workspace; it is read-only here until that instance exits",
+        holder
+    )))
+}
+
+/// Try to lock a lock file, recording this process as its owner.
+/// Returns the current owner, if readable, when the file is already locked.
+fn try_lock(path: &Path) -> Result<Result<(File, LockOwner), Option<LockOwner>>, SiemError> {
+    let mut file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(path)
+        .map_err(|e| SiemError::FileIO(format!("Cannot open workspace lock: {}", e)))?;
+
+    match file.try_lock() {
+        Ok(()) => {}
+        Err(TryLockError::WouldBlock) => return Ok(Err(read_owner(&mut file))),
+        Err(TryLockError::Error(e)) => {
+            return Err(SiemError::FileIO(format!("Cannot lock workspace: {}", e)))
+        }
+    }
+
+    let owner = LockOwner {
+        pid: std::process::id(),
+        acquired_at: chrono::Utc::now().to_rfc3339(),
+    };
+    let content = serde_json::to_string(&owner)
+        .map_err(|e| SiemError::Serialization(format!("Cannot serialize lock owner: {}", e)))?;
+    file.set_len(0)
+        .and_then(|_| file.seek(SeekFrom::Start(0)))
+        .and_then(|_| file.write_all(content.as_bytes()))
+        .and_then(|_| file.flush())
+        .map_err(|e| SiemError::FileIO(format!("Cannot write workspace lock: {}", e)))?;
+
+    Ok(Ok((file, owner)))
+}
+
+/// Read the owner record of a lock file (unreadable while locked on some
+/// platforms).
+fn read_owner(file: &mut File) -> Option<LockOwner> {
+    let mut content = String::new();
+    file.read_to_string(&mut content).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_second_lock_is_refused_until_released() {
+        let path = std::env::temp_dir().join("offline_siem_test_workspace.lock");
+        fs::remove_file(&path).ok();
+
+        let (file, owner) = try_lock(&path).unwrap().unwrap();
+        assert_eq!(owner.pid, std::process::id());
+
+        let refused = try_lock(&path).unwrap();
+        assert!(refused.is_err());
+        if let Err(Some(current)) = refused {
+            assert_eq!(current, owner);
+        }
+
+        drop(file);
+        assert!(try_lock(&path).unwrap().is_ok());
+        fs::remove_file(&path).ok();
+    }
+}
@@ -57,19 +57,27 @@ export const LogFileSelector: React.FC<LogFileSelectorProps> = ({
                         name: "JSON Log Files",
                         extensions: ["json"],
                     },
+                    {
+                        name: "Windows XML Exports",
+                        extensions: ["xml"],
+                    },
+                    {
+                        name: "ZIP Archives",
+                        extensions: ["zip"],
+                    },
                 ],
             });
 
             if (selected && typeof selected === "string") {
-                // Show dialog to select log type
-                const logType = await showLogTypeDialog();
+                // XML files are Windows event exports, other files need their log type
+                const logType = selected.toLowerCase().endsWith(".xml") ? "windowsxml" : await showLogTypeDialog();
                 if (!logType) return; // User cancelled
 
                 setLoading(true);
                 setError(null);
-                const importedFile = await logService.importLogFile(selected, logType);
+                const importedFiles = await logService.importLogFile(selected, logType);
                 await loadLogFiles(); // Refresh the list
-                onSelectFile(importedFile); // Auto-select the imported file
+                onSelectFile(importedFiles[0]); // Auto-select the (first) imported file
             }
         } catch (err) {
             setError(`Failed to import file: ${err}`);
@@ -187,12 +195,22 @@ export const LogFileSelector: React.FC<LogFileSelectorProps> = ({
                         name: "JSON Log Files",
                         extensions: ["json"],
                     },
+                    {
+                        name: "Windows XML Exports",
+                        extensions: ["xml"],
+                    },
+                    {
+                        name: "ZIP Archives",
+                        extensions: ["zip"],
+                    },
                 ],
             });
 
             if (selected && Array.isArray(selected) && selected.length > 0) {
-                // Show dialog to select log type
-                const logType = await showLogTypeDialog();
+                // XML files are Windows event exports, other files need their log type
+                const logType = selected.every((path) => path.toLowerCase().endsWith(".xml"))
+                    ? "windowsxml"
+                    : await showLogTypeDialog();
                 if (!logType) return; // User cancelled
 
                 setLoading(true);
@@ -222,6 +240,74 @@ export const LogFileSelector: React.FC<LogFileSelectorProps> = ({
         }
     };
 
+    const handleRegisterFile = async () => {
+        try {
+            const selected = await open({ multiple: false });
+            if (!selected || typeof selected !== "string") return;
+
+            const logType = selected.toLowerCase().endsWith(".xml") ? "windowsxml" : await showLogTypeDialog();
+            if (!logType) return; // User cancelled
+
+            setLoading(true);
+            setError(null);
+            const registeredFile = await logService.registerLogFile(selected, logType);
+            await loadLogFiles(); // Refresh the list
+            onSelectFile(registeredFile);
+        } catch (err) {
+            setError(`Failed to register file: ${err}`);
+            console.error("Error registering file:", err);
+        } finally {
+            setLoading(false);
+        }
+    };
+
+    const handleImportCollection = async (folder: boolean) => {
+        try {
+            // KAPE output is a folder, a Velociraptor collection a zip
+            const selected = await open(
+                folder
+                    ? { directory: true }
+                    : { filters: [{ name: "Velociraptor Collection", extensions: ["zip"] }] }
+            );
+            if (!selected || typeof selected !== "string") return;
+
+            setLoading(true);
+            setError(null);
+
+            const summary = await logService.importTriageCollection(selected);
+
+            await loadLogFiles(); // Refresh the list
+
+            if (summary.failed > 0) {
+                setError(`Imported ${summary.succeeded}/${summary.total} artifacts. Errors: ${summary.errors.join(", ")}`);
+            }
+            if (summary.imported_files.length > 0) {
+                onSelectFile(summary.imported_files[0]);
+            }
+        } catch (err) {
+            setError(`Failed to import collection: ${err}`);
+            console.error("Error importing collection:", err);
+        } finally {
+            setLoading(false);
+        }
+    };
+
+    const handleVerifyFile = async (filename: string) => {
+        try {
+            const result = await logService.verifyLogIntegrity(filename);
+            if (!result.recorded_sha256) {
+                alert(`${filename} has no recorded hash (imported before hashing).\nSHA-256: ${result.current_sha256}`);
+            } else if (result.intact) {
+                alert(`${filename} is intact.\nSHA-256: ${result.current_sha256}`);
+            } else {
+                alert(`WARNING: ${filename} has changed since import.\nRecorded: ${result.recorded_sha256}\nCurrent:  ${result.current_sha256}`);
+            }
+        } catch (err) {
+            setError(`Failed to verify file: ${err}`);
+            console.error("Error verifying file:", err);
+        }
+    };
+
     const handleDeleteFile = async (filename: string) => {
         if (!confirm(`Are you sure you want to delete "${filename}"?`)) {
             return;
@@ -286,6 +372,21 @@ export const LogFileSelector: React.FC<LogFileSelectorProps> = ({
                             Batch Import
                         </Button>
                     </Tooltip>
+                    <Tooltip content="Read a large log file where it is, without copying it into the library" position="left">
+                        <Button onClick={handleRegisterFile} disabled={loading}>
+                            Link in Place
+                        </Button>
+                    </Tooltip>
+                    <Tooltip content="Import the JSON/CSV artifacts of a Velociraptor collection zip" position="left">
+                        <Button onClick={() => handleImportCollection(false)} disabled={loading}>
+                            Velociraptor
+                        </Button>
+                    </Tooltip>
+                    <Tooltip content="Import the module output (CSV/JSON) of a KAPE folder" position="left">
+                        <Button onClick={() => handleImportCollection(true)} disabled={loading}>
+                            KAPE
+                        </Button>
+                    </Tooltip>
                 </div>
             </div>
 
@@ -384,9 +485,35 @@ export const LogFileSelector: React.FC<LogFileSelectorProps> = ({
                                         <span>Size:</span>
                                         <span>{formatFileSize(file.size_bytes)}</span>
 
+                                        {file.referenced && (
+                                            <>
+                                                <span>Linked:</span>
+                                                <span style={{ wordBreak: "break-all" }}>{file.path}</span>
+                                            </>
+                                        )}
+
                                         <span>Modified:</span>
                                         <span>{formatDate(file.modified)}</span>
 
+                                        {file.sha256 && (
+                                            <>
+                                                <span>SHA-256:</span>
+                                                <span style={{ display: "flex", alignItems: "center", gap: "0.5rem" }}>
+                                                    <code title={file.sha256}>{file.sha256.slice(0, 16)}…</code>
+                                                    <button
+                                                        onClick={(e) => {
+                                                            e.stopPropagation();
+                                                            handleVerifyFile(file.filename);
+                                                        }}
+                                                        title="Re-hash the file and compare with the hash recorded on import"
+                                                        style={{ fontSize: "0.75rem", padding: "0.1rem 0.4rem", cursor: "pointer" }}
+                                                    >
+                                                        Verify
+                                                    </button>
+                                                </span>
+                                            </>
+                                        )}
+
                                         <span>Format:</span>
                                         <div style={{ display: "flex", alignItems: "center", gap: "0.5rem" }}>
                                             <span style={{
@@ -397,13 +524,13 @@ export const LogFileSelector: React.FC<LogFileSelectorProps> = ({
                                                 backgroundColor: file.log_type === "cloudtrail" ? "#DBEAFE" : "#FEF3C7",
                                                 color: file.log_type === "cloudtrail" ? "#1E40AF" : "#92400E"
                                             }}>
-                                                {file.log_type === "cloudtrail" ? "CloudTrail" : file.log_type === "flatjson" ? "FlatJson" : "Unknown"}
+                                                {file.log_type === "cloudtrail" ? "CloudTrail" : file.log_type === "flatjson" ? "FlatJson" : file.log_type === "kv" ? "Key=Value" : file.log_type === "suricata" ? "Suricata" : file.log_type === "azureactivity" ? "Azure Activity" : file.log_type === "entrasignin" ? "Entra Sign-in" : file.log_type === "okta" ? "Okta" : file.log_type === "windows" ? "Windows" : file.log_type === "windowsxml" ? "Windows XML" : "Unknown"}
                                             </span>
                                             <select
                                                 value={file.log_type || "flatjson"}
                                                 onChange={async (e) => {
                                                     e.stopPropagation();
-                                                    const newType = e.target.value as "cloudtrail" | "flatjson";
+                                                    const newType = e.target.value as "cloudtrail" | "flatjson" | "kv" | "suricata" | "azureactivity" | "entrasignin" | "okta" | "windows" | "windowsxml";
                                                     try {
                                                         // Save scroll position before refresh
                                                         if (scrollContainerRef.current) {
@@ -428,6 +555,13 @@ export const LogFileSelector: React.FC<LogFileSelectorProps> = ({
                                             >
                                                 <option value="cloudtrail">CloudTrail</option>
                                                 <option value="flatjson">FlatJson</option>
+                                                <option value="kv">Key=Value</option>
+                                                <option value="suricata">Suricata EVE</option>
+                                                <option value="azureactivity">Azure Activity</option>
+                                                <option value="entrasignin">Entra Sign-in</option>
+                                                <option value="okta">Okta</option>
+                                                <option value="windows">Windows (Sysmon/Security)</option>
+                                                <option value="windowsxml">Windows XML export</option>
                                             </select>
                                         </div>
                                     </div>
@@ -285,6 +285,22 @@ export const ScanPage: React.FC = () => {
                                 </Card>
                             )}
 
+                            {/* Meta-rule alerts across files */}
+                            {bulkResults.meta_alerts.length > 0 && (
+                                <Card style={{ borderLeft: "4px solid var(--danger)" }}>
+                                    <h3 style={{ margin: "0 0 1rem 0" }}>Correlated Alerts ({bulkResults.meta_alerts.length})</h3>
+                                    {bulkResults.meta_alerts.map((alert, alertIdx) => (
+                                        <div key={alertIdx} style={{ marginBottom: "0.5rem", padding: "0.75rem", backgroundColor: "var(--bg-dark)", borderRadius: "var(--radius-sm)" }}>
+                                            <div style={{ fontWeight: "bold" }}>{alert.rule_title}</div>
+                                            <div style={{ fontSize: "0.8rem", color: "var(--text-secondary)" }}>
+                                                Severity: {alert.severity} • Rule ID: {alert.rule_id}
+                                                {alert.source_file ? ` • ${alert.source_file}` : " • multiple files"}
+                                            </div>
+                                        </div>
+                                    ))}
+                                </Card>
+                            )}
+
                             {/* Results by File */}
                             {bulkResults.file_results.length > 0 && (
                                 <div style={{ display: "flex", flexDirection: "column", gap: "1rem" }}>
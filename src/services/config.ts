@@ -70,3 +70,32 @@ export async function clearRecentFiles(): Promise<AppConfig> {
 export async function getRulesDirectory(): Promise<string> {
     return await invoke<string>("get_rules_directory");
 }
+
+export interface ApiStatus {
+    running: boolean;
+    url: string | null;
+    token: string | null;
+    started_at: string | null;
+    requests_served: number;
+}
+
+/**
+ * Start the local REST API (127.0.0.1 only)
+ */
+export async function startRestApi(port?: number): Promise<ApiStatus> {
+    return await invoke<ApiStatus>("start_rest_api", { port });
+}
+
+/**
+ * Stop the local REST API
+ */
+export async function stopRestApi(): Promise<ApiStatus> {
+    return await invoke<ApiStatus>("stop_rest_api");
+}
+
+/**
+ * Get the state, URL and token of the local REST API
+ */
+export async function getRestApiStatus(): Promise<ApiStatus> {
+    return await invoke<ApiStatus>("get_rest_api_status");
+}
@@ -1,6 +1,15 @@
 import { invoke } from "@tauri-apps/api/core";
 
-export type LogType = "cloudtrail" | "flatjson";
+/**
+ * `kv` reads lines of `key=value` pairs, `suricata` Suricata EVE JSON (NDJSON),
+ * `azureactivity`/`entrasignin` Azure Activity Log and Entra ID sign-in exports,
+ * `okta` Okta System Log exports, `windows` Windows event exports (Sysmon, Security)
+ * normalized to flat Sigma-style fields, `windowsxml` the same events exported as XML
+ * (Event Viewer "Save as XML", `wevtutil qe /f:xml`),
+ * `{ plugin: name }` reads the file through `plugins/<name>.wasm`,
+ * `{ text: name }` line by line through the text parser `name`
+ */
+export type LogType = "cloudtrail" | "flatjson" | "kv" | "suricata" | "azureactivity" | "entrasignin" | "okta" | "windows" | "windowsxml" | { plugin: string } | { text: string };
 
 export interface LogFileInfo {
     filename: string;
@@ -8,6 +17,21 @@ export interface LogFileInfo {
     size_bytes: number;
     modified: string;
     log_type: LogType | null;
+    /** Registered in place: `path` is outside the logs folder */
+    referenced: boolean;
+    /** SHA-256 recorded when the file entered the library */
+    sha256: string | null;
+}
+
+/** Result of re-hashing a log file against its recorded SHA-256. */
+export interface HashVerification {
+    filename: string;
+    recorded_sha256: string | null;
+    hashed_at: string | null;
+    current_sha256: string;
+    /** Whether the file still has its recorded hash (false without one) */
+    intact: boolean;
+    verified_at: string;
 }
 
 export interface ImportSummary {
@@ -18,6 +42,109 @@ export interface ImportSummary {
     errors: string[];
 }
 
+export type TimelineBucket = "minute" | "hour" | "day";
+
+export interface TimelineCount {
+    start: string;
+    count: number;
+}
+
+export interface EventTimeline {
+    bucket: TimelineBucket;
+    timestamp_field: string | null;
+    buckets: TimelineCount[];
+    total_events: number;
+    matched_events: number;
+    untimed_events: number;
+}
+
+export interface FieldValueCount {
+    value: string;
+    count: number;
+}
+
+export interface FieldStats {
+    field: string;
+    total_events: number;
+    null_count: number;
+    null_percentage: number;
+    cardinality: number;
+    top_values: FieldValueCount[];
+}
+
+export interface RareValue {
+    value: string;
+    count: number;
+    events: Record<string, unknown>[];
+}
+
+export interface EntitySummary {
+    field: string;
+    value: string;
+    event_count: number;
+    total_events: number;
+    first_seen: string | null;
+    last_seen: string | null;
+    timeline: EventTimeline;
+    actions: FieldValueCount[];
+    distinct_actions: number;
+    related_users: FieldValueCount[];
+    related_ips: FieldValueCount[];
+}
+
+export interface SchemaField {
+    name: string;
+    path: string;
+    types: string[];
+    sample_values: string[];
+    event_count: number;
+    presence_percentage: number;
+    children: SchemaField[];
+}
+
+export interface LogSchema {
+    total_events: number;
+    field_count: number;
+    fields: SchemaField[];
+}
+
+export interface EventPage {
+    events: any[];
+    offset: number;
+    limit: number;
+    matched_events: number;
+    total_events: number;
+    has_more: boolean;
+}
+
+export interface LogSearchHit {
+    filename: string;
+    log_path: string;
+    event_index: number;
+    event: any;
+}
+
+export interface LogSearchResult {
+    keyword: string;
+    hits: LogSearchHit[];
+    total_matches: number;
+    files_searched: number;
+    events_searched: number;
+    truncated: boolean;
+    failed_files: string[];
+}
+
+/** Grok/regex parser turning text log lines into events */
+export interface TextParser {
+    name: string;
+    /** File names it reads, with `*`/`?` wildcards, e.g. `auth.log*` */
+    file_patterns: string[];
+    /** Tried in order, e.g. `%{IP:source.ip} %{INT:port:int}` or `(?P<user>\\S+)` */
+    patterns: string[];
+    custom_patterns?: Record<string, string>;
+    keep_unmatched?: boolean;
+}
+
 export const logService = {
     /**
      * List all JSON log files in the monitored logs folder.
@@ -27,9 +154,10 @@ export const logService = {
     },
 
     /**
-     * Import an external log file by copying it to the monitored folder.
+     * Import an external log file by copying it to the monitored folder, or the
+     * JSON/CSV/XML log files of a `.zip` archive by extracting them.
      */
-    importLogFile: async (sourcePath: string, logType: LogType): Promise<LogFileInfo> => {
+    importLogFile: async (sourcePath: string, logType: LogType): Promise<LogFileInfo[]> => {
         return await invoke("import_log_file", { sourcePath, logType });
     },
 
@@ -40,6 +168,22 @@ export const logService = {
         return await invoke("import_multiple_log_files", { sourcePaths, logType });
     },
 
+    /**
+     * Import the log artifacts of a Velociraptor collection (zip) or a KAPE
+     * output folder, each as a log file of its detected log type.
+     */
+    importTriageCollection: async (sourcePath: string): Promise<ImportSummary> => {
+        return await invoke("import_triage_collection", { sourcePath });
+    },
+
+    /**
+     * Register an external log file in place, without copying it (for evidence
+     * too large to duplicate). Deleting it from the library only forgets it.
+     */
+    registerLogFile: async (sourcePath: string, logType: LogType): Promise<LogFileInfo> => {
+        return await invoke("register_log_file", { sourcePath, logType });
+    },
+
     /**
      * Delete a log file from the monitored folder.
      */
@@ -47,10 +191,113 @@ export const logService = {
         return await invoke("delete_log_file", { filename });
     },
 
+    /**
+     * Re-hash a log file and compare it with the SHA-256 recorded on import.
+     */
+    verifyLogIntegrity: async (filename: string): Promise<HashVerification> => {
+        return await invoke("verify_log_integrity", { filename });
+    },
+
     /**
      * Update the log type for a specific log file.
      */
     updateLogType: async (filename: string, logType: LogType): Promise<void> => {
         return await invoke("update_log_type", { filename, logType });
     },
+
+    /**
+     * Load one page of a log file's events, optionally filtered by a condition and
+     * sorted by a field (prefix with "-" for descending).
+     */
+    loadLogEventsPage: async (
+        logPath: string,
+        logType: LogType,
+        offset: number,
+        limit?: number,
+        sortField?: string,
+        filter?: string
+    ): Promise<EventPage> => {
+        return await invoke("load_log_events_page", { logPath, logType, offset, limit, sortField, filter });
+    },
+
+    /**
+     * Count a log file's events per minute/hour/day, optionally only those matching a condition.
+     */
+    getEventTimeline: async (
+        logPath: string,
+        logType: LogType,
+        bucket: TimelineBucket = "hour",
+        fieldFilter?: string
+    ): Promise<EventTimeline> => {
+        return await invoke("get_event_timeline", { logPath, logType, bucket, fieldFilter });
+    },
+
+    /**
+     * Stack a field: top values, distinct cardinality and null percentage.
+     */
+    getFieldStats: async (logPath: string, logType: LogType, field: string, topN?: number): Promise<FieldStats> => {
+        return await invoke("get_field_stats", { logPath, logType, field, topN });
+    },
+
+    /**
+     * Values of a field seen fewer than `threshold` times, rarest first.
+     */
+    findRareValues: async (logPath: string, logType: LogType, field: string, threshold?: number): Promise<RareValue[]> => {
+        return await invoke("find_rare_values", { logPath, logType, field, threshold });
+    },
+
+    /**
+     * Everything about one entity (events over time, actions, related users/IPs) for pivoting.
+     */
+    getEntitySummary: async (
+        logPath: string,
+        logType: LogType,
+        field: string,
+        value: string,
+        bucket?: TimelineBucket
+    ): Promise<EntitySummary> => {
+        return await invoke("get_entity_summary", { logPath, logType, field, value, bucket });
+    },
+
+    /**
+     * Infer a log file's full field tree: types, sample values and presence of every field.
+     */
+    getLogSchema: async (logPath: string, logType: LogType): Promise<LogSchema> => {
+        return await invoke("get_log_schema", { logPath, logType });
+    },
+
+    /**
+     * Search every log file in the monitored folder for a keyword or regex.
+     */
+    grepLogs: async (keyword: string, caseSensitive?: boolean, regex?: boolean): Promise<LogSearchResult> => {
+        return await invoke("grep_logs", { keyword, caseSensitive, regex });
+    },
+
+    /**
+     * List the text parsers for text log formats.
+     */
+    listTextParsers: async (): Promise<TextParser[]> => {
+        return await invoke("list_text_parsers");
+    },
+
+    /**
+     * Create or replace a text parser.
+     */
+    saveTextParser: async (parser: TextParser): Promise<TextParser> => {
+        return await invoke("save_text_parser", { parser });
+    },
+
+    /**
+     * Delete a text parser.
+     */
+    deleteTextParser: async (name: string): Promise<void> => {
+        return await invoke("delete_text_parser", { name });
+    },
+
+    /**
+     * Preview the events a text parser produces from sample lines.
+     */
+    testTextParser: async (parser: TextParser, sample: string): Promise<Record<string, unknown>[]> => {
+        return await invoke("test_text_parser", { parser, sample });
+    },
 };
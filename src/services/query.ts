@@ -1,15 +1,103 @@
 import { invoke } from "@tauri-apps/api/core";
+import type { LogType } from "./logService";
 
 export interface QueryResult {
     query: string;
     columns: string[];
+    column_types: string[];
     rows: any[];
     row_count: number;
     execution_time_ms: number;
 }
 
+export interface QueryPage {
+    query: string;
+    columns: string[];
+    column_types: string[];
+    rows: any[];
+    offset: number;
+    limit: number;
+    has_more: boolean;
+    execution_time_ms: number;
+}
+
+export type VariableType =
+    | "string"
+    | "integer"
+    | "number"
+    | "boolean"
+    | "timestamp"
+    | "ip"
+    | "list";
+
+export interface QueryVariable {
+    name: string;
+    type: VariableType;
+    prompt: string;
+}
+
+export interface SavedQuery {
+    id: string;
+    name: string;
+    description: string;
+    sql: string;
+    tags: string[];
+    placeholders: string[];
+    variables: QueryVariable[];
+    created_at: string;
+    updated_at: string;
+}
+
+export interface LogView {
+    name: string;
+    log_path: string;
+    log_type: LogType;
+    created_at: string;
+}
+
 export const queryService = {
-    runQuery: async (query: string): Promise<QueryResult> => {
-        return await invoke("run_query", { query });
+    runQuery: async (query: string, queryId?: string): Promise<QueryResult> => {
+        return await invoke("run_query", { query, queryId });
+    },
+
+    runQueryPaged: async (
+        query: string,
+        offset: number,
+        limit?: number,
+        queryId?: string
+    ): Promise<QueryPage> => {
+        return await invoke("run_query_paged", { query, offset, limit, queryId });
+    },
+
+    listSavedQueries: async (tag?: string): Promise<SavedQuery[]> => {
+        return await invoke("list_saved_queries", { tag });
+    },
+
+    saveQuery: async (query: Partial<SavedQuery> & Pick<SavedQuery, "name" | "sql">): Promise<SavedQuery> => {
+        return await invoke("save_query", { query });
+    },
+
+    runQueryTemplate: async (
+        name: string,
+        params: Record<string, string | number | boolean | null | (string | number)[]>,
+        queryId?: string
+    ): Promise<QueryResult> => {
+        return await invoke("run_query_template", { name, params, queryId });
+    },
+
+    cancelQuery: async (queryId: string): Promise<boolean> => {
+        return await invoke("cancel_query", { queryId });
+    },
+
+    registerLogView: async (name: string, logPath: string, logType: LogType): Promise<LogView> => {
+        return await invoke("register_log_view", { name, logPath, logType });
+    },
+
+    listLogViews: async (): Promise<LogView[]> => {
+        return await invoke("list_log_views");
+    },
+
+    dropLogView: async (name: string): Promise<boolean> => {
+        return await invoke("drop_log_view", { name });
     },
 };
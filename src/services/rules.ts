@@ -1,4 +1,5 @@
 import { invoke } from "@tauri-apps/api/core";
+import type { AlertEvent } from "./scan";
 
 export interface RuleYaml {
     id: string;
@@ -10,10 +11,14 @@ export interface RuleYaml {
     tags: string[];
     detection: {
         severity: string;
+        /** "script" makes `condition` a Rhai script over `event` */
+        type?: "condition" | "script";
         condition: string;
+        filters?: string[];
     };
     output?: {
         alert_title: string;
+        evidence_fields?: string[];
     };
 }
 
@@ -22,6 +27,15 @@ export const ruleService = {
         return await invoke("list_rules");
     },
 
+    searchRules: async (
+        query?: string,
+        tags?: string[],
+        severity?: string,
+        status?: string
+    ): Promise<RuleYaml[]> => {
+        return await invoke("search_rules", { query, tags, severity, status });
+    },
+
     getRule: async (ruleId: string): Promise<RuleYaml> => {
         return await invoke("get_rule", { ruleId });
     },
@@ -30,6 +44,22 @@ export const ruleService = {
         return await invoke("save_rule", { rule });
     },
 
+    duplicateRule: async (ruleId: string): Promise<RuleYaml> => {
+        return await invoke("duplicate_rule", { ruleId });
+    },
+
+    setRulesStatus: async (ruleIds: string[], status: string): Promise<string[]> => {
+        return await invoke("set_rules_status", { ruleIds, status });
+    },
+
+    addRuleFilter: async (ruleId: string, filter: string): Promise<RuleYaml> => {
+        return await invoke("add_rule_filter", { ruleId, filter });
+    },
+
+    addAlertFilter: async (alert: AlertEvent, fields: string[]): Promise<RuleYaml> => {
+        return await invoke("add_alert_filter", { alert, fields });
+    },
+
     deleteRule: async (ruleId: string): Promise<void> => {
         return await invoke("delete_rule", { ruleId });
     },
@@ -53,6 +83,37 @@ export const ruleService = {
     importMultipleRules: async (filePaths: string[], overwrite: boolean): Promise<ImportSummary> => {
         return await invoke("import_multiple_rules", { filePaths, overwrite });
     },
+
+    installDefaultRules: async (overwrite: boolean): Promise<ImportSummary> => {
+        return await invoke("install_default_rules", { overwrite });
+    },
+
+    profileRules: async (logPath: string): Promise<RuleProfileReport> => {
+        return await invoke("profile_rules", { logPath });
+    },
+
+    getRuleStatistics: async (): Promise<RuleStatistics[]> => {
+        return await invoke("get_rule_statistics");
+    },
+
+    setAlertTriage: async (
+        alert: AlertEvent,
+        status: TriageStatus | null,
+        note?: string
+    ): Promise<AlertTriage | null> => {
+        return await invoke("set_alert_triage", { alert, status, note });
+    },
+
+    listAlertTriage: async (): Promise<AlertTriage[]> => {
+        return await invoke("list_alert_triage");
+    },
+
+    getAuditLog: async (
+        limit?: number,
+        action?: AuditAction
+    ): Promise<AuditEntry[]> => {
+        return await invoke("get_audit_log", { limit, action });
+    },
 };
 
 export interface ImportSummary {
@@ -60,3 +121,63 @@ export interface ImportSummary {
     skipped: string[];
     errors: string[];
 }
+
+export interface RuleProfile {
+    rule_id: string;
+    rule_title: string;
+    execution_time_ms: number;
+    time_per_event_us: number;
+    match_count: number;
+    match_percentage: number;
+    noisy: boolean;
+}
+
+export interface RuleProfileReport {
+    log_path: string;
+    total_events: number;
+    total_time_ms: number;
+    rules_evaluation_time_ms: number;
+    rules: RuleProfile[];
+    noisy_rules: string[];
+}
+
+export type TriageStatus = "true_positive" | "benign" | "false_positive";
+
+export interface AlertTriage {
+    key: string;
+    rule_id: string;
+    status: TriageStatus;
+    note: string;
+    updated_at: string;
+}
+
+export type AuditAction =
+    | "rule_saved"
+    | "rule_deleted"
+    | "scan_run"
+    | "query_run"
+    | "alert_status_changed"
+    | "data_restored";
+
+export interface AuditEntry {
+    timestamp: string;
+    actor: "app" | "api";
+    action: AuditAction;
+    target: string;
+    details: Record<string, unknown>;
+}
+
+export interface RuleStatistics {
+    rule_id: string;
+    rule_title: string;
+    status: string | null;
+    scans_run: number;
+    scans_fired: number;
+    total_alerts: number;
+    total_matches: number;
+    first_fired: string | null;
+    last_fired: string | null;
+    triaged_alerts: number;
+    false_positives: number;
+    false_positive_rate: number | null;
+}
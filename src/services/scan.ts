@@ -3,6 +3,7 @@ import { invoke } from "@tauri-apps/api/core";
 export interface AlertEvent {
     rule_id: string;
     rule_title: string;
+    alert_title?: string; // Rendered from the rule's output.alert_title
     severity: string;
     timestamp: string;
     match_count: number;
@@ -10,10 +11,42 @@ export interface AlertEvent {
     source_file?: string; // Optional field for bulk scans
 }
 
+/** Subset of the active rules to scan with; omitted filters select every rule. */
+export interface RuleSelection {
+    ruleIds?: string[];
+    tags?: string[];
+    severity?: string;
+}
+
+/** Inclusive time window of the events to scan or view. */
+export interface TimeRange {
+    start_time?: string;
+    end_time?: string;
+    timestamp_field?: string;
+}
+
 export interface ScanResponse {
     alerts: AlertEvent[];
     rules_evaluated: number;
     scan_time_ms: number;
+    suppressed_alerts: number;
+    rule_results: RuleRunStats[];
+    routing: RoutingSummary;
+}
+
+/** Deliveries of a scan's alerts to the configured sinks; also the payload of `alert://routed`. */
+export interface RoutingSummary {
+    delivered: number;
+    errors: string[];
+}
+
+export interface RuleRunStats {
+    rule_id: string;
+    rule_title: string;
+    match_count: number;
+    alert_count: number;
+    execution_time_ms: number;
+    error?: string;
 }
 
 export interface FileScanResult {
@@ -21,6 +54,7 @@ export interface FileScanResult {
     file_path: string;
     alerts: AlertEvent[];
     scan_time_ms: number;
+    rule_results: RuleRunStats[];
 }
 
 export interface FailedFileScan {
@@ -35,18 +69,91 @@ export interface BulkScanResponse {
     total_scan_time_ms: number;
     rules_evaluated: number;
     file_results: FileScanResult[];
+    meta_alerts: AlertEvent[];
     failed_files: FailedFileScan[];
+    suppressed_alerts: number;
+    routing: RoutingSummary;
+}
+
+export type JobState = "queued" | "running" | "completed" | "failed" | "cancelled";
+
+export interface JobFileResult {
+    file_path: string;
+    alerts: AlertEvent[];
+    error?: string;
+}
+
+export interface ScanJob {
+    id: string;
+    state: JobState;
+    log_paths: string[];
+    created_at: string;
+    started_at?: string;
+    finished_at?: string;
+    files_done: number;
+    alert_count: number;
+    results: JobFileResult[];
+}
+
+export interface WatchStatus {
+    running: boolean;
+    directory?: string;
+    started_at?: string;
+    files_scanned: number;
+    alerts_emitted: number;
+}
+
+/** Payload of the `watch://scanned` event. */
+export interface WatchScanEvent {
+    file_name: string;
+    file_path: string;
+    alerts: AlertEvent[];
+    error?: string;
+    scanned_at: string;
 }
 
 export const scanService = {
-    scanLogs: async (logPath: string, logType: string): Promise<ScanResponse> => {
-        return await invoke("scan_logs", { logPath, logType });
+    scanLogs: async (
+        logPath: string,
+        logType: string,
+        selection?: RuleSelection,
+        timeRange?: TimeRange
+    ): Promise<ScanResponse> => {
+        return await invoke("scan_logs", { logPath, logType, ...selection, timeRange });
     },
 
     scanAllLogs: async (): Promise<BulkScanResponse> => {
         return await invoke("scan_all_logs");
     },
 
+    enqueueScan: async (logPaths?: string[]): Promise<ScanJob> => {
+        return await invoke("enqueue_scan", { logPaths });
+    },
+
+    getJobStatus: async (jobId: string): Promise<ScanJob> => {
+        return await invoke("get_job_status", { jobId });
+    },
+
+    listJobs: async (): Promise<ScanJob[]> => {
+        return await invoke("list_jobs");
+    },
+
+    cancelJob: async (jobId: string): Promise<ScanJob> => {
+        return await invoke("cancel_job", { jobId });
+    },
+
+    startFolderWatch: async (directory?: string): Promise<WatchStatus> => {
+        return await invoke("start_folder_watch", { directory });
+    },
+
+    stopFolderWatch: async (): Promise<WatchStatus> => {
+        return await invoke("stop_folder_watch");
+    },
+
+    getFolderWatchStatus: async (): Promise<WatchStatus> => {
+        return await invoke("get_folder_watch_status");
+    },
+
     validateLogFile: async (logPath: string): Promise<boolean> => {
         return await invoke("validate_log_file", { logPath });
     },